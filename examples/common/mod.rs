@@ -0,0 +1,196 @@
+// Bootstrap shared by the examples: a local validator with the vault program loaded, a funded
+// payer, and the SPL token plumbing the flows need.
+//
+// VAULT_RPC_URL picks the cluster (default http://127.0.0.1:8899). When nothing answers there,
+// a solana-test-validator is started with target/deploy/vault_program.so loaded at
+// vault_program::ID (run `cargo build-sbf` first) and stopped again when the example exits.
+// A validator that is already running must have the program at ID, e.g. one started with
+// `--bpf-program DvMJg65xGz7W7xa1tP6LW2RP4TecJDb5oN2Qcvf7Qc63 target/deploy/vault_program.so`.
+#![allow(dead_code)]
+
+use std::error::Error;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use vault_program::{client::check_program_account, state::Vault};
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+const PROGRAM_SO: &str = "target/deploy/vault_program.so";
+const LEDGER_DIR: &str = "target/example-ledger";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct Cluster {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    // The validator this process started, if it had to
+    validator: Option<Child>,
+}
+
+impl Cluster {
+    /// Connects to (or starts) the local validator, checks the program is deployed and airdrops
+    /// a fresh payer.
+    pub fn connect() -> Result<Self> {
+        let url = std::env::var("VAULT_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+        let rpc = RpcClient::new_with_commitment(url.clone(), CommitmentConfig::confirmed());
+        let validator = if rpc.get_health().is_ok() {
+            None
+        } else if url == DEFAULT_RPC_URL {
+            Some(start_validator(&rpc)?)
+        } else {
+            return Err(format!("no validator answering at {}", url).into());
+        };
+
+        let executable = rpc.get_account(&vault_program::ID).ok().map(|a| a.executable);
+        check_program_account(&vault_program::ID, &url, executable)?;
+
+        let cluster = Self {
+            rpc,
+            payer: Keypair::new(),
+            validator,
+        };
+        cluster.airdrop(&cluster.payer.pubkey(), 10 * LAMPORTS_PER_SOL)?;
+        Ok(cluster)
+    }
+
+    pub fn airdrop(&self, to: &Pubkey, lamports: u64) -> Result<()> {
+        let signature = self.rpc.request_airdrop(to, lamports)?;
+        let deadline = Instant::now() + STARTUP_TIMEOUT;
+        while !self.rpc.confirm_transaction(&signature)? {
+            if Instant::now() > deadline {
+                return Err(format!("airdrop to {} never confirmed", to).into());
+            }
+            sleep(Duration::from_millis(250));
+        }
+        Ok(())
+    }
+
+    /// Sends `instructions` paid for by the payer, who always signs alongside `signers`.
+    pub fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<Signature> {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        Ok(self.rpc.send_and_confirm_transaction(&transaction)?)
+    }
+
+    /// A new mint whose mint authority is the payer.
+    pub fn create_mint(&self, decimals: u8) -> Result<Pubkey> {
+        let mint = Keypair::new();
+        let rent = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer.pubkey(),
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &self.payer.pubkey(),
+                None,
+                decimals,
+            )?,
+        ];
+        self.send(&instructions, &[&mint])?;
+        Ok(mint.pubkey())
+    }
+
+    /// `owner`'s associated account for `mint`, created if needed and topped up with `amount`.
+    pub fn fund_ata(&self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Result<Pubkey> {
+        let ata = get_associated_token_address(owner, mint);
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &self.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::id(),
+        )];
+        if amount > 0 {
+            instructions.push(spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint,
+                &ata,
+                &self.payer.pubkey(),
+                &[],
+                amount,
+            )?);
+        }
+        self.send(&instructions, &[])?;
+        Ok(ata)
+    }
+
+    pub fn token_balance(&self, account: &Pubkey) -> Result<u64> {
+        Ok(self.rpc.get_token_account_balance(account)?.amount.parse()?)
+    }
+
+    pub fn fetch_vault(&self, vault: &Pubkey) -> Result<Vault> {
+        Ok(Vault::load(&self.rpc.get_account_data(vault)?)?)
+    }
+
+    /// The cluster's clock, for waiting out voting periods and delays.
+    pub fn unix_timestamp(&self) -> Result<i64> {
+        let slot = self.rpc.get_slot()?;
+        Ok(self.rpc.get_block_time(slot)?)
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        if let Some(validator) = self.validator.as_mut() {
+            let _ = validator.kill();
+            let _ = validator.wait();
+        }
+    }
+}
+
+fn start_validator(rpc: &RpcClient) -> Result<Child> {
+    if !std::path::Path::new(PROGRAM_SO).exists() {
+        return Err(format!("{} not found; run `cargo build-sbf` first", PROGRAM_SO).into());
+    }
+    println!("Starting solana-test-validator with the vault program at {}", vault_program::ID);
+    let mut validator = Command::new("solana-test-validator")
+        .args(["--reset", "--quiet", "--ledger", LEDGER_DIR, "--bpf-program"])
+        .arg(vault_program::ID.to_string())
+        .arg(PROGRAM_SO)
+        .stdout(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("couldn't start solana-test-validator: {}", e))?;
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while rpc.get_health().is_err() {
+        if let Some(status) = validator.try_wait()? {
+            return Err(format!("solana-test-validator exited with {}", status).into());
+        }
+        if Instant::now() > deadline {
+            let _ = validator.kill();
+            return Err("solana-test-validator didn't become healthy in time".into());
+        }
+        sleep(Duration::from_millis(500));
+    }
+    Ok(validator)
+}
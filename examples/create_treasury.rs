@@ -0,0 +1,40 @@
+// Creates a vault and puts it under a 2-of-3 multisig: the payer and two freshly generated
+// co-owners. See examples/common for how the local validator is found or started.
+//
+//     cargo build-sbf && cargo run --example create_treasury --features client
+mod common;
+
+use common::{Cluster, Result};
+use solana_sdk::signature::{Keypair, Signer};
+use vault_program::client::{initialize, initialize_multisig, multisig_signer_address};
+use vault_program::state::Vault;
+
+const NONCE: u8 = 1;
+
+fn main() -> Result<()> {
+    let cluster = Cluster::connect()?;
+    let authority = cluster.payer.pubkey();
+    let emergency_admin = Keypair::new();
+    let (vault, _) = Vault::find_address(&vault_program::ID, &authority, 0);
+
+    cluster.send(&[initialize(&authority, &emergency_admin.pubkey(), 0, false)], &[])?;
+    println!("Vault {} created, authority {}", vault, authority);
+
+    let co_owners = [Keypair::new(), Keypair::new()];
+    let mut owners = vec![authority];
+    owners.extend(co_owners.iter().map(|owner| owner.pubkey()));
+    cluster.send(&[initialize_multisig(&vault, &authority, owners, 2, NONCE)], &[])?;
+
+    let state = cluster.fetch_vault(&vault)?;
+    let multi_sig = state.multi_sig.as_ref().ok_or("multisig missing after initialization")?;
+    println!(
+        "Multisig {}-of-{}, signer PDA {}",
+        multi_sig.threshold,
+        multi_sig.owners.len(),
+        multisig_signer_address(&vault, NONCE)
+    );
+    for owner in &multi_sig.owners {
+        println!("  owner {}", owner);
+    }
+    Ok(())
+}
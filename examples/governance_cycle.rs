@@ -0,0 +1,75 @@
+// Runs a governance proposal through its whole life: initialize governance with a fresh voting
+// mint, lock voting tokens, propose, vote, wait out the voting period, queue and execute. The proposal carries no
+// instructions, so it only records the decision; ExecuteGovernanceProposal doesn't dispatch
+// stored instructions yet and is sent here to show where that step goes.
+//
+//     cargo build-sbf && cargo run --example governance_cycle --features client
+mod common;
+
+use std::thread::sleep;
+use std::time::Duration;
+
+use common::{Cluster, Result};
+use solana_sdk::signature::{Keypair, Signer};
+use vault_program::client::{
+    cast_vote, create_governance_proposal, execute_governance_proposal, initialize,
+    initialize_governance, queue_proposal, register_voter_with_lock,
+};
+use vault_program::state::{GovernanceConfig, GovernanceProposal, Vault, VoteType};
+
+const VOTING_PERIOD: i64 = 10;
+const HELD: u64 = 5_000_000;
+const LOCKED: u64 = 2_000_000;
+
+fn main() -> Result<()> {
+    let cluster = Cluster::connect()?;
+    let authority = cluster.payer.pubkey();
+    let (vault, _) = Vault::find_address(&vault_program::ID, &authority, 0);
+    cluster.send(&[initialize(&authority, &Keypair::new().pubkey(), 0, false)], &[])?;
+
+    let voting_mint = cluster.create_mint(6)?;
+    cluster.fund_ata(&voting_mint, &authority, HELD)?;
+    let config = GovernanceConfig {
+        voting_token_mint: voting_mint,
+        quorum_threshold: 2_000,
+        proposal_threshold: 1_000_000,
+        voting_period: VOTING_PERIOD,
+        time_lock_delay: 0,
+        execution_threshold: 5_000,
+        ..Default::default()
+    };
+    cluster.send(&[initialize_governance(&vault, &authority, &config)], &[])?;
+    println!("Governance initialized on {}, voting with {}", vault, voting_mint);
+
+    // Only tokens locked before a proposal opens vote on it
+    cluster.fund_ata(&voting_mint, &vault, 0)?;
+    cluster.send(&[register_voter_with_lock(&vault, &authority, &voting_mint, LOCKED, 0)], &[])?;
+    sleep(Duration::from_secs(1));
+
+    // The program enforces the same limits; checking first saves a failed transaction
+    let title = "Adopt the Q4 treasury policy".to_string();
+    let description = "Signalling proposal: no on-chain actions attached.".to_string();
+    GovernanceProposal::validate_text(&title, &description)?;
+    let proposal_id = cluster.fetch_vault(&vault)?.next_governance_proposal_id;
+    let propose =
+        create_governance_proposal(&vault, &authority, &voting_mint, title, description, vec![]);
+    cluster.send(&[propose], &[])?;
+    cluster.send(&[cast_vote(&vault, &authority, proposal_id, VoteType::For)], &[])?;
+
+    let end_time = cluster.fetch_vault(&vault)?.governance_proposals[0].end_time;
+    println!("Proposal {} open for votes until {}", proposal_id, end_time);
+    while cluster.unix_timestamp()? < end_time {
+        sleep(Duration::from_secs(1));
+    }
+
+    cluster.send(&[queue_proposal(&vault, &authority, proposal_id)], &[])?;
+    cluster.send(&[execute_governance_proposal(&vault, &authority, proposal_id)], &[])?;
+
+    let state = cluster.fetch_vault(&vault)?;
+    let proposal = &state.governance_proposals[0];
+    println!(
+        "Proposal {}: {} for, {} against, queued {} with eta {:?}",
+        proposal.id, proposal.for_votes, proposal.against_votes, proposal.queued, proposal.eta
+    );
+    Ok(())
+}
@@ -0,0 +1,82 @@
+// Pays a list of payees from a 2-of-2 multisig treasury. The payer proposes each transfer,
+// which counts as its approval; the second owner approves offline, standing in for a custody
+// service that returns detached ed25519 signatures over MultiSigTransaction::approval_message.
+// Those signatures ride in an ed25519 program instruction right before ApproveWithSignature,
+// so the co-owner's key never signs a transaction.
+//
+//     cargo build-sbf && cargo run --example payroll --features client
+mod common;
+
+use common::{Cluster, Result};
+use solana_sdk::{
+    ed25519_instruction::new_ed25519_instruction,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use vault_program::client::{
+    approve_with_signature, execute_multisig_transaction, initialize, initialize_multisig,
+    multisig_signer_address, propose_token_transfer,
+};
+use vault_program::state::{MultiSigTransaction, Vault};
+
+const NONCE: u8 = 2;
+const PAYROLL: [(&str, u64); 3] = [("alice", 4_000_000), ("bob", 2_500_000), ("carol", 1_250_000)];
+
+// The offline half: what a custody API would return for this approval
+fn offline_approval(co_owner: &Keypair, vault: &Pubkey, id: u64, seqno: u64) -> Instruction {
+    let keypair = ed25519_dalek::Keypair::from_bytes(&co_owner.to_bytes())
+        .expect("solana keypairs are ed25519 keypairs");
+    new_ed25519_instruction(&keypair, &MultiSigTransaction::approval_message(vault, id, seqno))
+}
+
+fn main() -> Result<()> {
+    let cluster = Cluster::connect()?;
+    let authority = cluster.payer.pubkey();
+    let co_owner = Keypair::new();
+    let (vault, _) = Vault::find_address(&vault_program::ID, &authority, 0);
+    cluster.send(
+        &[
+            initialize(&authority, &Keypair::new().pubkey(), 0, false),
+            initialize_multisig(&vault, &authority, vec![authority, co_owner.pubkey()], 2, NONCE),
+        ],
+        &[],
+    )?;
+
+    // Payroll is paid from the multisig signer's own token account
+    let mint = cluster.create_mint(6)?;
+    let signer = multisig_signer_address(&vault, NONCE);
+    let total = PAYROLL.iter().map(|(_, amount)| amount).sum();
+    let treasury = cluster.fund_ata(&mint, &signer, total)?;
+
+    for (name, amount) in PAYROLL {
+        let payee = Keypair::new().pubkey();
+        let payee_tokens = cluster.fund_ata(&mint, &payee, 0)?;
+        let memo = format!("payroll: {}", name);
+
+        let id = cluster.fetch_vault(&vault)?.next_transaction_id;
+        let propose = propose_token_transfer(&vault, &authority, &mint, &payee, amount, memo);
+        cluster.send(&[propose], &[])?;
+
+        let seqno = cluster.fetch_vault(&vault)?.owner_set_seqno;
+        cluster.send(
+            &[
+                offline_approval(&co_owner, &vault, id, seqno),
+                approve_with_signature(&vault, &co_owner.pubkey(), id, 0),
+            ],
+            &[],
+        )?;
+
+        let accounts = vec![
+            AccountMeta::new(treasury, false),
+            AccountMeta::new(payee_tokens, false),
+        ];
+        let execute =
+            execute_multisig_transaction(&vault, NONCE, &authority, id, &spl_token::id(), accounts);
+        cluster.send(&[execute], &[])?;
+        println!("Paid {} {} (transaction {})", name, cluster.token_balance(&payee_tokens)?, id);
+    }
+
+    println!("Treasury left with {}", cluster.token_balance(&treasury)?);
+    Ok(())
+}
@@ -0,0 +1,103 @@
+// Rotates a vault's idle tokens through a yield strategy: deploy, harvest the strategy's reward
+// token into the vault, then recall the principal and read back the strategy's books.
+//
+// The strategy is the mock from tests/fixtures/mock_defi.rs. It is a native program with no
+// SBF build, so unlike the other examples this one runs in solana-program-test's in-process
+// bank, where the mock and its config account are installed the same way the tests do it.
+// Against a validator with a real strategy deployed, the vault instructions are unchanged;
+// only the strategy's data and accounts differ.
+//
+//     cargo run --example yield_rotate --features client
+#[allow(dead_code)]
+#[path = "../tests/common/mod.rs"]
+mod test_support;
+
+use solana_sdk::signature::Signer;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use test_support::{
+    add_mock_defi, fetch_vault, fund_mock_pool, mock_defi, program_test, send, token_balance,
+    vault_with_token, MockDefiConfig, MOCK_DEFI_ID,
+};
+use vault_program::client::{
+    deploy_to_strategy, deposit, set_yield_strategy, withdraw_from_strategy,
+};
+
+const DEPOSIT: u64 = 1_000_000;
+const DEPLOYED: u64 = 600_000;
+
+#[tokio::main]
+async fn main() {
+    let mut test = program_test();
+    let config = MockDefiConfig {
+        harvest_reward: 25_000,
+        ..MockDefiConfig::default()
+    };
+    add_mock_defi(&mut test, MOCK_DEFI_ID, config);
+    let mut context = test.start_with_context().await;
+    let authority = context.payer.pubkey();
+
+    let (vault, mint) = vault_with_token(&mut context, DEPOSIT).await;
+    fund_mock_pool(&mut context, &MOCK_DEFI_ID, &mint, 0).await;
+    send(
+        &mut context,
+        &[
+            deposit(&vault, &mint, &authority, DEPOSIT),
+            set_yield_strategy(&vault, &authority, &mint, &MOCK_DEFI_ID),
+        ],
+        &[],
+    )
+    .await
+    .expect("deposit and strategy setup");
+
+    let strategy_accounts = mock_defi::strategy_accounts(&MOCK_DEFI_ID, &vault, &mint);
+    let deploy = deploy_to_strategy(
+        &vault,
+        &authority,
+        &mint,
+        &MOCK_DEFI_ID,
+        DEPLOYED,
+        mock_defi::deposit_data(DEPLOYED),
+        strategy_accounts.clone(),
+    );
+    send(&mut context, &[deploy], &[]).await.expect("deploy");
+    println!("Deployed {} of {} to the strategy", DEPLOYED, DEPOSIT);
+
+    // Anyone can crank the harvest; rewards land in the vault's account for the reward mint
+    let reward_mint = mock_defi::reward_mint(&MOCK_DEFI_ID);
+    let rewards = get_associated_token_address(&vault, &reward_mint);
+    let harvest = mock_defi::instruction(
+        &MOCK_DEFI_ID,
+        mock_defi::harvest_data(),
+        mock_defi::harvest_accounts(&MOCK_DEFI_ID, &rewards),
+    );
+    let create_rewards = create_associated_token_account_idempotent(
+        &authority,
+        &vault,
+        &reward_mint,
+        &spl_token::id(),
+    );
+    send(&mut context, &[create_rewards, harvest], &[]).await.expect("harvest");
+    println!("Harvested {} reward tokens", token_balance(&mut context, &rewards).await);
+
+    let recall = withdraw_from_strategy(
+        &vault,
+        &authority,
+        &mint,
+        &MOCK_DEFI_ID,
+        DEPLOYED,
+        mock_defi::withdraw_data(DEPLOYED),
+        strategy_accounts,
+    );
+    send(&mut context, &[recall], &[]).await.expect("withdraw");
+
+    let state = fetch_vault(&mut context, &vault).await;
+    let strategy = &state.yield_strategies[0];
+    println!(
+        "Recalled: {} deployed, realized pnl {}, vault tracks {}",
+        strategy.deployed_amount,
+        strategy.realized_pnl,
+        state.tracked_balance(&mint)
+    );
+}
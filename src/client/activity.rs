@@ -0,0 +1,12 @@
+use serde_json::Value;
+
+use crate::client::display::humanize_timestamps;
+use crate::state::Vault;
+
+/// The vault's recent_actions ring as a JSON array, oldest first, with RFC3339 timestamps.
+/// Meant for info/status output; the full history is in the emitted events.
+pub fn recent_actions_json(vault: &Vault) -> serde_json::Result<Value> {
+    let mut value = serde_json::to_value(vault.recent_actions())?;
+    humanize_timestamps(&mut value);
+    Ok(value)
+}
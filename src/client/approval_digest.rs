@@ -0,0 +1,200 @@
+use std::fmt;
+
+use borsh::BorshSerialize;
+use serde::{Deserialize, Serialize};
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+use crate::state::Vault;
+
+// Approval digests for auditors: a SHA-256 over the canonical bytes of multisig transaction
+// N's approval state (owner set, threshold, who approved and what they approved) in the vault
+// account data the caller supplies, labelled with an execute signature and slot.
+//
+// A digest attests nothing. Nothing binds the account data to the chain, and the signature
+// and slot are only checked for shape, so anyone can build a matching digest for any data
+// they write. It is a compact fingerprint to compare against account data the auditor
+// already trusts, e.g. from an archival snapshot at that slot. The vault records who
+// approved, not when, so a digest can't order approvals in time either.
+
+pub const APPROVAL_DIGEST_PREFIX: &[u8] = b"vault-approval-digest";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApprovalDigest {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub vault: Pubkey,
+    pub transaction_id: u64,
+    /// The execute transaction, base58
+    pub execute_signature: String,
+    pub slot: u64,
+    /// Owners, approvers and threshold as decoded from account_data, for reading;
+    /// check_consistent checks them against it
+    #[serde(with = "crate::serde_helpers::vec_pubkey")]
+    pub owners: Vec<Pubkey>,
+    #[serde(with = "crate::serde_helpers::vec_pubkey")]
+    pub approvers: Vec<Pubkey>,
+    pub threshold: u64,
+    pub account_data_base64: String,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalDigestError {
+    InvalidAccountData,
+    MultisigNotInitialized,
+    TransactionNotFound,
+    /// The account data is from after the transaction executed
+    AlreadyExecuted,
+    NotEnoughApprovals,
+    MalformedSignature,
+    /// The recorded digest or decoded fields don't match the account data
+    DigestMismatch,
+}
+
+impl fmt::Display for ApprovalDigestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalDigestError::InvalidAccountData => write!(f, "account data is not a vault"),
+            ApprovalDigestError::MultisigNotInitialized => write!(f, "the vault has no multisig"),
+            ApprovalDigestError::TransactionNotFound => {
+                write!(f, "the transaction isn't in the account data")
+            }
+            ApprovalDigestError::AlreadyExecuted => {
+                write!(f, "account data is from after the transaction executed")
+            }
+            ApprovalDigestError::NotEnoughApprovals => {
+                write!(f, "the transaction doesn't have enough approvals")
+            }
+            ApprovalDigestError::MalformedSignature => {
+                write!(f, "execute signature is not a base58 signature")
+            }
+            ApprovalDigestError::DigestMismatch => {
+                write!(f, "digest does not match its account data")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApprovalDigestError {}
+
+/// Canonical bytes of transaction `transaction_id`'s approval state in `vault`: the prefix,
+/// the vault address, the id, owner_set_seqno and threshold as little-endian u64s, then the
+/// owners, approval flags, target program, accounts and data, Borsh-encoded.
+pub fn approval_state_bytes(
+    vault_address: &Pubkey,
+    vault: &Vault,
+    transaction_id: u64,
+) -> Result<Vec<u8>, ApprovalDigestError> {
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(ApprovalDigestError::MultisigNotInitialized)?;
+    let transaction = vault
+        .multisig_transaction(transaction_id)
+        .ok_or(ApprovalDigestError::TransactionNotFound)?;
+    let mut bytes = APPROVAL_DIGEST_PREFIX.to_vec();
+    bytes.extend_from_slice(vault_address.as_ref());
+    bytes.extend_from_slice(&transaction_id.to_le_bytes());
+    bytes.extend_from_slice(&vault.owner_set_seqno.to_le_bytes());
+    bytes.extend_from_slice(&multi_sig.threshold.to_le_bytes());
+    let encoded = (|| -> std::io::Result<()> {
+        multi_sig.owners.serialize(&mut bytes)?;
+        transaction.signers.serialize(&mut bytes)?;
+        transaction.program_id.serialize(&mut bytes)?;
+        transaction.accounts.serialize(&mut bytes)?;
+        transaction.data.serialize(&mut bytes)
+    })();
+    encoded.map_err(|_| ApprovalDigestError::InvalidAccountData)?;
+    Ok(bytes)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl ApprovalDigest {
+    /// Digest of `transaction_id`'s approvals in `account_data`, labelled with the execute
+    /// signature and slot the caller says it belongs to. Fails on data from after the
+    /// execution or without enough approvals.
+    pub fn build(
+        vault_address: &Pubkey,
+        transaction_id: u64,
+        execute_signature: &str,
+        slot: u64,
+        account_data: &[u8],
+    ) -> Result<Self, ApprovalDigestError> {
+        let mut digest = ApprovalDigest {
+            vault: *vault_address,
+            transaction_id,
+            execute_signature: execute_signature.to_string(),
+            slot,
+            owners: Vec::new(),
+            approvers: Vec::new(),
+            threshold: 0,
+            account_data_base64: base64::encode(account_data),
+            sha256: String::new(),
+        };
+        let (owners, approvers, threshold, sha256) = digest.recompute()?;
+        digest.owners = owners;
+        digest.approvers = approvers;
+        digest.threshold = threshold;
+        digest.sha256 = sha256;
+        Ok(digest)
+    }
+
+    /// Decodes the included account data and recomputes everything the digest states from
+    /// it. This only shows the digest is consistent with its own data, not that the data is
+    /// what the chain held.
+    pub fn check_consistent(&self) -> Result<(), ApprovalDigestError> {
+        let (owners, approvers, threshold, digest) = self.recompute()?;
+        if digest != self.sha256
+            || owners != self.owners
+            || approvers != self.approvers
+            || threshold != self.threshold
+        {
+            return Err(ApprovalDigestError::DigestMismatch);
+        }
+        Ok(())
+    }
+
+    // Owners, approvers, threshold and digest from the account data
+    fn recompute(&self) -> Result<(Vec<Pubkey>, Vec<Pubkey>, u64, String), ApprovalDigestError> {
+        let signature = bs58::decode(&self.execute_signature)
+            .into_vec()
+            .map_err(|_| ApprovalDigestError::MalformedSignature)?;
+        if signature.len() != 64 {
+            return Err(ApprovalDigestError::MalformedSignature);
+        }
+        let data = base64::decode(&self.account_data_base64)
+            .map_err(|_| ApprovalDigestError::InvalidAccountData)?;
+        let vault =
+            Vault::from_account_data(&data).map_err(|_| ApprovalDigestError::InvalidAccountData)?;
+        let bytes = approval_state_bytes(&self.vault, &vault, self.transaction_id)?;
+
+        // approval_state_bytes found both
+        let multi_sig = vault.multi_sig.as_ref().unwrap();
+        let transaction = vault.multisig_transaction(self.transaction_id).unwrap();
+        if transaction.did_execute {
+            return Err(ApprovalDigestError::AlreadyExecuted);
+        }
+        let approvers: Vec<Pubkey> = multi_sig
+            .owners
+            .iter()
+            .zip(&transaction.signers)
+            .filter(|(_, &signed)| signed)
+            .map(|(owner, _)| *owner)
+            .collect();
+        if (approvers.len() as u64) < multi_sig.threshold {
+            return Err(ApprovalDigestError::NotEnoughApprovals);
+        }
+        let digest = hex(&hashv(&[&bytes]).to_bytes());
+        Ok((multi_sig.owners.clone(), approvers, multi_sig.threshold, digest))
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
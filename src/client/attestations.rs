@@ -0,0 +1,47 @@
+use borsh::BorshSerialize;
+use solana_program::{instruction::Instruction, pubkey::Pubkey, sysvar};
+
+use crate::client::instructions::{create_multisig_transaction, multisig_signer_address};
+use crate::instruction::VaultInstruction;
+use crate::state::{Attestation, TransactionAccount, Vault};
+use crate::ID;
+
+/// Proposes a multisig transaction that attests `message_hash` once approved and executed.
+/// The returned instruction is a regular CreateMultiSigTransaction signed by `proposer`.
+pub fn propose_attestation(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    nonce: u8,
+    message_hash: [u8; 32],
+) -> Instruction {
+    let accounts = vec![
+        TransactionAccount {
+            pubkey: *vault,
+            is_signer: false,
+            is_writable: true,
+        },
+        TransactionAccount {
+            pubkey: multisig_signer_address(vault, nonce),
+            is_signer: true,
+            is_writable: false,
+        },
+        TransactionAccount {
+            pubkey: sysvar::clock::id(),
+            is_signer: false,
+            is_writable: false,
+        },
+    ];
+    let data = VaultInstruction::AttestMessage { message_hash }
+        .try_to_vec()
+        .expect("instruction serialization");
+    create_multisig_transaction(vault, proposer, ID, accounts, data)
+}
+
+/// The stored attestation for `message_hash`, if the multisig executed one and it hasn't
+/// been evicted. Pending or rejected attestation transactions never show up here.
+pub fn verify_attestation<'a>(
+    vault: &'a Vault,
+    message_hash: &[u8; 32],
+) -> Option<&'a Attestation> {
+    vault.find_attestation(message_hash)
+}
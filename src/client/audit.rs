@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+use crate::client::decode::{decode_instruction, instruction_name};
+use crate::client::events::{parse_event, parse_events, ParsedEvent};
+
+// Audit export: a vault's history over a slot range as one chronological report, with the
+// balances the events reported along the way, sealed by a SHA-256 over its canonical JSON and
+// optionally an ed25519 signature by whoever produced it
+
+/// One transaction as fetched from RPC: the data of every instruction it sent to this
+/// program, top-level and inner, and its log messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    /// The transaction error, for transactions that failed
+    pub error: Option<String>,
+    pub instructions: Vec<Vec<u8>>,
+    pub logs: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub error: Option<String>,
+    /// Variant names of the decoded instructions, in order
+    pub instructions: Vec<String>,
+    pub events: Vec<Value>,
+}
+
+/// Last balance an event reported for an asset: a mint in base58, or `SOL` for tracked
+/// lamports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditBalance {
+    pub asset: String,
+    pub balance: u64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditReport {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub vault: Pubkey,
+    pub from_slot: u64,
+    pub to_slot: u64,
+    pub entries: Vec<AuditEntry>,
+    pub balances: Vec<AuditBalance>,
+    /// TVL after the last deposit or withdrawal in range
+    #[serde(with = "crate::serde_helpers::option_u128_string")]
+    pub total_value_locked: Option<u128>,
+}
+
+/// The report as written to disk: its digest and, when signed, the signer and signature, both
+/// in base58.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SealedAuditReport {
+    pub report: AuditReport,
+    pub sha256: String,
+    pub signer: Option<String>,
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditError {
+    InvalidKeypair,
+    /// The report no longer hashes to the recorded digest
+    DigestMismatch,
+    /// A signer or signature that doesn't decode, or one without the other
+    MalformedSignature,
+    SignatureInvalid,
+}
+
+impl fmt::Display for AuditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuditError::InvalidKeypair => write!(f, "not an ed25519 keypair"),
+            AuditError::DigestMismatch => write!(f, "report does not match its sha256"),
+            AuditError::MalformedSignature => write!(f, "signer or signature is malformed"),
+            AuditError::SignatureInvalid => write!(f, "signature does not verify"),
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+/// Signatures with their slot in `from_slot..=to_slot`, oldest first. `page(before)` stands
+/// in for `get_signatures_for_address` as in `backfill_signatures`, with each signature's
+/// slot; the walk stops at the first one older than the range.
+pub fn signatures_in_slot_range(
+    from_slot: u64,
+    to_slot: u64,
+    mut page: impl FnMut(Option<&str>) -> Vec<(String, u64)>,
+) -> Vec<String> {
+    let mut in_range = Vec::new();
+    let mut before: Option<String> = None;
+    'walk: loop {
+        let signatures = page(before.as_deref());
+        if signatures.is_empty() {
+            break;
+        }
+        for (signature, slot) in signatures {
+            if slot < from_slot {
+                break 'walk;
+            }
+            if slot <= to_slot {
+                in_range.push(signature.clone());
+            }
+            before = Some(signature);
+        }
+    }
+    in_range.reverse();
+    in_range
+}
+
+pub fn audit_entry(transaction: &AuditTransaction) -> AuditEntry {
+    AuditEntry {
+        signature: transaction.signature.clone(),
+        slot: transaction.slot,
+        block_time: transaction.block_time,
+        error: transaction.error.clone(),
+        instructions: transaction
+            .instructions
+            .iter()
+            .map(|data| match decode_instruction(data) {
+                Ok(instruction) => instruction_name(&instruction),
+                Err(err) => format!("undecodable ({})", err),
+            })
+            .collect(),
+        events: parse_events(&transaction.logs),
+    }
+}
+
+/// Report over the transactions in `from_slot..=to_slot`, which are taken oldest first as
+/// `signatures_in_slot_range` lists them.
+pub fn build_audit_report(
+    vault: &Pubkey,
+    from_slot: u64,
+    to_slot: u64,
+    transactions: &[AuditTransaction],
+) -> AuditReport {
+    let mut entries: Vec<AuditEntry> = transactions
+        .iter()
+        .filter(|transaction| (from_slot..=to_slot).contains(&transaction.slot))
+        .map(audit_entry)
+        .collect();
+    entries.sort_by_key(|entry| entry.slot);
+
+    let mut balances = BTreeMap::new();
+    let mut total_value_locked = None;
+    for entry in &entries {
+        for event in &entry.events {
+            let (asset, balance, tvl) = match parse_event(event.clone()) {
+                ParsedEvent::TokenDeposited(e) => {
+                    (e.token_mint.to_string(), e.resulting_balance, e.resulting_tvl)
+                }
+                ParsedEvent::TokenWithdrawn(e) => {
+                    (e.token_mint.to_string(), e.resulting_balance, e.resulting_tvl)
+                }
+                ParsedEvent::SolWithdrawn(e) => {
+                    ("SOL".to_string(), e.resulting_balance, e.resulting_tvl)
+                }
+                _ => continue,
+            };
+            balances.insert(asset.clone(), AuditBalance { asset, balance, slot: entry.slot });
+            total_value_locked = Some(tvl);
+        }
+    }
+
+    AuditReport {
+        vault: *vault,
+        from_slot,
+        to_slot,
+        entries,
+        balances: balances.into_values().collect(),
+        total_value_locked,
+    }
+}
+
+/// `value` with object keys sorted and no whitespace, so equal reports always hash the same.
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<_> = map.iter().collect();
+            fields.sort_by(|(a, _), (b, _)| a.cmp(b));
+            let fields: Vec<String> = fields
+                .into_iter()
+                .map(|(key, field)| {
+                    format!("{}:{}", Value::from(key.as_str()), canonical_json(field))
+                })
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical_json).collect();
+            format!("[{}]", items.join(","))
+        }
+        scalar => scalar.to_string(),
+    }
+}
+
+pub fn audit_digest(report: &AuditReport) -> [u8; 32] {
+    let value = serde_json::to_value(report).expect("audit reports serialize");
+    hashv(&[canonical_json(&value).as_bytes()]).to_bytes()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl SealedAuditReport {
+    /// Seals `report` with its digest, signed by `keypair` (the 64 bytes of a Solana keypair
+    /// file) when given.
+    pub fn seal(report: AuditReport, keypair: Option<&[u8]>) -> Result<Self, AuditError> {
+        let digest = audit_digest(&report);
+        let (signer, signature) = match keypair {
+            Some(bytes) => {
+                let keypair = Keypair::from_bytes(bytes).map_err(|_| AuditError::InvalidKeypair)?;
+                let signature = keypair.sign(&digest);
+                (
+                    Some(bs58::encode(keypair.public.as_bytes()).into_string()),
+                    Some(bs58::encode(signature.to_bytes()).into_string()),
+                )
+            }
+            None => (None, None),
+        };
+        Ok(SealedAuditReport {
+            report,
+            sha256: hex(&digest),
+            signer,
+            signature,
+        })
+    }
+
+    /// Recomputes the digest and checks the signature, if there is one.
+    pub fn verify(&self) -> Result<(), AuditError> {
+        let digest = audit_digest(&self.report);
+        if hex(&digest) != self.sha256 {
+            return Err(AuditError::DigestMismatch);
+        }
+        let (signer, signature) = match (&self.signer, &self.signature) {
+            (None, None) => return Ok(()),
+            (Some(signer), Some(signature)) => (signer, signature),
+            _ => return Err(AuditError::MalformedSignature),
+        };
+        let decode = |text: &str| bs58::decode(text).into_vec().ok();
+        let signer = decode(signer)
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .ok_or(AuditError::MalformedSignature)?;
+        let signature = decode(signature)
+            .and_then(|bytes| Signature::try_from(bytes.as_slice()).ok())
+            .ok_or(AuditError::MalformedSignature)?;
+        signer
+            .verify(&digest, &signature)
+            .map_err(|_| AuditError::SignatureInvalid)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
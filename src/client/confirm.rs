@@ -0,0 +1,233 @@
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use solana_program::pubkey::Pubkey;
+
+use crate::instruction::VaultInstruction;
+use crate::state::Vault;
+
+// Guard rails for instructions that can lock operators out of a vault: a before/after diff
+// against the fetched state, client-side refusal of changes the program would reject or
+// silently adjust, and the type-the-address confirmation prompt. There is no close-vault
+// instruction yet; it belongs in `review_change` once there is.
+
+/// One field the instruction changes, as it is on-chain now and as it would be after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldChange {
+    Value {
+        field: &'static str,
+        before: String,
+        after: String,
+    },
+    /// Owner lists are shown as members removed and added rather than two long lists
+    Members {
+        field: &'static str,
+        removed: Vec<Pubkey>,
+        added: Vec<Pubkey>,
+        kept: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeReview {
+    pub vault: Pubkey,
+    pub operation: &'static str,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeRefusal {
+    MultisigNotInitialized,
+    NoOwners,
+    DuplicateOwner(Pubkey),
+    /// The new owner set couldn't reach the current threshold; on-chain the threshold would
+    /// quietly drop to the owner count instead
+    OwnersBelowThreshold { owners: usize, threshold: u64 },
+    ThresholdOutOfRange { threshold: u64, owners: usize },
+    DefaultAuthority,
+    DefaultEmergencyAdmin,
+    AlreadyPaused,
+}
+
+impl fmt::Display for ChangeRefusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeRefusal::MultisigNotInitialized => {
+                write!(f, "the vault has no multisig to change")
+            }
+            ChangeRefusal::NoOwners => write!(f, "a multisig needs at least one owner"),
+            ChangeRefusal::DuplicateOwner(owner) => {
+                write!(f, "{} is listed more than once", owner)
+            }
+            ChangeRefusal::OwnersBelowThreshold { owners, threshold } => write!(
+                f,
+                "{} owners can't meet the current threshold of {}; lower the threshold first",
+                owners, threshold
+            ),
+            ChangeRefusal::ThresholdOutOfRange { threshold, owners } => write!(
+                f,
+                "threshold {} is outside 1..={} for {} owners",
+                threshold, owners, owners
+            ),
+            ChangeRefusal::DefaultAuthority => {
+                write!(f, "the default pubkey can't sign; the vault would have no authority")
+            }
+            ChangeRefusal::DefaultEmergencyAdmin => {
+                write!(f, "the default pubkey can't sign; nobody could pause the vault")
+            }
+            ChangeRefusal::AlreadyPaused => write!(f, "the vault is already paused"),
+        }
+    }
+}
+
+fn value(field: &'static str, before: impl ToString, after: impl ToString) -> FieldChange {
+    FieldChange::Value {
+        field,
+        before: before.to_string(),
+        after: after.to_string(),
+    }
+}
+
+fn review_owners(vault: &Vault, owners: &[Pubkey]) -> Result<Vec<FieldChange>, ChangeRefusal> {
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(ChangeRefusal::MultisigNotInitialized)?;
+    if owners.is_empty() {
+        return Err(ChangeRefusal::NoOwners);
+    }
+    let repeated = owners
+        .iter()
+        .enumerate()
+        .find_map(|(index, owner)| owners[..index].contains(owner).then_some(owner));
+    if let Some(owner) = repeated {
+        return Err(ChangeRefusal::DuplicateOwner(*owner));
+    }
+    if (owners.len() as u64) < multi_sig.threshold {
+        return Err(ChangeRefusal::OwnersBelowThreshold {
+            owners: owners.len(),
+            threshold: multi_sig.threshold,
+        });
+    }
+
+    let current = &multi_sig.owners;
+    Ok(vec![FieldChange::Members {
+        field: "owners",
+        removed: current.iter().filter(|o| !owners.contains(o)).copied().collect(),
+        added: owners.iter().filter(|o| !current.contains(o)).copied().collect(),
+        kept: owners.iter().filter(|o| current.contains(o)).count(),
+    }])
+}
+
+/// The diff `instruction` would make to `vault`, or `None` for instructions that don't need
+/// a confirmation. Refuses what would leave the vault unusable.
+pub fn review_change(
+    vault_key: &Pubkey,
+    vault: &Vault,
+    instruction: &VaultInstruction,
+) -> Result<Option<ChangeReview>, ChangeRefusal> {
+    let (operation, changes) = match instruction {
+        VaultInstruction::SetMultiSigOwners { owners } => {
+            ("update multisig owners", review_owners(vault, owners)?)
+        }
+        VaultInstruction::ChangeMultiSigThreshold { threshold } => {
+            let multi_sig = vault
+                .multi_sig
+                .as_ref()
+                .ok_or(ChangeRefusal::MultisigNotInitialized)?;
+            let owners = multi_sig.owners.len();
+            if *threshold == 0 || *threshold > owners as u64 {
+                return Err(ChangeRefusal::ThresholdOutOfRange {
+                    threshold: *threshold,
+                    owners,
+                });
+            }
+            let change = value(
+                "threshold",
+                format!("{} of {}", multi_sig.threshold, owners),
+                format!("{} of {}", threshold, owners),
+            );
+            ("change multisig threshold", vec![change])
+        }
+        VaultInstruction::TransferAuthority { new_authority } => {
+            if *new_authority == Pubkey::default() {
+                return Err(ChangeRefusal::DefaultAuthority);
+            }
+            let change = value("authority", vault.authority, new_authority);
+            ("transfer authority", vec![change])
+        }
+        VaultInstruction::UpdateEmergencyAdmin { new_admin } => {
+            if *new_admin == Pubkey::default() {
+                return Err(ChangeRefusal::DefaultEmergencyAdmin);
+            }
+            let change = value("emergency admin", vault.emergency_admin, new_admin);
+            ("change emergency admin", vec![change])
+        }
+        VaultInstruction::PauseVault => {
+            if vault.paused {
+                return Err(ChangeRefusal::AlreadyPaused);
+            }
+            let lapses = match vault.max_pause_duration {
+                0 => "never lapses".to_string(),
+                seconds => format!("lapses after {}s", seconds),
+            };
+            let change = value("paused", false, format!("true ({})", lapses));
+            ("pause vault", vec![change])
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(ChangeReview {
+        vault: *vault_key,
+        operation,
+        changes,
+    }))
+}
+
+impl fmt::Display for ChangeReview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "About to {} on vault {}", self.operation, self.vault)?;
+        for change in &self.changes {
+            match change {
+                FieldChange::Value {
+                    field,
+                    before,
+                    after,
+                } => writeln!(f, "  {}: {} -> {}", field, before, after)?,
+                FieldChange::Members {
+                    field,
+                    removed,
+                    added,
+                    kept,
+                } => {
+                    writeln!(f, "  {} ({} unchanged):", field, kept)?;
+                    for owner in removed {
+                        writeln!(f, "    - {}", owner)?;
+                    }
+                    for owner in added {
+                        writeln!(f, "    + {}", owner)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints `review` and asks for the vault address to be typed back; `assume_yes` (`--yes`)
+/// skips the question but still prints the diff. Anything but the exact address declines.
+pub fn confirm_change(
+    review: &ChangeReview,
+    assume_yes: bool,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    write!(output, "{}", review)?;
+    if assume_yes {
+        return Ok(true);
+    }
+    write!(output, "Type the vault address to proceed: ")?;
+    output.flush()?;
+    let mut typed = String::new();
+    input.read_line(&mut typed)?;
+    Ok(typed.trim() == review.vault.to_string())
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, fs, io, path::Path, time::Duration};
+
+// Resumable event forwarding: a cursor persisted after every forwarded event, the backfill
+// walk back to it on restart, and retrying delivery with exponential backoff
+
+/// Position of the last event forwarded. Events are identified by the transaction signature
+/// and the index of their log line within that transaction.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventCursor {
+    pub signature: Option<String>,
+    pub log_index: Option<usize>,
+    // Everything forwarded this session, so overlapping pages and live notifications
+    // aren't sent twice
+    #[serde(skip)]
+    seen: HashSet<(String, usize)>,
+}
+
+impl EventCursor {
+    /// Reads the cursor at `path`; a missing file starts from scratch.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes through a temporary file and a rename, so a crash never leaves a torn cursor.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+
+    /// False for events already forwarded, this session or before the cursor was saved.
+    pub fn should_forward(&self, signature: &str, log_index: usize) -> bool {
+        if self.seen.contains(&(signature.to_string(), log_index)) {
+            return false;
+        }
+        match (&self.signature, self.log_index) {
+            (Some(last), Some(last_index)) if last == signature => log_index > last_index,
+            _ => true,
+        }
+    }
+
+    /// Records `(signature, log_index)` as forwarded.
+    pub fn advance(&mut self, signature: &str, log_index: usize) {
+        self.seen.insert((signature.to_string(), log_index));
+        self.signature = Some(signature.to_string());
+        self.log_index = Some(log_index);
+    }
+}
+
+/// Signatures to process after a restart, oldest first. `page(before)` stands in for
+/// `get_signatures_for_address` with `before` set: it returns signatures newest first, and an
+/// empty page once history runs out. The walk stops at `cursor`, which is included again so
+/// events of a partly forwarded transaction are picked up; `EventCursor::should_forward`
+/// drops the ones already sent.
+pub fn backfill_signatures(
+    cursor: Option<&str>,
+    mut page: impl FnMut(Option<&str>) -> Vec<String>,
+) -> Vec<String> {
+    let mut pending = Vec::new();
+    let mut before: Option<String> = None;
+    loop {
+        let signatures = page(before.as_deref());
+        if signatures.is_empty() {
+            break;
+        }
+        for signature in signatures {
+            let reached = cursor == Some(signature.as_str());
+            before = Some(signature.clone());
+            pending.push(signature);
+            if reached {
+                pending.reverse();
+                return pending;
+            }
+        }
+    }
+    pending.reverse();
+    pending
+}
+
+/// Delays between delivery attempts: `base`, doubling each time, capped at `max`.
+pub fn backoff_delays(retries: u32, base: Duration, max: Duration) -> impl Iterator<Item = Duration> {
+    (0..retries).map(move |attempt| base.saturating_mul(1 << attempt.min(31)).min(max))
+}
+
+/// Calls `post` until it succeeds, sleeping through `backoff_delays` between attempts. Returns
+/// the last error once retries run out, for the caller to log and move on.
+pub fn deliver_with_retry<E>(
+    retries: u32,
+    base: Duration,
+    max: Duration,
+    mut post: impl FnMut() -> Result<(), E>,
+    mut sleep: impl FnMut(Duration),
+) -> Result<(), E> {
+    let mut delays = backoff_delays(retries, base, max);
+    loop {
+        match post() {
+            Ok(()) => return Ok(()),
+            Err(e) => match delays.next() {
+                Some(delay) => sleep(delay),
+                None => return Err(e),
+            },
+        }
+    }
+}
@@ -0,0 +1,340 @@
+use std::fmt;
+
+use borsh::BorshDeserialize;
+use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+
+use crate::instruction::VaultInstruction;
+use crate::state::MultiSigTransaction;
+use crate::{VaultError, ID};
+
+// Human-readable decoding of raw VaultInstruction bytes, e.g. the payload of a pending
+// multisig transaction that targets this program, and of the errors instructions fail with
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataEncoding {
+    Hex,
+    Base58,
+    Base64,
+}
+
+impl DataEncoding {
+    /// `0x`-prefixed or even-length all-hex input is hex. Otherwise the base58 alphabet
+    /// wins over base64, so pass the encoding explicitly for base64 strings without `+`, `/`
+    /// or padding.
+    pub fn detect(input: &str) -> Self {
+        let is_hex = |s: &str| s.len() % 2 == 0 && s.chars().all(|c| c.is_ascii_hexdigit());
+        if input.starts_with("0x") || is_hex(input) {
+            DataEncoding::Hex
+        } else if input.chars().all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c)) {
+            DataEncoding::Base58
+        } else {
+            DataEncoding::Base64
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The input isn't valid text in this encoding
+    Encoding(DataEncoding),
+    /// The bytes don't deserialize as a VaultInstruction. `offset` is how far decoding got:
+    /// the byte it failed on, or the first trailing byte past a complete instruction.
+    Instruction { offset: usize, message: String },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Encoding(encoding) => write!(f, "input is not valid {:?}", encoding),
+            DecodeError::Instruction { offset, message } => {
+                write!(f, "not a VaultInstruction at byte {}: {}", offset, message)
+            }
+        }
+    }
+}
+
+/// Bytes behind `input` in `encoding`, or in the detected one when None.
+pub fn parse_instruction_data(
+    input: &str,
+    encoding: Option<DataEncoding>,
+) -> Result<Vec<u8>, DecodeError> {
+    let input = input.trim();
+    let encoding = encoding.unwrap_or_else(|| DataEncoding::detect(input));
+    let bytes = match encoding {
+        DataEncoding::Hex => decode_hex(input.strip_prefix("0x").unwrap_or(input)),
+        DataEncoding::Base58 => bs58::decode(input).into_vec().ok(),
+        DataEncoding::Base64 => base64::decode(input).ok(),
+    };
+    bytes.ok_or(DecodeError::Encoding(encoding))
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Borsh-decodes `data`, which must hold exactly one instruction.
+pub fn decode_instruction(data: &[u8]) -> Result<VaultInstruction, DecodeError> {
+    let mut rest = data;
+    let failed_at = |rest: &[u8], message: String| DecodeError::Instruction {
+        offset: data.len() - rest.len(),
+        message,
+    };
+    let instruction = VaultInstruction::deserialize(&mut rest)
+        .map_err(|e| failed_at(rest, e.to_string()))?;
+    if !rest.is_empty() {
+        return Err(failed_at(rest, format!("{} trailing bytes", rest.len())));
+    }
+    Ok(instruction)
+}
+
+/// Variant name alone, e.g. `SetParam`.
+pub fn instruction_name(instruction: &VaultInstruction) -> String {
+    let name = match instruction {
+        VaultInstruction::Initialize { .. } => "Initialize",
+        VaultInstruction::Deposite { .. } => "Deposite",
+        VaultInstruction::Withdraw { .. } => "Withdraw",
+        VaultInstruction::WithdrawSOL { .. } => "WithdrawSOL",
+        VaultInstruction::Transfer { .. } => "Transfer",
+        VaultInstruction::InitializeMultiSig { .. } => "InitializeMultiSig",
+        VaultInstruction::CreateMultiSigTransaction { .. } => "CreateMultiSigTransaction",
+        VaultInstruction::ApproveMultiSigTransaction { .. } => "ApproveMultiSigTransaction",
+        VaultInstruction::ExecuteMultiSigTransaction { .. } => "ExecuteMultiSigTransaction",
+        VaultInstruction::SetMultiSigOwners { .. } => "SetMultiSigOwners",
+        VaultInstruction::ChangeMultiSigThreshold { .. } => "ChangeMultiSigThreshold",
+        VaultInstruction::CreateProposal { .. } => "CreateProposal",
+        VaultInstruction::ApproveProposal { .. } => "ApproveProposal",
+        VaultInstruction::ExecuteProposal { .. } => "ExecuteProposal",
+        VaultInstruction::RejectProposal { .. } => "RejectProposal",
+        VaultInstruction::PauseVault => "PauseVault",
+        VaultInstruction::UnpauseVault => "UnpauseVault",
+        VaultInstruction::EmergencyWithdraw { .. } => "EmergencyWithdraw",
+        VaultInstruction::AddSupportedToken { .. } => "AddSupportedToken",
+        VaultInstruction::DepositMultiToken { .. } => "DepositMultiToken",
+        VaultInstruction::CreateTimeLock { .. } => "CreateTimeLock",
+        VaultInstruction::ClaimTimeLock { .. } => "ClaimTimeLock",
+        VaultInstruction::CancelTimeLock { .. } => "CancelTimeLock",
+        VaultInstruction::SetYieldStrategy { .. } => "SetYieldStrategy",
+        VaultInstruction::HarvestYield { .. } => "HarvestYield",
+        VaultInstruction::CompoundYield { .. } => "CompoundYield",
+        VaultInstruction::JupiterSwap { .. } => "JupiterSwap",
+        VaultInstruction::JupiterRoute { .. } => "JupiterRoute",
+        VaultInstruction::CollectFees => "CollectFees",
+        VaultInstruction::TransferAuthority { .. } => "TransferAuthority",
+        VaultInstruction::UpdateEmergencyAdmin { .. } => "UpdateEmergencyAdmin",
+        VaultInstruction::InitializeGovernance { .. } => "InitializeGovernance",
+        VaultInstruction::CreateGovernanceProposal { .. } => "CreateGovernanceProposal",
+        VaultInstruction::CastVote { .. } => "CastVote",
+        VaultInstruction::QueueProposal { .. } => "QueueProposal",
+        VaultInstruction::ExecuteGovernanceProposal { .. } => "ExecuteGovernanceProposal",
+        VaultInstruction::UpdateGovernanceConfig { .. } => "UpdateGovernanceConfig",
+        VaultInstruction::UpdateVaultConfig { .. } => "UpdateVaultConfig",
+        VaultInstruction::MigrateVault => "MigrateVault",
+        VaultInstruction::SetMultiSigProposalLimit { .. } => "SetMultiSigProposalLimit",
+        VaultInstruction::AttestMessage { .. } => "AttestMessage",
+        VaultInstruction::SetMultiSigRecovery { .. } => "SetMultiSigRecovery",
+        VaultInstruction::InitiateRecovery => "InitiateRecovery",
+        VaultInstruction::VetoRecovery => "VetoRecovery",
+        VaultInstruction::FinalizeRecovery => "FinalizeRecovery",
+        VaultInstruction::DeployToStrategy { .. } => "DeployToStrategy",
+        VaultInstruction::WithdrawFromStrategy { .. } => "WithdrawFromStrategy",
+        VaultInstruction::AcknowledgeStrategyLoss { .. } => "AcknowledgeStrategyLoss",
+        VaultInstruction::FundExecutionTip { .. } => "FundExecutionTip",
+        VaultInstruction::CancelMultiSigTransaction { .. } => "CancelMultiSigTransaction",
+        VaultInstruction::ExtendPause => "ExtendPause",
+        VaultInstruction::SetMaxPauseDuration { .. } => "SetMaxPauseDuration",
+        VaultInstruction::ClaimTimeLockPartial { .. } => "ClaimTimeLockPartial",
+        VaultInstruction::SetStreamConfig { .. } => "SetStreamConfig",
+        VaultInstruction::PruneMultiSigTransactions => "PruneMultiSigTransactions",
+        VaultInstruction::SetExecutionDelay { .. } => "SetExecutionDelay",
+        VaultInstruction::ExpediteTransaction { .. } => "ExpediteTransaction",
+        VaultInstruction::SetParam { .. } => "SetParam",
+        VaultInstruction::View { .. } => "View",
+        VaultInstruction::CreateTemplate { .. } => "CreateTemplate",
+        VaultInstruction::DeleteTemplate { .. } => "DeleteTemplate",
+        VaultInstruction::CreateTransactionFromTemplate { .. } => "CreateTransactionFromTemplate",
+        VaultInstruction::DepositWrappedSOL { .. } => "DepositWrappedSOL",
+        VaultInstruction::RegisterVoterWithLock { .. } => "RegisterVoterWithLock",
+        VaultInstruction::UnregisterVoter => "UnregisterVoter",
+        VaultInstruction::InitializeVault { .. } => "InitializeVault",
+        VaultInstruction::UpdateYieldStrategy { .. } => "UpdateYieldStrategy",
+        VaultInstruction::SetStrategyStatus { .. } => "SetStrategyStatus",
+        VaultInstruction::EmergencyExitStrategy { .. } => "EmergencyExitStrategy",
+        VaultInstruction::InitializeGlobalConfig { .. } => "InitializeGlobalConfig",
+        VaultInstruction::UpdateGlobalConfig { .. } => "UpdateGlobalConfig",
+        VaultInstruction::AcknowledgeUpgradeAuthority { .. } => "AcknowledgeUpgradeAuthority",
+        VaultInstruction::ApproveWithSignature { .. } => "ApproveWithSignature",
+        VaultInstruction::ExtendTransactionExpiry { .. } => "ExtendTransactionExpiry",
+        VaultInstruction::ProposeSolTransfer { .. } => "ProposeSolTransfer",
+        VaultInstruction::ProposeTokenTransfer { .. } => "ProposeTokenTransfer",
+        VaultInstruction::SweepUntrackedLamports => "SweepUntrackedLamports",
+        VaultInstruction::SplitTimeLock { .. } => "SplitTimeLock",
+        VaultInstruction::RequestWithdrawal { .. } => "RequestWithdrawal",
+        VaultInstruction::ClaimWithdrawal { .. } => "ClaimWithdrawal",
+        VaultInstruction::CancelWithdrawalRequest { .. } => "CancelWithdrawalRequest",
+        VaultInstruction::EmitHealthCheck => "EmitHealthCheck",
+        VaultInstruction::TransferV2 { .. } => "TransferV2",
+        VaultInstruction::RefreshSupportedTokenMetadata { .. } => "RefreshSupportedTokenMetadata",
+        VaultInstruction::ClaimTimeLockV2 { .. } => "ClaimTimeLockV2",
+        VaultInstruction::CancelTimeLockV2 { .. } => "CancelTimeLockV2",
+        VaultInstruction::CouncilApproveProposal { .. } => "CouncilApproveProposal",
+        VaultInstruction::InitializeVaultV2 { .. } => "InitializeVaultV2",
+        VaultInstruction::FinalizeSetup => "FinalizeSetup",
+        VaultInstruction::DepositAsDelegate { .. } => "DepositAsDelegate",
+        VaultInstruction::Tick { .. } => "Tick",
+        VaultInstruction::CreateMultiSigTransactionV2 { .. } => "CreateMultiSigTransactionV2",
+        VaultInstruction::CreateMultiSigTransactionV3 { .. } => "CreateMultiSigTransactionV3",
+        VaultInstruction::CreateTransactionBuffer { .. } => "CreateTransactionBuffer",
+        VaultInstruction::WriteTransactionBuffer { .. } => "WriteTransactionBuffer",
+        VaultInstruction::FinalizeTransactionFromBuffer { .. } => "FinalizeTransactionFromBuffer",
+        VaultInstruction::CloseTransactionBuffer => "CloseTransactionBuffer",
+        VaultInstruction::AssignTimeLockMint { .. } => "AssignTimeLockMint",
+        VaultInstruction::SetYieldStrategyV2 { .. } => "SetYieldStrategyV2",
+        VaultInstruction::SetMintMinimums { .. } => "SetMintMinimums",
+    };
+    name.to_string()
+}
+
+// The field an instruction's token amount is in, with its value
+fn instruction_amount(instruction: &VaultInstruction) -> Option<(&'static str, u64)> {
+    match instruction {
+        VaultInstruction::Deposite { amount }
+        | VaultInstruction::Withdraw { amount }
+        | VaultInstruction::WithdrawSOL { amount }
+        | VaultInstruction::Transfer { amount, .. }
+        | VaultInstruction::EmergencyWithdraw { amount, .. }
+        | VaultInstruction::DepositMultiToken { amount, .. }
+        | VaultInstruction::CreateTimeLock { amount, .. }
+        | VaultInstruction::JupiterSwap { amount, .. }
+        | VaultInstruction::JupiterRoute { amount, .. }
+        | VaultInstruction::DeployToStrategy { amount, .. }
+        | VaultInstruction::WithdrawFromStrategy { amount, .. }
+        | VaultInstruction::ClaimTimeLockPartial { amount, .. }
+        | VaultInstruction::DepositWrappedSOL { amount, .. }
+        | VaultInstruction::RegisterVoterWithLock { amount, .. }
+        | VaultInstruction::ProposeSolTransfer { amount, .. }
+        | VaultInstruction::ProposeTokenTransfer { amount, .. }
+        | VaultInstruction::RequestWithdrawal { amount, .. }
+        | VaultInstruction::TransferV2 { amount, .. }
+        | VaultInstruction::DepositAsDelegate { amount, .. } => Some(("amount", *amount)),
+        VaultInstruction::SplitTimeLock { amount_to_new, .. } => {
+            Some(("amount_to_new", *amount_to_new))
+        }
+        _ => None,
+    }
+}
+
+/// `amount` in whole tokens of a mint with `decimals`, e.g. 1500000 at 6 decimals is `1.5`.
+pub fn format_token_amount(amount: u64, decimals: u8) -> String {
+    let scale = match 10u128.checked_pow(decimals as u32) {
+        Some(scale) => scale,
+        None => return amount.to_string(),
+    };
+    let (whole, frac) = (amount as u128 / scale, amount as u128 % scale);
+    if frac == 0 {
+        return whole.to_string();
+    }
+    let frac = format!("{:0width$}", frac, width = decimals as usize);
+    format!("{}.{}", whole, frac.trim_end_matches('0'))
+}
+
+/// The token an instruction moves or configures, for looking up a symbol to show next to
+/// the amounts: the input mint of a swap, the voting mint of InitializeGovernance, and None
+/// for a SOL TransferV2.
+pub fn instruction_mint(instruction: &VaultInstruction) -> Option<Pubkey> {
+    match instruction {
+        VaultInstruction::AddSupportedToken { mint, .. }
+        | VaultInstruction::DepositMultiToken { mint, .. }
+        | VaultInstruction::CreateTimeLock { mint, .. }
+        | VaultInstruction::ProposeTokenTransfer { mint, .. }
+        | VaultInstruction::RequestWithdrawal { mint, .. }
+        | VaultInstruction::RefreshSupportedTokenMetadata { mint, .. }
+        | VaultInstruction::AssignTimeLockMint { mint, .. }
+        | VaultInstruction::SetMintMinimums { mint, .. } => Some(*mint),
+        VaultInstruction::EmergencyWithdraw { token_mint, .. }
+        | VaultInstruction::SetYieldStrategy { token_mint, .. }
+        | VaultInstruction::HarvestYield { token_mint }
+        | VaultInstruction::CompoundYield { token_mint }
+        | VaultInstruction::DeployToStrategy { token_mint, .. }
+        | VaultInstruction::WithdrawFromStrategy { token_mint, .. }
+        | VaultInstruction::AcknowledgeStrategyLoss { token_mint }
+        | VaultInstruction::UpdateYieldStrategy { token_mint, .. }
+        | VaultInstruction::SetStrategyStatus { token_mint, .. }
+        | VaultInstruction::EmergencyExitStrategy { token_mint, .. }
+        | VaultInstruction::SetYieldStrategyV2 { token_mint, .. } => Some(*token_mint),
+        VaultInstruction::JupiterSwap { input_mint, .. }
+        | VaultInstruction::JupiterRoute { input_mint, .. } => Some(*input_mint),
+        VaultInstruction::InitializeGovernance { voting_token_mint, .. } => {
+            Some(*voting_token_mint)
+        }
+        VaultInstruction::TransferV2 { mint, .. } => *mint,
+        _ => None,
+    }
+}
+
+/// Multi-line rendering of the variant and every field, pubkeys in base58. With the mint's
+/// `decimals` (from its spl_token Mint account), the amount field is followed by a
+/// `// <whole tokens>` comment.
+pub fn format_instruction(instruction: &VaultInstruction, decimals: Option<u8>) -> String {
+    let pretty = format!("{:#?}", instruction);
+    let (decimals, (field, amount)) = match (decimals, instruction_amount(instruction)) {
+        (Some(decimals), Some(amount)) => (decimals, amount),
+        _ => return pretty,
+    };
+    // Top-level fields are the lines indented once
+    let line = format!("    {}: {},", field, amount);
+    pretty
+        .lines()
+        .map(|l| {
+            if l == line {
+                format!("{} // {}", l, format_token_amount(amount, decimals))
+            } else {
+                l.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text `input` in `encoding` (or the detected one) all the way to [`format_instruction`]
+/// output. On failure the error says where decoding stopped.
+pub fn decode_instruction_data(
+    input: &str,
+    encoding: Option<DataEncoding>,
+    decimals: Option<u8>,
+) -> Result<String, DecodeError> {
+    let data = parse_instruction_data(input, encoding)?;
+    Ok(format_instruction(&decode_instruction(&data)?, decimals))
+}
+
+/// The stored payload decoded, when the transaction calls this program; None for any other
+/// target, whose data means nothing to VaultInstruction.
+pub fn decode_multisig_payload(
+    transaction: &MultiSigTransaction,
+) -> Option<Result<VaultInstruction, DecodeError>> {
+    (transaction.program_id == ID).then(|| decode_instruction(&transaction.data))
+}
+
+/// The VaultError behind a failed instruction, if it failed with one of this program's codes.
+pub fn decode_instruction_error(error: &InstructionError) -> Option<VaultError> {
+    match error {
+        InstructionError::Custom(code) => VaultError::from_code(*code),
+        _ => None,
+    }
+}
+
+/// `error` as an operator should read it: `InvalidAmount (15): Invalid amount specified` for
+/// this program's errors, the runtime's own message otherwise.
+pub fn describe_instruction_error(error: &InstructionError) -> String {
+    match (decode_instruction_error(error), error) {
+        (Some(vault_error), InstructionError::Custom(code)) => {
+            format!("{:?} ({}): {}", vault_error, code, vault_error)
+        }
+        _ => error.to_string(),
+    }
+}
@@ -0,0 +1,62 @@
+use std::fmt;
+
+use solana_program::pubkey::Pubkey;
+
+// Startup check for tools about to send instructions: is the program they build for deployed
+// on the cluster they're pointed at? The builders always target crate::ID, so that is the
+// program to check; a missing one otherwise only shows up as an opaque simulation failure.
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgramCheckError {
+    NotDeployed { program_id: Pubkey, cluster: String },
+    NotExecutable { program_id: Pubkey, cluster: String },
+}
+
+impl fmt::Display for ProgramCheckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramCheckError::NotDeployed { program_id, cluster } => {
+                write!(f, "vault program {} isn't deployed on {}", program_id, cluster)
+            }
+            ProgramCheckError::NotExecutable { program_id, cluster } => {
+                write!(f, "{} on {} is not an executable account", program_id, cluster)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProgramCheckError {}
+
+/// Name of the cluster behind an RPC URL for messages: mainnet-beta, devnet, testnet or
+/// localnet for the well-known endpoints, the URL itself otherwise.
+pub fn cluster_name(rpc_url: &str) -> String {
+    let host = rpc_url
+        .split("://")
+        .last()
+        .unwrap_or_default()
+        .split(|c| c == '/' || c == ':')
+        .next()
+        .unwrap_or_default();
+    match host {
+        "api.mainnet-beta.solana.com" => "mainnet-beta".to_string(),
+        "api.devnet.solana.com" => "devnet".to_string(),
+        "api.testnet.solana.com" => "testnet".to_string(),
+        "localhost" | "127.0.0.1" | "0.0.0.0" => "localnet".to_string(),
+        _ => rpc_url.to_string(),
+    }
+}
+
+/// Checks `program_id` against what `get_account` returned for it at `rpc_url`: `None` when
+/// there is no such account, otherwise its `executable` flag.
+pub fn check_program_account(
+    program_id: &Pubkey,
+    rpc_url: &str,
+    executable: Option<bool>,
+) -> Result<(), ProgramCheckError> {
+    let cluster = cluster_name(rpc_url);
+    match executable {
+        Some(true) => Ok(()),
+        Some(false) => Err(ProgramCheckError::NotExecutable { program_id: *program_id, cluster }),
+        None => Err(ProgramCheckError::NotDeployed { program_id: *program_id, cluster }),
+    }
+}
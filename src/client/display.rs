@@ -0,0 +1,53 @@
+use serde_json::Value;
+
+// Display helpers for JSON dumps; on-chain timestamps stay plain unix seconds
+
+/// Formats unix seconds as an RFC3339 UTC timestamp, e.g. `2024-03-01T12:00:00Z`.
+pub fn rfc3339(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86_400);
+    let secs = timestamp.rem_euclid(86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+fn is_timestamp_field(key: &str) -> bool {
+    key == "timestamp"
+        || key.ends_with("last_updated")
+        || key.ends_with("_time")
+        || key.ends_with("_at")
+}
+
+/// Rewrites timestamp-like integer fields in a serialized state or event as RFC3339 strings.
+pub fn humanize_timestamps(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                match field.as_i64() {
+                    Some(ts) if is_timestamp_field(key) => *field = Value::String(rfc3339(ts)),
+                    _ => humanize_timestamps(field),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(humanize_timestamps),
+        _ => {}
+    }
+}
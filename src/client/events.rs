@@ -0,0 +1,345 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::events::*;
+
+// emit_event! logs events as `EVENT: <json>`, which the runtime prefixes with `Program log: `
+const EVENT_PREFIX: &str = "EVENT: ";
+const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+
+/// Parses one transaction log line, returning the event JSON if the line carries one.
+pub fn parse_event_line(line: &str) -> Option<Value> {
+    let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+    let json = line.strip_prefix(EVENT_PREFIX)?;
+    serde_json::from_str(json).ok()
+}
+
+/// All events found in a transaction's log messages, in emission order.
+pub fn parse_events<S: AsRef<str>>(logs: &[S]) -> Vec<Value> {
+    logs.iter()
+        .filter_map(|line| parse_event_line(line.as_ref()))
+        .collect()
+}
+
+/// The `event_type` recorded in an event's base fields.
+pub fn event_type(event: &Value) -> Option<&str> {
+    event.get("base")?.get("event_type")?.as_str()
+}
+
+/// The base `schema_version`; events logged before versioning report 0.
+pub fn schema_version(event: &Value) -> Option<u8> {
+    let base = event.get("base")?;
+    match base.get("schema_version") {
+        Some(version) => version.as_u64().and_then(|v| u8::try_from(v).ok()),
+        None => Some(0),
+    }
+}
+
+/// An event this client could not map to a known layout, kept as raw JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawEvent {
+    pub event_type: Option<String>,
+    pub schema_version: Option<u8>,
+    pub value: Value,
+}
+
+#[derive(Debug)]
+pub enum ParsedEvent {
+    VaultInitialized(VaultInitializedEvent),
+    VaultConfigUpdated(VaultConfigUpdatedEvent),
+    VaultMigrated(VaultMigratedEvent),
+    VaultPaused(VaultPausedEvent),
+    VaultUnpaused(VaultUnpausedEvent),
+    VaultPauseExtended(VaultPauseExtendedEvent),
+    VaultPauseExpired(VaultPauseExpiredEvent),
+    MaxPauseDurationUpdated(MaxPauseDurationUpdatedEvent),
+    TokenAdded(TokenAddedEvent),
+    TokenDeposited(TokenDepositedEvent),
+    // Also token_transferred, and sol_withdrawn and sol_transferred from before schema
+    // version 4
+    TokenWithdrawn(TokenWithdrawnEvent),
+    // sol_withdrawn and sol_transferred share this layout
+    SolWithdrawn(SolWithdrawnEvent),
+    TimeLockCreated(TimeLockCreatedEvent),
+    TimeLockClaimed(TimeLockClaimedEvent),
+    StreamConfigUpdated(StreamConfigUpdatedEvent),
+    MultiSigInitialized(MultiSigInitializedEvent),
+    MultiSigTransactionCreated(MultiSigTransactionCreatedEvent),
+    MultiSigTransactionApproved(MultiSigTransactionApprovedEvent),
+    MultiSigTransactionExecuted(MultiSigTransactionExecutedEvent),
+    MultiSigTransactionExecutionFailed(MultiSigTransactionExecutionFailedEvent),
+    MultiSigOwnersUpdated(MultiSigOwnersUpdatedEvent),
+    MultiSigThresholdUpdated(MultiSigThresholdUpdatedEvent),
+    MultiSigProposalLimitUpdated(MultiSigProposalLimitUpdatedEvent),
+    Attestation(AttestationEvent),
+    RecoveryConfigured(RecoveryConfiguredEvent),
+    RecoveryInitiated(RecoveryInitiatedEvent),
+    RecoveryVetoed(RecoveryVetoedEvent),
+    RecoveryFinalized(RecoveryFinalizedEvent),
+    YieldStrategySet(YieldStrategySetEvent),
+    StrategyDeployed(StrategyDeployedEvent),
+    StrategyWithdrawn(StrategyWithdrawnEvent),
+    StrategyLoss(StrategyLossEvent),
+    StrategyLossAcknowledged(StrategyLossAcknowledgedEvent),
+    StrategyStatusChanged(StrategyStatusChangedEvent),
+    StrategyExited(StrategyExitedEvent),
+    ExecutionTipFunded(ExecutionTipFundedEvent),
+    ExecutionTipPaid(ExecutionTipPaidEvent),
+    MultiSigTransactionCancelled(MultiSigTransactionCancelledEvent),
+    MultiSigTransactionsPruned(MultiSigTransactionsPrunedEvent),
+    MultiSigExecutionDelayUpdated(MultiSigExecutionDelayUpdatedEvent),
+    MultiSigTransactionExpedited(MultiSigTransactionExpeditedEvent),
+    ParamUpdated(ParamUpdatedEvent),
+    TemplateCreated(TemplateCreatedEvent),
+    TemplateDeleted(TemplateDeletedEvent),
+    GlobalConfigUpdated(GlobalConfigUpdatedEvent),
+    UpgradeAuthorityAcknowledged(UpgradeAuthorityAcknowledgedEvent),
+    FeesCollected(FeesCollectedEvent),
+    MultiSigTransactionExpiryExtended(MultiSigTransactionExpiryExtendedEvent),
+    MultiSigTransferProposed(MultiSigTransferProposedEvent),
+    UntrackedLamportsSwept(UntrackedLamportsSweptEvent),
+    CircuitBreakerTripped(CircuitBreakerTrippedEvent),
+    TimeLockSplit(TimeLockSplitEvent),
+    WithdrawalRequested(WithdrawalRequestedEvent),
+    WithdrawalClaimed(WithdrawalClaimedEvent),
+    WithdrawalRequestCancelled(WithdrawalRequestCancelledEvent),
+    HealthCheck(HealthCheckEvent),
+    SupportedTokenRefreshed(SupportedTokenRefreshedEvent),
+    GovernanceProposalExecuted(GovernanceProposalExecutedEvent),
+    CouncilProposalApproved(CouncilProposalApprovedEvent),
+    SetupFinalized(SetupFinalizedEvent),
+    Tick(TickEvent),
+    TransactionBufferFinalized(TransactionBufferFinalizedEvent),
+    TimeLockMintAssigned(TimeLockMintAssignedEvent),
+    MintMinimumsUpdated(MintMinimumsUpdatedEvent),
+    TimeLockCancelled(TimeLockCancelledEvent),
+    Raw(RawEvent),
+}
+
+fn typed<T: DeserializeOwned>(value: &Value, wrap: fn(T) -> ParsedEvent) -> Option<ParsedEvent> {
+    serde_json::from_value(value.clone()).ok().map(wrap)
+}
+
+/// Maps an event to its typed layout. Events from a newer schema than this client knows,
+/// unknown event types, and payloads that don't match the expected layout come back as
+/// `ParsedEvent::Raw` instead of failing.
+pub fn parse_event(value: Value) -> ParsedEvent {
+    let kind = event_type(&value).map(str::to_string);
+    let version = schema_version(&value);
+
+    let parsed = match (kind.as_deref(), version) {
+        (Some(kind), Some(version)) if version <= EVENT_SCHEMA_VERSION => match kind {
+            "vault_initialized" => typed(&value, ParsedEvent::VaultInitialized),
+            "vault_config_updated" => typed(&value, ParsedEvent::VaultConfigUpdated),
+            "vault_migrated" => typed(&value, ParsedEvent::VaultMigrated),
+            "vault_paused" => typed(&value, ParsedEvent::VaultPaused),
+            "vault_unpaused" => typed(&value, ParsedEvent::VaultUnpaused),
+            "vault_pause_extended" => typed(&value, ParsedEvent::VaultPauseExtended),
+            "vault_pause_expired" => typed(&value, ParsedEvent::VaultPauseExpired),
+            "max_pause_duration_updated" => typed(&value, ParsedEvent::MaxPauseDurationUpdated),
+            "token_added" => typed(&value, ParsedEvent::TokenAdded),
+            "token_deposited" => typed(&value, ParsedEvent::TokenDeposited),
+            "token_withdrawn" | "token_transferred" => typed(&value, ParsedEvent::TokenWithdrawn),
+            "sol_withdrawn" | "sol_transferred" if version < 4 => {
+                typed(&value, ParsedEvent::TokenWithdrawn)
+            }
+            "sol_withdrawn" | "sol_transferred" => typed(&value, ParsedEvent::SolWithdrawn),
+            "time_lock_created" => typed(&value, ParsedEvent::TimeLockCreated),
+            "time_lock_claimed" => typed(&value, ParsedEvent::TimeLockClaimed),
+            "stream_config_updated" => typed(&value, ParsedEvent::StreamConfigUpdated),
+            "multisig_initialized" => typed(&value, ParsedEvent::MultiSigInitialized),
+            "multisig_transaction_created" => {
+                typed(&value, ParsedEvent::MultiSigTransactionCreated)
+            }
+            "multisig_transaction_approved" => {
+                typed(&value, ParsedEvent::MultiSigTransactionApproved)
+            }
+            "multisig_transaction_executed" => {
+                typed(&value, ParsedEvent::MultiSigTransactionExecuted)
+            }
+            "multisig_transaction_execution_failed" => {
+                typed(&value, ParsedEvent::MultiSigTransactionExecutionFailed)
+            }
+            "multisig_owners_updated" => typed(&value, ParsedEvent::MultiSigOwnersUpdated),
+            "multisig_threshold_updated" => typed(&value, ParsedEvent::MultiSigThresholdUpdated),
+            "multisig_proposal_limit_updated" => {
+                typed(&value, ParsedEvent::MultiSigProposalLimitUpdated)
+            }
+            "message_attested" => typed(&value, ParsedEvent::Attestation),
+            "recovery_configured" => typed(&value, ParsedEvent::RecoveryConfigured),
+            "recovery_initiated" => typed(&value, ParsedEvent::RecoveryInitiated),
+            "recovery_vetoed" => typed(&value, ParsedEvent::RecoveryVetoed),
+            "recovery_finalized" => typed(&value, ParsedEvent::RecoveryFinalized),
+            "yield_strategy_set" => typed(&value, ParsedEvent::YieldStrategySet),
+            "strategy_deployed" => typed(&value, ParsedEvent::StrategyDeployed),
+            "strategy_withdrawn" => typed(&value, ParsedEvent::StrategyWithdrawn),
+            "strategy_loss" => typed(&value, ParsedEvent::StrategyLoss),
+            "strategy_loss_acknowledged" => typed(&value, ParsedEvent::StrategyLossAcknowledged),
+            "strategy_status_changed" => typed(&value, ParsedEvent::StrategyStatusChanged),
+            "strategy_exited" => typed(&value, ParsedEvent::StrategyExited),
+            "execution_tip_funded" => typed(&value, ParsedEvent::ExecutionTipFunded),
+            "execution_tip_paid" => typed(&value, ParsedEvent::ExecutionTipPaid),
+            "multisig_transaction_cancelled" => {
+                typed(&value, ParsedEvent::MultiSigTransactionCancelled)
+            }
+            "multisig_transactions_pruned" => {
+                typed(&value, ParsedEvent::MultiSigTransactionsPruned)
+            }
+            "multisig_execution_delay_updated" => {
+                typed(&value, ParsedEvent::MultiSigExecutionDelayUpdated)
+            }
+            "multisig_transaction_expedited" => {
+                typed(&value, ParsedEvent::MultiSigTransactionExpedited)
+            }
+            "param_updated" => typed(&value, ParsedEvent::ParamUpdated),
+            "template_created" => typed(&value, ParsedEvent::TemplateCreated),
+            "template_deleted" => typed(&value, ParsedEvent::TemplateDeleted),
+            "global_config_initialized" | "global_config_updated" => {
+                typed(&value, ParsedEvent::GlobalConfigUpdated)
+            }
+            "upgrade_authority_acknowledged" => {
+                typed(&value, ParsedEvent::UpgradeAuthorityAcknowledged)
+            }
+            "fees_collected" => typed(&value, ParsedEvent::FeesCollected),
+            "multisig_transaction_expiry_extended" => {
+                typed(&value, ParsedEvent::MultiSigTransactionExpiryExtended)
+            }
+            "multisig_transfer_proposed" => typed(&value, ParsedEvent::MultiSigTransferProposed),
+            "untracked_lamports_swept" => typed(&value, ParsedEvent::UntrackedLamportsSwept),
+            "circuit_breaker_tripped" => typed(&value, ParsedEvent::CircuitBreakerTripped),
+            "time_lock_split" => typed(&value, ParsedEvent::TimeLockSplit),
+            "withdrawal_requested" => typed(&value, ParsedEvent::WithdrawalRequested),
+            "withdrawal_claimed" => typed(&value, ParsedEvent::WithdrawalClaimed),
+            "withdrawal_request_cancelled" => {
+                typed(&value, ParsedEvent::WithdrawalRequestCancelled)
+            }
+            "health_check" => typed(&value, ParsedEvent::HealthCheck),
+            "supported_token_refreshed" => typed(&value, ParsedEvent::SupportedTokenRefreshed),
+            "governance_proposal_executed" => {
+                typed(&value, ParsedEvent::GovernanceProposalExecuted)
+            }
+            "council_proposal_approved" => typed(&value, ParsedEvent::CouncilProposalApproved),
+            "setup_finalized" => typed(&value, ParsedEvent::SetupFinalized),
+            "tick" => typed(&value, ParsedEvent::Tick),
+            "transaction_buffer_finalized" => {
+                typed(&value, ParsedEvent::TransactionBufferFinalized)
+            }
+            "time_lock_mint_assigned" => typed(&value, ParsedEvent::TimeLockMintAssigned),
+            "mint_minimums_updated" => typed(&value, ParsedEvent::MintMinimumsUpdated),
+            "time_lock_cancelled" => typed(&value, ParsedEvent::TimeLockCancelled),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    parsed.unwrap_or_else(|| {
+        ParsedEvent::Raw(RawEvent {
+            event_type: kind,
+            schema_version: version,
+            value,
+        })
+    })
+}
+
+/// Typed events from a transaction's log messages, in emission order.
+pub fn parse_typed_events<S: AsRef<str>>(logs: &[S]) -> Vec<ParsedEvent> {
+    parse_events(logs).into_iter().map(parse_event).collect()
+}
+
+/// An `ExecuteMultiSigTransaction` log marker: the stored instruction about to be invoked, or
+/// the error it failed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecLogLine {
+    Invoking {
+        target: String,
+        accounts: usize,
+        data_len: usize,
+        transaction_id: u64,
+    },
+    Failed {
+        transaction_id: u64,
+        error: String,
+    },
+}
+
+/// Parses one log line as an EXEC or EXEC_FAILED marker. Previews and execution reports print
+/// these ahead of the full log, as they name the instruction that ran and why it failed.
+pub fn parse_exec_line(line: &str) -> Option<ExecLogLine> {
+    let line = line.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(line);
+    if let Some(rest) = line.strip_prefix("EXEC_FAILED ") {
+        let rest = rest.strip_prefix("tx_id=")?;
+        let (id, error) = rest.split_once(" error=")?;
+        return Some(ExecLogLine::Failed {
+            transaction_id: id.parse().ok()?,
+            error: error.to_string(),
+        });
+    }
+    let rest = line.strip_prefix("EXEC ")?;
+    let mut fields = rest.split(' ');
+    let mut field = |name: &str| fields.next()?.strip_prefix(name).map(str::to_string);
+    let target = field("target=")?;
+    let accounts = field("accounts=")?.parse().ok()?;
+    let data_len = field("data_len=")?.parse().ok()?;
+    let transaction_id = field("tx_id=")?.parse().ok()?;
+    Some(ExecLogLine::Invoking {
+        target,
+        accounts,
+        data_len,
+        transaction_id,
+    })
+}
+
+/// EXEC markers from a transaction's log messages, in order.
+pub fn parse_exec_lines<S: AsRef<str>>(logs: &[S]) -> Vec<ExecLogLine> {
+    logs.iter()
+        .filter_map(|line| parse_exec_line(line.as_ref()))
+        .collect()
+}
+
+/// Columns of [`ledger_csv_row`], for a watcher exporting value-changing events to CSV.
+pub const LEDGER_CSV_HEADER: &str = "slot,timestamp,event_type,vault,mint,amount,fee_amount,\
+resulting_balance,resulting_tvl,accrued_fees_after";
+
+/// One CSV line for a token deposit, or a token or SOL withdrawal or transfer, `None` for any
+/// other event. SOL rows leave the mint empty. Events before schema version 6 carry 0 in the
+/// resulting_* and accrued_fees_after columns.
+pub fn ledger_csv_row(event: &ParsedEvent) -> Option<String> {
+    let (base, mint, amount, fee, resulting) = match event {
+        ParsedEvent::TokenDeposited(e) => (
+            &e.base,
+            e.token_mint.to_string(),
+            e.amount,
+            e.fee_amount,
+            (e.resulting_balance, e.resulting_tvl, e.accrued_fees_after),
+        ),
+        ParsedEvent::TokenWithdrawn(e) => (
+            &e.base,
+            e.token_mint.to_string(),
+            e.amount,
+            e.fee_amount,
+            (e.resulting_balance, e.resulting_tvl, e.accrued_fees_after),
+        ),
+        ParsedEvent::SolWithdrawn(e) => (
+            &e.base,
+            String::new(),
+            e.amount,
+            e.fee_amount,
+            (e.resulting_balance, e.resulting_tvl, e.accrued_fees_after),
+        ),
+        _ => return None,
+    };
+    let (resulting_balance, resulting_tvl, accrued_fees_after) = resulting;
+    Some(format!(
+        "{},{},{},{},{},{},{},{},{},{}",
+        base.slot,
+        base.timestamp,
+        base.event_type,
+        base.vault,
+        mint,
+        amount,
+        fee,
+        resulting_balance,
+        resulting_tvl,
+        accrued_fees_after
+    ))
+}
@@ -0,0 +1,96 @@
+use serde::Serialize;
+use solana_program::{program_pack::Pack, pubkey, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+
+use crate::state::Vault;
+
+// Per-mint holdings of a vault: what the vault thinks it holds next to what its ATAs actually
+// hold, for spotting drift between the two
+
+const WELL_KNOWN_MINTS: &[(Pubkey, &str)] = &[
+    (pubkey!("So11111111111111111111111111111111111111112"), "wSOL"),
+    (pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v"), "USDC"),
+    (pubkey!("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB"), "USDT"),
+    (pubkey!("mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So"), "mSOL"),
+    (pubkey!("J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn"), "JitoSOL"),
+    (pubkey!("JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN"), "JUP"),
+    (pubkey!("DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263"), "BONK"),
+];
+
+/// Symbol for a handful of mainnet mints; anything else needs an off-chain metadata lookup.
+pub fn well_known_symbol(mint: &Pubkey) -> Option<&'static str> {
+    WELL_KNOWN_MINTS
+        .iter()
+        .find(|(known, _)| known == mint)
+        .map(|(_, symbol)| *symbol)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TokenHolding {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub mint: Pubkey,
+    pub symbol: Option<String>,
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub token_account: Pubkey,
+    pub tracked: u64,
+    /// None when the ATA is missing or doesn't decode as a token account
+    pub on_chain: Option<u64>,
+    pub locked: u64,
+    /// `on_chain - tracked`; positive means the ATA holds more than the vault has recorded
+    pub drift: Option<i128>,
+}
+
+/// One entry per supported token (optionally just `mint_filter`), with the vault ATA's
+/// balance read through `fetch_account`, which returns raw account data or None if the
+/// account doesn't exist.
+pub fn token_holdings(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    mint_filter: Option<&Pubkey>,
+    mut fetch_account: impl FnMut(&Pubkey) -> Option<Vec<u8>>,
+) -> Vec<TokenHolding> {
+    vault
+        .supported_tokens
+        .iter()
+        .filter(|token| mint_filter.map_or(true, |mint| token.mint == *mint))
+        .map(|token| {
+            let token_account = get_associated_token_address(vault_key, &token.mint);
+            let on_chain = fetch_account(&token_account)
+                .and_then(|data| TokenAccount::unpack(&data).ok())
+                .map(|account| account.amount);
+            let tracked = vault.tracked_balance(&token.mint);
+            TokenHolding {
+                mint: token.mint,
+                symbol: well_known_symbol(&token.mint).map(str::to_string),
+                token_account,
+                tracked,
+                on_chain,
+                locked: vault.locked_for_timelocks(&token.mint),
+                drift: on_chain.map(|amount| amount as i128 - tracked as i128),
+            }
+        })
+        .collect()
+}
+
+/// Plain-text table of `holdings`, one row per mint.
+pub fn holdings_table(holdings: &[TokenHolding]) -> String {
+    let mut table = format!(
+        "{:<44} {:<8} {:>20} {:>20} {:>20} {:>21}\n",
+        "mint", "symbol", "tracked", "on-chain", "locked", "drift"
+    );
+    for holding in holdings {
+        let on_chain = holding.on_chain.map_or("missing".to_string(), |a| a.to_string());
+        let drift = holding.drift.map_or("-".to_string(), |d| format!("{:+}", d));
+        table.push_str(&format!(
+            "{:<44} {:<8} {:>20} {:>20} {:>20} {:>21}\n",
+            holding.mint.to_string(),
+            holding.symbol.as_deref().unwrap_or("?"),
+            holding.tracked,
+            on_chain,
+            holding.locked,
+            drift
+        ));
+    }
+    table
+}
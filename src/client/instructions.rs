@@ -0,0 +1,1500 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    bpf_loader_upgradeable,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+
+use crate::instruction::VaultInstruction;
+use crate::state::{
+    AccountRole, BalanceAssertion, GlobalConfig, GovernanceConfig, GovernanceInstruction,
+    GovernanceProposal, MultiSig, StrategyStatus, TransactionAccount, TransactionBuffer, Vault,
+    VaultConfig, VestingSchedule, VoteType, TOKEN_2022_PROGRAM_ID,
+};
+use crate::view::ViewQuery;
+use crate::ID;
+
+// Account orders mirror the next_account_info sequence in processor.rs
+
+fn build(accounts: Vec<AccountMeta>, data: VaultInstruction) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts,
+        data: data.try_to_vec().expect("instruction serialization"),
+    }
+}
+
+pub fn initialize(
+    authority: &Pubkey,
+    emergency_admin: &Pubkey,
+    index: u16,
+    allow_same_as_authority: bool,
+) -> Instruction {
+    let (vault, _) = Vault::find_address(&ID, authority, index);
+    build(
+        vec![
+            AccountMeta::new(vault, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*emergency_admin, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::InitializeVault {
+            index,
+            allow_same_as_authority,
+        },
+    )
+}
+
+/// [`initialize`] through InitializeVaultV2: unless `skip_setup_gate`, the vault takes no
+/// deposits or withdrawals until [`finalize_setup`].
+pub fn initialize_v2(
+    authority: &Pubkey,
+    emergency_admin: &Pubkey,
+    index: u16,
+    allow_same_as_authority: bool,
+    skip_setup_gate: bool,
+) -> Instruction {
+    let mut instruction = initialize(authority, emergency_admin, index, allow_same_as_authority);
+    instruction.data = VaultInstruction::InitializeVaultV2 {
+        index,
+        allow_same_as_authority,
+        skip_setup_gate,
+    }
+    .try_to_vec()
+    .expect("instruction serialization");
+    instruction
+}
+
+pub fn finalize_setup(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::FinalizeSetup,
+    )
+}
+
+pub fn deposit(vault: &Pubkey, mint: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::Deposite { amount },
+    )
+}
+
+/// Deposit from `on_behalf_of`'s associated token account, signed by `delegate` under an SPL
+/// approve for at least `amount`.
+pub fn deposit_as_delegate(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    delegate: &Pubkey,
+    on_behalf_of: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(on_behalf_of, mint), false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(*delegate, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::DepositAsDelegate {
+            amount,
+            on_behalf_of: *on_behalf_of,
+        },
+    )
+}
+
+pub fn deposit_wrapped_sol(
+    vault: &Pubkey,
+    user: &Pubkey,
+    amount: u64,
+    close_wrapped_account: bool,
+) -> Instruction {
+    let native_mint = spl_token::native_mint::id();
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(user, &native_mint), false),
+            AccountMeta::new(get_associated_token_address(vault, &native_mint), false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(native_mint, false),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::DepositWrappedSOL {
+            amount,
+            close_wrapped_account,
+        },
+    )
+}
+
+pub fn withdraw(vault: &Pubkey, mint: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(global_config_address(), false),
+        ],
+        VaultInstruction::Withdraw { amount },
+    )
+}
+
+/// Starts a two-step withdrawal on a vault with a withdrawal delay; `owner` signs and later
+/// claims.
+pub fn request_withdrawal(
+    vault: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::RequestWithdrawal {
+            mint: *mint,
+            amount,
+        },
+    )
+}
+
+/// Same accounts as [`withdraw`], paying ticket `ticket_id` out to `owner`.
+pub fn claim_withdrawal(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    ticket_id: u64,
+) -> Instruction {
+    build(
+        withdraw(vault, mint, owner, 0).accounts,
+        VaultInstruction::ClaimWithdrawal { ticket_id },
+    )
+}
+
+pub fn cancel_withdrawal_request(vault: &Pubkey, owner: &Pubkey, ticket_id: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CancelWithdrawalRequest { ticket_id },
+    )
+}
+
+pub fn withdraw_sol(
+    vault: &Pubkey,
+    recipient: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::WithdrawSOL { amount },
+    )
+}
+
+pub fn transfer(vault: &Pubkey, recipient: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::Transfer {
+            recipient: *recipient,
+            amount,
+        },
+    )
+}
+
+/// Transfer of `mint`, or of SOL without one, to `recipient`. The recipient's associated
+/// token account is created first, paid for by `payer`, when one is given. Apply
+/// [`with_token_program`] for a Token-2022 mint.
+pub fn transfer_v2(
+    vault: &Pubkey,
+    recipient: &Pubkey,
+    authority: &Pubkey,
+    mint: Option<&Pubkey>,
+    amount: u64,
+    payer: Option<&Pubkey>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*recipient, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    let create_ata = mint.is_some() && payer.is_some();
+    if let Some(mint) = mint {
+        accounts.push(AccountMeta::new(get_associated_token_address(vault, mint), false));
+        accounts.push(AccountMeta::new(get_associated_token_address(recipient, mint), false));
+        accounts.push(AccountMeta::new_readonly(spl_token::id(), false));
+        if let Some(payer) = payer {
+            accounts.push(AccountMeta::new(*payer, true));
+            accounts.push(AccountMeta::new_readonly(*mint, false));
+            accounts.push(AccountMeta::new_readonly(spl_associated_token_account::id(), false));
+        }
+    }
+    build(
+        accounts,
+        VaultInstruction::TransferV2 {
+            recipient: *recipient,
+            mint: mint.copied(),
+            amount,
+            create_ata,
+        },
+    )
+}
+
+// Permissionless; any fee payer can crank it
+pub fn sweep_untracked_lamports(vault: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SweepUntrackedLamports,
+    )
+}
+
+/// Heartbeat reporting the drift of the vault's associated token account for each of
+/// `mints`. Anyone may sign and pay for it.
+pub fn emit_health_check(vault: &Pubkey, mints: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    for mint in mints {
+        let token_account = get_associated_token_address(vault, mint);
+        accounts.push(AccountMeta::new_readonly(token_account, false));
+    }
+    build(accounts, VaultInstruction::EmitHealthCheck)
+}
+
+/// Crank settling up to `max_items` expired transactions, ended proposals and vested time
+/// locks. Anyone may sign and pay for it.
+pub fn tick(vault: &Pubkey, max_items: u16) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::Tick { max_items },
+    )
+}
+
+/// Picks up `mint`'s current decimals and token program after the mint was migrated. Refused
+/// while the vault tracks a balance of it, unless `allow_with_balance`.
+pub fn refresh_supported_token_metadata(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    allow_with_balance: bool,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::RefreshSupportedTokenMetadata {
+            mint: *mint,
+            allow_with_balance,
+        },
+    )
+}
+
+/// Idempotent, so a retry after the first attempt already landed still succeeds. Build the
+/// instruction by hand with `idempotent: false` to get `TokenAlreadySupported` instead.
+pub fn add_supported_token(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::AddSupportedToken {
+            mint: *mint,
+            bump: 0,
+            idempotent: true,
+        },
+    )
+}
+
+pub fn update_vault_config(vault: &Pubkey, authority: &Pubkey, config: VaultConfig) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::UpdateVaultConfig { config },
+    )
+}
+
+fn pause_call(vault: &Pubkey, signer: &Pubkey, data: VaultInstruction) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data,
+    )
+}
+
+pub fn pause_vault(vault: &Pubkey, emergency_admin: &Pubkey) -> Instruction {
+    pause_call(vault, emergency_admin, VaultInstruction::PauseVault)
+}
+
+pub fn unpause_vault(vault: &Pubkey, emergency_admin: &Pubkey) -> Instruction {
+    pause_call(vault, emergency_admin, VaultInstruction::UnpauseVault)
+}
+
+pub fn extend_pause(vault: &Pubkey, emergency_admin: &Pubkey) -> Instruction {
+    pause_call(vault, emergency_admin, VaultInstruction::ExtendPause)
+}
+
+/// Pays `amount` of `mint` to `recipient`'s ATA. Pass `cosigner` when the vault has
+/// `ParamKey::EmergencyRequiresCosign` set; it must be a multisig owner and sign too.
+pub fn emergency_withdraw(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    emergency_admin: &Pubkey,
+    cosigner: Option<&Pubkey>,
+    amount: u64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(get_associated_token_address(vault, mint), false),
+        AccountMeta::new(get_associated_token_address(recipient, mint), false),
+        AccountMeta::new_readonly(*emergency_admin, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    accounts.extend(cosigner.map(|cosigner| AccountMeta::new_readonly(*cosigner, true)));
+    accounts.push(AccountMeta::new_readonly(global_config_address(), false));
+    build(
+        accounts,
+        VaultInstruction::EmergencyWithdraw {
+            token_mint: *mint,
+            amount,
+        },
+    )
+}
+
+// One vault/recipient token account pair per mint, in the order given. Every mint has to
+// be held under `token_program`.
+pub fn collect_fees(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    fee_recipient: &Pubkey,
+    token_program: &Pubkey,
+    mints: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(*token_program, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    for mint in mints {
+        for owner in [vault, fee_recipient] {
+            let address = get_associated_token_address_with_program_id(owner, mint, token_program);
+            accounts.push(AccountMeta::new(address, false));
+        }
+    }
+    build(accounts, VaultInstruction::CollectFees)
+}
+
+pub fn set_max_pause_duration(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    max_pause_duration: i64,
+) -> Instruction {
+    pause_call(
+        vault,
+        authority,
+        VaultInstruction::SetMaxPauseDuration { max_pause_duration },
+    )
+}
+
+/// `payer` covers the rent if the account has to grow; it's only charged in that case.
+pub fn migrate_vault(vault: &Pubkey, authority: &Pubkey, payer: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        VaultInstruction::MigrateVault,
+    )
+}
+
+pub fn create_time_lock(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    beneficiary: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    duration: i64,
+    cliff_duration: Option<i64>,
+    schedule: VestingSchedule,
+    revocable: bool,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateTimeLock {
+            beneficiary: *beneficiary,
+            mint: *mint,
+            amount,
+            duration,
+            cliff_duration,
+            schedule,
+            revocable,
+        },
+    )
+}
+
+/// Claims everything releasable from lock `time_lock_id` (ClaimTimeLockV2).
+pub fn claim_time_lock(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    beneficiary: &Pubkey,
+    time_lock_id: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(beneficiary, mint), false),
+            AccountMeta::new_readonly(*beneficiary, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ClaimTimeLockV2 { time_lock_id },
+    )
+}
+
+/// Same accounts as [`claim_time_lock`]; `amount` may be anything up to the lock's
+/// currently claimable figure (see [`claimable_now`](crate::client::claimable_now)).
+pub fn claim_time_lock_partial(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    beneficiary: &Pubkey,
+    time_lock_id: u64,
+    amount: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(beneficiary, mint), false),
+            AccountMeta::new_readonly(*beneficiary, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ClaimTimeLockPartial {
+            time_lock_id,
+            amount,
+        },
+    )
+}
+
+pub fn split_time_lock(
+    vault: &Pubkey,
+    beneficiary: &Pubkey,
+    id: u64,
+    amount_to_new: u64,
+    new_beneficiary: &Pubkey,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*beneficiary, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SplitTimeLock {
+            id,
+            amount_to_new,
+            new_beneficiary: *new_beneficiary,
+        },
+    )
+}
+
+pub fn set_stream_config(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    exempt_from_pause: bool,
+    claim_fee_bps: u16,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SetStreamConfig {
+            exempt_from_pause,
+            claim_fee_bps,
+        },
+    )
+}
+
+/// Use [`MultiSig::signer_address`] instead when the vault's multisig state is at hand; it
+/// skips the bump search.
+pub fn multisig_signer_address(vault: &Pubkey, nonce: u8) -> Pubkey {
+    MultiSig::find_signer_address(vault, nonce, &ID).0
+}
+
+pub fn initialize_multisig(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u64,
+    nonce: u8,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::InitializeMultiSig {
+            owners,
+            threshold,
+            nonce,
+        },
+    )
+}
+
+/// Payloads are checked on-chain against `MultiSigTransaction::MAX_ACCOUNTS` and
+/// `MAX_DATA_LEN`; call `MultiSigTransaction::validate_payload` first to fail early.
+pub fn create_multisig_transaction(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+    data: Vec<u8>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateMultiSigTransaction {
+            program_id,
+            accounts,
+            data,
+        },
+    )
+}
+
+/// As `create_multisig_transaction`, with one role per account, which the program checks
+/// against `AccountRole::validate`.
+pub fn create_multisig_transaction_v2(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+    roles: Vec<AccountRole>,
+    data: Vec<u8>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateMultiSigTransactionV2 {
+            program_id,
+            accounts,
+            roles,
+            data,
+        },
+    )
+}
+
+/// As `create_multisig_transaction_v2`, with balance assertions the program checks once the
+/// transaction has run.
+pub fn create_multisig_transaction_v3(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+    roles: Vec<AccountRole>,
+    assertions: Vec<BalanceAssertion>,
+    data: Vec<u8>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateMultiSigTransactionV3 {
+            program_id,
+            accounts,
+            roles,
+            assertions,
+            data,
+        },
+    )
+}
+
+pub fn transaction_buffer_address(vault: &Pubkey, proposer: &Pubkey) -> Pubkey {
+    TransactionBuffer::find_address(vault, proposer, &ID).0
+}
+
+pub fn create_transaction_buffer(vault: &Pubkey, proposer: &Pubkey, total_len: u32) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new(transaction_buffer_address(vault, proposer), false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ],
+        VaultInstruction::CreateTransactionBuffer { total_len },
+    )
+}
+
+pub fn write_transaction_buffer(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    offset: u32,
+    chunk: Vec<u8>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new(transaction_buffer_address(vault, proposer), false),
+            AccountMeta::new_readonly(*proposer, true),
+        ],
+        VaultInstruction::WriteTransactionBuffer { offset, chunk },
+    )
+}
+
+/// Closes `proposer`'s buffer without proposing it; `signer` is the proposer or the vault
+/// authority, and the rent goes back to the proposer either way.
+pub fn close_transaction_buffer(vault: &Pubkey, proposer: &Pubkey, signer: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new(transaction_buffer_address(vault, proposer), false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*proposer, false),
+        ],
+        VaultInstruction::CloseTransactionBuffer,
+    )
+}
+
+/// Sets the mint of a time lock migrated without one.
+pub fn assign_time_lock_mint(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    time_lock_id: u64,
+    mint: &Pubkey,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::AssignTimeLockMint {
+            time_lock_id,
+            mint: *mint,
+        },
+    )
+}
+
+/// Revokes the unvested part of a revocable time lock.
+pub fn cancel_time_lock(vault: &Pubkey, authority: &Pubkey, time_lock_id: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CancelTimeLockV2 { time_lock_id },
+    )
+}
+
+/// Proposes the buffered data as a multisig transaction of `program_id` over `accounts`,
+/// closing the buffer.
+pub fn finalize_transaction_from_buffer(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(transaction_buffer_address(vault, proposer), false),
+        ],
+        VaultInstruction::FinalizeTransactionFromBuffer {
+            program_id,
+            accounts,
+        },
+    )
+}
+
+/// Goes right after an ed25519 program instruction carrying `owner`'s signature over
+/// `MultiSigTransaction::approval_message`; `signature_index` picks that signature out of
+/// it. Any fee payer can submit the pair.
+pub fn approve_with_signature(
+    vault: &Pubkey,
+    owner: &Pubkey,
+    transaction_id: u64,
+    signature_index: u8,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ApproveWithSignature {
+            transaction_id,
+            owner: *owner,
+            signature_index,
+        },
+    )
+}
+
+pub fn approve_multisig_transaction(
+    vault: &Pubkey,
+    approver: &Pubkey,
+    transaction_id: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*approver, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ApproveMultiSigTransaction { transaction_id },
+    )
+}
+
+/// `target_program` is the stored transaction's program id; it is passed right after the
+/// fixed accounts, and `remaining` carries whatever else the stored instruction needs.
+/// The executor is writable so it can receive an execution tip.
+pub fn execute_multisig_transaction(
+    vault: &Pubkey,
+    nonce: u8,
+    executor: &Pubkey,
+    transaction_id: u64,
+    target_program: &Pubkey,
+    remaining: Vec<AccountMeta>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(multisig_signer_address(vault, nonce), false),
+        AccountMeta::new(*executor, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(*target_program, false),
+    ];
+    accounts.extend(remaining);
+    build(accounts, VaultInstruction::ExecuteMultiSigTransaction { transaction_id })
+}
+
+pub fn set_yield_strategy(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SetYieldStrategy {
+            token_mint: *mint,
+            strategy_program: *strategy_program,
+        },
+    )
+}
+
+/// [`set_yield_strategy`] pinning `strategy_accounts` as the only accounts its CPIs may pass.
+pub fn set_yield_strategy_v2(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+    strategy_accounts: Vec<Pubkey>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SetYieldStrategyV2 {
+            token_mint: *mint,
+            strategy_program: *strategy_program,
+            accounts: strategy_accounts,
+        },
+    )
+}
+
+pub fn update_yield_strategy(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    max_allocation_bps: u16,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::UpdateYieldStrategy {
+            token_mint: *mint,
+            max_allocation_bps,
+        },
+    )
+}
+
+fn strategy_call_accounts(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+    strategy_accounts: Vec<AccountMeta>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(get_associated_token_address(vault, mint), false),
+        AccountMeta::new_readonly(*strategy_program, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    accounts.extend(strategy_accounts);
+    accounts
+}
+
+/// `strategy_data` and `strategy_accounts` make up the instruction the strategy program
+/// receives; the vault PDA signs it wherever it appears in `strategy_accounts`, which must be
+/// the accounts pinned with [`set_yield_strategy_v2`].
+pub fn deploy_to_strategy(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+    amount: u64,
+    strategy_data: Vec<u8>,
+    strategy_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    build(
+        strategy_call_accounts(vault, authority, mint, strategy_program, strategy_accounts),
+        VaultInstruction::DeployToStrategy {
+            token_mint: *mint,
+            amount,
+            strategy_data,
+        },
+    )
+}
+
+/// `signer` is the authority or the emergency admin; only the authority can reactivate.
+pub fn set_strategy_status(
+    vault: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    status: StrategyStatus,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SetStrategyStatus {
+            token_mint: *mint,
+            status,
+        },
+    )
+}
+
+/// Same account layout as [`deploy_to_strategy`], signed by the authority or the emergency
+/// admin. `strategy_data` should withdraw everything the strategy holds for the vault.
+pub fn emergency_exit_strategy(
+    vault: &Pubkey,
+    signer: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+    strategy_data: Vec<u8>,
+    strategy_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    build(
+        strategy_call_accounts(vault, signer, mint, strategy_program, strategy_accounts),
+        VaultInstruction::EmergencyExitStrategy {
+            token_mint: *mint,
+            strategy_data,
+        },
+    )
+}
+
+/// Same account layout as [`deploy_to_strategy`]; `amount` is the principal being recalled.
+pub fn withdraw_from_strategy(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    strategy_program: &Pubkey,
+    amount: u64,
+    strategy_data: Vec<u8>,
+    strategy_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    build(
+        strategy_call_accounts(vault, authority, mint, strategy_program, strategy_accounts),
+        VaultInstruction::WithdrawFromStrategy {
+            token_mint: *mint,
+            amount,
+            strategy_data,
+        },
+    )
+}
+
+pub fn acknowledge_strategy_loss(vault: &Pubkey, authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::AcknowledgeStrategyLoss { token_mint: *mint },
+    )
+}
+
+pub fn fund_execution_tip(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    transaction_id: u64,
+    lamports: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::FundExecutionTip {
+            transaction_id,
+            lamports,
+        },
+    )
+}
+
+pub fn cancel_multisig_transaction(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    transaction_id: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CancelMultiSigTransaction { transaction_id },
+    )
+}
+
+// `signer` is the proposer or any multisig owner
+pub fn extend_transaction_expiry(
+    vault: &Pubkey,
+    signer: &Pubkey,
+    transaction_id: u64,
+    new_expires_at: i64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ExtendTransactionExpiry {
+            transaction_id,
+            new_expires_at,
+        },
+    )
+}
+
+pub fn prune_multisig_transactions(vault: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*owner, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::PruneMultiSigTransactions,
+    )
+}
+
+pub fn expedite_transaction(
+    vault: &Pubkey,
+    emergency_admin: &Pubkey,
+    transaction_id: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*emergency_admin, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ExpediteTransaction { transaction_id },
+    )
+}
+
+// Read-only, so it only needs a fee payer to be simulated
+pub fn view(vault: &Pubkey, query: ViewQuery) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new_readonly(*vault, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::View { query },
+    )
+}
+
+/// Same accounts as [`create_multisig_transaction`]; the program, accounts and data come
+/// from the stored template, with `fill_bytes` spliced into its mutable ranges. Check the
+/// length against `TransactionTemplate::fill_len` to fail early.
+pub fn create_transaction_from_template(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    template_id: u64,
+    fill_bytes: Vec<u8>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateTransactionFromTemplate {
+            template_id,
+            fill_bytes,
+        },
+    )
+}
+
+pub fn propose_sol_transfer(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    memo: String,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ProposeSolTransfer {
+            recipient: *recipient,
+            amount,
+            memo,
+        },
+    )
+}
+
+pub fn propose_token_transfer(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    mint: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    memo: String,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::ProposeTokenTransfer {
+            mint: *mint,
+            recipient: *recipient,
+            amount,
+            memo,
+        },
+    )
+}
+
+/// The config's `timelock_delay` alias is ignored; the program sets it from `time_lock_delay`.
+pub fn initialize_governance(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    config: &GovernanceConfig,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::InitializeGovernance {
+            voting_token_mint: config.voting_token_mint,
+            quorum_threshold: config.quorum_threshold,
+            proposal_threshold: config.proposal_threshold,
+            voting_period: config.voting_period,
+            time_lock_delay: config.time_lock_delay,
+            execution_threshold: config.execution_threshold,
+        },
+    )
+}
+
+/// The proposer's voting weight is read from their associated account for `voting_mint`.
+pub fn create_governance_proposal(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    voting_mint: &Pubkey,
+    title: String,
+    description: String,
+    instructions: Vec<Vec<u8>>,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(get_associated_token_address(proposer, voting_mint), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CreateGovernanceProposal {
+            title,
+            description,
+            instructions,
+        },
+    )
+}
+
+/// Escrows `amount` of `voter`'s voting tokens in the vault's associated account for
+/// `voting_mint` until `lock_duration` seconds from now, registering them to vote.
+pub fn register_voter_with_lock(
+    vault: &Pubkey,
+    voter: &Pubkey,
+    voting_mint: &Pubkey,
+    amount: u64,
+    lock_duration: i64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*voter, true),
+            AccountMeta::new(get_associated_token_address(voter, voting_mint), false),
+            AccountMeta::new(get_associated_token_address(vault, voting_mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::RegisterVoterWithLock {
+            amount,
+            lock_duration,
+        },
+    )
+}
+
+/// Votes with `voter`'s vote lock, which has to predate the proposal.
+pub fn cast_vote(
+    vault: &Pubkey,
+    voter: &Pubkey,
+    proposal_id: u64,
+    vote_type: VoteType,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*voter, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CastVote {
+            proposal_id,
+            vote_type,
+        },
+    )
+}
+
+pub fn queue_proposal(vault: &Pubkey, caller: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*caller, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::QueueProposal { proposal_id },
+    )
+}
+
+/// Executes `proposal`, passing along the programs and accounts its instructions name. The
+/// caller, the vault authority or the multisig signer, is the only signer, so it is also the
+/// only signer a proposal can rely on.
+pub fn execute_governance_proposal(
+    vault: &Pubkey,
+    caller: &Pubkey,
+    proposal: &GovernanceProposal,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*caller, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ];
+    let decoded = proposal
+        .instructions
+        .iter()
+        .filter_map(|encoded| GovernanceInstruction::try_from_slice(encoded).ok());
+    for instruction in decoded {
+        let metas = instruction.accounts.iter().map(|acc| AccountMeta {
+            pubkey: acc.pubkey,
+            is_signer: acc.is_signer,
+            is_writable: acc.is_writable,
+        });
+        for meta in std::iter::once(AccountMeta::new_readonly(instruction.program_id, false))
+            .chain(metas)
+        {
+            // An account named twice gets the privileges of both, e.g. a caller that pays
+            match accounts.iter_mut().find(|known| known.pubkey == meta.pubkey) {
+                Some(known) => {
+                    known.is_signer |= meta.is_signer;
+                    known.is_writable |= meta.is_writable;
+                }
+                None => accounts.push(meta),
+            }
+        }
+    }
+    build(
+        accounts,
+        VaultInstruction::ExecuteGovernanceProposal {
+            proposal_id: proposal.id,
+        },
+    )
+}
+
+/// Multisig-signed: the data and accounts of the multisig transaction by which the council
+/// ratifies `proposal_id`.
+pub fn council_approve_proposal(
+    vault: &Pubkey,
+    multisig_signer: &Pubkey,
+    proposal_id: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*multisig_signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::CouncilApproveProposal { proposal_id },
+    )
+}
+
+pub fn global_config_address() -> Pubkey {
+    GlobalConfig::find_address(&ID).0
+}
+
+/// Appends the GlobalConfig as the optional trailing account of instructions that honour
+/// it, such as [`set_yield_strategy`] or a SetParam stored in a multisig transaction.
+pub fn with_global_config(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(global_config_address(), false));
+    instruction
+}
+
+/// Appends the accounts that make [`deposit`] or [`withdraw`] pay its fee out to the vault's
+/// `fee_recipient` and the global config's `protocol_fee_recipient`. A withdrawal already
+/// carries the GlobalConfig. Apply [`with_token_program`] first for a Token-2022 mint.
+pub fn with_fee_routing(
+    mut instruction: Instruction,
+    mint: &Pubkey,
+    fee_recipient: &Pubkey,
+    protocol_fee_recipient: &Pubkey,
+) -> Instruction {
+    let config = global_config_address();
+    if !instruction.accounts.iter().any(|meta| meta.pubkey == config) {
+        instruction.accounts.push(AccountMeta::new(config, false));
+    }
+    let token_program = if instruction
+        .accounts
+        .iter()
+        .any(|meta| meta.pubkey == TOKEN_2022_PROGRAM_ID)
+    {
+        TOKEN_2022_PROGRAM_ID
+    } else {
+        spl_token::id()
+    };
+    let fee_account =
+        |owner| get_associated_token_address_with_program_id(owner, mint, &token_program);
+    instruction.accounts.extend([
+        AccountMeta::new(fee_account(fee_recipient), false),
+        AccountMeta::new(fee_account(protocol_fee_recipient), false),
+    ]);
+    instruction
+}
+
+/// Rewrites a token instruction built for `mint` under classic SPL Token, such as
+/// [`add_supported_token`], [`deposit`] or [`withdraw`], for a mint owned by `token_program`:
+/// the token program account and every associated token account of an owner named in the
+/// instruction.
+pub fn with_token_program(
+    mut instruction: Instruction,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    let keys: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+    for meta in instruction.accounts.iter_mut() {
+        if meta.pubkey == spl_token::id() {
+            meta.pubkey = *token_program;
+        } else if let Some(owner) = keys
+            .iter()
+            .find(|owner| get_associated_token_address(owner, mint) == meta.pubkey)
+        {
+            meta.pubkey = get_associated_token_address_with_program_id(owner, mint, token_program);
+        }
+    }
+    instruction
+}
+
+/// The ProgramData account holding this program's upgrade authority.
+pub fn program_data_address() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&ID)
+}
+
+/// Appends the ProgramData account that a [`withdraw`] over the upgrade check threshold, or an
+/// emergency withdrawal, needs after the GlobalConfig once the config expects an upgrade
+/// authority.
+pub fn with_program_data(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(program_data_address(), false));
+    instruction
+}
+
+/// `payer` has to be the program's upgrade authority.
+pub fn initialize_global_config(payer: &Pubkey, config: GlobalConfig) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(global_config_address(), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(program_data_address(), false),
+        ],
+        VaultInstruction::InitializeGlobalConfig {
+            admin: config.admin,
+            max_fee_bps: config.max_fee_bps,
+            allowed_programs: config.allowed_programs,
+            protocol_fee_recipient: config.protocol_fee_recipient,
+            protocol_fee_bps: config.protocol_fee_bps,
+        },
+    )
+}
+
+/// Signed by the admin vault's multisig signer, so in practice the data and accounts of a
+/// multisig transaction on that vault.
+pub fn update_global_config(
+    admin_vault: &Pubkey,
+    multisig_signer: &Pubkey,
+    config: GlobalConfig,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(global_config_address(), false),
+            AccountMeta::new_readonly(*admin_vault, false),
+            AccountMeta::new_readonly(*multisig_signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::UpdateGlobalConfig {
+            admin: config.admin,
+            max_fee_bps: config.max_fee_bps,
+            allowed_programs: config.allowed_programs,
+            protocol_fee_recipient: config.protocol_fee_recipient,
+            protocol_fee_bps: config.protocol_fee_bps,
+        },
+    )
+}
+
+/// Multisig-signed like [`update_global_config`].
+pub fn acknowledge_upgrade_authority(
+    admin_vault: &Pubkey,
+    multisig_signer: &Pubkey,
+    expected_upgrade_authority: Option<Pubkey>,
+    upgrade_check_threshold: u64,
+) -> Instruction {
+    build(
+        vec![
+            AccountMeta::new(global_config_address(), false),
+            AccountMeta::new_readonly(*admin_vault, false),
+            AccountMeta::new_readonly(*multisig_signer, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::AcknowledgeUpgradeAuthority {
+            expected_upgrade_authority,
+            upgrade_check_threshold,
+        },
+    )
+}
@@ -0,0 +1,249 @@
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+
+use crate::client::decode::{
+    decode_multisig_payload, format_instruction, instruction_mint, instruction_name,
+};
+use crate::state::{GovernanceProposal, MultiSigTransaction, Vault};
+
+/// One page of a listing plus what's needed to ask for the next one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Pending,
+    Approved,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MultisigTransactionSummary {
+    pub id: u64,
+    pub status: TransactionStatus,
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub proposer: Pubkey,
+    pub created_at: i64,
+    pub approvals: usize,
+    pub expires_at: Option<i64>, // None when created without a MultisigTransactionTtl
+    /// Variant name of the payload when the transaction calls this program and it decodes
+    pub instruction: Option<String>,
+    /// Token the decoded payload moves, for resolving a symbol with `TokenCache`
+    #[serde(with = "crate::serde_helpers::option_pubkey")]
+    pub token_mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Active,
+    Ended,
+    Queued,
+    Executed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GovernanceProposalSummary {
+    pub id: u64,
+    pub status: ProposalStatus,
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub proposer: Pubkey,
+    pub created_at: i64,
+    pub title: String,
+}
+
+fn paginate<T>(mut items: Vec<T>, offset: usize, limit: usize) -> Page<T> {
+    let total = items.len();
+    let end = offset.saturating_add(limit).min(total);
+    let items = if offset < end {
+        items.drain(offset..end).collect()
+    } else {
+        Vec::new()
+    };
+    Page {
+        items,
+        total,
+        next_offset: (end < total).then_some(end),
+    }
+}
+
+fn transaction_status(
+    vault: &Vault,
+    now: i64,
+    id: u64,
+    transaction: &MultiSigTransaction,
+) -> TransactionStatus {
+    let threshold = vault.multi_sig.as_ref().map_or(u64::MAX, |m| m.threshold);
+    let approvals = transaction.signers.iter().filter(|&&signed| signed).count() as u64;
+    if transaction.did_execute {
+        TransactionStatus::Executed
+    } else if vault.is_transaction_cancelled(id) {
+        TransactionStatus::Cancelled
+    } else if vault.is_transaction_expired(id, now) {
+        TransactionStatus::Expired
+    } else if approvals >= threshold {
+        TransactionStatus::Approved
+    } else {
+        TransactionStatus::Pending
+    }
+}
+
+fn multisig_summaries(vault: &Vault, now: i64) -> Vec<MultisigTransactionSummary> {
+    vault
+        .multisig_transactions()
+        .map(|(id, transaction)| {
+            let payload = decode_multisig_payload(transaction).and_then(Result::ok);
+            MultisigTransactionSummary {
+                id,
+                status: transaction_status(vault, now, id, transaction),
+                proposer: transaction.proposer,
+                created_at: transaction.created_at,
+                approvals: transaction.signers.iter().filter(|&&signed| signed).count(),
+                expires_at: vault.transaction_expires_at(id),
+                instruction: payload.as_ref().map(instruction_name),
+                token_mint: payload.as_ref().and_then(instruction_mint),
+            }
+        })
+        .collect()
+}
+
+/// Multisig transactions still stored on the vault, by ascending id, with status as of
+/// `now`. Pruned transactions are gone from the account and only show up in the event
+/// history.
+pub fn list_multisig_transactions(
+    vault: &Vault,
+    now: i64,
+    offset: usize,
+    limit: usize,
+) -> Page<MultisigTransactionSummary> {
+    let mut summaries = multisig_summaries(vault, now);
+    summaries.sort_by_key(|summary| summary.id);
+    paginate(summaries, offset, limit)
+}
+
+/// Pending and approved transactions that expire within `within` seconds of `now`, soonest
+/// first: the ones owners have to act on, or extend, before they lapse.
+pub fn list_expiring_multisig_transactions(
+    vault: &Vault,
+    now: i64,
+    within: i64,
+) -> Vec<MultisigTransactionSummary> {
+    let deadline = now.saturating_add(within);
+    let mut expiring: Vec<_> = multisig_summaries(vault, now)
+        .into_iter()
+        .filter(|summary| {
+            matches!(
+                summary.status,
+                TransactionStatus::Pending | TransactionStatus::Approved
+            ) && summary.expires_at.map_or(false, |expires_at| expires_at <= deadline)
+        })
+        .collect();
+    expiring.sort_by_key(|summary| (summary.expires_at, summary.id));
+    expiring
+}
+
+/// Full stored transaction behind a summary, accounts and payload included.
+pub fn fetch_multisig_transaction(vault: &Vault, id: u64) -> Option<&MultiSigTransaction> {
+    vault.multisig_transaction(id)
+}
+
+/// Transaction `id` laid out for review before approving: target, accounts with their roles,
+/// any balance assertions, and the payload decoded with [`format_instruction`] when it calls
+/// this program (hex otherwise, or when it doesn't decode). `decimals` formats amounts as for
+/// `format_instruction`.
+pub fn preview_multisig_transaction(
+    vault: &Vault,
+    id: u64,
+    decimals: Option<u8>,
+) -> Option<String> {
+    let transaction = vault.multisig_transaction(id)?;
+    let mut preview = format!(
+        "transaction {}\nprogram: {}\naccounts:\n",
+        id, transaction.program_id
+    );
+    let roles = vault.transaction_roles(id);
+    for (index, account) in transaction.accounts.iter().enumerate() {
+        // Transactions stored before roles existed only have their flags to show
+        let role = match roles.and_then(|roles| roles.get(index)) {
+            Some(role) => format!(" {}", role.name()),
+            None if account.is_writable => " writable".to_string(),
+            None => String::new(),
+        };
+        preview.push_str(&format!(
+            "  {}{}{}\n",
+            account.pubkey,
+            role,
+            if account.is_signer { " signer" } else { "" }
+        ));
+    }
+    let assertions = vault.transaction_assertions(id);
+    if !assertions.is_empty() {
+        preview.push_str("assertions:\n");
+    }
+    for assertion in assertions {
+        preview.push_str(&format!(
+            "  {} keeps at least {} {}\n",
+            assertion.account,
+            assertion.min_post_balance,
+            if assertion.is_token { "tokens" } else { "lamports" }
+        ));
+    }
+    let hex: String = transaction.data.iter().map(|b| format!("{:02x}", b)).collect();
+    match decode_multisig_payload(transaction) {
+        Some(Ok(instruction)) => preview.push_str(&format_instruction(&instruction, decimals)),
+        Some(Err(err)) => preview.push_str(&format!("data: {} ({})", hex, err)),
+        None => preview.push_str(&format!("data: {}", hex)),
+    }
+    Some(preview)
+}
+
+fn proposal_status(proposal: &GovernanceProposal, now: i64) -> ProposalStatus {
+    if proposal.cancelled {
+        ProposalStatus::Cancelled
+    } else if proposal.executed {
+        ProposalStatus::Executed
+    } else if proposal.queued {
+        ProposalStatus::Queued
+    } else if now < proposal.end_time {
+        ProposalStatus::Active
+    } else {
+        ProposalStatus::Ended
+    }
+}
+
+/// Governance proposals by ascending id, with voting status as of `now`.
+pub fn list_governance_proposals(
+    vault: &Vault,
+    now: i64,
+    offset: usize,
+    limit: usize,
+) -> Page<GovernanceProposalSummary> {
+    let mut summaries: Vec<_> = vault
+        .governance_proposals
+        .iter()
+        .map(|proposal| GovernanceProposalSummary {
+            id: proposal.id,
+            status: proposal_status(proposal, now),
+            proposer: proposal.proposer,
+            created_at: proposal.created_at,
+            title: proposal.title.clone(),
+        })
+        .collect();
+    summaries.sort_by_key(|summary| summary.id);
+    paginate(summaries, offset, limit)
+}
+
+/// Full proposal behind a summary, description and instructions included.
+pub fn fetch_governance_proposal(vault: &Vault, id: u64) -> Option<&GovernanceProposal> {
+    vault
+        .governance_proposal_index(id)
+        .map(|index| &vault.governance_proposals[index])
+}
@@ -0,0 +1,50 @@
+// Off-chain helpers: instruction builders and decoding, event log parsing and forwarding
+// cursors, attestations, approval digests, activity, audit exports, state snapshots, stream
+// claims, token holdings, paginated transaction/proposal listings, per-instruction account
+// resolution, View decoding, time-weighted TVL, confirmations for destructive changes, token
+// metadata lookup, setup status, the deployed-program check and per-wallet position
+// statements.
+// Only compiled with the `client` feature so the on-chain build stays lean.
+pub mod activity;
+pub mod approval_digest;
+pub mod audit;
+pub mod attestations;
+pub mod confirm;
+pub mod cursor;
+pub mod decode;
+pub mod deployment;
+pub mod display;
+pub mod events;
+pub mod holdings;
+pub mod instructions;
+pub mod listing;
+pub mod position;
+pub mod resolver;
+pub mod setup;
+pub mod snapshot;
+pub mod streams;
+pub mod token_metadata;
+pub mod tvl;
+pub mod views;
+
+pub use activity::*;
+pub use approval_digest::*;
+pub use audit::*;
+pub use attestations::*;
+pub use confirm::*;
+pub use cursor::*;
+pub use decode::*;
+pub use deployment::*;
+pub use display::*;
+pub use events::*;
+pub use holdings::*;
+pub use instructions::*;
+pub use listing::*;
+pub use position::*;
+pub use resolver::*;
+pub use setup::*;
+pub use snapshot::*;
+pub use streams::*;
+pub use token_metadata::*;
+pub use tvl::*;
+pub use views::*;
@@ -0,0 +1,73 @@
+use serde::Serialize;
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+
+use crate::client::events::{parse_event, ParsedEvent};
+
+// One wallet's deposits into and withdrawals out of a vault for a mint, as a statement built
+// from the vault's events. The vault pools balances per mint and keeps no per-user positions,
+// so there are no on-chain lifetime counters to check these totals against; they are only as
+// complete as the event history passed in.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionEntryKind {
+    Deposit,
+    Withdrawal,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionEntry {
+    pub kind: PositionEntryKind,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub timestamp: i64,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PositionStatement {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub owner: Pubkey,
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub mint: Pubkey,
+    /// Event amounts summed with saturating adds
+    pub deposited: u64,
+    pub withdrawn: u64,
+    /// In the order the events were given
+    pub entries: Vec<PositionEntry>,
+}
+
+/// `owner`'s statement for `mint` from `events` as `parse_events` returns them: deposits
+/// booked to `owner` (including those a delegate made on their behalf) and withdrawals paid
+/// to them.
+pub fn position_statement(owner: &Pubkey, mint: &Pubkey, events: &[Value]) -> PositionStatement {
+    let mut statement = PositionStatement {
+        owner: *owner,
+        mint: *mint,
+        deposited: 0,
+        withdrawn: 0,
+        entries: Vec::new(),
+    };
+    for event in events {
+        let (kind, amount, fee_amount, base) = match parse_event(event.clone()) {
+            ParsedEvent::TokenDeposited(e) if e.depositor == *owner && e.token_mint == *mint => {
+                statement.deposited = statement.deposited.saturating_add(e.amount);
+                (PositionEntryKind::Deposit, e.amount, e.fee_amount, e.base)
+            }
+            ParsedEvent::TokenWithdrawn(e) if e.recipient == *owner && e.token_mint == *mint => {
+                statement.withdrawn = statement.withdrawn.saturating_add(e.amount);
+                (PositionEntryKind::Withdrawal, e.amount, e.fee_amount, e.base)
+            }
+            _ => continue,
+        };
+        statement.entries.push(PositionEntry {
+            kind,
+            amount,
+            fee_amount,
+            timestamp: base.timestamp,
+            slot: base.slot,
+        });
+    }
+    statement
+}
@@ -0,0 +1,442 @@
+use std::fmt;
+
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::client::instructions::*;
+use crate::instruction::VaultInstruction;
+use crate::jupiter;
+use crate::ID;
+
+/// What [`resolve_accounts`] needs beyond the instruction data to lay out its accounts.
+/// Only `vault` and `authority` are always used; the rest is asked for by the variants that
+/// need it, and the `Option`s that have a fallback override it.
+#[derive(Debug, Clone, Default)]
+pub struct ResolveContext {
+    /// The vault operated on; for UpdateGlobalConfig and AcknowledgeUpgradeAuthority, the
+    /// config's admin vault. Initialize variants derive it from `authority` instead.
+    pub vault: Pubkey,
+    /// Signer in the instruction's own role: the authority, depositor, beneficiary, multisig
+    /// owner, executor, emergency admin, voter or recovery key.
+    pub authority: Pubkey,
+    /// Mint for instructions whose data doesn't name one: deposits, withdrawals, time lock
+    /// claims, fee collection, and the voting token of governance instructions.
+    pub mint: Option<Pubkey>,
+    /// Nonce of the vault's multisig signer, for multisig-signed and executed instructions.
+    pub multisig_nonce: Option<u8>,
+    /// WithdrawSOL and EmergencyWithdraw recipient, the CollectFees fee recipient, and the
+    /// proposer of a CloseTransactionBuffer; all but EmergencyWithdraw fall back to
+    /// `authority`.
+    pub recipient: Option<Pubkey>,
+    /// Initialize emergency admin, `authority` by default.
+    pub emergency_admin: Option<Pubkey>,
+    /// Rent payer, `authority` by default; InitializeGlobalConfig's must be the upgrade
+    /// authority.
+    pub payer: Option<Pubkey>,
+    /// Multisig owner co-signing an emergency withdrawal under EmergencyRequiresCosign.
+    pub cosigner: Option<Pubkey>,
+    /// Strategy program of strategy calls, or the stored program of an executed multisig
+    /// transaction.
+    pub program: Option<Pubkey>,
+    /// Appended after the fixed accounts: optional trailing accounts (global config, fee
+    /// routing, ProgramData) or whatever gets forwarded to a CPI target.
+    pub remaining: Vec<AccountMeta>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveError {
+    /// The instruction needs this `ResolveContext` field and it was left unset
+    Missing(&'static str),
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::Missing(field) => write!(f, "resolve context is missing {}", field),
+        }
+    }
+}
+
+impl ResolveContext {
+    fn mint(&self) -> Result<Pubkey, ResolveError> {
+        self.mint.ok_or(ResolveError::Missing("mint"))
+    }
+
+    fn multisig_signer(&self) -> Result<Pubkey, ResolveError> {
+        let nonce = self.multisig_nonce.ok_or(ResolveError::Missing("multisig_nonce"))?;
+        Ok(multisig_signer_address(&self.vault, nonce))
+    }
+
+    fn program(&self) -> Result<Pubkey, ResolveError> {
+        self.program.ok_or(ResolveError::Missing("program"))
+    }
+
+    fn payer(&self) -> Pubkey {
+        self.payer.unwrap_or(self.authority)
+    }
+}
+
+// vault, signer, clock: the layout most authority, owner and admin instructions share
+fn signed_call(vault: &Pubkey, signer: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*signer, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ]
+}
+
+fn global_config_admin_call(vault: &Pubkey, multisig_signer: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(global_config_address(), false),
+        AccountMeta::new_readonly(*vault, false),
+        AccountMeta::new_readonly(*multisig_signer, true),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+    ]
+}
+
+/// The accounts `instruction` needs, in the order its processor reads them, followed by
+/// `ctx.remaining`. Variants whose processors are still stubs read nothing and resolve to
+/// `ctx.remaining` alone.
+pub fn resolve_accounts(
+    instruction: &VaultInstruction,
+    ctx: &ResolveContext,
+) -> Result<Vec<AccountMeta>, ResolveError> {
+    let vault = &ctx.vault;
+    let authority = &ctx.authority;
+    let mut accounts = match instruction {
+        VaultInstruction::Initialize {
+            index,
+            allow_same_as_authority,
+            ..
+        }
+        | VaultInstruction::InitializeVault {
+            index,
+            allow_same_as_authority,
+        }
+        | VaultInstruction::InitializeVaultV2 {
+            index,
+            allow_same_as_authority,
+            ..
+        } => {
+            let emergency_admin = ctx.emergency_admin.unwrap_or(*authority);
+            initialize(authority, &emergency_admin, *index, *allow_same_as_authority).accounts
+        }
+        VaultInstruction::Deposite { amount } => {
+            deposit(vault, &ctx.mint()?, authority, *amount).accounts
+        }
+        VaultInstruction::DepositAsDelegate {
+            amount,
+            on_behalf_of,
+        } => deposit_as_delegate(vault, &ctx.mint()?, authority, on_behalf_of, *amount).accounts,
+        VaultInstruction::Withdraw { amount } => {
+            withdraw(vault, &ctx.mint()?, authority, *amount).accounts
+        }
+        VaultInstruction::ClaimWithdrawal { ticket_id } => {
+            claim_withdrawal(vault, &ctx.mint()?, authority, *ticket_id).accounts
+        }
+        VaultInstruction::WithdrawSOL { amount } => {
+            let recipient = ctx.recipient.unwrap_or(*authority);
+            withdraw_sol(vault, &recipient, authority, *amount).accounts
+        }
+        VaultInstruction::Transfer { recipient, amount } => {
+            transfer(vault, recipient, authority, *amount).accounts
+        }
+        VaultInstruction::TransferV2 {
+            recipient,
+            mint,
+            amount,
+            create_ata,
+        } => {
+            let payer = ctx.payer();
+            let payer = create_ata.then_some(&payer);
+            transfer_v2(vault, recipient, authority, mint.as_ref(), *amount, payer).accounts
+        }
+        VaultInstruction::SweepUntrackedLamports => sweep_untracked_lamports(vault).accounts,
+        VaultInstruction::Tick { max_items } => tick(vault, *max_items).accounts,
+        VaultInstruction::EmitHealthCheck => {
+            let mints: Vec<Pubkey> = ctx.mint.into_iter().collect();
+            emit_health_check(vault, &mints).accounts
+        }
+        VaultInstruction::RefreshSupportedTokenMetadata {
+            mint,
+            allow_with_balance,
+        } => {
+            refresh_supported_token_metadata(vault, authority, mint, *allow_with_balance).accounts
+        }
+        VaultInstruction::DepositWrappedSOL {
+            amount,
+            close_wrapped_account,
+        } => deposit_wrapped_sol(vault, authority, *amount, *close_wrapped_account).accounts,
+        VaultInstruction::AddSupportedToken { mint, .. } => {
+            add_supported_token(vault, mint, authority, &ctx.payer()).accounts
+        }
+        VaultInstruction::EmergencyWithdraw { token_mint, amount } => {
+            let recipient = ctx.recipient.ok_or(ResolveError::Missing("recipient"))?;
+            emergency_withdraw(
+                vault,
+                token_mint,
+                &recipient,
+                authority,
+                ctx.cosigner.as_ref(),
+                *amount,
+            )
+            .accounts
+        }
+        VaultInstruction::MigrateVault => migrate_vault(vault, authority, &ctx.payer()).accounts,
+
+        VaultInstruction::InitializeMultiSig { .. }
+        | VaultInstruction::CreateMultiSigTransaction { .. }
+        | VaultInstruction::CreateMultiSigTransactionV2 { .. }
+        | VaultInstruction::CreateMultiSigTransactionV3 { .. }
+        | VaultInstruction::ApproveMultiSigTransaction { .. }
+        | VaultInstruction::CreateTransactionFromTemplate { .. }
+        | VaultInstruction::ProposeSolTransfer { .. }
+        | VaultInstruction::ProposeTokenTransfer { .. }
+        | VaultInstruction::PruneMultiSigTransactions
+        | VaultInstruction::ExpediteTransaction { .. }
+        | VaultInstruction::PauseVault
+        | VaultInstruction::UnpauseVault
+        | VaultInstruction::ExtendPause
+        | VaultInstruction::SetMaxPauseDuration { .. }
+        | VaultInstruction::UpdateVaultConfig { .. }
+        | VaultInstruction::CreateTimeLock { .. }
+        | VaultInstruction::SplitTimeLock { .. }
+        | VaultInstruction::AssignTimeLockMint { .. }
+        | VaultInstruction::CancelTimeLockV2 { .. }
+        | VaultInstruction::RequestWithdrawal { .. }
+        | VaultInstruction::CancelWithdrawalRequest { .. }
+        | VaultInstruction::SetStreamConfig { .. }
+        | VaultInstruction::SetYieldStrategy { .. }
+        | VaultInstruction::SetYieldStrategyV2 { .. }
+        | VaultInstruction::UpdateYieldStrategy { .. }
+        | VaultInstruction::SetStrategyStatus { .. }
+        | VaultInstruction::AcknowledgeStrategyLoss { .. }
+        | VaultInstruction::InitializeGovernance { .. }
+        | VaultInstruction::UpdateGovernanceConfig { .. }
+        | VaultInstruction::CastVote { .. }
+        | VaultInstruction::QueueProposal { .. }
+        | VaultInstruction::ExecuteGovernanceProposal { .. }
+        | VaultInstruction::InitiateRecovery
+        | VaultInstruction::VetoRecovery
+        | VaultInstruction::FinalizeRecovery
+        | VaultInstruction::FinalizeSetup => signed_call(vault, authority),
+        VaultInstruction::CreateTransactionBuffer { total_len } => {
+            create_transaction_buffer(vault, authority, *total_len).accounts
+        }
+        VaultInstruction::WriteTransactionBuffer { offset, chunk } => {
+            write_transaction_buffer(vault, authority, *offset, chunk.clone()).accounts
+        }
+        VaultInstruction::FinalizeTransactionFromBuffer {
+            program_id,
+            accounts,
+        } => {
+            finalize_transaction_from_buffer(vault, authority, *program_id, accounts.clone())
+                .accounts
+        }
+        VaultInstruction::CloseTransactionBuffer => {
+            let proposer = ctx.recipient.unwrap_or(*authority);
+            close_transaction_buffer(vault, &proposer, authority).accounts
+        }
+        VaultInstruction::CancelMultiSigTransaction { transaction_id } => {
+            cancel_multisig_transaction(vault, authority, *transaction_id).accounts
+        }
+        VaultInstruction::ApproveWithSignature {
+            transaction_id,
+            owner,
+            signature_index,
+        } => approve_with_signature(vault, owner, *transaction_id, *signature_index).accounts,
+        VaultInstruction::ExtendTransactionExpiry {
+            transaction_id,
+            new_expires_at,
+        } => extend_transaction_expiry(vault, authority, *transaction_id, *new_expires_at).accounts,
+        VaultInstruction::FundExecutionTip {
+            transaction_id,
+            lamports,
+        } => fund_execution_tip(vault, authority, *transaction_id, *lamports).accounts,
+        VaultInstruction::ExecuteMultiSigTransaction { transaction_id } => {
+            let nonce = ctx.multisig_nonce.ok_or(ResolveError::Missing("multisig_nonce"))?;
+            execute_multisig_transaction(
+                vault,
+                nonce,
+                authority,
+                *transaction_id,
+                &ctx.program()?,
+                vec![],
+            )
+            .accounts
+        }
+        // Signed by the authority, with the multisig signer passed alongside unsigned
+        VaultInstruction::SetMultiSigOwners { .. }
+        | VaultInstruction::ChangeMultiSigThreshold { .. } => vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(ctx.multisig_signer()?, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        VaultInstruction::SetMultiSigProposalLimit { .. }
+        | VaultInstruction::AttestMessage { .. }
+        | VaultInstruction::SetMultiSigRecovery { .. }
+        | VaultInstruction::SetExecutionDelay { .. }
+        | VaultInstruction::SetParam { .. }
+        | VaultInstruction::SetMintMinimums { .. }
+        | VaultInstruction::CreateTemplate { .. }
+        | VaultInstruction::DeleteTemplate { .. }
+        | VaultInstruction::CouncilApproveProposal { .. } => {
+            signed_call(vault, &ctx.multisig_signer()?)
+        }
+
+        VaultInstruction::ClaimTimeLockV2 { time_lock_id } => {
+            claim_time_lock(vault, &ctx.mint()?, authority, *time_lock_id).accounts
+        }
+        VaultInstruction::ClaimTimeLockPartial {
+            time_lock_id,
+            amount,
+        } => claim_time_lock_partial(vault, &ctx.mint()?, authority, *time_lock_id, *amount)
+            .accounts,
+
+        VaultInstruction::DeployToStrategy {
+            token_mint,
+            amount,
+            strategy_data,
+        } => deploy_to_strategy(
+            vault,
+            authority,
+            token_mint,
+            &ctx.program()?,
+            *amount,
+            strategy_data.clone(),
+            vec![],
+        )
+        .accounts,
+        VaultInstruction::WithdrawFromStrategy {
+            token_mint,
+            amount,
+            strategy_data,
+        } => withdraw_from_strategy(
+            vault,
+            authority,
+            token_mint,
+            &ctx.program()?,
+            *amount,
+            strategy_data.clone(),
+            vec![],
+        )
+        .accounts,
+        VaultInstruction::EmergencyExitStrategy {
+            token_mint,
+            strategy_data,
+        } => emergency_exit_strategy(
+            vault,
+            authority,
+            token_mint,
+            &ctx.program()?,
+            strategy_data.clone(),
+            vec![],
+        )
+        .accounts,
+        VaultInstruction::HarvestYield { .. } | VaultInstruction::CompoundYield { .. } => {
+            vec![AccountMeta::new_readonly(*vault, false)]
+        }
+        VaultInstruction::JupiterRoute {
+            input_mint,
+            output_mint,
+            auto_add,
+            ..
+        } => {
+            let mut accounts = vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(get_associated_token_address(vault, input_mint), false),
+                AccountMeta::new(get_associated_token_address(vault, output_mint), false),
+                AccountMeta::new_readonly(jupiter::PROGRAM_ID, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ];
+            if *auto_add {
+                accounts.extend([
+                    AccountMeta::new_readonly(*output_mint, false),
+                    AccountMeta::new(ctx.payer(), true),
+                    AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                    AccountMeta::new_readonly(spl_token::id(), false),
+                    AccountMeta::new_readonly(system_program::id(), false),
+                ]);
+            }
+            accounts
+        }
+
+        VaultInstruction::CreateGovernanceProposal { .. } => {
+            let voting_tokens = get_associated_token_address(authority, &ctx.mint()?);
+            vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(voting_tokens, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ]
+        }
+        VaultInstruction::RegisterVoterWithLock { .. } | VaultInstruction::UnregisterVoter => {
+            let mint = ctx.mint()?;
+            vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new(get_associated_token_address(authority, &mint), false),
+                AccountMeta::new(get_associated_token_address(vault, &mint), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ]
+        }
+
+        VaultInstruction::View { query } => view(vault, query.clone()).accounts,
+        VaultInstruction::InitializeGlobalConfig { .. } => vec![
+            AccountMeta::new(global_config_address(), false),
+            AccountMeta::new(ctx.payer(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(program_data_address(), false),
+        ],
+        VaultInstruction::UpdateGlobalConfig { .. }
+        | VaultInstruction::AcknowledgeUpgradeAuthority { .. } => {
+            global_config_admin_call(vault, &ctx.multisig_signer()?)
+        }
+
+        // Further mints go in `remaining`, as pairs in the same order
+        VaultInstruction::CollectFees => {
+            let fee_recipient = ctx.recipient.unwrap_or(*authority);
+            collect_fees(vault, authority, &fee_recipient, &spl_token::id(), &[ctx.mint()?])
+                .accounts
+        }
+
+        // Not implemented on-chain yet; their processors read no accounts
+        VaultInstruction::CreateProposal { .. }
+        | VaultInstruction::ApproveProposal { .. }
+        | VaultInstruction::ExecuteProposal { .. }
+        | VaultInstruction::RejectProposal { .. }
+        | VaultInstruction::DepositMultiToken { .. }
+        | VaultInstruction::JupiterSwap { .. }
+        | VaultInstruction::TransferAuthority { .. }
+        | VaultInstruction::UpdateEmergencyAdmin { .. } => vec![],
+
+        // Deprecated; rejected before any account is read
+        VaultInstruction::ClaimTimeLock { .. } | VaultInstruction::CancelTimeLock { .. } => {
+            vec![]
+        }
+    };
+    accounts.extend(ctx.remaining.iter().cloned());
+    Ok(accounts)
+}
+
+/// [`resolve_accounts`] packaged with the serialized instruction, ready to sign.
+pub fn resolve_instruction(
+    instruction: &VaultInstruction,
+    ctx: &ResolveContext,
+) -> Result<Instruction, ResolveError> {
+    Ok(Instruction {
+        program_id: ID,
+        accounts: resolve_accounts(instruction, ctx)?,
+        data: instruction.try_to_vec().expect("instruction serialization"),
+    })
+}
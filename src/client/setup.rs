@@ -0,0 +1,34 @@
+use serde::Serialize;
+
+use crate::state::{SetupStage, Vault};
+
+// Where a vault's setup stands, for the `Info` output: its stage, whether deposits and
+// withdrawals are held until setup is finalized, and the instruction that moves it along.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SetupStatus {
+    pub stage: SetupStage,
+    /// Created with the setup gate on, see InitializeVaultV2
+    pub gated: bool,
+    /// Deposits and withdrawals fail with VaultNotReady right now
+    pub held: bool,
+    pub next_step: Option<&'static str>,
+}
+
+/// The vault's setup status. The stage is worked out from what is configured, so vaults set
+/// up before the stage was stored report where they really are.
+pub fn setup_status(vault: &Vault) -> SetupStatus {
+    let stage = vault.configured_setup_stage();
+    let next_step = match stage {
+        SetupStage::Initialized => Some("AddSupportedToken"),
+        SetupStage::TokensConfigured => Some("InitializeMultiSig"),
+        SetupStage::MultisigConfigured => Some("FinalizeSetup"),
+        SetupStage::Ready => None,
+    };
+    SetupStatus {
+        stage,
+        gated: vault.setup_gate,
+        held: vault.setup_gate && stage != SetupStage::Ready,
+        next_step,
+    }
+}
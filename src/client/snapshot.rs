@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::Vault;
+
+// Full dump of a vault account for incident replay: the raw bytes restore the account exactly,
+// the decoded state is there for reading and diffing
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultSnapshot {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub address: Pubkey,
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data_base64: String,
+    pub state: Vault,
+}
+
+impl VaultSnapshot {
+    /// Captures an account as fetched from RPC. Fails if the data doesn't decode as a vault.
+    pub fn capture(
+        address: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: &[u8],
+    ) -> Result<Self, ProgramError> {
+        Ok(Self {
+            address,
+            owner,
+            lamports,
+            data_base64: base64::encode(data),
+            state: Vault::from_account_data(data)?,
+        })
+    }
+
+    /// Raw account bytes to recreate the account with, e.g. via `ProgramTest::add_account`.
+    /// Fails if the bytes were edited so they no longer match the decoded state.
+    pub fn account_data(&self) -> Result<Vec<u8>, ProgramError> {
+        let data =
+            base64::decode(&self.data_base64).map_err(|_| ProgramError::InvalidAccountData)?;
+        let decoded = Vault::from_account_data(&data)?;
+        if decoded.to_account_data()? != self.state.to_account_data()? {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(data)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
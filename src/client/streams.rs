@@ -0,0 +1,10 @@
+use crate::state::Vault;
+use crate::vesting;
+
+/// What the beneficiary of time lock `time_lock_id` could pull with ClaimTimeLockPartial at
+/// `now`, before the stream claim fee. None if the vault has no such lock or its schedule is
+/// invalid.
+pub fn claimable_now(vault: &Vault, time_lock_id: u64, now: i64) -> Option<u64> {
+    let index = vault.time_lock_index(time_lock_id)?;
+    vesting::releasable(&vault.time_locks[index], now).ok()
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use solana_program::{program_pack::Pack, pubkey, pubkey::Pubkey};
+use spl_token::state::Mint;
+use std::{collections::BTreeMap, fs, io, path::Path, str::FromStr};
+
+use crate::client::holdings::{well_known_symbol, TokenHolding};
+
+// Human-readable token identity for output that would otherwise show a bare mint: symbol and
+// name from the mint's Metaplex metadata account, decimals from the mint itself, cached on
+// disk so every listing doesn't cost two account reads per mint. Nothing here is on-chain.
+
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// File name of the cache within a profile directory.
+pub const TOKEN_CACHE_FILE: &str = "token-cache.json";
+
+// Metadata account prefix: key, update authority, mint, then the name/symbol/uri strings
+const METADATA_V1_KEY: u8 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenInfo {
+    #[serde(with = "crate::serde_helpers::pubkey")]
+    pub mint: Pubkey,
+    pub symbol: Option<String>,
+    pub name: Option<String>,
+    pub decimals: u8,
+    /// The metadata's `uri`, normally a JSON document whose `image` is the logo
+    pub logo_uri: Option<String>,
+}
+
+impl TokenInfo {
+    /// The symbol, or a shortened mint for tokens without one, e.g. `EPjF…Dt1v`.
+    pub fn label(&self) -> String {
+        match &self.symbol {
+            Some(symbol) => symbol.clone(),
+            None => {
+                let mint = self.mint.to_string();
+                format!("{}…{}", &mint[..4], &mint[mint.len() - 4..])
+            }
+        }
+    }
+}
+
+/// Name, symbol and uri as stored in a Metaplex metadata account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetaplexMetadata {
+    pub mint: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// The metadata PDA that Metaplex derives for `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    let seeds: &[&[u8]] = &[b"metadata", TOKEN_METADATA_PROGRAM_ID.as_ref(), mint.as_ref()];
+    Pubkey::find_program_address(seeds, &TOKEN_METADATA_PROGRAM_ID).0
+}
+
+// Borsh string whose fixed-width padding of trailing NULs is stripped
+fn read_string(data: &[u8], offset: &mut usize) -> Option<String> {
+    let len_bytes = data.get(*offset..*offset + 4)?;
+    let len = u32::from_le_bytes(len_bytes.try_into().ok()?) as usize;
+    let bytes = data.get(*offset + 4..(*offset + 4).checked_add(len)?)?;
+    *offset += 4 + len;
+    let text = std::str::from_utf8(bytes).ok()?;
+    Some(text.trim_end_matches('\0').trim().to_string())
+}
+
+/// Decodes the fields shown to users from metadata account `data`; None for anything that
+/// isn't a v1 metadata account.
+pub fn parse_metadata(data: &[u8]) -> Option<MetaplexMetadata> {
+    if *data.first()? != METADATA_V1_KEY {
+        return None;
+    }
+    let mint = Pubkey::new_from_array(data.get(33..65)?.try_into().ok()?);
+    let mut offset = 65;
+    Some(MetaplexMetadata {
+        mint,
+        name: read_string(data, &mut offset)?,
+        symbol: read_string(data, &mut offset)?,
+        uri: read_string(data, &mut offset)?,
+    })
+}
+
+fn non_empty(text: String) -> Option<String> {
+    (!text.is_empty()).then_some(text)
+}
+
+/// Reads `mint` and its metadata account through `fetch_account` (raw data, or None if the
+/// account doesn't exist). None when the mint doesn't; a mint without usable metadata still
+/// resolves, with only its decimals and any well-known symbol. Token-2022 mints work as long
+/// as their base layout is intact.
+pub fn resolve_token(
+    mint: &Pubkey,
+    mut fetch_account: impl FnMut(&Pubkey) -> Option<Vec<u8>>,
+) -> Option<TokenInfo> {
+    let mint_data = fetch_account(mint)?;
+    let decimals = Mint::unpack(mint_data.get(..Mint::LEN)?).ok()?.decimals;
+    let metadata = fetch_account(&metadata_address(mint))
+        .and_then(|data| parse_metadata(&data))
+        .filter(|metadata| metadata.mint == *mint);
+    let (name, symbol, logo_uri) = match metadata {
+        Some(m) => (non_empty(m.name), non_empty(m.symbol), non_empty(m.uri)),
+        None => (None, None, None),
+    };
+    Some(TokenInfo {
+        mint: *mint,
+        symbol: symbol.or_else(|| well_known_symbol(mint).map(str::to_string)),
+        name,
+        decimals,
+        logo_uri,
+    })
+}
+
+/// Resolved tokens by mint, persisted as JSON in the profile directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenCache {
+    tokens: BTreeMap<String, TokenInfo>,
+}
+
+impl TokenCache {
+    /// Reads the cache at `path`; a missing file is an empty cache.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Writes through a temporary file and a rename, like `EventCursor::save`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)
+    }
+
+    pub fn get(&self, mint: &Pubkey) -> Option<&TokenInfo> {
+        self.tokens.get(&mint.to_string())
+    }
+
+    /// The cached entry, or one resolved through `fetch_account` and remembered. Mints that
+    /// don't resolve aren't cached, so they're retried next time.
+    pub fn resolve(
+        &mut self,
+        mint: &Pubkey,
+        fetch_account: impl FnMut(&Pubkey) -> Option<Vec<u8>>,
+    ) -> Option<&TokenInfo> {
+        let key = mint.to_string();
+        if !self.tokens.contains_key(&key) {
+            let info = resolve_token(mint, fetch_account)?;
+            self.tokens.insert(key.clone(), info);
+        }
+        self.tokens.get(&key)
+    }
+
+    /// Drops `mint` so the next `resolve` reads it again, e.g. after a metadata update.
+    pub fn forget(&mut self, mint: &Pubkey) -> Option<TokenInfo> {
+        self.tokens.remove(&mint.to_string())
+    }
+}
+
+/// Fills in `symbol` on holdings that aren't well-known mints, for the `Info` table.
+pub fn label_holdings(
+    holdings: &mut [TokenHolding],
+    mut resolve: impl FnMut(&Pubkey) -> Option<TokenInfo>,
+) {
+    for holding in holdings.iter_mut().filter(|holding| holding.symbol.is_none()) {
+        holding.symbol = resolve(&holding.mint).and_then(|info| info.symbol);
+    }
+}
+
+fn is_mint_field(key: &str) -> bool {
+    key == "mint" || key.ends_with("_mint")
+}
+
+/// Adds `<field>_symbol` next to every mint field of a parsed event (or any serialized state)
+/// whose token resolves with a symbol, e.g. `"token_mint_symbol": "USDC"`.
+pub fn annotate_token_symbols(
+    value: &mut Value,
+    resolve: &mut impl FnMut(&Pubkey) -> Option<TokenInfo>,
+) {
+    match value {
+        Value::Object(map) => {
+            let mut symbols = Vec::new();
+            for (key, field) in map.iter_mut() {
+                let mint = match field.as_str() {
+                    Some(text) if is_mint_field(key) => Pubkey::from_str(text).ok(),
+                    _ => None,
+                };
+                match mint {
+                    Some(mint) => {
+                        if let Some(symbol) = resolve(&mint).and_then(|info| info.symbol) {
+                            symbols.push((format!("{}_symbol", key), symbol));
+                        }
+                    }
+                    None => annotate_token_symbols(field, resolve),
+                }
+            }
+            for (key, symbol) in symbols {
+                map.insert(key, Value::String(symbol));
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| annotate_token_symbols(item, resolve)),
+        _ => {}
+    }
+}
@@ -0,0 +1,32 @@
+use crate::state::Vault;
+
+// Time-weighted average TVL from two readings of a vault's tvl_cumulative accumulator
+
+/// A reading of the vault's TVL accumulator, brought up to `timestamp`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TvlObservation {
+    pub tvl_cumulative: u128,
+    pub timestamp: i64,
+}
+
+impl TvlObservation {
+    /// Reads `vault` as of `now`, crediting the TVL held since it last changed. `now` should
+    /// come from the same Clock the account was fetched at. None on overflow.
+    pub fn at(vault: &Vault, now: i64) -> Option<Self> {
+        Some(Self {
+            tvl_cumulative: vault.tvl_cumulative_at(now)?,
+            timestamp: now,
+        })
+    }
+}
+
+/// Average TVL over `[earlier.timestamp, later.timestamp)`. None unless `later` is strictly
+/// after `earlier` and its accumulator hasn't gone backwards.
+pub fn tvl_twap(earlier: &TvlObservation, later: &TvlObservation) -> Option<u128> {
+    let elapsed = later.timestamp.checked_sub(earlier.timestamp)?;
+    if elapsed <= 0 {
+        return None;
+    }
+    let held = later.tvl_cumulative.checked_sub(earlier.tvl_cumulative)?;
+    Some(held / elapsed as u128)
+}
@@ -0,0 +1,41 @@
+use solana_program::{instruction::Instruction, program_error::ProgramError, pubkey::Pubkey};
+
+use crate::client::instructions::view;
+use crate::view::{ViewQuery, ViewResult};
+use crate::{VaultError, ID};
+
+/// Decodes the return data a simulated View left behind. `program_id` is the program that set
+/// it, as reported by the simulation; data from any other program is rejected.
+pub fn decode_view_result(program_id: &Pubkey, data: &[u8]) -> Result<ViewResult, ProgramError> {
+    if *program_id != ID {
+        return Err(VaultError::UnexpectedReturnData.into());
+    }
+    ViewResult::decode(data)
+}
+
+/// Same as [`decode_view_result`] for RPC simulation results, which carry the data base64
+/// encoded.
+pub fn decode_view_return_data(
+    program_id: &Pubkey,
+    data_base64: &str,
+) -> Result<ViewResult, ProgramError> {
+    let data = base64::decode(data_base64).map_err(|_| VaultError::UnexpectedReturnData)?;
+    decode_view_result(program_id, &data)
+}
+
+/// Builds the View instruction and hands it to `simulate`, which wraps it in a transaction,
+/// runs simulateTransaction through the caller's client and returns the simulation's
+/// `(program_id, data)` return data. This crate doesn't depend on an RPC client, so the
+/// transport is left to the caller.
+pub fn simulate_view<E>(
+    vault: &Pubkey,
+    query: ViewQuery,
+    simulate: impl FnOnce(Instruction) -> Result<Option<(Pubkey, Vec<u8>)>, E>,
+) -> Result<ViewResult, E>
+where
+    E: From<ProgramError>,
+{
+    // A successful View always sets return data; none at all means it was all zero bytes
+    let (program_id, data) = simulate(view(vault, query))?.unwrap_or((ID, Vec::new()));
+    Ok(decode_view_result(&program_id, &data)?)
+}
@@ -0,0 +1,202 @@
+// Typed CPI helpers for programs that call into the vault.
+// Each account struct lists accounts in the order the processor reads them, with the
+// mutability the vault expects noted per field. Works from native and Anchor programs alike,
+// since it only needs AccountInfo and builds a plain Instruction.
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::instruction::VaultInstruction;
+use crate::state::TransactionAccount;
+use crate::{err, VaultError};
+
+pub trait VaultCpiAccounts<'info> {
+    fn to_account_metas(&self) -> Vec<AccountMeta>;
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>>;
+}
+
+/// Program account to invoke, the typed accounts, and seeds if a PDA of the caller signs.
+pub struct CpiContext<'a, 'b, 'c, 'info, T: VaultCpiAccounts<'info>> {
+    pub program: AccountInfo<'info>,
+    pub accounts: T,
+    pub signer_seeds: &'a [&'b [&'c [u8]]],
+}
+
+impl<'a, 'b, 'c, 'info, T: VaultCpiAccounts<'info>> CpiContext<'a, 'b, 'c, 'info, T> {
+    pub fn new(program: AccountInfo<'info>, accounts: T) -> Self {
+        Self {
+            program,
+            accounts,
+            signer_seeds: &[],
+        }
+    }
+
+    pub fn new_with_signer(
+        program: AccountInfo<'info>,
+        accounts: T,
+        signer_seeds: &'a [&'b [&'c [u8]]],
+    ) -> Self {
+        Self {
+            program,
+            accounts,
+            signer_seeds,
+        }
+    }
+}
+
+fn invoke_vault<'info, T: VaultCpiAccounts<'info>>(
+    ctx: CpiContext<'_, '_, '_, 'info, T>,
+    data: VaultInstruction,
+) -> ProgramResult {
+    if *ctx.program.key != crate::ID {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    let instruction = Instruction {
+        program_id: crate::ID,
+        accounts: ctx.accounts.to_account_metas(),
+        data: data.try_to_vec()?,
+    };
+    let mut infos = ctx.accounts.to_account_infos();
+    infos.push(ctx.program);
+    invoke_signed(&instruction, &infos, ctx.signer_seeds)
+}
+
+fn meta(info: &AccountInfo, is_writable: bool, is_signer: bool) -> AccountMeta {
+    if is_writable {
+        AccountMeta::new(*info.key, is_signer)
+    } else {
+        AccountMeta::new_readonly(*info.key, is_signer)
+    }
+}
+
+pub struct Deposit<'info> {
+    pub vault: AccountInfo<'info>,               // writable
+    pub user_token_account: AccountInfo<'info>,  // writable, user's ATA for the mint
+    pub vault_token_account: AccountInfo<'info>, // writable, vault's ATA for the mint
+    pub user_authority: AccountInfo<'info>,      // signer
+    pub token_program: AccountInfo<'info>,
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> VaultCpiAccounts<'info> for Deposit<'info> {
+    fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            meta(&self.vault, true, false),
+            meta(&self.user_token_account, true, false),
+            meta(&self.vault_token_account, true, false),
+            meta(&self.user_authority, false, true),
+            meta(&self.token_program, false, false),
+            meta(&self.clock, false, false),
+        ]
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vault.clone(),
+            self.user_token_account.clone(),
+            self.vault_token_account.clone(),
+            self.user_authority.clone(),
+            self.token_program.clone(),
+            self.clock.clone(),
+        ]
+    }
+}
+
+pub fn deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Deposit<'info>>,
+    amount: u64,
+) -> ProgramResult {
+    invoke_vault(ctx, VaultInstruction::Deposite { amount })
+}
+
+pub struct Withdraw<'info> {
+    pub vault: AccountInfo<'info>,               // writable
+    pub vault_token_account: AccountInfo<'info>, // writable, vault's ATA for the mint
+    pub user_token_account: AccountInfo<'info>,  // writable, user's ATA for the mint
+    pub user_authority: AccountInfo<'info>,      // signer
+    pub token_program: AccountInfo<'info>,
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> VaultCpiAccounts<'info> for Withdraw<'info> {
+    fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            meta(&self.vault, true, false),
+            meta(&self.vault_token_account, true, false),
+            meta(&self.user_token_account, true, false),
+            meta(&self.user_authority, false, true),
+            meta(&self.token_program, false, false),
+            meta(&self.clock, false, false),
+        ]
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![
+            self.vault.clone(),
+            self.vault_token_account.clone(),
+            self.user_token_account.clone(),
+            self.user_authority.clone(),
+            self.token_program.clone(),
+            self.clock.clone(),
+        ]
+    }
+}
+
+pub fn withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    amount: u64,
+) -> ProgramResult {
+    invoke_vault(ctx, VaultInstruction::Withdraw { amount })
+}
+
+// Shared by create/approve, which only need the vault, one signing owner and the clock
+pub struct MultiSigAction<'info> {
+    pub vault: AccountInfo<'info>, // writable
+    pub owner: AccountInfo<'info>, // signer, must be a multisig owner
+    pub clock: AccountInfo<'info>,
+}
+
+impl<'info> VaultCpiAccounts<'info> for MultiSigAction<'info> {
+    fn to_account_metas(&self) -> Vec<AccountMeta> {
+        vec![
+            meta(&self.vault, true, false),
+            meta(&self.owner, false, true),
+            meta(&self.clock, false, false),
+        ]
+    }
+
+    fn to_account_infos(&self) -> Vec<AccountInfo<'info>> {
+        vec![self.vault.clone(), self.owner.clone(), self.clock.clone()]
+    }
+}
+
+pub fn create_multisig_transaction<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, MultiSigAction<'info>>,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+    data: Vec<u8>,
+) -> ProgramResult {
+    invoke_vault(
+        ctx,
+        VaultInstruction::CreateMultiSigTransaction {
+            program_id,
+            accounts,
+            data,
+        },
+    )
+}
+
+pub fn approve_multisig_transaction<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, MultiSigAction<'info>>,
+    transaction_id: u64,
+) -> ProgramResult {
+    invoke_vault(
+        ctx,
+        VaultInstruction::ApproveMultiSigTransaction { transaction_id },
+    )
+}
@@ -2,15 +2,26 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::pubkey::Pubkey;
 use solana_program::clock::Clock;
 
+// Bump whenever an event's JSON layout changes; parsers dispatch on (event_type, schema_version)
+pub const EVENT_SCHEMA_VERSION: u8 = 7;
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct VaultEvent {
     pub event_type: String,
     pub vault: Pubkey,
     pub timestamp: i64,
     pub authority: Pubkey,
+    // Absent in events logged before versioning, which parse as version 0
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub schema_version: u8,
+    // Added in version 4; older events parse with slot 0
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub slot: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct DepositEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
@@ -19,6 +30,7 @@ pub struct DepositEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct WithdrawEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
@@ -27,6 +39,7 @@ pub struct WithdrawEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigInitializedEvent {
     pub base: VaultEvent,
     pub owners: Vec<Pubkey>,
@@ -35,6 +48,7 @@ pub struct MultiSigInitializedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigTransactionCreatedEvent {
     pub base: VaultEvent,
     pub transaction_id: u64,
@@ -44,6 +58,7 @@ pub struct MultiSigTransactionCreatedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigTransactionApprovedEvent {
     pub base: VaultEvent,
     pub transaction_id: u64,
@@ -53,6 +68,7 @@ pub struct MultiSigTransactionApprovedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigTransactionExecutedEvent {
     pub base: VaultEvent,
     pub transaction_id: u64,
@@ -60,14 +76,53 @@ pub struct MultiSigTransactionExecutedEvent {
     pub target_program: Pubkey,
 }
 
+// Emitted as global_config_initialized and global_config_updated; base.vault is the config PDA
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct GlobalConfigUpdatedEvent {
+    pub base: VaultEvent,
+    pub admin: Pubkey,
+    pub max_fee_bps: u64,
+    pub allowed_programs: Vec<Pubkey>,
+    pub protocol_fee_recipient: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub protocol_fee_bps: u16,
+}
+
+// base.vault is the config PDA, base.authority the admin vault's multisig signer
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct UpgradeAuthorityAcknowledgedEvent {
+    pub base: VaultEvent,
+    pub previous_authority: Option<Pubkey>,
+    pub expected_upgrade_authority: Option<Pubkey>,
+    pub upgrade_check_threshold: u64,
+}
+
+// Logged before the error is propagated, so the vault state it describes is rolled back
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransactionExecutionFailedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub executor: Pubkey,
+    pub target_program: Pubkey,
+    pub error: String, // the inner ProgramError, Debug-formatted
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigOwnersUpdatedEvent {
     pub base: VaultEvent,
     pub old_owners: Vec<Pubkey>,
     pub new_owners: Vec<Pubkey>,
+    // When each of new_owners joined; empty in events logged before join times were kept
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub owner_since: Vec<i64>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct MultiSigThresholdUpdatedEvent {
     pub base: VaultEvent,
     pub old_threshold: u64,
@@ -75,31 +130,168 @@ pub struct MultiSigThresholdUpdatedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigProposalLimitUpdatedEvent {
+    pub base: VaultEvent,
+    pub old_limit: u32,
+    pub new_limit: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct RecoveryConfiguredEvent {
+    pub base: VaultEvent,
+    pub recovery_key: Option<Pubkey>,
+    pub inactivity_period: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct RecoveryInitiatedEvent {
+    pub base: VaultEvent,
+    pub recovery_key: Pubkey,
+    pub last_owner_activity: i64,
+    pub finalize_after: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct RecoveryVetoedEvent {
+    pub base: VaultEvent,
+    pub vetoed_by: Pubkey,
+    pub initiated_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct RecoveryFinalizedEvent {
+    pub base: VaultEvent,
+    pub old_owners: Vec<Pubkey>,
+    pub new_owner: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct AttestationEvent {
+    pub base: VaultEvent,
+    pub message_hash: [u8; 32],
+    pub transaction_id: u64,
+    pub approvers: Vec<Pubkey>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct VaultInitializedEvent {
     pub base: VaultEvent,
     pub bump: u8,
     pub emergency_admin: Pubkey,
+    pub index: u16,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct TokenDepositedEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
     pub amount: u64,
     pub fee_amount: u64,
     pub depositor: Pubkey,
+    // Added in schema version 5: fee_amount split between the vault's fee recipient and the
+    // protocol. Unrouted fees are all vault share.
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub vault_fee_amount: u64,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub protocol_fee_amount: u64,
+    // Added in schema version 6: the mint's tracked balance, the vault's TVL and the mint's
+    // accrued fees once the deposit is booked, so a ledger reconciles from any one event.
+    // resulting_tvl is a decimal string from version 7, when TVL became a u128.
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub resulting_balance: u64,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    #[serde(with = "crate::serde_helpers::u128_string")]
+    pub resulting_tvl: u128,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub accrued_fees_after: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TokenWithdrawnEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub recipient: Pubkey,
+    // Added in schema version 5, as for TokenDepositedEvent
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub vault_fee_amount: u64,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub protocol_fee_amount: u64,
+    // Added in schema version 6, as for TokenDepositedEvent
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub resulting_balance: u64,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    #[serde(with = "crate::serde_helpers::u128_string")]
+    pub resulting_tvl: u128,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub accrued_fees_after: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct WithdrawalRequestedEvent {
+    pub base: VaultEvent,
+    pub ticket_id: u64,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub available_at: i64,
 }
 
+// Follows the token_withdrawn event of the payout, where the fee is reported
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
-pub struct TokenWithdrawnEvent {
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct WithdrawalClaimedEvent {
+    pub base: VaultEvent,
+    pub ticket_id: u64,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct WithdrawalRequestCancelledEvent {
     pub base: VaultEvent,
+    pub ticket_id: u64,
     pub token_mint: Pubkey,
     pub amount: u64,
+}
+
+// SOL moves carry no mint, so indexers don't mistake them for wrapped SOL. Before schema
+// version 4, sol_withdrawn and sol_transferred used TokenWithdrawnEvent with the native mint.
+// SOL comes in by plain system transfer, which the program never sees; the sweep that counts
+// it reports untracked_lamports_swept.
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct SolWithdrawnEvent {
+    pub base: VaultEvent,
+    pub amount: u64,
     pub fee_amount: u64,
     pub recipient: Pubkey,
+    pub vault_lamports: u64, // vault balance after the withdrawal
+    // Added in schema version 6: tracked_lamports, the vault's TVL and the SOL fees accrued
+    // apart from it afterwards. SOL fees stay tracked, so the last is 0 so far. As for
+    // TokenDepositedEvent, resulting_tvl is a string from version 7.
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub resulting_balance: u64,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    #[serde(with = "crate::serde_helpers::u128_string")]
+    pub resulting_tvl: u128,
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub accrued_fees_after: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct ProposalCreatedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
@@ -107,6 +299,7 @@ pub struct ProposalCreatedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct ProposalApprovedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
@@ -115,30 +308,62 @@ pub struct ProposalApprovedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct ProposalExecutedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct VaultPausedEvent {
     pub base: VaultEvent,
+    pub expires_at: Option<i64>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct VaultUnpausedEvent {
     pub base: VaultEvent,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VaultPauseExtendedEvent {
+    pub base: VaultEvent,
+    pub paused_at: i64,
+    pub expires_at: Option<i64>,
+}
+
+// Emitted by the first operation that finds the pause has run out
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VaultPauseExpiredEvent {
+    pub base: VaultEvent,
+    pub paused_at: i64,
+    pub expired_at: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MaxPauseDurationUpdatedEvent {
+    pub base: VaultEvent,
+    pub old_duration: i64,
+    pub new_duration: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct EmergencyWithdrawEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
     pub amount: u64,
     pub recipient: Pubkey,
+    pub cosigner: Option<Pubkey>, // the multisig owner who co-signed, if one was required
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct TokenAddedEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
@@ -146,33 +371,293 @@ pub struct TokenAddedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct TimeLockCreatedEvent {
     pub base: VaultEvent,
     pub time_lock_index: usize,
+    pub time_lock_id: u64,
     pub beneficiary: Pubkey,
+    pub mint: Pubkey,
     pub amount: u64,
-    pub duration: i64,
-    pub cliff_time: Option<i64>,
-    pub is_linear: bool,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+    pub schedule: crate::state::VestingSchedule,
+    pub revocable: bool,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct TimeLockClaimedEvent {
     pub base: VaultEvent,
     pub time_lock_index: usize,
+    pub time_lock_id: u64,
     pub beneficiary: Pubkey,
-    pub claimed_amount: u64,
+    pub claimed_amount: u64, // charged to the lock; the beneficiary receives it less fee_amount
     pub remaining_amount: u64,
+    // Added in schema version 3
+    #[cfg_attr(feature = "state-serde", serde(default))]
+    pub fee_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StreamConfigUpdatedEvent {
+    pub base: VaultEvent,
+    pub exempt_from_pause: bool,
+    pub claim_fee_bps: u16,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct YieldStrategySetEvent {
     pub base: VaultEvent,
     pub token_mint: Pubkey,
     pub strategy_program: Pubkey,
+    pub max_allocation_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyDeployedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub amount: u64, // measured
+    pub deployed_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyWithdrawnEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub principal: u64,
+    pub returned: u64, // measured
+    pub pnl: i64,
+    pub realized_pnl: i64,
+    pub deployed_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct SwapExecutedEvent {
+    pub base: VaultEvent,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub amount_in: u64,  // measured
+    pub amount_out: u64, // measured
+    pub route_instruction: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyLossEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub principal: u64,
+    pub returned: u64,
+    pub loss: u64,
+    pub realized_pnl: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyLossAcknowledgedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub realized_pnl: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyStatusChangedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub previous: crate::state::StrategyStatus,
+    pub status: crate::state::StrategyStatus,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct StrategyExitedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub principal: u64, // the full deployed_amount at exit
+    pub returned: u64,  // measured
+    pub pnl: i64,
+    pub realized_pnl: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct ExecutionTipFundedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub funder: Pubkey,
+    pub lamports: u64,
+    pub total_tip: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct ExecutionTipPaidEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub executor: Pubkey,
+    pub lamports: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransactionCancelledEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub proposer: Pubkey,
+    pub refunded_lamports: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigExecutionDelayUpdatedEvent {
+    pub base: VaultEvent,
+    pub old_delay: i64,
+    pub new_delay: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransactionExpeditedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub emergency_admin: Pubkey,
+    pub skipped_seconds: i64, // delay left when it was expedited
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransactionExpiryExtendedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub previous_expires_at: i64,
+    pub new_expires_at: i64,
+}
+
+// Follows the multisig_transaction_created event of a ProposeSolTransfer or
+// ProposeTokenTransfer; mint is None for SOL
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransferProposedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub mint: Option<Pubkey>,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub memo: String,
+}
+
+// mint is default for SOL. The outflow that tripped it is included in window_outflow.
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct CircuitBreakerTrippedEvent {
+    pub base: VaultEvent,
+    pub mint: Pubkey,
+    pub window_start: i64,
+    pub opening_balance: u64,
+    pub window_outflow: u64,
+    pub threshold_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct UntrackedLamportsSweptEvent {
+    pub base: VaultEvent,
+    pub amount: u64,
+    pub tracked_lamports: u64, // after the sweep
 }
 
+// Emitted for the original lock; the new one gets a TimeLockCreatedEvent of its own.
+// amount and released_amount are what the original keeps.
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TimeLockSplitEvent {
+    pub base: VaultEvent,
+    pub time_lock_id: u64,
+    pub new_time_lock_id: u64,
+    pub new_beneficiary: Pubkey,
+    pub amount_moved: u64,
+    pub released_moved: u64,
+    pub amount: u64,
+    pub released_amount: u64,
+}
+
+// One supported token in a heartbeat. drift is the associated token account's amount less
+// tracked, present only when that account was passed in.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct HealthCheckBalance {
+    pub mint: Pubkey,
+    pub tracked: u64,
+    pub drift: Option<i128>,
+}
+
+// Heartbeat from EmitHealthCheck. Monitoring alerts when these stop arriving or any drift is
+// non-zero. Pending counts leave out executed and cancelled entries and expired multisig
+// transactions.
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct HealthCheckEvent {
+    pub base: VaultEvent,
+    #[serde(with = "crate::serde_helpers::u128_string")]
+    pub total_value_locked: u128,
+    pub tracked_lamports: u64,
+    pub balances: Vec<HealthCheckBalance>,
+    pub paused: bool,
+    pub circuit_breaker_tripped: bool,
+    pub pending_multisig_transactions: u32,
+    pub pending_proposals: u32,
+    pub active_time_locks: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct ParamUpdatedEvent {
+    pub base: VaultEvent,
+    pub key: u8, // ParamKey discriminant
+    pub old: u64,
+    pub new: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TemplateCreatedEvent {
+    pub base: VaultEvent,
+    pub template_id: u64,
+    pub name: String,
+    pub program_id: Pubkey,
+    pub fill_len: u64, // bytes each instantiation supplies
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TemplateDeletedEvent {
+    pub base: VaultEvent,
+    pub template_id: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MultiSigTransactionsPrunedEvent {
+    pub base: VaultEvent,
+    pub transaction_ids: Vec<u64>,
+    pub remaining: usize,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct FeeConfigUpdatedEvent {
     pub base: VaultEvent,
     pub deposit_fee_bps: u16,
@@ -181,18 +666,21 @@ pub struct FeeConfigUpdatedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct AuthorityTransferredEvent {
     pub base: VaultEvent,
     pub new_authority: Pubkey,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct EmergencyAdminUpdatedEvent {
     pub base: VaultEvent,
     pub new_admin: Pubkey,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceInitializedEvent {
     pub base: VaultEvent,
     pub voting_token_mint: Pubkey,
@@ -201,6 +689,7 @@ pub struct GovernanceInitializedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceProposalCreatedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
@@ -210,6 +699,7 @@ pub struct GovernanceProposalCreatedEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceVoteCastEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
@@ -219,24 +709,160 @@ pub struct GovernanceVoteCastEvent {
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceProposalQueuedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
     pub eta: i64,
 }
 
+// voting_power is the escrowed amount plus its boost at registration
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VoterRegisteredEvent {
+    pub base: VaultEvent,
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
+    pub voting_power: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VoterUnregisteredEvent {
+    pub base: VaultEvent,
+    pub voter: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceProposalExecutedEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct GovernanceProposalCancelledEvent {
     pub base: VaultEvent,
     pub proposal_id: u64,
 }
 
+// CouncilApproveProposal; base.authority is the multisig signer PDA
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct CouncilProposalApprovedEvent {
+    pub base: VaultEvent,
+    pub proposal_id: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VaultConfigUpdatedEvent {
+    pub base: VaultEvent,
+    pub config: crate::state::VaultConfig,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct VaultMigratedEvent {
+    pub base: VaultEvent,
+    pub from_version: u8,
+    pub to_version: u8,
+    pub time_locks_migrated: u64,
+}
+
+// One per mint drained by CollectFees
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct FeesCollectedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub amount: u64,
+    pub recipient: Pubkey,
+}
+
+// RefreshSupportedTokenMetadata; balance is what the vault tracked when the record changed
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct SupportedTokenRefreshedEvent {
+    pub base: VaultEvent,
+    pub token_mint: Pubkey,
+    pub old_decimals: u8,
+    pub new_decimals: u8,
+    pub old_token_program: Pubkey,
+    pub new_token_program: Pubkey,
+    pub balance: u64,
+}
+
+// FinalizeSetup; setup_gate says whether deposits and withdrawals were held until now
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct SetupFinalizedEvent {
+    pub base: VaultEvent,
+    pub supported_tokens: u32,
+    pub setup_gate: bool,
+}
+
+// Tick; the counts are transitions this call recorded, scanned how many items it looked at
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TickEvent {
+    pub base: VaultEvent,
+    pub scanned: u32,
+    pub transactions_expired: u32,
+    pub proposals_passed: u32,
+    pub proposals_defeated: u32,
+    pub time_locks_vested: u32,
+    pub proposals_undetermined: u32,
+}
+
+// FinalizeTransactionFromBuffer; data_hash is the SHA-256 of the data the transaction was
+// given, for checking it against what the proposer meant to upload
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TransactionBufferFinalizedEvent {
+    pub base: VaultEvent,
+    pub transaction_id: u64,
+    pub proposer: Pubkey,
+    pub data_len: u32,
+    pub data_hash: [u8; 32],
+}
+
+// AssignTimeLockMint, for a lock migrated without a known mint
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TimeLockMintAssignedEvent {
+    pub base: VaultEvent,
+    pub time_lock_id: u64,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// CancelTimeLockV2. amount and released_amount are what the lock keeps, all of it vested.
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct TimeLockCancelledEvent {
+    pub base: VaultEvent,
+    pub time_lock_id: u64,
+    pub beneficiary: Pubkey,
+    pub mint: Pubkey,
+    pub amount_returned: u64,
+    pub amount: u64,
+    pub released_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
+pub struct MintMinimumsUpdatedEvent {
+    pub base: VaultEvent,
+    pub mint: Pubkey,
+    pub min_deposit: u64,
+    pub min_withdrawal: u64,
+}
+
 pub fn create_base_event(
     vault: Pubkey,
     authority: Pubkey,
@@ -248,10 +874,13 @@ pub fn create_base_event(
         vault,
         timestamp: clock.unix_timestamp,
         authority,
+        schema_version: EVENT_SCHEMA_VERSION,
+        slot: clock.slot,
     }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, serde::Serialize)]
+#[cfg_attr(feature = "state-serde", derive(serde::Deserialize))]
 pub struct TransferEvent {
     pub from: Pubkey,
     pub to: Pubkey,
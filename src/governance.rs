@@ -0,0 +1,111 @@
+use solana_program::pubkey::Pubkey;
+
+use crate::state::{GovernanceInstruction, VotingModel};
+
+// Pure voting math shared by the governance processors and off-chain tooling.
+// Tallies are in effective power: the raw voting-token balance under Linear, its integer
+// square root under Quadratic. Quorum and execution thresholds are basis points. Vote locks
+// add a boost on top of the escrowed amount that shrinks linearly with the time left.
+
+// ParamKey::CouncilCategories bits. A proposal calling a program whose bit is set needs the
+// council's approval as well as the token vote; COUNCIL_ALL_PROPOSALS covers every proposal,
+// including ones without instructions.
+pub const COUNCIL_VAULT_PROGRAM: u64 = 1;
+pub const COUNCIL_SYSTEM_PROGRAM: u64 = 1 << 1;
+pub const COUNCIL_TOKEN_PROGRAM: u64 = 1 << 2;
+pub const COUNCIL_ASSOCIATED_TOKEN_PROGRAM: u64 = 1 << 3;
+pub const COUNCIL_ALL_PROPOSALS: u64 = 1 << 4;
+pub const COUNCIL_CATEGORIES: u64 = (1 << 5) - 1;
+
+/// Highest `max_boost_bps` governance accepts: a full-length lock votes with at most 4x its
+/// escrowed amount.
+pub const MAX_BOOST_BPS: u16 = 30_000;
+
+/// Floor of the square root of `n`, digit by digit so it stays exact for every u64.
+pub fn integer_sqrt(n: u64) -> u64 {
+    let mut remainder = n;
+    let mut root = 0u64;
+    let mut bit = 1u64 << 62;
+    while bit > remainder {
+        bit >>= 2;
+    }
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+    root
+}
+
+/// Voting power that `balance` voting tokens carry under `model`.
+pub fn effective_power(model: VotingModel, balance: u64) -> u64 {
+    match model {
+        VotingModel::Linear => balance,
+        VotingModel::Quadratic => integer_sqrt(balance),
+    }
+}
+
+/// Whether the votes cast reach `quorum_bps` of `effective_supply`. A zero supply (nobody
+/// registered when the proposal was created) leaves quorum unchecked.
+pub fn quorum_reached(
+    for_votes: u64,
+    against_votes: u64,
+    abstain_votes: u64,
+    effective_supply: u64,
+    quorum_bps: u16,
+) -> bool {
+    let cast = for_votes as u128 + against_votes as u128 + abstain_votes as u128;
+    cast * 10_000 >= effective_supply as u128 * quorum_bps as u128
+}
+
+/// Whether `for_votes` make up at least `execution_bps` of the for and against votes.
+/// Abstentions count towards quorum only.
+pub fn approved(for_votes: u64, against_votes: u64, execution_bps: u16) -> bool {
+    let decisive = for_votes as u128 + against_votes as u128;
+    decisive > 0 && for_votes as u128 * 10_000 >= decisive * execution_bps as u128
+}
+
+/// Extra power a lock of `amount` earns with `remaining` seconds left: `max_boost_bps` of the
+/// amount at `max_lock` or more, shrinking linearly to nothing as the lock runs out. A
+/// `max_lock` of 0 turns boosting off.
+pub fn lock_boost(amount: u64, remaining: i64, max_lock: i64, max_boost_bps: u16) -> u64 {
+    if max_lock <= 0 || remaining <= 0 {
+        return 0;
+    }
+    let weighted = remaining.min(max_lock) as u128;
+    let boost = amount as u128 * weighted * max_boost_bps as u128 / (max_lock as u128 * 10_000);
+    boost.min(u64::MAX as u128) as u64
+}
+
+/// Voting power of a lock of `amount`: the amount itself plus its boost.
+pub fn locked_power(amount: u64, remaining: i64, max_lock: i64, max_boost_bps: u16) -> u64 {
+    amount.saturating_add(lock_boost(amount, remaining, max_lock, max_boost_bps))
+}
+
+/// The CouncilCategories bit for a proposal instruction calling `program_id`; 0 for programs
+/// proposals may not call.
+pub fn council_category(program_id: &Pubkey) -> u64 {
+    if *program_id == crate::ID {
+        COUNCIL_VAULT_PROGRAM
+    } else if *program_id == solana_program::system_program::ID {
+        COUNCIL_SYSTEM_PROGRAM
+    } else if *program_id == spl_token::ID {
+        COUNCIL_TOKEN_PROGRAM
+    } else if *program_id == spl_associated_token_account::ID {
+        COUNCIL_ASSOCIATED_TOKEN_PROGRAM
+    } else {
+        0
+    }
+}
+
+/// Whether a proposal made of `instructions` needs the council under `categories`.
+pub fn requires_council(categories: u64, instructions: &[GovernanceInstruction]) -> bool {
+    categories & COUNCIL_ALL_PROPOSALS != 0
+        || instructions
+            .iter()
+            .any(|instruction| categories & council_category(&instruction.program_id) != 0)
+}
@@ -1,26 +1,46 @@
 use solana_program::pubkey::Pubkey;
 use borsh::{BorshDeserialize, BorshSerialize};
-use crate::state::{GovernanceInstruction, VoteType};
+use crate::state::{
+    AccountRole, BalanceAssertion, ByteRange, GovernanceInstruction, ParamKey, StrategyStatus,
+    TransactionAccount, VestingSchedule, VoteType, VotingModel,
+};
+use crate::view::ViewQuery;
 
+// Wire format: borsh writes the variant's position as a one-byte tag ahead of its fields, so
+// the tag is the declaration order and tests/wire_format.rs holds it, and every variant's
+// bytes, against tests/fixtures/golden_instructions.txt. New variants go at the end.
+// Reordering, removing or retyping a variant breaks every client and stored multisig payload.
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum VaultInstruction {
     // why we need this-> every transaction to your program will specify which variant to execute
     // sol -> serialze instructions -> then send it to your program
+    // Superseded by InitializeVault. The bump is ignored: the vault always lands on the
+    // canonical PDA with the bump find_program_address gives.
     Initialize {
-        bump: u8, // initial vault creates PDA
-    },
+        bump: u8,
+        index: u16, // lets one authority own several vaults, 0 is the original vault
+        allow_same_as_authority: bool, // must be set to use the authority as emergency admin
+    },
+    // A deposit takes three optional accounts after the clock to route the fee: the
+    // GlobalConfig (writable) and the ATAs of the vault's fee recipient and of the protocol
+    // fee recipient. The fee is then paid to the two, split by protocol_fee_bps. Withdraw
+    // always takes the GlobalConfig PDA after the clock, created yet or not, and optionally
+    // the two ATAs after it. It also needs the program's ProgramData account last when the
+    // config expects an upgrade authority and the amount is over upgrade_check_threshold.
     Deposite {
         amount: u64, // V
     },
     Withdraw {
         amount: u64, // V and Security
     },
+    // Accounts: vault, recipient, authority (signer), system program, clock. Only the vault
+    // authority can pay tracked SOL out.
     WithdrawSOL {
         amount: u64, // Withdraw SOL directly from vault
     },
     Transfer {
         recipient: Pubkey,
-        amount: u64, // Transfer SOL directly to another account
+        amount: u64, // Transfer SOL directly to another account, no withdrawal fee
     },
     InitializeMultiSig {
         // multi sig
@@ -62,8 +82,12 @@ pub enum VaultInstruction {
         proposal_id: u64,
     },
 
-    PauseVault,   // emergency pause
-    UnpauseVault, // resume operations
+    PauseVault,   // emergency admin; lapses after max_pause_duration when that is set
+    UnpauseVault, // emergency admin; resume operations before the pause runs out
+    // Accounts: vault, vault token ATA, recipient token account, emergency admin (signer),
+    // token program, clock, then a multisig owner (signer) when EmergencyRequiresCosign is
+    // set. Works while paused. Then the GlobalConfig PDA, created yet or not, and the
+    // program's ProgramData account if the config expects an upgrade authority.
     EmergencyWithdraw {
         token_mint: Pubkey,
         amount: u64,
@@ -71,6 +95,7 @@ pub enum VaultInstruction {
     AddSupportedToken {
         mint: Pubkey,
         bump: u8,
+        idempotent: bool, // an already active mint succeeds as a no-op instead of failing
     },
     DepositMultiToken {
         mint: Pubkey,
@@ -78,17 +103,24 @@ pub enum VaultInstruction {
     },
     CreateTimeLock {
         beneficiary: Pubkey,
+        mint: Pubkey, // must be a supported token, claims are only paid in this mint
         amount: u64,
         duration: i64,
         cliff_duration: Option<i64>,
-        is_linear: bool,
+        schedule: VestingSchedule,
+        revocable: bool,
     },
+    // Deprecated: addressed locks by position, which shifts as locks are added and split.
+    // Rejected with DeprecatedInstruction; use ClaimTimeLockV2 / CancelTimeLockV2. The
+    // field was a usize, which Borsh encodes as these same 8 bytes.
     ClaimTimeLock {
-        time_lock_index: usize,
+        time_lock_index: u64,
     },
     CancelTimeLock {
-        time_lock_index: usize,
+        time_lock_index: u64,
     },
+    // Accounts: vault, authority (signer), clock, optionally the GlobalConfig, whose registry
+    // the strategy program must then be in. Clears any pinned strategy accounts.
     SetYieldStrategy {
         token_mint: Pubkey,
         strategy_program: Pubkey,
@@ -105,12 +137,18 @@ pub enum VaultInstruction {
         amount: u64,
     },
 
+    // Authority signs. route is Jupiter v6 instruction data, at most jupiter::MAX_ROUTE_LEN
+    // bytes and starting with a known discriminator; see process_jupiter_route for accounts.
+    // output_mint must be supported unless auto_add, which registers it first.
     JupiterRoute {
         input_mint: Pubkey,
         output_mint: Pubkey,
         amount: u64,
+        auto_add: bool,
         route: Vec<u8>,
     },
+    // Authority signs. Pays each mint's accrued fees to fee_config.fee_recipient; see
+    // process_collect_fees for accounts.
     CollectFees,
     TransferAuthority {
         new_authority: Pubkey,
@@ -118,6 +156,8 @@ pub enum VaultInstruction {
     UpdateEmergencyAdmin {
         new_admin: Pubkey,
     },
+    // Authority signs; accounts are the vault, the authority and the clock. Sets up governance
+    // once; later changes go through UpdateGovernanceConfig.
     InitializeGovernance {
         voting_token_mint: Pubkey,
         quorum_threshold: u16,
@@ -126,32 +166,427 @@ pub enum VaultInstruction {
         time_lock_delay: i64,
         execution_threshold: u16,
     },
+    // Accounts: vault, proposer (signer), the proposer's voting token account, clock. Each
+    // entry of instructions is a Borsh GovernanceInstruction, checked against its limits and
+    // the allowed programs before the proposal is stored. title and description are held to
+    // GovernanceProposal::validate_text.
     CreateGovernanceProposal {
         title: String,
         description: String,
         instructions: Vec<Vec<u8>>,
     },
+    // Accounts: vault, voter (signer), clock. The voter's vote lock counts, boost included,
+    // under the vault's voting_model; it must have been made before the proposal opened.
     CastVote {
         proposal_id: u64,
         vote_type: crate::state::VoteType,
     },
+    // Accounts: vault, caller (signer), clock. Once voting has ended, queues a proposal that
+    // reached quorum of its supply snapshot and its execution threshold. Proposals created
+    // before snapshots can't be queued.
     QueueProposal {
         proposal_id: u64,
     },
+    // Accounts: vault, caller (signer), clock, then every account the proposal's instructions
+    // name. The caller is the vault authority or the multisig signer. Runs a queued proposal
+    // once its eta has passed and unless Tick settled it defeated or undetermined; proposals
+    // that need the council also need its CouncilApproveProposal first.
     ExecuteGovernanceProposal {
         proposal_id: u64,
     },
+    // Authority signs; accounts are the vault, the authority and the clock. Thresholds are
+    // basis points. voting_model can only change while no proposal is open for votes.
+    // A vote lock of max_lock_duration seconds or longer earns max_boost_bps of its amount,
+    // at most governance::MAX_BOOST_BPS.
     UpdateGovernanceConfig {
         quorum_threshold: u16,
         proposal_threshold: u64,
         voting_period: i64,
         time_lock_delay: i64,
         execution_threshold: u16,
+        voting_model: VotingModel,
+        max_lock_duration: i64,
+        max_boost_bps: u16,
+    },
+    UpdateVaultConfig {
+        config: crate::state::VaultConfig,
+    },
+    // Rewrites a vault account from an older state version in the current layout,
+    // reallocating it (paid by the optional trailing payer) when it no longer fits. Versions
+    // from before SOL tracking have their lamports above rent seeded into tracked_lamports.
+    MigrateVault,
+    // Signed by the multisig PDA, so it can only run as an executed multisig transaction
+    SetMultiSigProposalLimit {
+        max_proposals_per_day: u32,
+    },
+    // Multisig PDA signed; records the hash with the owners who approved the executing transaction
+    AttestMessage {
+        message_hash: [u8; 32],
+    },
+    // Multisig PDA signed; None disables recovery and cancels any pending attempt
+    SetMultiSigRecovery {
+        recovery: Option<crate::state::RecoveryConfig>,
+    },
+    InitiateRecovery, // recovery key, once owners have been inactive long enough
+    VetoRecovery,     // any owner, during the challenge window
+    FinalizeRecovery, // recovery key, after the challenge window
+    // Authority moves up to `amount` of the mint into its strategy. `strategy_data` is passed
+    // to the strategy program as-is, with the vault PDA signing; only the measured outflow
+    // from the vault token account is booked as deployed.
+    DeployToStrategy {
+        token_mint: Pubkey,
+        amount: u64,
+        strategy_data: Vec<u8>,
+    },
+    // Authority pulls `amount` of deployed principal back; whatever the vault token account
+    // measurably receives is booked, and the difference is the realized pnl
+    WithdrawFromStrategy {
+        token_mint: Pubkey,
+        amount: u64,
+        strategy_data: Vec<u8>,
+    },
+    // Authority sign-off on a realized loss, required before deploying to the strategy again
+    AcknowledgeStrategyLoss {
+        token_mint: Pubkey,
+    },
+    // Proposer escrows lamports in the vault for whoever executes the transaction; paid out
+    // only when ExecuteMultiSigTransaction succeeds. Repeated calls top the tip up.
+    FundExecutionTip {
+        transaction_id: u64,
+        lamports: u64,
+    },
+    // Proposer withdraws an unexecuted transaction; any escrowed tip is refunded
+    CancelMultiSigTransaction {
+        transaction_id: u64,
+    },
+    ExtendPause, // emergency admin; restarts the max_pause_duration countdown of a live pause
+    // Authority, while not paused; seconds after which a pause lapses on its own, 0 for
+    // indefinite pauses
+    SetMaxPauseDuration {
+        max_pause_duration: i64,
+    },
+    // Beneficiary pulls any amount up to what has vested and not yet been claimed; same
+    // accounts as ClaimTimeLock, but the lock is addressed by its id
+    ClaimTimeLockPartial {
+        time_lock_id: u64,
+        amount: u64,
+    },
+    // Authority; applies to both claim instructions
+    SetStreamConfig {
+        exempt_from_pause: bool,
+        claim_fee_bps: u16,
+    },
+    // Multisig owner; drops executed, cancelled and expired transactions. Pending
+    // transactions keep their ids, so later approve/execute calls are unaffected. Creating a
+    // transaction drops expired ones on its own.
+    PruneMultiSigTransactions,
+    // Multisig signer; delay between a transaction reaching its threshold and execution
+    SetExecutionDelay {
+        execution_delay_seconds: i64,
+    },
+    // Emergency admin; lets an approved transaction skip the rest of its execution delay
+    ExpediteTransaction {
+        transaction_id: u64,
+    },
+    // Multisig signer; sets one registry parameter within its ParamKey::bounds. A GlobalConfig
+    // passed after the clock replaces the fee ceiling with its max_fee_bps.
+    SetParam {
+        key: ParamKey,
+        value: u64,
+    },
+    // Read-only; accounts are the vault and the clock. The answer is a Borsh ViewResult in
+    // the return data, also logged as `VIEW: <json>`.
+    View {
+        query: ViewQuery,
+    },
+    // Multisig signer; stores a payload shape that owners can later propose with only the
+    // mutable_ranges of its data filled in
+    CreateTemplate {
+        name: String,
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        data_prefix: Vec<u8>,
+        mutable_ranges: Vec<ByteRange>,
+    },
+    // Multisig signer; transactions already created from the template are unaffected
+    DeleteTemplate {
+        template_id: u64,
+    },
+    // Multisig owner; same accounts as CreateMultiSigTransaction. fill_bytes covers every
+    // mutable range in order and must match their total length exactly.
+    CreateTransactionFromTemplate {
+        template_id: u64,
+        fill_bytes: Vec<u8>,
+    },
+    // Depositor signs and pays. Wraps `amount` lamports into the depositor's wSOL ATA, creating
+    // it if needed, then deposits them like a regular deposit under the native mint.
+    // close_wrapped_account unwraps what is left in that ATA, its rent included, back to the
+    // depositor afterwards.
+    DepositWrappedSOL {
+        amount: u64,
+        close_wrapped_account: bool,
+    },
+    // Accounts: vault, voter (signer), the voter's voting token account, the vault's voting
+    // token ATA, token program, clock. Escrows `amount` voting tokens until now +
+    // lock_duration and registers the voter; the lock's boost decays as it runs down.
+    RegisterVoterWithLock {
+        amount: u64,
+        lock_duration: i64,
+    },
+    // Same accounts as RegisterVoterWithLock. Returns escrowed tokens and drops the voter
+    // from the registry, once any lock has expired.
+    UnregisterVoter,
+    // Same accounts as Initialize. The vault PDA and its bump are derived on-chain from the
+    // authority and index.
+    InitializeVault {
+        index: u16,
+        allow_same_as_authority: bool,
+    },
+    // Same accounts as SetYieldStrategy. Caps the mint's strategy at max_allocation_bps of
+    // its holdings (tracked balance plus deployed); a cap below what is already deployed
+    // only blocks further deploys.
+    UpdateYieldStrategy {
+        token_mint: Pubkey,
+        max_allocation_bps: u16,
+    },
+    // Accounts: vault, authority or emergency admin (signer), clock. The emergency admin may
+    // only pause or exit; making a strategy Active again takes the authority.
+    SetStrategyStatus {
+        token_mint: Pubkey,
+        status: StrategyStatus,
+    },
+    // Same accounts as DeployToStrategy, signed by the authority or emergency admin.
+    // strategy_data is the protocol's withdraw for the full deployed_amount; the measured
+    // return is booked and the strategy is left Exiting. Whatever doesn't come back stays
+    // deployed until a WithdrawFromStrategy books it. Works while the vault is paused.
+    EmergencyExitStrategy {
+        token_mint: Pubkey,
+        strategy_data: Vec<u8>,
+    },
+    // Accounts: global config PDA, payer (signer), system program, rent, clock, this
+    // program's ProgramData. Creates the singleton GlobalConfig; the payer must be the
+    // program's upgrade authority. Fails once it exists.
+    InitializeGlobalConfig {
+        admin: Pubkey,
+        max_fee_bps: u64,
+        allowed_programs: Vec<Pubkey>,
+        protocol_fee_recipient: Pubkey,
+        protocol_fee_bps: u16,
+    },
+    // Accounts: global config PDA, admin vault, the admin vault's multisig signer (signer),
+    // clock. Replaces every setting, the admin included; the protocol fee totals are kept.
+    UpdateGlobalConfig {
+        admin: Pubkey,
+        max_fee_bps: u64,
+        allowed_programs: Vec<Pubkey>,
+        protocol_fee_recipient: Pubkey,
+        protocol_fee_bps: u16,
+    },
+    // Accounts: global config PDA, admin vault, the admin vault's multisig signer (signer),
+    // clock. Records the upgrade authority the program should have, e.g. after a planned
+    // handover; None turns the check off.
+    AcknowledgeUpgradeAuthority {
+        expected_upgrade_authority: Option<Pubkey>,
+        upgrade_check_threshold: u64,
+    },
+    // Accounts: vault, instructions sysvar, clock. Approves for `owner` without their
+    // signature on the transaction: the instruction right before this one must be an
+    // ed25519 program instruction whose `signature_index`-th signature is by `owner` over
+    // MultiSigTransaction::approval_message. Anyone may submit it.
+    ApproveWithSignature {
+        transaction_id: u64,
+        owner: Pubkey,
+        signature_index: u8,
+    },
+    // Proposer or any multisig owner; pushes back the deadline of a pending transaction
+    // that hasn't expired yet, to no more than MultisigTransactionTtl from now
+    ExtendTransactionExpiry {
+        transaction_id: u64,
+        new_expires_at: i64,
+    },
+    // Multisig owner; same accounts as CreateMultiSigTransaction. Proposes a system transfer
+    // of `amount` lamports from the multisig signer PDA to `recipient`, approved and executed
+    // like any other transaction. The memo only goes into the event log.
+    ProposeSolTransfer {
+        recipient: Pubkey,
+        amount: u64,
+        memo: String,
+    },
+    // Same for an SPL transfer from the multisig signer's associated token account for
+    // `mint` to the recipient's, which has to exist by the time the transaction executes
+    ProposeTokenTransfer {
+        mint: Pubkey,
+        recipient: Pubkey,
+        amount: u64,
+        memo: String,
+    },
+    // Accounts: vault, clock. Anyone may call it. Counts SOL sent straight to the vault
+    // address, less the rent floor and escrowed tips, into tracked_lamports so WithdrawSOL
+    // and Transfer can pay it out.
+    SweepUntrackedLamports,
+    // Beneficiary; moves amount_to_new of lock `id`'s principal, with the same share of what
+    // it has released, into a new lock for new_beneficiary on the same schedule. Refused once
+    // Vault::MAX_OPEN_TIME_LOCKS locks are open. Accounts: vault, beneficiary (signer), clock.
+    SplitTimeLock {
+        id: u64,
+        amount_to_new: u64,
+        new_beneficiary: Pubkey,
+    },
+    // Two-step withdrawals, used instead of Withdraw while ParamKey::WithdrawalDelaySeconds is
+    // set. RequestWithdrawal escrows amount of the free balance under a new ticket owned by
+    // the signer, at most Vault::MAX_WITHDRAWAL_TICKETS_PER_OWNER at a time. Accounts: vault,
+    // owner (signer), clock.
+    RequestWithdrawal {
+        mint: Pubkey,
+        amount: u64,
+    },
+    // From the ticket's available_at on; pays it out like Withdraw and takes Withdraw's
+    // accounts, with the ticket's owner as the signing user
+    ClaimWithdrawal {
+        ticket_id: u64,
+    },
+    // Owner; drops the ticket and releases its reservation. Accounts: vault, owner (signer),
+    // clock.
+    CancelWithdrawalRequest {
+        ticket_id: u64,
+    },
+    // Accounts: vault, clock, then any of the vault's associated token accounts to report
+    // drift for. Anyone may call it, at most once per Vault::HEALTH_CHECK_INTERVAL_SLOTS.
+    EmitHealthCheck,
+    // Authority; Transfer for either asset, and like it exempt from the withdrawal fee.
+    // Accounts as Transfer: vault, recipient, authority (signer), system program, clock.
+    // With a mint, followed by the vault's token account, the recipient's associated token
+    // account and the token program the mint was added under, and with create_ata also a
+    // payer (signer), the mint and the associated token program to create it first.
+    TransferV2 {
+        recipient: Pubkey,
+        mint: Option<Pubkey>,
+        amount: u64,
+        create_ata: bool,
+    },
+    // Re-reads a supported token's mint after a migration (new decimals, or reissued under
+    // Token-2022) and updates its stored decimals and token program. Refused while the vault
+    // tracks a balance of the token unless allow_with_balance is set.
+    // Accounts: vault, authority (signer), mint, clock.
+    RefreshSupportedTokenMetadata {
+        mint: Pubkey,
+        allow_with_balance: bool,
+    },
+    // Claims everything releasable from the lock with this stable id.
+    // Accounts as ClaimTimeLockPartial: vault, vault token account, beneficiary token
+    // account, beneficiary (signer), token program, clock.
+    ClaimTimeLockV2 {
+        time_lock_id: u64,
+    },
+    // Revokes the unvested part of a revocable lock; what has vested stays claimable.
+    // Accounts: vault, authority (signer), clock.
+    CancelTimeLockV2 {
+        time_lock_id: u64,
+    },
+    // Ratifies a proposal that ParamKey::CouncilCategories put before the council. Only
+    // reachable through ExecuteMultiSigTransaction: vault, multisig signer PDA (signer),
+    // clock.
+    CouncilApproveProposal {
+        proposal_id: u64,
+    },
+    // Same accounts as Initialize. Unless skip_setup_gate is set, deposits and withdrawals
+    // fail with VaultNotReady until the authority calls FinalizeSetup.
+    InitializeVaultV2 {
+        index: u16,
+        allow_same_as_authority: bool,
+        skip_setup_gate: bool,
+    },
+    // Accounts: vault, authority (signer), clock. Needs supported tokens and a multisig.
+    // Afterwards InitializeMultiSig can no longer replace the multisig.
+    FinalizeSetup,
+    // Deposit on behalf of another wallet: the user token account is on_behalf_of's and the
+    // signer is its SPL delegate, approved for at least `amount`. Same accounts as a deposit;
+    // the deposit, event and action log are booked to on_behalf_of.
+    DepositAsDelegate {
+        amount: u64,
+        on_behalf_of: Pubkey,
+    },
+    // Permissionless crank: looks at up to max_items of the vault's multisig transactions,
+    // governance proposals and time locks, resuming where the last Tick stopped, and records
+    // the ones that expired, finished voting or fully vested. Proposals with no supply
+    // snapshot finish undetermined, and nothing more is recorded while
+    // Vault::MAX_SETTLED_ITEMS are held. Accounts: vault, clock.
+    Tick {
+        max_items: u16,
+    },
+    // CreateMultiSigTransaction with each account's role spelled out, one per account.
+    // Fails if a MultisigSigner isn't the multisig signer PDA, a VaultPda isn't the vault,
+    // or a role disagrees with is_writable. Same accounts as CreateMultiSigTransaction.
+    CreateMultiSigTransactionV2 {
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        roles: Vec<AccountRole>,
+        data: Vec<u8>,
+    },
+    // CreateMultiSigTransactionV2 with up to BalanceAssertion::MAX_PER_TRANSACTION balance
+    // assertions on the transaction's accounts. Execution fails with AssertionFailed, undoing
+    // the CPI, if one doesn't hold afterwards. Same accounts as CreateMultiSigTransaction.
+    CreateMultiSigTransactionV3 {
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        roles: Vec<AccountRole>,
+        assertions: Vec<BalanceAssertion>,
+        data: Vec<u8>,
+    },
+    // Allocates the proposer's TransactionBuffer PDA for a payload of total_len bytes, at
+    // most MultiSigTransaction::MAX_DATA_LEN. Multisig owners only. Accounts: vault, buffer
+    // (writable), proposer (signer, writable, pays rent), system program, rent.
+    CreateTransactionBuffer {
+        total_len: u32,
+    },
+    // Copies chunk into the buffer at offset, which can't be past what has been written so
+    // far. The proposer must still be a multisig owner. Accounts: vault, buffer (writable),
+    // proposer (signer).
+    WriteTransactionBuffer {
+        offset: u32,
+        chunk: Vec<u8>,
+    },
+    // CreateMultiSigTransaction with the fully written buffer as its data, then closes the
+    // buffer, refunding its rent to the proposer. Accounts: vault (writable), proposer
+    // (signer, writable), clock, buffer (writable).
+    FinalizeTransactionFromBuffer {
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+    },
+    // Closes a buffer without proposing it, refunding its rent to the proposer. The proposer
+    // or the vault authority signs. Accounts: vault, buffer (writable), signer, proposer
+    // (writable).
+    CloseTransactionBuffer,
+    // Authority; sets the mint of a time lock migrated without one, which can't be claimed
+    // until then. mint must be supported. Accounts: vault, authority (signer), clock.
+    AssignTimeLockMint {
+        time_lock_id: u64,
+        mint: Pubkey,
+    },
+    // SetYieldStrategy that also pins accounts, at most StrategyAccounts::MAX_ACCOUNTS: every
+    // deploy, withdraw and emergency exit for the mint must pass exactly these, in order, as
+    // its strategy accounts. SetYieldStrategy pins none. Same accounts as SetYieldStrategy.
+    SetYieldStrategyV2 {
+        token_mint: Pubkey,
+        strategy_program: Pubkey,
+        accounts: Vec<Pubkey>,
+    },
+    // Multisig signer; same accounts as SetParam. Deposit and withdrawal minimums in `mint`'s
+    // base units, which must be a supported mint. They replace MinDepositAmount and
+    // MinWithdrawalAmount for that mint, including 0 to exempt it from them.
+    SetMintMinimums {
+        mint: Pubkey,
+        min_deposit: u64,
+        min_withdrawal: u64,
     },
 }
 
 impl Default for VaultInstruction {
     fn default() -> Self {
-        VaultInstruction::Initialize { bump: 0 }
+        VaultInstruction::Initialize {
+            bump: 0,
+            index: 0,
+            allow_same_as_authority: false,
+        }
     }
 }
@@ -0,0 +1,42 @@
+use solana_program::{msg, pubkey, pubkey::Pubkey};
+
+use crate::VaultError;
+
+// What JupiterRoute is willing to forward: route data for the Jupiter v6 aggregator, which the
+// program passes through as-is as the CPI instruction data
+
+pub const PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+
+/// Longest route accepted. Real v6 routes are a few hundred bytes; the cap keeps a route, its
+/// accounts and the outer instruction within one transaction.
+pub const MAX_ROUTE_LEN: usize = 512;
+
+/// Anchor discriminators (first 8 bytes of sha256("global:<name>")) of the v6 swap
+/// instructions.
+pub const ROUTE_DISCRIMINATORS: &[(&str, [u8; 8])] = &[
+    ("route", [229, 23, 203, 151, 122, 227, 173, 42]),
+    ("route_with_token_ledger", [150, 86, 71, 116, 167, 93, 14, 104]),
+    ("exact_out_route", [208, 51, 239, 151, 123, 43, 237, 92]),
+    ("shared_accounts_route", [193, 32, 155, 51, 65, 214, 156, 129]),
+    ("shared_accounts_route_with_token_ledger", [230, 121, 143, 80, 119, 159, 106, 170]),
+    ("shared_accounts_exact_out_route", [176, 209, 105, 168, 154, 125, 69, 62]),
+];
+
+/// Name of the v6 instruction `route` calls, if its discriminator is a known one.
+pub fn route_instruction(route: &[u8]) -> Option<&'static str> {
+    let discriminator = route.get(..8)?;
+    ROUTE_DISCRIMINATORS
+        .iter()
+        .find(|(_, known)| known[..] == *discriminator)
+        .map(|(name, _)| *name)
+}
+
+/// Checks `route` against the size cap and the known discriminators, returning the name of
+/// the instruction it calls.
+pub fn validate_route(route: &[u8]) -> Result<&'static str, VaultError> {
+    if route.len() > MAX_ROUTE_LEN {
+        msg!("Route is {} bytes, at most {} are accepted", route.len(), MAX_ROUTE_LEN);
+        return Err(VaultError::SwapRouteTooLarge);
+    }
+    route_instruction(route).ok_or(VaultError::UnknownSwapInstruction)
+}
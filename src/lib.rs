@@ -13,8 +13,22 @@ pub mod processor;
 pub mod state;
 pub mod events;
 pub mod protocols;
+pub mod vesting;
+pub mod governance;
+pub mod jupiter;
+pub mod cpi;
+pub mod migration;
+pub mod view;
+pub mod modules;
 
-// Custom error codes for multisig operations
+#[cfg(feature = "state-serde")]
+pub mod serde_helpers;
+
+#[cfg(feature = "client")]
+pub mod client;
+
+// Custom error codes for multisig operations. New variants take the next code and go at the
+// end of VaultError::ALL too.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum VaultError {
     // Multisig errors
@@ -36,8 +50,168 @@ pub enum VaultError {
     InvalidAccountOwner = 13,
     ArithmeticOverflow = 14,
     InvalidAmount = 15,
+    SameSlotWithdrawal = 16,
+    InvalidTimeLock = 17,
+    AccountTooSmall = 18,
+    InvalidEmergencyAdmin = 19,
+    CompromisedTokenAccount = 20,
+    InvalidMint = 21,
+    StateMigrationRequired = 22,
+    ProposalRateLimited = 23,
+    RecoveryNotConfigured = 24,
+    OwnersStillActive = 25,
+    RecoveryNotInitiated = 26,
+    RecoveryChallengeActive = 27,
+    StrategyNotFound = 28,
+    StrategyLossUnacknowledged = 29,
+    StrategyStillDeployed = 30,
+    TransactionCancelled = 31,
+    InvalidSysvar = 32,
+    ExecutionDelayActive = 33,
+    TokenMintMismatch = 34,
+    VaultTokenAccountOwnerMismatch = 35,
+    UserTokenAccountOwnerMismatch = 36,
+    TokenAlreadySupported = 37,
+    TemplateNotFound = 38,
+    InvalidGovernanceInstruction = 39,
+    GovernanceInstructionTooLarge = 40,
+    GovernanceProposalTooLarge = 41,
+    GovernanceProgramNotAllowed = 42,
+    GovernanceNotInitialized = 43,
+    GovernanceProposalNotFound = 44,
+    VotingClosed = 45,
+    AlreadyVoted = 46,
+    GovernanceProposalActive = 47,
+    ProposalNotPassed = 48,
+    VoteLockActive = 49,
+    VoterAlreadyRegistered = 50,
+    VoterNotRegistered = 51,
+    EmergencyCosignRequired = 52,
+    SwapRouteTooLarge = 53,
+    UnknownSwapInstruction = 54,
+    StrategyAllocationExceeded = 55,
+    StrategyNotActive = 56,
+    OwnerJoinedAfterProposal = 57,
+    ProgramNotAllowed = 58,
+    UpgradeAuthorityMismatch = 59,
+    InvalidApprovalSignature = 60,
+    TransactionExpired = 61,
+    WithdrawalRequestRequired = 62,
+    WithdrawalTicketNotFound = 63,
+    WithdrawalNotReady = 64,
+    WithdrawalQueueFull = 65,
+    HealthCheckTooSoon = 66,
+    TokenBalanceOutstanding = 67,
+    DeprecatedInstruction = 68,
+    CouncilApprovalRequired = 69,
+    VaultNotReady = 70,
+    SetupFinalized = 71,
+    DelegationInsufficient = 72,
+    AssertionFailed = 73,
+    InvalidProposalText = 74,
+    VoteLockTooRecent = 75,
+    TimeLockMintUnassigned = 76,
+    CustodyShortfall = 77,
+    UnexpectedReturnData = 78,
+    TimeLockLimitReached = 79,
+    TimeLockNotRevocable = 80,
 }
 
+impl VaultError {
+    /// Every variant, in code order: `ALL[code]` is the error with that custom code.
+    pub const ALL: &'static [VaultError] = &[
+        VaultError::InvalidOwner,
+        VaultError::NotEnoughSigners,
+        VaultError::TransactionAlreadyExecuted,
+        VaultError::TransactionAlreadySigned,
+        VaultError::InvalidThreshold,
+        VaultError::MultisigNotInitialized,
+        VaultError::TransactionNotFound,
+        VaultError::InsufficientAuthority,
+        VaultError::InvalidTransactionData,
+        VaultError::UnauthorizedAccess,
+        VaultError::InvalidInstruction,
+        VaultError::InvalidAccountData,
+        VaultError::AccountNotRentExempt,
+        VaultError::InvalidAccountOwner,
+        VaultError::ArithmeticOverflow,
+        VaultError::InvalidAmount,
+        VaultError::SameSlotWithdrawal,
+        VaultError::InvalidTimeLock,
+        VaultError::AccountTooSmall,
+        VaultError::InvalidEmergencyAdmin,
+        VaultError::CompromisedTokenAccount,
+        VaultError::InvalidMint,
+        VaultError::StateMigrationRequired,
+        VaultError::ProposalRateLimited,
+        VaultError::RecoveryNotConfigured,
+        VaultError::OwnersStillActive,
+        VaultError::RecoveryNotInitiated,
+        VaultError::RecoveryChallengeActive,
+        VaultError::StrategyNotFound,
+        VaultError::StrategyLossUnacknowledged,
+        VaultError::StrategyStillDeployed,
+        VaultError::TransactionCancelled,
+        VaultError::InvalidSysvar,
+        VaultError::ExecutionDelayActive,
+        VaultError::TokenMintMismatch,
+        VaultError::VaultTokenAccountOwnerMismatch,
+        VaultError::UserTokenAccountOwnerMismatch,
+        VaultError::TokenAlreadySupported,
+        VaultError::TemplateNotFound,
+        VaultError::InvalidGovernanceInstruction,
+        VaultError::GovernanceInstructionTooLarge,
+        VaultError::GovernanceProposalTooLarge,
+        VaultError::GovernanceProgramNotAllowed,
+        VaultError::GovernanceNotInitialized,
+        VaultError::GovernanceProposalNotFound,
+        VaultError::VotingClosed,
+        VaultError::AlreadyVoted,
+        VaultError::GovernanceProposalActive,
+        VaultError::ProposalNotPassed,
+        VaultError::VoteLockActive,
+        VaultError::VoterAlreadyRegistered,
+        VaultError::VoterNotRegistered,
+        VaultError::EmergencyCosignRequired,
+        VaultError::SwapRouteTooLarge,
+        VaultError::UnknownSwapInstruction,
+        VaultError::StrategyAllocationExceeded,
+        VaultError::StrategyNotActive,
+        VaultError::OwnerJoinedAfterProposal,
+        VaultError::ProgramNotAllowed,
+        VaultError::UpgradeAuthorityMismatch,
+        VaultError::InvalidApprovalSignature,
+        VaultError::TransactionExpired,
+        VaultError::WithdrawalRequestRequired,
+        VaultError::WithdrawalTicketNotFound,
+        VaultError::WithdrawalNotReady,
+        VaultError::WithdrawalQueueFull,
+        VaultError::HealthCheckTooSoon,
+        VaultError::TokenBalanceOutstanding,
+        VaultError::DeprecatedInstruction,
+        VaultError::CouncilApprovalRequired,
+        VaultError::VaultNotReady,
+        VaultError::SetupFinalized,
+        VaultError::DelegationInsufficient,
+        VaultError::AssertionFailed,
+        VaultError::InvalidProposalText,
+        VaultError::VoteLockTooRecent,
+        VaultError::TimeLockMintUnassigned,
+        VaultError::CustodyShortfall,
+        VaultError::UnexpectedReturnData,
+        VaultError::TimeLockLimitReached,
+        VaultError::TimeLockNotRevocable,
+    ];
+
+    /// The error a custom program error code stands for, e.g. from
+    /// `InstructionError::Custom` in a failed transaction.
+    pub fn from_code(code: u32) -> Option<VaultError> {
+        Self::ALL.get(code as usize).cloned()
+    }
+}
+
+impl std::error::Error for VaultError {}
+
 impl std::fmt::Display for VaultError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -70,6 +244,170 @@ impl std::fmt::Display for VaultError {
             VaultError::InvalidAccountOwner => write!(f, "Invalid account owner"),
             VaultError::ArithmeticOverflow => write!(f, "Arithmetic operation overflow"),
             VaultError::InvalidAmount => write!(f, "Invalid amount specified"),
+            VaultError::SameSlotWithdrawal => {
+                write!(f, "Withdrawal in the same slot as a deposit is not allowed")
+            }
+            VaultError::InvalidTimeLock => write!(f, "Invalid time lock schedule"),
+            VaultError::AccountTooSmall => {
+                write!(f, "Vault account is too small for its serialized state")
+            }
+            VaultError::InvalidEmergencyAdmin => {
+                write!(f, "Emergency admin must be a distinct, non-default key")
+            }
+            VaultError::CompromisedTokenAccount => write!(
+                f,
+                "Vault token account is frozen or has a delegate or close authority set"
+            ),
+            VaultError::InvalidMint => write!(f, "Account is not a supported token mint"),
+            VaultError::StateMigrationRequired => {
+                write!(f, "Vault state uses an old layout, run MigrateVault first")
+            }
+            VaultError::ProposalRateLimited => {
+                write!(f, "Proposer has reached the daily multisig proposal limit")
+            }
+            VaultError::RecoveryNotConfigured => write!(f, "Multisig recovery is not configured"),
+            VaultError::OwnersStillActive => {
+                write!(f, "Owners were active within the inactivity period")
+            }
+            VaultError::RecoveryNotInitiated => write!(f, "No recovery is pending"),
+            VaultError::RecoveryChallengeActive => {
+                write!(f, "Recovery challenge window has not finished")
+            }
+            VaultError::StrategyNotFound => write!(f, "No yield strategy is set for this mint"),
+            VaultError::StrategyLossUnacknowledged => {
+                write!(f, "Strategy realized a loss that the authority has not acknowledged")
+            }
+            VaultError::StrategyStillDeployed => write!(f, "Strategy still holds deployed funds"),
+            VaultError::TransactionCancelled => write!(f, "Multi-sig transaction was cancelled"),
+            VaultError::InvalidSysvar => write!(f, "Account is not the expected sysvar"),
+            VaultError::ExecutionDelayActive => {
+                write!(f, "Multi-sig transaction is still within its execution delay")
+            }
+            VaultError::TokenMintMismatch => {
+                write!(f, "User and vault token accounts hold different mints")
+            }
+            VaultError::VaultTokenAccountOwnerMismatch => {
+                write!(f, "Vault token account is not owned by the vault")
+            }
+            VaultError::UserTokenAccountOwnerMismatch => {
+                write!(f, "User token account is not owned by the signing authority")
+            }
+            VaultError::TokenAlreadySupported => {
+                write!(f, "Token is already registered with this vault")
+            }
+            VaultError::TemplateNotFound => write!(f, "No transaction template with the given ID"),
+            VaultError::InvalidGovernanceInstruction => {
+                write!(f, "Governance instruction does not decode")
+            }
+            VaultError::GovernanceInstructionTooLarge => {
+                write!(f, "Governance instruction exceeds the size or account limit")
+            }
+            VaultError::GovernanceProposalTooLarge => {
+                write!(f, "Governance proposal has too many instructions or bytes")
+            }
+            VaultError::GovernanceProgramNotAllowed => {
+                write!(f, "Governance instruction targets a program proposals may not call")
+            }
+            VaultError::GovernanceNotInitialized => {
+                write!(f, "Governance has not been initialized for this vault")
+            }
+            VaultError::GovernanceProposalNotFound => {
+                write!(f, "No governance proposal with the given ID")
+            }
+            VaultError::VotingClosed => write!(f, "Governance proposal is not open for votes"),
+            VaultError::AlreadyVoted => write!(f, "Voter has already voted on this proposal"),
+            VaultError::GovernanceProposalActive => {
+                write!(f, "A governance proposal is still open for votes")
+            }
+            VaultError::ProposalNotPassed => {
+                write!(f, "Governance proposal missed quorum or its execution threshold")
+            }
+            VaultError::VoteLockActive => write!(f, "Voting tokens are still locked"),
+            VaultError::VoterAlreadyRegistered => write!(f, "Voter is already registered"),
+            VaultError::VoterNotRegistered => write!(f, "Voter is not registered"),
+            VaultError::EmergencyCosignRequired => {
+                write!(f, "Emergency withdrawal needs a multisig owner to co-sign")
+            }
+            VaultError::SwapRouteTooLarge => write!(f, "Swap route exceeds the size limit"),
+            VaultError::UnknownSwapInstruction => {
+                write!(f, "Swap route is not a known Jupiter instruction")
+            }
+            VaultError::StrategyAllocationExceeded => {
+                write!(f, "Deployment exceeds the strategy's allocation cap")
+            }
+            VaultError::StrategyNotActive => {
+                write!(f, "Strategy is paused or exiting")
+            }
+            VaultError::OwnerJoinedAfterProposal => {
+                write!(f, "Owner joined after the transaction was proposed")
+            }
+            VaultError::ProgramNotAllowed => {
+                write!(f, "Program is not in the global config registry")
+            }
+            VaultError::UpgradeAuthorityMismatch => {
+                write!(f, "Program upgrade authority is not the one the global config expects")
+            }
+            VaultError::InvalidApprovalSignature => {
+                write!(f, "No ed25519 signature by the owner over the approval message")
+            }
+            VaultError::TransactionExpired => write!(f, "Multi-sig transaction has expired"),
+            VaultError::WithdrawalRequestRequired => {
+                write!(f, "Vault has a withdrawal delay, withdrawals go through RequestWithdrawal")
+            }
+            VaultError::WithdrawalTicketNotFound => {
+                write!(f, "No withdrawal ticket with the given ID")
+            }
+            VaultError::WithdrawalNotReady => {
+                write!(f, "Withdrawal ticket is still within the withdrawal delay")
+            }
+            VaultError::WithdrawalQueueFull => {
+                write!(f, "Vault holds the maximum number of withdrawal tickets")
+            }
+            VaultError::HealthCheckTooSoon => {
+                write!(f, "Health check already emitted within the heartbeat interval")
+            }
+            VaultError::TokenBalanceOutstanding => {
+                write!(f, "Vault still tracks a balance of this token")
+            }
+            VaultError::DeprecatedInstruction => {
+                write!(f, "Instruction is deprecated; use its V2 replacement")
+            }
+            VaultError::CouncilApprovalRequired => {
+                write!(f, "Governance proposal has not been approved by the council")
+            }
+            VaultError::VaultNotReady => {
+                write!(f, "Vault setup is not finalized; deposits and withdrawals are held")
+            }
+            VaultError::SetupFinalized => {
+                write!(f, "Vault setup is finalized; change it through the multisig")
+            }
+            VaultError::DelegationInsufficient => {
+                write!(f, "Signer is not the token account's delegate for the full amount")
+            }
+            VaultError::AssertionFailed => {
+                write!(f, "A balance assertion failed after the multisig transaction ran")
+            }
+            VaultError::InvalidProposalText => {
+                write!(f, "Proposal title or description contains control characters")
+            }
+            VaultError::VoteLockTooRecent => {
+                write!(f, "Voting tokens were locked after the proposal opened")
+            }
+            VaultError::TimeLockMintUnassigned => {
+                write!(f, "Time lock has no mint assigned")
+            }
+            VaultError::CustodyShortfall => {
+                write!(f, "Vault token account holds less than the vault has booked to it")
+            }
+            VaultError::UnexpectedReturnData => {
+                write!(f, "Return data did not come from a View of this program")
+            }
+            VaultError::TimeLockLimitReached => {
+                write!(f, "Too many open time locks")
+            }
+            VaultError::TimeLockNotRevocable => {
+                write!(f, "Time lock is not revocable")
+            }
         }
     }
 }
@@ -80,6 +418,26 @@ impl From<VaultError> for ProgramError {
     }
 }
 
+/// Hands back the ProgramError unchanged when it isn't one of this program's custom codes.
+impl TryFrom<ProgramError> for VaultError {
+    type Error = ProgramError;
+
+    fn try_from(error: ProgramError) -> Result<Self, Self::Error> {
+        match error {
+            ProgramError::Custom(code) => VaultError::from_code(code).ok_or(error),
+            other => Err(other),
+        }
+    }
+}
+
+// The one way program code builds a ProgramError, so every failure the program raises comes
+// back as a VaultError custom code and never as one the runtime also produces. The only raw
+// ProgramErrors left are MissingRequiredSignature for an absent signature and
+// NotEnoughAccountKeys, which next_account_info returns anyway.
+pub(crate) fn err(e: VaultError) -> ProgramError {
+    e.into()
+}
+
 // reexport
 pub use instruction::*;
 pub use defi::*;
@@ -100,6 +458,19 @@ macro_rules! emit_event {
     };
 }
 
+// debug_msg: msg! that only logs with the `debug-logs` feature. Formatting a pubkey as base58
+// is compute spent on every call, so success-path detail goes through here and release builds
+// log just the instruction name and the EVENT line, which already carries every key and amount.
+// Numbers worth keeping in release go out through sol_log_64 instead.
+#[macro_export]
+macro_rules! debug_msg {
+    ($($arg:tt)+) => {
+        if cfg!(feature = "debug-logs") {
+            solana_program::msg!($($arg)+);
+        }
+    };
+}
+
 // Entry point
 #[cfg(not(feature = "no-entrypoint"))]
 entrypoint!(process_instruction);
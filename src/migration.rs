@@ -0,0 +1,196 @@
+// Frozen copies of older on-chain layouts and their conversions into the current Vault.
+// These structs must never change: they describe bytes already sitting in accounts.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::{
+    EmergencyActionLog, FeeConfig, GovernanceConfig, GovernanceProposal, MultiSig,
+    MultiSigTransaction, Proposal, SupportedToken, TimeLock, TokenBalance, UserActionSlot, Vault,
+    VaultConfig, VestingSchedule, VoteRecord, VoterRegistry, YieldStrategyConfig,
+};
+use crate::{err, VaultError};
+
+// TimeLock before state_version 1, with duration/cliff_duration/claimed_amount alongside
+// the timestamps they were derived from
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct LegacyTimeLockV0 {
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub start_time: i64,
+    pub duration: i64,
+    pub cliff_duration: Option<i64>,
+    pub is_linear: bool,
+    pub claimed_amount: u64,
+    pub end_time: i64,
+    pub cliff_time: i64,
+    pub released_amount: u64,
+}
+
+// YieldStrategyConfig before deployment accounting. SetYieldStrategy didn't store anything
+// back then, so these lists are empty in practice (also in version 1 accounts, which is why
+// those decode with the current type).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct LegacyYieldStrategyConfigV0 {
+    pub token_mint: Pubkey,
+    pub strategy_program: Pubkey,
+    pub auto_compound: bool,
+    pub last_harvested_slot: u64,
+}
+
+// Vault before state_version 1 (no next_time_lock_id or state_version)
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct LegacyVaultV0 {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub emergency_admin: Pubkey,
+    pub paused: bool,
+    pub supported_tokens: Vec<SupportedToken>,
+    pub token_balances: Vec<TokenBalance>,
+    pub time_locks: Vec<LegacyTimeLockV0>,
+    pub proposals: Vec<Proposal>,
+    pub next_proposal_id: u64,
+    pub fee_config: FeeConfig,
+    pub total_value_locked: u64,
+    pub total_fees_collected: u64,
+    pub legacy_mint: Option<Pubkey>,
+    pub legacy_total_deposited: u64,
+    pub governance_config: Option<GovernanceConfig>,
+    pub governance_proposals: Vec<GovernanceProposal>,
+    pub next_governance_proposal_id: u64,
+    pub vote_records: Vec<VoteRecord>,
+    pub voter_registry: Vec<VoterRegistry>,
+    pub multi_sig: Option<MultiSig>,
+    pub multi_sig_transactions: Vec<MultiSigTransaction>,
+    pub yield_strategies: Vec<LegacyYieldStrategyConfigV0>,
+    pub emergency_logs: Vec<EmergencyActionLog>,
+    pub config: VaultConfig,
+    pub user_action_slots: Vec<UserActionSlot>,
+    pub index: u16,
+}
+
+impl LegacyVaultV0 {
+    // Same trailing-bytes tolerance as Vault::load
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = data;
+        Self::deserialize(&mut buf).map_err(|_| err(VaultError::InvalidAccountData))
+    }
+}
+
+// Vault in state_version 1 and 2, up to the TVL and fee counters, which were u64 until
+// version 3. Everything that follows them is laid out as in the current Vault.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct LegacyVaultHeadV2 {
+    pub authority: Pubkey,
+    pub bump: u8,
+    pub emergency_admin: Pubkey,
+    pub paused: bool,
+    pub supported_tokens: Vec<SupportedToken>,
+    pub token_balances: Vec<TokenBalance>,
+    pub time_locks: Vec<TimeLock>,
+    pub proposals: Vec<Proposal>,
+    pub next_proposal_id: u64,
+    pub fee_config: FeeConfig,
+    pub total_value_locked: u64,
+    pub total_fees_collected: u64,
+}
+
+impl LegacyVaultHeadV2 {
+    // Version 1 or 2 account data with both counters widened in place. A little-endian u128
+    // is the u64's bytes followed by eight zero bytes.
+    pub fn widen_counters(data: &[u8]) -> Result<Vec<u8>, ProgramError> {
+        let mut buf = data;
+        Self::deserialize(&mut buf).map_err(|_| err(VaultError::InvalidAccountData))?;
+        let end = data.len() - buf.len();
+        let (tvl_end, fees_end) = (end - 8, end);
+        let mut widened = Vec::with_capacity(data.len() + 16);
+        widened.extend_from_slice(&data[..tvl_end]);
+        widened.extend_from_slice(&[0; 8]);
+        widened.extend_from_slice(&data[tvl_end..fees_end]);
+        widened.extend_from_slice(&[0; 8]);
+        widened.extend_from_slice(&data[fees_end..]);
+        Ok(widened)
+    }
+
+    // A version 1 or 2 vault in the current layout, read as Vault::load_zero_extended reads
+    // it. No version check.
+    pub fn load_vault(data: &[u8]) -> Result<Vault, ProgramError> {
+        Vault::load_zero_extended(&Self::widen_counters(data)?)
+    }
+}
+
+impl From<LegacyVaultV0> for Vault {
+    fn from(legacy: LegacyVaultV0) -> Self {
+        // V0 locks didn't record a mint; it is legacy_mint, or the one supported token if
+        // there is only one. Otherwise it stays default, and the lock can't be claimed until
+        // the authority assigns one with AssignTimeLockMint.
+        let only_mint = match legacy.supported_tokens.as_slice() {
+            [token] => Some(token.mint),
+            _ => None,
+        };
+        let lock_mint = legacy.legacy_mint.or(only_mint).unwrap_or_default();
+        let time_locks: Vec<TimeLock> = legacy
+            .time_locks
+            .into_iter()
+            .enumerate()
+            .map(|(i, lock)| TimeLock {
+                id: i as u64,
+                beneficiary: lock.beneficiary,
+                mint: lock_mint,
+                amount: lock.amount,
+                released_amount: lock.released_amount.max(lock.claimed_amount),
+                start_time: lock.start_time,
+                cliff_time: lock.cliff_time,
+                end_time: lock.end_time,
+                schedule: if lock.is_linear {
+                    VestingSchedule::Linear
+                } else {
+                    VestingSchedule::CliffAll
+                },
+                revocable: false,
+            })
+            .collect();
+
+        Vault {
+            authority: legacy.authority,
+            bump: legacy.bump,
+            emergency_admin: legacy.emergency_admin,
+            paused: legacy.paused,
+            supported_tokens: legacy.supported_tokens,
+            token_balances: legacy.token_balances,
+            next_time_lock_id: time_locks.len() as u64,
+            time_locks,
+            proposals: legacy.proposals,
+            next_proposal_id: legacy.next_proposal_id,
+            fee_config: legacy.fee_config,
+            total_value_locked: u128::from(legacy.total_value_locked),
+            total_fees_collected: u128::from(legacy.total_fees_collected),
+            legacy_mint: legacy.legacy_mint,
+            legacy_total_deposited: legacy.legacy_total_deposited,
+            governance_config: legacy.governance_config,
+            governance_proposals: legacy.governance_proposals,
+            next_governance_proposal_id: legacy.next_governance_proposal_id,
+            vote_records: legacy.vote_records,
+            voter_registry: legacy.voter_registry,
+            multi_sig: legacy.multi_sig,
+            multi_sig_transactions: legacy.multi_sig_transactions,
+            yield_strategies: legacy
+                .yield_strategies
+                .into_iter()
+                .map(|strategy| YieldStrategyConfig {
+                    token_mint: strategy.token_mint,
+                    strategy_program: strategy.strategy_program,
+                    auto_compound: strategy.auto_compound,
+                    last_harvested_slot: strategy.last_harvested_slot,
+                    ..YieldStrategyConfig::default()
+                })
+                .collect(),
+            emergency_logs: legacy.emergency_logs,
+            config: legacy.config,
+            user_action_slots: legacy.user_action_slots,
+            index: legacy.index,
+            state_version: Vault::STATE_VERSION,
+            // Fields added after V1 start out empty, as they do for zero-padded V1 accounts
+            ..Vault::default()
+        }
+    }
+}
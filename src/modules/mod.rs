@@ -1,9 +1 @@
 pub mod utils;
-pub mod token_operations;
-pub mod account_management;
-pub mod access_control;
-pub mod emergency_controls;
-pub mod governance;
-pub mod multisig;
-pub mod time_lock;
-pub mod audit;
@@ -1,27 +1,56 @@
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    ed25519_program,
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     pubkey::Pubkey,
     clock::Clock,
-    program::{invoke_signed, invoke},
+    program::{invoke_signed, invoke, set_return_data},
     instruction::{AccountMeta, Instruction},
+    log::sol_log_64,
     rent::Rent,
-    sysvar::Sysvar,
+    sysvar::{self, Sysvar},
     system_instruction,
     system_program,
     program_pack::Pack,
+    program_option::COption,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
-use spl_token::{instruction as token_instruction, state::Account as TokenAccount};
-use spl_associated_token_account::{instruction as ata_instruction, get_associated_token_address};
+use spl_token::{
+    instruction as token_instruction,
+    state::{Account as TokenAccount, AccountState},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+    instruction as ata_instruction,
+};
 
 use crate::instruction::VaultInstruction;
-use crate::state::{Vault, MultiSig, MultiSigTransaction, FeeConfig, SupportedToken, TokenBalance};
+use crate::state::{
+    Vault, MultiSig, MultiSigTransaction, FeeConfig, SupportedToken, TokenBalance, UserActionSlot,
+    VaultConfig, TimeLock, VestingSchedule, Attestation, EmergencyActionLog, RecoveryConfig,
+    ActionKind, YieldStrategyConfig, ExecutionTip, ThresholdReached, ParamKey, TransactionTemplate,
+    GovernanceInstruction, GovernanceProposal, ProposalSnapshot, VoteRecord, VoteType, VotingModel,
+    VoteLock, VoterRegistry, SwapRecord, StrategyStatus, GlobalConfig, WithdrawalTicket,
+    GovernanceConfig, CouncilReview, SetupStage, SettledItem, SettledKind, AccountRole,
+    TransactionRoles, BalanceAssertion, TransactionAssertions, TransactionBuffer,
+    StrategyAccounts, MintMinimums, program_data_upgrade_authority,
+    TOKEN_2022_PROGRAM_ID,
+};
+use crate::migration::{LegacyVaultHeadV2, LegacyVaultV0};
 use crate::events::*;
-use crate::VaultError;
-use crate::emit_event;
+use crate::{err, VaultError};
+use crate::vesting;
+use crate::governance;
+use crate::jupiter;
+use crate::view::{self, ViewQuery};
+use crate::{debug_msg, emit_event};
+use crate::modules::utils::{
+    load_vault_checked, require_authority, require_multisig_owner, require_not_paused,
+    require_setup_ready, validate_account_ownership,
+};
 
 pub fn process_instruction(
     program_id: &Pubkey,
@@ -29,12 +58,23 @@ pub fn process_instruction(
     instruction_data: &[u8],
 ) -> ProgramResult {
     let instruction = VaultInstruction::try_from_slice(instruction_data)
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
+        .map_err(|_| err(VaultError::InvalidInstruction))?;
 
     match instruction {
-        VaultInstruction::Initialize { bump } => {
+        VaultInstruction::Initialize {
+            bump,
+            index,
+            allow_same_as_authority,
+        } => {
             msg!("Instruction: Initialize Vault");
-            process_initialize(program_id, accounts, bump)
+            process_initialize(
+                program_id,
+                accounts,
+                Some(bump),
+                index,
+                allow_same_as_authority,
+                false,
+            )
         }
         VaultInstruction::Deposite { amount } => {
             msg!("Instruction: Deposit tokens");
@@ -49,7 +89,8 @@ pub fn process_instruction(
             process_withdraw_sol(program_id, accounts, amount)
         }
         VaultInstruction::Transfer { recipient, amount } => {
-            msg!("Instruction: Transfer tokens to {}", recipient);
+            msg!("Instruction: Transfer tokens");
+            debug_msg!("Recipient: {}", recipient);
             process_transfer(program_id, accounts, recipient, amount)
         }
         VaultInstruction::InitializeMultiSig {
@@ -71,6 +112,8 @@ pub fn process_instruction(
                 accounts,
                 target_program_id,
                 transaction_accounts,
+                None,
+                Vec::new(),
                 data,
             )
         }
@@ -118,9 +161,13 @@ pub fn process_instruction(
             msg!("Instruction: Emergency Withdraw");
             process_emergency_withdraw(program_id, accounts, token_mint, amount)
         }
-        VaultInstruction::AddSupportedToken { mint, bump } => {
+        VaultInstruction::AddSupportedToken {
+            mint,
+            bump,
+            idempotent,
+        } => {
             msg!("Instruction: Add Supported Token");
-            process_add_supported_token(program_id, accounts, mint, bump)
+            process_add_supported_token(program_id, accounts, mint, bump, idempotent)
         }
         VaultInstruction::DepositMultiToken { mint, amount } => {
             msg!("Instruction: Deposit Multi Token");
@@ -128,36 +175,40 @@ pub fn process_instruction(
         }
         VaultInstruction::CreateTimeLock {
             beneficiary,
+            mint,
             amount,
             duration,
             cliff_duration,
-            is_linear,
+            schedule,
+            revocable,
         } => {
             msg!("Instruction: Create Time Lock");
             process_create_time_lock(
                 program_id,
                 accounts,
                 beneficiary,
+                mint,
                 amount,
                 duration,
                 cliff_duration,
-                is_linear,
+                schedule,
+                revocable,
             )
         }
-        VaultInstruction::ClaimTimeLock { time_lock_index } => {
-            msg!("Instruction: Claim Time Lock");
-            process_claim_time_lock(program_id, accounts, time_lock_index)
+        VaultInstruction::ClaimTimeLock { .. } => {
+            msg!("ClaimTimeLock is deprecated, use ClaimTimeLockV2 with the lock's id");
+            Err(err(VaultError::DeprecatedInstruction))
         }
-        VaultInstruction::CancelTimeLock { time_lock_index } => {
-            msg!("Instruction: Cancel Time Lock");
-            process_cancel_time_lock(program_id, accounts, time_lock_index)
+        VaultInstruction::CancelTimeLock { .. } => {
+            msg!("CancelTimeLock is deprecated, use CancelTimeLockV2 with the lock's id");
+            Err(err(VaultError::DeprecatedInstruction))
         }
         VaultInstruction::SetYieldStrategy {
             token_mint,
             strategy_program,
         } => {
             msg!("Instruction: Set Yield Strategy");
-            process_set_yield_strategy(program_id, accounts, token_mint, strategy_program)
+            process_set_yield_strategy(program_id, accounts, token_mint, strategy_program, vec![])
         }
         VaultInstruction::HarvestYield { token_mint } => {
             msg!("Instruction: Harvest Yield");
@@ -179,10 +230,19 @@ pub fn process_instruction(
             input_mint,
             output_mint,
             amount,
+            auto_add,
             route,
         } => {
             msg!("Instruction: Jupiter Route");
-            process_jupiter_route(program_id, accounts, input_mint, output_mint, amount, route)
+            process_jupiter_route(
+                program_id,
+                accounts,
+                input_mint,
+                output_mint,
+                amount,
+                auto_add,
+                route,
+            )
         }
         VaultInstruction::CollectFees => {
             msg!("Instruction: Collect Fees");
@@ -251,23 +311,481 @@ pub fn process_instruction(
             voting_period,
             time_lock_delay,
             execution_threshold,
+            voting_model,
+            max_lock_duration,
+            max_boost_bps,
         } => {
             msg!("Instruction: Update Governance Config");
             process_update_governance_config(
                 program_id,
                 accounts,
-                quorum_threshold,
-                proposal_threshold,
-                voting_period,
-                time_lock_delay,
-                execution_threshold,
+                GovernanceConfigUpdate {
+                    quorum_threshold,
+                    proposal_threshold,
+                    voting_period,
+                    time_lock_delay,
+                    execution_threshold,
+                    voting_model,
+                    max_lock_duration,
+                    max_boost_bps,
+                },
+            )
+        }
+        VaultInstruction::UpdateVaultConfig { config } => {
+            msg!("Instruction: Update Vault Config");
+            process_update_vault_config(program_id, accounts, config)
+        }
+        VaultInstruction::MigrateVault => {
+            msg!("Instruction: Migrate Vault");
+            process_migrate_vault(program_id, accounts)
+        }
+        VaultInstruction::SetMultiSigProposalLimit {
+            max_proposals_per_day,
+        } => {
+            msg!("Instruction: Set Multi-Sig Proposal Limit");
+            process_set_multi_sig_proposal_limit(program_id, accounts, max_proposals_per_day)
+        }
+        VaultInstruction::AttestMessage { message_hash } => {
+            msg!("Instruction: Attest Message");
+            process_attest_message(program_id, accounts, message_hash)
+        }
+        VaultInstruction::SetMultiSigRecovery { recovery } => {
+            msg!("Instruction: Set Multi-Sig Recovery");
+            process_set_multi_sig_recovery(program_id, accounts, recovery)
+        }
+        VaultInstruction::InitiateRecovery => {
+            msg!("Instruction: Initiate Recovery");
+            process_initiate_recovery(program_id, accounts)
+        }
+        VaultInstruction::VetoRecovery => {
+            msg!("Instruction: Veto Recovery");
+            process_veto_recovery(program_id, accounts)
+        }
+        VaultInstruction::FinalizeRecovery => {
+            msg!("Instruction: Finalize Recovery");
+            process_finalize_recovery(program_id, accounts)
+        }
+        VaultInstruction::DeployToStrategy {
+            token_mint,
+            amount,
+            strategy_data,
+        } => {
+            msg!("Instruction: Deploy To Strategy");
+            process_deploy_to_strategy(program_id, accounts, token_mint, amount, strategy_data)
+        }
+        VaultInstruction::WithdrawFromStrategy {
+            token_mint,
+            amount,
+            strategy_data,
+        } => {
+            msg!("Instruction: Withdraw From Strategy");
+            process_withdraw_from_strategy(program_id, accounts, token_mint, amount, strategy_data)
+        }
+        VaultInstruction::AcknowledgeStrategyLoss { token_mint } => {
+            msg!("Instruction: Acknowledge Strategy Loss");
+            process_acknowledge_strategy_loss(program_id, accounts, token_mint)
+        }
+        VaultInstruction::FundExecutionTip {
+            transaction_id,
+            lamports,
+        } => {
+            msg!("Instruction: Fund Execution Tip");
+            process_fund_execution_tip(program_id, accounts, transaction_id, lamports)
+        }
+        VaultInstruction::CancelMultiSigTransaction { transaction_id } => {
+            msg!("Instruction: Cancel Multi-Sig Transaction");
+            process_cancel_multi_sig_transaction(program_id, accounts, transaction_id)
+        }
+        VaultInstruction::ExtendPause => {
+            msg!("Instruction: Extend Pause");
+            process_extend_pause(program_id, accounts)
+        }
+        VaultInstruction::SetMaxPauseDuration { max_pause_duration } => {
+            msg!("Instruction: Set Max Pause Duration");
+            process_set_max_pause_duration(program_id, accounts, max_pause_duration)
+        }
+        VaultInstruction::ClaimTimeLockPartial {
+            time_lock_id,
+            amount,
+        } => {
+            msg!("Instruction: Claim Time Lock Partial");
+            process_claim_time_lock_partial(program_id, accounts, time_lock_id, amount)
+        }
+        VaultInstruction::SetStreamConfig {
+            exempt_from_pause,
+            claim_fee_bps,
+        } => {
+            msg!("Instruction: Set Stream Config");
+            process_set_stream_config(program_id, accounts, exempt_from_pause, claim_fee_bps)
+        }
+        VaultInstruction::PruneMultiSigTransactions => {
+            msg!("Instruction: Prune Multi-Sig Transactions");
+            process_prune_multi_sig_transactions(program_id, accounts)
+        }
+        VaultInstruction::SetExecutionDelay {
+            execution_delay_seconds,
+        } => {
+            msg!("Instruction: Set Execution Delay");
+            process_set_execution_delay(program_id, accounts, execution_delay_seconds)
+        }
+        VaultInstruction::ExpediteTransaction { transaction_id } => {
+            msg!("Instruction: Expedite Transaction");
+            process_expedite_transaction(program_id, accounts, transaction_id)
+        }
+        VaultInstruction::SetParam { key, value } => {
+            msg!("Instruction: Set Param");
+            process_set_param(program_id, accounts, key, value)
+        }
+        VaultInstruction::View { query } => {
+            msg!("Instruction: View");
+            process_view(program_id, accounts, query)
+        }
+        VaultInstruction::CreateTemplate {
+            name,
+            program_id: template_program_id,
+            accounts: template_accounts,
+            data_prefix,
+            mutable_ranges,
+        } => {
+            msg!("Instruction: Create Template");
+            let template = TransactionTemplate {
+                id: 0, // assigned on creation
+                name,
+                program_id: template_program_id,
+                accounts: template_accounts,
+                data_prefix,
+                mutable_ranges,
+            };
+            process_create_template(program_id, accounts, template)
+        }
+        VaultInstruction::DeleteTemplate { template_id } => {
+            msg!("Instruction: Delete Template");
+            process_delete_template(program_id, accounts, template_id)
+        }
+        VaultInstruction::CreateTransactionFromTemplate {
+            template_id,
+            fill_bytes,
+        } => {
+            msg!("Instruction: Create Transaction From Template");
+            process_create_transaction_from_template(program_id, accounts, template_id, fill_bytes)
+        }
+        VaultInstruction::DepositWrappedSOL {
+            amount,
+            close_wrapped_account,
+        } => {
+            msg!("Instruction: Deposit Wrapped SOL");
+            process_deposit_wrapped_sol(program_id, accounts, amount, close_wrapped_account)
+        }
+        VaultInstruction::RegisterVoterWithLock {
+            amount,
+            lock_duration,
+        } => {
+            msg!("Instruction: Register Voter With Lock");
+            process_register_voter_with_lock(program_id, accounts, amount, lock_duration)
+        }
+        VaultInstruction::UnregisterVoter => {
+            msg!("Instruction: Unregister Voter");
+            process_unregister_voter(program_id, accounts)
+        }
+        VaultInstruction::InitializeVault {
+            index,
+            allow_same_as_authority,
+        } => {
+            msg!("Instruction: Initialize Vault");
+            process_initialize(program_id, accounts, None, index, allow_same_as_authority, false)
+        }
+        VaultInstruction::UpdateYieldStrategy {
+            token_mint,
+            max_allocation_bps,
+        } => {
+            msg!("Instruction: Update Yield Strategy");
+            process_update_yield_strategy(program_id, accounts, token_mint, max_allocation_bps)
+        }
+        VaultInstruction::SetStrategyStatus { token_mint, status } => {
+            msg!("Instruction: Set Strategy Status");
+            process_set_strategy_status(program_id, accounts, token_mint, status)
+        }
+        VaultInstruction::EmergencyExitStrategy {
+            token_mint,
+            strategy_data,
+        } => {
+            msg!("Instruction: Emergency Exit Strategy");
+            process_emergency_exit_strategy(program_id, accounts, token_mint, strategy_data)
+        }
+        VaultInstruction::InitializeGlobalConfig {
+            admin,
+            max_fee_bps,
+            allowed_programs,
+            protocol_fee_recipient,
+            protocol_fee_bps,
+        } => {
+            msg!("Instruction: Initialize Global Config");
+            let config = GlobalConfig {
+                admin,
+                max_fee_bps,
+                allowed_programs,
+                protocol_fee_recipient,
+                protocol_fee_bps,
+                ..GlobalConfig::default()
+            };
+            process_initialize_global_config(program_id, accounts, config)
+        }
+        VaultInstruction::UpdateGlobalConfig {
+            admin,
+            max_fee_bps,
+            allowed_programs,
+            protocol_fee_recipient,
+            protocol_fee_bps,
+        } => {
+            msg!("Instruction: Update Global Config");
+            let config = GlobalConfig {
+                admin,
+                max_fee_bps,
+                allowed_programs,
+                protocol_fee_recipient,
+                protocol_fee_bps,
+                ..GlobalConfig::default()
+            };
+            process_update_global_config(program_id, accounts, config)
+        }
+        VaultInstruction::AcknowledgeUpgradeAuthority {
+            expected_upgrade_authority,
+            upgrade_check_threshold,
+        } => {
+            msg!("Instruction: Acknowledge Upgrade Authority");
+            process_acknowledge_upgrade_authority(
+                program_id,
+                accounts,
+                expected_upgrade_authority,
+                upgrade_check_threshold,
+            )
+        }
+        VaultInstruction::ApproveWithSignature {
+            transaction_id,
+            owner,
+            signature_index,
+        } => {
+            msg!("Instruction: Approve With Signature");
+            process_approve_with_signature(
+                program_id,
+                accounts,
+                transaction_id,
+                owner,
+                signature_index,
+            )
+        }
+        VaultInstruction::ExtendTransactionExpiry {
+            transaction_id,
+            new_expires_at,
+        } => {
+            msg!("Instruction: Extend Transaction Expiry");
+            process_extend_transaction_expiry(program_id, accounts, transaction_id, new_expires_at)
+        }
+        VaultInstruction::ProposeSolTransfer {
+            recipient,
+            amount,
+            memo,
+        } => {
+            msg!("Instruction: Propose SOL Transfer");
+            process_propose_transfer(program_id, accounts, None, recipient, amount, memo)
+        }
+        VaultInstruction::ProposeTokenTransfer {
+            mint,
+            recipient,
+            amount,
+            memo,
+        } => {
+            msg!("Instruction: Propose Token Transfer");
+            process_propose_transfer(program_id, accounts, Some(mint), recipient, amount, memo)
+        }
+        VaultInstruction::SweepUntrackedLamports => {
+            msg!("Instruction: Sweep Untracked Lamports");
+            process_sweep_untracked_lamports(program_id, accounts)
+        }
+        VaultInstruction::SplitTimeLock {
+            id,
+            amount_to_new,
+            new_beneficiary,
+        } => {
+            msg!("Instruction: Split Time Lock");
+            process_split_time_lock(program_id, accounts, id, amount_to_new, new_beneficiary)
+        }
+        VaultInstruction::RequestWithdrawal { mint, amount } => {
+            msg!("Instruction: Request Withdrawal");
+            process_request_withdrawal(program_id, accounts, mint, amount)
+        }
+        VaultInstruction::ClaimWithdrawal { ticket_id } => {
+            msg!("Instruction: Claim Withdrawal");
+            process_claim_withdrawal(program_id, accounts, ticket_id)
+        }
+        VaultInstruction::CancelWithdrawalRequest { ticket_id } => {
+            msg!("Instruction: Cancel Withdrawal Request");
+            process_cancel_withdrawal_request(program_id, accounts, ticket_id)
+        }
+        VaultInstruction::EmitHealthCheck => {
+            msg!("Instruction: Emit Health Check");
+            process_emit_health_check(program_id, accounts)
+        }
+        VaultInstruction::TransferV2 {
+            recipient,
+            mint,
+            amount,
+            create_ata,
+        } => {
+            msg!("Instruction: Transfer V2");
+            process_transfer_v2(program_id, accounts, recipient, mint, amount, create_ata)
+        }
+        VaultInstruction::RefreshSupportedTokenMetadata {
+            mint,
+            allow_with_balance,
+        } => {
+            msg!("Instruction: Refresh Supported Token Metadata");
+            process_refresh_supported_token_metadata(program_id, accounts, mint, allow_with_balance)
+        }
+        VaultInstruction::ClaimTimeLockV2 { time_lock_id } => {
+            msg!("Instruction: Claim Time Lock V2");
+            process_claim_time_lock(program_id, accounts, time_lock_id)
+        }
+        VaultInstruction::CancelTimeLockV2 { time_lock_id } => {
+            msg!("Instruction: Cancel Time Lock V2");
+            process_cancel_time_lock(program_id, accounts, time_lock_id)
+        }
+        VaultInstruction::CouncilApproveProposal { proposal_id } => {
+            msg!("Instruction: Council Approve Proposal");
+            process_council_approve_proposal(program_id, accounts, proposal_id)
+        }
+        VaultInstruction::InitializeVaultV2 {
+            index,
+            allow_same_as_authority,
+            skip_setup_gate,
+        } => {
+            msg!("Instruction: Initialize Vault V2");
+            process_initialize(
+                program_id,
+                accounts,
+                None,
+                index,
+                allow_same_as_authority,
+                !skip_setup_gate,
+            )
+        }
+        VaultInstruction::FinalizeSetup => {
+            msg!("Instruction: Finalize Setup");
+            process_finalize_setup(program_id, accounts)
+        }
+        VaultInstruction::DepositAsDelegate {
+            amount,
+            on_behalf_of,
+        } => {
+            msg!("Instruction: Deposit As Delegate");
+            process_deposit_as_delegate(program_id, accounts, amount, on_behalf_of)
+        }
+        VaultInstruction::Tick { max_items } => {
+            msg!("Instruction: Tick");
+            process_tick(program_id, accounts, max_items)
+        }
+        VaultInstruction::CreateMultiSigTransactionV2 {
+            program_id: target_program_id,
+            accounts: transaction_accounts,
+            roles,
+            data,
+        } => {
+            msg!("Instruction: Create Multi-Sig Transaction V2");
+            process_create_multi_sig_transaction(
+                program_id,
+                accounts,
+                target_program_id,
+                transaction_accounts,
+                Some(roles),
+                Vec::new(),
+                data,
+            )
+        }
+        VaultInstruction::CreateMultiSigTransactionV3 {
+            program_id: target_program_id,
+            accounts: transaction_accounts,
+            roles,
+            assertions,
+            data,
+        } => {
+            msg!("Instruction: Create Multi-Sig Transaction V3");
+            process_create_multi_sig_transaction(
+                program_id,
+                accounts,
+                target_program_id,
+                transaction_accounts,
+                Some(roles),
+                assertions,
+                data,
+            )
+        }
+        VaultInstruction::CreateTransactionBuffer { total_len } => {
+            msg!("Instruction: Create Transaction Buffer");
+            process_create_transaction_buffer(program_id, accounts, total_len)
+        }
+        VaultInstruction::WriteTransactionBuffer { offset, chunk } => {
+            msg!("Instruction: Write Transaction Buffer");
+            process_write_transaction_buffer(program_id, accounts, offset, chunk)
+        }
+        VaultInstruction::FinalizeTransactionFromBuffer {
+            program_id: target_program_id,
+            accounts: transaction_accounts,
+        } => {
+            msg!("Instruction: Finalize Transaction From Buffer");
+            process_finalize_transaction_from_buffer(
+                program_id,
+                accounts,
+                target_program_id,
+                transaction_accounts,
+            )
+        }
+        VaultInstruction::CloseTransactionBuffer => {
+            msg!("Instruction: Close Transaction Buffer");
+            process_close_transaction_buffer(program_id, accounts)
+        }
+        VaultInstruction::AssignTimeLockMint { time_lock_id, mint } => {
+            msg!("Instruction: Assign Time Lock Mint");
+            process_assign_time_lock_mint(program_id, accounts, time_lock_id, mint)
+        }
+        VaultInstruction::SetYieldStrategyV2 {
+            token_mint,
+            strategy_program,
+            accounts: strategy_accounts,
+        } => {
+            msg!("Instruction: Set Yield Strategy V2");
+            process_set_yield_strategy(
+                program_id,
+                accounts,
+                token_mint,
+                strategy_program,
+                strategy_accounts,
             )
         }
+        VaultInstruction::SetMintMinimums {
+            mint,
+            min_deposit,
+            min_withdrawal,
+        } => {
+            msg!("Instruction: Set Mint Minimums");
+            let minimums = MintMinimums {
+                mint,
+                min_deposit,
+                min_withdrawal,
+            };
+            process_set_mint_minimums(program_id, accounts, minimums)
+        }
     }
 }
 
 // Initialize vault with proper PDA creation and setup
-fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -> ProgramResult {
+fn process_initialize(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    requested_bump: Option<u8>,
+    index: u16,
+    allow_same_as_authority: bool,
+    setup_gate: bool,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
     let authority = next_account_info(account_info_iter)?;
@@ -281,26 +799,38 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != system_program.key {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
-    // Verify PDA derivation
-    let expected_vault_pda =
-        Pubkey::create_program_address(&[b"vault", authority.key.as_ref(), &[bump]], program_id)?;
+    // Still a bare system account, not yet created as a vault
+    validate_account_ownership(vault_account, system_program.key)?;
 
+    // Only the canonical PDA is accepted, so clients deriving with find_program_address
+    // always agree with the stored bump
+    let (expected_vault_pda, bump) = Vault::find_address(program_id, authority.key, index);
     if expected_vault_pda != *vault_account.key {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
+    if let Some(requested) = requested_bump.filter(|b| *b != bump) {
+        msg!("Ignoring bump {}, the canonical bump is {}", requested, bump);
+    }
+    let index_bytes = index.to_le_bytes();
+    let bump_seed = [bump];
+    let mut vault_seeds = Vault::seeds(authority.key, &index_bytes);
+    vault_seeds.push(&bump_seed);
 
     // Check if vault is already initialized
     if !vault_account.data_is_empty() {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
 
+    validate_emergency_admin_key(
+        emergency_admin.key,
+        vault_account.key,
+        authority.key,
+        allow_same_as_authority,
+    )?;
+
     // Get rent exemption amount
-    let rent = Rent::from_account_info(rent_sysvar)?;
-    let vault_size = std::mem::size_of::<Vault>() + 1024; // Extra space for dynamic data
+    let rent = load_rent(rent_sysvar)?;
+    let vault_size = Vault::initial_space()?;
     let required_lamports = rent.minimum_balance(vault_size);
 
     // Transfer lamports to make vault account rent-exempt
@@ -327,7 +857,7 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -
     invoke_signed(
         &allocate_ix,
         &[vault_account.clone(), system_program.clone()],
-        &[&[b"vault", authority.key.as_ref(), &[bump]]],
+        &[&vault_seeds],
     )?;
 
     // Assign ownership to the vault program
@@ -335,15 +865,17 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -
     invoke_signed(
         &assign_ix,
         &[vault_account.clone(), system_program.clone()],
-        &[&[b"vault", authority.key.as_ref(), &[bump]]],
+        &[&vault_seeds],
     )?;
 
     // Initialize vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
+    let clock = load_clock(clock_sysvar)?;
     let mut vault = Vault::default();
     vault.authority = *authority.key;
     vault.emergency_admin = *emergency_admin.key;
     vault.bump = bump;
+    vault.index = index;
+    vault.state_version = Vault::STATE_VERSION;
     vault.paused = false;
     vault.fee_config = FeeConfig {
         deposit_fee_bps: 0,
@@ -352,9 +884,19 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -
     };
     vault.total_value_locked = 0;
     vault.total_fees_collected = 0;
+    vault.tvl_last_updated = clock.unix_timestamp;
+    vault.setup_gate = setup_gate;
+
+    vault.log_action(
+        ActionKind::Initialize,
+        *authority.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
 
     // Serialize vault state
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
     // Emit initialization event
     let init_event = VaultInitializedEvent {
@@ -366,20 +908,151 @@ fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo], bump: u8) -
         ),
         bump,
         emergency_admin: *emergency_admin.key,
+        index,
     };
     emit_event!(init_event, init_event);
 
-    msg!(
-        "Vault initialized successfully with PDA: {}",
+    debug_msg!(
+        "Vault {} initialized successfully with PDA: {}",
+        index,
         vault_account.key
     );
-    msg!("Authority: {}", authority.key);
-    msg!("Emergency Admin: {}", emergency_admin.key);
+    debug_msg!("Authority: {}", authority.key);
+    debug_msg!("Emergency Admin: {}", emergency_admin.key);
+    if setup_gate {
+        msg!("Deposits and withdrawals open once setup is finalized");
+    }
+
+    Ok(())
+}
+
+fn process_finalize_setup(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    vault.refresh_setup_stage();
+    match vault.setup_stage {
+        SetupStage::Ready => return Err(err(VaultError::SetupFinalized)),
+        SetupStage::MultisigConfigured => {}
+        stage => {
+            msg!("Setup is at {:?}; it needs a supported token and a multisig", stage);
+            return Err(err(VaultError::VaultNotReady));
+        }
+    }
+    vault.setup_stage = SetupStage::Ready;
+
+    vault.log_action(
+        ActionKind::SetupFinalized,
+        *authority.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = SetupFinalizedEvent {
+        base: create_base_event(*vault_account.key, *authority.key, "setup_finalized", &clock),
+        supported_tokens: vault.supported_tokens.len() as u32,
+        setup_gate: vault.setup_gate,
+    };
+    emit_event!(event, event);
+
+    msg!("Vault setup finalized");
+    Ok(())
+}
+
+// Anyone may call it. Items already settled are skipped, so repeated calls only record what has
+// become due since.
+fn process_tick(program_id: &Pubkey, accounts: &[AccountInfo], max_items: u16) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
+    if max_items == 0 || max_items > Vault::MAX_TICK_ITEMS {
+        msg!("Tick takes 1..={} items", Vault::MAX_TICK_ITEMS);
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    vault.prune_settled_items();
+    let total = vault.tick_items();
+    let scanned = total.min(max_items as usize);
+    let start = vault.tick_cursor as usize;
+    let mut event = TickEvent {
+        base: create_base_event(*vault_account.key, Pubkey::default(), "tick", &clock),
+        scanned: 0,
+        transactions_expired: 0,
+        proposals_passed: 0,
+        proposals_defeated: 0,
+        time_locks_vested: 0,
+        proposals_undetermined: 0,
+    };
+    // A full list stops the scan on the item that didn't fit, so the next Tick starts there
+    let mut steps = 0;
+    while steps < scanned {
+        let position = (start + steps) % total;
+        if let Some((kind, id)) = vault.due_settlement(position, clock.unix_timestamp) {
+            let settled_len = vault.settled_items.len();
+            if settled_len >= Vault::MAX_SETTLED_ITEMS {
+                msg!("{} items settled already, stopping at {}", settled_len, position);
+                break;
+            }
+            let count = match kind {
+                SettledKind::TransactionExpired => &mut event.transactions_expired,
+                SettledKind::ProposalPassed => &mut event.proposals_passed,
+                SettledKind::ProposalDefeated => &mut event.proposals_defeated,
+                SettledKind::TimeLockVested => &mut event.time_locks_vested,
+                SettledKind::ProposalUndetermined => &mut event.proposals_undetermined,
+            };
+            *count += 1;
+            vault.settled_items.push(SettledItem {
+                kind,
+                id,
+                settled_at: clock.unix_timestamp,
+            });
+        }
+        steps += 1;
+    }
+    event.scanned = steps as u32;
+    vault.tick_cursor = if total == 0 { 0 } else { ((start + steps) % total) as u32 };
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    emit_event!(event, event);
     Ok(())
 }
 
 fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    deposit_tokens(program_id, accounts, amount, None)
+}
+
+// The signer is the SPL delegate of on_behalf_of's token account, approved for at least
+// `amount`; the deposit is booked to on_behalf_of
+fn process_deposit_as_delegate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    on_behalf_of: Pubkey,
+) -> ProgramResult {
+    deposit_tokens(program_id, accounts, amount, Some(on_behalf_of))
+}
+
+// Deposit and DepositAsDelegate. `user_authority` signs the transfer, as the token account's
+// owner or, with `on_behalf_of`, its delegate.
+fn deposit_tokens(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    on_behalf_of: Option<Pubkey>,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
     let user_token_account = next_account_info(account_info_iter)?;
@@ -393,59 +1066,70 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != program_id {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
-    if *token_program.key != spl_token::ID {
-        return Err(VaultError::InvalidAccountData.into());
+    if !is_token_program(token_program.key) {
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     // Load vault state
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_setup_ready(&vault)?;
 
     // Check if vault is paused
-    if vault.paused {
-        return Err(VaultError::UnauthorizedAccess.into());
-    }
-
-    // Get token mint from user's token account
-    let user_token_data = user_token_account.data.borrow();
-    let user_token = TokenAccount::unpack(&user_token_data)?;
-    let token_mint = user_token.mint;
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, user_authority.key, &clock)?;
 
-    // Check if token is supported
-    let supported_token = vault
-        .supported_tokens
-        .iter()
-        .find(|t| t.mint == token_mint && t.is_active);
-    if supported_token.is_none() {
-        return Err(VaultError::InvalidAccountData.into());
-    }
-
-    // Verify vault token account belongs to vault
-    let expected_vault_token_account = get_associated_token_address(vault_account.key, &token_mint);
-    if expected_vault_token_account != *vault_token_account.key {
-        return Err(VaultError::InvalidAccountData.into());
+    let depositor = on_behalf_of.unwrap_or(*user_authority.key);
+    let token_mint = validate_token_account_pair(
+        &vault,
+        vault_account.key,
+        token_program.key,
+        vault_token_account,
+        user_token_account,
+        &depositor,
+    )?;
+    if on_behalf_of.is_some() {
+        validate_delegation(user_token_account, user_authority.key, amount)?;
     }
+    let mut fee_routing = load_fee_routing(program_id, &vault, &token_mint, account_info_iter)?;
+    validate_amount(amount, vault.min_deposit(&token_mint))?;
 
     // Calculate fees
     let deposit_fee = if amount > 0 {
-        (amount as u128 * vault.fee_config.deposit_fee_bps as u128 / 10000) as u64
+        vault.fee_for(ParamKey::DepositFeeBps, amount)
     } else {
         0
     };
     let net_deposit_amount = amount - deposit_fee;
 
+    // Routed, the fee leaves the depositor for the two recipients; otherwise it reaches the
+    // vault with the deposit and is set aside in accrued_fees
+    let (vault_fee, protocol_fee) = match fee_routing.as_mut() {
+        Some(routing) => route_fee(
+            routing,
+            &vault,
+            user_token_account,
+            user_authority,
+            token_program,
+            &token_mint,
+            deposit_fee,
+            false,
+            &clock,
+        )?,
+        None => (deposit_fee, 0),
+    };
+    let received = if fee_routing.is_some() {
+        net_deposit_amount
+    } else {
+        amount
+    };
+
     // Perform token transfer
-    let transfer_ix = token_instruction::transfer(
+    let transfer_ix = transfer_instruction(
         token_program.key,
         user_token_account.key,
         vault_token_account.key,
         user_authority.key,
-        &[],
-        net_deposit_amount,
+        received,
     )?;
 
     invoke(
@@ -458,17 +1142,8 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         ],
     )?;
 
-    // Update vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
-
     // Update supported token totals
-    if let Some(supported_token) = vault
-        .supported_tokens
-        .iter_mut()
-        .find(|t| t.mint == token_mint)
-    {
-        supported_token.total_deposited += net_deposit_amount;
-    }
+    update_supported_token_totals(&mut vault, &token_mint, net_deposit_amount, 0);
 
     // Update token balance
     let balance_index = vault
@@ -476,7 +1151,10 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         .iter()
         .position(|b| b.mint == token_mint);
     if let Some(index) = balance_index {
-        vault.token_balances[index].balance += net_deposit_amount;
+        let balance = &mut vault.token_balances[index].balance;
+        *balance = balance
+            .checked_add(net_deposit_amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
         vault.token_balances[index].last_updated = clock.unix_timestamp;
     } else {
         vault.token_balances.push(TokenBalance {
@@ -486,41 +1164,192 @@ fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -
         });
     }
 
+    // Remember the deposit slot so a same-slot withdrawal can be refused
+    if vault.config.same_slot_guard {
+        record_user_action_slot(&mut vault, &depositor, clock.slot);
+    }
+
     // Update total value locked and fees
-    vault.total_value_locked += net_deposit_amount;
-    vault.total_fees_collected += deposit_fee;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_add(u128::from(net_deposit_amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.total_fees_collected = vault
+        .total_fees_collected
+        .checked_add(u128::from(deposit_fee))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    if fee_routing.is_none() {
+        vault.accrue_fee(token_mint, deposit_fee, clock.unix_timestamp);
+    }
+
+    vault.log_action(
+        ActionKind::Deposit,
+        depositor,
+        token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
 
     // Serialize updated vault state
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
     // Emit deposit event
     let deposit_event = TokenDepositedEvent {
-        base: create_base_event(
-            *vault_account.key,
-            *user_authority.key,
-            "token_deposited",
-            &clock,
-        ),
+        base: create_base_event(*vault_account.key, depositor, "token_deposited", &clock),
         token_mint,
         amount: net_deposit_amount,
         fee_amount: deposit_fee,
-        depositor: *user_authority.key,
+        depositor,
+        vault_fee_amount: vault_fee,
+        protocol_fee_amount: protocol_fee,
+        resulting_balance: vault.tracked_balance(&token_mint),
+        resulting_tvl: vault.total_value_locked,
+        accrued_fees_after: vault.accrued_fee(&token_mint),
     };
     emit_event!(deposit_event, deposit_event);
 
-    msg!(
+    debug_msg!(
         "Successfully deposited {} tokens (fee: {}) to vault",
         net_deposit_amount,
         deposit_fee
     );
-    msg!("Token mint: {}", token_mint);
-    msg!("Depositor: {}", user_authority.key);
+    debug_msg!("Token mint: {}", token_mint);
+    debug_msg!("Depositor: {}", depositor);
+
+    Ok(())
+}
+
+// Accounts: vault, depositor wSOL ATA, vault wSOL ATA, depositor, native mint, associated
+// token program, token program, system program, clock. The deposit itself is the regular
+// token path, so fees, tracking and the event are those of a native mint deposit.
+fn process_deposit_wrapped_sol(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    close_wrapped_account: bool,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let user_wsol_account = next_account_info(account_info_iter)?;
+    let vault_wsol_account = next_account_info(account_info_iter)?;
+    let depositor = next_account_info(account_info_iter)?;
+    let native_mint = next_account_info(account_info_iter)?;
+    let associated_token_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !depositor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Refused before any SOL is wrapped, rather than only by the deposit it hands off to
+    require_setup_ready(&load_vault_checked(vault_account, program_id)?)?;
+
+    if *native_mint.key != spl_token::native_mint::ID {
+        return Err(err(VaultError::InvalidMint));
+    }
+
+    if *token_program.key != spl_token::ID
+        || *associated_token_program.key != spl_associated_token_account::ID
+        || *system_program_account.key != system_program::ID
+    {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    if *user_wsol_account.key != get_associated_token_address(depositor.key, native_mint.key) {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let create_ata_ix = ata_instruction::create_associated_token_account_idempotent(
+        depositor.key,
+        depositor.key,
+        native_mint.key,
+        token_program.key,
+    );
+    invoke(
+        &create_ata_ix,
+        &[
+            depositor.clone(),
+            user_wsol_account.clone(),
+            native_mint.clone(),
+            system_program_account.clone(),
+            token_program.clone(),
+            associated_token_program.clone(),
+        ],
+    )?;
+
+    invoke(
+        &system_instruction::transfer(depositor.key, user_wsol_account.key, amount),
+        &[
+            depositor.clone(),
+            user_wsol_account.clone(),
+            system_program_account.clone(),
+        ],
+    )?;
+    invoke(
+        &token_instruction::sync_native(token_program.key, user_wsol_account.key)?,
+        &[user_wsol_account.clone(), token_program.clone()],
+    )?;
+
+    process_deposit(
+        program_id,
+        &[
+            vault_account.clone(),
+            user_wsol_account.clone(),
+            vault_wsol_account.clone(),
+            depositor.clone(),
+            token_program.clone(),
+            clock_sysvar.clone(),
+        ],
+        amount,
+    )?;
+
+    if close_wrapped_account {
+        let close_ix = token_instruction::close_account(
+            token_program.key,
+            user_wsol_account.key,
+            depositor.key,
+            depositor.key,
+            &[],
+        )?;
+        invoke(
+            &close_ix,
+            &[user_wsol_account.clone(), depositor.clone(), token_program.clone()],
+        )?;
+    }
 
     Ok(())
 }
 
 fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    validate_amount(amount, 0)?;
+    withdraw_tokens(program_id, accounts, Withdrawal::Instant(amount))
+}
+
+fn process_claim_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ticket_id: u64,
+) -> ProgramResult {
+    withdraw_tokens(program_id, accounts, Withdrawal::Claim(ticket_id))
+}
+
+// Instant withdrawals name their amount; a claim pays out a matured withdrawal ticket
+enum Withdrawal {
+    Instant(u64),
+    Claim(u64),
+}
+
+// Withdraw and ClaimWithdrawal share accounts and everything after the amount is known
+fn withdraw_tokens(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    withdrawal: Withdrawal,
+) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
     let vault_token_account = next_account_info(account_info_iter)?;
@@ -534,76 +1363,123 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64)
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != program_id {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
-    if *token_program.key != spl_token::ID {
-        return Err(VaultError::InvalidAccountData.into());
+    if !is_token_program(token_program.key) {
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     // Load vault state
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_setup_ready(&vault)?;
 
     // Check if vault is paused
-    if vault.paused {
-        return Err(VaultError::UnauthorizedAccess.into());
-    }
-
-    // Get token mint from vault's token account
-    let vault_token_data = vault_token_account.data.borrow();
-    let vault_token = TokenAccount::unpack(&vault_token_data)?;
-    let token_mint = vault_token.mint;
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, user_authority.key, &clock)?;
 
-    // Check if token is supported
-    let supported_token = vault
-        .supported_tokens
-        .iter()
-        .find(|t| t.mint == token_mint && t.is_active);
-    if supported_token.is_none() {
-        return Err(VaultError::InvalidAccountData.into());
-    }
+    let token_mint = validate_token_account_pair(
+        &vault,
+        vault_account.key,
+        token_program.key,
+        vault_token_account,
+        user_token_account,
+        user_authority.key,
+    )?;
 
     // Verify user token account belongs to user
-    let expected_user_token_account = get_associated_token_address(user_authority.key, &token_mint);
+    let expected_user_token_account = get_associated_token_address_with_program_id(
+        user_authority.key,
+        &token_mint,
+        token_program.key,
+    );
     if expected_user_token_account != *user_token_account.key {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
 
-    // Check vault balance
-    let vault_balance = vault
-        .token_balances
-        .iter()
-        .find(|b| b.mint == token_mint)
-        .map(|b| b.balance)
-        .unwrap_or(0);
+    let (amount, ticket_id) = match withdrawal {
+        Withdrawal::Instant(amount) => {
+            let delay = vault.param(ParamKey::WithdrawalDelaySeconds);
+            if delay > 0 {
+                msg!("Withdrawals take {}s here, start one with RequestWithdrawal", delay);
+                return Err(err(VaultError::WithdrawalRequestRequired));
+            }
+            validate_amount(amount, vault.min_withdrawal(&token_mint))?;
+            (amount, None)
+        }
+        Withdrawal::Claim(ticket_id) => {
+            let index = vault
+                .withdrawal_ticket_index(ticket_id)
+                .ok_or(VaultError::WithdrawalTicketNotFound)?;
+            let ticket = &vault.withdrawal_tickets[index];
+            if ticket.owner != *user_authority.key {
+                return Err(err(VaultError::UnauthorizedAccess));
+            }
+            if ticket.mint != token_mint {
+                return Err(err(VaultError::InvalidMint));
+            }
+            if clock.unix_timestamp < ticket.available_at {
+                msg!("Withdrawal ticket {} is claimable at {}", ticket_id, ticket.available_at);
+                return Err(err(VaultError::WithdrawalNotReady));
+            }
+            // Paid out of its escrow, which already left the tracked balance and TVL
+            (vault.withdrawal_tickets.remove(index).amount, Some(ticket_id))
+        }
+    };
+
+    // The GlobalConfig is required even before it exists, so leaving it out can't skip the
+    // upgrade authority check. Routing accounts follow unless the ProgramData comes next.
+    let config_account = next_account_info(account_info_iter)?;
+    let config = load_created_global_config(program_id, config_account)?;
+    let program_data_key = bpf_loader_upgradeable::get_program_data_address(program_id);
+    let routes_fee = account_info_iter
+        .as_slice()
+        .first()
+        .map_or(false, |next| *next.key != program_data_key);
+    let mut fee_routing = match &config {
+        Some(config) if routes_fee => Some(load_fee_routing_accounts(
+            &vault,
+            &token_mint,
+            config_account,
+            config.clone(),
+            account_info_iter,
+        )?),
+        _ => None,
+    };
+    if let Some(config) = &config {
+        if config.requires_upgrade_check(Some(amount)) {
+            verify_upgrade_authority(program_id, config, account_info_iter.next())?;
+        }
+    }
+
+    if ticket_id.is_none() {
+        validate_free_balance(&vault, &token_mint, amount)?;
+    }
 
-    if vault_balance < amount {
-        return Err(VaultError::InvalidAmount.into());
+    // Deposit-and-withdraw within one slot is refused when the guard is enabled
+    if vault.config.same_slot_guard {
+        if deposited_in_slot(&vault, user_authority.key, clock.slot) {
+            return Err(err(VaultError::SameSlotWithdrawal));
+        }
     }
 
     // Calculate fees
     let withdrawal_fee = if amount > 0 {
-        (amount as u128 * vault.fee_config.withdrawal_fee_bps as u128 / 10000) as u64
+        vault.fee_for(ParamKey::WithdrawalFeeBps, amount)
     } else {
         0
     };
     let net_withdrawal_amount = amount - withdrawal_fee;
 
     // Perform token transfer from vault to user
-    let transfer_ix = token_instruction::transfer(
+    let transfer_ix = transfer_instruction(
         token_program.key,
         vault_token_account.key,
         user_token_account.key,
         vault_account.key, // Vault is the authority for its token account
-        &[],
         net_withdrawal_amount,
     )?;
 
-    // Use invoke_signed since vault is a PDA
-    let vault_seeds = &[b"vault", vault.authority.as_ref(), &[vault.bump]];
-    invoke_signed(
+    // Vault is a PDA, so it signs with its own seeds
+    invoke_signed_by_vault(
+        &vault,
         &transfer_ix,
         &[
             vault_token_account.clone(),
@@ -611,38 +1487,78 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64)
             vault_account.clone(),
             token_program.clone(),
         ],
-        &[vault_seeds],
     )?;
 
-    // Update vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
-
+    // Routed, the fee leaves the vault too; otherwise it stays behind as an accrued fee
+    let (vault_fee, protocol_fee) = match fee_routing.as_mut() {
+        Some(routing) => route_fee(
+            routing,
+            &vault,
+            vault_token_account,
+            vault_account,
+            token_program,
+            &token_mint,
+            withdrawal_fee,
+            true,
+            &clock,
+        )?,
+        None => (withdrawal_fee, 0),
+    };
     // Update supported token totals
-    if let Some(supported_token) = vault
-        .supported_tokens
-        .iter_mut()
-        .find(|t| t.mint == token_mint)
-    {
-        supported_token.total_withdrawn += net_withdrawal_amount;
-    }
+    update_supported_token_totals(&mut vault, &token_mint, 0, net_withdrawal_amount);
+    // The circuit breaker measures a claim against the balance that still held its escrow
+    let balance_before = match ticket_id {
+        Some(_) => vault.tracked_balance(&token_mint).saturating_add(amount),
+        None => vault.tracked_balance(&token_mint),
+    };
 
-    // Update token balance
-    if let Some(balance) = vault
-        .token_balances
-        .iter_mut()
-        .find(|b| b.mint == token_mint)
-    {
-        balance.balance -= net_withdrawal_amount;
-        balance.last_updated = clock.unix_timestamp;
+    // Update token balance and total value locked, unless a claim's escrow already did
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    if ticket_id.is_none() {
+        if let Some(balance) = vault
+            .token_balances
+            .iter_mut()
+            .find(|b| b.mint == token_mint)
+        {
+            balance.balance = balance
+                .balance
+                .checked_sub(amount)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            balance.last_updated = clock.unix_timestamp;
+        }
+        vault.total_value_locked = vault
+            .total_value_locked
+            .checked_sub(u128::from(amount))
+            .ok_or(VaultError::ArithmeticOverflow)?;
     }
+    vault.total_fees_collected = vault
+        .total_fees_collected
+        .checked_add(u128::from(withdrawal_fee))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    if fee_routing.is_none() {
+        vault.accrue_fee(token_mint, withdrawal_fee, clock.unix_timestamp);
+    }
+    require_custody_covered(&vault, &token_mint, vault_token_account)?;
 
-    // Update total value locked and fees
-    vault.total_value_locked -= net_withdrawal_amount;
-    vault.total_fees_collected += withdrawal_fee;
+    vault.log_action(
+        ActionKind::Withdraw,
+        *user_authority.key,
+        token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
+    check_circuit_breaker(
+        &mut vault,
+        vault_account.key,
+        user_authority.key,
+        token_mint,
+        amount,
+        balance_before,
+        &clock,
+    );
 
     // Serialize updated vault state
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
     // Emit withdrawal event
     let withdrawal_event = TokenWithdrawnEvent {
@@ -656,110 +1572,317 @@ fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64)
         amount: net_withdrawal_amount,
         fee_amount: withdrawal_fee,
         recipient: *user_authority.key,
+        vault_fee_amount: vault_fee,
+        protocol_fee_amount: protocol_fee,
+        resulting_balance: vault.tracked_balance(&token_mint),
+        resulting_tvl: vault.total_value_locked,
+        accrued_fees_after: vault.accrued_fee(&token_mint),
     };
     emit_event!(withdrawal_event, withdrawal_event);
 
-    msg!(
+    if let Some(ticket_id) = ticket_id {
+        let claimed_event = WithdrawalClaimedEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *user_authority.key,
+                "withdrawal_claimed",
+                &clock,
+            ),
+            ticket_id,
+            token_mint,
+            amount,
+        };
+        emit_event!(claimed_event, claimed_event);
+    }
+
+    debug_msg!(
         "Successfully withdrew {} tokens (fee: {}) from vault",
         net_withdrawal_amount,
         withdrawal_fee
     );
-    msg!("Token mint: {}", token_mint);
-    msg!("Recipient: {}", user_authority.key);
+    debug_msg!("Token mint: {}", token_mint);
+    debug_msg!("Recipient: {}", user_authority.key);
+
+    Ok(())
+}
+
+fn process_request_withdrawal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let owner = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_setup_ready(&vault)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, owner.key, &clock)?;
+
+    let delay = vault.param(ParamKey::WithdrawalDelaySeconds);
+    if delay == 0 {
+        msg!("No withdrawal delay is set, use Withdraw");
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    validate_token_supported(&vault, &mint)?;
+    validate_amount(amount, vault.min_withdrawal(&mint))?;
+    validate_free_balance(&vault, &mint, amount)?;
+
+    if vault.config.same_slot_guard && deposited_in_slot(&vault, owner.key, clock.slot) {
+        return Err(err(VaultError::SameSlotWithdrawal));
+    }
+
+    if vault.withdrawal_tickets.len() >= Vault::MAX_WITHDRAWAL_TICKETS {
+        msg!("Vault already holds {} withdrawal tickets", Vault::MAX_WITHDRAWAL_TICKETS);
+        return Err(err(VaultError::WithdrawalQueueFull));
+    }
+    if vault.withdrawal_tickets_of(owner.key) >= Vault::MAX_WITHDRAWAL_TICKETS_PER_OWNER {
+        msg!(
+            "{} already holds {} withdrawal tickets",
+            owner.key,
+            Vault::MAX_WITHDRAWAL_TICKETS_PER_OWNER
+        );
+        return Err(err(VaultError::WithdrawalQueueFull));
+    }
+
+    let ticket = WithdrawalTicket {
+        id: vault.next_withdrawal_ticket_id,
+        owner: *owner.key,
+        mint,
+        amount,
+        requested_at: clock.unix_timestamp,
+        available_at: clock.unix_timestamp.saturating_add(delay as i64),
+    };
+    vault.next_withdrawal_ticket_id = vault
+        .next_withdrawal_ticket_id
+        .checked_add(1)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.withdrawal_tickets.push(ticket.clone());
+
+    // Escrowed out of the tracked balance, so nothing that runs before the claim can spend it
+    escrow_withdrawal(&mut vault, &mint, amount, true, clock.unix_timestamp)?;
+
+    vault.log_action(
+        ActionKind::WithdrawalRequested,
+        *owner.key,
+        mint,
+        amount,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let requested_event = WithdrawalRequestedEvent {
+        base: create_base_event(*vault_account.key, *owner.key, "withdrawal_requested", &clock),
+        ticket_id: ticket.id,
+        token_mint: mint,
+        amount,
+        available_at: ticket.available_at,
+    };
+    emit_event!(requested_event, requested_event);
+
+    msg!(
+        "Withdrawal ticket {} for {} tokens, claimable at {}",
+        ticket.id,
+        amount,
+        ticket.available_at
+    );
+    Ok(())
+}
+
+// Open to the owner at any time, paused or not: it only hands the amount back to the free
+// balance
+fn process_cancel_withdrawal_request(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ticket_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let owner = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    let index = vault
+        .withdrawal_ticket_index(ticket_id)
+        .ok_or(VaultError::WithdrawalTicketNotFound)?;
+    if vault.withdrawal_tickets[index].owner != *owner.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    let ticket = vault.withdrawal_tickets.remove(index);
+
+    let clock = load_clock(clock_sysvar)?;
+    escrow_withdrawal(&mut vault, &ticket.mint, ticket.amount, false, clock.unix_timestamp)?;
+    vault.log_action(
+        ActionKind::WithdrawalCancelled,
+        *owner.key,
+        ticket.mint,
+        ticket.amount,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let cancelled_event = WithdrawalRequestCancelledEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *owner.key,
+            "withdrawal_request_cancelled",
+            &clock,
+        ),
+        ticket_id,
+        token_mint: ticket.mint,
+        amount: ticket.amount,
+    };
+    emit_event!(cancelled_event, cancelled_event);
+
+    msg!("Withdrawal ticket {} cancelled, {} tokens released", ticket_id, ticket.amount);
+    Ok(())
+}
 
+// Moves a withdrawal request's amount out of the mint's tracked balance and TVL, or back in
+// when it is cancelled
+fn escrow_withdrawal(
+    vault: &mut Vault,
+    mint: &Pubkey,
+    amount: u64,
+    into_escrow: bool,
+    now: i64,
+) -> ProgramResult {
+    vault.accrue_tvl(now)?;
+    let balance = vault
+        .token_balances
+        .iter_mut()
+        .find(|b| b.mint == *mint)
+        .ok_or(VaultError::InvalidMint)?;
+    let (tracked, tvl) = if into_escrow {
+        (
+            balance.balance.checked_sub(amount),
+            vault.total_value_locked.checked_sub(u128::from(amount)),
+        )
+    } else {
+        (
+            balance.balance.checked_add(amount),
+            vault.total_value_locked.checked_add(u128::from(amount)),
+        )
+    };
+    balance.balance = tracked.ok_or(VaultError::ArithmeticOverflow)?;
+    balance.last_updated = now;
+    vault.total_value_locked = tvl.ok_or(VaultError::ArithmeticOverflow)?;
     Ok(())
 }
 
 fn process_withdraw_sol(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
     let recipient = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
     let system_program = next_account_info(account_info_iter)?;
     let clock_sysvar = next_account_info(account_info_iter)?;
 
     // Validate accounts
-    if vault_account.owner != program_id {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
     if *system_program.key != system_program::ID {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     // Load vault state
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_setup_ready(&vault)?;
 
     // Check if vault is paused
-    if vault.paused {
-        return Err(VaultError::UnauthorizedAccess.into());
-    }
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
 
-    // Check vault SOL balance
-    let vault_balance = vault_account.lamports();
-    if vault_balance < amount {
-        return Err(VaultError::InvalidAmount.into());
-    }
+    // Only the authority can send tracked SOL out, to a recipient of its choosing
+    require_authority(&vault, authority)?;
+
+    ensure_sol_available(&vault, vault_account, amount)?;
 
     // Calculate fees
     let withdrawal_fee = if amount > 0 {
-        (amount as u128 * vault.fee_config.withdrawal_fee_bps as u128 / 10000) as u64
+        vault.fee_for(ParamKey::WithdrawalFeeBps, amount)
     } else {
         0
     };
     let net_withdrawal_amount = amount - withdrawal_fee;
 
-    // Perform SOL transfer from vault to recipient
-    let transfer_ix = system_instruction::transfer(
+    // The vault is program-owned and carries data, so the system program can't move its
+    // lamports; they're debited directly
+    pay_out_of_vault(vault_account, recipient, net_withdrawal_amount)?;
+    let balance_before = vault.tracked_lamports;
+
+    // Update total value locked and fees; the fee stays in the vault and stays tracked
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(net_withdrawal_amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.tracked_lamports = vault
+        .tracked_lamports
+        .checked_sub(net_withdrawal_amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.total_fees_collected = vault
+        .total_fees_collected
+        .checked_add(u128::from(withdrawal_fee))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    vault.log_action(
+        ActionKind::WithdrawSol,
+        *recipient.key,
+        Pubkey::default(),
+        amount,
+        clock.unix_timestamp,
+    );
+    check_circuit_breaker(
+        &mut vault,
         vault_account.key,
         recipient.key,
+        Pubkey::default(),
         net_withdrawal_amount,
+        balance_before,
+        &clock,
     );
 
-    // Use invoke_signed since vault is a PDA
-    let vault_seeds = &[b"vault", vault.authority.as_ref(), &[vault.bump]];
-    invoke_signed(
-        &transfer_ix,
-        &[
-            vault_account.clone(),
-            recipient.clone(),
-            system_program.clone(),
-        ],
-        &[vault_seeds],
-    )?;
-
-    // Update vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
-
-    // Update total value locked and fees
-    vault.total_value_locked -= net_withdrawal_amount;
-    vault.total_fees_collected += withdrawal_fee;
-
     // Serialize updated vault state
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
     // Emit withdrawal event
-    let withdrawal_event = TokenWithdrawnEvent {
+    let withdrawal_event = SolWithdrawnEvent {
         base: create_base_event(
             *vault_account.key,
             *recipient.key,
             "sol_withdrawn",
             &clock,
         ),
-        token_mint: spl_token::native_mint::id(), // Use native SOL mint
         amount: net_withdrawal_amount,
         fee_amount: withdrawal_fee,
         recipient: *recipient.key,
+        vault_lamports: vault_account.lamports(),
+        resulting_balance: vault.tracked_lamports,
+        resulting_tvl: vault.total_value_locked,
+        accrued_fees_after: vault.accrued_fee(&Pubkey::default()),
     };
     emit_event!(withdrawal_event, withdrawal_event);
 
-    msg!(
+    debug_msg!(
         "Successfully withdrew {} SOL (fee: {}) from vault",
         net_withdrawal_amount,
         withdrawal_fee
     );
-    msg!("Recipient: {}", recipient.key);
+    debug_msg!("Recipient: {}", recipient.key);
 
     Ok(())
 }
@@ -770,6 +1893,8 @@ fn process_transfer(
     recipient: Pubkey,
     amount: u64,
 ) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
     let recipient_account = next_account_info(account_info_iter)?;
@@ -782,96 +1907,395 @@ fn process_transfer(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != program_id {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
     if *system_program.key != system_program::ID {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     // Load vault state
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
     // Check if vault is paused
-    if vault.paused {
-        return Err(VaultError::UnauthorizedAccess.into());
-    }
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
 
     // Verify authority
-    if vault.authority != *authority.key {
-        return Err(VaultError::InsufficientAuthority.into());
-    }
+    require_authority(&vault, authority)?;
+
+    ensure_sol_available(&vault, vault_account, amount)?;
+
+    // The authority moving the treasury's own funds pays no withdrawal fee: the vault would
+    // only be paying its own fee recipient. The vault is program-owned and carries data, so
+    // the system program can't move its lamports; they're debited directly
+    pay_out_of_vault(vault_account, recipient_account, amount)?;
+    let balance_before = vault.tracked_lamports;
+
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.tracked_lamports = vault
+        .tracked_lamports
+        .checked_sub(amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    vault.log_action(
+        ActionKind::Transfer,
+        *authority.key,
+        Pubkey::default(),
+        amount,
+        clock.unix_timestamp,
+    );
+    check_circuit_breaker(
+        &mut vault,
+        vault_account.key,
+        authority.key,
+        Pubkey::default(),
+        amount,
+        balance_before,
+        &clock,
+    );
 
-    // Check vault SOL balance
-    let vault_balance = vault_account.lamports();
-    if vault_balance < amount {
-        return Err(VaultError::InvalidAmount.into());
-    }
+    // Serialize updated vault state
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Calculate fees
-    let transfer_fee = if amount > 0 {
-        (amount as u128 * vault.fee_config.withdrawal_fee_bps as u128 / 10000) as u64
-    } else {
-        0
+    // Emit transfer event
+    let transfer_event = SolWithdrawnEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "sol_transferred",
+            &clock,
+        ),
+        amount,
+        fee_amount: 0,
+        recipient: *recipient_account.key,
+        vault_lamports: vault_account.lamports(),
+        resulting_balance: vault.tracked_lamports,
+        resulting_tvl: vault.total_value_locked,
+        accrued_fees_after: vault.accrued_fee(&Pubkey::default()),
     };
-    let net_transfer_amount = amount - transfer_fee;
+    emit_event!(transfer_event, transfer_event);
 
-    // Perform SOL transfer from vault to recipient
-    let transfer_ix = system_instruction::transfer(
-        vault_account.key,
-        recipient_account.key,
-        net_transfer_amount,
-    );
+    debug_msg!("Successfully transferred {} SOL from vault to {}", amount, recipient);
+    debug_msg!("Authority: {}", authority.key);
+    debug_msg!("Recipient: {}", recipient_account.key);
 
-    // Use invoke_signed since vault is a PDA
-    let vault_seeds = &[b"vault", vault.authority.as_ref(), &[vault.bump]];
-    invoke_signed(
+    Ok(())
+}
+
+// TransferV2 without a mint is Transfer, which never looked at the recipient account's key
+fn process_transfer_v2(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    recipient: Pubkey,
+    mint: Option<Pubkey>,
+    amount: u64,
+    create_ata: bool,
+) -> ProgramResult {
+    let recipient_account = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if *recipient_account.key != recipient {
+        msg!("Recipient account {} is not {}", recipient_account.key, recipient);
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    match mint {
+        Some(mint) => transfer_tokens(program_id, accounts, mint, amount, create_ata),
+        None => process_transfer(program_id, accounts, recipient, amount),
+    }
+}
+
+// The SPL side of TransferV2: Withdraw's fee and bookkeeping, paid to the recipient's
+// associated token account on the authority's say-so
+fn transfer_tokens(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    amount: u64,
+    create_ata: bool,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let recipient = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let system_program_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let recipient_token_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if *system_program_account.key != system_program::ID || !is_token_program(token_program.key)
+    {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
+    require_authority(&vault, authority)?;
+
+    validate_token_supported(&vault, &token_mint).map_err(|_| VaultError::InvalidMint)?;
+    require_token_program(&vault, &token_mint, token_program.key)?;
+    verify_vault_token_account(
+        vault_account.key,
+        &token_mint,
+        token_program.key,
+        vault_token_account,
+    )?;
+    let expected_recipient =
+        get_associated_token_address_with_program_id(recipient.key, &token_mint, token_program.key);
+    if expected_recipient != *recipient_token_account.key {
+        msg!("Recipient token account is not {}'s associated token account", recipient.key);
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    if create_ata {
+        let payer = next_account_info(account_info_iter)?;
+        let mint_account = next_account_info(account_info_iter)?;
+        let associated_token_program = next_account_info(account_info_iter)?;
+        if *mint_account.key != token_mint
+            || *associated_token_program.key != spl_associated_token_account::ID
+        {
+            return Err(err(VaultError::InvalidAccountData));
+        }
+        let create_ata_ix = ata_instruction::create_associated_token_account_idempotent(
+            payer.key,
+            recipient.key,
+            &token_mint,
+            token_program.key,
+        );
+        invoke(
+            &create_ata_ix,
+            &[
+                payer.clone(),
+                recipient_token_account.clone(),
+                recipient.clone(),
+                mint_account.clone(),
+                system_program_account.clone(),
+                token_program.clone(),
+                associated_token_program.clone(),
+            ],
+        )?;
+    }
+
+    validate_free_balance(&vault, &token_mint, amount)?;
+
+    // Fee-exempt like SOL transfers: the authority is paying out the treasury's own funds
+    let transfer_ix = transfer_instruction(
+        token_program.key,
+        vault_token_account.key,
+        recipient_token_account.key,
+        vault_account.key,
+        amount,
+    )?;
+    invoke_signed_by_vault(
+        &vault,
         &transfer_ix,
         &[
+            vault_token_account.clone(),
+            recipient_token_account.clone(),
             vault_account.clone(),
-            recipient_account.clone(),
-            system_program.clone(),
+            token_program.clone(),
         ],
-        &[vault_seeds],
     )?;
 
-    // Update vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
+    update_supported_token_totals(&mut vault, &token_mint, 0, amount);
+    let balance_before = vault.tracked_balance(&token_mint);
+    if let Some(balance) = vault
+        .token_balances
+        .iter_mut()
+        .find(|b| b.mint == token_mint)
+    {
+        balance.balance = balance
+            .balance
+            .checked_sub(amount)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        balance.last_updated = clock.unix_timestamp;
+    }
 
-    // Update total value locked and fees
-    vault.total_value_locked -= net_transfer_amount;
-    vault.total_fees_collected += transfer_fee;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
 
-    // Serialize updated vault state
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.log_action(
+        ActionKind::Transfer,
+        *authority.key,
+        token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
+    check_circuit_breaker(
+        &mut vault,
+        vault_account.key,
+        authority.key,
+        token_mint,
+        amount,
+        balance_before,
+        &clock,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Emit transfer event
     let transfer_event = TokenWithdrawnEvent {
         base: create_base_event(
             *vault_account.key,
             *authority.key,
-            "sol_transferred",
+            "token_transferred",
             &clock,
         ),
-        token_mint: spl_token::native_mint::id(), // Use native SOL mint
-        amount: net_transfer_amount,
-        fee_amount: transfer_fee,
-        recipient: *recipient_account.key,
+        token_mint,
+        amount,
+        fee_amount: 0,
+        recipient: *recipient.key,
+        vault_fee_amount: 0,
+        protocol_fee_amount: 0,
+        resulting_balance: vault.tracked_balance(&token_mint),
+        resulting_tvl: vault.total_value_locked,
+        accrued_fees_after: vault.accrued_fee(&token_mint),
     };
     emit_event!(transfer_event, transfer_event);
 
-    msg!(
-        "Successfully transferred {} SOL (fee: {}) from vault to {}",
-        net_transfer_amount,
-        transfer_fee,
-        recipient
+    debug_msg!("Transferred {} of {} from vault to {}", amount, token_mint, recipient.key);
+
+    Ok(())
+}
+
+// Accounts: vault, clock. Permissionless: it only re-labels lamports the vault already holds.
+fn process_sweep_untracked_lamports(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let rent_floor = Rent::get()?.minimum_balance(vault_account.data_len());
+    let swept = vault.untracked_lamports(vault_account.lamports(), rent_floor);
+    if swept == 0 {
+        msg!("No untracked lamports to sweep");
+        return Ok(());
+    }
+
+    vault.tracked_lamports = vault
+        .tracked_lamports
+        .checked_add(swept)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_add(u128::from(swept))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.log_action(
+        ActionKind::LamportsSwept,
+        Pubkey::default(),
+        Pubkey::default(),
+        swept,
+        clock.unix_timestamp,
     );
-    msg!("Authority: {}", authority.key);
-    msg!("Recipient: {}", recipient_account.key);
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let swept_event = UntrackedLamportsSweptEvent {
+        base: create_base_event(
+            *vault_account.key,
+            Pubkey::default(),
+            "untracked_lamports_swept",
+            &clock,
+        ),
+        amount: swept,
+        tracked_lamports: vault.tracked_lamports,
+    };
+    emit_event!(swept_event, swept_event);
+
+    msg!("Swept {} untracked lamports into the vault", swept);
+    Ok(())
+}
+
+// Accounts: vault, clock, then vault associated token accounts to compare with the tracked
+// balances. Only last_heartbeat_slot changes; heartbeats stay out of recent_actions so they
+// don't crowd real operations out of it.
+fn process_emit_health_check(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let next_slot = vault
+        .last_heartbeat_slot
+        .saturating_add(Vault::HEALTH_CHECK_INTERVAL_SLOTS);
+    if vault.last_heartbeat_slot != 0 && clock.slot < next_slot {
+        msg!("Next health check from slot {}", next_slot);
+        return Err(err(VaultError::HealthCheckTooSoon));
+    }
+
+    let mut balances: Vec<HealthCheckBalance> = vault
+        .supported_tokens
+        .iter()
+        .map(|token| HealthCheckBalance {
+            mint: token.mint,
+            tracked: vault.tracked_balance(&token.mint),
+            drift: None,
+        })
+        .collect();
+    for token_account in account_info_iter {
+        if !is_token_program(token_account.owner) {
+            return Err(err(VaultError::InvalidAccountOwner));
+        }
+        let mint = unpack_token_account(token_account)?.mint;
+        let token_program = token_account.owner;
+        require_token_program(&vault, &mint, token_program)?;
+        let held =
+            verify_vault_token_account(vault_account.key, &mint, token_program, token_account)?
+                .amount;
+        let balance = balances
+            .iter_mut()
+            .find(|balance| balance.mint == mint)
+            .ok_or(VaultError::InvalidMint)?;
+        balance.drift = Some(held as i128 - balance.tracked as i128);
+    }
 
+    let now = clock.unix_timestamp;
+    let pending_multisig_transactions = vault
+        .multisig_transactions()
+        .filter(|&(id, transaction)| {
+            !transaction.did_execute
+                && !vault.is_transaction_cancelled(id)
+                && !vault.is_transaction_expired(id, now)
+        })
+        .count();
+    let pending_proposals = vault
+        .governance_proposals
+        .iter()
+        .filter(|proposal| !proposal.executed && !proposal.cancelled)
+        .count();
+    let active_time_locks = vault
+        .time_locks
+        .iter()
+        .filter(|lock| lock.released_amount < lock.amount)
+        .count();
+
+    vault.last_heartbeat_slot = clock.slot;
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let health_event = HealthCheckEvent {
+        base: create_base_event(*vault_account.key, Pubkey::default(), "health_check", &clock),
+        total_value_locked: vault.total_value_locked,
+        tracked_lamports: vault.tracked_lamports,
+        balances,
+        paused: vault.is_paused(now),
+        circuit_breaker_tripped: vault.circuit_breaker_tripped,
+        pending_multisig_transactions: pending_multisig_transactions as u32,
+        pending_proposals: pending_proposals as u32,
+        active_time_locks: active_time_locks as u32,
+    };
+    emit_event!(health_event, health_event);
     Ok(())
 }
 
@@ -891,20 +2315,17 @@ fn process_initialize_multi_sig(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    if vault.authority != *initializer.key {
-        return Err(VaultError::InsufficientAuthority.into());
+    require_authority(&vault, initializer)?;
+    if vault.setup_stage == SetupStage::Ready {
+        msg!("Setup is finalized; use SetMultiSigOwners and ChangeMultiSigThreshold");
+        return Err(err(VaultError::SetupFinalized));
     }
 
     // Validate threshold
     if threshold == 0 || threshold > owners.len() as u64 {
-        return Err(VaultError::InvalidThreshold.into());
+        return Err(err(VaultError::InvalidThreshold));
     }
 
     // Validate owners (no duplicates)
@@ -912,20 +2333,30 @@ fn process_initialize_multi_sig(
     unique_owners.sort();
     unique_owners.dedup();
     if unique_owners.len() != owners.len() {
-        return Err(VaultError::InvalidAccountData.into());
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.multi_sig = Some(MultiSig {
         owners: owners.clone(),
         threshold,
         nonce,
-        bump: 0, // Will be calculated when needed
+        bump: MultiSig::find_signer_address(vault_account.key, nonce, program_id).1,
     });
 
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    let clock = load_clock(clock_sysvar)?;
+    vault.multisig_owner_since = vec![clock.unix_timestamp; owners.len()];
+    vault.owner_set_seqno = vault.owner_set_seqno.wrapping_add(1);
+    vault.last_owner_activity = clock.unix_timestamp;
+    vault.refresh_setup_stage();
 
-    let clock = Clock::from_account_info(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::MultiSigInitialized,
+        *initializer.key,
+        Pubkey::default(),
+        threshold,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
     // Emit event
     let multisig_event = MultiSigInitializedEvent {
@@ -985,720 +2416,5964 @@ fn process_reject_proposal(
     Ok(())
 }
 
+// Accounts for the pause instructions: vault, emergency admin, clock
 fn process_pause_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    msg!("Processing pause vault");
-    Ok(())
-}
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-fn process_unpause_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    msg!("Processing unpause vault");
-    Ok(())
-}
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
 
-fn process_emergency_withdraw(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    token_mint: Pubkey,
-    amount: u64,
-) -> ProgramResult {
-    msg!("Processing emergency withdraw");
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_emergency_admin(&vault, admin.key)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    // Renewing a live pause has its own instruction so the admin can't do it by accident
+    if vault.is_paused(clock.unix_timestamp) {
+        msg!("Vault is already paused; use ExtendPause to renew it");
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    vault.paused = true;
+    vault.paused_at = clock.unix_timestamp;
+    let expires_at = vault.pause_expires_at();
+
+    vault.log_action(
+        ActionKind::Paused,
+        *admin.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let paused_event = VaultPausedEvent {
+        base: create_base_event(*vault_account.key, *admin.key, "vault_paused", &clock),
+        expires_at,
+    };
+    emit_event!(paused_event, paused_event);
+
+    match expires_at {
+        Some(expires_at) => msg!("Vault paused until {}", expires_at),
+        None => msg!("Vault paused"),
+    }
     Ok(())
 }
 
-fn process_add_supported_token(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    mint: Pubkey,
-    bump: u8,
-) -> ProgramResult {
+fn process_unpause_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
-    let vault_token_account = next_account_info(account_info_iter)?;
-    let token_mint = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let associated_token_program = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let system_program = next_account_info(account_info_iter)?;
-    let rent_sysvar = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
     let clock_sysvar = next_account_info(account_info_iter)?;
 
-    // Validate accounts
-    if !authority.is_signer {
+    if !admin.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if vault_account.owner != program_id {
-        return Err(VaultError::InvalidAccountOwner.into());
-    }
-
-    if *token_mint.key != mint {
-        return Err(VaultError::InvalidAccountData.into());
-    }
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    // Load vault state
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    validate_emergency_admin(&vault, admin.key)?;
 
-    // Check if authority is vault authority
-    if vault.authority != *authority.key {
-        return Err(VaultError::InsufficientAuthority.into());
-    }
+    vault.paused = false;
+    vault.paused_at = 0;
+    vault.circuit_breaker_tripped = false;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::Unpaused,
+        *admin.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Check if token is already supported
-    if vault.supported_tokens.iter().any(|t| t.mint == mint) {
-        return Err(VaultError::InvalidAccountData.into());
-    }
+    let unpaused_event = VaultUnpausedEvent {
+        base: create_base_event(*vault_account.key, *admin.key, "vault_unpaused", &clock),
+    };
+    emit_event!(unpaused_event, unpaused_event);
 
-    // Verify vault token account derivation
-    let expected_vault_token_account = get_associated_token_address(vault_account.key, &mint);
-    if expected_vault_token_account != *vault_token_account.key {
-        return Err(VaultError::InvalidAccountData.into());
-    }
+    msg!("Vault unpaused");
+    Ok(())
+}
 
-    // Create associated token account for vault if it doesn't exist
-    if vault_token_account.data_is_empty() {
-        let create_ata_ix = ata_instruction::create_associated_token_account(
-            authority.key,
-            vault_account.key,
-            &mint,
-            &spl_token::ID,
-        );
+fn process_extend_pause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let admin = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-        invoke(
-            &create_ata_ix,
-            &[
-                authority.clone(),
-                vault_token_account.clone(),
-                vault_account.clone(),
-                token_mint.clone(),
-                system_program.clone(),
-                token_program.clone(),
-                rent_sysvar.clone(),
-                associated_token_program.clone(),
-            ],
-        )?;
+    if !admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Update vault state
-    let clock = Clock::from_account_info(clock_sysvar)?;
-    let supported_token = SupportedToken {
-        mint,
-        bump,
-        total_deposited: 0,
-        total_withdrawn: 0,
-        is_active: true,
-    };
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    vault.supported_tokens.push(supported_token);
+    validate_emergency_admin(&vault, admin.key)?;
 
-    // Serialize updated vault state
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    // A lapsed pause is over; the admin pauses again instead
+    let clock = load_clock(clock_sysvar)?;
+    if !vault.is_paused(clock.unix_timestamp) {
+        msg!("Vault is not paused");
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    vault.paused_at = clock.unix_timestamp;
+    let expires_at = vault.pause_expires_at();
+
+    vault.log_action(
+        ActionKind::PauseExtended,
+        *admin.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Emit token added event
-    let token_added_event = TokenAddedEvent {
-        base: create_base_event(*vault_account.key, *authority.key, "token_added", &clock),
-        token_mint: mint,
-        vault_token_account: *vault_token_account.key,
+    let extended_event = VaultPauseExtendedEvent {
+        base: create_base_event(*vault_account.key, *admin.key, "vault_pause_extended", &clock),
+        paused_at: clock.unix_timestamp,
+        expires_at,
     };
-    emit_event!(token_added_event, token_added_event);
-
-    msg!("Successfully added token {} to vault", mint);
-    msg!("Vault token account: {}", vault_token_account.key);
+    emit_event!(extended_event, extended_event);
 
+    msg!("Vault pause renewed at {}", clock.unix_timestamp);
     Ok(())
 }
 
-fn process_deposit_multi_token(
+fn process_set_max_pause_duration(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    mint: Pubkey,
-    amount: u64,
+    max_pause_duration: i64,
 ) -> ProgramResult {
-    msg!("Processing deposit multi token");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if max_pause_duration < 0 {
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    // Counted from paused_at, a new value would reshape the pause in progress; shortening it
+    // would end the emergency admin's pause at once
+    let clock = load_clock(clock_sysvar)?;
+    if vault.is_paused(clock.unix_timestamp) {
+        msg!("The max pause duration can't change while the vault is paused");
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    let old_duration = vault.max_pause_duration;
+    vault.max_pause_duration = max_pause_duration;
+
+    vault.log_action(
+        ActionKind::PauseDurationChanged,
+        *authority.key,
+        Pubkey::default(),
+        max_pause_duration as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let duration_event = MaxPauseDurationUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "max_pause_duration_updated",
+            &clock,
+        ),
+        old_duration,
+        new_duration: max_pause_duration,
+    };
+    emit_event!(duration_event, duration_event);
+
+    msg!("Max pause duration: {} -> {} seconds", old_duration, max_pause_duration);
     Ok(())
 }
 
-fn process_create_time_lock(
+fn process_emergency_withdraw(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    beneficiary: Pubkey,
+    token_mint: Pubkey,
     amount: u64,
-    duration: i64,
-    cliff_duration: Option<i64>,
-    is_linear: bool,
 ) -> ProgramResult {
-    msg!("Processing create time lock");
-    Ok(())
-}
+    validate_amount(amount, 0)?;
 
-fn process_claim_time_lock(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    time_lock_index: usize,
-) -> ProgramResult {
-    msg!("Processing claim time lock");
-    Ok(())
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let recipient_token_account = next_account_info(account_info_iter)?;
+    let emergency_admin = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !emergency_admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !is_token_program(token_program.key) {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    validate_emergency_admin(&vault, emergency_admin.key)?;
+
+    // Dual control: one key alone can't drain the vault
+    let cosigner = if vault.emergency_requires_cosign() {
+        let cosigner = next_account_info(account_info_iter)
+            .map_err(|_| VaultError::EmergencyCosignRequired)?;
+        let is_owner = vault
+            .multi_sig
+            .as_ref()
+            .map_or(false, |multisig| multisig.owners.contains(cosigner.key));
+        if !cosigner.is_signer || !is_owner || cosigner.key == emergency_admin.key {
+            msg!("Emergency withdrawal needs a current multisig owner to co-sign");
+            return Err(err(VaultError::EmergencyCosignRequired));
+        }
+        Some(*cosigner.key)
+    } else {
+        None
+    };
+    // Required even before the GlobalConfig exists, so leaving it out can't skip the check
+    let config_account = next_account_info(account_info_iter)?;
+    if let Some(config) = load_created_global_config(program_id, config_account)? {
+        if config.requires_upgrade_check(None) {
+            verify_upgrade_authority(program_id, &config, account_info_iter.next())?;
+        }
+    }
+
+    require_token_program(&vault, &token_mint, token_program.key)?;
+    verify_vault_token_account(vault_account.key, &token_mint, token_program.key, vault_token_account)?;
+    if recipient_token_account.owner != token_program.key {
+        return Err(err(VaultError::InvalidAccountOwner));
+    }
+    let recipient = unpack_token_account(recipient_token_account)?;
+    if recipient.mint != token_mint {
+        return Err(err(VaultError::TokenMintMismatch));
+    }
+    validate_vault_balance(&vault, &token_mint, amount)?;
+
+    let transfer_ix = transfer_instruction(
+        token_program.key,
+        vault_token_account.key,
+        recipient_token_account.key,
+        vault_account.key,
+        amount,
+    )?;
+    invoke_signed_by_vault(
+        &vault,
+        &transfer_ix,
+        &[
+            vault_token_account.clone(),
+            recipient_token_account.clone(),
+            vault_account.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    update_supported_token_totals(&mut vault, &token_mint, 0, amount);
+    update_token_balance(&mut vault, &token_mint, -(amount as i64), &clock)?;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    // Details: mint, amount (LE), then the co-signer when there was one
+    let mut details = token_mint.to_bytes().to_vec();
+    details.extend_from_slice(&amount.to_le_bytes());
+    if let Some(cosigner) = cosigner {
+        details.extend_from_slice(cosigner.as_ref());
+    }
+    vault.log_emergency_action(
+        *emergency_admin.key,
+        EmergencyActionLog::EMERGENCY_WITHDRAW,
+        details,
+        clock.unix_timestamp,
+    );
+    vault.log_action(
+        ActionKind::EmergencyWithdraw,
+        *emergency_admin.key,
+        token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = EmergencyWithdrawEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *emergency_admin.key,
+            "emergency_withdraw",
+            &clock,
+        ),
+        token_mint,
+        amount,
+        recipient: *recipient_token_account.key,
+        cosigner,
+    };
+    emit_event!(event, event);
+
+    match cosigner {
+        Some(cosigner) => debug_msg!(
+            "Emergency withdrawal of {} by {}, co-signed by {}",
+            amount,
+            emergency_admin.key,
+            cosigner
+        ),
+        None => debug_msg!("Emergency withdrawal of {} by {}", amount, emergency_admin.key),
+    }
+    Ok(())
+}
+
+// Decimals of `token_mint`, which must be the initialized SPL Token or Token-2022 mint
+// `mint`. Token-2022 mints carry extensions after the classic layout, which is all that's read.
+fn mint_decimals(token_mint: &AccountInfo, mint: &Pubkey) -> Result<u8, ProgramError> {
+    if token_mint.key != mint {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if !is_token_program(token_mint.owner) {
+        return Err(err(VaultError::InvalidMint));
+    }
+    let data = token_mint.data.borrow();
+    let base = data
+        .get(..spl_token::state::Mint::LEN)
+        .ok_or(VaultError::InvalidMint)?;
+    let state =
+        spl_token::state::Mint::unpack_from_slice(base).map_err(|_| VaultError::InvalidMint)?;
+    if !state.is_initialized {
+        return Err(err(VaultError::InvalidMint));
+    }
+    Ok(state.decimals)
+}
+
+// Creates the vault's associated token account for `token_mint`, or reuses it if it was
+// already created. `extra` is passed through to the CPI (the rent sysvar, for callers that
+// have it).
+#[allow(clippy::too_many_arguments)]
+fn create_vault_token_account<'a>(
+    payer: &AccountInfo<'a>,
+    vault_token_account: &AccountInfo<'a>,
+    vault_account: &AccountInfo<'a>,
+    token_mint: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    associated_token_program: &AccountInfo<'a>,
+    extra: &[AccountInfo<'a>],
+) -> ProgramResult {
+    // The ATA is created under whichever token program owns the mint
+    if token_program.key != token_mint.owner {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    let expected_vault_token_account = get_associated_token_address_with_program_id(
+        vault_account.key,
+        token_mint.key,
+        token_program.key,
+    );
+    if expected_vault_token_account != *vault_token_account.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let create_ata_ix = ata_instruction::create_associated_token_account_idempotent(
+        payer.key,
+        vault_account.key,
+        token_mint.key,
+        token_program.key,
+    );
+    let mut infos = vec![
+        payer.clone(),
+        vault_token_account.clone(),
+        vault_account.clone(),
+        token_mint.clone(),
+        system_program.clone(),
+        token_program.clone(),
+    ];
+    infos.extend_from_slice(extra);
+    infos.push(associated_token_program.clone());
+    invoke(&create_ata_ix, &infos)?;
+
+    // A pre-existing ATA may have been tampered with before the vault started using it
+    let vault_token = unpack_token_account(vault_token_account)?;
+    if vault_token.is_frozen()
+        || vault_token.delegate.is_some()
+        || vault_token.close_authority.is_some()
+    {
+        msg!(
+            "Vault token account {} is frozen or has a delegate/close authority",
+            vault_token_account.key
+        );
+        return Err(err(VaultError::CompromisedTokenAccount));
+    }
+    Ok(())
+}
+
+fn process_add_supported_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    bump: u8,
+    idempotent: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let token_mint = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let associated_token_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    // Validate accounts; the payer only funds the ATA rent, authorization comes from the authority
+    if !authority.is_signer || !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let decimals = mint_decimals(token_mint, &mint)?;
+
+    // Load vault state
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    // Check if authority is vault authority
+    require_authority(&vault, authority)?;
+
+    // An active registration is left alone when the caller asked for idempotency; a
+    // deactivated one can't be revived through here
+    let already_supported = match vault.supported_tokens.iter().find(|t| t.mint == mint) {
+        Some(token) if token.is_active && idempotent => true,
+        Some(_) => return Err(err(VaultError::TokenAlreadySupported)),
+        None => false,
+    };
+
+    // Checks the ATA derivation before creating it
+    create_vault_token_account(
+        payer,
+        vault_token_account,
+        vault_account,
+        token_mint,
+        system_program,
+        token_program,
+        associated_token_program,
+        &[rent_sysvar.clone()],
+    )?;
+
+    if already_supported {
+        debug_msg!("Token {} is already supported, nothing to do", mint);
+        return Ok(());
+    }
+
+    // Update vault state
+    let clock = load_clock(clock_sysvar)?;
+    let supported_token = SupportedToken {
+        mint,
+        bump,
+        total_deposited: 0,
+        total_withdrawn: 0,
+        is_active: true,
+        deposit_count: 0,
+        withdrawal_count: 0,
+        decimals,
+    };
+
+    vault.supported_tokens.push(supported_token);
+    if *token_program.key != spl_token::ID {
+        vault.set_supported_token_program(vault.supported_tokens.len() - 1, *token_program.key);
+    }
+    vault.refresh_setup_stage();
+
+    vault.log_action(ActionKind::AddSupportedToken, *authority.key, mint, 0, clock.unix_timestamp);
+
+    // Serialize updated vault state
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Emit token added event
+    let token_added_event = TokenAddedEvent {
+        base: create_base_event(*vault_account.key, *authority.key, "token_added", &clock),
+        token_mint: mint,
+        vault_token_account: *vault_token_account.key,
+    };
+    emit_event!(token_added_event, token_added_event);
+
+    debug_msg!("Successfully added token {} to vault", mint);
+    debug_msg!("Vault token account: {}", vault_token_account.key);
+
+    Ok(())
+}
+
+// Picks up a mint's current decimals and owning token program, for tokens whose mint was
+// migrated after they were added. Changing them under a tracked balance would reprice it, so
+// that takes an explicit allow_with_balance.
+fn process_refresh_supported_token_metadata(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    allow_with_balance: bool,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let token_mint = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let index = vault
+        .supported_tokens
+        .iter()
+        .position(|t| t.mint == mint)
+        .ok_or(VaultError::InvalidMint)?;
+    if *token_mint.key != mint {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    // Token-2022 mints carry extensions after the classic layout, which is all that's read
+    let new_token_program = *token_mint.owner;
+    if new_token_program != spl_token::ID && new_token_program != TOKEN_2022_PROGRAM_ID {
+        return Err(err(VaultError::InvalidMint));
+    }
+    let new_decimals = {
+        let data = token_mint.data.borrow();
+        let base = data
+            .get(..spl_token::state::Mint::LEN)
+            .ok_or(VaultError::InvalidMint)?;
+        let state = spl_token::state::Mint::unpack_from_slice(base)
+            .map_err(|_| VaultError::InvalidMint)?;
+        if !state.is_initialized {
+            return Err(err(VaultError::InvalidMint));
+        }
+        state.decimals
+    };
+
+    let old_decimals = vault.supported_tokens[index].decimals;
+    let old_token_program = vault.supported_token_program(index);
+    if old_decimals == new_decimals && old_token_program == new_token_program {
+        debug_msg!("Token {} is up to date, nothing to refresh", mint);
+        return Ok(());
+    }
+
+    let balance = vault.tracked_balance(&mint);
+    if balance > 0 && !allow_with_balance {
+        msg!("Vault tracks {} of {}; pass allow_with_balance to refresh anyway", balance, mint);
+        return Err(err(VaultError::TokenBalanceOutstanding));
+    }
+
+    vault.supported_tokens[index].decimals = new_decimals;
+    vault.set_supported_token_program(index, new_token_program);
+
+    vault.log_action(
+        ActionKind::SupportedTokenRefreshed,
+        *authority.key,
+        mint,
+        balance,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let refreshed_event = SupportedTokenRefreshedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "supported_token_refreshed",
+            &clock,
+        ),
+        token_mint: mint,
+        old_decimals,
+        new_decimals,
+        old_token_program,
+        new_token_program,
+        balance,
+    };
+    emit_event!(refreshed_event, refreshed_event);
+
+    msg!(
+        "Token {} refreshed: decimals {} -> {}, token program {} -> {}",
+        mint,
+        old_decimals,
+        new_decimals,
+        old_token_program,
+        new_token_program
+    );
+    Ok(())
+}
+
+fn process_deposit_multi_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mint: Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    msg!("Processing deposit multi token");
+    Ok(())
+}
+
+fn process_create_time_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    beneficiary: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    duration: i64,
+    cliff_duration: Option<i64>,
+    schedule: VestingSchedule,
+    revocable: bool,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_setup_ready(&vault)?;
+
+    require_authority(&vault, authority)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
+
+    validate_token_supported(&vault, &mint)?;
+    // Locks are reservations against tokens already in the vault, never promises beyond it
+    validate_free_balance(&vault, &mint, amount)?;
+
+    let start_time = clock.unix_timestamp;
+    let end_time = start_time
+        .checked_add(duration)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    // Without an explicit cliff, linear locks start vesting immediately and
+    // cliff-all locks release everything at the end
+    let cliff_offset = cliff_duration.unwrap_or(match schedule {
+        VestingSchedule::Linear => 0,
+        VestingSchedule::CliffAll => duration,
+    });
+    let cliff_time = start_time
+        .checked_add(cliff_offset)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    let time_lock = TimeLock {
+        id: vault.next_time_lock_id,
+        beneficiary,
+        mint,
+        amount,
+        released_amount: 0,
+        start_time,
+        cliff_time,
+        end_time,
+        schedule,
+        revocable,
+    };
+    vesting::validate_schedule(&time_lock)?;
+    vault.require_time_lock_room()?;
+
+    let time_lock_id = time_lock.id;
+    let time_lock_index = vault.time_locks.len();
+    vault.time_locks.push(time_lock);
+    vault.next_time_lock_id = vault
+        .next_time_lock_id
+        .checked_add(1)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    vault.log_action(
+        ActionKind::CreateTimeLock,
+        *authority.key,
+        mint,
+        amount,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let time_lock_event = TimeLockCreatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "time_lock_created",
+            &clock,
+        ),
+        time_lock_index,
+        time_lock_id,
+        beneficiary,
+        mint,
+        amount,
+        start_time,
+        cliff_time,
+        end_time,
+        schedule,
+        revocable,
+    };
+    emit_event!(time_lock_event, time_lock_event);
+
+    msg!("Time lock {} created for {} tokens to {}", time_lock_index, amount, beneficiary);
+    Ok(())
+}
+
+fn process_claim_time_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    time_lock_id: u64,
+) -> ProgramResult {
+    claim_time_lock(program_id, accounts, |vault| vault.time_lock_index(time_lock_id), None)
+}
+
+// Stream-style claim of any part of what has vested so far, addressed by the lock's stable id
+fn process_claim_time_lock_partial(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    time_lock_id: u64,
+    amount: u64,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+    claim_time_lock(
+        program_id,
+        accounts,
+        |vault| vault.time_lock_index(time_lock_id),
+        Some(amount),
+    )
+}
+
+// Shared by both claim instructions. `requested` None claims everything releasable.
+// Accounts: vault, vault token account, beneficiary token account, beneficiary, token program,
+// clock.
+fn claim_time_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    locate: impl FnOnce(&Vault) -> Option<usize>,
+    requested: Option<u64>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let beneficiary_token_account = next_account_info(account_info_iter)?;
+    let beneficiary = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !beneficiary.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !is_token_program(token_program.key) {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    // Payroll keeps flowing through unrelated incidents when the authority opted in
+    if !vault.streams_exempt_from_pause {
+        require_not_paused(&mut vault, vault_account.key, beneficiary.key, &clock)?;
+    }
+
+    let time_lock_index = locate(&vault).ok_or(VaultError::InvalidInstruction)?;
+    let time_lock = vault
+        .time_locks
+        .get(time_lock_index)
+        .ok_or(VaultError::InvalidInstruction)?;
+
+    if time_lock.beneficiary != *beneficiary.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+
+    let releasable_amount = vesting::releasable(time_lock, clock.unix_timestamp)?;
+    let claim_amount = requested.unwrap_or(releasable_amount);
+    if claim_amount == 0 || claim_amount > releasable_amount {
+        msg!("{} of time lock {} is claimable now", releasable_amount, time_lock.id);
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    // Get token mint from vault's token account
+    let token_mint = unpack_token_account(vault_token_account)?.mint;
+
+    // Locks migrated without a known mint wait for AssignTimeLockMint
+    if time_lock.mint == Pubkey::default() {
+        msg!("Time lock {} has no mint yet", time_lock.id);
+        return Err(err(VaultError::TimeLockMintUnassigned));
+    }
+    if time_lock.mint != token_mint {
+        return Err(err(VaultError::InvalidMint));
+    }
+    let time_lock_id = time_lock.id;
+
+    require_token_program(&vault, &token_mint, token_program.key)?;
+    verify_vault_token_account(
+        vault_account.key,
+        &token_mint,
+        token_program.key,
+        vault_token_account,
+    )?;
+
+    let expected_beneficiary_token_account = get_associated_token_address_with_program_id(
+        beneficiary.key,
+        &token_mint,
+        token_program.key,
+    );
+    if expected_beneficiary_token_account != *beneficiary_token_account.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    validate_token_supported(&vault, &token_mint)?;
+    validate_vault_balance(&vault, &token_mint, claim_amount)?;
+
+    // The fee stays in the vault as an accrued fee, as it does for withdrawals; the lock is
+    // charged in full
+    let fee_amount = (claim_amount as u128 * vault.stream_claim_fee_bps as u128 / 10000) as u64;
+    let net_claim_amount = claim_amount - fee_amount;
+
+    let transfer_ix = transfer_instruction(
+        token_program.key,
+        vault_token_account.key,
+        beneficiary_token_account.key,
+        vault_account.key,
+        net_claim_amount,
+    )?;
+
+    invoke_signed_by_vault(
+        &vault,
+        &transfer_ix,
+        &[
+            vault_token_account.clone(),
+            beneficiary_token_account.clone(),
+            vault_account.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    // Fully claimed locks stay in place so later indices don't shift
+    let time_lock = &mut vault.time_locks[time_lock_index];
+    time_lock.released_amount = time_lock
+        .released_amount
+        .checked_add(claim_amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    let remaining_amount = time_lock
+        .amount
+        .checked_sub(time_lock.released_amount)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    update_token_balance(&mut vault, &token_mint, -(claim_amount as i64), &clock)?;
+    update_supported_token_totals(&mut vault, &token_mint, 0, net_claim_amount);
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(claim_amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.total_fees_collected = vault
+        .total_fees_collected
+        .checked_add(u128::from(fee_amount))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.accrue_fee(token_mint, fee_amount, clock.unix_timestamp);
+
+    vault.log_action(
+        ActionKind::ClaimTimeLock,
+        *beneficiary.key,
+        token_mint,
+        claim_amount,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let claim_event = TimeLockClaimedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *beneficiary.key,
+            "time_lock_claimed",
+            &clock,
+        ),
+        time_lock_index,
+        time_lock_id,
+        beneficiary: *beneficiary.key,
+        claimed_amount: claim_amount,
+        remaining_amount,
+        fee_amount,
+    };
+    emit_event!(claim_event, claim_event);
+
+    msg!(
+        "Claimed {} tokens (fee: {}) from time lock {}",
+        claim_amount,
+        fee_amount,
+        time_lock_index
+    );
+    msg!("Remaining locked: {}", remaining_amount);
+    Ok(())
+}
+
+// Gives a lock migrated without a known mint the one it was granted in. Only locks without
+// a mint qualify, so a lock's mint can never be swapped for another.
+fn process_assign_time_lock_mint(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    time_lock_id: u64,
+    mint: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+    validate_token_supported(&vault, &mint)?;
+
+    let index = vault
+        .time_lock_index(time_lock_id)
+        .ok_or(VaultError::InvalidInstruction)?;
+    let time_lock = &mut vault.time_locks[index];
+    if time_lock.mint != Pubkey::default() {
+        msg!("Time lock {} is already in {}", time_lock_id, time_lock.mint);
+        return Err(err(VaultError::InvalidMint));
+    }
+    time_lock.mint = mint;
+    let amount = time_lock.amount;
+    let beneficiary = time_lock.beneficiary;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::TimeLockMintAssigned,
+        *authority.key,
+        mint,
+        time_lock_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let assigned_event = TimeLockMintAssignedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "time_lock_mint_assigned",
+            &clock,
+        ),
+        time_lock_id,
+        beneficiary,
+        mint,
+        amount,
+    };
+    emit_event!(assigned_event, assigned_event);
+
+    msg!("Time lock {} is now in {}", time_lock_id, mint);
+    Ok(())
+}
+
+// Only moves principal between locks; no tokens leave the vault, so it isn't held up by a
+// pause
+fn process_split_time_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    time_lock_id: u64,
+    amount_to_new: u64,
+    new_beneficiary: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let beneficiary = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !beneficiary.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let time_lock_index = vault
+        .time_lock_index(time_lock_id)
+        .ok_or(VaultError::InvalidInstruction)?;
+    if vault.time_locks[time_lock_index].beneficiary != *beneficiary.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    vault.require_time_lock_room()?;
+
+    let new_time_lock_id = vault.next_time_lock_id;
+    let new_lock = vesting::split(
+        &mut vault.time_locks[time_lock_index],
+        amount_to_new,
+        new_time_lock_id,
+        new_beneficiary,
+    )?;
+    let original = vault.time_locks[time_lock_index].clone();
+    let new_time_lock_index = vault.time_locks.len();
+    vault.time_locks.push(new_lock.clone());
+    vault.next_time_lock_id = new_time_lock_id
+        .checked_add(1)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    vault.log_action(
+        ActionKind::TimeLockSplit,
+        *beneficiary.key,
+        original.mint,
+        amount_to_new,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let split_event = TimeLockSplitEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *beneficiary.key,
+            "time_lock_split",
+            &clock,
+        ),
+        time_lock_id,
+        new_time_lock_id,
+        new_beneficiary,
+        amount_moved: new_lock.amount,
+        released_moved: new_lock.released_amount,
+        amount: original.amount,
+        released_amount: original.released_amount,
+    };
+    emit_event!(split_event, split_event);
+
+    let created_event = TimeLockCreatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *beneficiary.key,
+            "time_lock_created",
+            &clock,
+        ),
+        time_lock_index: new_time_lock_index,
+        time_lock_id: new_time_lock_id,
+        beneficiary: new_beneficiary,
+        mint: new_lock.mint,
+        amount: new_lock.amount,
+        start_time: new_lock.start_time,
+        cliff_time: new_lock.cliff_time,
+        end_time: new_lock.end_time,
+        schedule: new_lock.schedule,
+        revocable: new_lock.revocable,
+    };
+    emit_event!(created_event, created_event);
+
+    msg!(
+        "Split {} of time lock {} into time lock {} for {}",
+        amount_to_new,
+        time_lock_id,
+        new_time_lock_id,
+        new_beneficiary
+    );
+    Ok(())
+}
+
+fn process_set_stream_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    exempt_from_pause: bool,
+    claim_fee_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if claim_fee_bps > 10000 {
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    vault.streams_exempt_from_pause = exempt_from_pause;
+    vault.stream_claim_fee_bps = claim_fee_bps;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::StreamConfigChanged,
+        *authority.key,
+        Pubkey::default(),
+        claim_fee_bps as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let stream_event = StreamConfigUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "stream_config_updated",
+            &clock,
+        ),
+        exempt_from_pause,
+        claim_fee_bps,
+    };
+    emit_event!(stream_event, stream_event);
+
+    msg!(
+        "Stream config: exempt from pause {}, claim fee {} bps",
+        exempt_from_pause,
+        claim_fee_bps
+    );
+    Ok(())
+}
+
+// Revokes what hasn't vested yet. The beneficiary keeps everything vested by now, claimable as
+// before; the rest stops being held by locked_for_timelocks and is free in the vault again.
+// Accounts: vault, authority (signer), clock.
+fn process_cancel_time_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    time_lock_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let index = vault
+        .time_lock_index(time_lock_id)
+        .ok_or(VaultError::InvalidInstruction)?;
+    let time_lock = &mut vault.time_locks[index];
+    if !time_lock.revocable {
+        return Err(err(VaultError::TimeLockNotRevocable));
+    }
+    let vested = vesting::vested_amount(time_lock, clock.unix_timestamp)?;
+    let returned = time_lock.amount - vested;
+    if returned == 0 {
+        msg!("Time lock {} has fully vested, nothing to cancel", time_lock_id);
+        return Err(err(VaultError::InvalidTimeLock));
+    }
+
+    // Once anything has vested the lock ends now, so its new amount stays fully vested
+    time_lock.amount = vested;
+    if vested > 0 {
+        time_lock.end_time = clock.unix_timestamp;
+    }
+    time_lock.revocable = false;
+    let cancelled = time_lock.clone();
+
+    vault.log_action(
+        ActionKind::TimeLockCancelled,
+        *authority.key,
+        cancelled.mint,
+        returned,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let cancelled_event = TimeLockCancelledEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "time_lock_cancelled",
+            &clock,
+        ),
+        time_lock_id,
+        beneficiary: cancelled.beneficiary,
+        mint: cancelled.mint,
+        amount_returned: returned,
+        amount: cancelled.amount,
+        released_amount: cancelled.released_amount,
+    };
+    emit_event!(cancelled_event, cancelled_event);
+
+    msg!("Time lock {} cancelled, {} returned to the vault", time_lock_id, returned);
+    Ok(())
+}
+
+// Registers the strategy program for a mint, or swaps it while nothing is deployed
+// SetYieldStrategy pins no accounts, SetYieldStrategyV2 the ones it names
+fn process_set_yield_strategy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    strategy_program: Pubkey,
+    strategy_accounts: Vec<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let global_config = load_global_config(program_id, account_info_iter.next())?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // The strategy CPI runs with the vault PDA signing, so it must never be this program
+    if strategy_program == *program_id || strategy_program == Pubkey::default() {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if let Some(config) = &global_config {
+        if !config.is_allowed_program(&strategy_program) {
+            msg!("Strategy program {} is not in the global registry", strategy_program);
+            return Err(err(VaultError::ProgramNotAllowed));
+        }
+    }
+    if strategy_accounts.len() > StrategyAccounts::MAX_ACCOUNTS {
+        msg!("At most {} strategy accounts can be pinned", StrategyAccounts::MAX_ACCOUNTS);
+        return Err(err(VaultError::InvalidInstruction));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    let index = match vault.strategy_index(&token_mint) {
+        Some(index) => {
+            let strategy = &mut vault.yield_strategies[index];
+            if strategy.deployed_amount > 0 && strategy.strategy_program != strategy_program {
+                return Err(err(VaultError::StrategyStillDeployed));
+            }
+            strategy.strategy_program = strategy_program;
+            index
+        }
+        None => {
+            vault.yield_strategies.push(YieldStrategyConfig {
+                token_mint,
+                strategy_program,
+                ..YieldStrategyConfig::default()
+            });
+            vault.yield_strategies.len() - 1
+        }
+    };
+    vault.pin_strategy_accounts(token_mint, strategy_accounts);
+    let max_allocation_bps = vault.strategy_allocation_cap(index);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::StrategySet,
+        *authority.key,
+        token_mint,
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let strategy_event = YieldStrategySetEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "yield_strategy_set",
+            &clock,
+        ),
+        token_mint,
+        strategy_program,
+        max_allocation_bps,
+    };
+    emit_event!(strategy_event, strategy_event);
+
+    debug_msg!("Yield strategy for {} set to {}", token_mint, strategy_program);
+    Ok(())
+}
+
+// Changes the allocation cap of the mint's strategy. Nothing is recalled when the cap drops
+// below what is deployed; DeployToStrategy just refuses until holdings catch up.
+fn process_update_yield_strategy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    max_allocation_bps: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if max_allocation_bps > YieldStrategyConfig::MAX_ALLOCATION_BPS {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    let index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    vault.set_strategy_allocation_cap(index, max_allocation_bps);
+    let strategy_program = vault.yield_strategies[index].strategy_program;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::StrategySet,
+        *authority.key,
+        token_mint,
+        max_allocation_bps as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let strategy_event = YieldStrategySetEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "yield_strategy_set",
+            &clock,
+        ),
+        token_mint,
+        strategy_program,
+        max_allocation_bps,
+    };
+    emit_event!(strategy_event, strategy_event);
+
+    msg!(
+        "Yield strategy for {} capped at {} bps",
+        token_mint,
+        max_allocation_bps
+    );
+    Ok(())
+}
+
+// Accounts: vault, authority, vault token account, strategy program, clock, then the strategy
+// accounts pinned for the mint (including the vault and its token account where it needs them)
+fn process_deploy_to_strategy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    amount: u64,
+    strategy_data: Vec<u8>,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let strategy_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let strategy_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
+    validate_token_supported(&vault, &token_mint)?;
+    validate_free_balance(&vault, &token_mint, amount)?;
+
+    let strategy_index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    let strategy = &vault.yield_strategies[strategy_index];
+    if strategy.strategy_program != *strategy_program.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if strategy.loss_pending {
+        msg!("Strategy has an unacknowledged loss; deploys are blocked");
+        return Err(err(VaultError::StrategyLossUnacknowledged));
+    }
+    ensure_strategy_status(&vault, strategy_index, StrategyStatus::allows_deploy)?;
+    let balance_before = verify_vault_token_account(
+        vault_account.key,
+        &token_mint,
+        &vault.token_program_for(&token_mint),
+        vault_token_account,
+    )?
+    .amount;
+    invoke_strategy(
+        &vault,
+        vault_account.key,
+        &token_mint,
+        strategy_program,
+        &strategy_accounts,
+        strategy_data,
+    )?;
+    let balance_after = unpack_token_account(vault_token_account)?.amount;
+
+    // The strategy may take less than asked, never more, and must not pay in on a deploy
+    let deployed = balance_before
+        .checked_sub(balance_after)
+        .ok_or(VaultError::InvalidAmount)?;
+    if deployed > amount {
+        msg!("Strategy took {} of {} requested", deployed, amount);
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    update_token_balance(&mut vault, &token_mint, -(deployed as i64), &clock)?;
+    let max_allocation_bps = vault.strategy_allocation_cap(strategy_index);
+    let tracked = vault.tracked_balance(&token_mint);
+    let strategy = &mut vault.yield_strategies[strategy_index];
+    strategy.deployed_amount = strategy
+        .deployed_amount
+        .checked_add(deployed)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    let deployed_amount = strategy.deployed_amount;
+    if !YieldStrategyConfig::within_allocation_cap(deployed_amount, tracked, max_allocation_bps) {
+        msg!(
+            "Strategy would hold {} of {} ({} bps cap)",
+            deployed_amount,
+            deployed_amount as u128 + tracked as u128,
+            max_allocation_bps
+        );
+        return Err(err(VaultError::StrategyAllocationExceeded));
+    }
+
+    vault.log_action(
+        ActionKind::StrategyDeployed,
+        *authority.key,
+        token_mint,
+        deployed,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let deployed_event = StrategyDeployedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "strategy_deployed",
+            &clock,
+        ),
+        token_mint,
+        strategy_program: *strategy_program.key,
+        amount: deployed,
+        deployed_amount,
+    };
+    emit_event!(deployed_event, deployed_event);
+
+    debug_msg!("Deployed {} to strategy {}", deployed, strategy_program.key);
+    Ok(())
+}
+
+// Same accounts as DeployToStrategy. Bookkeeping follows the measured inflow, so a strategy
+// that lost principal shows up as negative pnl rather than phantom balance.
+fn process_withdraw_from_strategy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    amount: u64,
+    strategy_data: Vec<u8>,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let strategy_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let strategy_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    let strategy_index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    let strategy = &vault.yield_strategies[strategy_index];
+    if strategy.strategy_program != *strategy_program.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if amount > strategy.deployed_amount {
+        return Err(err(VaultError::InvalidAmount));
+    }
+    let balance_before = verify_vault_token_account(
+        vault_account.key,
+        &token_mint,
+        &vault.token_program_for(&token_mint),
+        vault_token_account,
+    )?
+    .amount;
+    invoke_strategy(
+        &vault,
+        vault_account.key,
+        &token_mint,
+        strategy_program,
+        &strategy_accounts,
+        strategy_data,
+    )?;
+    let balance_after = unpack_token_account(vault_token_account)?.amount;
+
+    let returned = balance_after
+        .checked_sub(balance_before)
+        .ok_or(VaultError::InvalidAmount)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    let pnl = book_strategy_return(&mut vault, strategy_index, amount, returned, &clock)?;
+    let strategy = &vault.yield_strategies[strategy_index];
+    let realized_pnl = strategy.realized_pnl;
+    let deployed_amount = strategy.deployed_amount;
+
+    vault.log_action(
+        ActionKind::StrategyWithdrawn,
+        *authority.key,
+        token_mint,
+        returned,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    if pnl < 0 {
+        let loss_event = StrategyLossEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *authority.key,
+                "strategy_loss",
+                &clock,
+            ),
+            token_mint,
+            strategy_program: *strategy_program.key,
+            principal: amount,
+            returned,
+            loss: pnl.unsigned_abs(),
+            realized_pnl,
+        };
+        emit_event!(loss_event, loss_event);
+        msg!("Strategy lost {} of {} principal", pnl.unsigned_abs(), amount);
+    }
+
+    let withdrawn_event = StrategyWithdrawnEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "strategy_withdrawn",
+            &clock,
+        ),
+        token_mint,
+        strategy_program: *strategy_program.key,
+        principal: amount,
+        returned,
+        pnl,
+        realized_pnl,
+        deployed_amount,
+    };
+    emit_event!(withdrawn_event, withdrawn_event);
+
+    msg!("Withdrew {} principal from strategy, {} returned", amount, returned);
+    Ok(())
+}
+
+fn process_acknowledge_strategy_loss(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    let strategy_index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    let strategy = &mut vault.yield_strategies[strategy_index];
+    if !strategy.loss_pending {
+        msg!("No unacknowledged loss for this strategy");
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    strategy.loss_pending = false;
+    let realized_pnl = strategy.realized_pnl;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::StrategyLossAcknowledged,
+        *authority.key,
+        token_mint,
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let acknowledged_event = StrategyLossAcknowledgedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "strategy_loss_acknowledged",
+            &clock,
+        ),
+        token_mint,
+        realized_pnl,
+    };
+    emit_event!(acknowledged_event, acknowledged_event);
+
+    msg!("Strategy loss acknowledged, realized pnl {}", realized_pnl);
+    Ok(())
+}
+
+// Books `returned` coming back against `principal` of strategy `index`, crediting the vault
+// balance and moving TVL by the realized pnl, which is returned
+fn book_strategy_return(
+    vault: &mut Vault,
+    index: usize,
+    principal: u64,
+    returned: u64,
+    clock: &Clock,
+) -> Result<i64, ProgramError> {
+    let strategy = &mut vault.yield_strategies[index];
+    let token_mint = strategy.token_mint;
+    let pnl = strategy.record_withdrawal(principal, returned)?;
+
+    update_token_balance(vault, &token_mint, returned as i64, clock)?;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = if pnl >= 0 {
+        vault
+            .total_value_locked
+            .checked_add(pnl as u128)
+            .ok_or(VaultError::ArithmeticOverflow)?
+    } else {
+        vault
+            .total_value_locked
+            .checked_sub(u128::from(pnl.unsigned_abs()))
+            .ok_or(VaultError::ArithmeticOverflow)?
+    };
+    Ok(pnl)
+}
+
+fn ensure_strategy_status(
+    vault: &Vault,
+    index: usize,
+    allowed: fn(StrategyStatus) -> bool,
+) -> Result<(), VaultError> {
+    let status = vault.strategy_status(index);
+    if !allowed(status) {
+        msg!("Strategy is {:?}", status);
+        return Err(VaultError::StrategyNotActive);
+    }
+    Ok(())
+}
+
+// Accounts: vault, authority or emergency admin (signer), clock
+fn process_set_strategy_status(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    status: StrategyStatus,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    // The emergency admin can halt a strategy but not put it back to work
+    if status == StrategyStatus::Active {
+        require_authority(&vault, signer)?;
+    } else if require_authority(&vault, signer).is_err() {
+        validate_emergency_admin(&vault, signer.key)?;
+    }
+
+    let index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    let previous = vault.strategy_status(index);
+    vault.set_strategy_status(index, status);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::StrategyStatusChanged,
+        *signer.key,
+        token_mint,
+        status as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let status_event = StrategyStatusChangedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *signer.key,
+            "strategy_status_changed",
+            &clock,
+        ),
+        token_mint,
+        previous,
+        status,
+    };
+    emit_event!(status_event, status_event);
+
+    debug_msg!("Strategy for {} is now {:?}", token_mint, status);
+    Ok(())
+}
+
+// Same accounts as DeployToStrategy, with the authority or emergency admin signing. Pulls the
+// full deployed_amount back regardless of pause or a pending loss, and leaves the strategy
+// Exiting so nothing is deployed into it again until the authority reactivates it.
+fn process_emergency_exit_strategy(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+    strategy_data: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let signer = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let strategy_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let strategy_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    if require_authority(&vault, signer).is_err() {
+        validate_emergency_admin(&vault, signer.key)?;
+    }
+
+    let strategy_index = vault
+        .strategy_index(&token_mint)
+        .ok_or(VaultError::StrategyNotFound)?;
+    let strategy = &vault.yield_strategies[strategy_index];
+    if strategy.strategy_program != *strategy_program.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    let principal = strategy.deployed_amount;
+    let balance_before = verify_vault_token_account(
+        vault_account.key,
+        &token_mint,
+        &vault.token_program_for(&token_mint),
+        vault_token_account,
+    )?
+    .amount;
+    invoke_strategy(
+        &vault,
+        vault_account.key,
+        &token_mint,
+        strategy_program,
+        &strategy_accounts,
+        strategy_data,
+    )?;
+    let balance_after = unpack_token_account(vault_token_account)?.amount;
+
+    let returned = balance_after
+        .checked_sub(balance_before)
+        .ok_or(VaultError::InvalidAmount)?;
+
+    // A short return may only be stuck, so the shortfall stays deployed as outstanding
+    // principal; the loss is booked by whichever WithdrawFromStrategy finally recalls it
+    let booked = principal.min(returned);
+    let clock = load_clock(clock_sysvar)?;
+    let pnl = book_strategy_return(&mut vault, strategy_index, booked, returned, &clock)?;
+    let realized_pnl = vault.yield_strategies[strategy_index].realized_pnl;
+    let outstanding = vault.yield_strategies[strategy_index].deployed_amount;
+    let previous = vault.strategy_status(strategy_index);
+    vault.set_strategy_status(strategy_index, StrategyStatus::Exiting);
+
+    vault.log_action(
+        ActionKind::StrategyEmergencyExit,
+        *signer.key,
+        token_mint,
+        returned,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    if previous != StrategyStatus::Exiting {
+        let status_event = StrategyStatusChangedEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *signer.key,
+                "strategy_status_changed",
+                &clock,
+            ),
+            token_mint,
+            previous,
+            status: StrategyStatus::Exiting,
+        };
+        emit_event!(status_event, status_event);
+    }
+
+    let exited_event = StrategyExitedEvent {
+        base: create_base_event(*vault_account.key, *signer.key, "strategy_exited", &clock),
+        token_mint,
+        strategy_program: *strategy_program.key,
+        principal,
+        returned,
+        pnl,
+        realized_pnl,
+    };
+    emit_event!(exited_event, exited_event);
+
+    msg!(
+        "Exited strategy {}: {} of {} principal returned, {} outstanding",
+        strategy_program.key,
+        returned,
+        principal,
+        outstanding
+    );
+    Ok(())
+}
+
+fn process_harvest_yield(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+) -> ProgramResult {
+    let vault_account = next_account_info(&mut accounts.iter())?;
+    let vault = load_vault_checked(vault_account, program_id)?;
+    if let Some(index) = vault.strategy_index(&token_mint) {
+        ensure_strategy_status(&vault, index, StrategyStatus::allows_harvest)?;
+    }
+    msg!("Processing harvest yield");
+    Ok(())
+}
+
+fn process_compound_yield(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    token_mint: Pubkey,
+) -> ProgramResult {
+    let vault_account = next_account_info(&mut accounts.iter())?;
+    // Compounding redeploys, so it is held to the same status as DeployToStrategy
+    let vault = load_vault_checked(vault_account, program_id)?;
+    if let Some(index) = vault.strategy_index(&token_mint) {
+        ensure_strategy_status(&vault, index, StrategyStatus::allows_deploy)?;
+    }
+    msg!("Processing compound yield");
+    Ok(())
+}
+
+fn process_jupiter_swap(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    msg!("Processing jupiter swap");
+    Ok(())
+}
+
+// Accounts: vault, authority (signer), the vault's input and output token ATAs, the Jupiter
+// program, clock, then the accounts the route needs, forwarded with the vault PDA signing.
+// Balances move by what the two ATAs measure across the CPI, not by what was asked.
+fn process_jupiter_route(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    input_mint: Pubkey,
+    output_mint: Pubkey,
+    amount: u64,
+    auto_add: bool,
+    route: Vec<u8>,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let vault_input_account = next_account_info(account_info_iter)?;
+    let vault_output_account = next_account_info(account_info_iter)?;
+    let router_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    // With auto_add: output mint, payer (signer), associated token program, token program,
+    // system program, for registering the output mint
+    let registration = if auto_add {
+        Some((
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+            next_account_info(account_info_iter)?,
+        ))
+    } else {
+        None
+    };
+    let route_accounts: Vec<AccountInfo> = account_info_iter.cloned().collect();
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if input_mint == output_mint {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    let route_instruction = jupiter::validate_route(&route)?;
+    if *router_program.key != jupiter::PROGRAM_ID {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+    let clock = load_clock(clock_sysvar)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
+    validate_token_supported(&vault, &input_mint)?;
+    validate_free_balance(&vault, &input_mint, amount)?;
+
+    // Output lands in a tracked ATA or not at all. A mint the vault has never seen can be
+    // registered on the way; a deactivated one stays rejected.
+    let unregistered = !vault.supported_tokens.iter().any(|t| t.mint == output_mint);
+    match registration {
+        Some((output_token_mint, payer, associated_token_program, token_program, system_program))
+            if unregistered =>
+        {
+            if !payer.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            // The vault's output ATA goes under the program owning the mint, which
+            // mint_decimals checks is a token program
+            if output_token_mint.owner != token_program.key {
+                return Err(err(VaultError::InvalidMint));
+            }
+            let decimals = mint_decimals(output_token_mint, &output_mint)?;
+            create_vault_token_account(
+                payer,
+                vault_output_account,
+                vault_account,
+                output_token_mint,
+                system_program,
+                token_program,
+                associated_token_program,
+                &[],
+            )?;
+            vault.supported_tokens.push(SupportedToken {
+                mint: output_mint,
+                bump: 0,
+                total_deposited: 0,
+                total_withdrawn: 0,
+                is_active: true,
+                deposit_count: 0,
+                withdrawal_count: 0,
+                decimals,
+            });
+            if *token_program.key != spl_token::ID {
+                let index = vault.supported_tokens.len() - 1;
+                vault.set_supported_token_program(index, *token_program.key);
+            }
+            vault.log_action(
+                ActionKind::AddSupportedToken,
+                *authority.key,
+                output_mint,
+                0,
+                clock.unix_timestamp,
+            );
+            let token_added_event = TokenAddedEvent {
+                base: create_base_event(*vault_account.key, *authority.key, "token_added", &clock),
+                token_mint: output_mint,
+                vault_token_account: *vault_output_account.key,
+            };
+            emit_event!(token_added_event, token_added_event);
+            debug_msg!("Registered output token {} for swap", output_mint);
+        }
+        _ => validate_token_supported(&vault, &output_mint)?,
+    }
+
+    let input_before = verify_vault_token_account(
+        vault_account.key,
+        &input_mint,
+        &vault.token_program_for(&input_mint),
+        vault_input_account,
+    )?
+    .amount;
+    let output_before = verify_vault_token_account(
+        vault_account.key,
+        &output_mint,
+        &vault.token_program_for(&output_mint),
+        vault_output_account,
+    )?
+    .amount;
+    // The vault signs the route, so it may only move the vault's tokens in the two accounts
+    // checked around it
+    for account in &route_accounts {
+        if account.key == vault_input_account.key || account.key == vault_output_account.key {
+            continue;
+        }
+        let vault_owned = is_token_program(account.owner)
+            && unpack_token_account(account)
+                .map_or(false, |token| token.owner == *vault_account.key);
+        if vault_owned {
+            msg!("Route names vault token account {}", account.key);
+            return Err(err(VaultError::InvalidAccountData));
+        }
+    }
+    invoke_with_vault_signer(&vault, vault_account.key, router_program, &route_accounts, route)?;
+    let input_after = unpack_token_account(vault_input_account)?.amount;
+    let output_after = unpack_token_account(vault_output_account)?.amount;
+
+    // The route may spend less than asked, never more, and must not take output tokens
+    let amount_in = input_before
+        .checked_sub(input_after)
+        .ok_or(VaultError::InvalidAmount)?;
+    if amount_in > amount {
+        msg!("Route spent {} of {} allowed", amount_in, amount);
+        return Err(err(VaultError::InvalidAmount));
+    }
+    let amount_out = output_after
+        .checked_sub(output_before)
+        .ok_or(VaultError::InvalidAmount)?;
+
+    let balance_before = vault.tracked_balance(&input_mint);
+    update_token_balance(&mut vault, &input_mint, -(amount_in as i64), &clock)?;
+    update_token_balance(&mut vault, &output_mint, amount_out as i64, &clock)?;
+    vault.accrue_tvl(clock.unix_timestamp)?;
+    vault.total_value_locked = vault
+        .total_value_locked
+        .checked_sub(u128::from(amount_in))
+        .and_then(|tvl| tvl.checked_add(u128::from(amount_out)))
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.record_swap(SwapRecord {
+        input_mint,
+        output_mint,
+        amount_in_measured: amount_in,
+        amount_out_measured: amount_out,
+        timestamp: clock.unix_timestamp,
+        executor: *authority.key,
+    });
+
+    vault.log_action(
+        ActionKind::Swap,
+        *authority.key,
+        input_mint,
+        amount_in,
+        clock.unix_timestamp,
+    );
+    check_circuit_breaker(
+        &mut vault,
+        vault_account.key,
+        authority.key,
+        input_mint,
+        amount_in,
+        balance_before,
+        &clock,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = SwapExecutedEvent {
+        base: create_base_event(*vault_account.key, *authority.key, "swap_executed", &clock),
+        input_mint,
+        output_mint,
+        amount_in,
+        amount_out,
+        route_instruction: route_instruction.to_string(),
+    };
+    emit_event!(event, event);
+
+    msg!(
+        "Swapped {} {} for {} {} via {}",
+        amount_in,
+        input_mint,
+        amount_out,
+        output_mint,
+        route_instruction
+    );
+    Ok(())
+}
+
+// Accounts: vault, authority, token program, clock, then one (vault token account, fee
+// recipient's associated token account) pair per mint to collect. Every mint has to be held
+// under the token program passed, so Token-2022 mints are collected in a call of their own.
+// Every accrual is zeroed and saved before the first transfer goes out, so nothing reached
+// through the token CPI can collect the same fees twice.
+fn process_collect_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !is_token_program(token_program.key) {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    require_authority(&vault, authority)?;
+    require_not_paused(&mut vault, vault_account.key, authority.key, &clock)?;
+
+    let fee_recipient = vault.fee_config.fee_recipient;
+    let mut payouts = Vec::new();
+    while let Some(vault_token_account) = account_info_iter.next() {
+        let recipient_token_account = next_account_info(account_info_iter)?;
+        let token_mint = unpack_token_account(vault_token_account)?.mint;
+        require_token_program(&vault, &token_mint, token_program.key)?;
+        verify_vault_token_account(
+            vault_account.key,
+            &token_mint,
+            token_program.key,
+            vault_token_account,
+        )?;
+        let expected_recipient = get_associated_token_address_with_program_id(
+            &fee_recipient,
+            &token_mint,
+            token_program.key,
+        );
+        if *recipient_token_account.key != expected_recipient {
+            msg!("Fees for {} go to the fee recipient's associated token account", token_mint);
+            return Err(err(VaultError::InvalidAccountData));
+        }
+
+        // A mint passed twice finds nothing left the second time
+        let amount = vault.take_accrued_fee(&token_mint);
+        if amount == 0 {
+            debug_msg!("No fees accrued in {}", token_mint);
+            continue;
+        }
+        vault.log_action(
+            ActionKind::FeesCollected,
+            *authority.key,
+            token_mint,
+            amount,
+            clock.unix_timestamp,
+        );
+        payouts.push((vault_token_account, recipient_token_account, token_mint, amount));
+    }
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    for (vault_token_account, recipient_token_account, token_mint, amount) in payouts {
+        let transfer_ix = transfer_instruction(
+            token_program.key,
+            vault_token_account.key,
+            recipient_token_account.key,
+            vault_account.key,
+            amount,
+        )?;
+        invoke_signed_by_vault(
+            &vault,
+            &transfer_ix,
+            &[
+                vault_token_account.clone(),
+                recipient_token_account.clone(),
+                vault_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+        require_custody_covered(&vault, &token_mint, vault_token_account)?;
+
+        let collected_event = FeesCollectedEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *authority.key,
+                "fees_collected",
+                &clock,
+            ),
+            token_mint,
+            amount,
+            recipient: fee_recipient,
+        };
+        emit_event!(collected_event, collected_event);
+        debug_msg!("Collected {} of {} in fees", amount, token_mint);
+    }
+
+    Ok(())
+}
+
+fn process_transfer_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey,
+) -> ProgramResult {
+    msg!("Processing transfer authority");
+    Ok(())
+}
+
+fn process_update_emergency_admin(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_admin: Pubkey,
+) -> ProgramResult {
+    msg!("Processing update emergency admin");
+    Ok(())
+}
+
+fn process_initialize_governance(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    voting_token_mint: Pubkey,
+    quorum_threshold: u16,
+    proposal_threshold: u64,
+    voting_period: i64,
+    time_lock_delay: i64,
+    execution_threshold: u16,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    require_authority(&vault, authority)?;
+
+    if quorum_threshold > 10_000
+        || execution_threshold > 10_000
+        || voting_period <= 0
+        || time_lock_delay < 0
+    {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    if vault.governance_config.is_some() {
+        msg!("Governance is already initialized; use UpdateGovernanceConfig to change it");
+        return Err(err(VaultError::InvalidInstruction));
+    }
+
+    vault.governance_config = Some(GovernanceConfig {
+        voting_token_mint,
+        quorum_threshold,
+        proposal_threshold,
+        voting_period,
+        time_lock_delay,
+        execution_threshold,
+        timelock_delay: time_lock_delay,
+    });
+
+    vault.log_action(
+        ActionKind::GovernanceConfigUpdated,
+        *authority.key,
+        voting_token_mint,
+        0,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    debug_msg!("Governance initialized, voting with {}", voting_token_mint);
+    Ok(())
+}
+
+fn process_create_governance_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    title: String,
+    description: String,
+    instructions: Vec<Vec<u8>>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let proposer_token_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let governance_config = vault
+        .governance_config
+        .as_ref()
+        .ok_or(VaultError::GovernanceNotInitialized)?;
+
+    // Proposing takes proposal_threshold of the voting token, held by the proposer
+    if *proposer_token_account.owner != spl_token::ID {
+        return Err(err(VaultError::InvalidAccountOwner));
+    }
+    let voting_tokens = unpack_token_account(proposer_token_account)?;
+    if voting_tokens.mint != governance_config.voting_token_mint {
+        return Err(err(VaultError::TokenMintMismatch));
+    }
+    if voting_tokens.owner != *proposer.key {
+        return Err(err(VaultError::UserTokenAccountOwnerMismatch));
+    }
+    if voting_tokens.amount < governance_config.proposal_threshold {
+        msg!(
+            "Proposer holds {} voting tokens, proposing takes {}",
+            voting_tokens.amount,
+            governance_config.proposal_threshold
+        );
+        return Err(err(VaultError::InsufficientAuthority));
+    }
+
+    // Malformed payloads fail here rather than when the vote is over
+    GovernanceProposal::validate_text(&title, &description)?;
+    let decoded = GovernanceInstruction::validate_all(&instructions)?;
+    let requires_council =
+        governance::requires_council(vault.param(ParamKey::CouncilCategories), &decoded);
+
+    let proposal_id = vault.next_governance_proposal_id;
+    let end_time = clock.unix_timestamp + governance_config.voting_period;
+    if requires_council {
+        vault.prune_council_reviews();
+        if vault.council_reviews.len() >= CouncilReview::MAX_PENDING {
+            msg!(
+                "{} proposals already await the council, max {}",
+                vault.council_reviews.len(),
+                CouncilReview::MAX_PENDING
+            );
+            return Err(err(VaultError::ProposalRateLimited));
+        }
+    }
+    vault.governance_proposals.push(GovernanceProposal {
+        id: proposal_id,
+        proposer: *proposer.key,
+        title: title.clone(),
+        description,
+        instructions,
+        created_at: clock.unix_timestamp,
+        start_time: clock.unix_timestamp,
+        end_time,
+        ..Default::default()
+    });
+    vault.next_governance_proposal_id += 1;
+    let effective_supply = vault.effective_registry_supply(clock.unix_timestamp);
+    vault.proposal_snapshots.push(ProposalSnapshot {
+        proposal_id,
+        effective_supply,
+    });
+    if requires_council {
+        vault.council_reviews.push(CouncilReview {
+            proposal_id,
+            approved_at: None,
+        });
+    }
+
+    vault.log_action(
+        ActionKind::GovernanceProposalCreated,
+        *proposer.key,
+        Pubkey::default(),
+        proposal_id,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = GovernanceProposalCreatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *proposer.key,
+            "governance_proposal_created",
+            &clock,
+        ),
+        proposal_id,
+        proposer: *proposer.key,
+        title,
+        end_time,
+    };
+    emit_event!(event, event);
+
+    msg!("Governance proposal {} created, voting ends at {}", proposal_id, end_time);
+    if requires_council {
+        msg!("Proposal {} also needs the council's approval", proposal_id);
+    }
+    Ok(())
+}
+
+fn process_cast_vote(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+    vote_type: crate::state::VoteType,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let voter = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    if vault.governance_config.is_none() {
+        return Err(err(VaultError::GovernanceNotInitialized));
+    }
+
+    let proposal_index = vault
+        .governance_proposal_index(proposal_id)
+        .ok_or(VaultError::GovernanceProposalNotFound)?;
+    let proposal = &vault.governance_proposals[proposal_index];
+    if proposal.executed
+        || proposal.cancelled
+        || clock.unix_timestamp < proposal.start_time
+        || clock.unix_timestamp >= proposal.end_time
+        || vault.proposal_settlement(proposal_id).is_some()
+    {
+        msg!(
+            "Proposal {} takes votes from {} until {}",
+            proposal_id,
+            proposal.start_time,
+            proposal.end_time
+        );
+        return Err(err(VaultError::VotingClosed));
+    }
+    if vault
+        .vote_records
+        .iter()
+        .any(|r| r.proposal_id == proposal_id && r.voter == *voter.key)
+    {
+        return Err(err(VaultError::AlreadyVoted));
+    }
+
+    // Only tokens escrowed before the proposal opened vote, so a balance can't vote, move to
+    // another wallet and vote again; the boost is the lock's as it stands today
+    let lock = vault
+        .vote_lock(voter.key)
+        .ok_or(VaultError::VoterNotRegistered)?;
+    if lock.locked_at >= proposal.created_at {
+        msg!(
+            "Voting tokens locked at {}, proposal {} opened at {}",
+            lock.locked_at,
+            proposal_id,
+            proposal.created_at
+        );
+        return Err(err(VaultError::VoteLockTooRecent));
+    }
+    let raw_balance = lock.amount;
+    let locked_power = vault.locked_power(lock, clock.unix_timestamp);
+    let boost = locked_power - raw_balance;
+    let voting_power = governance::effective_power(vault.voting_model, locked_power);
+    if voting_power == 0 {
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    let proposal = &mut vault.governance_proposals[proposal_index];
+    let tally = match vote_type {
+        VoteType::For => &mut proposal.for_votes,
+        VoteType::Against => &mut proposal.against_votes,
+        VoteType::Abstain => &mut proposal.abstain_votes,
+    };
+    *tally = tally
+        .checked_add(voting_power)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    // Records written before raw balances were kept have no entry; pad so the index lines up
+    let record_count = vault.vote_records.len();
+    vault.vote_raw_balances.resize(record_count, 0);
+    vault.vote_records.push(VoteRecord {
+        proposal_id,
+        voter: *voter.key,
+        vote_type,
+        voting_power,
+        voted_at: clock.unix_timestamp,
+    });
+    vault.vote_raw_balances.push(raw_balance);
+
+    vault.log_action(
+        ActionKind::GovernanceVoteCast,
+        *voter.key,
+        Pubkey::default(),
+        proposal_id,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = GovernanceVoteCastEvent {
+        base: create_base_event(*vault_account.key, *voter.key, "governance_vote_cast", &clock),
+        proposal_id,
+        voter: *voter.key,
+        vote_type,
+        voting_power,
+    };
+    emit_event!(event, event);
+
+    msg!(
+        "Vote on proposal {}: {} voting tokens, boosted by {}, count as {}",
+        proposal_id,
+        raw_balance,
+        boost,
+        voting_power
+    );
+    Ok(())
+}
+
+fn process_queue_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let caller = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let config = vault
+        .governance_config
+        .clone()
+        .ok_or(VaultError::GovernanceNotInitialized)?;
+    let proposal_index = vault
+        .governance_proposal_index(proposal_id)
+        .ok_or(VaultError::GovernanceProposalNotFound)?;
+    let effective_supply = vault.proposal_effective_supply(proposal_id);
+    let has_snapshot = vault.has_proposal_snapshot(proposal_id);
+
+    let proposal = &mut vault.governance_proposals[proposal_index];
+    if proposal.queued || proposal.executed || proposal.cancelled {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    if clock.unix_timestamp < proposal.end_time {
+        msg!("Voting on proposal {} runs until {}", proposal_id, proposal.end_time);
+        return Err(err(VaultError::VotingClosed));
+    }
+    if !has_snapshot {
+        msg!("Proposal {} has no supply snapshot to measure quorum against", proposal_id);
+        return Err(err(VaultError::ProposalNotPassed));
+    }
+
+    let quorum = governance::quorum_reached(
+        proposal.for_votes,
+        proposal.against_votes,
+        proposal.abstain_votes,
+        effective_supply,
+        config.quorum_threshold,
+    );
+    let approved = governance::approved(
+        proposal.for_votes,
+        proposal.against_votes,
+        config.execution_threshold,
+    );
+    if !quorum || !approved {
+        msg!(
+            "Proposal {}: for {}, against {}, abstain {} of {} effective supply",
+            proposal_id,
+            proposal.for_votes,
+            proposal.against_votes,
+            proposal.abstain_votes,
+            effective_supply
+        );
+        return Err(err(VaultError::ProposalNotPassed));
+    }
+
+    let eta = clock.unix_timestamp + config.time_lock_delay;
+    proposal.queued = true;
+    proposal.eta = Some(eta);
+
+    vault.log_action(
+        ActionKind::GovernanceProposalQueued,
+        *caller.key,
+        Pubkey::default(),
+        proposal_id,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = GovernanceProposalQueuedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *caller.key,
+            "governance_proposal_queued",
+            &clock,
+        ),
+        proposal_id,
+        eta,
+    };
+    emit_event!(event, event);
+
+    msg!("Proposal {} queued, executable from {}", proposal_id, eta);
+    Ok(())
+}
+
+fn process_execute_governance_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let caller = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !caller.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    // The caller's signature is the only one the CPIs carry, so it has to be the authority's
+    // or the council's: the multisig signer, through an executed multisig transaction
+    let is_council = vault.multi_sig.as_ref().map_or(false, |multi_sig| {
+        multi_sig.signer_address(vault_account.key, program_id) == *caller.key
+    });
+    if !is_council {
+        require_authority(&vault, caller)?;
+    }
+
+    let proposal_index = vault
+        .governance_proposal_index(proposal_id)
+        .ok_or(VaultError::GovernanceProposalNotFound)?;
+    let proposal = &vault.governance_proposals[proposal_index];
+    if !proposal.queued || proposal.executed || proposal.cancelled {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    // Queueing already checked quorum and the execution threshold; Tick's record, if any,
+    // has to agree
+    let settled = vault.proposal_settlement(proposal_id);
+    if settled.map_or(false, |kind| kind != SettledKind::ProposalPassed) {
+        msg!("Proposal {} settled as {:?}", proposal_id, settled);
+        return Err(err(VaultError::ProposalNotPassed));
+    }
+    let eta = proposal.eta.ok_or(VaultError::InvalidInstruction)?;
+    if clock.unix_timestamp < eta {
+        msg!("Proposal {} executable from {}", proposal_id, eta);
+        return Err(err(VaultError::ExecutionDelayActive));
+    }
+    if let Some(review) = vault.council_review(proposal_id) {
+        if review.approved_at.is_none() {
+            msg!("Proposal {} passed the token vote but awaits the council", proposal_id);
+            return Err(err(VaultError::CouncilApprovalRequired));
+        }
+    }
+
+    // Checked again rather than trusted from creation, so a stored proposal naming a program
+    // outside GovernanceInstruction::is_allowed_program never gets to call it
+    let instructions = GovernanceInstruction::validate_all(&proposal.instructions)?;
+    if let Some(missing) = instructions
+        .iter()
+        .flat_map(|instruction| {
+            let keys = instruction.accounts.iter().map(|acc| acc.pubkey);
+            std::iter::once(instruction.program_id).chain(keys)
+        })
+        .find(|key| !accounts.iter().any(|info| info.key == key))
+    {
+        msg!("Proposal account {} was not passed in", missing);
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+
+    // Marked executed before the CPIs, as ExecuteMultiSigTransaction does, so a proposal
+    // calling back into the vault can neither run itself again nor be overwritten by our copy
+    vault.governance_proposals[proposal_index].executed = true;
+    vault.log_action(
+        ActionKind::GovernanceProposalExecuted,
+        *caller.key,
+        Pubkey::default(),
+        proposal_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    for instruction in &instructions {
+        let ix = Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|acc| AccountMeta {
+                    pubkey: acc.pubkey,
+                    is_signer: acc.is_signer,
+                    is_writable: acc.is_writable,
+                })
+                .collect(),
+            data: instruction.data.clone(),
+        };
+        invoke(&ix, accounts)?;
+    }
+
+    let event = GovernanceProposalExecutedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *caller.key,
+            "governance_proposal_executed",
+            &clock,
+        ),
+        proposal_id,
+    };
+    emit_event!(event, event);
+
+    msg!("Proposal {} executed, {} instructions", proposal_id, instructions.len());
+    Ok(())
+}
+
+// Only reachable through ExecuteMultiSigTransaction: the multisig signer PDA signing is the
+// council's threshold of owners having approved
+fn process_council_approve_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proposal_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let proposal_index = vault
+        .governance_proposal_index(proposal_id)
+        .ok_or(VaultError::GovernanceProposalNotFound)?;
+    let proposal = &vault.governance_proposals[proposal_index];
+    if proposal.executed || proposal.cancelled {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    let review = vault
+        .council_reviews
+        .iter_mut()
+        .find(|r| r.proposal_id == proposal_id)
+        .ok_or_else(|| {
+            msg!("Proposal {} does not need the council", proposal_id);
+            err(VaultError::InvalidInstruction)
+        })?;
+    if review.approved_at.is_some() {
+        msg!("Council already approved proposal {}", proposal_id);
+        return Err(err(VaultError::InvalidInstruction));
+    }
+    review.approved_at = Some(clock.unix_timestamp);
+
+    vault.log_action(
+        ActionKind::CouncilProposalApproved,
+        *multisig_signer.key,
+        Pubkey::default(),
+        proposal_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = CouncilProposalApprovedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "council_proposal_approved",
+            &clock,
+        ),
+        proposal_id,
+    };
+    emit_event!(event, event);
+
+    msg!("Council approved proposal {}", proposal_id);
+    Ok(())
+}
+
+// UpdateGovernanceConfig's fields, bundled to keep the processor signature short
+struct GovernanceConfigUpdate {
+    quorum_threshold: u16,
+    proposal_threshold: u64,
+    voting_period: i64,
+    time_lock_delay: i64,
+    execution_threshold: u16,
+    voting_model: VotingModel,
+    max_lock_duration: i64,
+    max_boost_bps: u16,
+}
+
+fn process_update_governance_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    update: GovernanceConfigUpdate,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    require_authority(&vault, authority)?;
+
+    if update.quorum_threshold > 10_000
+        || update.execution_threshold > 10_000
+        || update.voting_period <= 0
+        || update.time_lock_delay < 0
+        || update.max_lock_duration < 0
+        || update.max_boost_bps > governance::MAX_BOOST_BPS
+    {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+
+    // Votes already cast were weighed under the current model
+    if update.voting_model != vault.voting_model && vault.has_active_proposal(clock.unix_timestamp)
+    {
+        msg!("Voting model can't change while a proposal is open for votes");
+        return Err(err(VaultError::GovernanceProposalActive));
+    }
+
+    let config = vault
+        .governance_config
+        .as_mut()
+        .ok_or(VaultError::GovernanceNotInitialized)?;
+    config.quorum_threshold = update.quorum_threshold;
+    config.proposal_threshold = update.proposal_threshold;
+    config.voting_period = update.voting_period;
+    config.time_lock_delay = update.time_lock_delay;
+    config.timelock_delay = update.time_lock_delay;
+    config.execution_threshold = update.execution_threshold;
+    vault.voting_model = update.voting_model;
+    vault.max_lock_duration = update.max_lock_duration;
+    vault.max_boost_bps = update.max_boost_bps;
+
+    vault.log_action(
+        ActionKind::GovernanceConfigUpdated,
+        *authority.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    msg!("Governance config updated, voting model {:?}", update.voting_model);
+    Ok(())
+}
+
+// Voter's voting token account and the vault's escrow ATA for the voting mint
+fn validate_voter_accounts(
+    vault_key: &Pubkey,
+    voting_token_mint: &Pubkey,
+    voter: &Pubkey,
+    voter_token_account: &AccountInfo,
+    vault_token_account: &AccountInfo,
+    token_program: &AccountInfo,
+) -> ProgramResult {
+    if *token_program.key != spl_token::ID {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if *voter_token_account.owner != spl_token::ID {
+        return Err(err(VaultError::InvalidAccountOwner));
+    }
+    let voter_tokens = unpack_token_account(voter_token_account)?;
+    if voter_tokens.mint != *voting_token_mint {
+        return Err(err(VaultError::TokenMintMismatch));
+    }
+    if voter_tokens.owner != *voter {
+        return Err(err(VaultError::UserTokenAccountOwnerMismatch));
+    }
+    verify_vault_token_account(vault_key, voting_token_mint, token_program.key, vault_token_account)?;
+    Ok(())
+}
+
+fn process_register_voter_with_lock(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    lock_duration: i64,
+) -> ProgramResult {
+    validate_amount(amount, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let voter = next_account_info(account_info_iter)?;
+    let voter_token_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if lock_duration < 0 {
+        return Err(err(VaultError::InvalidInstruction));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let voting_token_mint = vault
+        .governance_config
+        .as_ref()
+        .ok_or(VaultError::GovernanceNotInitialized)?
+        .voting_token_mint;
+    if vault.voter_registry.iter().any(|v| v.voter == *voter.key) {
+        return Err(err(VaultError::VoterAlreadyRegistered));
+    }
+    validate_voter_accounts(
+        vault_account.key,
+        &voting_token_mint,
+        voter.key,
+        voter_token_account,
+        vault_token_account,
+        token_program,
+    )?;
+
+    let transfer_ix = token_instruction::transfer(
+        token_program.key,
+        voter_token_account.key,
+        vault_token_account.key,
+        voter.key,
+        &[],
+        amount,
+    )?;
+    invoke(
+        &transfer_ix,
+        &[
+            voter_token_account.clone(),
+            vault_token_account.clone(),
+            voter.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    let unlock_at = clock
+        .unix_timestamp
+        .checked_add(lock_duration)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    let lock = VoteLock {
+        voter: *voter.key,
+        amount,
+        locked_at: clock.unix_timestamp,
+        unlock_at,
+    };
+    let voting_power = vault.locked_power(&lock, clock.unix_timestamp);
+    vault.vote_locks.push(lock);
+    vault.voter_registry.push(VoterRegistry {
+        voter: *voter.key,
+        voting_power,
+        registered_at: clock.unix_timestamp,
+    });
+
+    vault.log_action(
+        ActionKind::VoterRegistered,
+        *voter.key,
+        voting_token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = VoterRegisteredEvent {
+        base: create_base_event(*vault_account.key, *voter.key, "voter_registered", &clock),
+        voter: *voter.key,
+        amount,
+        unlock_at,
+        voting_power,
+    };
+    emit_event!(event, event);
+
+    msg!(
+        "Voter {} locked {} voting tokens until {}, voting power {}",
+        voter.key,
+        amount,
+        unlock_at,
+        voting_power
+    );
+    Ok(())
+}
+
+fn process_unregister_voter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let voter = next_account_info(account_info_iter)?;
+    let voter_token_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !voter.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let voting_token_mint = vault
+        .governance_config
+        .as_ref()
+        .ok_or(VaultError::GovernanceNotInitialized)?
+        .voting_token_mint;
+    let registry_index = vault
+        .voter_registry
+        .iter()
+        .position(|v| v.voter == *voter.key)
+        .ok_or(VaultError::VoterNotRegistered)?;
+    let lock_index = vault.vote_locks.iter().position(|l| l.voter == *voter.key);
+    if let Some(lock) = lock_index.map(|i| &vault.vote_locks[i]) {
+        if clock.unix_timestamp < lock.unlock_at {
+            msg!("Voting tokens stay locked until {}", lock.unlock_at);
+            return Err(err(VaultError::VoteLockActive));
+        }
+    }
+    validate_voter_accounts(
+        vault_account.key,
+        &voting_token_mint,
+        voter.key,
+        voter_token_account,
+        vault_token_account,
+        token_program,
+    )?;
+
+    let amount = lock_index.map_or(0, |i| vault.vote_locks[i].amount);
+    if amount > 0 {
+        let transfer_ix = token_instruction::transfer(
+            token_program.key,
+            vault_token_account.key,
+            voter_token_account.key,
+            vault_account.key,
+            &[],
+            amount,
+        )?;
+        invoke_signed_by_vault(
+            &vault,
+            &transfer_ix,
+            &[
+                vault_token_account.clone(),
+                voter_token_account.clone(),
+                vault_account.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+    if let Some(i) = lock_index {
+        vault.vote_locks.remove(i);
+    }
+    vault.voter_registry.remove(registry_index);
+
+    vault.log_action(
+        ActionKind::VoterUnregistered,
+        *voter.key,
+        voting_token_mint,
+        amount,
+        clock.unix_timestamp,
+    );
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let event = VoterUnregisteredEvent {
+        base: create_base_event(*vault_account.key, *voter.key, "voter_unregistered", &clock),
+        voter: *voter.key,
+        amount,
+    };
+    emit_event!(event, event);
+
+    debug_msg!("Voter {} unregistered, {} voting tokens returned", voter.key, amount);
+    Ok(())
+}
+
+fn process_update_vault_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    config: VaultConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_authority(&vault, authority)?;
+
+    // Slot records are only meaningful while the guard is on
+    if !config.same_slot_guard {
+        vault.user_action_slots.clear();
+    }
+    vault.config = config.clone();
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::UpdateConfig,
+        *authority.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let config_event = VaultConfigUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "vault_config_updated",
+            &clock,
+        ),
+        config,
+    };
+    emit_event!(config_event, config_event);
+
+    msg!("Vault config updated");
+    Ok(())
+}
+
+// Rewrites a vault stored in an older layout. Already-current vaults are left alone.
+// Accounts: vault, authority, clock, then payer and system program when the account has to
+// grow to fit the new layout.
+fn process_migrate_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    validate_account_ownership(vault_account, program_id)?;
+
+    let vault_data = vault_account.data.borrow();
+    match Vault::load(&vault_data) {
+        Ok(vault) => {
+            require_authority(&vault, authority)?;
+            msg!("Vault already at state version {}", vault.state_version);
+            return Ok(());
+        }
+        Err(e) if e != err(VaultError::StateMigrationRequired) => return Err(e),
+        Err(_) => {}
+    }
+
+    // Versions 1 and 2 are checked first: without time locks their bytes are also a valid
+    // version 0
+    let widened = LegacyVaultHeadV2::load_vault(&vault_data);
+    let (mut vault, from_version, time_locks_migrated) = match widened {
+        Ok(vault) if matches!(vault.state_version, 1 | 2) => {
+            let from_version = vault.state_version;
+            (vault, from_version, 0)
+        }
+        _ => {
+            let legacy = LegacyVaultV0::load(&vault_data)?;
+            let time_locks_migrated = legacy.time_locks.len() as u64;
+            (legacy.into(), 0, time_locks_migrated)
+        }
+    };
+    if vault.authority != *authority.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    drop(vault_data);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.state_version = Vault::STATE_VERSION;
+    vault.log_action(
+        ActionKind::Migrate,
+        *authority.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+
+    let required_space = vault.required_space()?;
+    if required_space > vault_account.data_len() {
+        let payer = next_account_info(account_info_iter)?;
+        let system_program_account = next_account_info(account_info_iter)?;
+        if !payer.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *system_program_account.key != system_program::ID {
+            return Err(err(VaultError::InvalidAccountData));
+        }
+
+        let new_len = required_space + Vault::DYNAMIC_HEADROOM;
+        let required_lamports = Rent::get()?.minimum_balance(new_len);
+        if vault_account.lamports() < required_lamports {
+            invoke(
+                &system_instruction::transfer(
+                    payer.key,
+                    vault_account.key,
+                    required_lamports - vault_account.lamports(),
+                ),
+                &[
+                    payer.clone(),
+                    vault_account.clone(),
+                    system_program_account.clone(),
+                ],
+            )?;
+        }
+        vault_account.realloc(new_len, false)?;
+        msg!("Vault account grown to {} bytes", new_len);
+    }
+
+    // Versions 0 and 1 didn't track SOL, so whatever sits above the rent floor and escrowed
+    // tips is the vault's; the top-up above only reaches the floor
+    if from_version < 2 {
+        let rent_floor = Rent::get()?.minimum_balance(vault_account.data_len());
+        vault.tracked_lamports = vault.untracked_lamports(vault_account.lamports(), rent_floor);
+        msg!("Tracked lamports seeded at {}", vault.tracked_lamports);
+    }
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let migrated_event = VaultMigratedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "vault_migrated",
+            &clock,
+        ),
+        from_version,
+        to_version: Vault::STATE_VERSION,
+        time_locks_migrated,
+    };
+    emit_event!(migrated_event, migrated_event);
+
+    msg!(
+        "Vault migrated from state version {} to {}",
+        from_version,
+        Vault::STATE_VERSION
+    );
+    msg!("Time locks migrated: {}", time_locks_migrated);
+    Ok(())
+}
+
+// Multi-sig processor functions. Without roles, as from CreateMultiSigTransaction, each
+// account's role is derived from its address and flags.
+fn process_create_multi_sig_transaction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target_program_id: Pubkey,
+    transaction_accounts: Vec<crate::state::TransactionAccount>,
+    roles: Option<Vec<AccountRole>>,
+    assertions: Vec<BalanceAssertion>,
+    data: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    // Only owners of an initialized multisig can propose
+    require_multisig_owner(&vault, proposer)?;
+
+    MultiSigTransaction::validate_payload(&transaction_accounts, &data)?;
+
+    let clock = load_clock(clock_sysvar)?;
+
+    let proposals_in_window = match vault.record_proposal(proposer.key, clock.unix_timestamp) {
+        Ok(count) => count,
+        Err(e) => {
+            msg!(
+                "Proposer {} reached {} proposals per day",
+                proposer.key,
+                vault.effective_max_proposals_per_day()
+            );
+            return Err(e.into());
+        }
+    };
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+
+    // Find owner index
+    let owner_index = multi_sig
+        .owners
+        .iter()
+        .position(|owner| owner == proposer.key)
+        .ok_or(VaultError::InvalidOwner)?;
+
+    let mut signers = vec![false; multi_sig.owners.len()];
+    signers[owner_index] = true;
+    let threshold = multi_sig.threshold;
+
+    let signer_address = multi_sig.signer_address(vault_account.key, program_id);
+    let roles = roles.unwrap_or_else(|| {
+        transaction_accounts
+            .iter()
+            .map(|account| AccountRole::derive(account, vault_account.key, &signer_address))
+            .collect()
+    });
+    AccountRole::validate(&transaction_accounts, &roles, vault_account.key, &signer_address)?;
+    BalanceAssertion::validate(&assertions, &transaction_accounts)?;
+
+    let transaction = MultiSigTransaction {
+        multisig: *vault_account.key,
+        program_id: target_program_id,
+        accounts: transaction_accounts.clone(),
+        data: data.clone(),
+        signers,
+        did_execute: false,
+        proposer: *proposer.key,
+        created_at: clock.unix_timestamp,
+    };
+
+    let expired = vault.prune_expired_transactions(clock.unix_timestamp);
+    let transaction_id = vault.push_multisig_transaction(transaction);
+    vault.transaction_roles.push(TransactionRoles { transaction_id, roles });
+    if !assertions.is_empty() {
+        vault.transaction_assertions.push(TransactionAssertions {
+            transaction_id,
+            assertions,
+        });
+    }
+    let ttl = vault.param(ParamKey::MultisigTransactionTtl) as i64;
+    if ttl > 0 {
+        vault.set_transaction_expiry(transaction_id, clock.unix_timestamp + ttl);
+    }
+    // The proposer's own approval already meets a threshold of one
+    if threshold <= 1 {
+        vault.record_threshold_reached(transaction_id, clock.unix_timestamp);
+    }
+    vault.last_owner_activity = clock.unix_timestamp;
+
+    vault.log_action(
+        ActionKind::MultiSigProposed,
+        *proposer.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Emit event
+    let transaction_event = MultiSigTransactionCreatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *proposer.key,
+            "multisig_transaction_created",
+            &clock,
+        ),
+        transaction_id,
+        proposer: *proposer.key,
+        target_program: target_program_id,
+        instruction_count: transaction_accounts.len(),
+    };
+    emit_event!(transaction_event, transaction_event);
+    if !expired.is_empty() {
+        let pruned_event = MultiSigTransactionsPrunedEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *proposer.key,
+                "multisig_transactions_pruned",
+                &clock,
+            ),
+            transaction_ids: expired,
+            remaining: vault.multi_sig_transactions.len(),
+        };
+        emit_event!(pruned_event, pruned_event);
+    }
+
+    msg!(
+        "Multi-sig transaction {} created by {}",
+        transaction_id,
+        proposer.key
+    );
+    msg!(
+        "Proposals in window: {}/{}",
+        proposals_in_window,
+        vault.effective_max_proposals_per_day()
+    );
+    Ok(())
+}
+
+fn process_create_transaction_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    total_len: u32,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let vault = load_vault_checked(vault_account, program_id)?;
+    require_multisig_owner(&vault, proposer)?;
+    if total_len == 0 || total_len as usize > MultiSigTransaction::MAX_DATA_LEN {
+        msg!(
+            "Buffer of {} bytes, max {}",
+            total_len,
+            MultiSigTransaction::MAX_DATA_LEN
+        );
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+
+    let (expected_buffer, bump) =
+        TransactionBuffer::find_address(vault_account.key, proposer.key, program_id);
+    if expected_buffer != *buffer_account.key || !buffer_account.data_is_empty() {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let space = TransactionBuffer::space(total_len as usize);
+    let rent = load_rent(rent_sysvar)?;
+    let required_lamports = rent.minimum_balance(space);
+    if buffer_account.lamports() < required_lamports {
+        invoke(
+            &system_instruction::transfer(
+                proposer.key,
+                buffer_account.key,
+                required_lamports - buffer_account.lamports(),
+            ),
+            &[proposer.clone(), buffer_account.clone(), system_program.clone()],
+        )?;
+    }
+    let bump_seed = [bump];
+    let buffer_seeds: &[&[u8]] = &[
+        TransactionBuffer::SEED,
+        vault_account.key.as_ref(),
+        proposer.key.as_ref(),
+        &bump_seed,
+    ];
+    invoke_signed(
+        &system_instruction::allocate(buffer_account.key, space as u64),
+        &[buffer_account.clone(), system_program.clone()],
+        &[buffer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(buffer_account.key, program_id),
+        &[buffer_account.clone(), system_program.clone()],
+        &[buffer_seeds],
+    )?;
+
+    let buffer = TransactionBuffer {
+        vault: *vault_account.key,
+        proposer: *proposer.key,
+        bump,
+        total_len,
+        written_len: 0,
+        data: vec![0; total_len as usize],
+    };
+    buffer.save(&mut buffer_account.data.borrow_mut())?;
+
+    msg!("Transaction buffer of {} bytes created by {}", total_len, proposer.key);
+    Ok(())
+}
+
+fn process_write_transaction_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    offset: u32,
+    chunk: Vec<u8>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // A proposer removed from the owners since creating the buffer can't keep filling it
+    let vault = load_vault_checked(vault_account, program_id)?;
+    require_multisig_owner(&vault, proposer)?;
+    let mut buffer = load_transaction_buffer(program_id, buffer_account, vault_account.key)?;
+    if buffer.proposer != *proposer.key {
+        return Err(err(VaultError::InvalidOwner));
+    }
+    buffer.write(offset, &chunk)?;
+    buffer.save(&mut buffer_account.data.borrow_mut())?;
+
+    msg!(
+        "Transaction buffer has {}/{} bytes",
+        buffer.written_len,
+        buffer.total_len
+    );
+    Ok(())
+}
+
+// The vault, proposer and clock are the first accounts, as CreateMultiSigTransaction takes
+// them, so the proposal goes through the same checks as one made directly
+fn process_finalize_transaction_from_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    target_program_id: Pubkey,
+    transaction_accounts: Vec<crate::state::TransactionAccount>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let buffer = load_transaction_buffer(program_id, buffer_account, vault_account.key)?;
+    if buffer.proposer != *proposer.key {
+        return Err(err(VaultError::InvalidOwner));
+    }
+    if !buffer.is_complete() {
+        msg!(
+            "Transaction buffer has {}/{} bytes",
+            buffer.written_len,
+            buffer.total_len
+        );
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+    let data_hash = solana_program::hash::hash(&buffer.data).to_bytes();
+
+    process_create_multi_sig_transaction(
+        program_id,
+        accounts,
+        target_program_id,
+        transaction_accounts,
+        None,
+        Vec::new(),
+        buffer.data,
+    )?;
+    let transaction_id = load_vault_checked(vault_account, program_id)?.next_transaction_id - 1;
+
+    close_transaction_buffer(buffer_account, proposer)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    let finalized_event = TransactionBufferFinalizedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *proposer.key,
+            "transaction_buffer_finalized",
+            &clock,
+        ),
+        transaction_id,
+        proposer: *proposer.key,
+        data_len: buffer.total_len,
+        data_hash,
+    };
+    emit_event!(finalized_event, finalized_event);
+    Ok(())
+}
+
+// Throws away a buffer that was sized wrong or is no longer wanted, e.g. because its proposer
+// left the owners, which frees the proposer's buffer address again. The proposer or the vault
+// authority signs; the rent always goes back to the proposer. Accounts: vault, buffer
+// (writable), signer, proposer (writable).
+fn process_close_transaction_buffer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let buffer_account = next_account_info(account_info_iter)?;
+    let signer = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let vault = load_vault_checked(vault_account, program_id)?;
+    let buffer = load_transaction_buffer(program_id, buffer_account, vault_account.key)?;
+    if buffer.proposer != *proposer.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    if *signer.key != buffer.proposer {
+        require_authority(&vault, signer)?;
+    }
+    close_transaction_buffer(buffer_account, proposer)?;
+
+    msg!("Transaction buffer of {} closed by {}", buffer.proposer, signer.key);
+    Ok(())
+}
+
+// A buffer of this program's staged for `vault_key`
+fn load_transaction_buffer(
+    program_id: &Pubkey,
+    buffer_account: &AccountInfo,
+    vault_key: &Pubkey,
+) -> Result<TransactionBuffer, ProgramError> {
+    if buffer_account.owner != program_id {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    let buffer = TransactionBuffer::load(&buffer_account.data.borrow())?;
+    if buffer.vault != *vault_key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    Ok(buffer)
+}
+
+// Refunds the buffer's rent to `proposer`; the runtime drops it once it holds no lamports
+fn close_transaction_buffer(buffer_account: &AccountInfo, proposer: &AccountInfo) -> ProgramResult {
+    pay_out_of_vault(buffer_account, proposer, buffer_account.lamports())?;
+    buffer_account.realloc(0, false)
+}
+
+fn process_approve_multi_sig_transaction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let approver = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !approver.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    record_approval(program_id, vault_account, approver.key, transaction_id, clock_sysvar)
+}
+
+// Accounts: vault, instructions sysvar, clock
+fn process_approve_with_signature(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+    owner: Pubkey,
+    signature_index: u8,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let instructions_sysvar = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let owner_set_seqno = load_vault_checked(vault_account, program_id)?.owner_set_seqno;
+    let message =
+        MultiSigTransaction::approval_message(vault_account.key, transaction_id, owner_set_seqno);
+    verify_ed25519_signature(instructions_sysvar, &owner, &message, signature_index)?;
+
+    debug_msg!("Approval by {} carried by an ed25519 signature", owner);
+    record_approval(program_id, vault_account, &owner, transaction_id, clock_sysvar)
+}
+
+// Checks that the instruction right before the current one is an ed25519 program
+// instruction whose `signature_index`-th entry verified `signer`'s signature over
+// `message`. The ed25519 program fails the whole transaction on a bad signature, so what
+// is left is making sure it checked the right key and message. Offsets must point into
+// that same instruction's data.
+fn verify_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+    signature_index: u8,
+) -> Result<(), ProgramError> {
+    if *instructions_sysvar.key != sysvar::instructions::id() {
+        return Err(err(VaultError::InvalidSysvar));
+    }
+    let current = sysvar::instructions::load_current_index_checked(instructions_sysvar)?;
+    let previous = current
+        .checked_sub(1)
+        .ok_or(VaultError::InvalidApprovalSignature)?;
+    let ed25519_ix =
+        sysvar::instructions::load_instruction_at_checked(previous as usize, instructions_sysvar)?;
+    if ed25519_ix.program_id != ed25519_program::id() {
+        return Err(err(VaultError::InvalidApprovalSignature));
+    }
+
+    const HEADER_LEN: usize = 2;
+    const OFFSETS_LEN: usize = 14;
+    const SAME_INSTRUCTION: u16 = u16::MAX;
+    let data = &ed25519_ix.data;
+    let read_u16 = |at: usize| -> Result<u16, VaultError> {
+        data.get(at..at + 2)
+            .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+            .ok_or(VaultError::InvalidApprovalSignature)
+    };
+    let slice = |at: u16, len: usize| -> Result<&[u8], VaultError> {
+        data.get(at as usize..at as usize + len)
+            .ok_or(VaultError::InvalidApprovalSignature)
+    };
+
+    let count = *data.first().ok_or(VaultError::InvalidApprovalSignature)?;
+    if signature_index >= count {
+        return Err(err(VaultError::InvalidApprovalSignature));
+    }
+    let offsets = HEADER_LEN + signature_index as usize * OFFSETS_LEN;
+    let signature_instruction = read_u16(offsets + 2)?;
+    let public_key_offset = read_u16(offsets + 4)?;
+    let public_key_instruction = read_u16(offsets + 6)?;
+    let message_offset = read_u16(offsets + 8)?;
+    let message_size = read_u16(offsets + 10)?;
+    let message_instruction = read_u16(offsets + 12)?;
+    if [signature_instruction, public_key_instruction, message_instruction]
+        .iter()
+        .any(|index| *index != SAME_INSTRUCTION)
+    {
+        return Err(err(VaultError::InvalidApprovalSignature));
+    }
+
+    if slice(public_key_offset, 32)? != signer.as_ref()
+        || slice(message_offset, message_size as usize)? != message
+    {
+        return Err(err(VaultError::InvalidApprovalSignature));
+    }
+    Ok(())
+}
+
+// Records `approver`'s approval of a pending transaction. Shared by direct approvals and
+// signature-carried ones; callers have established that `approver` really approves.
+fn record_approval(
+    program_id: &Pubkey,
+    vault_account: &AccountInfo,
+    approver: &Pubkey,
+    transaction_id: u64,
+    clock_sysvar: &AccountInfo,
+) -> ProgramResult {
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    // Check if multisig is initialized
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+    if vault.is_transaction_expired(transaction_id, clock.unix_timestamp) {
+        return Err(err(VaultError::TransactionExpired));
+    }
+
+    // Find approver in owners list
+    let owner_index = multi_sig
+        .owners
+        .iter()
+        .position(|owner| owner == approver)
+        .ok_or(VaultError::InvalidOwner)?;
+    let owner_count = multi_sig.owners.len();
+    let threshold = multi_sig.threshold;
+    let joined_at = vault.owner_since(owner_index);
+
+    let transaction_index = vault
+        .multisig_transaction_index(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    let transaction = &mut vault.multi_sig_transactions[transaction_index];
+
+    // Check if transaction is already executed
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+
+    // Owners only approve what was proposed after they joined
+    if transaction.created_at < joined_at {
+        return Err(err(VaultError::OwnerJoinedAfterProposal));
+    }
+
+    // Transactions stored before owner changes realigned approvals may be short
+    if transaction.signers.len() < owner_count {
+        transaction.signers.resize(owner_count, false);
+    }
+
+    // Check if already approved
+    if transaction.signers[owner_index] {
+        return Err(err(VaultError::TransactionAlreadySigned));
+    }
+
+    // Approve the transaction
+    transaction.signers[owner_index] = true;
+
+    let current_approvals = transaction.signers.iter().filter(|&&signed| signed).count();
+    if current_approvals as u64 >= threshold {
+        vault.record_threshold_reached(transaction_id, clock.unix_timestamp);
+    }
+    vault.last_owner_activity = clock.unix_timestamp;
+
+    vault.log_action(
+        ActionKind::MultiSigApproved,
+        *approver,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Emit event
+    let approval_event = MultiSigTransactionApprovedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *approver,
+            "multisig_transaction_approved",
+            &clock,
+        ),
+        transaction_id,
+        approver: *approver,
+        current_approvals,
+        required_approvals: threshold as usize,
+    };
+    emit_event!(approval_event, approval_event);
+
+    msg!(
+        "Multi-sig transaction {} approved by {} ({} of {} approvals)",
+        transaction_id,
+        approver,
+        current_approvals,
+        threshold
+    );
+    Ok(())
+}
+
+fn process_execute_multi_sig_transaction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let executor = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !executor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    // A multisig initialized before the signer bump was stored derives it this once; the
+    // save below keeps it
+    if let Some(multi_sig) = vault.multi_sig.as_mut().filter(|m| m.bump == 0) {
+        multi_sig.bump =
+            MultiSig::find_signer_address(vault_account.key, multi_sig.nonce, program_id).1;
+    }
+
+    // Check if multisig is initialized
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+
+    let transaction_index = vault
+        .multisig_transaction_index(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    let transaction = &vault.multi_sig_transactions[transaction_index];
+
+    // Check if transaction is already executed
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+
+    // Check if we have enough approvals
+    let current_approvals = transaction.signers.iter().filter(|&&signed| signed).count();
+    if current_approvals < multi_sig.threshold as usize {
+        return Err(err(VaultError::NotEnoughSigners));
+    }
+
+    let clock = load_clock(clock_sysvar)?;
+    if vault.is_transaction_expired(transaction_id, clock.unix_timestamp) {
+        return Err(err(VaultError::TransactionExpired));
+    }
+    // Met its threshold without a record, so the delay starts now rather than being skipped.
+    // The record has to persist, so this call succeeds without executing.
+    if vault.execution_delay_seconds > 0 && vault.threshold_reached(transaction_id).is_none() {
+        vault.record_threshold_reached(transaction_id, clock.unix_timestamp);
+        vault.save(&mut vault_account.data.borrow_mut())?;
+        msg!(
+            "Multi-sig transaction {} reached its threshold just now, executable at {}",
+            transaction_id,
+            clock.unix_timestamp.saturating_add(vault.execution_delay_seconds)
+        );
+        return Ok(());
+    }
+    let executable_at = vault
+        .executable_at(transaction_id, multi_sig.threshold)
+        .ok_or(VaultError::NotEnoughSigners)?;
+    if clock.unix_timestamp < executable_at {
+        msg!("Multi-sig transaction {} executable at {}", transaction_id, executable_at);
+        return Err(err(VaultError::ExecutionDelayActive));
+    }
+
+    // The target program comes first among the remaining accounts, and everything the stored
+    // instruction names has to be passed in; otherwise the CPI would only fail deep in the
+    // runtime after burning compute
+    let target_program_account = next_account_info(account_info_iter).map_err(|_| {
+        msg!("Missing target program account {}", transaction.program_id);
+        VaultError::InvalidTransactionData
+    })?;
+    if *target_program_account.key != transaction.program_id || !target_program_account.executable
+    {
+        msg!(
+            "Expected executable program {} as the first remaining account, got {}",
+            transaction.program_id,
+            target_program_account.key
+        );
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+    if let Some(missing) = transaction
+        .accounts
+        .iter()
+        .find(|acc| !accounts.iter().any(|info| info.key == &acc.pubkey))
+    {
+        msg!("Stored account {} was not passed in", missing.pubkey);
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+
+    // Create the instruction to execute, with metas from the roles validated at creation.
+    // Transactions stored before roles existed fall back to their flags.
+    let legacy_role = |acc: &crate::state::TransactionAccount| {
+        if &acc.pubkey == multisig_signer.key {
+            AccountRole::MultisigSigner
+        } else if acc.is_writable {
+            AccountRole::Writable
+        } else {
+            AccountRole::ReadOnly
+        }
+    };
+    let roles: Vec<AccountRole> = match vault.transaction_roles(transaction_id) {
+        Some(roles) => roles.to_vec(),
+        None => transaction.accounts.iter().map(legacy_role).collect(),
+    };
+    let ix = Instruction {
+        program_id: transaction.program_id,
+        accounts: transaction
+            .accounts
+            .iter()
+            .zip(&roles)
+            .map(|(acc, role)| match role {
+                // The signer PDA may also be the payer of a system transfer, so it keeps the
+                // stored writability
+                AccountRole::MultisigSigner => AccountMeta {
+                    pubkey: acc.pubkey,
+                    is_signer: true,
+                    is_writable: acc.is_writable,
+                },
+                AccountRole::VaultPda => AccountMeta::new(acc.pubkey, false),
+                AccountRole::Writable => AccountMeta::new(acc.pubkey, acc.is_signer),
+                AccountRole::ReadOnly => AccountMeta::new_readonly(acc.pubkey, acc.is_signer),
+            })
+            .collect(),
+        data: transaction.data.clone(),
+    };
+
+    let target_program = transaction.program_id;
+    let assertions = vault.transaction_assertions(transaction_id).to_vec();
+
+    // The multisig signer PDA, from the stored bump
+    if multi_sig.signer_address(vault_account.key, program_id) != *multisig_signer.key {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    // Signing here is also how this multisig acts for vaults whose authority is its signer
+    // PDA: their authority checks see that PDA as a signer of the self-CPI
+    let nonce = multi_sig.nonce;
+    let bump = multi_sig.bump;
+    let seeds = &[vault_account.key.as_ref(), &[nonce], &[bump]];
+    let signer_seeds = &[&seeds[..]];
+
+    // Mark as executed before the CPI. A self-CPI then sees the flag and cannot run this
+    // transaction again, and whatever it writes to the vault is not clobbered afterwards
+    // by a re-serialize of our now stale copy.
+    if multi_sig.owners.contains(executor.key) {
+        vault.last_owner_activity = clock.unix_timestamp;
+    }
+    vault.multi_sig_transactions[transaction_index].did_execute = true;
+    // The tip leaves the books together with the flag, so it can't be paid twice; it is only
+    // handed over once the CPI below has succeeded, otherwise the whole transaction reverts
+    let tip = vault.take_execution_tip(transaction_id);
+    if tip.is_some() && !executor.is_writable {
+        msg!("Executor must be writable to receive the execution tip");
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    vault.log_action(
+        ActionKind::MultiSigExecuted,
+        *executor.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Execute the transaction; the full account list is passed so a self-CPI can reach the vault.
+    // The EXEC line tells operators reading the logs which stored instruction ran. On-chain a
+    // failing callee aborts the transaction before control comes back here, so EXEC_FAILED
+    // and its event only appear where the error is handed back, as under native processors.
+    msg!(
+        "EXEC target={} accounts={} data_len={} tx_id={}",
+        target_program,
+        ix.accounts.len(),
+        ix.data.len(),
+        transaction_id
+    );
+    if let Err(error) = invoke_signed(&ix, accounts, signer_seeds) {
+        msg!("EXEC_FAILED tx_id={} error={:?}", transaction_id, error);
+        let failed_event = MultiSigTransactionExecutionFailedEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *executor.key,
+                "multisig_transaction_execution_failed",
+                &clock,
+            ),
+            transaction_id,
+            executor: *executor.key,
+            target_program,
+            error: format!("{:?}", error),
+        };
+        emit_event!(failed_event, failed_event);
+        return Err(error);
+    }
+    check_balance_assertions(&assertions, accounts)?;
+
+    // Emit event
+    let execution_event = MultiSigTransactionExecutedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *executor.key,
+            "multisig_transaction_executed",
+            &clock,
+        ),
+        transaction_id,
+        executor: *executor.key,
+        target_program,
+    };
+    emit_event!(execution_event, execution_event);
+
+    if let Some(tip) = tip {
+        pay_out_of_vault(vault_account, executor, tip.lamports)?;
+
+        let tip_event = ExecutionTipPaidEvent {
+            base: create_base_event(
+                *vault_account.key,
+                *executor.key,
+                "execution_tip_paid",
+                &clock,
+            ),
+            transaction_id,
+            executor: *executor.key,
+            lamports: tip.lamports,
+        };
+        emit_event!(tip_event, tip_event);
+        // Transaction id and tip as raw numbers, which skips the formatting
+        sol_log_64(transaction_id, tip.lamports, 0, 0, 0);
+    }
+
+    debug_msg!(
+        "Multi-sig transaction {} executed by {}",
+        transaction_id,
+        executor.key
+    );
+    Ok(())
+}
+
+// Balances the CPI left behind against a transaction's assertions. Failing one fails the
+// whole transaction, so the CPI's effects are undone as well.
+fn check_balance_assertions(
+    assertions: &[BalanceAssertion],
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    for (index, assertion) in assertions.iter().enumerate() {
+        let balance = accounts
+            .iter()
+            .find(|info| *info.key == assertion.account)
+            .and_then(|info| {
+                if assertion.is_token {
+                    unpack_token_account(info).ok().map(|t| t.amount)
+                } else {
+                    Some(info.lamports())
+                }
+            });
+        match balance {
+            Some(balance) if balance >= assertion.min_post_balance => {}
+            _ => {
+                msg!(
+                    "Balance assertion {} failed: {} holds {:?}, needs at least {}",
+                    index,
+                    assertion.account,
+                    balance,
+                    assertion.min_post_balance
+                );
+                return Err(err(VaultError::AssertionFailed));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn process_fund_execution_tip(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+    lamports: u64,
+) -> ProgramResult {
+    validate_amount(lamports, 0)?;
+
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let funder = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !funder.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program.key != system_program::ID {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    let transaction = vault
+        .multisig_transaction(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+    // Refunds go back to the proposer, so only the proposer can put a tip up
+    if transaction.proposer != *funder.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+
+    let total_tip = match vault
+        .execution_tips
+        .iter_mut()
+        .find(|t| t.transaction_id == transaction_id)
+    {
+        Some(tip) => {
+            tip.lamports = tip
+                .lamports
+                .checked_add(lamports)
+                .ok_or(VaultError::ArithmeticOverflow)?;
+            tip.lamports
+        }
+        None => {
+            vault.execution_tips.push(ExecutionTip {
+                transaction_id,
+                funder: *funder.key,
+                lamports,
+            });
+            lamports
+        }
+    };
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::ExecutionTipFunded,
+        *funder.key,
+        Pubkey::default(),
+        lamports,
+        clock.unix_timestamp,
+    );
+    // The transfer below writes the vault's lamports, so the data borrow has to go first
+
+    invoke(
+        &system_instruction::transfer(funder.key, vault_account.key, lamports),
+        &[
+            funder.clone(),
+            vault_account.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let funded_event = ExecutionTipFundedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *funder.key,
+            "execution_tip_funded",
+            &clock,
+        ),
+        transaction_id,
+        funder: *funder.key,
+        lamports,
+        total_tip,
+    };
+    emit_event!(funded_event, funded_event);
+
+    msg!(
+        "Execution tip for transaction {} is now {} lamports",
+        transaction_id,
+        total_tip
+    );
+    Ok(())
+}
+
+fn process_cancel_multi_sig_transaction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let proposer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !proposer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    let transaction = vault
+        .multisig_transaction(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+    if transaction.proposer != *proposer.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+
+    vault.cancelled_transactions.push(transaction_id);
+    let refunded_lamports = vault
+        .take_execution_tip(transaction_id)
+        .map_or(0, |tip| tip.lamports);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::MultiSigCancelled,
+        *proposer.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    if refunded_lamports > 0 {
+        pay_out_of_vault(vault_account, proposer, refunded_lamports)?;
+    }
+
+    let cancelled_event = MultiSigTransactionCancelledEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *proposer.key,
+            "multisig_transaction_cancelled",
+            &clock,
+        ),
+        transaction_id,
+        proposer: *proposer.key,
+        refunded_lamports,
+    };
+    emit_event!(cancelled_event, cancelled_event);
+
+    msg!(
+        "Multi-sig transaction {} cancelled, {} lamports refunded",
+        transaction_id,
+        refunded_lamports
+    );
+    Ok(())
+}
+
+// Accounts: vault, proposer or owner, clock
+fn process_extend_transaction_expiry(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    transaction_id: u64,
+    new_expires_at: i64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+    let transaction = vault
+        .multisig_transaction(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    if transaction.proposer != *signer.key && !multi_sig.owners.contains(signer.key) {
+        return Err(err(VaultError::InvalidOwner));
+    }
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+    if vault.is_transaction_expired(transaction_id, clock.unix_timestamp) {
+        return Err(err(VaultError::TransactionExpired));
+    }
+    let previous_expires_at = vault.transaction_expires_at(transaction_id).ok_or_else(|| {
+        msg!("Multi-sig transaction {} has no expiry to extend", transaction_id);
+        VaultError::InvalidInstruction
+    })?;
+
+    let latest = clock
+        .unix_timestamp
+        .saturating_add(vault.param(ParamKey::MultisigTransactionTtl) as i64);
+    if new_expires_at <= previous_expires_at || new_expires_at > latest {
+        msg!(
+            "New expiry must be after {} and no later than {}",
+            previous_expires_at,
+            latest
+        );
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    vault.set_transaction_expiry(transaction_id, new_expires_at);
+    vault.log_action(
+        ActionKind::MultiSigExpiryExtended,
+        *signer.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let extended_event = MultiSigTransactionExpiryExtendedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *signer.key,
+            "multisig_transaction_expiry_extended",
+            &clock,
+        ),
+        transaction_id,
+        previous_expires_at,
+        new_expires_at,
+    };
+    emit_event!(extended_event, extended_event);
+
+    msg!(
+        "Multi-sig transaction {} now expires at {}",
+        transaction_id,
+        new_expires_at
+    );
+    Ok(())
+}
+
+fn process_prune_multi_sig_transactions(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let owner = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    require_multisig_owner(&vault, owner)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    let transaction_ids = vault.prune_multisig_transactions(clock.unix_timestamp);
+    let remaining = vault.multi_sig_transactions.len();
+
+    vault.log_action(
+        ActionKind::MultiSigPruned,
+        *owner.key,
+        Pubkey::default(),
+        transaction_ids.len() as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let pruned_count = transaction_ids.len();
+    let pruned_event = MultiSigTransactionsPrunedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *owner.key,
+            "multisig_transactions_pruned",
+            &clock,
+        ),
+        transaction_ids,
+        remaining,
+    };
+    emit_event!(pruned_event, pruned_event);
+
+    msg!(
+        "Pruned {} multi-sig transactions, {} remaining",
+        pruned_count,
+        remaining
+    );
+    Ok(())
 }
 
-fn process_cancel_time_lock(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    time_lock_index: usize,
-) -> ProgramResult {
-    msg!("Processing cancel time lock");
+fn process_set_multi_sig_owners(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    owners: Vec<Pubkey>,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+
+    let multi_sig = vault
+        .multi_sig
+        .as_mut()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+
+    // Validate new owners (no duplicates)
+    let mut unique_owners = owners.clone();
+    unique_owners.sort();
+    unique_owners.dedup();
+    if unique_owners.len() != owners.len() {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+
+    // Adjust threshold if necessary
+    if (owners.len() as u64) < multi_sig.threshold {
+        multi_sig.threshold = owners.len() as u64;
+    }
+
+    let clock = load_clock(clock_sysvar)?;
+    let old_owners = vault.replace_multisig_owners(owners.clone(), clock.unix_timestamp);
+    vault.record_thresholds_met(clock.unix_timestamp);
+    vault.log_action(
+        ActionKind::MultiSigOwnersChanged,
+        *authority.key,
+        Pubkey::default(),
+        owners.len() as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Emit event
+    let owners_event = MultiSigOwnersUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "multisig_owners_updated",
+            &clock,
+        ),
+        old_owners: old_owners.clone(),
+        new_owners: owners.clone(),
+        owner_since: vault.multisig_owner_since.clone(),
+    };
+    emit_event!(owners_event, owners_event);
+
+    msg!(
+        "Multi-sig owners updated from {:?} to {:?}",
+        old_owners,
+        owners
+    );
     Ok(())
 }
 
-fn process_set_yield_strategy(
+fn process_change_multi_sig_threshold(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    token_mint: Pubkey,
-    strategy_program: Pubkey,
+    threshold: u64,
 ) -> ProgramResult {
-    msg!("Processing set yield strategy");
-    Ok(())
-}
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-fn process_harvest_yield(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    token_mint: Pubkey,
-) -> ProgramResult {
-    msg!("Processing harvest yield");
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    require_authority(&vault, authority)?;
+
+    let multi_sig = vault
+        .multi_sig
+        .as_mut()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+
+    // Validate threshold
+    if threshold == 0 || threshold > multi_sig.owners.len() as u64 {
+        return Err(err(VaultError::InvalidThreshold));
+    }
+
+    // Store old threshold for event
+    let old_threshold = multi_sig.threshold;
+
+    multi_sig.threshold = threshold;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.record_thresholds_met(clock.unix_timestamp);
+    vault.log_action(
+        ActionKind::MultiSigThresholdChanged,
+        *authority.key,
+        Pubkey::default(),
+        threshold,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    // Emit event
+    let threshold_event = MultiSigThresholdUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *authority.key,
+            "multisig_threshold_updated",
+            &clock,
+        ),
+        old_threshold,
+        new_threshold: threshold,
+    };
+    emit_event!(threshold_event, threshold_event);
+
+    msg!(
+        "Multi-sig threshold changed from {} to {}",
+        old_threshold,
+        threshold
+    );
     Ok(())
 }
 
-fn process_compound_yield(
+// Lets the owners raise (or lower) the per-proposer limit by executing a multisig transaction
+fn process_set_multi_sig_proposal_limit(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    token_mint: Pubkey,
+    max_proposals_per_day: u32,
 ) -> ProgramResult {
-    msg!("Processing compound yield");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    ParamKey::MaxProposalsPerDay.validate(max_proposals_per_day as u64)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let old_limit = vault.set_param(ParamKey::MaxProposalsPerDay, max_proposals_per_day as u64);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::ProposalLimitChanged,
+        *multisig_signer.key,
+        Pubkey::default(),
+        max_proposals_per_day as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let limit_event = MultiSigProposalLimitUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "multisig_proposal_limit_updated",
+            &clock,
+        ),
+        old_limit: old_limit as u32,
+        new_limit: max_proposals_per_day,
+    };
+    emit_event!(limit_event, limit_event);
+
+    msg!(
+        "Multi-sig proposal limit changed from {} to {} per day",
+        old_limit,
+        max_proposals_per_day
+    );
     Ok(())
 }
 
-fn process_jupiter_swap(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    input_mint: Pubkey,
-    output_mint: Pubkey,
-    amount: u64,
-) -> ProgramResult {
-    msg!("Processing jupiter swap");
+fn process_view(program_id: &Pubkey, accounts: &[AccountInfo], query: ViewQuery) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let vault = load_vault_checked(vault_account, program_id)?;
+    let clock = load_clock(clock_sysvar)?;
+    let result = view::evaluate(&vault, &query, clock.unix_timestamp)?;
+
+    set_return_data(&result.try_to_vec()?);
+    msg!(
+        "VIEW: {}",
+        serde_json::to_string(&result).unwrap_or_else(|_| "Failed to serialize view".to_string())
+    );
     Ok(())
 }
 
-fn process_jupiter_route(
+fn process_set_param(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    input_mint: Pubkey,
-    output_mint: Pubkey,
-    amount: u64,
-    route: Vec<u8>,
+    key: ParamKey,
+    value: u64,
 ) -> ProgramResult {
-    msg!("Processing jupiter route");
-    Ok(())
-}
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let global_config = load_global_config(program_id, account_info_iter.next())?;
 
-fn process_collect_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    msg!("Processing collect fees");
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let fee_cap = global_config.map_or(ParamKey::MAX_FEE_BPS, |config| config.max_fee_bps);
+    key.validate_capped(value, fee_cap)?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let old = vault.set_param(key, value);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::ParamChanged,
+        *multisig_signer.key,
+        Pubkey::default(),
+        value,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let param_event = ParamUpdatedEvent {
+        base: create_base_event(*vault_account.key, *multisig_signer.key, "param_updated", &clock),
+        key: key as u8,
+        old,
+        new: value,
+    };
+    emit_event!(param_event, param_event);
+
+    msg!("Param {:?} changed from {} to {}", key, old, value);
     Ok(())
 }
 
-fn process_transfer_authority(
+fn process_set_mint_minimums(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    new_authority: Pubkey,
+    minimums: MintMinimums,
 ) -> ProgramResult {
-    msg!("Processing transfer authority");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+    validate_token_supported(&vault, &minimums.mint)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    let minimums_event = MintMinimumsUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "mint_minimums_updated",
+            &clock,
+        ),
+        mint: minimums.mint,
+        min_deposit: minimums.min_deposit,
+        min_withdrawal: minimums.min_withdrawal,
+    };
+    vault.log_action(
+        ActionKind::ParamChanged,
+        *multisig_signer.key,
+        minimums.mint,
+        minimums.min_deposit,
+        clock.unix_timestamp,
+    );
+    vault.set_mint_minimums(minimums);
+    vault.save(&mut vault_account.data.borrow_mut())?;
+    emit_event!(minimums_event, minimums_event);
+
+    debug_msg!(
+        "Minimums for {} set to {} deposit, {} withdrawal",
+        minimums_event.mint,
+        minimums_event.min_deposit,
+        minimums_event.min_withdrawal
+    );
     Ok(())
 }
 
-fn process_update_emergency_admin(
+fn process_create_template(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    new_admin: Pubkey,
+    mut template: TransactionTemplate,
 ) -> ProgramResult {
-    msg!("Processing update emergency admin");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    template.validate()?;
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    if vault.templates.len() >= Vault::MAX_TEMPLATES {
+        msg!("Vault already holds {} templates", Vault::MAX_TEMPLATES);
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+
+    // Ids only ever grow, so a deleted template's id is never reused
+    template.id = vault.next_template_id;
+    vault.next_template_id += 1;
+    let template_id = template.id;
+    let name = template.name.clone();
+    let target_program = template.program_id;
+    let fill_len = template.fill_len() as u64;
+    vault.templates.push(template);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::TemplateCreated,
+        *multisig_signer.key,
+        Pubkey::default(),
+        template_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let template_event = TemplateCreatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "template_created",
+            &clock,
+        ),
+        template_id,
+        name,
+        program_id: target_program,
+        fill_len,
+    };
+    emit_event!(template_event, template_event);
+
+    msg!("Template {} ({}) created", template_id, template_event.name);
     Ok(())
 }
 
-fn process_initialize_governance(
+fn process_delete_template(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    voting_token_mint: Pubkey,
-    quorum_threshold: u16,
-    proposal_threshold: u64,
-    voting_period: i64,
-    time_lock_delay: i64,
-    execution_threshold: u16,
+    template_id: u64,
 ) -> ProgramResult {
-    msg!("Processing initialize governance");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let index = vault
+        .templates
+        .iter()
+        .position(|t| t.id == template_id)
+        .ok_or(VaultError::TemplateNotFound)?;
+    vault.templates.remove(index);
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::TemplateDeleted,
+        *multisig_signer.key,
+        Pubkey::default(),
+        template_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let template_event = TemplateDeletedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "template_deleted",
+            &clock,
+        ),
+        template_id,
+    };
+    emit_event!(template_event, template_event);
+
+    msg!("Template {} deleted", template_id);
     Ok(())
 }
 
-fn process_create_governance_proposal(
+// Only the data is built here; proposing goes through the regular path, so owner checks,
+// the proposal rate limit and the payload limits all apply unchanged
+fn process_create_transaction_from_template(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    title: String,
-    description: String,
-    instructions: Vec<Vec<u8>>,
+    template_id: u64,
+    fill_bytes: Vec<u8>,
 ) -> ProgramResult {
-    msg!("Processing create governance proposal");
-    Ok(())
+    let vault_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let vault = load_vault_checked(vault_account, program_id)?;
+    let template = vault
+        .template(template_id)
+        .ok_or(VaultError::TemplateNotFound)?;
+    let data = template.instantiate(&fill_bytes)?;
+
+    msg!("Proposing from template {} ({})", template_id, template.name);
+    process_create_multi_sig_transaction(
+        program_id,
+        accounts,
+        template.program_id,
+        template.accounts.clone(),
+        None,
+        Vec::new(),
+        data,
+    )
 }
 
-fn process_cast_vote(
+// Builds the transfer the multisig signer PDA would sign and proposes it through the regular
+// CreateMultiSigTransaction path. SOL comes out of the signer's lamports, tokens out of its
+// associated token account for the mint.
+fn process_propose_transfer(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    proposal_id: u64,
-    vote_type: crate::state::VoteType,
+    mint: Option<Pubkey>,
+    recipient: Pubkey,
+    amount: u64,
+    memo: String,
 ) -> ProgramResult {
-    msg!("Processing cast vote");
+    validate_amount(amount, 0)?;
+    if memo.len() > MultiSigTransaction::MAX_MEMO_LEN {
+        msg!("Memo is {} bytes, at most {}", memo.len(), MultiSigTransaction::MAX_MEMO_LEN);
+        return Err(err(VaultError::InvalidTransactionData));
+    }
+
+    let vault_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let proposer = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let clock_sysvar = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let vault = load_vault_checked(vault_account, program_id)?;
+    let multi_sig = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+    let multisig_signer = multi_sig.signer_address(vault_account.key, program_id);
+    let transaction_id = vault.next_transaction_id;
+
+    let transfer_ix = match mint {
+        None => system_instruction::transfer(&multisig_signer, &recipient, amount),
+        Some(mint) => {
+            let token_program = vault.token_program_for(&mint);
+            transfer_instruction(
+                &token_program,
+                &get_associated_token_address_with_program_id(
+                    &multisig_signer,
+                    &mint,
+                    &token_program,
+                ),
+                &get_associated_token_address_with_program_id(&recipient, &mint, &token_program),
+                &multisig_signer,
+                amount,
+            )?
+        }
+    };
+    let transaction_accounts = transfer_ix
+        .accounts
+        .iter()
+        .map(|meta| crate::state::TransactionAccount {
+            pubkey: meta.pubkey,
+            is_signer: meta.is_signer,
+            is_writable: meta.is_writable,
+        })
+        .collect();
+    process_create_multi_sig_transaction(
+        program_id,
+        accounts,
+        transfer_ix.program_id,
+        transaction_accounts,
+        None,
+        Vec::new(),
+        transfer_ix.data,
+    )?;
+
+    let clock = load_clock(clock_sysvar)?;
+    let proposed_event = MultiSigTransferProposedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *proposer.key,
+            "multisig_transfer_proposed",
+            &clock,
+        ),
+        transaction_id,
+        mint,
+        recipient,
+        amount,
+        memo,
+    };
+    emit_event!(proposed_event, proposed_event);
     Ok(())
 }
 
-fn process_queue_proposal(
+fn process_set_execution_delay(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    proposal_id: u64,
+    execution_delay_seconds: i64,
 ) -> ProgramResult {
-    msg!("Processing queue proposal");
-    Ok(())
-}
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !multisig_signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if execution_delay_seconds < 0 {
+        return Err(err(VaultError::InvalidAmount));
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let old_delay = vault.execution_delay_seconds;
+    vault.execution_delay_seconds = execution_delay_seconds;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.log_action(
+        ActionKind::ExecutionDelayChanged,
+        *multisig_signer.key,
+        Pubkey::default(),
+        execution_delay_seconds as u64,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let delay_event = MultiSigExecutionDelayUpdatedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "multisig_execution_delay_updated",
+            &clock,
+        ),
+        old_delay,
+        new_delay: execution_delay_seconds,
+    };
+    emit_event!(delay_event, delay_event);
 
-fn process_execute_governance_proposal(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    proposal_id: u64,
-) -> ProgramResult {
-    msg!("Processing execute governance proposal");
+    msg!(
+        "Multi-sig execution delay changed from {}s to {}s",
+        old_delay,
+        execution_delay_seconds
+    );
     Ok(())
 }
 
-fn process_update_governance_config(
+fn process_expedite_transaction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    quorum_threshold: u16,
-    proposal_threshold: u64,
-    voting_period: i64,
-    time_lock_delay: i64,
-    execution_threshold: u16,
+    transaction_id: u64,
 ) -> ProgramResult {
-    msg!("Processing update governance config");
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let emergency_admin = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    if !emergency_admin.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_emergency_admin(&vault, emergency_admin.key)?;
+
+    let threshold = vault
+        .multi_sig
+        .as_ref()
+        .ok_or(VaultError::MultisigNotInitialized)?
+        .threshold;
+    let transaction = vault
+        .multisig_transaction(transaction_id)
+        .ok_or(VaultError::TransactionNotFound)?;
+    if transaction.did_execute {
+        return Err(err(VaultError::TransactionAlreadyExecuted));
+    }
+    if vault.is_transaction_cancelled(transaction_id) {
+        return Err(err(VaultError::TransactionCancelled));
+    }
+    // Skipping the delay never stands in for the approvals themselves
+    let clock = load_clock(clock_sysvar)?;
+    let executable_at = vault
+        .executable_at(transaction_id, threshold)
+        .ok_or(VaultError::NotEnoughSigners)?;
+
+    match vault
+        .threshold_reached
+        .iter_mut()
+        .find(|t| t.transaction_id == transaction_id)
+    {
+        Some(reached) => reached.expedited = true,
+        None => vault.threshold_reached.push(ThresholdReached {
+            transaction_id,
+            reached_at: clock.unix_timestamp,
+            expedited: true,
+        }),
+    }
+
+    vault.log_emergency_action(
+        *emergency_admin.key,
+        EmergencyActionLog::TRANSACTION_EXPEDITED,
+        transaction_id.to_le_bytes().to_vec(),
+        clock.unix_timestamp,
+    );
+    vault.log_action(
+        ActionKind::MultiSigExpedited,
+        *emergency_admin.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let expedited_event = MultiSigTransactionExpeditedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *emergency_admin.key,
+            "multisig_transaction_expedited",
+            &clock,
+        ),
+        transaction_id,
+        emergency_admin: *emergency_admin.key,
+        skipped_seconds: executable_at.saturating_sub(clock.unix_timestamp).max(0),
+    };
+    emit_event!(expedited_event, expedited_event);
+
+    msg!(
+        "WARNING: emergency admin {} expedited multi-sig transaction {}, skipping its execution delay",
+        emergency_admin.key,
+        transaction_id
+    );
     Ok(())
 }
 
-// Multi-sig processor functions
-fn process_create_multi_sig_transaction(
+// Only reachable through ExecuteMultiSigTransaction, which marks the transaction executed
+// before invoking, so the approvals are read from the newest executed copy of this instruction
+fn process_attest_message(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    target_program_id: Pubkey,
-    transaction_accounts: Vec<crate::state::TransactionAccount>,
-    data: Vec<u8>,
+    message_hash: [u8; 32],
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
-    let proposer = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
     let clock_sysvar = next_account_info(account_info_iter)?;
 
-    if !proposer.is_signer {
+    if !multisig_signer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
+
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let expected_data = VaultInstruction::AttestMessage { message_hash }.try_to_vec()?;
+    let (transaction_index, transaction) = vault
+        .multi_sig_transactions
+        .iter()
+        .enumerate()
+        .rev()
+        .find(|(_, tx)| {
+            tx.did_execute && tx.program_id == *program_id && tx.data == expected_data
+        })
+        .ok_or(VaultError::TransactionNotFound)?;
+    let transaction_id = vault
+        .multisig_transaction_id(transaction_index)
+        .ok_or(VaultError::TransactionNotFound)?;
 
-    // Check if multisig is initialized
     let multi_sig = vault
         .multi_sig
         .as_ref()
         .ok_or(VaultError::MultisigNotInitialized)?;
-
-    // Check if proposer is authorized
-    if !multi_sig.owners.contains(proposer.key) {
-        return Err(VaultError::InvalidOwner.into());
-    }
-
-    let clock = Clock::from_account_info(clock_sysvar)?;
-    let transaction_id = vault.multi_sig_transactions.len() as u64;
-
-    // Find owner index
-    let owner_index = multi_sig
+    let approvers: Vec<Pubkey> = multi_sig
         .owners
         .iter()
-        .position(|owner| owner == proposer.key)
-        .ok_or(VaultError::InvalidOwner)?;
+        .zip(&transaction.signers)
+        .filter(|(_, signed)| **signed)
+        .map(|(owner, _)| *owner)
+        .collect();
 
-    let mut signers = vec![false; multi_sig.owners.len()];
-    signers[owner_index] = true;
+    let clock = load_clock(clock_sysvar)?;
 
-    // Validate transaction data
-    if transaction_accounts.is_empty() {
-        return Err(VaultError::InvalidTransactionData.into());
+    if vault.attestations.len() >= Vault::MAX_ATTESTATIONS {
+        let evicted = vault.attestations.remove(0);
+        msg!("Dropping oldest attestation from transaction {}", evicted.transaction_id);
     }
+    vault.attestations.push(Attestation {
+        message_hash,
+        transaction_id,
+        approvers: approvers.clone(),
+        attested_at: clock.unix_timestamp,
+    });
 
-    let transaction = MultiSigTransaction {
-        multisig: *vault_account.key,
-        program_id: target_program_id,
-        accounts: transaction_accounts.clone(),
-        data: data.clone(),
-        signers,
-        did_execute: false,
-        proposer: *proposer.key,
-        created_at: clock.unix_timestamp,
-    };
-
-    vault.multi_sig_transactions.push(transaction);
-
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.log_action(
+        ActionKind::Attestation,
+        *multisig_signer.key,
+        Pubkey::default(),
+        transaction_id,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Emit event
-    let transaction_event = MultiSigTransactionCreatedEvent {
+    let attestation_event = AttestationEvent {
         base: create_base_event(
             *vault_account.key,
-            *proposer.key,
-            "multisig_transaction_created",
+            *multisig_signer.key,
+            "message_attested",
             &clock,
         ),
+        message_hash,
         transaction_id,
-        proposer: *proposer.key,
-        target_program: target_program_id,
-        instruction_count: transaction_accounts.len(),
+        approvers: approvers.clone(),
     };
-    emit_event!(transaction_event, transaction_event);
+    emit_event!(attestation_event, attestation_event);
 
-    msg!(
-        "Multi-sig transaction {} created by {}",
-        transaction_id,
-        proposer.key
-    );
+    msg!("Message attested by transaction {}", transaction_id);
+    msg!("Approvers: {:?}", approvers);
     Ok(())
 }
 
-fn process_approve_multi_sig_transaction(
+fn process_set_multi_sig_recovery(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    transaction_id: u64,
+    recovery: Option<RecoveryConfig>,
 ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
-    let approver = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
     let clock_sysvar = next_account_info(account_info_iter)?;
 
-    if !approver.is_signer {
+    if !multisig_signer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    if let Some(config) = &recovery {
+        if config.inactivity_period <= 0 || config.recovery_key == Pubkey::default() {
+            return Err(err(VaultError::InvalidAccountData));
+        }
+    }
 
-    // Check if multisig is initialized
-    let multi_sig = vault
-        .multi_sig
-        .as_ref()
-        .ok_or(VaultError::MultisigNotInitialized)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    // Check if transaction exists
-    if transaction_id as usize >= vault.multi_sig_transactions.len() {
-        return Err(VaultError::TransactionNotFound.into());
+    validate_multisig_signer(program_id, &vault, vault_account.key, multisig_signer.key)?;
+
+    let clock = load_clock(clock_sysvar)?;
+    vault.multisig_recovery = recovery.clone();
+    vault.recovery_initiated_at = None;
+    vault.last_owner_activity = clock.unix_timestamp;
+
+    vault.log_action(
+        ActionKind::RecoveryConfigured,
+        *multisig_signer.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let recovery_event = RecoveryConfiguredEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *multisig_signer.key,
+            "recovery_configured",
+            &clock,
+        ),
+        recovery_key: recovery.as_ref().map(|config| config.recovery_key),
+        inactivity_period: recovery.as_ref().map_or(0, |config| config.inactivity_period),
+    };
+    emit_event!(recovery_event, recovery_event);
+
+    match &recovery {
+        Some(config) => msg!(
+            "Recovery key {} after {}s of owner inactivity",
+            config.recovery_key,
+            config.inactivity_period
+        ),
+        None => msg!("Multi-sig recovery disabled"),
     }
+    Ok(())
+}
 
-    let transaction = &mut vault.multi_sig_transactions[transaction_id as usize];
+fn process_initiate_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let recovery_key = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-    // Check if transaction is already executed
-    if transaction.did_execute {
-        return Err(VaultError::TransactionAlreadyExecuted.into());
+    if !recovery_key.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // Find approver in owners list
-    let owner_index = multi_sig
-        .owners
-        .iter()
-        .position(|owner| owner == approver.key)
-        .ok_or(VaultError::InvalidOwner)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    // Check if already approved
-    if transaction.signers[owner_index] {
-        return Err(VaultError::TransactionAlreadySigned.into());
+    let config = vault
+        .multisig_recovery
+        .as_ref()
+        .ok_or(VaultError::RecoveryNotConfigured)?;
+    if config.recovery_key != *recovery_key.key {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    if vault.recovery_initiated_at.is_some() {
+        return Err(err(VaultError::RecoveryChallengeActive));
     }
 
-    // Approve the transaction
-    transaction.signers[owner_index] = true;
+    let clock = load_clock(clock_sysvar)?;
+    let inactive_for = clock.unix_timestamp.saturating_sub(vault.last_owner_activity);
+    if inactive_for < config.inactivity_period {
+        return Err(err(VaultError::OwnersStillActive));
+    }
 
-    let clock = Clock::from_account_info(clock_sysvar)?;
-    let current_approvals = transaction.signers.iter().filter(|&&signed| signed).count();
+    let challenge_seconds = vault.param(ParamKey::RecoveryChallengeSeconds) as i64;
+    let finalize_after = clock
+        .unix_timestamp
+        .checked_add(challenge_seconds)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    vault.recovery_initiated_at = Some(clock.unix_timestamp);
+    vault.log_emergency_action(
+        *recovery_key.key,
+        EmergencyActionLog::RECOVERY_INITIATED,
+        vault.last_owner_activity.to_le_bytes().to_vec(),
+        clock.unix_timestamp,
+    );
 
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+    vault.log_action(
+        ActionKind::RecoveryInitiated,
+        *recovery_key.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
 
-    // Emit event
-    let approval_event = MultiSigTransactionApprovedEvent {
+    let initiated_event = RecoveryInitiatedEvent {
         base: create_base_event(
             *vault_account.key,
-            *approver.key,
-            "multisig_transaction_approved",
+            *recovery_key.key,
+            "recovery_initiated",
             &clock,
         ),
-        transaction_id,
-        approver: *approver.key,
-        current_approvals,
-        required_approvals: multi_sig.threshold as usize,
+        recovery_key: *recovery_key.key,
+        last_owner_activity: vault.last_owner_activity,
+        finalize_after,
     };
-    emit_event!(approval_event, approval_event);
+    emit_event!(initiated_event, initiated_event);
 
-    msg!(
-        "Multi-sig transaction {} approved by {} ({} of {} approvals)",
-        transaction_id,
-        approver.key,
-        current_approvals,
-        multi_sig.threshold
-    );
+    debug_msg!("Recovery initiated by {}", recovery_key.key);
+    debug_msg!("Owners can veto until {}", finalize_after);
     Ok(())
 }
 
-fn process_execute_multi_sig_transaction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    transaction_id: u64,
-) -> ProgramResult {
+fn process_veto_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let vault_account = next_account_info(account_info_iter)?;
-    let multisig_signer = next_account_info(account_info_iter)?;
-    let executor = next_account_info(account_info_iter)?;
+    let owner = next_account_info(account_info_iter)?;
     let clock_sysvar = next_account_info(account_info_iter)?;
 
-    if !executor.is_signer {
+    if !owner.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    // Check if multisig is initialized
-    let multi_sig = vault
-        .multi_sig
-        .as_ref()
-        .ok_or(VaultError::MultisigNotInitialized)?;
+    require_multisig_owner(&vault, owner)?;
+
+    let initiated_at = vault
+        .recovery_initiated_at
+        .ok_or(VaultError::RecoveryNotInitiated)?;
+
+    // A veto is owner activity, so the recovery key has to wait a full period again
+    let clock = load_clock(clock_sysvar)?;
+    vault.recovery_initiated_at = None;
+    vault.last_owner_activity = clock.unix_timestamp;
+    vault.log_emergency_action(
+        *owner.key,
+        EmergencyActionLog::RECOVERY_VETOED,
+        initiated_at.to_le_bytes().to_vec(),
+        clock.unix_timestamp,
+    );
+
+    vault.log_action(
+        ActionKind::RecoveryVetoed,
+        *owner.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let veto_event = RecoveryVetoedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *owner.key,
+            "recovery_vetoed",
+            &clock,
+        ),
+        vetoed_by: *owner.key,
+        initiated_at,
+    };
+    emit_event!(veto_event, veto_event);
+
+    debug_msg!("Recovery initiated at {} vetoed by {}", initiated_at, owner.key);
+    Ok(())
+}
+
+fn process_finalize_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_account = next_account_info(account_info_iter)?;
+    let recovery_key = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-    // Check if transaction exists
-    if transaction_id as usize >= vault.multi_sig_transactions.len() {
-        return Err(VaultError::TransactionNotFound.into());
+    if !recovery_key.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let transaction = &vault.multi_sig_transactions[transaction_id as usize];
+    let mut vault = load_vault_checked(vault_account, program_id)?;
 
-    // Check if transaction is already executed
-    if transaction.did_execute {
-        return Err(VaultError::TransactionAlreadyExecuted.into());
+    let config = vault
+        .multisig_recovery
+        .as_ref()
+        .ok_or(VaultError::RecoveryNotConfigured)?;
+    if config.recovery_key != *recovery_key.key {
+        return Err(err(VaultError::UnauthorizedAccess));
     }
 
-    // Check if we have enough approvals
-    let current_approvals = transaction.signers.iter().filter(|&&signed| signed).count();
-    if current_approvals < multi_sig.threshold as usize {
-        return Err(VaultError::NotEnoughSigners.into());
+    let initiated_at = vault
+        .recovery_initiated_at
+        .ok_or(VaultError::RecoveryNotInitiated)?;
+    let clock = load_clock(clock_sysvar)?;
+    let challenge_seconds = vault.param(ParamKey::RecoveryChallengeSeconds) as i64;
+    if clock.unix_timestamp.saturating_sub(initiated_at) < challenge_seconds {
+        return Err(err(VaultError::RecoveryChallengeActive));
     }
 
-    // Create the instruction to execute
-    let mut ix = Instruction {
-        program_id: transaction.program_id,
-        accounts: transaction
-            .accounts
-            .iter()
-            .map(|acc| {
-                if &acc.pubkey == multisig_signer.key {
-                    AccountMeta::new_readonly(acc.pubkey, true)
-                } else if acc.is_writable {
-                    AccountMeta::new(acc.pubkey, acc.is_signer)
-                } else {
-                    AccountMeta::new_readonly(acc.pubkey, acc.is_signer)
-                }
-            })
-            .collect(),
-        data: transaction.data.clone(),
+    let multi_sig = vault
+        .multi_sig
+        .as_mut()
+        .ok_or(VaultError::MultisigNotInitialized)?;
+    let old_owners = std::mem::replace(&mut multi_sig.owners, vec![*recovery_key.key]);
+    multi_sig.threshold = 1;
+    vault.multisig_owner_since = vec![clock.unix_timestamp];
+    vault.owner_set_seqno = vault.owner_set_seqno.wrapping_add(1);
+
+    // Pending transactions were signed against the old owner set
+    for transaction in vault.multi_sig_transactions.iter_mut().filter(|tx| !tx.did_execute) {
+        transaction.signers = vec![false];
+    }
+    // and so were their execution delays
+    vault.threshold_reached.clear();
+
+    vault.recovery_initiated_at = None;
+    vault.last_owner_activity = clock.unix_timestamp;
+    vault.log_emergency_action(
+        *recovery_key.key,
+        EmergencyActionLog::RECOVERY_FINALIZED,
+        old_owners.iter().flat_map(|owner| owner.to_bytes()).collect(),
+        clock.unix_timestamp,
+    );
+
+    vault.log_action(
+        ActionKind::RecoveryFinalized,
+        *recovery_key.key,
+        Pubkey::default(),
+        0,
+        clock.unix_timestamp,
+    );
+    vault.save(&mut vault_account.data.borrow_mut())?;
+
+    let finalized_event = RecoveryFinalizedEvent {
+        base: create_base_event(
+            *vault_account.key,
+            *recovery_key.key,
+            "recovery_finalized",
+            &clock,
+        ),
+        old_owners: old_owners.clone(),
+        new_owner: *recovery_key.key,
     };
+    emit_event!(finalized_event, finalized_event);
 
-    // Get remaining accounts for the CPI
-    let remaining_accounts = account_info_iter.as_slice();
+    debug_msg!("Multi-sig recovered: owners {:?} replaced by {}", old_owners, recovery_key.key);
+    Ok(())
+}
 
-    // Derive the multisig signer PDA
-    let (expected_signer, bump) = Pubkey::find_program_address(
-        &[vault_account.key.as_ref(), &[multi_sig.nonce]],
-        program_id,
-    );
+fn process_initialize_global_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut config: GlobalConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let payer = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_sysvar = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+    let program_data = next_account_info(account_info_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // The singleton decides who administers every vault, so whoever deployed the program
+    // creates it rather than whoever gets there first
+    let upgrade_authority = load_upgrade_authority(program_id, program_data)?;
+    if upgrade_authority != Some(*payer.key) {
+        msg!("Global config is created by the upgrade authority, {:?}", upgrade_authority);
+        return Err(err(VaultError::UpgradeAuthorityMismatch));
+    }
 
-    if expected_signer != *multisig_signer.key {
-        return Err(VaultError::InvalidAccountData.into());
+    let (expected_config, bump) = GlobalConfig::find_address(program_id);
+    if expected_config != *config_account.key || !config_account.data_is_empty() {
+        return Err(err(VaultError::InvalidAccountData));
     }
+    config.validate(program_id)?;
+    config.bump = bump;
 
-    let seeds = &[vault_account.key.as_ref(), &[bump]];
-    let signer_seeds = &[&seeds[..]];
+    let rent = load_rent(rent_sysvar)?;
+    let required_lamports = rent.minimum_balance(GlobalConfig::SPACE);
+    if config_account.lamports() < required_lamports {
+        invoke(
+            &system_instruction::transfer(
+                payer.key,
+                config_account.key,
+                required_lamports - config_account.lamports(),
+            ),
+            &[payer.clone(), config_account.clone(), system_program.clone()],
+        )?;
+    }
+    let bump_seed = [bump];
+    let config_seeds: &[&[u8]] = &[GlobalConfig::SEED, &bump_seed];
+    invoke_signed(
+        &system_instruction::allocate(config_account.key, GlobalConfig::SPACE as u64),
+        &[config_account.clone(), system_program.clone()],
+        &[config_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(config_account.key, program_id),
+        &[config_account.clone(), system_program.clone()],
+        &[config_seeds],
+    )?;
+    config.save(&mut config_account.data.borrow_mut())?;
+
+    let clock = load_clock(clock_sysvar)?;
+    emit_global_config_event(
+        config_account.key,
+        payer.key,
+        "global_config_initialized",
+        &config,
+        &clock,
+    );
+
+    msg!("Global config initialized with admin {}", config.admin);
+    Ok(())
+}
+
+// The admin is a vault, so changes go through that vault's multisig
+fn process_update_global_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    mut config: GlobalConfig,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let admin_vault = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
+
+    let current =
+        load_admin_global_config(program_id, config_account, admin_vault, multisig_signer)?;
+
+    config.validate(program_id)?;
+    config.bump = current.bump;
+    config.protocol_fees = current.protocol_fees;
+    config.expected_upgrade_authority = current.expected_upgrade_authority;
+    config.upgrade_check_threshold = current.upgrade_check_threshold;
+    config.save(&mut config_account.data.borrow_mut())?;
+
+    let clock = load_clock(clock_sysvar)?;
+    emit_global_config_event(
+        config_account.key,
+        multisig_signer.key,
+        "global_config_updated",
+        &config,
+        &clock,
+    );
 
-    // Execute the transaction
-    invoke_signed(&ix, remaining_accounts, signer_seeds)?;
+    debug_msg!("Global config updated by {}", admin_vault.key);
+    Ok(())
+}
 
-    // Mark transaction as executed
-    drop(vault_data);
-    let mut vault = Vault::try_from_slice(&vault_account.data.borrow())?;
-    vault.multi_sig_transactions[transaction_id as usize].did_execute = true;
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+// Rotates the expected upgrade authority, e.g. after a planned handover of the deploy key
+fn process_acknowledge_upgrade_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected_upgrade_authority: Option<Pubkey>,
+    upgrade_check_threshold: u64,
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let admin_vault = next_account_info(account_info_iter)?;
+    let multisig_signer = next_account_info(account_info_iter)?;
+    let clock_sysvar = next_account_info(account_info_iter)?;
 
-    let clock = Clock::from_account_info(clock_sysvar)?;
+    let mut config =
+        load_admin_global_config(program_id, config_account, admin_vault, multisig_signer)?;
+    let previous_authority = config.expected_upgrade_authority;
+    config.expected_upgrade_authority = expected_upgrade_authority;
+    config.upgrade_check_threshold = upgrade_check_threshold;
+    config.save(&mut config_account.data.borrow_mut())?;
 
-    // Emit event
-    let execution_event = MultiSigTransactionExecutedEvent {
+    let clock = load_clock(clock_sysvar)?;
+    let acknowledged_event = UpgradeAuthorityAcknowledgedEvent {
         base: create_base_event(
-            *vault_account.key,
-            *executor.key,
-            "multisig_transaction_executed",
+            *config_account.key,
+            *multisig_signer.key,
+            "upgrade_authority_acknowledged",
             &clock,
         ),
-        transaction_id,
-        executor: *executor.key,
-        target_program: transaction.program_id,
+        previous_authority,
+        expected_upgrade_authority,
+        upgrade_check_threshold,
     };
-    emit_event!(execution_event, execution_event);
+    emit_event!(acknowledged_event, acknowledged_event);
 
     msg!(
-        "Multi-sig transaction {} executed by {}",
-        transaction_id,
-        executor.key
+        "Expected upgrade authority set to {:?} above {}",
+        expected_upgrade_authority,
+        upgrade_check_threshold
     );
     Ok(())
 }
 
-fn process_set_multi_sig_owners(
+// The current GlobalConfig, once the caller has shown it is the admin vault's multisig
+fn load_admin_global_config(
     program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    owners: Vec<Pubkey>,
-) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vault_account = next_account_info(account_info_iter)?;
-    let multisig_signer = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let clock_sysvar = next_account_info(account_info_iter)?;
-
-    if !authority.is_signer {
+    config_account: &AccountInfo,
+    admin_vault: &AccountInfo,
+    multisig_signer: &AccountInfo,
+) -> Result<GlobalConfig, ProgramError> {
+    if !multisig_signer.is_signer {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
+    let config = load_global_config(program_id, Some(config_account))?
+        .ok_or(VaultError::InvalidAccountData)?;
+    if config.admin != *admin_vault.key || admin_vault.owner != program_id {
+        return Err(err(VaultError::UnauthorizedAccess));
+    }
+    let vault = Vault::load(&admin_vault.data.borrow())?;
+    validate_multisig_signer(program_id, &vault, admin_vault.key, multisig_signer.key)?;
+    Ok(config)
+}
+
+fn emit_global_config_event(
+    config_key: &Pubkey,
+    signer: &Pubkey,
+    event_type: &str,
+    config: &GlobalConfig,
+    clock: &Clock,
+) {
+    let config_event = GlobalConfigUpdatedEvent {
+        base: create_base_event(*config_key, *signer, event_type, clock),
+        admin: config.admin,
+        max_fee_bps: config.max_fee_bps,
+        allowed_programs: config.allowed_programs.clone(),
+        protocol_fee_recipient: config.protocol_fee_recipient,
+        protocol_fee_bps: config.protocol_fee_bps,
+    };
+    emit_event!(config_event, config_event);
+}
 
+// Instructions the owners act through must be signed by the multisig PDA itself
+fn validate_multisig_signer(
+    program_id: &Pubkey,
+    vault: &Vault,
+    vault_key: &Pubkey,
+    signer: &Pubkey,
+) -> ProgramResult {
     let multi_sig = vault
         .multi_sig
-        .as_mut()
+        .as_ref()
         .ok_or(VaultError::MultisigNotInitialized)?;
-
-    // Validate new owners (no duplicates)
-    let mut unique_owners = owners.clone();
-    unique_owners.sort();
-    unique_owners.dedup();
-    if unique_owners.len() != owners.len() {
-        return Err(VaultError::InvalidAccountData.into());
+    if multi_sig.signer_address(vault_key, program_id) != *signer {
+        return Err(err(VaultError::InsufficientAuthority));
     }
+    Ok(())
+}
 
-    // Store old owners for event
-    let old_owners = multi_sig.owners.clone();
+// CPI signed by the vault PDA, using the seeds it was initialized with
+fn invoke_signed_by_vault(
+    vault: &Vault,
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+) -> ProgramResult {
+    let index_bytes = vault.index.to_le_bytes();
+    let bump_seed = [vault.bump];
+    let mut vault_seeds = Vault::seeds(&vault.authority, &index_bytes);
+    vault_seeds.push(&bump_seed);
+    invoke_signed(instruction, account_infos, &[&vault_seeds])
+}
 
-    // Adjust threshold if necessary
-    if (owners.len() as u64) < multi_sig.threshold {
-        multi_sig.threshold = owners.len() as u64;
+// CPI into token_mint's registered strategy over exactly the accounts pinned for it. Callers
+// have checked strategy_program against the registration.
+fn invoke_strategy(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    token_mint: &Pubkey,
+    strategy_program: &AccountInfo,
+    strategy_accounts: &[AccountInfo],
+    data: Vec<u8>,
+) -> ProgramResult {
+    let pinned = vault.pinned_strategy_accounts(token_mint);
+    if strategy_accounts.len() != pinned.len()
+        || strategy_accounts.iter().zip(pinned).any(|(account, key)| account.key != key)
+    {
+        msg!("Strategy accounts don't match the {} pinned for {}", pinned.len(), token_mint);
+        return Err(err(VaultError::InvalidAccountData));
     }
+    invoke_with_vault_signer(vault, vault_key, strategy_program, strategy_accounts, data)
+}
 
-    multi_sig.owners = owners.clone();
-
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+// CPI with the vault PDA signing wherever it appears in the accounts. Only reached for a
+// registered strategy or the pinned Jupiter program.
+fn invoke_with_vault_signer(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    program: &AccountInfo,
+    cpi_accounts: &[AccountInfo],
+    data: Vec<u8>,
+) -> ProgramResult {
+    let instruction = Instruction {
+        program_id: *program.key,
+        accounts: cpi_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: *account.key,
+                is_signer: account.is_signer || account.key == vault_key,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data,
+    };
+    let mut account_infos = cpi_accounts.to_vec();
+    account_infos.push(program.clone());
+    invoke_signed_by_vault(vault, &instruction, &account_infos)
+}
 
-    let clock = Clock::from_account_info(clock_sysvar)?;
+// Runs after an outflow has been booked. When it takes the mint's window past the auto-pause
+// threshold the outflow still goes through, but the vault is paused behind it until the
+// emergency admin unpauses.
+fn check_circuit_breaker(
+    vault: &mut Vault,
+    vault_key: &Pubkey,
+    actor: &Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    balance_before: u64,
+    clock: &Clock,
+) {
+    if !vault.record_outflow(mint, amount, balance_before, clock.unix_timestamp) {
+        return;
+    }
+    vault.paused = true;
+    vault.paused_at = clock.unix_timestamp;
+    vault.circuit_breaker_tripped = true;
+    vault.log_action(
+        ActionKind::CircuitBreakerTripped,
+        *actor,
+        mint,
+        amount,
+        clock.unix_timestamp,
+    );
 
-    // Emit event
-    let owners_event = MultiSigOwnersUpdatedEvent {
-        base: create_base_event(
-            *vault_account.key,
-            *authority.key,
-            "multisig_owners_updated",
-            &clock,
-        ),
-        old_owners: old_owners.clone(),
-        new_owners: owners.clone(),
+    let window = vault
+        .outflow_windows
+        .iter()
+        .find(|w| w.mint == mint)
+        .cloned()
+        .unwrap_or_default();
+    let tripped_event = CircuitBreakerTrippedEvent {
+        base: create_base_event(*vault_key, *actor, "circuit_breaker_tripped", clock),
+        mint,
+        window_start: window.window_start,
+        opening_balance: window.opening_balance,
+        window_outflow: window.amount_out,
+        threshold_bps: vault.param(ParamKey::AutoPauseThresholdBps) as u16,
     };
-    emit_event!(owners_event, owners_event);
-
+    emit_event!(tripped_event, tripped_event);
     msg!(
-        "Multi-sig owners updated from {:?} to {:?}",
-        old_owners,
-        owners
+        "Circuit breaker tripped: {} out of {} since {}; vault paused",
+        window.amount_out,
+        window.opening_balance,
+        window.window_start
     );
+}
+
+// SOL payouts come out of tracked_lamports, and never out of the rent floor or escrowed tips
+fn ensure_sol_available(vault: &Vault, vault_account: &AccountInfo, amount: u64) -> ProgramResult {
+    let rent_floor = Rent::get()?.minimum_balance(vault_account.data_len());
+    let spendable = vault_account
+        .lamports()
+        .saturating_sub(rent_floor)
+        .saturating_sub(vault.reserved_tip_lamports());
+    if amount > vault.tracked_lamports || amount > spendable {
+        msg!(
+            "Requested {} lamports; {} tracked, {} above the rent floor and tips",
+            amount,
+            vault.tracked_lamports,
+            spendable
+        );
+        return Err(err(VaultError::InvalidAmount));
+    }
     Ok(())
 }
 
-fn process_change_multi_sig_threshold(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    threshold: u64,
+// Lamports escrowed for someone else (execution tips) leave the program-owned vault by a
+// direct debit, which needs no CPI; the recipient must be passed writable
+fn pay_out_of_vault(
+    vault_account: &AccountInfo,
+    recipient: &AccountInfo,
+    lamports: u64,
 ) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    let vault_account = next_account_info(account_info_iter)?;
-    let multisig_signer = next_account_info(account_info_iter)?;
-    let authority = next_account_info(account_info_iter)?;
-    let clock_sysvar = next_account_info(account_info_iter)?;
+    let remaining = vault_account
+        .lamports()
+        .checked_sub(lamports)
+        .ok_or(VaultError::InvalidAmount)?;
+    let credited = recipient
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    **vault_account.try_borrow_mut_lamports()? = remaining;
+    **recipient.try_borrow_mut_lamports()? = credited;
+    Ok(())
+}
 
-    if !authority.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+// Sysvars are checked by key before they are read, so a look-alike account can't feed the
+// program a made-up timestamp or rent schedule
+fn load_clock(clock_sysvar: &AccountInfo) -> Result<Clock, ProgramError> {
+    if !sysvar::clock::check_id(clock_sysvar.key) {
+        msg!("Expected the clock sysvar, got {}", clock_sysvar.key);
+        return Err(err(VaultError::InvalidSysvar));
     }
+    Clock::from_account_info(clock_sysvar)
+}
 
-    let vault_data = vault_account.data.borrow();
-    let mut vault = Vault::try_from_slice(&vault_data)?;
-
-    let multi_sig = vault
-        .multi_sig
-        .as_mut()
-        .ok_or(VaultError::MultisigNotInitialized)?;
+fn load_rent(rent_sysvar: &AccountInfo) -> Result<Rent, ProgramError> {
+    if !sysvar::rent::check_id(rent_sysvar.key) {
+        msg!("Expected the rent sysvar, got {}", rent_sysvar.key);
+        return Err(err(VaultError::InvalidSysvar));
+    }
+    Rent::from_account_info(rent_sysvar)
+}
 
-    // Validate threshold
-    if threshold == 0 || threshold > multi_sig.owners.len() as u64 {
-        return Err(VaultError::InvalidThreshold.into());
+// The optional trailing GlobalConfig account, which must be the program's singleton PDA.
+// None when the caller left it out, so the built-in defaults apply.
+fn load_global_config(
+    program_id: &Pubkey,
+    config_account: Option<&AccountInfo>,
+) -> Result<Option<GlobalConfig>, ProgramError> {
+    let config_account = match config_account {
+        Some(account) => account,
+        None => return Ok(None),
+    };
+    if *config_account.key != GlobalConfig::find_address(program_id).0
+        || config_account.owner != program_id
+    {
+        return Err(err(VaultError::InvalidAccountData));
     }
+    Ok(Some(GlobalConfig::load(&config_account.data.borrow())?))
+}
 
-    // Store old threshold for event
-    let old_threshold = multi_sig.threshold;
+// The GlobalConfig PDA on paths that take it unconditionally; None until it is initialized
+fn load_created_global_config(
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<Option<GlobalConfig>, ProgramError> {
+    if config_account.owner != program_id && config_account.data_is_empty() {
+        if *config_account.key != GlobalConfig::find_address(program_id).0 {
+            return Err(err(VaultError::InvalidAccountData));
+        }
+        return Ok(None);
+    }
+    load_global_config(program_id, Some(config_account))
+}
 
-    multi_sig.threshold = threshold;
+// Funds only move on high-value paths while the program still has the upgrade authority the
+// config expects; the ProgramData account proving it follows the config. An immutable program
+// can't be swapped, so it passes as well.
+fn verify_upgrade_authority(
+    program_id: &Pubkey,
+    config: &GlobalConfig,
+    program_data: Option<&AccountInfo>,
+) -> ProgramResult {
+    let expected = match config.expected_upgrade_authority {
+        Some(expected) => expected,
+        None => return Ok(()),
+    };
+    let program_data = program_data.ok_or_else(|| {
+        msg!("Missing the ProgramData account for the upgrade authority check");
+        VaultError::UpgradeAuthorityMismatch
+    })?;
+    match load_upgrade_authority(program_id, program_data)? {
+        Some(actual) if actual != expected => {
+            msg!("Upgrade authority is {}, expected {}", actual, expected);
+            Err(err(VaultError::UpgradeAuthorityMismatch))
+        }
+        _ => Ok(()),
+    }
+}
 
-    drop(vault_data);
-    vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
+// Upgrade authority recorded in this program's ProgramData account; None once immutable
+fn load_upgrade_authority(
+    program_id: &Pubkey,
+    program_data: &AccountInfo,
+) -> Result<Option<Pubkey>, ProgramError> {
+    if *program_data.key != bpf_loader_upgradeable::get_program_data_address(program_id)
+        || *program_data.owner != bpf_loader_upgradeable::id()
+    {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    Ok(program_data_upgrade_authority(&program_data.data.borrow())?)
+}
 
-    let clock = Clock::from_account_info(clock_sysvar)?;
+// Optional trailing accounts of Deposit and Withdraw that move the fee out of the flow: the
+// GlobalConfig (writable, for the protocol fee totals), the vault fee recipient's token
+// account for the mint and the protocol fee recipient's
+struct FeeRouting<'a, 'b> {
+    config_account: &'a AccountInfo<'b>,
+    config: GlobalConfig,
+    fee_account: &'a AccountInfo<'b>,
+    protocol_fee_account: &'a AccountInfo<'b>,
+}
 
-    // Emit event
-    let threshold_event = MultiSigThresholdUpdatedEvent {
-        base: create_base_event(
-            *vault_account.key,
-            *authority.key,
-            "multisig_threshold_updated",
-            &clock,
-        ),
-        old_threshold,
-        new_threshold: threshold,
+fn load_fee_routing<'a, 'b>(
+    program_id: &Pubkey,
+    vault: &Vault,
+    token_mint: &Pubkey,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<Option<FeeRouting<'a, 'b>>, ProgramError> {
+    let config_account = match account_info_iter.next() {
+        Some(account) => account,
+        None => return Ok(None),
     };
-    emit_event!(threshold_event, threshold_event);
+    let config = load_global_config(program_id, Some(config_account))?
+        .ok_or(VaultError::InvalidAccountData)?;
+    load_fee_routing_accounts(vault, token_mint, config_account, config, account_info_iter)
+        .map(Some)
+}
 
-    msg!(
-        "Multi-sig threshold changed from {} to {}",
-        old_threshold,
-        threshold
-    );
-    Ok(())
+// The two fee recipient token accounts that follow a loaded GlobalConfig
+fn load_fee_routing_accounts<'a, 'b>(
+    vault: &Vault,
+    token_mint: &Pubkey,
+    config_account: &'a AccountInfo<'b>,
+    config: GlobalConfig,
+    account_info_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+) -> Result<FeeRouting<'a, 'b>, ProgramError> {
+    let fee_account = next_account_info(account_info_iter)?;
+    let protocol_fee_account = next_account_info(account_info_iter)?;
+    let token_program = vault.token_program_for(token_mint);
+    let fee_recipient = &vault.fee_config.fee_recipient;
+    if *fee_account.key
+        != get_associated_token_address_with_program_id(fee_recipient, token_mint, &token_program)
+        || *protocol_fee_account.key
+            != get_associated_token_address_with_program_id(
+                &config.protocol_fee_recipient,
+                token_mint,
+                &token_program,
+            )
+    {
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    Ok(FeeRouting {
+        config_account,
+        config,
+        fee_account,
+        protocol_fee_account,
+    })
+}
+
+// Pays both shares of a routed fee out of `source`, which `owner` controls (the vault, for
+// withdrawals, signing with its seeds), and tallies the protocol's share. Returns (vault
+// share, protocol share).
+#[allow(clippy::too_many_arguments)]
+fn route_fee<'b>(
+    routing: &mut FeeRouting<'_, 'b>,
+    vault: &Vault,
+    source: &AccountInfo<'b>,
+    owner: &AccountInfo<'b>,
+    token_program: &AccountInfo<'b>,
+    token_mint: &Pubkey,
+    fee: u64,
+    owner_is_vault: bool,
+    clock: &Clock,
+) -> Result<(u64, u64), ProgramError> {
+    let (vault_share, protocol_share) = routing.config.split_fee(fee);
+    for (destination, share) in [
+        (routing.fee_account, vault_share),
+        (routing.protocol_fee_account, protocol_share),
+    ] {
+        if share == 0 {
+            continue;
+        }
+        let ix =
+            transfer_instruction(token_program.key, source.key, destination.key, owner.key, share)?;
+        let infos = [source.clone(), destination.clone(), owner.clone(), token_program.clone()];
+        if owner_is_vault {
+            invoke_signed_by_vault(vault, &ix, &infos)?;
+        } else {
+            invoke(&ix, &infos)?;
+        }
+    }
+    if protocol_share > 0 {
+        routing
+            .config
+            .record_protocol_fee(token_mint, protocol_share, clock.unix_timestamp);
+        routing
+            .config
+            .save(&mut routing.config_account.data.borrow_mut())?;
+    }
+    Ok((vault_share, protocol_share))
 }
 
 // Validation helper functions
-fn validate_vault_authority(vault: &Vault, authority: &Pubkey) -> Result<(), VaultError> {
-    if vault.authority != *authority {
-        return Err(VaultError::InsufficientAuthority);
+// Every amount-bearing instruction calls this first with no minimum, so zero amounts are
+// refused before they cost compute, move nothing, or stamp timestamps and events. Deposits
+// and withdrawals check again against the mint's minimums, see Vault::min_deposit.
+fn validate_amount(amount: u64, min: u64) -> Result<(), VaultError> {
+    if amount == 0 {
+        return Err(VaultError::InvalidAmount);
+    }
+    if amount < min {
+        msg!("Amount {} is below the minimum of {}", amount, min);
+        return Err(VaultError::InvalidAmount);
     }
     Ok(())
 }
@@ -1710,6 +8385,25 @@ fn validate_emergency_admin(vault: &Vault, admin: &Pubkey) -> Result<(), VaultEr
     Ok(())
 }
 
+// An unusable emergency admin would lock emergency functions for good
+fn validate_emergency_admin_key(
+    admin: &Pubkey,
+    vault: &Pubkey,
+    authority: &Pubkey,
+    allow_same_as_authority: bool,
+) -> Result<(), VaultError> {
+    if *admin == Pubkey::default() || admin == vault {
+        return Err(VaultError::InvalidEmergencyAdmin);
+    }
+    if admin == authority {
+        if !allow_same_as_authority {
+            return Err(VaultError::InvalidEmergencyAdmin);
+        }
+        msg!("Warning: emergency admin is the same key as the vault authority");
+    }
+    Ok(())
+}
+
 fn validate_token_supported(vault: &Vault, token_mint: &Pubkey) -> Result<(), VaultError> {
     let supported = vault
         .supported_tokens
@@ -1722,6 +8416,182 @@ fn validate_token_supported(vault: &Vault, token_mint: &Pubkey) -> Result<(), Va
     Ok(())
 }
 
+// Both sides of a deposit or withdrawal hold the same supported mint, the vault's account is
+// owned by the vault and the user's by the signer. Returns the mint; the borrows end here,
+// before the transfer CPI
+fn validate_token_account_pair(
+    vault: &Vault,
+    vault_key: &Pubkey,
+    token_program: &Pubkey,
+    vault_token_account: &AccountInfo,
+    user_token_account: &AccountInfo,
+    user_authority: &Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let vault_mint = unpack_token_account(vault_token_account)?.mint;
+    let user_token = unpack_token_account(user_token_account)?;
+
+    if vault_mint != user_token.mint {
+        msg!(
+            "Vault token account holds {}, user token account holds {}",
+            vault_mint,
+            user_token.mint
+        );
+        return Err(err(VaultError::TokenMintMismatch));
+    }
+    validate_token_supported(vault, &vault_mint).map_err(|_| VaultError::InvalidMint)?;
+    require_token_program(vault, &vault_mint, token_program)?;
+    verify_vault_token_account(vault_key, &vault_mint, token_program, vault_token_account)?;
+
+    if user_token.owner != *user_authority {
+        msg!("User token account is owned by {}", user_token.owner);
+        return Err(err(VaultError::UserTokenAccountOwnerMismatch));
+    }
+    Ok(vault_mint)
+}
+
+// A delegated deposit spends the delegate's allowance, fee included, so the signer must be
+// the token account's delegate for at least `amount`
+fn validate_delegation(
+    user_token_account: &AccountInfo,
+    delegate: &Pubkey,
+    amount: u64,
+) -> ProgramResult {
+    let user_token = unpack_token_account(user_token_account)?;
+    if user_token.delegate != COption::Some(*delegate) {
+        msg!("Signer is not the delegate of the user token account");
+        return Err(err(VaultError::DelegationInsufficient));
+    }
+    if user_token.delegated_amount < amount {
+        msg!("Delegated {}, depositing {}", user_token.delegated_amount, amount);
+        return Err(err(VaultError::DelegationInsufficient));
+    }
+    Ok(())
+}
+
+// Reads the classic layout, which Token-2022 accounts start with before their extensions
+fn unpack_token_account(account: &AccountInfo) -> Result<TokenAccount, ProgramError> {
+    let data = account.data.borrow();
+    let base = data
+        .get(..TokenAccount::LEN)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    // Past the base, Token-2022 tags what the account is; 2 is a token account
+    if data.len() > TokenAccount::LEN && data[TokenAccount::LEN] != 2 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let token_account = TokenAccount::unpack_from_slice(base)?;
+    if token_account.state == AccountState::Uninitialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(token_account)
+}
+
+fn is_token_program(key: &Pubkey) -> bool {
+    *key == spl_token::ID || *key == TOKEN_2022_PROGRAM_ID
+}
+
+// `token_program` is the one AddSupportedToken recorded for `mint`
+fn require_token_program(vault: &Vault, mint: &Pubkey, token_program: &Pubkey) -> ProgramResult {
+    let expected = vault.token_program_for(mint);
+    if *token_program != expected {
+        msg!("{} is held under token program {}", mint, expected);
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    Ok(())
+}
+
+// A Transfer for either token program. spl_token's builder refuses any program id but its
+// own, and Token-2022 takes the same instruction.
+fn transfer_instruction(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut instruction =
+        token_instruction::transfer(&spl_token::ID, source, destination, authority, &[], amount)?;
+    instruction.program_id = *token_program;
+    Ok(instruction)
+}
+
+// Every path that moves the vault's tokens checks its side with this before any CPI: a
+// `token_program` account holding `mint`, owned by the vault PDA, at the vault's associated
+// token address for that mint and program
+fn verify_vault_token_account(
+    vault_key: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    vault_token_account: &AccountInfo,
+) -> Result<TokenAccount, ProgramError> {
+    if vault_token_account.owner != token_program {
+        msg!("Vault token account belongs to program {}", vault_token_account.owner);
+        return Err(err(VaultError::InvalidAccountOwner));
+    }
+    let token_account = unpack_token_account(vault_token_account)?;
+    if token_account.mint != *mint {
+        msg!("Vault token account holds {}, expected {}", token_account.mint, mint);
+        return Err(err(VaultError::TokenMintMismatch));
+    }
+    if token_account.owner != *vault_key {
+        msg!("Vault token account is owned by {}", token_account.owner);
+        return Err(err(VaultError::VaultTokenAccountOwnerMismatch));
+    }
+    let expected = get_associated_token_address_with_program_id(vault_key, mint, token_program);
+    if expected != *vault_token_account.key {
+        msg!("Vault token account is not the vault's associated token account");
+        return Err(err(VaultError::InvalidAccountData));
+    }
+    // An ATA that was closed and recreated at the same address could carry a delegate or
+    // close authority that lets someone else drain it; the vault never sets either
+    if token_account.delegate.is_some() {
+        msg!("Vault token account has a delegate");
+        return Err(err(VaultError::CompromisedTokenAccount));
+    }
+    if token_account.close_authority.is_some()
+        && token_account.close_authority != COption::Some(*vault_key)
+    {
+        msg!("Vault token account can be closed by someone other than the vault");
+        return Err(err(VaultError::CompromisedTokenAccount));
+    }
+    if token_account.state != AccountState::Initialized {
+        msg!("Vault token account is frozen");
+        return Err(err(VaultError::CompromisedTokenAccount));
+    }
+    Ok(token_account)
+}
+
+// For anything that moves tokens out other than a time lock claim: what time locks still
+// owe stays put
+fn validate_free_balance(vault: &Vault, token_mint: &Pubkey, amount: u64) -> ProgramResult {
+    let free = vault.free_balance(token_mint);
+    if free < amount {
+        msg!(
+            "Only {} of the {} tracked is free; time locks are owed {}",
+            free,
+            vault.tracked_balance(token_mint),
+            vault.locked_for_timelocks(token_mint)
+        );
+        return Err(err(VaultError::InvalidAmount));
+    }
+    Ok(())
+}
+
+// Run after the books and the token account have both moved: a payout that leaves the account
+// short of Vault::custodied_balance took someone else's share, so it's refused outright
+fn require_custody_covered(
+    vault: &Vault,
+    token_mint: &Pubkey,
+    vault_token_account: &AccountInfo,
+) -> ProgramResult {
+    let held = unpack_token_account(vault_token_account)?.amount;
+    let booked = vault.custodied_balance(token_mint)?;
+    if held < booked {
+        msg!("Vault holds {} of {} but has {} booked to it", held, token_mint, booked);
+        return Err(err(VaultError::CustodyShortfall));
+    }
+    Ok(())
+}
+
 fn validate_vault_balance(
     vault: &Vault,
     token_mint: &Pubkey,
@@ -1747,7 +8617,12 @@ fn calculate_fee(amount: u64, fee_bps: u16) -> u64 {
     (amount as u128 * fee_bps as u128 / 10000) as u64
 }
 
-fn update_token_balance(vault: &mut Vault, token_mint: &Pubkey, amount_change: i64, clock: &Clock) {
+fn update_token_balance(
+    vault: &mut Vault,
+    token_mint: &Pubkey,
+    amount_change: i64,
+    clock: &Clock,
+) -> ProgramResult {
     let balance_index = vault
         .token_balances
         .iter()
@@ -1755,7 +8630,12 @@ fn update_token_balance(vault: &mut Vault, token_mint: &Pubkey, amount_change: i
 
     if let Some(index) = balance_index {
         let balance = &mut vault.token_balances[index];
-        balance.balance = (balance.balance as i64 + amount_change) as u64;
+        balance.balance = if amount_change >= 0 {
+            balance.balance.checked_add(amount_change as u64)
+        } else {
+            balance.balance.checked_sub(amount_change.unsigned_abs())
+        }
+        .ok_or(VaultError::ArithmeticOverflow)?;
         balance.last_updated = clock.unix_timestamp;
     } else if amount_change > 0 {
         vault.token_balances.push(TokenBalance {
@@ -1763,7 +8643,10 @@ fn update_token_balance(vault: &mut Vault, token_mint: &Pubkey, amount_change: i
             balance: amount_change as u64,
             last_updated: clock.unix_timestamp,
         });
+    } else if amount_change < 0 {
+        return Err(err(VaultError::ArithmeticOverflow));
     }
+    Ok(())
 }
 
 fn update_supported_token_totals(
@@ -1777,8 +8660,31 @@ fn update_supported_token_totals(
         .iter_mut()
         .find(|t| t.mint == *token_mint)
     {
-        supported_token.total_deposited += deposited;
-        supported_token.total_withdrawn += withdrawn;
+        supported_token.total_deposited = supported_token.total_deposited.saturating_add(deposited);
+        supported_token.total_withdrawn = supported_token.total_withdrawn.saturating_add(withdrawn);
+        if deposited > 0 {
+            supported_token.deposit_count = supported_token.deposit_count.saturating_add(1);
+        }
+        if withdrawn > 0 {
+            supported_token.withdrawal_count = supported_token.withdrawal_count.saturating_add(1);
+        }
     }
 }
 
+fn record_user_action_slot(vault: &mut Vault, user: &Pubkey, slot: u64) {
+    // Entries from earlier slots can never trip the guard again, so drop them
+    vault
+        .user_action_slots
+        .retain(|a| a.last_action_slot == slot && a.user != *user);
+    vault.user_action_slots.push(UserActionSlot {
+        user: *user,
+        last_action_slot: slot,
+    });
+}
+
+fn deposited_in_slot(vault: &Vault, user: &Pubkey, slot: u64) -> bool {
+    vault
+        .user_action_slots
+        .iter()
+        .any(|a| a.user == *user && a.last_action_slot == slot)
+}
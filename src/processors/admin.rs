@@ -8,6 +8,7 @@ use solana_program::{
 };
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 
 pub fn process_transfer_authority(
     _program_id: &Pubkey,
@@ -26,7 +27,7 @@ pub fn process_transfer_authority(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.authority != *current_authority.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.authority = new_authority;
@@ -56,7 +57,7 @@ pub fn process_update_emergency_admin(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.emergency_admin != *current_admin.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.emergency_admin = new_admin;
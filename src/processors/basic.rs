@@ -15,6 +15,7 @@ use spl_token::instruction as token_instruction;
 use spl_associated_token_account::instruction as ata_instruction;
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 use crate::events::{DepositEvent, WithdrawEvent, create_base_event};
 use crate::{emit_event};
 
@@ -129,7 +130,7 @@ pub fn process_deposit(
     }
 
     if vault_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+        return Err(err(VaultError::InvalidAccountOwner));
     }
 
     let vault_data = vault_account.data.borrow();
@@ -142,11 +143,11 @@ pub fn process_deposit(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InsufficientAuthority));
     }
 
     if vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     invoke(
@@ -168,18 +169,18 @@ pub fn process_deposit(
 
     if let Some(legacy_mint) = vault.legacy_mint {
         vault.legacy_total_deposited = vault.legacy_total_deposited.checked_add(amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+            .ok_or(VaultError::ArithmeticOverflow)?;
     }
 
     if let Some(legacy_mint) = vault.legacy_mint {
         if let Some(token_balance) = vault.supported_tokens.iter_mut().find(|tb| tb.mint == legacy_mint) {
             token_balance.balance = token_balance.balance.checked_add(amount)
-                .ok_or(ProgramError::InvalidArgument)?;
+                .ok_or(VaultError::ArithmeticOverflow)?;
         }
     }
 
     vault.total_value_locked = vault.total_value_locked.checked_add(amount)
-        .ok_or(ProgramError::InvalidArgument)?;
+        .ok_or(VaultError::ArithmeticOverflow)?;
 
     drop(vault_data);
     vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
@@ -216,7 +217,7 @@ pub fn process_withdraw(
     }
 
     if vault_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
+        return Err(err(VaultError::InvalidAccountOwner));
     }
 
     let vault_data = vault_account.data.borrow();
@@ -229,15 +230,15 @@ pub fn process_withdraw(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InsufficientAuthority));
     }
 
     if vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if vault.legacy_total_deposited < amount {
-        return Err(ProgramError::InsufficientFunds);
+        return Err(err(VaultError::InvalidAmount));
     }
 
     invoke(
@@ -259,18 +260,18 @@ pub fn process_withdraw(
 
     if let Some(legacy_mint) = vault.legacy_mint {
         vault.legacy_total_deposited = vault.legacy_total_deposited.checked_sub(amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+            .ok_or(VaultError::ArithmeticOverflow)?;
     }
 
     if let Some(legacy_mint) = vault.legacy_mint {
         if let Some(token_balance) = vault.supported_tokens.iter_mut().find(|tb| tb.mint == legacy_mint) {
             token_balance.balance = token_balance.balance.checked_sub(amount)
-                .ok_or(ProgramError::InvalidArgument)?;
+                .ok_or(VaultError::ArithmeticOverflow)?;
         }
     }
 
     vault.total_value_locked = vault.total_value_locked.checked_sub(amount)
-        .ok_or(ProgramError::InvalidArgument)?;
+        .ok_or(VaultError::ArithmeticOverflow)?;
 
     drop(vault_data);
     vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
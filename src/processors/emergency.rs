@@ -10,6 +10,7 @@ use solana_program::{
 use spl_token::instruction as token_instruction;
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 
 pub fn process_pause_vault(
     _program_id: &Pubkey,
@@ -27,7 +28,7 @@ pub fn process_pause_vault(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.emergency_admin != *authority.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.paused = true;
@@ -55,7 +56,7 @@ pub fn process_unpause_vault(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.emergency_admin != *authority.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.paused = false;
@@ -88,7 +89,7 @@ pub fn process_emergency_withdraw(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.emergency_admin != *emergency_admin.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     invoke(
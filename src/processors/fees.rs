@@ -8,6 +8,7 @@ use solana_program::{
 };
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 
 pub fn process_update_fee_config(
     _program_id: &Pubkey,
@@ -34,7 +35,7 @@ pub fn process_update_fee_config(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.fee_config = crate::state::FeeConfig {
@@ -10,6 +10,7 @@ use solana_program::{
 };
 
 use crate::state::{Vault, VoteType};
+use crate::{err, VaultError};
 
 pub fn process_initialize_governance(
     _program_id: &Pubkey,
@@ -40,7 +41,7 @@ pub fn process_initialize_governance(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.governance_config = Some(crate::state::GovernanceConfig {
@@ -81,10 +82,10 @@ pub fn process_create_governance_proposal(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     let governance_config = vault.governance_config.as_ref()
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     if vault.legacy_total_deposited < governance_config.proposal_threshold {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let clock = Clock::from_account_info(clock_sysvar)?;
@@ -135,16 +136,16 @@ pub fn process_cast_vote(
 
     let proposal = vault.governance_proposals.iter_mut()
         .find(|p| p.id == proposal_id)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let clock = Clock::from_account_info(clock_sysvar)?;
 
     if clock.unix_timestamp < proposal.start_time || clock.unix_timestamp > proposal.end_time {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if vault.vote_records.iter().any(|v| v.voter == *voter.key && v.proposal_id == proposal_id) {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let voting_power = 100;
@@ -190,16 +191,16 @@ pub fn process_queue_proposal(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     let governance_config = vault.governance_config.as_ref()
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let proposal_index = vault.governance_proposals.iter()
         .position(|p| p.id == proposal_id)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let clock = Clock::from_account_info(clock_sysvar)?;
 
     if clock.unix_timestamp <= vault.governance_proposals[proposal_index].end_time {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let proposal = &vault.governance_proposals[proposal_index];
@@ -207,7 +208,7 @@ pub fn process_queue_proposal(
     let quorum_reached = total_votes >= governance_config.quorum_threshold as u64;
 
     if !quorum_reached {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.governance_proposals[proposal_index].eta = clock.unix_timestamp + governance_config.timelock_delay;
@@ -237,16 +238,16 @@ pub fn process_execute_governance_proposal(
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     let governance_config = vault.governance_config.as_ref()
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let proposal = vault.governance_proposals.iter_mut()
         .find(|p| p.id == proposal_id)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let clock = Clock::from_account_info(clock_sysvar)?;
 
     if clock.unix_timestamp < proposal.eta {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let total_votes = proposal.for_votes + proposal.against_votes;
@@ -257,7 +258,7 @@ pub fn process_execute_governance_proposal(
     };
 
     if !execution_threshold_reached {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     proposal.executed = true;
@@ -287,10 +288,10 @@ pub fn process_cancel_governance_proposal(
 
     let proposal = vault.governance_proposals.iter_mut()
         .find(|p| p.id == proposal_id)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     if proposal.proposer != *canceller.key && vault.emergency_admin != *canceller.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     proposal.cancelled = true;
@@ -329,7 +330,7 @@ pub fn process_update_governance_config(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if let Some(governance_config) = &mut vault.governance_config {
@@ -339,7 +340,7 @@ pub fn process_update_governance_config(
         governance_config.timelock_delay = timelock_delay;
         governance_config.execution_threshold = execution_threshold;
     } else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     drop(vault_data);
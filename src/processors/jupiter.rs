@@ -10,6 +10,7 @@ use solana_program::{
 };
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 use crate::defi_protocols;
 
 pub fn process_jupiter_swap(
@@ -43,11 +44,11 @@ pub fn process_jupiter_swap(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if *jupiter_program.key != defi_protocols::JUPITER_AGGREGATOR {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let swap_accounts = vec![
@@ -114,11 +115,11 @@ pub fn process_jupiter_route(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if *jupiter_program.key != defi_protocols::JUPITER_AGGREGATOR {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let route_accounts = vec![
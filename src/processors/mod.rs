@@ -2,7 +2,6 @@ pub mod basic;
 pub mod multisig;
 pub mod emergency;
 pub mod multitoken;
-pub mod timelock;
 pub mod yield_farming;
 pub mod jupiter;
 pub mod fees;
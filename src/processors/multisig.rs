@@ -11,6 +11,7 @@ use solana_program::{
 
 use crate::instruction::VaultInstruction;
 use crate::state::{Vault, MultiSigAuthority, Proposal};
+use crate::{err, VaultError};
 use crate::events::{create_base_event, MultiSigInitializedEvent};
 use crate::emit_event;
 
@@ -31,14 +32,14 @@ pub fn process_initialize_multi_sig(
     }
 
     if vault_account.owner != _program_id {
-        return Err(ProgramError::IncorrectProgramId);
+        return Err(err(VaultError::InvalidAccountOwner));
     }
 
     let vault_data = vault_account.data.borrow();
     let mut vault = Vault::try_from_slice(&vault_data)?;
 
     if vault.authority != *initializer.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     vault.multi_sig = Some(MultiSigAuthority {
@@ -86,7 +87,7 @@ pub fn process_create_proposal(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let clock = Clock::from_account_info(clock_sysvar)?;
@@ -127,15 +128,15 @@ pub fn process_approve_proposal(
 
     if let Some(proposal) = vault.proposals.iter_mut().find(|p| p.id == proposal_id) {
         if proposal.approvals.contains(approver.key) {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(err(VaultError::InvalidAccountData));
         }
 
         if let Some(multi_sig) = &vault.multi_sig {
             if !multi_sig.authorities.contains(approver.key) {
-                return Err(ProgramError::InvalidAccountData);
+                return Err(err(VaultError::InvalidAccountData));
             }
         } else if vault.authority != *approver.key {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(err(VaultError::InvalidAccountData));
         }
 
         proposal.approvals.push(*approver.key);
@@ -145,7 +146,7 @@ pub fn process_approve_proposal(
         msg!("Proposal {} approved by {}", proposal_id, approver.key);
         Ok(())
     } else {
-        Err(ProgramError::InvalidAccountData)
+        Err(err(VaultError::InvalidAccountData))
     }
 }
 
@@ -165,7 +166,7 @@ pub fn process_execute_proposal(
         let proposal = &vault.proposals[proposal_index];
 
         if proposal.executed {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(err(VaultError::InvalidAccountData));
         }
 
         let required_approvals = if let Some(multi_sig) = &vault.multi_sig {
@@ -175,7 +176,7 @@ pub fn process_execute_proposal(
         };
 
         if proposal.approvals.len() < required_approvals {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(err(VaultError::InvalidAccountData));
         }
 
         vault.proposals[proposal_index].executed = true;
@@ -186,7 +187,7 @@ pub fn process_execute_proposal(
         msg!("Proposal {} executed", proposal_id);
         Ok(())
     } else {
-        Err(ProgramError::InvalidAccountData)
+        Err(err(VaultError::InvalidAccountData))
     }
 }
 
@@ -210,7 +211,7 @@ pub fn process_reject_proposal(
         let proposal = &vault.proposals[proposal_index];
 
         if proposal.executed {
-            return Err(ProgramError::InvalidAccountData);
+            return Err(err(VaultError::InvalidAccountData));
         }
 
         vault.proposals.remove(proposal_index);
@@ -221,6 +222,6 @@ pub fn process_reject_proposal(
         msg!("Proposal {} rejected", proposal_id);
         Ok(())
     } else {
-        Err(ProgramError::InvalidAccountData)
+        Err(err(VaultError::InvalidAccountData))
     }
 }
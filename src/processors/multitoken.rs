@@ -11,6 +11,7 @@ use spl_token::instruction as token_instruction;
 use spl_associated_token_account::instruction as ata_instruction;
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 
 pub fn process_add_supported_token(
     _program_id: &Pubkey,
@@ -41,7 +42,7 @@ pub fn process_add_supported_token(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InsufficientAuthority));
     }
 
     invoke(
@@ -102,11 +103,11 @@ pub fn process_deposit_multi_token(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if !vault.supported_tokens.iter().any(|tb| tb.mint == mint) {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidMint));
     }
 
     invoke(
@@ -128,10 +129,10 @@ pub fn process_deposit_multi_token(
 
     if let Some(token_balance) = vault.supported_tokens.iter_mut().find(|tb| tb.mint == mint) {
         token_balance.balance = token_balance.balance.checked_add(amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+            .ok_or(VaultError::ArithmeticOverflow)?;
     }
     vault.total_value_locked = vault.total_value_locked.checked_add(amount)
-        .ok_or(ProgramError::InvalidArgument)?;
+        .ok_or(VaultError::ArithmeticOverflow)?;
 
     drop(vault_data);
     vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
@@ -167,15 +168,15 @@ pub fn process_withdraw_multi_token(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if let Some(token_balance) = vault.supported_tokens.iter().find(|tb| tb.mint == mint) {
         if token_balance.balance < amount {
-            return Err(ProgramError::InsufficientFunds);
+            return Err(err(VaultError::InvalidAmount));
         }
     } else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidMint));
     }
 
     invoke(
@@ -197,10 +198,10 @@ pub fn process_withdraw_multi_token(
 
     if let Some(token_balance) = vault.supported_tokens.iter_mut().find(|tb| tb.mint == mint) {
         token_balance.balance = token_balance.balance.checked_sub(amount)
-            .ok_or(ProgramError::InvalidArgument)?;
+            .ok_or(VaultError::ArithmeticOverflow)?;
     }
     vault.total_value_locked = vault.total_value_locked.checked_sub(amount)
-        .ok_or(ProgramError::InvalidArgument)?;
+        .ok_or(VaultError::ArithmeticOverflow)?;
 
     drop(vault_data);
     vault.serialize(&mut &mut vault_account.data.borrow_mut()[..])?;
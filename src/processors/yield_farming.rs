@@ -10,6 +10,7 @@ use solana_program::{
 };
 
 use crate::state::Vault;
+use crate::{err, VaultError};
 
 pub fn process_set_yield_strategy(
     _program_id: &Pubkey,
@@ -35,13 +36,13 @@ pub fn process_set_yield_strategy(
     };
 
     if !is_authorized {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     if let Some(token_balance) = vault.supported_tokens.iter_mut().find(|tb| tb.mint == token_mint) {
         token_balance.yield_strategy = Some(strategy_program);
     } else {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     drop(vault_data);
@@ -80,18 +81,18 @@ pub fn process_harvest_yield(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let token_balance = vault.supported_tokens.iter()
         .find(|tb| tb.mint == token_mint)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let strategy_program_id = token_balance.yield_strategy
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     if strategy_program_id != *strategy_program.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let harvest_accounts = vec![
@@ -150,18 +151,18 @@ pub fn process_compound_yield(
     };
 
     if !is_authorized || vault.paused {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let token_balance = vault.supported_tokens.iter_mut()
         .find(|tb| tb.mint == token_mint)
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     let strategy_program_id = token_balance.yield_strategy
-        .ok_or(ProgramError::InvalidAccountData)?;
+        .ok_or(VaultError::InvalidAccountData)?;
 
     if strategy_program_id != *strategy_program.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(err(VaultError::InvalidAccountData));
     }
 
     let harvest_accounts = vec![
@@ -0,0 +1,104 @@
+// serde `with` modules that render pubkeys as base58 strings instead of byte arrays,
+// used by the state-serde derives in state.rs, and u128 counters as decimal strings so
+// JavaScript consumers don't lose precision
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use solana_program::pubkey::Pubkey;
+use std::str::FromStr;
+
+fn parse<E: Error>(s: &str) -> Result<Pubkey, E> {
+    Pubkey::from_str(s).map_err(|e| E::custom(format!("invalid pubkey {}: {}", s, e)))
+}
+
+pub mod pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(key: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&key.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        parse(&String::deserialize(deserializer)?)
+    }
+}
+
+pub mod option_pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(key: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        key.map(|k| k.to_string()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Pubkey>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| parse(&s))
+            .transpose()
+    }
+}
+
+pub mod vec_pubkey {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(keys: &[Pubkey], serializer: S) -> Result<S::Ok, S::Error> {
+        keys.iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Pubkey>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|s| parse(s))
+            .collect()
+    }
+}
+
+// Strings on the way out; plain numbers are still read, as events carried them when these
+// counters were u64
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum U128Repr {
+    Text(String),
+    Number(u64),
+}
+
+impl U128Repr {
+    fn value<E: Error>(self) -> Result<u128, E> {
+        match self {
+            U128Repr::Text(s) => s
+                .parse()
+                .map_err(|e| E::custom(format!("invalid u128 {}: {}", s, e))),
+            U128Repr::Number(n) => Ok(u128::from(n)),
+        }
+    }
+}
+
+pub mod u128_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u128, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+        U128Repr::deserialize(deserializer)?.value()
+    }
+}
+
+pub mod option_u128_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<u128>, serializer: S) -> Result<S::Ok, S::Error> {
+        value.map(|v| v.to_string()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u128>, D::Error> {
+        Option::<U128Repr>::deserialize(deserializer)?
+            .map(U128Repr::value)
+            .transpose()
+    }
+}
@@ -1,74 +1,213 @@
-use solana_program::pubkey::Pubkey;
+use solana_program::{entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::Serialize;
+#[cfg(feature = "state-serde")]
+use serde::Deserialize;
+
+use crate::{err, VaultError};
 
 // Define VoteType enum
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
 pub enum VoteType {
     For = 0,
     Against = 1,
     Abstain = 2,
 }
 
-// Define GovernanceInstruction type
-pub type GovernanceInstruction = Vec<u8>;
+// How a voting-token balance turns into voting power; see governance::effective_power
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub enum VotingModel {
+    #[default]
+    Linear,
+    Quadratic, // integer square root of the balance, to blunt large holders
+}
+
+// One instruction of a governance proposal. Proposals store each one Borsh-encoded in
+// GovernanceProposal::instructions, and creation refuses any entry that doesn't decode.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct GovernanceInstruction {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data: Vec<u8>,
+}
+
+impl GovernanceInstruction {
+    // Per instruction; MAX_ACCOUNTS full account metas still fit in MAX_ENCODED_LEN
+    pub const MAX_ENCODED_LEN: usize = 512;
+    pub const MAX_ACCOUNTS: usize = 12;
+    // Per proposal, so one proposal never needs more than the headroom a vault starts with
+    pub const MAX_INSTRUCTIONS: usize = 8;
+    pub const MAX_TOTAL_LEN: usize = Vault::DYNAMIC_HEADROOM;
+
+    /// Programs a proposal may call: the vault itself and the system, token and associated
+    /// token programs.
+    pub fn is_allowed_program(program_id: &Pubkey) -> bool {
+        *program_id == crate::ID
+            || *program_id == solana_program::system_program::ID
+            || *program_id == spl_token::ID
+            || *program_id == spl_associated_token_account::ID
+    }
+
+    /// Structural checks run when a proposal is created, logging the index that failed.
+    pub fn validate_all(instructions: &[Vec<u8>]) -> Result<Vec<Self>, VaultError> {
+        if instructions.len() > Self::MAX_INSTRUCTIONS {
+            msg!(
+                "Governance proposal has {} instructions, max {}",
+                instructions.len(),
+                Self::MAX_INSTRUCTIONS
+            );
+            return Err(VaultError::GovernanceProposalTooLarge);
+        }
+        let total: usize = instructions.iter().map(Vec::len).sum();
+        if total > Self::MAX_TOTAL_LEN {
+            msg!(
+                "Governance instructions take {} bytes, max {}",
+                total,
+                Self::MAX_TOTAL_LEN
+            );
+            return Err(VaultError::GovernanceProposalTooLarge);
+        }
+
+        let mut decoded = Vec::with_capacity(instructions.len());
+        for (index, encoded) in instructions.iter().enumerate() {
+            if encoded.len() > Self::MAX_ENCODED_LEN {
+                msg!(
+                    "Governance instruction {} is {} bytes, max {}",
+                    index,
+                    encoded.len(),
+                    Self::MAX_ENCODED_LEN
+                );
+                return Err(VaultError::GovernanceInstructionTooLarge);
+            }
+            let instruction = Self::try_from_slice(encoded).map_err(|_| {
+                msg!("Governance instruction {} does not decode", index);
+                VaultError::InvalidGovernanceInstruction
+            })?;
+            if instruction.accounts.len() > Self::MAX_ACCOUNTS {
+                msg!(
+                    "Governance instruction {} has {} accounts, max {}",
+                    index,
+                    instruction.accounts.len(),
+                    Self::MAX_ACCOUNTS
+                );
+                return Err(VaultError::GovernanceInstructionTooLarge);
+            }
+            if !Self::is_allowed_program(&instruction.program_id) {
+                msg!(
+                    "Governance instruction {} targets {}, which proposals may not call",
+                    index,
+                    instruction.program_id
+                );
+                return Err(VaultError::GovernanceProgramNotAllowed);
+            }
+            decoded.push(instruction);
+        }
+        Ok(decoded)
+    }
+}
 
 // Fee configuration structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct FeeConfig {
     pub deposit_fee_bps: u16,
     pub withdrawal_fee_bps: u16,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub fee_recipient: Pubkey,
 }
 
 // Supported token structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct SupportedToken {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub mint: Pubkey,
     pub bump: u8,
     pub total_deposited: u64,
     pub total_withdrawn: u64,
     pub is_active: bool,
+    pub deposit_count: u32,
+    pub withdrawal_count: u32,
+    pub decimals: u8,
 }
 
+impl SupportedToken {
+    pub const LEN: usize = 32 + 1 + 8 + 8 + 1 + 4 + 4 + 1;
+}
+
+// Owner of Token-2022 mints, recorded for tokens reissued under it
+pub const TOKEN_2022_PROGRAM_ID: Pubkey =
+    solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
 // Token balance structure
-#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct TokenBalance {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub mint: Pubkey,
     pub balance: u64,
     pub last_updated: i64,
 }
 
+impl TokenBalance {
+    pub const LEN: usize = 32 + 8 + 8;
+}
+
+// How a time lock releases its amount between cliff_time and end_time
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub enum VestingSchedule {
+    #[default]
+    Linear,   // pro rata from start_time, nothing before cliff_time
+    CliffAll, // everything at cliff_time
+}
+
 // Time lock structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct TimeLock {
+    pub id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub beneficiary: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey, // default for locks migrated from vaults without a legacy mint
     pub amount: u64,
+    pub released_amount: u64,
     pub start_time: i64,
-    pub duration: i64,
-    pub cliff_duration: Option<i64>,
-    pub is_linear: bool,
-    pub claimed_amount: u64,
-    pub end_time: i64,
     pub cliff_time: i64,
-    pub released_amount: u64,
+    pub end_time: i64,
+    pub schedule: VestingSchedule,
+    pub revocable: bool,
+}
+
+impl TimeLock {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 1;
 }
 
 // Proposal structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct Proposal {
     pub id: u64,
     pub instruction_data: Vec<u8>,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
     pub approvals: Vec<Pubkey>,
     pub executed: bool,
     pub created_at: i64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub proposer: Pubkey,
 }
 
 // Governance proposal structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct GovernanceProposal {
     pub id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub proposer: Pubkey,
     pub title: String,
     pub description: String,
@@ -85,10 +224,46 @@ pub struct GovernanceProposal {
     pub cancelled: bool,
 }
 
+impl GovernanceProposal {
+    // Byte lengths, not characters
+    pub const MAX_TITLE_LEN: usize = 80;
+    pub const MAX_DESCRIPTION_LEN: usize = 1000;
+    // A proposal with the longest title and description, GovernanceInstruction::
+    // MAX_INSTRUCTIONS instructions of MAX_TOTAL_LEN bytes between them and an eta
+    pub const MAX_SPACE: usize = 8 + 32
+        + 4 + Self::MAX_TITLE_LEN
+        + 4 + Self::MAX_DESCRIPTION_LEN
+        + 4 + 4 * GovernanceInstruction::MAX_INSTRUCTIONS + GovernanceInstruction::MAX_TOTAL_LEN
+        + 8 + 8 + 8 + 8 + 8 + 1 + 1 + 9 + 8 + 1;
+
+    /// Checks on a new proposal's title and description, which CreateGovernanceProposal runs
+    /// and off-chain tools can run before sending one: each within its byte limit, and no
+    /// control characters other than newline, C1 ones such as U+009B CSI included, so they
+    /// print safely in a terminal.
+    pub fn validate_text(title: &str, description: &str) -> Result<(), VaultError> {
+        for (field, text, max_len) in [
+            ("title", title, Self::MAX_TITLE_LEN),
+            ("description", description, Self::MAX_DESCRIPTION_LEN),
+        ] {
+            if text.len() > max_len {
+                msg!("Proposal {} is {} bytes, max {}", field, text.len(), max_len);
+                return Err(VaultError::GovernanceProposalTooLarge);
+            }
+            if let Some(index) = text.find(|c: char| c.is_control() && c != '\n') {
+                msg!("Proposal {} has a control character at byte {}", field, index);
+                return Err(VaultError::InvalidProposalText);
+            }
+        }
+        Ok(())
+    }
+}
+
 // Vote record structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct VoteRecord {
     pub proposal_id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub voter: Pubkey,
     pub vote_type: VoteType,
     pub voting_power: u64,
@@ -97,7 +272,9 @@ pub struct VoteRecord {
 
 // Voter registry structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct VoterRegistry {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub voter: Pubkey,
     pub voting_power: u64,
     pub registered_at: i64,
@@ -105,7 +282,9 @@ pub struct VoterRegistry {
 
 // Governance configuration structure
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct GovernanceConfig {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub voting_token_mint: Pubkey,
     pub quorum_threshold: u16,
     pub proposal_threshold: u64,
@@ -117,57 +296,945 @@ pub struct GovernanceConfig {
 
 // Multi-sig structure (adapted from coral-xyz multisig)
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct MultiSig {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
     pub owners: Vec<Pubkey>,
     pub threshold: u64,
     pub nonce: u8,
+    // Canonical bump of the signer PDA, stored at initialization. Multisigs from before it
+    // was stored have 0 until their first ExecuteMultiSigTransaction fills it in.
     pub bump: u8,
 }
 
+impl MultiSig {
+    pub const DEFAULT_MAX_PROPOSALS_PER_DAY: u32 = 10;
+    pub const PROPOSAL_WINDOW_SECONDS: i64 = 86_400;
+    // Time owners have to veto an initiated recovery before it can be finalized, unless
+    // ParamKey::RecoveryChallengeSeconds says otherwise
+    pub const RECOVERY_CHALLENGE_SECONDS: i64 = 3 * 86_400;
+
+    // The PDA [vault, nonce] that signs for the owners, with its canonical bump
+    pub fn find_signer_address(vault: &Pubkey, nonce: u8, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[vault.as_ref(), &[nonce]], program_id)
+    }
+
+    // Same address from the stored bump, which skips the search. Without one it is derived.
+    pub fn signer_address(&self, vault: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        if self.bump != 0 {
+            let seeds: &[&[u8]] = &[vault.as_ref(), &[self.nonce], &[self.bump]];
+            if let Ok(address) = Pubkey::create_program_address(seeds, program_id) {
+                return address;
+            }
+        }
+        Self::find_signer_address(vault, self.nonce, program_id).0
+    }
+}
+
+// Dead-man switch: after `inactivity_period` without owner activity the recovery key
+// may take over the multisig, unless an owner vetoes during the challenge window
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct RecoveryConfig {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub recovery_key: Pubkey,
+    pub inactivity_period: i64,
+}
+
 // Transaction account for multisig execution
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct MultiSigTransaction {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub multisig: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub program_id: Pubkey,
     pub accounts: Vec<TransactionAccount>,
     pub data: Vec<u8>,
     pub signers: Vec<bool>,
     pub did_execute: bool,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub proposer: Pubkey,
     pub created_at: i64,
 }
 
+impl MultiSigTransaction {
+    // Keeps stored payloads small enough to serialize into the vault and to fit the
+    // ExecuteMultiSigTransaction instruction that replays them
+    pub const MAX_DATA_LEN: usize = 900;
+    pub const MAX_ACCOUNTS: usize = 30;
+    pub const APPROVAL_PREFIX: &'static [u8] = b"approve";
+    pub const MAX_MEMO_LEN: usize = 128; // ProposeSolTransfer / ProposeTokenTransfer
+
+    // Borsh size at MAX_ACCOUNTS and MAX_DATA_LEN, with one approval flag per owner
+    pub const fn max_space(owners: usize) -> usize {
+        32 + 32
+            + 4 + Self::MAX_ACCOUNTS * TransactionAccount::LEN
+            + 4 + Self::MAX_DATA_LEN
+            + 4 + owners
+            + 1 + 32 + 8
+    }
+
+    /// What an owner signs with ed25519 to approve `transaction_id` through
+    /// ApproveWithSignature: the prefix, the vault, then the id and the vault's
+    /// owner_set_seqno as little-endian u64s.
+    pub fn approval_message(
+        vault: &Pubkey,
+        transaction_id: u64,
+        owner_set_seqno: u64,
+    ) -> Vec<u8> {
+        let mut message = Self::APPROVAL_PREFIX.to_vec();
+        message.extend_from_slice(vault.as_ref());
+        message.extend_from_slice(&transaction_id.to_le_bytes());
+        message.extend_from_slice(&owner_set_seqno.to_le_bytes());
+        message
+    }
+
+    /// Checks a proposed payload against the creation limits, logging which bound failed.
+    pub fn validate_payload(
+        accounts: &[TransactionAccount],
+        data: &[u8],
+    ) -> Result<(), VaultError> {
+        if accounts.is_empty() {
+            msg!("Multisig transaction needs at least one account");
+            return Err(VaultError::InvalidTransactionData);
+        }
+        if accounts.len() > Self::MAX_ACCOUNTS {
+            msg!(
+                "Multisig transaction has {} accounts, max {}",
+                accounts.len(),
+                Self::MAX_ACCOUNTS
+            );
+            return Err(VaultError::InvalidTransactionData);
+        }
+        if data.len() > Self::MAX_DATA_LEN {
+            msg!(
+                "Multisig transaction data is {} bytes, max {}",
+                data.len(),
+                Self::MAX_DATA_LEN
+            );
+            return Err(VaultError::InvalidTransactionData);
+        }
+        Ok(())
+    }
+}
+
 // Transaction account metadata
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct TransactionAccount {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub pubkey: Pubkey,
     pub is_signer: bool,
     pub is_writable: bool,
 }
 
+impl TransactionAccount {
+    pub const LEN: usize = 32 + 1 + 1;
+}
+
+// What a stored account is to ExecuteMultiSigTransaction, which builds the CPI metas from it
+// rather than from the flags: the signer PDA signs, the vault is writable, the rest are
+// passed as Writable or ReadOnly with their stored is_signer
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub enum AccountRole {
+    #[default]
+    ReadOnly,
+    Writable,
+    MultisigSigner, // keeps the stored writability, the PDA may pay for a transfer
+    VaultPda,
+}
+
+impl AccountRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountRole::ReadOnly => "readonly",
+            AccountRole::Writable => "writable",
+            AccountRole::MultisigSigner => "multisig-signer",
+            AccountRole::VaultPda => "vault",
+        }
+    }
+
+    // Role of an account proposed through CreateMultiSigTransaction, which has no roles
+    pub fn derive(account: &TransactionAccount, vault: &Pubkey, signer: &Pubkey) -> Self {
+        if account.pubkey == *signer {
+            AccountRole::MultisigSigner
+        } else if account.pubkey == *vault && account.is_writable {
+            AccountRole::VaultPda
+        } else if account.is_writable {
+            AccountRole::Writable
+        } else {
+            AccountRole::ReadOnly
+        }
+    }
+
+    /// Checks one role per account against the vault and its multisig signer PDA: only
+    /// that PDA is a MultisigSigner, only the vault a VaultPda, and the other roles agree
+    /// with is_writable, logging the first account that doesn't. The two PDA roles also fix
+    /// is_signer, the signer PDA always signing and the vault never, so the stored flag has
+    /// to say the same as the meta execution builds.
+    pub fn validate(
+        accounts: &[TransactionAccount],
+        roles: &[AccountRole],
+        vault: &Pubkey,
+        signer: &Pubkey,
+    ) -> Result<(), VaultError> {
+        if roles.len() != accounts.len() {
+            msg!("{} roles for {} accounts", roles.len(), accounts.len());
+            return Err(VaultError::InvalidTransactionData);
+        }
+        for (account, role) in accounts.iter().zip(roles) {
+            let valid = match role {
+                AccountRole::MultisigSigner => account.pubkey == *signer && account.is_signer,
+                AccountRole::VaultPda => {
+                    account.pubkey == *vault && account.is_writable && !account.is_signer
+                }
+                AccountRole::Writable => account.pubkey != *signer && account.is_writable,
+                AccountRole::ReadOnly => account.pubkey != *signer && !account.is_writable,
+            };
+            if !valid {
+                msg!("Account {} can't have role {}", account.pubkey, role.name());
+                return Err(VaultError::InvalidTransactionData);
+            }
+        }
+        Ok(())
+    }
+}
+
+// Roles of multisig transaction transaction_id's accounts, in order. Transactions stored
+// before roles existed have none and execute from their flags.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct TransactionRoles {
+    pub transaction_id: u64,
+    pub roles: Vec<AccountRole>,
+}
+
+impl TransactionRoles {
+    pub const MAX_LEN: usize = 8 + 4 + MultiSigTransaction::MAX_ACCOUNTS;
+}
+
+// Checked once a multisig transaction's CPI has run: account must still hold at least
+// min_post_balance, as lamports or, with is_token, as an SPL token amount. A token account
+// that no longer unpacks fails the check.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct BalanceAssertion {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub account: Pubkey,
+    pub min_post_balance: u64,
+    pub is_token: bool,
+}
+
+impl BalanceAssertion {
+    pub const LEN: usize = 32 + 8 + 1;
+    pub const MAX_PER_TRANSACTION: usize = 4;
+
+    // At most MAX_PER_TRANSACTION, each on one of the transaction's own accounts: no other
+    // account reaches the CPI, so no other balance can change
+    pub fn validate(
+        assertions: &[BalanceAssertion],
+        accounts: &[TransactionAccount],
+    ) -> Result<(), VaultError> {
+        if assertions.len() > Self::MAX_PER_TRANSACTION {
+            msg!(
+                "{} balance assertions, at most {}",
+                assertions.len(),
+                Self::MAX_PER_TRANSACTION
+            );
+            return Err(VaultError::InvalidTransactionData);
+        }
+        if let Some(stray) = assertions
+            .iter()
+            .find(|a| !accounts.iter().any(|account| account.pubkey == a.account))
+        {
+            msg!("Balance assertion on {}, which the transaction doesn't use", stray.account);
+            return Err(VaultError::InvalidTransactionData);
+        }
+        Ok(())
+    }
+}
+
+// Balance assertions of multisig transaction transaction_id, set when it was created
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct TransactionAssertions {
+    pub transaction_id: u64,
+    pub assertions: Vec<BalanceAssertion>,
+}
+
+impl TransactionAssertions {
+    pub const MAX_LEN: usize =
+        8 + 4 + BalanceAssertion::MAX_PER_TRANSACTION * BalanceAssertion::LEN;
+}
+
+// Staging PDA [b"transaction_buffer", vault, proposer] for a multisig payload too large to
+// propose in one transaction. The proposer writes it in chunks, then
+// FinalizeTransactionFromBuffer copies it into a MultiSigTransaction and closes the account.
+// data is allocated at total_len up front; written_len is how much of it has been filled in.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct TransactionBuffer {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub vault: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub proposer: Pubkey,
+    pub bump: u8,
+    pub total_len: u32,
+    pub written_len: u32,
+    pub data: Vec<u8>,
+}
+
+impl TransactionBuffer {
+    pub const SEED: &'static [u8] = b"transaction_buffer";
+
+    pub fn find_address(vault: &Pubkey, proposer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED, vault.as_ref(), proposer.as_ref()], program_id)
+    }
+
+    pub const fn space(total_len: usize) -> usize {
+        32 + 32 + 1 + 4 + 4 + 4 + total_len
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.written_len == self.total_len
+    }
+
+    // Copies `chunk` in at `offset`. Chunks may overlap what is already there, so a resent
+    // write is harmless, but can't leave a gap or run past total_len.
+    pub fn write(&mut self, offset: u32, chunk: &[u8]) -> Result<(), VaultError> {
+        let end = (offset as usize)
+            .checked_add(chunk.len())
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        if offset > self.written_len || end > self.total_len as usize {
+            msg!(
+                "Write of {} bytes at {} past {} written of {}",
+                chunk.len(),
+                offset,
+                self.written_len,
+                self.total_len
+            );
+            return Err(VaultError::InvalidTransactionData);
+        }
+        self.data[offset as usize..end].copy_from_slice(chunk);
+        self.written_len = self.written_len.max(end as u32);
+        Ok(())
+    }
+
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = data;
+        Self::deserialize(&mut buf).map_err(|_| err(VaultError::InvalidAccountData))
+    }
+
+    pub fn save(&self, data: &mut [u8]) -> ProgramResult {
+        let bytes = self.try_to_vec()?;
+        if bytes.len() > data.len() {
+            return Err(err(VaultError::AccountTooSmall));
+        }
+        data[..bytes.len()].copy_from_slice(&bytes);
+        data[bytes.len()..].fill(0);
+        Ok(())
+    }
+}
+
+// Bytes [offset, offset + len) of a template's data that each instantiation fills in
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ByteRange {
+    pub offset: u16,
+    pub len: u16,
+}
+
+// A multisig payload with a fixed program and account list whose data differs between
+// uses only in mutable_ranges, e.g. the amount of a recurring payout
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct TransactionTemplate {
+    pub id: u64,
+    pub name: String,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub program_id: Pubkey,
+    pub accounts: Vec<TransactionAccount>,
+    pub data_prefix: Vec<u8>, // full instruction data; mutable ranges hold placeholder bytes
+    pub mutable_ranges: Vec<ByteRange>, // ascending and non-overlapping
+}
+
+impl TransactionTemplate {
+    pub const MAX_NAME_LEN: usize = 32;
+    pub const MAX_RANGES: usize = 8;
+
+    // Checks a template before it is stored: the payload limits of the transactions it will
+    // produce, plus ranges that lie inside the data in ascending order without overlapping
+    pub fn validate(&self) -> Result<(), VaultError> {
+        if self.name.is_empty() || self.name.len() > Self::MAX_NAME_LEN {
+            msg!("Template name must be 1..={} bytes", Self::MAX_NAME_LEN);
+            return Err(VaultError::InvalidTransactionData);
+        }
+        MultiSigTransaction::validate_payload(&self.accounts, &self.data_prefix)?;
+        if self.mutable_ranges.is_empty() || self.mutable_ranges.len() > Self::MAX_RANGES {
+            msg!("Template needs 1..={} mutable ranges", Self::MAX_RANGES);
+            return Err(VaultError::InvalidTransactionData);
+        }
+        let mut next_free = 0usize;
+        for range in &self.mutable_ranges {
+            let start = range.offset as usize;
+            let end = start + range.len as usize;
+            if range.len == 0 || start < next_free || end > self.data_prefix.len() {
+                msg!(
+                    "Mutable range {}+{} is empty, overlaps or runs past {} data bytes",
+                    range.offset,
+                    range.len,
+                    self.data_prefix.len()
+                );
+                return Err(VaultError::InvalidTransactionData);
+            }
+            next_free = end;
+        }
+        Ok(())
+    }
+
+    // Total bytes an instantiation has to supply
+    pub fn fill_len(&self) -> usize {
+        self.mutable_ranges.iter().map(|r| r.len as usize).sum()
+    }
+
+    // Instruction data with `fill_bytes` spliced into the mutable ranges, in range order
+    pub fn instantiate(&self, fill_bytes: &[u8]) -> Result<Vec<u8>, VaultError> {
+        if fill_bytes.len() != self.fill_len() {
+            msg!(
+                "Template {} takes {} fill bytes, got {}",
+                self.id,
+                self.fill_len(),
+                fill_bytes.len()
+            );
+            return Err(VaultError::InvalidTransactionData);
+        }
+        let mut data = self.data_prefix.clone();
+        let mut fill = fill_bytes;
+        for range in &self.mutable_ranges {
+            let (chunk, rest) = fill.split_at(range.len as usize);
+            let start = range.offset as usize;
+            data[start..start + chunk.len()].copy_from_slice(chunk);
+            fill = rest;
+        }
+        Ok(data)
+    }
+}
+
 // Yield strategy configuration
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct YieldStrategyConfig {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub token_mint: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub strategy_program: Pubkey,
     pub auto_compound: bool,
     pub last_harvested_slot: u64,
+    pub deployed_amount: u64, // principal currently sitting in the strategy
+    pub realized_pnl: i64,    // measured returns minus principal, over the strategy's lifetime
+    // Highest realized_pnl reached so far; a performance fee may only be taken above it
+    pub high_water_mark: i64,
+    pub loss_pending: bool, // set by a losing withdrawal, blocks deploys until acknowledged
+}
+
+// The accounts, in order, that every CPI into token_mint's strategy must pass. Set with its
+// registration, so neither the authority's later calls nor the emergency admin's exit can
+// hand the vault's signature to accounts nobody registered.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct StrategyAccounts {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub token_mint: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
+    pub accounts: Vec<Pubkey>,
+}
+
+impl StrategyAccounts {
+    pub const MAX_ACCOUNTS: usize = 16;
+}
+
+impl YieldStrategyConfig {
+    // Allocation cap that leaves deployments unrestricted
+    pub const MAX_ALLOCATION_BPS: u16 = 10_000;
+
+    // Whether `deployed` sitting in the strategy stays within `max_allocation_bps` of the
+    // mint's holdings, i.e. `deployed` plus the `tracked` balance left in the vault
+    pub fn within_allocation_cap(deployed: u64, tracked: u64, max_allocation_bps: u16) -> bool {
+        let holdings = deployed as u128 + tracked as u128;
+        deployed as u128 * 10_000 <= holdings * max_allocation_bps as u128
+    }
+
+    // Books a withdrawal of `principal` that measurably brought `returned` back to the vault
+    // and returns the realized pnl of this withdrawal
+    pub fn record_withdrawal(&mut self, principal: u64, returned: u64) -> Result<i64, VaultError> {
+        self.deployed_amount = self
+            .deployed_amount
+            .checked_sub(principal)
+            .ok_or(VaultError::InvalidAmount)?;
+        let pnl = i64::try_from(returned as i128 - principal as i128)
+            .map_err(|_| VaultError::ArithmeticOverflow)?;
+        self.realized_pnl = self
+            .realized_pnl
+            .checked_add(pnl)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.high_water_mark = self.high_water_mark.max(self.realized_pnl);
+        if pnl < 0 {
+            self.loss_pending = true;
+        }
+        Ok(pnl)
+    }
+}
+
+// Vault-side switch for a single strategy, independent of the vault-wide pause.
+// Withdrawals always go through; Exiting also blocks harvests, so nothing flows back in.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub enum StrategyStatus {
+    #[default]
+    Active,
+    DepositsPaused, // no deploys or compounding, harvests still allowed
+    Exiting,
+}
+
+// How far a vault's setup has come; see Vault::refresh_setup_stage. Vaults created with the
+// setup gate on refuse deposits and withdrawals until FinalizeSetup makes them Ready.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub enum SetupStage {
+    #[default]
+    Initialized,
+    TokensConfigured,
+    MultisigConfigured, // supported tokens and a multisig, waiting for FinalizeSetup
+    Ready,
+}
+
+impl StrategyStatus {
+    pub fn allows_deploy(self) -> bool {
+        self == StrategyStatus::Active
+    }
+
+    pub fn allows_harvest(self) -> bool {
+        self != StrategyStatus::Exiting
+    }
 }
 
 // Emergency action log entry
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct EmergencyActionLog {
     pub timestamp: i64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub admin: Pubkey,
-    pub action: u8, // 0: Pause, 1: Unpause, 2: EmergencyWithdraw
+    pub action: u8, // see the action constants below
     pub details: Vec<u8>,
 }
 
+impl EmergencyActionLog {
+    pub const PAUSE: u8 = 0;
+    pub const UNPAUSE: u8 = 1;
+    pub const EMERGENCY_WITHDRAW: u8 = 2;
+    pub const RECOVERY_INITIATED: u8 = 3;
+    pub const RECOVERY_VETOED: u8 = 4;
+    pub const RECOVERY_FINALIZED: u8 = 5;
+    pub const TRANSACTION_EXPEDITED: u8 = 6;
+}
+
+// Vault-wide behaviour toggles, changed through UpdateVaultConfig
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub struct VaultConfig {
+    pub same_slot_guard: bool, // reject withdrawals in the same slot as the user's deposit
+}
+
+// Tunables kept in Vault::params and changed through SetParam. Discriminants are the stored
+// Param::key, so keys are only ever appended.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub enum ParamKey {
+    DepositFeeBps = 0,
+    WithdrawalFeeBps = 1,
+    MaxProposalsPerDay = 2,
+    // 1 makes EmergencyWithdraw need a multisig owner's signature next to the emergency admin's
+    EmergencyRequiresCosign = 3,
+    // Smallest deposit / withdrawal, in base units of whichever mint moves; 0 only refuses 0.
+    // Mints given their own minimums with SetMintMinimums don't use these.
+    MinDepositAmount = 4,
+    MinWithdrawalAmount = 5,
+    // Seconds a new multisig transaction stays approvable and executable; 0 never expires
+    MultisigTransactionTtl = 6,
+    // Circuit breaker: an outflow that takes a mint's withdrawals within the current
+    // OutflowWindowSeconds past this share of what it held when the window opened pauses
+    // the vault until the emergency admin unpauses it. 0 in either turns it off.
+    AutoPauseThresholdBps = 7,
+    OutflowWindowSeconds = 8,
+    // Seconds between RequestWithdrawal and the ClaimWithdrawal that pays it out. Non-zero
+    // turns off instant Withdraw, so funds deployed to strategies get time to come back.
+    WithdrawalDelaySeconds = 9,
+    // Bitmask of the governance proposals the multisig has to ratify as a council before they
+    // execute, see governance::requires_council. 0 leaves token voting alone in charge.
+    CouncilCategories = 10,
+    // Seconds owners have to veto an initiated recovery before FinalizeRecovery can run.
+    // Unset means MultiSig::RECOVERY_CHALLENGE_SECONDS.
+    RecoveryChallengeSeconds = 11,
+}
+
+impl ParamKey {
+    pub const MAX_FEE_BPS: u64 = 1_000;
+    pub const MAX_TRANSACTION_TTL: u64 = 365 * 24 * 60 * 60;
+    pub const MAX_OUTFLOW_WINDOW: u64 = 30 * 24 * 60 * 60;
+    pub const MAX_WITHDRAWAL_DELAY: u64 = 30 * 24 * 60 * 60;
+    pub const MIN_RECOVERY_CHALLENGE: u64 = 24 * 60 * 60;
+    pub const MAX_RECOVERY_CHALLENGE: u64 = 30 * 24 * 60 * 60;
+
+    // Every key, in discriminant order
+    pub const ALL: &'static [ParamKey] = &[
+        ParamKey::DepositFeeBps,
+        ParamKey::WithdrawalFeeBps,
+        ParamKey::MaxProposalsPerDay,
+        ParamKey::EmergencyRequiresCosign,
+        ParamKey::MinDepositAmount,
+        ParamKey::MinWithdrawalAmount,
+        ParamKey::MultisigTransactionTtl,
+        ParamKey::AutoPauseThresholdBps,
+        ParamKey::OutflowWindowSeconds,
+        ParamKey::WithdrawalDelaySeconds,
+        ParamKey::CouncilCategories,
+        ParamKey::RecoveryChallengeSeconds,
+    ];
+
+    pub fn from_u8(key: u8) -> Option<Self> {
+        Self::ALL.get(key as usize).copied()
+    }
+
+    // Inclusive range SetParam accepts
+    pub fn bounds(self) -> (u64, u64) {
+        self.bounds_capped(Self::MAX_FEE_BPS)
+    }
+
+    // Same, with fees capped at `fee_cap` instead of MAX_FEE_BPS (see GlobalConfig)
+    pub fn bounds_capped(self, fee_cap: u64) -> (u64, u64) {
+        match self {
+            ParamKey::DepositFeeBps | ParamKey::WithdrawalFeeBps => (0, fee_cap),
+            ParamKey::MaxProposalsPerDay => (1, 1_000),
+            ParamKey::EmergencyRequiresCosign => (0, 1),
+            ParamKey::MinDepositAmount | ParamKey::MinWithdrawalAmount => (0, u64::MAX),
+            ParamKey::MultisigTransactionTtl => (0, Self::MAX_TRANSACTION_TTL),
+            ParamKey::AutoPauseThresholdBps => (0, 10_000),
+            ParamKey::OutflowWindowSeconds => (0, Self::MAX_OUTFLOW_WINDOW),
+            ParamKey::WithdrawalDelaySeconds => (0, Self::MAX_WITHDRAWAL_DELAY),
+            ParamKey::CouncilCategories => (0, crate::governance::COUNCIL_CATEGORIES),
+            ParamKey::RecoveryChallengeSeconds => {
+                (Self::MIN_RECOVERY_CHALLENGE, Self::MAX_RECOVERY_CHALLENGE)
+            }
+        }
+    }
+
+    pub fn validate(self, value: u64) -> Result<(), VaultError> {
+        self.validate_capped(value, Self::MAX_FEE_BPS)
+    }
+
+    pub fn validate_capped(self, value: u64, fee_cap: u64) -> Result<(), VaultError> {
+        let (min, max) = self.bounds_capped(fee_cap);
+        if value < min || value > max {
+            msg!("{:?} must be within {}..={}, got {}", self, min, max, value);
+            return Err(VaultError::InvalidAmount);
+        }
+        Ok(())
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct Param {
+    pub key: u8, // ParamKey discriminant
+    pub value: u64,
+}
+
+// SetMintMinimums' deposit and withdrawal minimums for one mint, in its base units
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct MintMinimums {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub min_deposit: u64,
+    pub min_withdrawal: u64,
+}
+
+// Slot of a user's latest deposit, only kept while the same-slot guard is on
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct UserActionSlot {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub user: Pubkey,
+    pub last_action_slot: u64,
+}
+
+// Multisig proposals created by one owner in the current rate limit window
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ProposalWindow {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub proposer: Pubkey,
+    pub window_start: i64,
+    pub proposals_in_window: u32,
+}
+
+// What left the vault in one mint since window_start, measured against opening_balance: the
+// tracked balance just before the window's first outflow
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct OutflowWindow {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey, // default for SOL
+    pub window_start: i64,
+    pub opening_balance: u64,
+    pub amount_out: u64,
+}
+
+// Off-chain statement hash signed off by the multisig through an executed transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct Attestation {
+    pub message_hash: [u8; 32],
+    pub transaction_id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
+    pub approvers: Vec<Pubkey>,
+    pub attested_at: i64,
+}
+
+// What a recent_actions entry records. None marks a slot that hasn't been written yet.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub enum ActionKind {
+    #[default]
+    None,
+    Initialize,
+    Deposit,
+    Withdraw,
+    WithdrawSol,
+    Transfer,
+    AddSupportedToken,
+    CreateTimeLock,
+    ClaimTimeLock,
+    UpdateConfig,
+    Migrate,
+    MultiSigInitialized,
+    MultiSigProposed,
+    MultiSigApproved,
+    MultiSigExecuted,
+    MultiSigOwnersChanged,
+    MultiSigThresholdChanged,
+    ProposalLimitChanged,
+    Attestation,
+    RecoveryConfigured,
+    RecoveryInitiated,
+    RecoveryVetoed,
+    RecoveryFinalized,
+    StrategySet,
+    StrategyDeployed,
+    StrategyWithdrawn,
+    StrategyLossAcknowledged,
+    ExecutionTipFunded,
+    MultiSigCancelled,
+    Paused,
+    Unpaused,
+    PauseExtended,
+    PauseDurationChanged,
+    StreamConfigChanged,
+    MultiSigPruned,
+    ExecutionDelayChanged,
+    MultiSigExpedited,
+    ParamChanged,
+    TemplateCreated,
+    TemplateDeleted,
+    GovernanceProposalCreated,
+    GovernanceVoteCast,
+    GovernanceProposalQueued,
+    GovernanceConfigUpdated,
+    VoterRegistered,
+    VoterUnregistered,
+    EmergencyWithdraw,
+    Swap,
+    StrategyStatusChanged,
+    StrategyEmergencyExit,
+    FeesCollected,
+    MultiSigExpiryExtended,
+    LamportsSwept,
+    CircuitBreakerTripped,
+    TimeLockSplit,
+    WithdrawalRequested,
+    WithdrawalCancelled,
+    SupportedTokenRefreshed,
+    GovernanceProposalExecuted,
+    CouncilProposalApproved,
+    SetupFinalized,
+    TimeLockMintAssigned,
+    TimeLockCancelled,
+}
+
+// One entry of the vault's recent_actions ring. mint is default and amount 0 where the
+// action has neither (for multisig actions amount carries the transaction id).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ActionRecord {
+    pub kind: ActionKind,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub actor: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+impl ActionRecord {
+    pub const LEN: usize = 1 + 32 + 32 + 8 + 8;
+}
+
+// Lamports escrowed in the vault for the executor of a multisig transaction
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ExecutionTip {
+    pub transaction_id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub funder: Pubkey,
+    pub lamports: u64,
+}
+
+// When a multisig transaction first gathered enough approvals, which starts its execution
+// delay. expedited transactions were cleared by the emergency admin to skip the delay.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ThresholdReached {
+    pub transaction_id: u64,
+    pub reached_at: i64,
+    pub expedited: bool,
+}
+
+impl ThresholdReached {
+    pub const LEN: usize = 8 + 8 + 1;
+}
+
+// Deadline of a multisig transaction created under a MultisigTransactionTtl. From
+// expires_at on it can no longer be approved, executed or extended.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct TransactionExpiry {
+    pub transaction_id: u64,
+    pub expires_at: i64,
+}
+
+impl TransactionExpiry {
+    pub const LEN: usize = 8 + 8;
+}
+
+// Claim ticket left by RequestWithdrawal. Its amount is held back from the free balance until
+// the owner claims it from available_at on, or cancels it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct WithdrawalTicket {
+    pub id: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub owner: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub requested_at: i64,
+    pub available_at: i64,
+}
+
+// Effective voting power of the voter registry when a governance proposal was created, which
+// its quorum is measured against
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct ProposalSnapshot {
+    pub proposal_id: u64,
+    pub effective_supply: u64,
+}
+
+// A governance proposal that needed the council's approval when it was created, see
+// ParamKey::CouncilCategories. approved_at is set by CouncilApproveProposal.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct CouncilReview {
+    pub proposal_id: u64,
+    pub approved_at: Option<i64>,
+}
+
+impl CouncilReview {
+    // Proposals that can still go before the council at once
+    pub const MAX_PENDING: usize = 16;
+}
+
+// Terminal states Tick records, see Vault::settled_items
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub enum SettledKind {
+    #[default]
+    TransactionExpired, // multisig transaction id, expired before it executed
+    ProposalPassed, // governance proposal id, voting ended with quorum and approval
+    ProposalDefeated,
+    TimeLockVested, // time lock id, fully vested and waiting to be claimed
+    // governance proposal id, voting ended with no supply snapshot to measure quorum against
+    ProposalUndetermined,
+}
+
+impl SettledKind {
+    pub fn is_proposal(self) -> bool {
+        matches!(
+            self,
+            Self::ProposalPassed | Self::ProposalDefeated | Self::ProposalUndetermined
+        )
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct SettledItem {
+    pub kind: SettledKind,
+    pub id: u64,
+    pub settled_at: i64,
+}
+
+// Voting tokens a voter escrowed in the vault until unlock_at, in exchange for a boost that
+// shrinks as the lock runs down
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct VoteLock {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub voter: Pubkey,
+    pub amount: u64,
+    pub locked_at: i64,
+    pub unlock_at: i64,
+}
+
+// A JupiterRoute swap, with amounts measured from the vault's token accounts around the CPI
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct SwapRecord {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub input_mint: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub output_mint: Pubkey,
+    pub amount_in_measured: u64,
+    pub amount_out_measured: u64,
+    pub timestamp: i64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub executor: Pubkey,
+}
+
+// Capacity of Vault::recent_actions; module level so the field's array type can name it
+pub const RECENT_ACTIONS_LEN: usize = 32;
+
 // Main Vault structure (what tests expect)
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct Vault {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub authority: Pubkey,
     pub bump: u8,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub emergency_admin: Pubkey,
     pub paused: bool,
     pub supported_tokens: Vec<SupportedToken>,
@@ -176,8 +1243,10 @@ pub struct Vault {
     pub proposals: Vec<Proposal>,
     pub next_proposal_id: u64,
     pub fee_config: FeeConfig,
-    pub total_value_locked: u64,
-    pub total_fees_collected: u64,
+    // u128 since state_version 3, so the sum over several mints near u64::MAX can't overflow
+    pub total_value_locked: u128,
+    pub total_fees_collected: u128,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::option_pubkey"))]
     pub legacy_mint: Option<Pubkey>,
     pub legacy_total_deposited: u64,
     pub governance_config: Option<GovernanceConfig>,
@@ -189,16 +1258,1322 @@ pub struct Vault {
     pub multi_sig_transactions: Vec<MultiSigTransaction>,
     pub yield_strategies: Vec<YieldStrategyConfig>,
     pub emergency_logs: Vec<EmergencyActionLog>,
+    pub config: VaultConfig,
+    pub user_action_slots: Vec<UserActionSlot>,
+    pub index: u16,
+    pub next_time_lock_id: u64,
+    pub state_version: u8,
+    // 0 means MultiSig::DEFAULT_MAX_PROPOSALS_PER_DAY. Kept on the vault rather than inside
+    // MultiSig so accounts written before the limit existed still decode.
+    pub max_proposals_per_day: u32,
+    pub proposal_windows: Vec<ProposalWindow>, // open windows only, at most MAX_PROPOSAL_WINDOWS
+    pub attestations: Vec<Attestation>, // newest last, at most MAX_ATTESTATIONS
+    pub multisig_recovery: Option<RecoveryConfig>,
+    pub last_owner_activity: i64, // create/approve/execute by an owner
+    pub recovery_initiated_at: Option<i64>,
+    // Fixed size so logging never grows the account; recent_actions_head is the next slot
+    // to overwrite, i.e. the oldest entry once the ring has wrapped
+    pub recent_actions: [ActionRecord; RECENT_ACTIONS_LEN],
+    pub recent_actions_head: u8,
+    // Tips stay in the vault's lamports until paid or refunded, so SOL withdrawals leave
+    // reserved_tip_lamports() untouched
+    pub execution_tips: Vec<ExecutionTip>,
+    pub cancelled_transactions: Vec<u64>, // multisig transaction ids
+    // A pause lapses on its own at paused_at + max_pause_duration; 0 keeps it indefinite.
+    // Kept on the vault rather than in VaultConfig so the config layout doesn't shift.
+    pub paused_at: i64,
+    pub max_pause_duration: i64,
+    // Time lock claims: whether they keep working while paused, and their own fee
+    pub streams_exempt_from_pause: bool,
+    pub stream_claim_fee_bps: u16,
+    // multi_sig_transaction_ids[i] is the id of multi_sig_transactions[i]; only
+    // push_multisig_transaction and remove_multisig_transactions change either Vec. An id
+    // field inside MultiSigTransaction would change how every stored transaction decodes.
+    pub next_transaction_id: u64,
+    pub multi_sig_transaction_ids: Vec<u64>,
+    // Seconds between a transaction reaching its threshold and becoming executable; 0 lets
+    // it run straight away
+    pub execution_delay_seconds: i64,
+    pub threshold_reached: Vec<ThresholdReached>,
+    // Only keys set through SetParam are stored; see Vault::param for the defaults
+    pub params: Vec<Param>,
+    pub templates: Vec<TransactionTemplate>,
+    pub next_template_id: u64,
+    // How token balances turn into voting power. vote_raw_balances[i] is the balance that
+    // vote_records[i].voting_power was computed from under voting_model.
+    pub voting_model: VotingModel,
+    pub proposal_snapshots: Vec<ProposalSnapshot>,
+    pub vote_raw_balances: Vec<u64>,
+    // Vote escrow: a lock of max_lock_duration or more earns max_boost_bps of its amount as
+    // extra voting power. Escrowed tokens sit in the vault's ATA for the voting mint without
+    // being tracked in token_balances.
+    pub vote_locks: Vec<VoteLock>,
+    pub max_lock_duration: i64,
+    pub max_boost_bps: u16,
+    pub swap_history: Vec<SwapRecord>, // newest last, at most MAX_SWAP_RECORDS
+    // strategy_allocation_caps[i] is the max_allocation_bps of yield_strategies[i]; strategies
+    // without an entry, which includes every strategy set before caps existed, are uncapped.
+    pub strategy_allocation_caps: Vec<u16>,
+    // Same indexing as strategy_allocation_caps; missing entries are Active
+    pub strategy_statuses: Vec<StrategyStatus>,
+    // multisig_owner_since[i] is when multi_sig.owners[i] joined. Owners without an entry
+    // predate it and may approve any pending transaction.
+    pub multisig_owner_since: Vec<i64>,
+    // Bumped whenever multi_sig.owners is set or replaced. Signed approvals cover it, so a
+    // signature made under one owner set is void under the next.
+    pub owner_set_seqno: u64,
+    // Withheld fees per mint, waiting for CollectFees. Held in the vault's token accounts but
+    // never part of token_balances, so no user withdrawal can spend them.
+    pub accrued_fees: Vec<TokenBalance>,
+    // Transactions without an entry never expire
+    pub transaction_expiries: Vec<TransactionExpiry>,
+    // SOL held for depositors, the only lamports WithdrawSOL and Transfer may pay out.
+    // Lamports sent straight to the vault address only count once they're swept in.
+    pub tracked_lamports: u64,
+    // Circuit breaker state, see ParamKey::AutoPauseThresholdBps. A pause the breaker set
+    // doesn't lapse after max_pause_duration; only UnpauseVault lifts it.
+    pub outflow_windows: Vec<OutflowWindow>,
+    pub circuit_breaker_tripped: bool,
+    // Sum of total_value_locked * seconds held, brought up to tvl_last_updated by accrue_tvl.
+    // Two observations of the pair give the time-weighted average TVL between them.
+    pub tvl_cumulative: u128,
+    pub tvl_last_updated: i64,
+    // Pending two-step withdrawals, oldest first, at most MAX_WITHDRAWAL_TICKETS and
+    // MAX_WITHDRAWAL_TICKETS_PER_OWNER. Their amounts are escrowed out of token_balances.
+    pub withdrawal_tickets: Vec<WithdrawalTicket>,
+    pub next_withdrawal_ticket_id: u64,
+    // Slot of the last EmitHealthCheck, 0 before the first
+    pub last_heartbeat_slot: u64,
+    // supported_token_programs[i] owns supported_tokens[i]'s mint; tokens without an entry
+    // are classic SPL Token, as every token added before Token-2022 support is.
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
+    pub supported_token_programs: Vec<Pubkey>,
+    // Only proposals that needed the council when created have an entry, by proposal_id
+    pub council_reviews: Vec<CouncilReview>,
+    // Vaults from InitializeVaultV2 without skip_setup_gate hold deposits and withdrawals
+    // until setup_stage is Ready. Older vaults, and the other initializers, leave it off.
+    pub setup_stage: SetupStage,
+    pub setup_gate: bool,
+    // What the permissionless Tick crank settled, so status queries don't re-derive it.
+    // tick_cursor is where the next Tick resumes in transactions, proposals, then time locks.
+    pub settled_items: Vec<SettledItem>,
+    pub tick_cursor: u32,
+    // By transaction_id; transactions without an entry execute from their stored flags
+    pub transaction_roles: Vec<TransactionRoles>,
+    // Only transactions created with assertions have an entry
+    pub transaction_assertions: Vec<TransactionAssertions>,
+    // Strategies registered without accounts, or before pinning existed, have no entry and
+    // can only be called with none until SetYieldStrategyV2 pins them
+    pub strategy_accounts: Vec<StrategyAccounts>,
+    // One entry per supported mint given its own minimums, so a 6-decimal and a 9-decimal
+    // mint can each have a sensible floor
+    pub mint_minimums: Vec<MintMinimums>,
+}
+
+impl Vault {
+    pub const SEED_PREFIX: &'static [u8] = b"vault";
+
+    // 0 is the pre-versioning layout (see migration.rs), 1 predates recent_actions and 2
+    // kept total_value_locked and total_fees_collected as u64. All are upgraded with
+    // MigrateVault.
+    pub const STATE_VERSION: u8 = 3;
+
+    // Oldest attestations are dropped past this; their events remain the permanent record
+    pub const MAX_ATTESTATIONS: usize = 32;
+    // Proposers with a rate limit window open at once
+    pub const MAX_PROPOSAL_WINDOWS: usize = 32;
+    pub const MAX_SWAP_RECORDS: usize = 16;
+    // CreateTimeLock and SplitTimeLock stop here, so a beneficiary can't split a grant into
+    // enough dust locks to fill the account
+    pub const MAX_OPEN_TIME_LOCKS: usize = 64;
+
+    pub const MAX_TEMPLATES: usize = 16;
+
+    pub const MAX_WITHDRAWAL_TICKETS: usize = 8;
+    pub const MAX_WITHDRAWAL_TICKETS_PER_OWNER: usize = 2;
+    // About a minute of slots between two EmitHealthCheck heartbeats
+    pub const HEALTH_CHECK_INTERVAL_SLOTS: u64 = 150;
+    // Most items one Tick looks at, to keep it well inside the compute budget
+    pub const MAX_TICK_ITEMS: u16 = 64;
+    // Tick records nothing more once settled_items holds this many, until some are pruned
+    pub const MAX_SETTLED_ITEMS: usize = 64;
+
+    pub const RECENT_ACTIONS_LEN: usize = RECENT_ACTIONS_LEN;
+
+    // Borsh size of recent_actions plus its head index
+    pub const RECENT_ACTIONS_SPACE: usize = Self::RECENT_ACTIONS_LEN * ActionRecord::LEN + 1;
+
+    // Spare bytes past the Borsh payload for the Vec fields to grow into
+    pub const DYNAMIC_HEADROOM: usize = 1024;
+
+    // A supported mint with its balance and accrued fee entries
+    pub const TOKEN_SPACE: usize = SupportedToken::LEN + 2 * TokenBalance::LEN;
+    // Multisig size the transaction estimate assumes: each stored transaction keeps an
+    // approval flag per owner
+    pub const SPACE_OWNERS: usize = 10;
+    // A stored multisig transaction at its limits with its id, expiry and threshold entries
+    pub const TRANSACTION_SPACE: usize = MultiSigTransaction::max_space(Self::SPACE_OWNERS)
+        + 8
+        + TransactionExpiry::LEN
+        + ThresholdReached::LEN
+        + TransactionRoles::MAX_LEN
+        + TransactionAssertions::MAX_LEN;
+
+    /// Borsh size of a vault with every Vec empty and every Option None. It is measured on an
+    /// encoded default vault, so new fields are counted without anyone updating a tally.
+    pub fn empty_space() -> Result<usize, ProgramError> {
+        Self::default().required_space()
+    }
+
+    /// What InitializeVault and Initialize allocate: room for a few mints, time locks, a
+    /// proposal and a transaction, with DYNAMIC_HEADROOM for the multisig, params and logs.
+    /// A CPI can't create more than MAX_PERMITTED_DATA_INCREASE bytes at once.
+    pub fn initial_space() -> Result<usize, ProgramError> {
+        Ok(Self::space_for(4, 4, 1, 1)? + Self::DYNAMIC_HEADROOM)
+    }
+
+    /// Account size for a vault holding `tokens` supported mints, `time_locks` time locks,
+    /// `proposals` governance proposals and `transactions` multisig transactions, each at
+    /// its largest and with nothing else stored. Off-chain code sizes vault accounts with
+    /// this rather than guessing from the in-memory layout.
+    pub fn space_for(
+        tokens: usize,
+        time_locks: usize,
+        proposals: usize,
+        transactions: usize,
+    ) -> Result<usize, ProgramError> {
+        Ok(Self::empty_space()?
+            + tokens * Self::TOKEN_SPACE
+            + time_locks * TimeLock::LEN
+            + proposals * GovernanceProposal::MAX_SPACE
+            + transactions * Self::TRANSACTION_SPACE)
+    }
+
+    // Index 0 keeps the original [b"vault", authority] seeds so existing vaults stay valid
+    pub fn seeds<'a>(authority: &'a Pubkey, index_bytes: &'a [u8; 2]) -> Vec<&'a [u8]> {
+        if *index_bytes == [0, 0] {
+            vec![Self::SEED_PREFIX, authority.as_ref()]
+        } else {
+            vec![Self::SEED_PREFIX, authority.as_ref(), &index_bytes[..]]
+        }
+    }
+
+    pub fn find_address(program_id: &Pubkey, authority: &Pubkey, index: u16) -> (Pubkey, u8) {
+        let index_bytes = index.to_le_bytes();
+        Pubkey::find_program_address(&Self::seeds(authority, &index_bytes), program_id)
+    }
+
+    pub fn pause_expires_at(&self) -> Option<i64> {
+        if self.max_pause_duration > 0 && !self.circuit_breaker_tripped {
+            Some(self.paused_at.saturating_add(self.max_pause_duration))
+        } else {
+            None
+        }
+    }
+
+    // tvl_cumulative as it would read at now, counting the current TVL as held since
+    // tvl_last_updated. None on overflow. A vault that has never accrued (tvl_last_updated 0,
+    // as written before the accumulator existed) has nothing to add.
+    pub fn tvl_cumulative_at(&self, now: i64) -> Option<u128> {
+        if self.tvl_last_updated == 0 {
+            return Some(self.tvl_cumulative);
+        }
+        let elapsed = now.saturating_sub(self.tvl_last_updated).max(0) as u128;
+        self.total_value_locked
+            .checked_mul(elapsed)
+            .and_then(|held| self.tvl_cumulative.checked_add(held))
+    }
+
+    // Call before every change to total_value_locked, so the old TVL is credited for the
+    // time it was actually held
+    pub fn accrue_tvl(&mut self, now: i64) -> Result<(), ProgramError> {
+        self.tvl_cumulative = self
+            .tvl_cumulative_at(now)
+            .ok_or(VaultError::ArithmeticOverflow)?;
+        self.tvl_last_updated = self.tvl_last_updated.max(now);
+        Ok(())
+    }
+
+    // The paused flag only counts until the pause runs out
+    // The stage what is configured so far amounts to. Ready is only reached through
+    // FinalizeSetup and never left.
+    pub fn configured_setup_stage(&self) -> SetupStage {
+        if self.setup_stage == SetupStage::Ready {
+            return SetupStage::Ready;
+        }
+        match (self.supported_tokens.is_empty(), self.multi_sig.is_some()) {
+            (true, _) => SetupStage::Initialized,
+            (false, false) => SetupStage::TokensConfigured,
+            (false, true) => SetupStage::MultisigConfigured,
+        }
+    }
+
+    pub fn refresh_setup_stage(&mut self) {
+        self.setup_stage = self.configured_setup_stage();
+    }
+
+    // Whether deposits and withdrawals wait for FinalizeSetup
+    pub fn awaits_setup(&self) -> bool {
+        self.setup_gate && self.setup_stage != SetupStage::Ready
+    }
+
+    pub fn is_paused(&self, now: i64) -> bool {
+        match self.pause_expires_at() {
+            Some(expires_at) => self.paused && now < expires_at,
+            None => self.paused,
+        }
+    }
+
+    pub fn effective_max_proposals_per_day(&self) -> u32 {
+        self.param(ParamKey::MaxProposalsPerDay) as u32
+    }
+
+    // Value of a registry parameter. Unset keys fall back to the dedicated field they
+    // replaced, so vaults configured before the registry keep their settings:
+    // - DepositFeeBps / WithdrawalFeeBps: fee_config, 0 for new vaults
+    // - MaxProposalsPerDay: max_proposals_per_day, or MultiSig::DEFAULT_MAX_PROPOSALS_PER_DAY
+    // - RecoveryChallengeSeconds: MultiSig::RECOVERY_CHALLENGE_SECONDS, which had no field
+    pub fn param(&self, key: ParamKey) -> u64 {
+        if let Some(param) = self.params.iter().find(|p| p.key == key as u8) {
+            return param.value;
+        }
+        match key {
+            ParamKey::DepositFeeBps => self.fee_config.deposit_fee_bps as u64,
+            ParamKey::WithdrawalFeeBps => self.fee_config.withdrawal_fee_bps as u64,
+            ParamKey::MaxProposalsPerDay => match self.max_proposals_per_day {
+                0 => MultiSig::DEFAULT_MAX_PROPOSALS_PER_DAY as u64,
+                limit => limit as u64,
+            },
+            ParamKey::EmergencyRequiresCosign
+            | ParamKey::MinDepositAmount
+            | ParamKey::MinWithdrawalAmount
+            | ParamKey::MultisigTransactionTtl
+            | ParamKey::AutoPauseThresholdBps
+            | ParamKey::OutflowWindowSeconds
+            | ParamKey::WithdrawalDelaySeconds
+            | ParamKey::CouncilCategories => 0,
+            ParamKey::RecoveryChallengeSeconds => MultiSig::RECOVERY_CHALLENGE_SECONDS as u64,
+        }
+    }
+
+    // Counts an outflow of `amount` against the mint's window, opening a new one once the
+    // last has run out. `balance_before` is the tracked balance before the outflow. True
+    // when the window total now exceeds AutoPauseThresholdBps of its opening balance;
+    // nothing is recorded while the breaker is off.
+    pub fn record_outflow(
+        &mut self,
+        mint: Pubkey,
+        amount: u64,
+        balance_before: u64,
+        now: i64,
+    ) -> bool {
+        let threshold_bps = self.param(ParamKey::AutoPauseThresholdBps);
+        let window_seconds = self.param(ParamKey::OutflowWindowSeconds) as i64;
+        if threshold_bps == 0 || window_seconds == 0 {
+            return false;
+        }
+        let index = match self.outflow_windows.iter().position(|w| w.mint == mint) {
+            Some(index) => index,
+            None => {
+                self.outflow_windows.push(OutflowWindow {
+                    mint,
+                    ..Default::default()
+                });
+                self.outflow_windows.len() - 1
+            }
+        };
+        let window = &mut self.outflow_windows[index];
+        if window.amount_out == 0 || now >= window.window_start.saturating_add(window_seconds) {
+            window.window_start = now;
+            window.opening_balance = balance_before;
+            window.amount_out = 0;
+        }
+        window.amount_out = window.amount_out.saturating_add(amount);
+        let limit = window.opening_balance as u128 * threshold_bps as u128;
+        window.amount_out as u128 * 10_000 > limit
+    }
+
+    // The mint's own deposit minimum, or MinDepositAmount when it has none
+    pub fn min_deposit(&self, mint: &Pubkey) -> u64 {
+        match self.mint_minimums.iter().find(|m| m.mint == *mint) {
+            Some(minimums) => minimums.min_deposit,
+            None => self.param(ParamKey::MinDepositAmount),
+        }
+    }
+
+    // Same for withdrawals and MinWithdrawalAmount
+    pub fn min_withdrawal(&self, mint: &Pubkey) -> u64 {
+        match self.mint_minimums.iter().find(|m| m.mint == *mint) {
+            Some(minimums) => minimums.min_withdrawal,
+            None => self.param(ParamKey::MinWithdrawalAmount),
+        }
+    }
+
+    pub fn set_mint_minimums(&mut self, minimums: MintMinimums) {
+        self.mint_minimums.retain(|m| m.mint != minimums.mint);
+        self.mint_minimums.push(minimums);
+    }
+
+    // Only enforced while the vault has a multisig to draw a co-signer from
+    pub fn emergency_requires_cosign(&self) -> bool {
+        self.multi_sig.is_some() && self.param(ParamKey::EmergencyRequiresCosign) == 1
+    }
+
+    // Stores a value already checked with ParamKey::validate, returning the previous one
+    pub fn set_param(&mut self, key: ParamKey, value: u64) -> u64 {
+        let old = self.param(key);
+        match self.params.iter_mut().find(|p| p.key == key as u8) {
+            Some(param) => param.value = value,
+            None => self.params.push(Param {
+                key: key as u8,
+                value,
+            }),
+        }
+        old
+    }
+
+    pub fn fee_for(&self, key: ParamKey, amount: u64) -> u64 {
+        (amount as u128 * self.param(key) as u128 / 10000) as u64
+    }
+
+    // Counts one more proposal for `proposer`. Lapsed windows are dropped first, so a
+    // proposer whose window ran out starts a fresh one, and at most MAX_PROPOSAL_WINDOWS
+    // proposers can have one open at a time. Nothing is recorded when refused.
+    pub fn record_proposal(&mut self, proposer: &Pubkey, now: i64) -> Result<u32, VaultError> {
+        let limit = self.effective_max_proposals_per_day();
+        self.proposal_windows.retain(|w| {
+            now.saturating_sub(w.window_start) < MultiSig::PROPOSAL_WINDOW_SECONDS
+        });
+        let index = match self.proposal_windows.iter().position(|w| w.proposer == *proposer) {
+            Some(index) => index,
+            None if self.proposal_windows.len() >= Self::MAX_PROPOSAL_WINDOWS => {
+                return Err(VaultError::ProposalRateLimited);
+            }
+            None => {
+                self.proposal_windows.push(ProposalWindow {
+                    proposer: *proposer,
+                    window_start: now,
+                    proposals_in_window: 0,
+                });
+                self.proposal_windows.len() - 1
+            }
+        };
+
+        let window = &mut self.proposal_windows[index];
+        if window.proposals_in_window >= limit {
+            return Err(VaultError::ProposalRateLimited);
+        }
+        window.proposals_in_window += 1;
+        Ok(window.proposals_in_window)
+    }
+
+    pub fn log_emergency_action(&mut self, admin: Pubkey, action: u8, details: Vec<u8>, now: i64) {
+        self.emergency_logs.push(EmergencyActionLog {
+            timestamp: now,
+            admin,
+            action,
+            details,
+        });
+    }
+
+    pub fn time_lock_index(&self, time_lock_id: u64) -> Option<usize> {
+        self.time_locks.iter().position(|lock| lock.id == time_lock_id)
+    }
+
+    // Refuses another lock once MAX_OPEN_TIME_LOCKS still have principal to release. Fully
+    // released locks don't count, so finished grants never block new ones.
+    pub fn require_time_lock_room(&self) -> Result<(), VaultError> {
+        let open = self
+            .time_locks
+            .iter()
+            .filter(|lock| lock.released_amount < lock.amount)
+            .count();
+        if open >= Self::MAX_OPEN_TIME_LOCKS {
+            msg!("{} time locks are still open", open);
+            return Err(VaultError::TimeLockLimitReached);
+        }
+        Ok(())
+    }
+
+    pub fn tracked_balance(&self, mint: &Pubkey) -> u64 {
+        self.token_balances
+            .iter()
+            .find(|b| b.mint == *mint)
+            .map(|b| b.balance)
+            .unwrap_or(0)
+    }
+
+    // Still owed to vesting beneficiaries. Locks migrated without a mint pay out in any
+    // supported mint, so they are held against each one.
+    pub fn locked_for_timelocks(&self, mint: &Pubkey) -> u64 {
+        self.time_locks
+            .iter()
+            .filter(|lock| lock.mint == *mint || lock.mint == Pubkey::default())
+            .fold(0u64, |total, lock| {
+                total.saturating_add(lock.amount.saturating_sub(lock.released_amount))
+            })
+    }
+
+    // Amounts of pending withdrawal requests in the mint, already out of the tracked balance
+    pub fn escrowed_for_withdrawals(&self, mint: &Pubkey) -> u64 {
+        self.withdrawal_tickets
+            .iter()
+            .filter(|request| request.mint == *mint)
+            .fold(0u64, |total, request| total.saturating_add(request.amount))
+    }
+
+    // What can leave the vault without eating into a reservation. Strategy deployments and
+    // withdrawal requests come out of the tracked balance when they are made, so only time
+    // locks are subtracted here. Accrued fees live outside token_balances and never count.
+    pub fn free_balance(&self, mint: &Pubkey) -> u64 {
+        self.tracked_balance(mint).saturating_sub(self.locked_for_timelocks(mint))
+    }
+
+    // What the vault's token account for `mint` has to hold at least: the tracked balance,
+    // time locks included, plus escrowed withdrawal requests and accrued fees
+    pub fn custodied_balance(&self, mint: &Pubkey) -> Result<u64, VaultError> {
+        self.tracked_balance(mint)
+            .checked_add(self.escrowed_for_withdrawals(mint))
+            .and_then(|total| total.checked_add(self.accrued_fee(mint)))
+            .ok_or(VaultError::ArithmeticOverflow)
+    }
+
+    pub fn withdrawal_tickets_of(&self, owner: &Pubkey) -> usize {
+        self.withdrawal_tickets.iter().filter(|ticket| ticket.owner == *owner).count()
+    }
+
+    pub fn withdrawal_ticket_index(&self, id: u64) -> Option<usize> {
+        self.withdrawal_tickets.iter().position(|request| request.id == id)
+    }
+
+    pub fn accrued_fee(&self, mint: &Pubkey) -> u64 {
+        self.accrued_fees
+            .iter()
+            .find(|b| b.mint == *mint)
+            .map(|b| b.balance)
+            .unwrap_or(0)
+    }
+
+    pub fn accrue_fee(&mut self, mint: Pubkey, amount: u64, now: i64) {
+        if amount == 0 {
+            return;
+        }
+        match self.accrued_fees.iter_mut().find(|b| b.mint == mint) {
+            Some(entry) => {
+                entry.balance = entry.balance.saturating_add(amount);
+                entry.last_updated = now;
+            }
+            None => self.accrued_fees.push(TokenBalance {
+                mint,
+                balance: amount,
+                last_updated: now,
+            }),
+        }
+    }
+
+    // Drops the mint's entry and returns what it held
+    pub fn take_accrued_fee(&mut self, mint: &Pubkey) -> u64 {
+        match self.accrued_fees.iter().position(|b| b.mint == *mint) {
+            Some(index) => self.accrued_fees.remove(index).balance,
+            None => 0,
+        }
+    }
+
+    pub fn pinned_strategy_accounts(&self, token_mint: &Pubkey) -> &[Pubkey] {
+        self.strategy_accounts
+            .iter()
+            .find(|pinned| pinned.token_mint == *token_mint)
+            .map(|pinned| pinned.accounts.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn pin_strategy_accounts(&mut self, token_mint: Pubkey, accounts: Vec<Pubkey>) {
+        self.strategy_accounts.retain(|pinned| pinned.token_mint != token_mint);
+        if !accounts.is_empty() {
+            self.strategy_accounts.push(StrategyAccounts { token_mint, accounts });
+        }
+    }
+
+    pub fn strategy_index(&self, token_mint: &Pubkey) -> Option<usize> {
+        self.yield_strategies
+            .iter()
+            .position(|s| s.token_mint == *token_mint)
+    }
+
+    pub fn strategy_allocation_cap(&self, index: usize) -> u16 {
+        self.strategy_allocation_caps
+            .get(index)
+            .copied()
+            .unwrap_or(YieldStrategyConfig::MAX_ALLOCATION_BPS)
+    }
+
+    pub fn set_strategy_allocation_cap(&mut self, index: usize, max_allocation_bps: u16) {
+        if self.strategy_allocation_caps.len() <= index {
+            self.strategy_allocation_caps
+                .resize(index + 1, YieldStrategyConfig::MAX_ALLOCATION_BPS);
+        }
+        self.strategy_allocation_caps[index] = max_allocation_bps;
+    }
+
+    pub fn supported_token_program(&self, index: usize) -> Pubkey {
+        self.supported_token_programs.get(index).copied().unwrap_or(spl_token::ID)
+    }
+
+    // The token program holding `mint`, classic SPL Token unless one was recorded for it
+    pub fn token_program_for(&self, mint: &Pubkey) -> Pubkey {
+        self.supported_tokens
+            .iter()
+            .position(|token| token.mint == *mint)
+            .map_or(spl_token::ID, |index| self.supported_token_program(index))
+    }
+
+    pub fn set_supported_token_program(&mut self, index: usize, token_program: Pubkey) {
+        if self.supported_token_programs.len() <= index {
+            self.supported_token_programs.resize(index + 1, spl_token::ID);
+        }
+        self.supported_token_programs[index] = token_program;
+    }
+
+    pub fn strategy_status(&self, index: usize) -> StrategyStatus {
+        self.strategy_statuses.get(index).copied().unwrap_or_default()
+    }
+
+    pub fn set_strategy_status(&mut self, index: usize, status: StrategyStatus) {
+        if self.strategy_statuses.len() <= index {
+            self.strategy_statuses.resize(index + 1, StrategyStatus::Active);
+        }
+        self.strategy_statuses[index] = status;
+    }
+
+    pub fn owner_since(&self, owner_index: usize) -> i64 {
+        self.multisig_owner_since.get(owner_index).copied().unwrap_or(0)
+    }
+
+    // Swaps in a new owner set, returning the old one. Owners who stay keep their join time
+    // and their approvals on pending transactions, which follow them to their new position;
+    // everyone else joins at `now` with nothing approved.
+    pub fn replace_multisig_owners(&mut self, owners: Vec<Pubkey>, now: i64) -> Vec<Pubkey> {
+        let multi_sig = match self.multi_sig.as_mut() {
+            Some(multi_sig) => multi_sig,
+            None => return Vec::new(),
+        };
+        let old_owners = std::mem::replace(&mut multi_sig.owners, owners);
+        self.owner_set_seqno = self.owner_set_seqno.wrapping_add(1);
+        let previous: Vec<Option<usize>> = multi_sig
+            .owners
+            .iter()
+            .map(|owner| old_owners.iter().position(|old| old == owner))
+            .collect();
+
+        self.multisig_owner_since = previous
+            .iter()
+            .map(|index| index.map_or(now, |i| self.owner_since(i)))
+            .collect();
+        for transaction in self.multi_sig_transactions.iter_mut().filter(|tx| !tx.did_execute) {
+            transaction.signers = previous
+                .iter()
+                .map(|index| index.map_or(false, |i| transaction.signers.get(i) == Some(&true)))
+                .collect();
+        }
+        old_owners
+    }
+
+    pub fn execution_tip(&self, transaction_id: u64) -> Option<&ExecutionTip> {
+        self.execution_tips
+            .iter()
+            .find(|t| t.transaction_id == transaction_id)
+    }
+
+    // Removes the tip so it can only ever be paid or refunded once
+    pub fn take_execution_tip(&mut self, transaction_id: u64) -> Option<ExecutionTip> {
+        let index = self
+            .execution_tips
+            .iter()
+            .position(|t| t.transaction_id == transaction_id)?;
+        Some(self.execution_tips.remove(index))
+    }
+
+    pub fn reserved_tip_lamports(&self) -> u64 {
+        self.execution_tips
+            .iter()
+            .fold(0u64, |total, t| total.saturating_add(t.lamports))
+    }
+
+    // Lamports above the rent floor that are neither escrowed tips nor tracked
+    pub fn untracked_lamports(&self, lamports: u64, rent_floor: u64) -> u64 {
+        lamports
+            .saturating_sub(rent_floor)
+            .saturating_sub(self.reserved_tip_lamports())
+            .saturating_sub(self.tracked_lamports)
+    }
+
+    pub fn is_transaction_cancelled(&self, transaction_id: u64) -> bool {
+        self.cancelled_transactions.contains(&transaction_id)
+    }
+
+    pub fn multisig_transaction_index(&self, transaction_id: u64) -> Option<usize> {
+        self.multi_sig_transaction_ids
+            .iter()
+            .position(|id| *id == transaction_id)
+    }
+
+    pub fn multisig_transaction(&self, transaction_id: u64) -> Option<&MultiSigTransaction> {
+        let index = self.multisig_transaction_index(transaction_id)?;
+        self.multi_sig_transactions.get(index)
+    }
+
+    pub fn multisig_transaction_id(&self, index: usize) -> Option<u64> {
+        self.multi_sig_transaction_ids.get(index).copied()
+    }
+
+    // Each stored transaction with its id. Callers go through this, push_multisig_transaction
+    // and remove_multisig_transactions rather than pairing the two Vecs themselves.
+    pub fn multisig_transactions(&self) -> impl Iterator<Item = (u64, &MultiSigTransaction)> {
+        self.multi_sig_transaction_ids
+            .iter()
+            .copied()
+            .zip(&self.multi_sig_transactions)
+    }
+
+    pub fn threshold_reached(&self, transaction_id: u64) -> Option<&ThresholdReached> {
+        self.threshold_reached
+            .iter()
+            .find(|t| t.transaction_id == transaction_id)
+    }
+
+    // Earliest time the transaction may execute, or None while it lacks approvals. A
+    // transaction that met its threshold without a record (approved before delays existed)
+    // counts from its creation here; executing it records one first and waits the delay.
+    pub fn executable_at(&self, transaction_id: u64, threshold: u64) -> Option<i64> {
+        if let Some(reached) = self.threshold_reached(transaction_id) {
+            if reached.expedited {
+                return Some(i64::MIN);
+            }
+            return Some(reached.reached_at.saturating_add(self.execution_delay_seconds));
+        }
+        let transaction = self.multisig_transaction(transaction_id)?;
+        let approvals = transaction.signers.iter().filter(|&&signed| signed).count();
+        if (approvals as u64) < threshold {
+            return None;
+        }
+        Some(transaction.created_at.saturating_add(self.execution_delay_seconds))
+    }
+
+    pub fn transaction_expires_at(&self, transaction_id: u64) -> Option<i64> {
+        self.transaction_expiries
+            .iter()
+            .find(|e| e.transaction_id == transaction_id)
+            .map(|e| e.expires_at)
+    }
+
+    pub fn is_transaction_expired(&self, transaction_id: u64, now: i64) -> bool {
+        self.transaction_expires_at(transaction_id)
+            .map_or(false, |expires_at| now >= expires_at)
+    }
+
+    pub fn transaction_roles(&self, transaction_id: u64) -> Option<&[AccountRole]> {
+        self.transaction_roles
+            .iter()
+            .find(|r| r.transaction_id == transaction_id)
+            .map(|r| r.roles.as_slice())
+    }
+
+    pub fn transaction_assertions(&self, transaction_id: u64) -> &[BalanceAssertion] {
+        self.transaction_assertions
+            .iter()
+            .find(|a| a.transaction_id == transaction_id)
+            .map(|a| a.assertions.as_slice())
+            .unwrap_or(&[])
+    }
+
+    pub fn set_transaction_expiry(&mut self, transaction_id: u64, expires_at: i64) {
+        match self
+            .transaction_expiries
+            .iter_mut()
+            .find(|e| e.transaction_id == transaction_id)
+        {
+            Some(expiry) => expiry.expires_at = expires_at,
+            None => self.transaction_expiries.push(TransactionExpiry {
+                transaction_id,
+                expires_at,
+            }),
+        }
+    }
+
+    // Starts the delay the first time the threshold is met; later approvals don't restart it
+    pub fn record_threshold_reached(&mut self, transaction_id: u64, now: i64) {
+        if self.threshold_reached(transaction_id).is_none() {
+            self.threshold_reached.push(ThresholdReached {
+                transaction_id,
+                reached_at: now,
+                expedited: false,
+            });
+        }
+    }
+
+    // Starts the delay of every pending transaction a new threshold or owner set lets through
+    pub fn record_thresholds_met(&mut self, now: i64) {
+        let threshold = match &self.multi_sig {
+            Some(multi_sig) => multi_sig.threshold,
+            None => return,
+        };
+        let met: Vec<u64> = self
+            .multisig_transactions()
+            .filter(|(_, transaction)| {
+                let approvals = transaction.signers.iter().filter(|&&signed| signed).count();
+                !transaction.did_execute && approvals as u64 >= threshold
+            })
+            .map(|(transaction_id, _)| transaction_id)
+            .collect();
+        for transaction_id in met {
+            self.record_threshold_reached(transaction_id, now);
+        }
+    }
+
+    // Ids only ever grow, so one is never handed out twice even after pruning
+    pub fn push_multisig_transaction(&mut self, transaction: MultiSigTransaction) -> u64 {
+        let transaction_id = self.next_transaction_id;
+        self.multi_sig_transactions.push(transaction);
+        self.multi_sig_transaction_ids.push(transaction_id);
+        self.next_transaction_id += 1;
+        transaction_id
+    }
+
+    // Drops executed, cancelled and expired transactions, returning their ids. Pending ones
+    // keep their ids, whatever position they end up at.
+    pub fn prune_multisig_transactions(&mut self, now: i64) -> Vec<u64> {
+        let pruned: Vec<u64> = self
+            .multisig_transactions()
+            .filter(|(transaction_id, transaction)| {
+                transaction.did_execute
+                    || self.is_transaction_cancelled(*transaction_id)
+                    || self.is_transaction_expired(*transaction_id, now)
+            })
+            .map(|(transaction_id, _)| transaction_id)
+            .collect();
+        self.remove_multisig_transactions(&pruned);
+        pruned
+    }
+
+    // Expired transactions can never run, so creating a transaction frees their slots first
+    pub fn prune_expired_transactions(&mut self, now: i64) -> Vec<u64> {
+        let pruned: Vec<u64> = self
+            .multisig_transactions()
+            .filter(|(transaction_id, transaction)| {
+                !transaction.did_execute && self.is_transaction_expired(*transaction_id, now)
+            })
+            .map(|(transaction_id, _)| transaction_id)
+            .collect();
+        self.remove_multisig_transactions(&pruned);
+        pruned
+    }
+
+    fn remove_multisig_transactions(&mut self, pruned: &[u64]) {
+        if pruned.is_empty() {
+            return;
+        }
+        let mut index = 0;
+        while index < self.multi_sig_transactions.len() {
+            if pruned.contains(&self.multi_sig_transaction_ids[index]) {
+                self.multi_sig_transactions.remove(index);
+                self.multi_sig_transaction_ids.remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        self.cancelled_transactions.retain(|id| !pruned.contains(id));
+        self.threshold_reached
+            .retain(|t| !pruned.contains(&t.transaction_id));
+        self.transaction_expiries
+            .retain(|e| !pruned.contains(&e.transaction_id));
+        self.transaction_roles
+            .retain(|r| !pruned.contains(&r.transaction_id));
+        self.transaction_assertions
+            .retain(|a| !pruned.contains(&a.transaction_id));
+    }
+
+    // Accounts written before ids were stored used the Vec index as the id
+    fn backfill_transaction_ids(&mut self) {
+        let stored = self.multi_sig_transaction_ids.len();
+        let count = self.multi_sig_transactions.len();
+        if stored < count {
+            self.multi_sig_transaction_ids
+                .extend((stored..count).map(|index| index as u64));
+        }
+        self.next_transaction_id = self.next_transaction_id.max(count as u64);
+    }
+
+    pub fn template(&self, template_id: u64) -> Option<&TransactionTemplate> {
+        self.templates.iter().find(|t| t.id == template_id)
+    }
+
+    pub fn governance_proposal_index(&self, proposal_id: u64) -> Option<usize> {
+        self.governance_proposals.iter().position(|p| p.id == proposal_id)
+    }
+
+    // Registered voting power under the current model at `now`, snapshotted into each new
+    // proposal. Locked voters count with their boost as it stands at `now`.
+    pub fn effective_registry_supply(&self, now: i64) -> u64 {
+        self.voter_registry.iter().fold(0u64, |total, voter| {
+            let power = self
+                .vote_lock(&voter.voter)
+                .map_or(voter.voting_power, |lock| self.locked_power(lock, now));
+            total.saturating_add(crate::governance::effective_power(self.voting_model, power))
+        })
+    }
+
+    // Appends to swap_history, dropping the oldest record once it is full
+    pub fn record_swap(&mut self, record: SwapRecord) {
+        if self.swap_history.len() >= Self::MAX_SWAP_RECORDS {
+            self.swap_history.remove(0);
+        }
+        self.swap_history.push(record);
+    }
+
+    pub fn vote_lock(&self, voter: &Pubkey) -> Option<&VoteLock> {
+        self.vote_locks.iter().find(|l| l.voter == *voter)
+    }
+
+    // Escrowed amount plus its boost at `now`
+    pub fn locked_power(&self, lock: &VoteLock, now: i64) -> u64 {
+        crate::governance::locked_power(
+            lock.amount,
+            lock.unlock_at.saturating_sub(now),
+            self.max_lock_duration,
+            self.max_boost_bps,
+        )
+    }
+
+    // Proposals created before snapshots were taken have none; their quorum can't be judged
+    pub fn has_proposal_snapshot(&self, proposal_id: u64) -> bool {
+        self.proposal_snapshots.iter().any(|s| s.proposal_id == proposal_id)
+    }
+
+    // 0 without a snapshot, see has_proposal_snapshot
+    pub fn proposal_effective_supply(&self, proposal_id: u64) -> u64 {
+        self.proposal_snapshots
+            .iter()
+            .find(|s| s.proposal_id == proposal_id)
+            .map_or(0, |s| s.effective_supply)
+    }
+
+    // Whether proposal `proposal_id` has to be ratified by the council before it executes
+    pub fn requires_council(&self, proposal_id: u64) -> bool {
+        self.council_review(proposal_id).is_some()
+    }
+
+    pub fn council_review(&self, proposal_id: u64) -> Option<&CouncilReview> {
+        self.council_reviews.iter().find(|r| r.proposal_id == proposal_id)
+    }
+
+    // Drops reviews of proposals that were executed, cancelled, removed or, by Tick's
+    // account, defeated or undetermined; none of them can come before the council again
+    pub fn prune_council_reviews(&mut self) {
+        let mut reviews = std::mem::take(&mut self.council_reviews);
+        reviews.retain(|review| {
+            let open = self
+                .governance_proposal_index(review.proposal_id)
+                .map(|i| &self.governance_proposals[i])
+                .map_or(false, |p| !p.executed && !p.cancelled);
+            let settled = self.proposal_settlement(review.proposal_id);
+            open && settled.map_or(true, |kind| kind == SettledKind::ProposalPassed)
+        });
+        self.council_reviews = reviews;
+    }
+
+    pub fn is_settled(&self, kind: SettledKind, id: u64) -> bool {
+        self.settled_items.iter().any(|item| item.kind == kind && item.id == id)
+    }
+
+    // What Tick recorded for a proposal once its voting closed
+    pub fn proposal_settlement(&self, proposal_id: u64) -> Option<SettledKind> {
+        self.settled_items
+            .iter()
+            .find(|item| item.id == proposal_id && item.kind.is_proposal())
+            .map(|item| item.kind)
+    }
+
+    // Whether a proposal passed, once Tick has closed its voting; None while it is open or
+    // when it settled undetermined
+    pub fn proposal_outcome(&self, proposal_id: u64) -> Option<bool> {
+        match self.proposal_settlement(proposal_id)? {
+            SettledKind::ProposalPassed => Some(true),
+            SettledKind::ProposalDefeated => Some(false),
+            _ => None,
+        }
+    }
+
+    // Items Tick walks: multisig transactions, governance proposals, then time locks
+    pub fn tick_items(&self) -> usize {
+        self.multi_sig_transactions.len() + self.governance_proposals.len() + self.time_locks.len()
+    }
+
+    // The terminal state Tick should record for item `position` of tick_items at `now`, or
+    // None while it is still live or already settled
+    pub fn due_settlement(&self, position: usize, now: i64) -> Option<(SettledKind, u64)> {
+        let transactions = self.multi_sig_transactions.len();
+        let proposals = self.governance_proposals.len();
+        let due = if position < transactions {
+            let id = self.multisig_transaction_id(position)?;
+            let live = !self.multi_sig_transactions[position].did_execute
+                && !self.is_transaction_cancelled(id);
+            (live && self.is_transaction_expired(id, now))
+                .then_some((SettledKind::TransactionExpired, id))
+        } else if position < transactions + proposals {
+            let proposal = &self.governance_proposals[position - transactions];
+            let config = self.governance_config.as_ref()?;
+            if proposal.executed || proposal.cancelled || now < proposal.end_time {
+                return None;
+            }
+            let quorum = crate::governance::quorum_reached(
+                proposal.for_votes,
+                proposal.against_votes,
+                proposal.abstain_votes,
+                self.proposal_effective_supply(proposal.id),
+                config.quorum_threshold,
+            );
+            let approved = crate::governance::approved(
+                proposal.for_votes,
+                proposal.against_votes,
+                config.execution_threshold,
+            );
+            if !self.has_proposal_snapshot(proposal.id) {
+                Some((SettledKind::ProposalUndetermined, proposal.id))
+            } else if quorum && approved {
+                Some((SettledKind::ProposalPassed, proposal.id))
+            } else {
+                Some((SettledKind::ProposalDefeated, proposal.id))
+            }
+        } else {
+            let lock = self.time_locks.get(position - transactions - proposals)?;
+            let vested = crate::vesting::vested_amount(lock, now).unwrap_or(0);
+            (vested >= lock.amount && lock.released_amount < lock.amount)
+                .then_some((SettledKind::TimeLockVested, lock.id))
+        };
+        due.filter(|(kind, id)| !self.is_settled(*kind, *id))
+    }
+
+    // Drops records of items that have been pruned, claimed, executed, cancelled or otherwise
+    // removed
+    pub fn prune_settled_items(&mut self) {
+        let mut settled = std::mem::take(&mut self.settled_items);
+        settled.retain(|item| match item.kind {
+            SettledKind::TransactionExpired => self.multisig_transaction_index(item.id).is_some(),
+            SettledKind::ProposalPassed
+            | SettledKind::ProposalDefeated
+            | SettledKind::ProposalUndetermined => self
+                .governance_proposal_index(item.id)
+                .map(|i| &self.governance_proposals[i])
+                .map_or(false, |p| !p.executed && !p.cancelled),
+            SettledKind::TimeLockVested => self.time_locks.iter().any(|lock| {
+                lock.id == item.id && lock.released_amount < lock.amount
+            }),
+        });
+        self.settled_items = settled;
+    }
+
+    // A proposal still open for votes at `now`
+    pub fn has_active_proposal(&self, now: i64) -> bool {
+        self.governance_proposals
+            .iter()
+            .any(|p| !p.executed && !p.cancelled && now < p.end_time)
+    }
+
+    pub fn find_attestation(&self, message_hash: &[u8; 32]) -> Option<&Attestation> {
+        self.attestations
+            .iter()
+            .rev()
+            .find(|a| a.message_hash == *message_hash)
+    }
+
+    pub fn log_action(
+        &mut self,
+        kind: ActionKind,
+        actor: Pubkey,
+        mint: Pubkey,
+        amount: u64,
+        now: i64,
+    ) {
+        let head = self.recent_actions_head as usize % Self::RECENT_ACTIONS_LEN;
+        self.recent_actions[head] = ActionRecord {
+            kind,
+            actor,
+            mint,
+            amount,
+            timestamp: now,
+        };
+        self.recent_actions_head = ((head + 1) % Self::RECENT_ACTIONS_LEN) as u8;
+    }
+
+    // Logged actions, oldest first
+    pub fn recent_actions(&self) -> Vec<ActionRecord> {
+        let head = self.recent_actions_head as usize % Self::RECENT_ACTIONS_LEN;
+        self.recent_actions[head..]
+            .iter()
+            .chain(&self.recent_actions[..head])
+            .filter(|record| record.kind != ActionKind::None)
+            .copied()
+            .collect()
+    }
+
+    // Exact Borsh payload, without the headroom a live account carries
+    pub fn to_account_data(&self) -> Result<Vec<u8>, ProgramError> {
+        Ok(self.try_to_vec()?)
+    }
+
+    pub fn from_account_data(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::load(data)
+    }
+
+    // Vault accounts are allocated with headroom, so bytes past the Borsh payload are
+    // ignored instead of rejected (same behaviour as try_from_slice_unchecked)
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = data;
+        match Self::deserialize(&mut buf) {
+            Ok(mut vault) if vault.state_version == Self::STATE_VERSION => {
+                vault.backfill_transaction_ids();
+                Ok(vault)
+            }
+            Ok(_) => Err(err(VaultError::StateMigrationRequired)),
+            Err(_)
+                if crate::migration::LegacyVaultHeadV2::load_vault(data).is_ok()
+                    || crate::migration::LegacyVaultV0::load(data).is_ok() =>
+            {
+                Err(err(VaultError::StateMigrationRequired))
+            }
+            Err(_) => Err(err(VaultError::InvalidAccountData)),
+        }
+    }
+
+    // Decodes as if the account had room for recent_actions and the tail fields after it, so
+    // a version 1 vault whose headroom is too short still reads, with those fields empty.
+    // No version check.
+    pub fn load_zero_extended(data: &[u8]) -> Result<Self, ProgramError> {
+        let padding = Self::RECENT_ACTIONS_SPACE + Self::DYNAMIC_HEADROOM;
+        let mut padded = Vec::with_capacity(data.len() + padding);
+        padded.extend_from_slice(data);
+        padded.resize(data.len() + padding, 0);
+        let mut buf = &padded[..];
+        Self::deserialize(&mut buf).map_err(|_| err(VaultError::InvalidAccountData))
+    }
+
+    pub fn required_space(&self) -> Result<usize, ProgramError> {
+        Ok(self.try_to_vec()?.len())
+    }
+
+    // Refuses to write rather than leaving a truncated blob behind when the account is too
+    // small. Zeroes everything past the encoding, so bytes left over from a larger state can't
+    // decode as tail fields a later layout appends.
+    pub fn save(&self, data: &mut [u8]) -> ProgramResult {
+        let bytes = self.try_to_vec()?;
+        if bytes.len() > data.len() {
+            msg!(
+                "Vault account too small: needed {} bytes, have {}",
+                bytes.len(),
+                data.len()
+            );
+            return Err(err(VaultError::AccountTooSmall));
+        }
+        data[..bytes.len()].copy_from_slice(&bytes);
+        data[bytes.len()..].fill(0);
+        Ok(())
+    }
+}
+
+// Protocol-wide settings in the singleton PDA [b"global"]. Vault instructions that are
+// handed it as an optional trailing account enforce its registry and fee cap; without it they
+// fall back to the built-in checks and ParamKey::MAX_FEE_BPS.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
+pub struct GlobalConfig {
+    // A vault whose multisig signs UpdateGlobalConfig
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub admin: Pubkey,
+    pub max_fee_bps: u64, // ceiling for DepositFeeBps and WithdrawalFeeBps
+    // Strategy and router programs vaults may register
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
+    pub allowed_programs: Vec<Pubkey>,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub protocol_fee_recipient: Pubkey,
+    pub bump: u8,
+    // Share of every routed vault fee skimmed for the protocol fee recipient
+    pub protocol_fee_bps: u16,
+    // Cumulative protocol fees per mint; `balance` is the running total
+    pub protocol_fees: Vec<TokenBalance>,
+    // Upgrade authority the deployed program must still have before high-value withdrawals
+    // go through; None skips the check. Rotated with AcknowledgeUpgradeAuthority.
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::option_pubkey"))]
+    pub expected_upgrade_authority: Option<Pubkey>,
+    // Withdrawals of more than this need the check; emergency withdrawals always do
+    pub upgrade_check_threshold: u64,
+}
+
+impl GlobalConfig {
+    pub const SEED: &'static [u8] = b"global";
+    pub const MAX_ALLOWED_PROGRAMS: usize = 32;
+    pub const MAX_FEE_CAP_BPS: u64 = 10_000;
+    // Mints past this still pay the protocol fee, they just aren't tallied
+    pub const MAX_PROTOCOL_FEE_MINTS: usize = 64;
+    // The registry and the fee totals at their caps, with headroom for fields appended later
+    pub const SPACE: usize = 32 + 8 + 4 + 32 * Self::MAX_ALLOWED_PROGRAMS + 32 + 1 + 2
+        + 4 + 48 * Self::MAX_PROTOCOL_FEE_MINTS
+        + 33 + 8
+        + 512;
+
+    pub fn find_address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[Self::SEED], program_id)
+    }
+
+    pub fn is_allowed_program(&self, program_id: &Pubkey) -> bool {
+        self.allowed_programs.contains(program_id)
+    }
+
+    // Settings checks shared by InitializeGlobalConfig and UpdateGlobalConfig. Programs the
+    // vault PDA signs CPIs into must never include this program itself.
+    pub fn validate(&self, program_id: &Pubkey) -> Result<(), VaultError> {
+        if self.max_fee_bps > Self::MAX_FEE_CAP_BPS || self.protocol_fee_bps > 10_000 {
+            return Err(VaultError::InvalidAmount);
+        }
+        if self.allowed_programs.len() > Self::MAX_ALLOWED_PROGRAMS
+            || self
+                .allowed_programs
+                .iter()
+                .any(|p| p == program_id || *p == Pubkey::default())
+        {
+            return Err(VaultError::InvalidAccountData);
+        }
+        Ok(())
+    }
+
+    // Splits a charged fee into (vault share, protocol share). The protocol share rounds
+    // down, so a zero fee never costs the protocol anything and dust stays with the vault.
+    pub fn split_fee(&self, fee: u64) -> (u64, u64) {
+        let protocol_share = (fee as u128 * self.protocol_fee_bps as u128 / 10_000) as u64;
+        (fee - protocol_share, protocol_share)
+    }
+
+    pub fn record_protocol_fee(&mut self, mint: &Pubkey, amount: u64, now: i64) {
+        if let Some(total) = self.protocol_fees.iter_mut().find(|t| t.mint == *mint) {
+            total.balance = total.balance.saturating_add(amount);
+            total.last_updated = now;
+        } else if self.protocol_fees.len() < Self::MAX_PROTOCOL_FEE_MINTS {
+            self.protocol_fees.push(TokenBalance {
+                mint: *mint,
+                balance: amount,
+                last_updated: now,
+            });
+        } else {
+            msg!("Protocol fee totals are full; {} of {} not tallied", amount, mint);
+        }
+    }
+
+    // Whether a withdrawal of `amount` (None for an emergency withdrawal) has to prove the
+    // upgrade authority
+    pub fn requires_upgrade_check(&self, amount: Option<u64>) -> bool {
+        self.expected_upgrade_authority.is_some()
+            && amount.map_or(true, |amount| amount > self.upgrade_check_threshold)
+    }
+
+    pub fn protocol_fees_collected(&self, mint: &Pubkey) -> u64 {
+        self.protocol_fees
+            .iter()
+            .find(|t| t.mint == *mint)
+            .map_or(0, |t| t.balance)
+    }
+
+    // The account is allocated with headroom, so this reads a prefix and ignores the zeros
+    pub fn load(data: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = data;
+        Self::deserialize(&mut buf).map_err(|_| err(VaultError::InvalidAccountData))
+    }
+
+    pub fn save(&self, data: &mut [u8]) -> ProgramResult {
+        let bytes = self.try_to_vec()?;
+        if bytes.len() > data.len() {
+            return Err(err(VaultError::AccountTooSmall));
+        }
+        data[..bytes.len()].copy_from_slice(&bytes);
+        // A shorter encoding, e.g. after allowed programs are removed, must not leave the old
+        // tail to be read as fields appended later
+        data[bytes.len()..].fill(0);
+        Ok(())
+    }
+}
+
+// Upgrade authority recorded in a BPF upgradeable loader ProgramData account: a u32 state tag
+// (3 for ProgramData), the u64 deployment slot, then an Option<Pubkey> with a one-byte tag.
+// None once the program has been made immutable.
+pub fn program_data_upgrade_authority(data: &[u8]) -> Result<Option<Pubkey>, VaultError> {
+    const PROGRAM_DATA_TAG: u32 = 3;
+    let tag = data
+        .get(..4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(VaultError::InvalidAccountData)?;
+    if tag != PROGRAM_DATA_TAG {
+        return Err(VaultError::InvalidAccountData);
+    }
+    match data.get(12) {
+        Some(0) => Ok(None),
+        Some(1) => data
+            .get(13..45)
+            .map(|key| Some(Pubkey::new_from_array(key.try_into().unwrap())))
+            .ok_or(VaultError::InvalidAccountData),
+        _ => Err(VaultError::InvalidAccountData),
+    }
+}
+
+// Per-mint analytics view, combining the supported token entry with its tracked balance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[cfg_attr(feature = "state-serde", derive(Deserialize))]
+pub struct TokenStats {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
+    pub mint: Pubkey,
+    pub decimals: u8,
+    pub is_active: bool,
+    pub balance: u64,
+    pub withdrawable: u64, // free_balance: balance less what time locks still owe
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub deposit_count: u32,
+    pub withdrawal_count: u32,
+    pub last_updated: i64,
+}
+
+impl Vault {
+    pub fn token_stats(&self) -> Vec<TokenStats> {
+        self.supported_tokens
+            .iter()
+            .map(|token| {
+                let balance = self.token_balances.iter().find(|b| b.mint == token.mint);
+                TokenStats {
+                    mint: token.mint,
+                    decimals: token.decimals,
+                    is_active: token.is_active,
+                    balance: balance.map(|b| b.balance).unwrap_or(0),
+                    withdrawable: self.free_balance(&token.mint),
+                    total_deposited: token.total_deposited,
+                    total_withdrawn: token.total_withdrawn,
+                    deposit_count: token.deposit_count,
+                    withdrawal_count: token.withdrawal_count,
+                    last_updated: balance.map(|b| b.last_updated).unwrap_or(0),
+                }
+            })
+            .collect()
+    }
 }
 
 // Vault state structure (simplified version)
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct VaultState {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub authority: Pubkey,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub emergency_admin: Pubkey,
     pub is_paused: bool,
     pub total_deposits: u64,
     pub total_withdrawals: u64,
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub fee_recipient: Pubkey,
     pub deposit_fee_bps: u16,
     pub withdrawal_fee_bps: u16,
@@ -206,27 +2581,19 @@ pub struct VaultState {
 
 // Multi-sig state structure
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct MultiSigState {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::vec_pubkey"))]
     pub authorities: Vec<Pubkey>,
     pub threshold: u8,
     pub bump: u8,
 }
 
-// Time lock state structure
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct TimeLockState {
-    pub beneficiary: Pubkey,
-    pub amount: u64,
-    pub start_time: i64,
-    pub duration: i64,
-    pub cliff_duration: Option<i64>,
-    pub is_linear: bool,
-    pub claimed_amount: u64,
-}
-
 // Governance state structure
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
+#[cfg_attr(feature = "state-serde", derive(Serialize, Deserialize))]
 pub struct GovernanceState {
+    #[cfg_attr(feature = "state-serde", serde(with = "crate::serde_helpers::pubkey"))]
     pub voting_token_mint: Pubkey,
     pub quorum_threshold: u16,
     pub proposal_threshold: u64,
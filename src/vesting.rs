@@ -0,0 +1,97 @@
+use solana_program::pubkey::Pubkey;
+
+use crate::state::{TimeLock, VestingSchedule};
+use crate::VaultError;
+
+// Pure vesting math shared by the claim processor and off-chain tooling.
+// Linear locks vest pro rata between start_time and end_time once the cliff has passed;
+// CliffAll locks release everything at the cliff (which defaults to end_time).
+
+/// Checks that the schedule timestamps are ordered start <= cliff <= end with a non-zero duration.
+pub fn validate_schedule(lock: &TimeLock) -> Result<(), VaultError> {
+    if lock.end_time <= lock.start_time {
+        return Err(VaultError::InvalidTimeLock);
+    }
+    if lock.cliff_time < lock.start_time || lock.cliff_time > lock.end_time {
+        return Err(VaultError::InvalidTimeLock);
+    }
+    Ok(())
+}
+
+/// Total amount vested at `now`, including whatever was already released.
+pub fn vested_amount(lock: &TimeLock, now: i64) -> Result<u64, VaultError> {
+    validate_schedule(lock)?;
+
+    if now < lock.cliff_time {
+        return Ok(0);
+    }
+    if now >= lock.end_time || lock.schedule == VestingSchedule::CliffAll {
+        return Ok(lock.amount);
+    }
+
+    // u128 keeps amount * elapsed from overflowing for large grants
+    let elapsed = (now - lock.start_time) as u128;
+    let total = (lock.end_time - lock.start_time) as u128;
+    let vested = (lock.amount as u128)
+        .checked_mul(elapsed)
+        .ok_or(VaultError::ArithmeticOverflow)?
+        / total;
+
+    u64::try_from(vested).map_err(|_| VaultError::ArithmeticOverflow)
+}
+
+/// Amount the beneficiary can claim at `now`.
+pub fn releasable(lock: &TimeLock, now: i64) -> Result<u64, VaultError> {
+    let vested = vested_amount(lock, now)?;
+    Ok(vested.saturating_sub(lock.released_amount))
+}
+
+/// Carves `amount_to_new` of the principal out of `lock` into a lock for `new_beneficiary` on
+/// the same schedule, taking its pro rata share of `released_amount` (rounded down) along.
+/// `lock` keeps the rest, so the two amounts and the two released amounts each add up to
+/// what `lock` had before. Fully claimed locks can't be split, and both halves must keep a
+/// non-zero principal.
+pub fn split(
+    lock: &mut TimeLock,
+    amount_to_new: u64,
+    new_id: u64,
+    new_beneficiary: Pubkey,
+) -> Result<TimeLock, VaultError> {
+    validate_schedule(lock)?;
+    if lock.released_amount >= lock.amount {
+        return Err(VaultError::InvalidTimeLock);
+    }
+    if amount_to_new == 0 || amount_to_new >= lock.amount {
+        return Err(VaultError::InvalidAmount);
+    }
+
+    let released_to_new = (lock.released_amount as u128)
+        .checked_mul(amount_to_new as u128)
+        .ok_or(VaultError::ArithmeticOverflow)?
+        / lock.amount as u128;
+    let released_to_new =
+        u64::try_from(released_to_new).map_err(|_| VaultError::ArithmeticOverflow)?;
+
+    let original_amount = lock.amount;
+    let original_released = lock.released_amount;
+    lock.amount = original_amount
+        .checked_sub(amount_to_new)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+    lock.released_amount = original_released
+        .checked_sub(released_to_new)
+        .ok_or(VaultError::ArithmeticOverflow)?;
+
+    let new_lock = TimeLock {
+        id: new_id,
+        beneficiary: new_beneficiary,
+        amount: amount_to_new,
+        released_amount: released_to_new,
+        ..lock.clone()
+    };
+    if lock.amount.checked_add(new_lock.amount) != Some(original_amount)
+        || lock.released_amount.checked_add(new_lock.released_amount) != Some(original_released)
+    {
+        return Err(VaultError::ArithmeticOverflow);
+    }
+    Ok(new_lock)
+}
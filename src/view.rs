@@ -0,0 +1,108 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+use crate::state::Vault;
+use crate::vesting;
+use crate::VaultError;
+
+// Derived figures served by the View instruction. It never writes, so indexers can run it
+// through simulateTransaction and read the Borsh-encoded ViewResult from the return data
+// (or the `VIEW: <json>` log line) without decoding the whole vault.
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub enum ViewQuery {
+    Claimable { time_lock_id: u64 }, // what the beneficiary could claim now, before fees
+    FreeBalance { mint: Pubkey },    // tracked balance not reserved for time locks or tickets
+    TransactionStatus { transaction_id: u64 },
+    QuorumProgress { transaction_id: u64 }, // multisig approvals against the threshold
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub enum TransactionStatus {
+    NeedsApprovals,
+    Delayed { executable_at: i64 },
+    Executable,
+    Executed,
+    Cancelled,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct QuorumProgress {
+    pub approvals: u64,
+    pub threshold: u64,
+    pub owners: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub enum ViewResult {
+    Amount(u64),
+    TransactionStatus(TransactionStatus),
+    QuorumProgress(QuorumProgress),
+}
+
+impl ViewResult {
+    // Tag plus the largest variant, QuorumProgress
+    pub const MAX_LEN: usize = 1 + 24;
+
+    // The runtime trims trailing zero bytes from return data (dropping it entirely when it is
+    // all zeros), so they are restored before decoding
+    pub fn decode(return_data: &[u8]) -> Result<Self, ProgramError> {
+        let mut padded = return_data.to_vec();
+        if padded.len() < Self::MAX_LEN {
+            padded.resize(Self::MAX_LEN, 0);
+        }
+        let mut buf = &padded[..];
+        Self::deserialize(&mut buf).map_err(|_| VaultError::UnexpectedReturnData.into())
+    }
+}
+
+pub fn evaluate(vault: &Vault, query: &ViewQuery, now: i64) -> Result<ViewResult, ProgramError> {
+    match query {
+        ViewQuery::Claimable { time_lock_id } => {
+            let index = vault
+                .time_lock_index(*time_lock_id)
+                .ok_or(VaultError::InvalidInstruction)?;
+            Ok(ViewResult::Amount(vesting::releasable(&vault.time_locks[index], now)?))
+        }
+        ViewQuery::FreeBalance { mint } => Ok(ViewResult::Amount(vault.free_balance(mint))),
+        ViewQuery::TransactionStatus { transaction_id } => {
+            let threshold = multisig_threshold(vault)?;
+            let transaction = vault
+                .multisig_transaction(*transaction_id)
+                .ok_or(VaultError::TransactionNotFound)?;
+            let status = if transaction.did_execute {
+                TransactionStatus::Executed
+            } else if vault.is_transaction_cancelled(*transaction_id) {
+                TransactionStatus::Cancelled
+            } else {
+                match vault.executable_at(*transaction_id, threshold) {
+                    None => TransactionStatus::NeedsApprovals,
+                    Some(executable_at) if now < executable_at => {
+                        TransactionStatus::Delayed { executable_at }
+                    }
+                    Some(_) => TransactionStatus::Executable,
+                }
+            };
+            Ok(ViewResult::TransactionStatus(status))
+        }
+        ViewQuery::QuorumProgress { transaction_id } => {
+            let threshold = multisig_threshold(vault)?;
+            let transaction = vault
+                .multisig_transaction(*transaction_id)
+                .ok_or(VaultError::TransactionNotFound)?;
+            Ok(ViewResult::QuorumProgress(QuorumProgress {
+                approvals: transaction.signers.iter().filter(|&&signed| signed).count() as u64,
+                threshold,
+                owners: transaction.signers.len() as u64,
+            }))
+        }
+    }
+}
+
+fn multisig_threshold(vault: &Vault) -> Result<u64, VaultError> {
+    vault
+        .multi_sig
+        .as_ref()
+        .map(|multi_sig| multi_sig.threshold)
+        .ok_or(VaultError::MultisigNotInitialized)
+}
@@ -0,0 +1,149 @@
+mod common;
+
+#[cfg(test)]
+mod account_role_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{AccountRole, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 21;
+
+    fn create_v2_ix(
+        vault: &Pubkey,
+        proposer: &Pubkey,
+        program_id: Pubkey,
+        accounts: Vec<TransactionAccount>,
+        roles: Vec<AccountRole>,
+        data: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*proposer, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateMultiSigTransactionV2 {
+                program_id,
+                accounts,
+                roles,
+                data,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // 1-of-1 multisig of the payer whose signer PDA holds some lamports to pay out
+    async fn multisig_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                system_instruction::transfer(&payer, &signer, 10_000_000),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, signer)
+    }
+
+    fn account(pubkey: Pubkey, is_signer: bool, is_writable: bool) -> TransactionAccount {
+        TransactionAccount { pubkey, is_signer, is_writable }
+    }
+
+    #[tokio::test]
+    async fn test_bogus_roles_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, signer) = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let impostor = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        let claims = [
+            // Only the signer PDA signs for the multisig
+            (vec![account(impostor, true, true)], vec![AccountRole::MultisigSigner]),
+            (vec![account(vault, true, true)], vec![AccountRole::MultisigSigner]),
+            // Only the vault is the vault
+            (vec![account(impostor, false, true)], vec![AccountRole::VaultPda]),
+            // The PDAs' signer flags are fixed by their roles
+            (vec![account(vault, true, true)], vec![AccountRole::VaultPda]),
+            (vec![account(signer, false, true)], vec![AccountRole::MultisigSigner]),
+            // The signer PDA can't pass as an ordinary account
+            (vec![account(signer, true, true)], vec![AccountRole::Writable]),
+            // Roles have to agree with the flags, and there is one per account
+            (vec![account(recipient, false, false)], vec![AccountRole::Writable]),
+            (vec![account(recipient, false, true)], vec![AccountRole::ReadOnly]),
+            (vec![account(recipient, false, true)], vec![]),
+        ];
+        for (accounts, roles) in claims {
+            let create =
+                create_v2_ix(&vault, &payer, system_program::id(), accounts, roles, vec![]);
+            let err = send(&mut context, &[create], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+        }
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multi_sig_transactions.is_empty());
+        assert!(state.transaction_roles.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_roles_stored_and_executed() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, signer) = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let recipient = Pubkey::new_unique();
+
+        let transfer = system_instruction::transfer(&signer, &recipient, 1_000_000);
+        let create = create_v2_ix(
+            &vault,
+            &payer,
+            system_program::id(),
+            vec![account(signer, true, true), account(recipient, false, true)],
+            vec![AccountRole::MultisigSigner, AccountRole::Writable],
+            transfer.data.clone(),
+        );
+        // The legacy instruction gets its roles derived
+        let legacy = create_multisig_tx_ix(
+            &vault,
+            &payer,
+            system_program::id(),
+            vec![
+                account(vault, false, true),
+                account(signer, true, false),
+                account(sysvar::clock::id(), false, false),
+            ],
+            vec![],
+        );
+        send(&mut context, &[create, legacy], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let derived = [AccountRole::VaultPda, AccountRole::MultisigSigner, AccountRole::ReadOnly];
+        assert_eq!(state.transaction_roles(1), Some(&derived[..]));
+
+        let remaining = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let execute = execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining);
+        send(&mut context, &[execute], &[]).await.unwrap();
+        let received = context.banks_client.get_balance(recipient).await.unwrap();
+        assert_eq!(received, 1_000_000);
+    }
+}
@@ -0,0 +1,253 @@
+mod common;
+
+#[cfg(test)]
+mod account_size_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        entrypoint::MAX_PERMITTED_DATA_INCREASE, instruction::InstructionError,
+        program_error::ProgramError, pubkey::Pubkey,
+    };
+    use solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        state::{
+            BalanceAssertion, GlobalConfig, GovernanceInstruction, GovernanceProposal,
+            MultiSigTransaction, SupportedToken, ThresholdReached, TimeLock, TokenBalance,
+            TransactionAccount, TransactionAssertions, TransactionExpiry, TransactionRoles, Vault,
+        },
+        VaultError, PROGRAM_ID,
+    };
+
+    fn vault_for(authority: &Keypair) -> Vault {
+        Vault {
+            authority: authority.pubkey(),
+            emergency_admin: authority.pubkey(),
+            state_version: Vault::STATE_VERSION,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_load_ignores_trailing_bytes() {
+        let vault = vault_for(&Keypair::new());
+        let mut data = vault.try_to_vec().unwrap();
+        assert_eq!(vault.required_space().unwrap(), data.len());
+
+        // Zero padding and stale bytes from a previously larger state both load
+        data.extend_from_slice(&[0u8; 64]);
+        data.extend_from_slice(&[0xAB; 16]);
+        let loaded = Vault::load(&data).unwrap();
+        assert_eq!(loaded.authority, vault.authority);
+    }
+
+    #[test]
+    fn test_save_refuses_short_buffer() {
+        let vault = vault_for(&Keypair::new());
+        let needed = vault.required_space().unwrap();
+
+        let mut short = vec![0u8; needed - 1];
+        assert_eq!(
+            vault.save(&mut short),
+            Err(ProgramError::Custom(VaultError::AccountTooSmall as u32))
+        );
+        assert!(short.iter().all(|b| *b == 0));
+
+        let mut exact = vec![0u8; needed];
+        vault.save(&mut exact).unwrap();
+        assert_eq!(exact, vault.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn test_save_zeroes_stale_tail() {
+        let mut vault = vault_for(&Keypair::new());
+        vault.time_locks = vec![TimeLock::default(); 3];
+        let mut data = vec![0u8; vault.required_space().unwrap() + 32];
+        vault.save(&mut data).unwrap();
+
+        // Shrinking leaves nothing of the time locks behind the new encoding
+        vault.time_locks.clear();
+        vault.save(&mut data).unwrap();
+        let len = vault.required_space().unwrap();
+        assert_eq!(data[..len], vault.try_to_vec().unwrap()[..]);
+        assert!(data[len..].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_global_config_save_zeroes_stale_tail() {
+        let mut config = GlobalConfig {
+            allowed_programs: vec![Pubkey::new_unique(); 4],
+            ..GlobalConfig::default()
+        };
+        let mut data = vec![0u8; GlobalConfig::SPACE];
+        config.save(&mut data).unwrap();
+
+        config.allowed_programs.truncate(1);
+        config.save(&mut data).unwrap();
+        let len = config.try_to_vec().unwrap().len();
+        assert!(data[len..].iter().all(|b| *b == 0));
+        assert_eq!(GlobalConfig::load(&data).unwrap(), config);
+    }
+
+    // Every entry space_for counts, at its largest
+    fn populated_vault(
+        tokens: usize,
+        time_locks: usize,
+        proposals: usize,
+        transactions: usize,
+    ) -> Vault {
+        let mut vault = Vault::default();
+        for _ in 0..tokens {
+            let mint = Pubkey::new_unique();
+            vault.supported_tokens.push(SupportedToken { mint, ..Default::default() });
+            vault.token_balances.push(TokenBalance { mint, ..Default::default() });
+            vault.accrued_fees.push(TokenBalance { mint, ..Default::default() });
+        }
+        vault.time_locks = vec![TimeLock::default(); time_locks];
+        let instruction_len =
+            GovernanceInstruction::MAX_TOTAL_LEN / GovernanceInstruction::MAX_INSTRUCTIONS;
+        let proposal = GovernanceProposal {
+            title: "t".repeat(GovernanceProposal::MAX_TITLE_LEN),
+            description: "d".repeat(GovernanceProposal::MAX_DESCRIPTION_LEN),
+            instructions: vec![vec![0; instruction_len]; GovernanceInstruction::MAX_INSTRUCTIONS],
+            eta: Some(0),
+            ..Default::default()
+        };
+        vault.governance_proposals = vec![proposal; proposals];
+        let account = TransactionAccount {
+            pubkey: Pubkey::new_unique(),
+            is_signer: false,
+            is_writable: true,
+        };
+        let transaction = MultiSigTransaction {
+            accounts: vec![account; MultiSigTransaction::MAX_ACCOUNTS],
+            data: vec![0; MultiSigTransaction::MAX_DATA_LEN],
+            signers: vec![false; Vault::SPACE_OWNERS],
+            ..Default::default()
+        };
+        vault.multi_sig_transactions = vec![transaction; transactions];
+        vault.multi_sig_transaction_ids = (0..transactions as u64).collect();
+        vault.transaction_expiries = vec![TransactionExpiry::default(); transactions];
+        vault.threshold_reached = vec![ThresholdReached::default(); transactions];
+        let roles = TransactionRoles {
+            roles: vec![Default::default(); MultiSigTransaction::MAX_ACCOUNTS],
+            ..Default::default()
+        };
+        vault.transaction_roles = vec![roles; transactions];
+        let assertions = TransactionAssertions {
+            assertions: vec![Default::default(); BalanceAssertion::MAX_PER_TRANSACTION],
+            ..Default::default()
+        };
+        vault.transaction_assertions = vec![assertions; transactions];
+        vault
+    }
+
+    #[test]
+    fn test_space_for_matches_borsh_length() {
+        for (tokens, time_locks, proposals, transactions) in
+            [(0, 0, 0, 0), (1, 0, 0, 0), (0, 3, 0, 0), (0, 0, 2, 0), (0, 0, 0, 2), (4, 4, 1, 1)]
+        {
+            let vault = populated_vault(tokens, time_locks, proposals, transactions);
+            assert_eq!(
+                vault.try_to_vec().unwrap().len(),
+                Vault::space_for(tokens, time_locks, proposals, transactions).unwrap(),
+                "{} tokens, {} time locks, {} proposals, {} transactions",
+                tokens,
+                time_locks,
+                proposals,
+                transactions
+            );
+        }
+        assert!(Vault::initial_space().unwrap() <= MAX_PERMITTED_DATA_INCREASE);
+    }
+
+    #[tokio::test]
+    async fn test_new_vault_allocated_initial_space() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let account = context
+            .banks_client
+            .get_account(vault)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.data.len(), Vault::initial_space().unwrap());
+    }
+
+    async fn run_add_token(extra_space: usize) -> (Result<(), TransactionError>, Vec<u8>, Vec<u8>) {
+        let authority = Keypair::new();
+        let (vault_key, _) = vault_pda(&authority.pubkey(), 0);
+        let mut data = vault_for(&authority).try_to_vec().unwrap();
+        data.resize(data.len() + extra_space, 0);
+        let original = data.clone();
+
+        let mut test = program_test();
+        test.add_account(
+            authority.pubkey(),
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+        test.add_account(
+            vault_key,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let mut context = test.start_with_context().await;
+        let mint = create_mint(&mut context, 6).await.pubkey();
+
+        let result = send(
+            &mut context,
+            &[add_supported_token_ix(
+                &vault_key,
+                &mint,
+                &authority.pubkey(),
+                &authority.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await
+        .map_err(|e| e.unwrap());
+
+        let after = context
+            .banks_client
+            .get_account(vault_key)
+            .await
+            .unwrap()
+            .unwrap()
+            .data;
+        (result, original, after)
+    }
+
+    #[tokio::test]
+    async fn test_under_allocated_vault_rejected_without_corruption() {
+        let (result, original, after) = run_add_token(0).await;
+        assert_eq!(
+            result.unwrap_err(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::AccountTooSmall as u32)
+            )
+        );
+        assert_eq!(after, original);
+    }
+
+    #[tokio::test]
+    async fn test_over_allocated_vault_accepted() {
+        let (result, original, after) = run_add_token(1024).await;
+        result.unwrap();
+        assert_eq!(after.len(), original.len());
+
+        let vault = Vault::load(&after).unwrap();
+        assert_eq!(vault.supported_tokens.len(), 1);
+    }
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod approval_digest_tests {
+    use super::common::*;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use vault_program::{
+        client::{ApprovalDigest, ApprovalDigestError},
+        state::{TransactionAccount, Vault},
+    };
+
+    const NONCE: u8 = 9;
+    const EXECUTE_SLOT: u64 = 100;
+
+    async fn vault_data(context: &mut ProgramTestContext, vault: &Pubkey) -> Vec<u8> {
+        context.banks_client.get_account(*vault).await.unwrap().unwrap().data
+    }
+
+    // Sends `instruction` at EXECUTE_SLOT and returns its signature
+    async fn execute_at_slot(context: &mut ProgramTestContext, instruction: Instruction) -> String {
+        context.warp_to_slot(EXECUTE_SLOT).unwrap();
+        let payer = context.payer.pubkey();
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let signature = transaction.signatures[0].to_string();
+        context.banks_client.process_transaction(transaction).await.unwrap();
+        signature
+    }
+
+    // History of a 2-of-2 transfer out of the signer PDA: the vault before and after it ran,
+    // and the execute signature
+    async fn executed_transfer(
+        context: &mut ProgramTestContext,
+        cosigner: &Keypair,
+    ) -> (Pubkey, Vec<u8>, Vec<u8>, String) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let recipient = Pubkey::new_unique();
+        let owners = vec![payer, cosigner.pubkey()];
+        let transfer = system_instruction::transfer(&signer, &recipient, 1_000_000);
+        let accounts: Vec<_> = transfer
+            .accounts
+            .iter()
+            .map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, owners, 2, NONCE),
+                system_instruction::transfer(&payer, &signer, 10_000_000),
+                create_multisig_tx_ix(&vault, &payer, transfer.program_id, accounts, transfer.data),
+                approve_multisig_tx_ix(&vault, &cosigner.pubkey(), 0),
+            ],
+            &[cosigner],
+        )
+        .await
+        .unwrap();
+        let before = vault_data(context, &vault).await;
+
+        let remaining = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let execute = execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining);
+        let signature = execute_at_slot(context, execute).await;
+        let after = vault_data(context, &vault).await;
+        (vault, before, after, signature)
+    }
+
+    #[tokio::test]
+    async fn test_digest_is_consistent_from_json() {
+        let mut context = program_test().start_with_context().await;
+        let cosigner = Keypair::new();
+        let (vault, before, _, signature) = executed_transfer(&mut context, &cosigner).await;
+
+        let digest = ApprovalDigest::build(&vault, 0, &signature, EXECUTE_SLOT, &before).unwrap();
+        assert_eq!(digest.approvers, vec![context.payer.pubkey(), cosigner.pubkey()]);
+        assert_eq!(digest.threshold, 2);
+        let loaded = ApprovalDigest::from_json(&digest.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.check_consistent(), Ok(()));
+
+        // Claiming fewer approvers than the data shows, or swapping an owner in the data
+        let mut edited = loaded.clone();
+        edited.approvers.pop();
+        assert_eq!(edited.check_consistent(), Err(ApprovalDigestError::DigestMismatch));
+        let mut state = Vault::from_account_data(&before).unwrap();
+        state.multi_sig.as_mut().unwrap().owners[1] = Pubkey::new_unique();
+        let forged_data = state.to_account_data().unwrap();
+        let mut forged = loaded;
+        forged.account_data_base64 = base64::encode(&forged_data);
+        assert_eq!(forged.check_consistent(), Err(ApprovalDigestError::DigestMismatch));
+
+        // Whoever rebuilds it from the edited data gets a consistent digest: nothing ties the
+        // data to the chain
+        let rebuilt =
+            ApprovalDigest::build(&vault, 0, &signature, EXECUTE_SLOT, &forged_data).unwrap();
+        assert_eq!(rebuilt.check_consistent(), Ok(()));
+        assert_ne!(rebuilt.sha256, digest.sha256);
+    }
+
+    #[tokio::test]
+    async fn test_digest_needs_pre_execution_state() {
+        let mut context = program_test().start_with_context().await;
+        let cosigner = Keypair::new();
+        let (vault, before, after, signature) = executed_transfer(&mut context, &cosigner).await;
+
+        let built = ApprovalDigest::build(&vault, 0, &signature, EXECUTE_SLOT, &after);
+        assert_eq!(built, Err(ApprovalDigestError::AlreadyExecuted));
+        let built = ApprovalDigest::build(&vault, 1, &signature, EXECUTE_SLOT, &before);
+        assert_eq!(built, Err(ApprovalDigestError::TransactionNotFound));
+        let built = ApprovalDigest::build(&vault, 0, "not-a-signature", EXECUTE_SLOT, &before);
+        assert_eq!(built, Err(ApprovalDigestError::MalformedSignature));
+    }
+}
@@ -0,0 +1,131 @@
+mod common;
+
+#[cfg(test)]
+mod attestation_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::instruction::{AccountMeta, InstructionError};
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        instruction::VaultInstruction, state::TransactionAccount, VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 5;
+    const HASH: [u8; 32] = [7; 32];
+
+    #[tokio::test]
+    async fn test_attestation_requires_execution() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let second_owner = Keypair::new();
+        let multisig_signer = multisig_signer_pda(&vault, NONCE);
+        send(
+            &mut context,
+            &[initialize_multisig_ix(
+                &vault,
+                &payer,
+                vec![payer, second_owner.pubkey()],
+                2,
+                NONCE,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: multisig_signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::AttestMessage { message_hash: HASH }
+            .try_to_vec()
+            .unwrap();
+        send(
+            &mut context,
+            &[create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let execute = execute_multisig_tx_ix(
+            &vault,
+            &multisig_signer,
+            &payer,
+            0,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        );
+
+        // Proposed but short of threshold: not executable and not verifiable
+        let err = send(&mut context, &[execute.clone()], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::NotEnoughSigners as u32)
+            )
+        );
+        assert!(fetch_vault(&mut context, &vault).await.find_attestation(&HASH).is_none());
+
+        send(
+            &mut context,
+            &[approve_multisig_tx_ix(&vault, &second_owner.pubkey(), 0), execute],
+            &[&second_owner],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let attestation = state.find_attestation(&HASH).unwrap();
+        assert_eq!(attestation.transaction_id, 0);
+        assert_eq!(attestation.approvers, vec![payer, second_owner.pubkey()]);
+        assert!(state.find_attestation(&[8; 32]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_direct_attestation_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let impostor = Keypair::new();
+        let ix = solana_program::instruction::Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(impostor.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::AttestMessage { message_hash: HASH }
+                .try_to_vec()
+                .unwrap(),
+        };
+        let err = send(&mut context, &[ix], &[&impostor]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InsufficientAuthority as u32)
+            )
+        );
+    }
+}
@@ -0,0 +1,145 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod audit_export_tests {
+    use super::common::*;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use vault_program::client::{
+        build_audit_report, signatures_in_slot_range, AuditError, AuditTransaction,
+        SealedAuditReport,
+    };
+
+    // Sends `instructions` at `slot` and records the transaction the way an exporter would
+    // have fetched it
+    async fn record(
+        context: &mut ProgramTestContext,
+        slot: u64,
+        instructions: &[Instruction],
+    ) -> AuditTransaction {
+        context.warp_to_slot(slot).unwrap();
+        let payer = context.payer.pubkey();
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let signature = transaction.signatures[0].to_string();
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        AuditTransaction {
+            signature,
+            slot,
+            block_time: None,
+            error: processed.result.err().map(|e| e.to_string()),
+            instructions: instructions.iter().map(|ix| ix.data.clone()).collect(),
+            logs: processed.metadata.unwrap().log_messages,
+        }
+    }
+
+    struct History {
+        vault: Pubkey,
+        mint: Pubkey,
+        transactions: Vec<AuditTransaction>,
+        // Tracked balance of the mint once the slot 200 withdrawal is booked
+        after_withdrawal: u64,
+    }
+
+    async fn history(context: &mut ProgramTestContext) -> History {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let user = context.payer.pubkey();
+        let mut transactions = vec![
+            record(context, 100, &[deposit_ix(&vault, &mint, &user, 400)]).await,
+            record(context, 200, &[withdraw_ix(&vault, &mint, &user, 150)]).await,
+        ];
+        let after_withdrawal = fetch_vault(context, &vault).await.tracked_balance(&mint);
+        // Fails: more than the vault holds
+        let overdraw = withdraw_ix(&vault, &mint, &user, 10_000);
+        transactions.push(record(context, 300, &[overdraw]).await);
+        transactions.push(record(context, 400, &[deposit_ix(&vault, &mint, &user, 50)]).await);
+        History {
+            vault,
+            mint,
+            transactions,
+            after_withdrawal,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_report_reconstructs_balances() {
+        let mut context = program_test().start_with_context().await;
+        let history = history(&mut context).await;
+
+        let report = build_audit_report(&history.vault, 100, 300, &history.transactions);
+        let names: Vec<_> = report.entries.iter().map(|e| e.instructions.join(",")).collect();
+        assert_eq!(names, ["Deposite", "Withdraw", "Withdraw"]);
+        assert!(!report.entries[0].events.is_empty());
+        assert!(report.entries[2].error.is_some());
+        assert!(report.entries[2].events.is_empty());
+
+        // The slot 400 deposit is out of range, so the balance is the one after the withdrawal
+        assert_eq!(report.balances.len(), 1);
+        assert_eq!(report.balances[0].asset, history.mint.to_string());
+        assert_eq!(report.balances[0].balance, history.after_withdrawal);
+        assert_eq!(report.balances[0].slot, 200);
+        assert!(report.total_value_locked.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sealed_report_verifies_from_disk() {
+        let mut context = program_test().start_with_context().await;
+        let history = history(&mut context).await;
+        let operator = Keypair::new();
+
+        let report = build_audit_report(&history.vault, 0, u64::MAX, &history.transactions);
+        let sealed = SealedAuditReport::seal(report, Some(&operator.to_bytes()[..])).unwrap();
+        assert_eq!(sealed.signer, Some(operator.pubkey().to_string()));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json");
+        std::fs::write(&path, sealed.to_json().unwrap()).unwrap();
+        let json = std::fs::read_to_string(&path).unwrap();
+        let loaded = SealedAuditReport::from_json(&json).unwrap();
+        assert_eq!(loaded.verify(), Ok(()));
+
+        // Any edit to the history breaks the digest
+        let mut edited = loaded.clone();
+        edited.report.entries.remove(1);
+        assert_eq!(edited.verify(), Err(AuditError::DigestMismatch));
+
+        // Re-sealing an edited report without the operator's key can't reuse their signature
+        let mut forged = SealedAuditReport::seal(edited.report, None).unwrap();
+        forged.signer = loaded.signer.clone();
+        forged.signature = loaded.signature.clone();
+        assert_eq!(forged.verify(), Err(AuditError::SignatureInvalid));
+
+        let mut unsigned = loaded;
+        unsigned.signature = None;
+        assert_eq!(unsigned.verify(), Err(AuditError::MalformedSignature));
+    }
+
+    #[test]
+    fn test_signature_walk_stops_below_range() {
+        // Newest first, one signature per slot from 10 down to 1, pages of 3
+        let feed: Vec<(String, u64)> = (1..=10).rev().map(|s| (format!("sig{}", s), s)).collect();
+        let mut pages = 0;
+        let signatures = signatures_in_slot_range(4, 7, |before| {
+            pages += 1;
+            let start = before.map_or(0, |b| feed.iter().position(|(s, _)| s == b).unwrap() + 1);
+            feed[start..].iter().take(3).cloned().collect()
+        });
+        assert_eq!(signatures, ["sig4", "sig5", "sig6", "sig7"]);
+        assert_eq!(pages, 3);
+    }
+}
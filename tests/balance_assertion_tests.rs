@@ -0,0 +1,167 @@
+mod common;
+
+#[cfg(test)]
+mod balance_assertion_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{AccountRole, BalanceAssertion, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 23;
+    const FUNDED: u64 = 10_000_000;
+
+    fn create_v3_ix(
+        vault: &Pubkey,
+        proposer: &Pubkey,
+        accounts: Vec<TransactionAccount>,
+        roles: Vec<AccountRole>,
+        assertions: Vec<BalanceAssertion>,
+        data: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*proposer, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateMultiSigTransactionV3 {
+                program_id: system_program::id(),
+                accounts,
+                roles,
+                assertions,
+                data,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // 1-of-1 multisig of the payer whose signer PDA holds FUNDED lamports
+    async fn multisig_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                system_instruction::transfer(&payer, &signer, FUNDED),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, signer)
+    }
+
+    // Proposes a transfer of `lamports` out of the signer PDA that must leave it `keep`
+    fn propose_transfer(
+        vault: &Pubkey,
+        payer: &Pubkey,
+        signer: &Pubkey,
+        recipient: &Pubkey,
+        lamports: u64,
+        keep: u64,
+    ) -> Instruction {
+        let transfer = system_instruction::transfer(signer, recipient, lamports);
+        create_v3_ix(
+            vault,
+            payer,
+            vec![
+                TransactionAccount { pubkey: *signer, is_signer: true, is_writable: true },
+                TransactionAccount { pubkey: *recipient, is_signer: false, is_writable: true },
+            ],
+            vec![AccountRole::MultisigSigner, AccountRole::Writable],
+            vec![BalanceAssertion { account: *signer, min_post_balance: keep, is_token: false }],
+            transfer.data,
+        )
+    }
+
+    fn execute_ix(
+        vault: &Pubkey,
+        signer: &Pubkey,
+        payer: &Pubkey,
+        id: u64,
+        to: &Pubkey,
+    ) -> Instruction {
+        let remaining = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(*signer, false),
+            AccountMeta::new(*to, false),
+        ];
+        execute_multisig_tx_ix(vault, signer, payer, id, remaining)
+    }
+
+    #[tokio::test]
+    async fn test_violated_assertion_rolls_back_transfer() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, signer) = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let recipient = Pubkey::new_unique();
+
+        let too_much = propose_transfer(&vault, &payer, &signer, &recipient, 9_000_000, 2_000_000);
+        let within = propose_transfer(&vault, &payer, &signer, &recipient, 1_000_000, 2_000_000);
+        send(&mut context, &[too_much, within], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.transaction_assertions(0)[0].min_post_balance, 2_000_000);
+
+        let execute = execute_ix(&vault, &signer, &payer, 0, &recipient);
+        let err = send(&mut context, &[execute], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::AssertionFailed));
+        assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 0);
+        assert_eq!(context.banks_client.get_balance(signer).await.unwrap(), FUNDED);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.multisig_transaction(0).unwrap().did_execute);
+
+        let execute = execute_ix(&vault, &signer, &payer, 1, &recipient);
+        send(&mut context, &[execute], &[]).await.unwrap();
+        assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_assertions_checked_at_creation() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, signer) = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let recipient = Pubkey::new_unique();
+        let accounts = vec![TransactionAccount {
+            pubkey: recipient,
+            is_signer: false,
+            is_writable: true,
+        }];
+        let on = |account: Pubkey| BalanceAssertion {
+            account,
+            min_post_balance: 1,
+            is_token: false,
+        };
+
+        // On an account the transaction doesn't touch, or more than the cap
+        let too_many = vec![on(recipient); BalanceAssertion::MAX_PER_TRANSACTION + 1];
+        for assertions in [vec![on(signer)], too_many] {
+            let create = create_v3_ix(
+                &vault,
+                &payer,
+                accounts.clone(),
+                vec![AccountRole::Writable],
+                assertions,
+                vec![],
+            );
+            let err = send(&mut context, &[create], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+        }
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multi_sig_transactions.is_empty());
+        assert!(state.transaction_assertions.is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+mod common;
+
+#[cfg(test)]
+mod canonical_bump_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::signature::Signer;
+    use vault_program::{instruction::VaultInstruction, state::Vault, VaultError, PROGRAM_ID};
+
+    fn legacy_initialize_data(bump: u8) -> Vec<u8> {
+        VaultInstruction::Initialize {
+            bump,
+            index: 0,
+            allow_same_as_authority: true,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    // Highest bump below the canonical one that still gives an off-curve address
+    fn non_canonical_pda(authority: &Pubkey) -> (Pubkey, u8) {
+        let (_, canonical) = vault_pda(authority, 0);
+        let index_bytes = 0u16.to_le_bytes();
+        (0..canonical)
+            .rev()
+            .find_map(|bump| {
+                let bump_seed = [bump];
+                let mut seeds = Vault::seeds(authority, &index_bytes);
+                seeds.push(&bump_seed);
+                Pubkey::create_program_address(&seeds, &PROGRAM_ID)
+                    .ok()
+                    .map(|address| (address, bump))
+            })
+            .expect("no non-canonical bump")
+    }
+
+    #[tokio::test]
+    async fn test_caller_bump_ignored() {
+        let mut context = program_test().start_with_context().await;
+        let authority = context.payer.pubkey();
+        let (vault, canonical) = vault_pda(&authority, 0);
+        let (_, wrong_bump) = non_canonical_pda(&authority);
+
+        let mut ix = initialize_ix(&vault, &authority, &authority, 0, true);
+        ix.data = legacy_initialize_data(wrong_bump);
+        send(&mut context, &[ix], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.bump, canonical);
+        // The stored bump is the one the vault signs with
+        let mint = create_mint(&mut context, 0).await.pubkey();
+        send(&mut context, &[add_supported_token_ix(&vault, &mint, &authority, &authority)], &[])
+            .await
+            .unwrap();
+        create_funded_ata(&mut context, &mint, &authority, 50).await;
+        send(
+            &mut context,
+            &[deposit_ix(&vault, &mint, &authority, 50), withdraw_ix(&vault, &mint, &authority, 50)],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_non_canonical_pda_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let authority = context.payer.pubkey();
+        let (off_canonical, wrong_bump) = non_canonical_pda(&authority);
+
+        for data in [
+            legacy_initialize_data(wrong_bump),
+            VaultInstruction::InitializeVault {
+                index: 0,
+                allow_same_as_authority: true,
+            }
+            .try_to_vec()
+            .unwrap(),
+        ] {
+            let mut ix = initialize_ix(&off_canonical, &authority, &authority, 0, true);
+            ix.data = data;
+            let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        }
+        assert!(context
+            .banks_client
+            .get_account(off_canonical)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
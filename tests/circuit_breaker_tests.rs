@@ -0,0 +1,125 @@
+mod common;
+
+#[cfg(test)]
+mod circuit_breaker_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{instruction::VaultInstruction, state::ParamKey, VaultError, PROGRAM_ID};
+
+    const DEPOSIT: u64 = 1_000;
+
+    fn unpause_ix(vault: &Pubkey, admin: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UnpauseVault.try_to_vec().unwrap(),
+        }
+    }
+
+    // Vault holding a DEPOSIT of its mint from the payer, who is also the emergency admin
+    async fn funded_vault(
+        context: &mut ProgramTestContext,
+        threshold_bps: u64,
+        window_seconds: u64,
+    ) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, DEPOSIT).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(context, &vault).await;
+        state.set_param(ParamKey::AutoPauseThresholdBps, threshold_bps);
+        state.set_param(ParamKey::OutflowWindowSeconds, window_seconds);
+        state.max_pause_duration = 100;
+        store_vault(context, &vault, &state).await;
+        send(context, &[deposit_ix(&vault, &mint, &payer, DEPOSIT)], &[]).await.unwrap();
+        (vault, mint)
+    }
+
+    #[tokio::test]
+    async fn test_consecutive_withdrawals_trip_the_breaker() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, 2_500, 3_600).await;
+        let payer = context.payer.pubkey();
+        let user_tokens = get_associated_token_address(&payer, &mint);
+
+        // 100 and 200 out of the 1_000 the window opened with stay under 25%
+        for _ in 0..2 {
+            advance_clock(&mut context, 1).await;
+            send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 100)], &[]).await.unwrap();
+        }
+        assert!(!fetch_vault(&mut context, &vault).await.paused);
+
+        // 300 doesn't: this one still lands, and pauses the vault behind it
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 100)], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &user_tokens).await, 300);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.paused);
+        assert!(state.circuit_breaker_tripped);
+        let window = &state.outflow_windows[0];
+        assert_eq!((window.opening_balance, window.amount_out), (DEPOSIT, 300));
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        // Unlike an admin pause, it doesn't run out after max_pause_duration
+        advance_clock(&mut context, 1_000).await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 2)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        send(
+            &mut context,
+            &[unpause_ix(&vault, &payer), withdraw_ix(&vault, &mint, &payer, 50)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.paused);
+        assert!(!state.circuit_breaker_tripped);
+    }
+
+    #[tokio::test]
+    async fn test_breaker_off_by_default() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, 0, 0).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 900)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.paused);
+        assert!(state.outflow_windows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_outflows_in_separate_windows_dont_add_up() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, 2_500, 60).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 200)], &[]).await.unwrap();
+
+        // The second 200 opens a new window, measured against the 800 left
+        advance_clock(&mut context, 61).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 200)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.paused);
+        assert_eq!(state.outflow_windows[0].opening_balance, 800);
+    }
+}
@@ -0,0 +1,380 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod client_tests {
+    use borsh::BorshDeserialize;
+    use solana_program::pubkey::Pubkey;
+    use vault_program::{
+        client,
+        events::{SolWithdrawnEvent, TokenDepositedEvent, VaultEvent, EVENT_SCHEMA_VERSION},
+        instruction::VaultInstruction,
+        state::{SupportedToken, TimeLock, TokenBalance, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    #[test]
+    fn test_initialize_builder_derives_vault() {
+        let authority = Pubkey::new_unique();
+        let admin = Pubkey::new_unique();
+        let ix = client::initialize(&authority, &admin, 3, false);
+
+        let (vault, _) = Vault::find_address(&PROGRAM_ID, &authority, 3);
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        assert_eq!(ix.accounts[0].pubkey, vault);
+        assert!(ix.accounts[1].is_signer);
+        match VaultInstruction::try_from_slice(&ix.data).unwrap() {
+            VaultInstruction::InitializeVault {
+                index,
+                allow_same_as_authority,
+            } => assert_eq!((index, allow_same_as_authority), (3, false)),
+            other => panic!("unexpected instruction {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_events_from_logs() {
+        let logs = vec![
+            "Program DvMJg65xGz7W7xa1tP6LW2RP4TecJDb5oN2Qcvf7Qc63 invoke [1]".to_string(),
+            "Program log: Instruction: Deposit".to_string(),
+            r#"Program log: EVENT: {"base":{"event_type":"token_deposited","timestamp":5},"amount":10}"#
+                .to_string(),
+            "Program log: EVENT: not json".to_string(),
+        ];
+
+        let events = client::parse_events(&logs);
+        assert_eq!(events.len(), 1);
+        assert_eq!(client::event_type(&events[0]), Some("token_deposited"));
+        assert_eq!(events[0]["amount"], 10);
+    }
+
+    #[test]
+    fn test_parse_exec_markers() {
+        let logs = vec![
+            "Program log: Instruction: ExecuteMultiSigTransaction".to_string(),
+            "Program log: EXEC target=11111111111111111111111111111111 accounts=2 data_len=12 tx_id=4"
+                .to_string(),
+            "Program log: EXEC_FAILED tx_id=4 error=Custom(1)".to_string(),
+        ];
+
+        assert_eq!(
+            client::parse_exec_lines(&logs),
+            vec![
+                client::ExecLogLine::Invoking {
+                    target: "11111111111111111111111111111111".to_string(),
+                    accounts: 2,
+                    data_len: 12,
+                    transaction_id: 4,
+                },
+                client::ExecLogLine::Failed {
+                    transaction_id: 4,
+                    error: "Custom(1)".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_timestamps_rendered_as_rfc3339() {
+        assert_eq!(client::rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(client::rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(client::rfc3339(1_709_294_400), "2024-03-01T12:00:00Z");
+        assert_eq!(client::rfc3339(-1), "1969-12-31T23:59:59Z");
+
+        let mut lock = serde_json::json!({
+            "amount": 5,
+            "start_time": 0,
+            "duration": 86_400,
+            "base": { "timestamp": 1_709_294_400 },
+        });
+        client::humanize_timestamps(&mut lock);
+        assert_eq!(lock["start_time"], "1970-01-01T00:00:00Z");
+        assert_eq!(lock["base"]["timestamp"], "2024-03-01T12:00:00Z");
+        assert_eq!(lock["duration"], 86_400);
+        assert_eq!(lock["amount"], 5);
+    }
+
+    fn deposited_event_json() -> serde_json::Value {
+        let event = TokenDepositedEvent {
+            base: VaultEvent {
+                event_type: "token_deposited".to_string(),
+                vault: Pubkey::new_unique(),
+                timestamp: 1_700_000_000,
+                authority: Pubkey::new_unique(),
+                schema_version: EVENT_SCHEMA_VERSION,
+                slot: 250_000_000,
+            },
+            token_mint: Pubkey::new_unique(),
+            amount: 42,
+            fee_amount: 0,
+            depositor: Pubkey::new_unique(),
+            vault_fee_amount: 0,
+            protocol_fee_amount: 0,
+            resulting_balance: 1_042,
+            resulting_tvl: 5_042,
+            accrued_fees_after: 3,
+        };
+        serde_json::to_value(&event).unwrap()
+    }
+
+    #[test]
+    fn test_parse_current_version_event() {
+        match client::parse_event(deposited_event_json()) {
+            client::ParsedEvent::TokenDeposited(event) => {
+                assert_eq!(event.amount, 42);
+                assert_eq!(event.base.schema_version, EVENT_SCHEMA_VERSION);
+            }
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_pre_versioning_event() {
+        let mut json = deposited_event_json();
+        json["base"].as_object_mut().unwrap().remove("schema_version");
+        match client::parse_event(json) {
+            client::ParsedEvent::TokenDeposited(event) => assert_eq!(event.base.schema_version, 0),
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        }
+    }
+
+    // Events from before version 6 have no resulting balances and parse with zeros
+    #[test]
+    fn test_v5_event_without_resulting_balances() {
+        let mut json = deposited_event_json();
+        json["base"]["schema_version"] = 5.into();
+        for field in ["resulting_balance", "resulting_tvl", "accrued_fees_after"] {
+            json.as_object_mut().unwrap().remove(field);
+        }
+        match client::parse_event(json) {
+            client::ParsedEvent::TokenDeposited(event) => {
+                assert_eq!(event.amount, 42);
+                assert_eq!(event.resulting_balance, 0);
+                assert_eq!(event.accrued_fees_after, 0);
+            }
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        }
+    }
+
+    // resulting_tvl is written as a string since version 7; version 6 wrote a number
+    #[test]
+    fn test_resulting_tvl_as_string() {
+        let mut json = deposited_event_json();
+        assert_eq!(json["resulting_tvl"], "5042");
+        json["resulting_tvl"] = u128::from(u64::MAX).pow(2).to_string().into();
+        match client::parse_event(json.clone()) {
+            client::ParsedEvent::TokenDeposited(event) => {
+                assert_eq!(event.resulting_tvl, u128::from(u64::MAX).pow(2));
+            }
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        }
+
+        json["base"]["schema_version"] = 6.into();
+        json["resulting_tvl"] = 5_042.into();
+        match client::parse_event(json) {
+            client::ParsedEvent::TokenDeposited(event) => assert_eq!(event.resulting_tvl, 5_042),
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_ledger_csv_row() {
+        let json = deposited_event_json();
+        let event = client::parse_event(json.clone());
+        let mint = match &event {
+            client::ParsedEvent::TokenDeposited(event) => event.token_mint.to_string(),
+            other => panic!("expected TokenDeposited, got {:?}", other),
+        };
+        let row = client::ledger_csv_row(&event).unwrap();
+        let columns: Vec<_> = row.split(',').collect();
+        assert_eq!(columns.len(), client::LEDGER_CSV_HEADER.split(',').count());
+        assert_eq!(columns[..3], ["250000000", "1700000000", "token_deposited"]);
+        assert_eq!(columns[4], mint);
+        assert_eq!(columns[5..], ["42", "0", "1042", "5042", "3"]);
+
+        let mut other = json;
+        other["base"]["event_type"] = "time_lock_created".into();
+        assert!(client::ledger_csv_row(&client::parse_event(other)).is_none());
+    }
+
+    #[test]
+    fn test_sol_withdrawn_round_trip() {
+        let event = SolWithdrawnEvent {
+            base: VaultEvent {
+                event_type: "sol_transferred".to_string(),
+                vault: Pubkey::new_unique(),
+                timestamp: 1_700_000_000,
+                authority: Pubkey::new_unique(),
+                schema_version: EVENT_SCHEMA_VERSION,
+                slot: 250_000_000,
+            },
+            amount: 990,
+            fee_amount: 10,
+            recipient: Pubkey::new_unique(),
+            vault_lamports: 5_000,
+            resulting_balance: 4_000,
+            resulting_tvl: 4_000,
+            accrued_fees_after: 0,
+        };
+        match client::parse_event(serde_json::to_value(&event).unwrap()) {
+            client::ParsedEvent::SolWithdrawn(parsed) => {
+                assert_eq!(parsed.base.slot, 250_000_000);
+                assert_eq!(parsed.recipient, event.recipient);
+                assert_eq!(parsed.vault_lamports, 5_000);
+            }
+            other => panic!("expected SolWithdrawn, got {:?}", other),
+        }
+    }
+
+    // Older SOL withdrawals were logged in the token layout with the native mint
+    #[test]
+    fn test_v3_sol_withdrawal_keeps_token_layout() {
+        let mut json = deposited_event_json();
+        json["base"]["event_type"] = "sol_withdrawn".into();
+        json["base"]["schema_version"] = 3.into();
+        json["base"].as_object_mut().unwrap().remove("slot");
+        json["recipient"] = json["depositor"].take();
+        match client::parse_event(json) {
+            client::ParsedEvent::TokenWithdrawn(event) => {
+                assert_eq!(event.amount, 42);
+                assert_eq!(event.base.slot, 0);
+            }
+            other => panic!("expected TokenWithdrawn, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_future_version_degrades_to_raw() {
+        let mut json = deposited_event_json();
+        json["base"]["schema_version"] = (EVENT_SCHEMA_VERSION + 1).into();
+        json["renamed_amount"] = json["amount"].take();
+
+        match client::parse_event(json.clone()) {
+            client::ParsedEvent::Raw(raw) => {
+                assert_eq!(raw.event_type.as_deref(), Some("token_deposited"));
+                assert_eq!(raw.schema_version, Some(EVENT_SCHEMA_VERSION + 1));
+                assert_eq!(raw.value, json);
+            }
+            other => panic!("expected Raw, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_unknown_event_type_degrades_to_raw() {
+        let mut json = deposited_event_json();
+        json["base"]["event_type"] = "something_new".into();
+        assert!(matches!(client::parse_event(json), client::ParsedEvent::Raw(_)));
+    }
+
+    fn token_account_data(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Vec<u8> {
+        use solana_program::program_pack::Pack;
+        use spl_token::state::{Account, AccountState};
+        let mut data = vec![0u8; Account::LEN];
+        Account::pack(
+            Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                state: AccountState::Initialized,
+                ..Account::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_holdings_drift_against_ata_balances() {
+        let vault_key = Pubkey::new_unique();
+        let usdc: Pubkey = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".parse().unwrap();
+        let (over, missing) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut vault = Vault::default();
+        for (mint, tracked) in [(usdc, 1_000), (over, 50), (missing, 10)] {
+            vault.supported_tokens.push(SupportedToken {
+                mint,
+                is_active: true,
+                ..Default::default()
+            });
+            vault.token_balances.push(TokenBalance {
+                mint,
+                balance: tracked,
+                last_updated: 0,
+            });
+        }
+        vault.time_locks.push(TimeLock {
+            mint: usdc,
+            amount: 400,
+            released_amount: 100,
+            ..Default::default()
+        });
+
+        // USDC ATA short by 20, the other over by 5, the third never created
+        let accounts = [
+            (
+                spl_associated_token_account::get_associated_token_address(&vault_key, &usdc),
+                token_account_data(&usdc, &vault_key, 980),
+            ),
+            (
+                spl_associated_token_account::get_associated_token_address(&vault_key, &over),
+                token_account_data(&over, &vault_key, 55),
+            ),
+        ];
+        let fetch = |key: &Pubkey| {
+            accounts
+                .iter()
+                .find(|(address, _)| address == key)
+                .map(|(_, data)| data.clone())
+        };
+
+        let holdings = client::token_holdings(&vault, &vault_key, None, fetch);
+        assert_eq!(holdings.len(), 3);
+        assert_eq!(holdings[0].symbol.as_deref(), Some("USDC"));
+        assert_eq!((holdings[0].tracked, holdings[0].on_chain), (1_000, Some(980)));
+        assert_eq!(holdings[0].locked, 300);
+        assert_eq!(holdings[0].drift, Some(-20));
+        assert_eq!(holdings[1].symbol, None);
+        assert_eq!(holdings[1].drift, Some(5));
+        assert_eq!((holdings[2].on_chain, holdings[2].drift), (None, None));
+
+        let only_over = client::token_holdings(&vault, &vault_key, Some(&over), fetch);
+        assert_eq!(only_over.len(), 1);
+        assert_eq!(only_over[0].mint, over);
+
+        let json = serde_json::to_value(&only_over).unwrap();
+        assert_eq!(json[0]["mint"], over.to_string());
+        assert_eq!(json[0]["drift"], 5);
+        let table = client::holdings_table(&holdings);
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.contains("missing"));
+        assert!(table.contains("-20"));
+    }
+
+    #[test]
+    fn test_tvl_twap_between_observations() {
+        let vault = Vault {
+            total_value_locked: 600,
+            tvl_cumulative: 100_000,
+            tvl_last_updated: 1_000,
+            ..Vault::default()
+        };
+        let earlier = client::TvlObservation {
+            tvl_cumulative: 0,
+            timestamp: 900,
+        };
+
+        // 1_000 for 100s, then 600 for the 50s since the last change
+        let later = client::TvlObservation::at(&vault, 1_050).unwrap();
+        assert_eq!(later.tvl_cumulative, 130_000);
+        assert_eq!(client::tvl_twap(&earlier, &later), Some(866));
+        assert_eq!(client::tvl_twap(&later, &earlier), None);
+        assert_eq!(client::tvl_twap(&later, &later), None);
+    }
+
+    #[test]
+    fn test_view_decoding_errors_are_vault_errors() {
+        let foreign = client::decode_view_result(&Pubkey::new_unique(), &[]);
+        assert_eq!(foreign.unwrap_err(), VaultError::UnexpectedReturnData.into());
+        let garbled = client::decode_view_return_data(&PROGRAM_ID, "not base64!");
+        assert_eq!(garbled.unwrap_err(), VaultError::UnexpectedReturnData.into());
+    }
+}
@@ -0,0 +1,526 @@
+#![allow(dead_code)]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    bpf_loader_upgradeable,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::get_associated_token_address;
+use vault_program::{
+    instruction::VaultInstruction,
+    state::{GlobalConfig, TransactionAccount, Vault},
+    VaultError, PROGRAM_ID,
+};
+
+#[path = "../fixtures/mock_defi.rs"]
+pub mod mock_defi;
+pub use mock_defi::{MockDefiConfig, MOCK_DEFI_ID};
+
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "vault_program",
+        PROGRAM_ID,
+        processor!(vault_program::processor::process_instruction),
+    )
+}
+
+pub fn vault_pda(authority: &Pubkey, index: u16) -> (Pubkey, u8) {
+    Vault::find_address(&PROGRAM_ID, authority, index)
+}
+
+pub async fn send(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+/// The error a transaction fails with when its vault instruction at `index` returns `error`.
+pub fn vault_error(index: u8, error: VaultError) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(error as u32))
+}
+
+pub async fn fetch_vault(context: &mut ProgramTestContext, vault: &Pubkey) -> Vault {
+    let account = context
+        .banks_client
+        .get_account(*vault)
+        .await
+        .unwrap()
+        .unwrap();
+    Vault::load(&account.data).unwrap()
+}
+
+/// Writes `state` over the vault account in place, for setting up state no instruction
+/// produces yet.
+pub async fn store_vault(context: &mut ProgramTestContext, vault: &Pubkey, state: &Vault) {
+    let mut account = context
+        .banks_client
+        .get_account(*vault)
+        .await
+        .unwrap()
+        .unwrap();
+    state.save(&mut account.data).unwrap();
+    context.set_account(vault, &account.into());
+}
+
+/// Moves to a fresh slot (so repeated transactions get a new blockhash) with the clock
+/// `seconds` ahead of where it was.
+pub async fn advance_clock(context: &mut ProgramTestContext, seconds: i64) {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let timestamp = clock.unix_timestamp + seconds;
+    context.warp_to_slot(clock.slot + 2).unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = timestamp;
+    context.set_sysvar(&clock);
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, token_account: &Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(*token_account)
+        .await
+        .unwrap()
+        .unwrap();
+    // The classic layout, which Token-2022 accounts start with
+    let base = &account.data[..spl_token::state::Account::LEN];
+    spl_token::state::Account::unpack_from_slice(base).unwrap().amount
+}
+
+pub fn initialize_ix(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    emergency_admin: &Pubkey,
+    index: u16,
+    allow_same_as_authority: bool,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(*emergency_admin, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::InitializeVault {
+            index,
+            allow_same_as_authority,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+/// Initializes a vault owned by the context payer, which is also its emergency admin,
+/// and returns its PDA.
+pub async fn create_vault(context: &mut ProgramTestContext) -> Pubkey {
+    create_vault_with_index(context, 0).await
+}
+
+pub async fn create_vault_with_index(context: &mut ProgramTestContext, index: u16) -> Pubkey {
+    let authority = context.payer.pubkey();
+    let (vault, _) = vault_pda(&authority, index);
+    let ix = initialize_ix(&vault, &authority, &authority, index, true);
+    send(context, &[ix], &[]).await.unwrap();
+    vault
+}
+
+/// Funds `authority` from the payer and initializes its vault, with the payer as emergency admin.
+pub async fn create_vault_for(context: &mut ProgramTestContext, authority: &Keypair) -> Pubkey {
+    let payer = context.payer.pubkey();
+    let (vault, _) = vault_pda(&authority.pubkey(), 0);
+    let instructions = [
+        system_instruction::transfer(&payer, &authority.pubkey(), 100_000_000),
+        initialize_ix(&vault, &authority.pubkey(), &payer, 0, false),
+    ];
+    send(context, &instructions, &[authority]).await.unwrap();
+    vault
+}
+
+pub async fn create_mint(context: &mut ProgramTestContext, decimals: u8) -> Keypair {
+    let mint = Keypair::new();
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::id(),
+            &mint.pubkey(),
+            &payer,
+            None,
+            decimals,
+        )
+        .unwrap(),
+    ];
+    send(context, &instructions, &[&mint]).await.unwrap();
+    mint
+}
+
+/// Same keypair for the same seed on every run, for tests that want fixed addresses without
+/// touching the system RNG.
+pub fn test_keypair(seed: u8) -> Keypair {
+    solana_sdk::signer::keypair::keypair_from_seed(&[seed; 32]).unwrap()
+}
+
+/// Installs `mock_defi` at `program_id` with `config`, along with its reward mint. Call before
+/// the test starts; fund the pool afterwards with `fund_mock_pool`.
+pub fn add_mock_defi(test: &mut ProgramTest, program_id: Pubkey, config: MockDefiConfig) {
+    test.add_program("mock_defi", program_id, processor!(mock_defi::process));
+    test.add_account(
+        mock_defi::config_address(&program_id),
+        mock_config_account(&program_id, &config),
+    );
+
+    let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: COption::Some(mock_defi::pool_authority(&program_id).0),
+        supply: 0,
+        decimals: 6,
+        is_initialized: true,
+        freeze_authority: COption::None,
+    }
+    .pack_into_slice(&mut mint_data);
+    test.add_account(
+        mock_defi::reward_mint(&program_id),
+        Account {
+            lamports: Rent::default().minimum_balance(mint_data.len()),
+            data: mint_data,
+            owner: spl_token::id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+}
+
+fn mock_config_account(program_id: &Pubkey, config: &MockDefiConfig) -> Account {
+    let data = config.try_to_vec().unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *program_id,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Replaces the config of the `mock_defi` at `program_id` mid-test.
+pub fn configure_mock_defi(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    config: &MockDefiConfig,
+) {
+    let account = mock_config_account(program_id, config);
+    context.set_account(&mock_defi::config_address(program_id), &account.into());
+}
+
+pub async fn mock_defi_config(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+) -> MockDefiConfig {
+    let account = context
+        .banks_client
+        .get_account(mock_defi::config_address(program_id))
+        .await
+        .unwrap()
+        .expect("mock_defi not installed");
+    MockDefiConfig::try_from_slice(&account.data).unwrap()
+}
+
+/// Gives the `mock_defi` pool at `program_id` a token account for `mint` holding `amount`.
+pub async fn fund_mock_pool(
+    context: &mut ProgramTestContext,
+    program_id: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    create_funded_ata(context, mint, &mock_defi::pool_authority(program_id).0, amount).await
+}
+
+/// Creates `owner`'s associated token account for `mint` and mints `amount` into it.
+pub async fn create_funded_ata(
+    context: &mut ProgramTestContext,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let payer = context.payer.pubkey();
+    let ata = get_associated_token_address(owner, mint);
+    let mut instructions = vec![
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &payer,
+            owner,
+            mint,
+            &spl_token::id(),
+        ),
+    ];
+    if amount > 0 {
+        instructions.push(
+            spl_token::instruction::mint_to(&spl_token::id(), mint, &ata, &payer, &[], amount)
+                .unwrap(),
+        );
+    }
+    send(context, &instructions, &[]).await.unwrap();
+    ata
+}
+
+pub fn add_supported_token_ix(
+    vault: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::AddSupportedToken {
+            mint: *mint,
+            bump: 0,
+            idempotent: false,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+pub fn deposit_ix(vault: &Pubkey, mint: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::Deposite { amount }.try_to_vec().unwrap(),
+    }
+}
+
+pub fn withdraw_ix(vault: &Pubkey, mint: &Pubkey, user: &Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(user, mint), false),
+            AccountMeta::new_readonly(*user, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            AccountMeta::new(global_config_pda(), false),
+        ],
+        data: VaultInstruction::Withdraw { amount }.try_to_vec().unwrap(),
+    }
+}
+
+/// Vault with one supported mint and `amount` of it sitting in the payer's ATA.
+pub async fn vault_with_token(context: &mut ProgramTestContext, amount: u64) -> (Pubkey, Pubkey) {
+    let vault = create_vault(context).await;
+    let mint = create_mint(context, 6).await.pubkey();
+    let authority = context.payer.pubkey();
+    send(context, &[add_supported_token_ix(&vault, &mint, &authority, &authority)], &[])
+        .await
+        .unwrap();
+    create_funded_ata(context, &mint, &authority, amount).await;
+    (vault, mint)
+}
+
+pub fn global_config_pda() -> Pubkey {
+    GlobalConfig::find_address(&PROGRAM_ID).0
+}
+
+pub fn program_data_address() -> Pubkey {
+    bpf_loader_upgradeable::get_program_data_address(&PROGRAM_ID)
+}
+
+// The 45-byte metadata header the upgradeable loader writes ahead of the program bytes
+pub fn program_data(authority: Option<Pubkey>) -> Vec<u8> {
+    let mut data = 3u32.to_le_bytes().to_vec();
+    data.extend_from_slice(&42u64.to_le_bytes());
+    match authority {
+        Some(key) => {
+            data.push(1);
+            data.extend_from_slice(key.as_ref());
+        }
+        None => data.extend_from_slice(&[0; 33]),
+    }
+    data.extend_from_slice(&[0; 16]);
+    data
+}
+
+// Stands in a ProgramData account for the program, upgradeable by `authority`
+pub fn deploy_program_data(context: &mut ProgramTestContext, authority: Option<Pubkey>) {
+    let account = Account {
+        lamports: 1_000_000_000,
+        data: program_data(authority),
+        owner: bpf_loader_upgradeable::id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    context.set_account(&program_data_address(), &account.into());
+}
+
+// `payer` has to be the upgrade authority of the deployed ProgramData, see deploy_program_data
+pub fn initialize_global_config_ix(payer: &Pubkey, config: GlobalConfig) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(global_config_pda(), false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::rent::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            AccountMeta::new_readonly(program_data_address(), false),
+        ],
+        data: VaultInstruction::InitializeGlobalConfig {
+            admin: config.admin,
+            max_fee_bps: config.max_fee_bps,
+            allowed_programs: config.allowed_programs,
+            protocol_fee_recipient: config.protocol_fee_recipient,
+            protocol_fee_bps: config.protocol_fee_bps,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+pub async fn fetch_global_config(context: &mut ProgramTestContext) -> GlobalConfig {
+    let account = context
+        .banks_client
+        .get_account(global_config_pda())
+        .await
+        .unwrap()
+        .expect("global config account");
+    GlobalConfig::load(&account.data).unwrap()
+}
+
+pub fn multisig_signer_pda(vault: &Pubkey, nonce: u8) -> Pubkey {
+    Pubkey::find_program_address(&[vault.as_ref(), &[nonce]], &PROGRAM_ID).0
+}
+
+pub fn initialize_multisig_ix(
+    vault: &Pubkey,
+    authority: &Pubkey,
+    owners: Vec<Pubkey>,
+    threshold: u64,
+    nonce: u8,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::InitializeMultiSig {
+            owners,
+            threshold,
+            nonce,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+pub fn create_multisig_tx_ix(
+    vault: &Pubkey,
+    proposer: &Pubkey,
+    program_id: Pubkey,
+    accounts: Vec<TransactionAccount>,
+    data: Vec<u8>,
+) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::CreateMultiSigTransaction {
+            program_id,
+            accounts,
+            data,
+        }
+        .try_to_vec()
+        .unwrap(),
+    }
+}
+
+pub fn approve_multisig_tx_ix(vault: &Pubkey, approver: &Pubkey, transaction_id: u64) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*approver, true),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ],
+        data: VaultInstruction::ApproveMultiSigTransaction { transaction_id }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
+
+/// `remaining` carries whatever the stored instruction needs beyond the fixed accounts,
+/// starting with the target program.
+pub fn execute_multisig_tx_ix(
+    vault: &Pubkey,
+    multisig_signer: &Pubkey,
+    executor: &Pubkey,
+    transaction_id: u64,
+    remaining: Vec<AccountMeta>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*vault, false),
+        AccountMeta::new_readonly(*multisig_signer, false),
+        AccountMeta::new(*executor, true),
+        AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+    ];
+    accounts.extend(remaining);
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts,
+        data: VaultInstruction::ExecuteMultiSigTransaction { transaction_id }
+            .try_to_vec()
+            .unwrap(),
+    }
+}
@@ -19,12 +19,11 @@ mod comprehensive_tests {
     use spl_associated_token_account::instruction as ata_instruction;
     use vault_program::{
         instruction::VaultInstruction,
-        state::{Vault, MultiSig, MultiSigTransaction, TransactionAccount, FeeConfig, SupportedToken, TimeLock, GovernanceConfig, GovernanceProposal, VoteType},
+        state::{Vault, MultiSig, MultiSigTransaction, TransactionAccount, FeeConfig, SupportedToken, TimeLock, VestingSchedule, GovernanceConfig, GovernanceProposal, VoteType},
         processor::process_instruction,
         PROGRAM_ID,
     };
     use borsh::{BorshSerialize, BorshDeserialize};
-    use std::mem;
 
     fn program_test() -> ProgramTest {
         ProgramTest::new(
@@ -43,7 +42,7 @@ mod comprehensive_tests {
         let vault_token_account = spl_associated_token_account::get_associated_token_address(&vault_pubkey, &mint_pubkey);
 
         // Calculate vault size
-        let vault_size = mem::size_of::<Vault>() as u64;
+        let vault_size = Vault::initial_space().unwrap() as u64;
         let rent = banks_client.get_rent().await.unwrap();
         let vault_rent = rent.minimum_balance(vault_size as usize);
 
@@ -70,9 +69,13 @@ mod comprehensive_tests {
                 AccountMeta::new_readonly(spl_token::id(), false),
                 AccountMeta::new_readonly(spl_associated_token_account::id(), false),
             ],
-            data: VaultInstruction::Initialize { bump: 0 }
-                .try_to_vec()
-                .unwrap(),
+            data: VaultInstruction::Initialize {
+                bump: 0,
+                index: 0,
+                allow_same_as_authority: true,
+            }
+            .try_to_vec()
+            .unwrap(),
         };
 
         let transaction = Transaction::new_signed_with_payer(
@@ -187,10 +190,12 @@ mod comprehensive_tests {
             ],
             data: VaultInstruction::CreateTimeLock {
                 beneficiary: beneficiary.pubkey(),
+                mint: Pubkey::new_unique(),
                 amount,
                 duration,
                 cliff_duration,
-                is_linear: true,
+                schedule: VestingSchedule::Linear,
+                revocable: false,
             }
             .try_to_vec()
             .unwrap(),
@@ -245,7 +250,7 @@ mod comprehensive_tests {
             ],
             data: VaultInstruction::InitializeGovernance {
                 voting_token_mint,
-                quorm_threshold: quorum_threshold,
+                quorum_threshold,
                 proposal_threshold,
                 voting_period,
                 time_lock_delay,
@@ -352,6 +357,7 @@ mod comprehensive_tests {
             data: VaultInstruction::AddSupportedToken {
                 mint: token_mint,
                 bump: 0,
+                idempotent: false,
             }
             .try_to_vec()
             .unwrap(),
@@ -397,6 +403,7 @@ mod comprehensive_tests {
             accounts: vec![
                 AccountMeta::new(vault_pubkey, false),
                 AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
             ],
             data: VaultInstruction::PauseVault
                 .try_to_vec()
@@ -428,6 +435,7 @@ mod comprehensive_tests {
             accounts: vec![
                 AccountMeta::new(vault_pubkey, false),
                 AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
             ],
             data: VaultInstruction::UnpauseVault
                 .try_to_vec()
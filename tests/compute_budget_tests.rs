@@ -0,0 +1,298 @@
+mod common;
+
+// Compute used by the heaviest instructions as the vault state grows. Every case is simulated
+// under the default 200k CU limit and the figures are printed as a table (run with
+// --nocapture). By default small sizes run against the natively built processor, which
+// exercises the paths but reports little compute. For real numbers build the program first
+// and enable `cu-profile`, which loads the SBF build and profiles the large sizes:
+//
+//     cargo build-sbf && cargo test --features cu-profile --test compute_budget_tests -- --nocapture
+//
+// What verbose logging costs shows up by running it again against
+// `cargo build-sbf --features debug-logs` and comparing the two tables; Deposit and
+// ExecuteMultiSigTransaction format the most pubkeys.
+#[cfg(test)]
+mod compute_budget_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::{processor, ProgramTest, ProgramTestContext};
+    use solana_sdk::{
+        account::{Account, AccountSharedData},
+        compute_budget::ComputeBudgetInstruction,
+        signature::Signer,
+        transaction::Transaction,
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{
+            GovernanceConfig, GovernanceProposal, MultiSigTransaction, ParamKey, SupportedToken,
+            TokenBalance, TransactionAccount, Vault, VoteLock, VoteType,
+        },
+        PROGRAM_ID,
+    };
+
+    const CU_LIMIT: u32 = 200_000;
+    const NONCE: u8 = 9;
+
+    // Entries of each kind (supported tokens, multisig transactions, governance proposals)
+    // added on top of what the scenario itself needs
+    #[cfg(not(feature = "cu-profile"))]
+    const SIZES: &[usize] = &[0, 8];
+    #[cfg(feature = "cu-profile")]
+    const SIZES: &[usize] = &[0, 16, 64, 128];
+
+    fn bench_program_test() -> ProgramTest {
+        if cfg!(feature = "cu-profile") {
+            let mut test = ProgramTest::default();
+            test.prefer_bpf(true);
+            test.add_program(
+                "vault_program",
+                PROGRAM_ID,
+                processor!(vault_program::processor::process_instruction),
+            );
+            test
+        } else {
+            program_test()
+        }
+    }
+
+    // Pads the vault with `size` inert entries of each kind, growing the account to fit, then
+    // opens governance proposal `size` over `voting_mint`, with a lock for the payer to vote
+    async fn populate(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        voting_mint: &Pubkey,
+        size: usize,
+    ) -> usize {
+        let mut state = fetch_vault(context, vault).await;
+        for _ in 0..size {
+            let mint = Pubkey::new_unique();
+            // Ahead of the real entries, so lookups walk past them
+            state.supported_tokens.insert(
+                0,
+                SupportedToken {
+                    mint,
+                    is_active: true,
+                    ..Default::default()
+                },
+            );
+            state.token_balances.insert(
+                0,
+                TokenBalance {
+                    mint,
+                    balance: 1,
+                    last_updated: 0,
+                },
+            );
+            let owners = state.multi_sig.as_ref().map_or(0, |m| m.owners.len());
+            state.push_multisig_transaction(MultiSigTransaction {
+                multisig: *vault,
+                program_id: PROGRAM_ID,
+                accounts: vec![TransactionAccount {
+                    pubkey: *vault,
+                    is_signer: false,
+                    is_writable: true,
+                }],
+                data: vec![0; 32],
+                signers: vec![false; owners],
+                did_execute: false,
+                proposer: Pubkey::new_unique(),
+                created_at: 0,
+            });
+            let id = state.next_governance_proposal_id;
+            state.governance_proposals.push(GovernanceProposal {
+                id,
+                title: "padding".to_string(),
+                instructions: vec![vec![0; 32]],
+                ..Default::default()
+            });
+            state.next_governance_proposal_id += 1;
+        }
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: *voting_mint,
+            voting_period: 1,
+            ..Default::default()
+        });
+        let id = state.next_governance_proposal_id;
+        state.governance_proposals.push(GovernanceProposal {
+            id,
+            title: "live".to_string(),
+            created_at: 1,
+            end_time: i64::MAX,
+            ..Default::default()
+        });
+        state.next_governance_proposal_id += 1;
+        state.vote_locks.push(VoteLock {
+            voter: context.payer.pubkey(),
+            amount: 1,
+            ..Default::default()
+        });
+
+        let mut data = state.try_to_vec().unwrap();
+        data.resize(data.len() + Vault::DYNAMIC_HEADROOM, 0);
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: PROGRAM_ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+        let len = account.data.len();
+        context.set_account(vault, &AccountSharedData::from(account));
+        len
+    }
+
+    async fn units_consumed(context: &mut ProgramTestContext, instruction: Instruction) -> u64 {
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[ComputeBudgetInstruction::set_compute_unit_limit(CU_LIMIT), instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            blockhash,
+        );
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        assert!(
+            matches!(simulation.result, Some(Ok(()))),
+            "simulation failed: {:?}",
+            simulation.result
+        );
+        simulation.simulation_details.unwrap().units_consumed
+    }
+
+    // Vault with one funded mint and a 1-of-1 multisig whose transaction 0 (a SetParam
+    // self-CPI) is ready to execute
+    async fn scenario(context: &mut ProgramTestContext) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(&vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::SetParam {
+            key: ParamKey::DepositFeeBps,
+            value: 5,
+        }
+        .try_to_vec()
+        .unwrap();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, mint)
+    }
+
+    fn cast_vote_ix(vault: &Pubkey, voter: &Pubkey, proposal_id: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*voter, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CastVote {
+                proposal_id,
+                vote_type: VoteType::For,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_heaviest_instructions_fit_default_budget() {
+        let mut rows = Vec::new();
+        for &size in SIZES {
+            let mut context = bench_program_test().start_with_context().await;
+            let (vault, mint) = scenario(&mut context).await;
+            let payer = context.payer.pubkey();
+            let account_len = populate(&mut context, &vault, &mint, size).await;
+
+            let execute = execute_multisig_tx_ix(
+                &vault,
+                &multisig_signer_pda(&vault, NONCE),
+                &payer,
+                0,
+                vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+            );
+            let cases = [
+                ("ExecuteMultiSigTransaction", execute),
+                ("CastVote", cast_vote_ix(&vault, &payer, size as u64)),
+                ("Deposit", deposit_ix(&vault, &mint, &payer, 100)),
+            ];
+            for (name, instruction) in cases {
+                let units = units_consumed(&mut context, instruction).await;
+                assert!(
+                    units <= CU_LIMIT as u64,
+                    "{} used {} CU with {} entries",
+                    name,
+                    units,
+                    size
+                );
+                rows.push((name, size, account_len, units));
+            }
+        }
+
+        let logs = if cfg!(feature = "debug-logs") { "debug-logs" } else { "release logs" };
+        println!("{}", logs);
+        println!("{:<28} {:>8} {:>12} {:>10}", "instruction", "entries", "vault bytes", "CU");
+        for (name, size, account_len, units) in rows {
+            println!("{:<28} {:>8} {:>12} {:>10}", name, size, account_len, units);
+        }
+    }
+
+    // Without debug-logs a deposit logs its instruction name and its event, and formats no
+    // pubkeys of its own
+    #[cfg(not(feature = "debug-logs"))]
+    #[tokio::test]
+    async fn test_release_logs_are_terse() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[deposit_ix(&vault, &mint, &payer, 100)],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert!(processed.result.is_ok());
+
+        let logs = processed.metadata.unwrap().log_messages;
+        let program_logs: Vec<&str> = logs
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program log: "))
+            .collect();
+        assert_eq!(program_logs.len(), 2, "{:#?}", logs);
+        assert_eq!(program_logs[0], "Instruction: Deposit tokens");
+        assert!(program_logs[1].starts_with("EVENT: "));
+    }
+}
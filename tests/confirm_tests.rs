@@ -0,0 +1,148 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod confirm_tests {
+    use solana_program::pubkey::Pubkey;
+    use vault_program::{
+        client::{confirm_change, review_change, ChangeRefusal, FieldChange},
+        instruction::VaultInstruction,
+        state::{MultiSig, Vault},
+    };
+
+    // 2-of-3 multisig vault, as fetched
+    fn vault(owners: &[Pubkey]) -> Vault {
+        Vault {
+            authority: Pubkey::new_unique(),
+            emergency_admin: Pubkey::new_unique(),
+            multi_sig: Some(MultiSig {
+                owners: owners.to_vec(),
+                threshold: 2,
+                ..MultiSig::default()
+            }),
+            ..Vault::default()
+        }
+    }
+
+    #[test]
+    fn test_owner_diff_rendering() {
+        let (a, b, c, d) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let key = Pubkey::new_unique();
+        let state = vault(&[a, b, c]);
+
+        let instruction = VaultInstruction::SetMultiSigOwners { owners: vec![a, b, d] };
+        let review = review_change(&key, &state, &instruction).unwrap().unwrap();
+        assert_eq!(
+            review.changes,
+            vec![FieldChange::Members {
+                field: "owners",
+                removed: vec![c],
+                added: vec![d],
+                kept: 2,
+            }]
+        );
+        let rendered = review.to_string();
+        let lines: Vec<_> = rendered.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                format!("About to update multisig owners on vault {}", key),
+                "  owners (2 unchanged):".to_string(),
+                format!("    - {}", c),
+                format!("    + {}", d),
+            ]
+        );
+
+        let instruction = VaultInstruction::ChangeMultiSigThreshold { threshold: 3 };
+        let review = review_change(&key, &state, &instruction).unwrap().unwrap();
+        assert!(review.to_string().ends_with("  threshold: 2 of 3 -> 3 of 3\n"));
+
+        let instruction = VaultInstruction::TransferAuthority { new_authority: d };
+        let review = review_change(&key, &state, &instruction).unwrap().unwrap();
+        let line = format!("  authority: {} -> {}\n", state.authority, d);
+        assert!(review.to_string().ends_with(&line));
+
+        // Everyday instructions go through without a prompt
+        let deposit = VaultInstruction::Deposite { amount: 1 };
+        assert_eq!(review_change(&key, &state, &deposit), Ok(None));
+    }
+
+    #[test]
+    fn test_lockout_changes_refused() {
+        let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let key = Pubkey::new_unique();
+        let state = vault(&owners);
+        let review = |instruction| review_change(&key, &state, &instruction);
+
+        // The program would accept this and drop the threshold to 1
+        let shrink = VaultInstruction::SetMultiSigOwners { owners: vec![owners[0]] };
+        assert_eq!(
+            review(shrink),
+            Err(ChangeRefusal::OwnersBelowThreshold { owners: 1, threshold: 2 })
+        );
+        let repeated = VaultInstruction::SetMultiSigOwners {
+            owners: vec![owners[0], owners[1], owners[0]],
+        };
+        assert_eq!(review(repeated), Err(ChangeRefusal::DuplicateOwner(owners[0])));
+        let empty = VaultInstruction::SetMultiSigOwners { owners: vec![] };
+        assert_eq!(review(empty), Err(ChangeRefusal::NoOwners));
+
+        for threshold in [0, 4] {
+            let change = VaultInstruction::ChangeMultiSigThreshold { threshold };
+            assert_eq!(
+                review(change),
+                Err(ChangeRefusal::ThresholdOutOfRange { threshold, owners: 3 })
+            );
+        }
+
+        let new_authority = Pubkey::default();
+        let transfer = VaultInstruction::TransferAuthority { new_authority };
+        assert_eq!(review(transfer), Err(ChangeRefusal::DefaultAuthority));
+        let admin = VaultInstruction::UpdateEmergencyAdmin { new_admin: Pubkey::default() };
+        assert_eq!(review(admin), Err(ChangeRefusal::DefaultEmergencyAdmin));
+
+        let unpaired = Vault::default();
+        let change = VaultInstruction::ChangeMultiSigThreshold { threshold: 1 };
+        assert_eq!(
+            review_change(&key, &unpaired, &change),
+            Err(ChangeRefusal::MultisigNotInitialized)
+        );
+        let paused = Vault { paused: true, ..vault(&owners) };
+        assert_eq!(
+            review_change(&key, &paused, &VaultInstruction::PauseVault),
+            Err(ChangeRefusal::AlreadyPaused)
+        );
+    }
+
+    #[test]
+    fn test_confirmation_requires_vault_address() {
+        let key = Pubkey::new_unique();
+        let state = vault(&[Pubkey::new_unique(), Pubkey::new_unique()]);
+        let review = review_change(&key, &state, &VaultInstruction::PauseVault)
+            .unwrap()
+            .unwrap();
+
+        let mut output = Vec::new();
+        let typed = format!("{}\n", key);
+        assert!(confirm_change(&review, false, &mut typed.as_bytes(), &mut output).unwrap());
+        let printed = String::from_utf8(output).unwrap();
+        assert!(printed.contains("  paused: false -> true (never lapses)\n"));
+        assert!(printed.ends_with("Type the vault address to proceed: "));
+
+        for typed in ["y\n", "", Pubkey::new_unique().to_string().as_str()] {
+            let mut output = Vec::new();
+            assert!(!confirm_change(&review, false, &mut typed.as_bytes(), &mut output).unwrap());
+        }
+
+        // --yes still shows what is about to happen
+        let mut output = Vec::new();
+        assert!(confirm_change(&review, true, &mut "".as_bytes(), &mut output).unwrap());
+        let printed = String::from_utf8(output).unwrap();
+        assert!(printed.starts_with("About to pause vault"));
+        assert!(!printed.contains("Type the vault address"));
+    }
+}
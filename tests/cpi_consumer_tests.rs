@@ -0,0 +1,68 @@
+mod common;
+
+#[cfg(test)]
+mod cpi_consumer_tests {
+    use super::common::*;
+    use solana_program::{
+        account_info::{next_account_info, AccountInfo},
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::processor;
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{cpi, PROGRAM_ID};
+
+    // Minimal consumer program: forwards its accounts into a vault deposit of the u64 in `data`
+    fn consumer_process(
+        _program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        data: &[u8],
+    ) -> ProgramResult {
+        let iter = &mut accounts.iter();
+        let vault_program = next_account_info(iter)?;
+        let accounts = cpi::Deposit {
+            vault: next_account_info(iter)?.clone(),
+            user_token_account: next_account_info(iter)?.clone(),
+            vault_token_account: next_account_info(iter)?.clone(),
+            user_authority: next_account_info(iter)?.clone(),
+            token_program: next_account_info(iter)?.clone(),
+            clock: next_account_info(iter)?.clone(),
+        };
+        let amount = u64::from_le_bytes(data.try_into().unwrap());
+        cpi::deposit(cpi::CpiContext::new(vault_program.clone(), accounts), amount)
+    }
+
+    #[tokio::test]
+    async fn test_deposit_through_consumer_program() {
+        let consumer_id = Pubkey::new_unique();
+        let mut test = program_test();
+        test.add_program("vault_consumer", consumer_id, processor!(consumer_process));
+        let mut context = test.start_with_context().await;
+
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+
+        let ix = Instruction {
+            program_id: consumer_id,
+            accounts: vec![
+                AccountMeta::new_readonly(PROGRAM_ID, false),
+                AccountMeta::new(vault, false),
+                AccountMeta::new(get_associated_token_address(&user, &mint), false),
+                AccountMeta::new(get_associated_token_address(&vault, &mint), false),
+                AccountMeta::new_readonly(user, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: 400u64.to_le_bytes().to_vec(),
+        };
+        send(&mut context, &[ix], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.total_value_locked, 400);
+        assert_eq!(state.token_stats()[0].deposit_count, 1);
+        let vault_ata = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &vault_ata).await, 400);
+    }
+}
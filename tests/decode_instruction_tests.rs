@@ -0,0 +1,201 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod decode_instruction_tests {
+    use borsh::BorshSerialize;
+    use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+    use std::collections::HashSet;
+    use vault_program::{
+        client::{self, DataEncoding, DecodeError},
+        instruction::VaultInstruction,
+        state::{
+            AccountRole, BalanceAssertion, MultiSigTransaction, ParamKey, TransactionAccount,
+            TransactionAssertions, TransactionRoles, Vault,
+        },
+        VaultError, PROGRAM_ID,
+    };
+
+    const GOLDEN: &str = include_str!("fixtures/golden_instructions.txt");
+
+    fn golden() -> Vec<(&'static str, &'static str)> {
+        GOLDEN
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split_once(' ').unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_every_variant_decodes_from_golden_bytes() {
+        let fixtures = golden();
+        assert_eq!(fixtures.len(), 100);
+        let names: HashSet<_> = fixtures.iter().map(|(name, _)| name).collect();
+        assert_eq!(names.len(), fixtures.len());
+
+        for (name, hex) in fixtures {
+            let data = client::parse_instruction_data(hex, None).unwrap();
+            let instruction = client::decode_instruction(&data)
+                .unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(client::instruction_name(&instruction), name);
+            assert!(client::format_instruction(&instruction, None).starts_with(name));
+        }
+    }
+
+    #[test]
+    fn test_encodings_and_amounts() {
+        // Deposite { amount: 1000 }
+        for (input, encoding) in [
+            ("01e803000000000000", None),
+            ("0x01e803000000000000", None),
+            ("2QdKrEY3BV27", None),
+            ("AegDAAAAAAAA", Some(DataEncoding::Base64)),
+        ] {
+            let decoded = client::decode_instruction_data(input, encoding, Some(3)).unwrap();
+            assert_eq!(decoded, "Deposite {\n    amount: 1000, // 1\n}", "input {}", input);
+        }
+        assert_eq!(
+            client::parse_instruction_data("zz", Some(DataEncoding::Hex)),
+            Err(DecodeError::Encoding(DataEncoding::Hex))
+        );
+
+        let recipient = Pubkey::new_unique();
+        let transfer = VaultInstruction::Transfer {
+            recipient,
+            amount: 1_500_000,
+        };
+        let formatted = client::format_instruction(&transfer, Some(6));
+        assert!(formatted.contains(&format!("recipient: {},", recipient)));
+        assert!(formatted.contains("amount: 1500000, // 1.5"));
+        assert_eq!(client::format_token_amount(1, 9), "0.000000001");
+        assert_eq!(client::format_token_amount(7, 0), "7");
+    }
+
+    #[test]
+    fn test_mints_and_amounts_read_from_fields() {
+        let (mint, output_mint, recipient) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let swap = VaultInstruction::JupiterSwap {
+            input_mint: mint,
+            output_mint,
+            amount: 2_500,
+        };
+        let token_transfer = VaultInstruction::TransferV2 {
+            recipient,
+            mint: Some(mint),
+            amount: 1,
+            create_ata: false,
+        };
+        let sol_transfer = VaultInstruction::TransferV2 {
+            recipient,
+            mint: None,
+            amount: 1,
+            create_ata: false,
+        };
+        assert_eq!(client::instruction_mint(&swap), Some(mint));
+        assert_eq!(client::instruction_mint(&token_transfer), Some(mint));
+        assert_eq!(client::instruction_mint(&sol_transfer), None);
+        assert_eq!(client::instruction_mint(&VaultInstruction::CollectFees), None);
+
+        let split = VaultInstruction::SplitTimeLock {
+            id: 3,
+            amount_to_new: 2_500,
+            new_beneficiary: recipient,
+        };
+        let formatted = client::format_instruction(&split, Some(3));
+        assert!(formatted.contains("amount_to_new: 2500, // 2.5"), "{}", formatted);
+        assert!(formatted.contains("id: 3,\n"), "{}", formatted);
+        assert!(client::format_instruction(&swap, Some(3)).contains("amount: 2500, // 2.5"));
+    }
+
+    #[test]
+    fn test_failure_offsets() {
+        // The amount is cut short right after the variant byte
+        let short = client::decode_instruction(&[1, 0xe8, 3, 0, 0]).unwrap_err();
+        assert!(matches!(short, DecodeError::Instruction { offset: 1, .. }), "{:?}", short);
+
+        let mut trailing = VaultInstruction::Deposite { amount: 1 }.try_to_vec().unwrap();
+        trailing.push(0);
+        let err = client::decode_instruction(&trailing).unwrap_err();
+        assert!(matches!(err, DecodeError::Instruction { offset: 9, .. }), "{:?}", err);
+        assert!(err.to_string().contains("byte 9"));
+    }
+
+    #[test]
+    fn test_listing_and_preview_decode_own_payloads() {
+        let set_ttl = VaultInstruction::SetParam {
+            key: ParamKey::MultisigTransactionTtl,
+            value: 86_400,
+        };
+        let vault_key = Pubkey::new_unique();
+        let own = MultiSigTransaction {
+            program_id: PROGRAM_ID,
+            accounts: vec![TransactionAccount {
+                pubkey: vault_key,
+                is_signer: false,
+                is_writable: true,
+            }],
+            data: set_ttl.try_to_vec().unwrap(),
+            ..MultiSigTransaction::default()
+        };
+        let foreign = MultiSigTransaction {
+            program_id: spl_token::id(),
+            data: vec![3, 1, 2],
+            ..MultiSigTransaction::default()
+        };
+        let garbled = MultiSigTransaction {
+            program_id: PROGRAM_ID,
+            data: vec![0xff],
+            ..MultiSigTransaction::default()
+        };
+        let vault = Vault {
+            multi_sig_transactions: vec![own, foreign, garbled],
+            multi_sig_transaction_ids: vec![0, 1, 2],
+            transaction_roles: vec![TransactionRoles {
+                transaction_id: 0,
+                roles: vec![AccountRole::VaultPda],
+            }],
+            transaction_assertions: vec![TransactionAssertions {
+                transaction_id: 0,
+                assertions: vec![BalanceAssertion {
+                    account: vault_key,
+                    min_post_balance: 5_000,
+                    is_token: false,
+                }],
+            }],
+            ..Vault::default()
+        };
+
+        let page = client::list_multisig_transactions(&vault, 0, 0, 10);
+        let names: Vec<_> = page.items.iter().map(|s| s.instruction.as_deref()).collect();
+        assert_eq!(names, vec![Some("SetParam"), None, None]);
+
+        let preview = client::preview_multisig_transaction(&vault, 0, None).unwrap();
+        assert!(preview.contains("key: MultisigTransactionTtl"));
+        assert!(preview.contains("value: 86400"));
+        assert!(preview.contains(&format!("  {} vault\n", vault_key)));
+        let assertion = format!("assertions:\n  {} keeps at least 5000 lamports\n", vault_key);
+        assert!(preview.contains(&assertion));
+        let preview = client::preview_multisig_transaction(&vault, 1, None).unwrap();
+        assert!(preview.ends_with("data: 030102"));
+        let preview = client::preview_multisig_transaction(&vault, 2, None).unwrap();
+        assert!(preview.contains("data: ff (not a VaultInstruction"));
+        assert!(client::preview_multisig_transaction(&vault, 3, None).is_none());
+    }
+
+    #[test]
+    fn test_instruction_errors_described() {
+        let failed = InstructionError::Custom(VaultError::InvalidAmount as u32);
+        assert_eq!(client::decode_instruction_error(&failed), Some(VaultError::InvalidAmount));
+        assert_eq!(
+            client::describe_instruction_error(&failed),
+            "InvalidAmount (15): Invalid amount specified"
+        );
+
+        // Codes past the last variant and runtime errors pass through as they are
+        let unknown = InstructionError::Custom(VaultError::ALL.len() as u32);
+        assert_eq!(client::decode_instruction_error(&unknown), None);
+        let missing = InstructionError::MissingRequiredSignature;
+        assert_eq!(client::decode_instruction_error(&missing), None);
+        assert_eq!(client::describe_instruction_error(&missing), missing.to_string());
+    }
+}
@@ -0,0 +1,104 @@
+mod common;
+
+#[cfg(test)]
+mod delegate_deposit_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::Account as TokenAccount;
+    use vault_program::{instruction::VaultInstruction, state::ActionKind, VaultError, PROGRAM_ID};
+
+    fn delegate_deposit_ix(
+        vault: &Pubkey,
+        mint: &Pubkey,
+        delegate: &Pubkey,
+        on_behalf_of: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(get_associated_token_address(on_behalf_of, mint), false),
+                AccountMeta::new(get_associated_token_address(vault, mint), false),
+                AccountMeta::new_readonly(*delegate, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::DepositAsDelegate {
+                amount,
+                on_behalf_of: *on_behalf_of,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // A user holding 1_000 of the vault's token who approved the payer, an integrator, to
+    // spend `allowance` of it
+    async fn approved_user(
+        context: &mut ProgramTestContext,
+        mint: &Pubkey,
+        allowance: u64,
+    ) -> (Keypair, Pubkey) {
+        let user = Keypair::new();
+        let user_tokens = create_funded_ata(context, mint, &user.pubkey(), 1_000).await;
+        let approve = spl_token::instruction::approve(
+            &spl_token::id(),
+            &user_tokens,
+            &context.payer.pubkey(),
+            &user.pubkey(),
+            &[],
+            allowance,
+        )
+        .unwrap();
+        send(context, &[approve], &[&user]).await.unwrap();
+        (user, user_tokens)
+    }
+
+    #[tokio::test]
+    async fn test_delegate_deposits_for_user() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 0).await;
+        let integrator = context.payer.pubkey();
+        let (user, user_tokens) = approved_user(&mut context, &mint, 400).await;
+
+        let deposit = delegate_deposit_ix(&vault, &mint, &integrator, &user.pubkey(), 300);
+        send(&mut context, &[deposit], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 300);
+        let booked = state.recent_actions().pop().unwrap();
+        assert_eq!((booked.kind, booked.actor), (ActionKind::Deposit, user.pubkey()));
+
+        let account = context.banks_client.get_account(user_tokens).await.unwrap().unwrap();
+        let user_account = TokenAccount::unpack(&account.data).unwrap();
+        assert_eq!((user_account.amount, user_account.delegated_amount), (700, 100));
+    }
+
+    #[tokio::test]
+    async fn test_delegate_limited_to_allowance() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 0).await;
+        let integrator = context.payer.pubkey();
+        let (user, _) = approved_user(&mut context, &mint, 100).await;
+
+        let deposit = delegate_deposit_ix(&vault, &mint, &integrator, &user.pubkey(), 101);
+        let err = send(&mut context, &[deposit], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::DelegationInsufficient));
+
+        // Someone the user never approved gets nowhere either
+        let stranger = Keypair::new();
+        let deposit = delegate_deposit_ix(&vault, &mint, &stranger.pubkey(), &user.pubkey(), 50);
+        let err = send(&mut context, &[deposit], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::DelegationInsufficient));
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 0);
+    }
+}
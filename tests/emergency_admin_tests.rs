@@ -0,0 +1,76 @@
+mod common;
+
+#[cfg(test)]
+mod emergency_admin_tests {
+    use super::common::*;
+    use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::VaultError;
+
+    async fn initialize_with_admin(
+        admin: Option<Pubkey>,
+        allow_same_as_authority: bool,
+    ) -> Result<(), TransactionError> {
+        let mut context = program_test().start_with_context().await;
+        let authority = context.payer.pubkey();
+        let (vault, _) = vault_pda(&authority, 0);
+        // None means "use the vault PDA itself"
+        let admin = admin.unwrap_or(vault);
+        let ix = initialize_ix(&vault, &authority, &admin, 0, allow_same_as_authority);
+        send(&mut context, &[ix], &[]).await.map_err(|e| e.unwrap())
+    }
+
+    fn invalid_admin() -> TransactionError {
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidEmergencyAdmin as u32),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_default_pubkey_rejected() {
+        let result = initialize_with_admin(Some(Pubkey::default()), true).await;
+        assert_eq!(result.unwrap_err(), invalid_admin());
+    }
+
+    #[tokio::test]
+    async fn test_vault_pda_rejected() {
+        let result = initialize_with_admin(None, true).await;
+        assert_eq!(result.unwrap_err(), invalid_admin());
+    }
+
+    #[tokio::test]
+    async fn test_authority_requires_explicit_flag() {
+        let mut context = program_test().start_with_context().await;
+        let authority = context.payer.pubkey();
+        let (vault, _) = vault_pda(&authority, 0);
+
+        let err = send(
+            &mut context,
+            &[initialize_ix(&vault, &authority, &authority, 0, false)],
+            &[],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(err, invalid_admin());
+
+        send(
+            &mut context,
+            &[initialize_ix(&vault, &authority, &authority, 0, true)],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.emergency_admin, authority);
+    }
+
+    #[tokio::test]
+    async fn test_distinct_admin_accepted() {
+        let admin = Keypair::new().pubkey();
+        initialize_with_admin(Some(admin), false).await.unwrap();
+    }
+}
@@ -0,0 +1,145 @@
+mod common;
+
+#[cfg(test)]
+mod emergency_withdraw_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{EmergencyActionLog, ParamKey},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 4;
+
+    fn emergency_withdraw_ix(
+        vault: &Pubkey,
+        mint: &Pubkey,
+        recipient: &Pubkey,
+        admin: &Pubkey,
+        cosigner: Option<&Pubkey>,
+        amount: u64,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(recipient, mint), false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        accounts.extend(cosigner.map(|key| AccountMeta::new_readonly(*key, true)));
+        accounts.push(AccountMeta::new_readonly(global_config_pda(), false));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: VaultInstruction::EmergencyWithdraw {
+                token_mint: *mint,
+                amount,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // Vault holding 1_000 tokens, an empty recipient ATA, and optionally a 1-of-1 multisig
+    // owned by `owner`. `cosign` turns on EmergencyRequiresCosign.
+    async fn funded_vault(
+        context: &mut ProgramTestContext,
+        owner: Option<&Keypair>,
+        cosign: bool,
+    ) -> (Pubkey, Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let recipient = Pubkey::new_unique();
+        create_funded_ata(context, &mint, &recipient, 0).await;
+        let mut setup = vec![deposit_ix(&vault, &mint, &payer, 1_000)];
+        if let Some(owner) = owner {
+            setup.push(initialize_multisig_ix(&vault, &payer, vec![owner.pubkey()], 1, NONCE));
+        }
+        send(context, &setup, &[]).await.unwrap();
+        if cosign {
+            let mut state = fetch_vault(context, &vault).await;
+            state.set_param(ParamKey::EmergencyRequiresCosign, 1);
+            store_vault(context, &vault, &state).await;
+        }
+        (vault, mint, recipient)
+    }
+
+    #[tokio::test]
+    async fn test_admin_alone_by_default() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, mint, recipient) = funded_vault(&mut context, Some(&owner), false).await;
+        let admin = context.payer.pubkey();
+
+        let ix = emergency_withdraw_ix(&vault, &mint, &recipient, &admin, None, 400);
+        send(&mut context, &[ix], &[]).await.unwrap();
+
+        let recipient_ata = get_associated_token_address(&recipient, &mint);
+        assert_eq!(token_balance(&mut context, &recipient_ata).await, 400);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 600);
+        let log = state.emergency_logs.last().unwrap();
+        assert_eq!(log.action, EmergencyActionLog::EMERGENCY_WITHDRAW);
+        assert_eq!(log.admin, admin);
+        assert_eq!(log.details.len(), 40);
+    }
+
+    #[tokio::test]
+    async fn test_cosign_required_when_enabled() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, mint, recipient) = funded_vault(&mut context, Some(&owner), true).await;
+        let admin = context.payer.pubkey();
+
+        let alone = emergency_withdraw_ix(&vault, &mint, &recipient, &admin, None, 400);
+        let err = send(&mut context, &[alone], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::EmergencyCosignRequired));
+
+        // A signer who isn't an owner doesn't count
+        let stranger = Keypair::new();
+        let ix = emergency_withdraw_ix(&vault, &mint, &recipient, &admin, Some(&stranger.pubkey()), 400);
+        let err = send(&mut context, &[ix], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::EmergencyCosignRequired));
+
+        let ix = emergency_withdraw_ix(&vault, &mint, &recipient, &admin, Some(&owner.pubkey()), 400);
+        send(&mut context, &[ix], &[&owner]).await.unwrap();
+
+        let recipient_ata = get_associated_token_address(&recipient, &mint);
+        assert_eq!(token_balance(&mut context, &recipient_ata).await, 400);
+        let state = fetch_vault(&mut context, &vault).await;
+        let log = state.emergency_logs.last().unwrap();
+        assert_eq!(log.admin, admin);
+        assert_eq!(&log.details[40..], owner.pubkey().as_ref());
+    }
+
+    #[tokio::test]
+    async fn test_cosign_skipped_without_multisig() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, recipient) = funded_vault(&mut context, None, true).await;
+        let admin = context.payer.pubkey();
+
+        let ix = emergency_withdraw_ix(&vault, &mint, &recipient, &admin, None, 1_000);
+        send(&mut context, &[ix], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 0);
+    }
+
+    #[tokio::test]
+    async fn test_only_emergency_admin() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, mint, recipient) = funded_vault(&mut context, Some(&owner), false).await;
+
+        let ix = emergency_withdraw_ix(&vault, &mint, &recipient, &owner.pubkey(), None, 1);
+        let err = send(&mut context, &[ix], &[&owner]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+    }
+}
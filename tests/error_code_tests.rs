@@ -0,0 +1,193 @@
+mod common;
+
+// Custom codes are part of the program's interface: clients match on the number, so these
+// pin the number itself rather than the VaultError variant it happens to be today.
+#[cfg(test)]
+mod error_code_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        account_info::AccountInfo,
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        system_program, sysvar,
+    };
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        cpi::{self, CpiContext, MultiSigAction},
+        instruction::VaultInstruction,
+        migration::LegacyVaultV0,
+        state::{program_data_upgrade_authority, GlobalConfig, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 16;
+
+    fn code(code: u32) -> TransactionError {
+        TransactionError::InstructionError(0, InstructionError::Custom(code))
+    }
+
+    fn account<'a>(key: &'a Pubkey, lamports: &'a mut u64, data: &'a mut [u8]) -> AccountInfo<'a> {
+        AccountInfo::new(key, false, false, lamports, data, &system_program::ID, false, 0)
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_instruction() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let garbage = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![AccountMeta::new(vault, false)],
+            data: vec![0xff, 0xff],
+        };
+        let err = send(&mut context, &[garbage], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, code(10)); // InvalidInstruction
+    }
+
+    #[tokio::test]
+    async fn test_vault_not_owned_by_program() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let not_a_vault = Pubkey::new_unique();
+        let ix = initialize_multisig_ix(&not_a_vault, &payer, vec![payer], 1, NONCE);
+        let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, code(13)); // InvalidAccountOwner
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_beyond_balance() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 1_000)], &[]).await.unwrap();
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 1_001)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, code(15)); // InvalidAmount
+    }
+
+    #[tokio::test]
+    async fn test_transfer_by_non_authority() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let stranger = Keypair::new();
+        let transfer = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(stranger.pubkey(), false),
+                AccountMeta::new_readonly(stranger.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::Transfer {
+                recipient: stranger.pubkey(),
+                amount: 1,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        let err = send(&mut context, &[transfer], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, code(7)); // InsufficientAuthority
+    }
+
+    #[tokio::test]
+    async fn test_multisig_failures() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        let err = send(&mut context, &[approve_multisig_tx_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, code(5)); // MultisigNotInitialized
+
+        send(&mut context, &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)], &[])
+            .await
+            .unwrap();
+        let err = send(&mut context, &[approve_multisig_tx_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, code(6)); // TransactionNotFound
+    }
+
+    #[test]
+    fn test_state_decoding_failures() {
+        assert_eq!(GlobalConfig::load(&[]).unwrap_err(), ProgramError::Custom(11));
+        assert_eq!(LegacyVaultV0::load(&[]).unwrap_err(), ProgramError::Custom(11));
+        let not_program_data = ProgramError::from(program_data_upgrade_authority(&[]).unwrap_err());
+        assert_eq!(not_program_data, ProgramError::Custom(11)); // InvalidAccountData
+    }
+
+    #[test]
+    fn test_save_into_short_account() {
+        let mut data = [0u8; 8];
+        let err = Vault::default().save(&mut data).unwrap_err();
+        assert_eq!(err, ProgramError::Custom(18)); // AccountTooSmall
+    }
+
+    #[test]
+    fn test_cpi_to_wrong_program() {
+        let vault = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let clock = sysvar::clock::id();
+        let wrong_program = Pubkey::new_unique();
+        let mut lamports = [0u64; 4];
+        let [l0, l1, l2, l3] = &mut lamports;
+        let (mut d0, mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+        let accounts = MultiSigAction {
+            vault: account(&vault, l0, &mut d0),
+            owner: account(&owner, l1, &mut d1),
+            clock: account(&clock, l2, &mut d2),
+        };
+        let ctx = CpiContext::new(account(&wrong_program, l3, &mut d3), accounts);
+        let err = cpi::approve_multisig_transaction(ctx, 0).unwrap_err();
+        assert_eq!(err, ProgramError::Custom(11)); // InvalidAccountData
+    }
+
+    // One line per code: `<code> <variant> <Display message>`
+    const MESSAGES: &str = include_str!("fixtures/error_messages.txt");
+
+    #[test]
+    fn test_every_code_round_trips() {
+        for (index, error) in VaultError::ALL.iter().enumerate() {
+            let code = error.clone() as u32;
+            assert_eq!(code, index as u32, "{:?} is out of order in VaultError::ALL", error);
+            assert_eq!(VaultError::from_code(code).as_ref(), Some(error));
+            let program_error = ProgramError::from(error.clone());
+            assert_eq!(VaultError::try_from(program_error), Ok(error.clone()));
+        }
+        let past_the_end = VaultError::ALL.len() as u32;
+        assert_eq!(VaultError::from_code(past_the_end), None);
+        assert_eq!(
+            VaultError::try_from(ProgramError::Custom(past_the_end)),
+            Err(ProgramError::Custom(past_the_end))
+        );
+        assert_eq!(
+            VaultError::try_from(ProgramError::MissingRequiredSignature),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_display_messages_are_stable() {
+        let pinned: Vec<_> = MESSAGES.lines().collect();
+        assert_eq!(pinned.len(), VaultError::ALL.len(), "fixtures/error_messages.txt");
+        for (line, error) in pinned.into_iter().zip(VaultError::ALL) {
+            let rendered = format!("{} {:?} {}", error.clone() as u32, error, error);
+            assert_eq!(rendered, line);
+        }
+
+        // Propagates with `?` like any other error
+        let boxed: Box<dyn std::error::Error> = Box::new(VaultError::InvalidAmount);
+        assert_eq!(boxed.to_string(), "Invalid amount specified");
+    }
+}
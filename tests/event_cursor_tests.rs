@@ -0,0 +1,105 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod event_cursor_tests {
+    use std::time::Duration;
+    use vault_program::client::{
+        backfill_signatures, backoff_delays, deliver_with_retry, EventCursor,
+    };
+
+    // Newest first, as get_signatures_for_address returns them
+    fn history(count: usize) -> Vec<String> {
+        (0..count).rev().map(|i| format!("sig{}", i)).collect()
+    }
+
+    // Serves `history` in pages of `size`, continuing after the `before` signature
+    fn paged(history: &[String], size: usize) -> impl FnMut(Option<&str>) -> Vec<String> + '_ {
+        move |before| {
+            let start = before.map_or(0, |b| history.iter().position(|s| s == b).unwrap() + 1);
+            history[start..].iter().take(size).cloned().collect()
+        }
+    }
+
+    #[test]
+    fn test_backfill_stops_at_cursor() {
+        let feed = history(10);
+        let pending = backfill_signatures(Some("sig3"), paged(&feed, 4));
+        let expected: Vec<String> = (3..10).map(|i| format!("sig{}", i)).collect();
+        assert_eq!(pending, expected);
+
+        // No cursor yet: the whole history, oldest first
+        let pending = backfill_signatures(None, paged(&feed, 4));
+        assert_eq!(pending.len(), 10);
+        assert_eq!(pending[0], "sig0");
+    }
+
+    #[test]
+    fn test_restart_resumes_without_gaps_or_repeats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cursor.json");
+
+        // Two events per transaction; the first run dies after forwarding sig4's first event
+        let feed = history(6);
+        let mut forwarded = Vec::new();
+        let mut run = |crash_at: Option<(&str, usize)>| {
+            let mut cursor = EventCursor::load(&path).unwrap();
+            for signature in backfill_signatures(cursor.signature.as_deref(), paged(&feed, 2)) {
+                for log_index in 0..2 {
+                    if !cursor.should_forward(&signature, log_index) {
+                        continue;
+                    }
+                    forwarded.push((signature.clone(), log_index));
+                    cursor.advance(&signature, log_index);
+                    cursor.save(&path).unwrap();
+                    if crash_at == Some((signature.as_str(), log_index)) {
+                        return;
+                    }
+                }
+            }
+        };
+        run(Some(("sig4", 0)));
+        run(None);
+        // A second restart has nothing left to send
+        run(None);
+
+        let expected: Vec<(String, usize)> = (0..6)
+            .flat_map(|i| (0..2).map(move |log| (format!("sig{}", i), log)))
+            .collect();
+        assert_eq!(forwarded, expected);
+    }
+
+    #[test]
+    fn test_same_event_not_forwarded_twice_in_session() {
+        let mut cursor = EventCursor::default();
+        cursor.advance("a", 3);
+        cursor.advance("b", 0);
+        assert!(!cursor.should_forward("a", 3));
+        assert!(cursor.should_forward("a", 4));
+        assert!(!cursor.should_forward("b", 0));
+    }
+
+    #[test]
+    fn test_delivery_backs_off_then_gives_up() {
+        let base = Duration::from_millis(100);
+        let max = Duration::from_millis(500);
+        let delays: Vec<_> = backoff_delays(4, base, max).collect();
+        assert_eq!(delays, [100, 200, 400, 500].map(Duration::from_millis));
+
+        let mut slept = Vec::new();
+        let mut attempts = 0;
+        let post = || {
+            attempts += 1;
+            if attempts == 3 {
+                Ok(())
+            } else {
+                Err("503")
+            }
+        };
+        let result = deliver_with_retry(3, base, max, post, |d| slept.push(d));
+        assert_eq!(result, Ok(()));
+        assert_eq!(slept, [100, 200].map(Duration::from_millis));
+
+        let result: Result<(), &str> = deliver_with_retry(2, base, max, || Err("down"), |_| {});
+        assert_eq!(result, Err("down"));
+    }
+}
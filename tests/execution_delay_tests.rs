@@ -0,0 +1,198 @@
+mod common;
+
+#[cfg(test)]
+mod execution_delay_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{EmergencyActionLog, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 6;
+    const DELAY: i64 = 3_600;
+
+    // Proposes an instruction signed by the multisig signer alone
+    fn propose_ix(vault: &Pubkey, proposer: &Pubkey, inner: VaultInstruction) -> Instruction {
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = inner.try_to_vec().unwrap();
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, inner_accounts, inner_data)
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, transaction_id: u64) -> Instruction {
+        execute_multisig_tx_ix(
+            vault,
+            &multisig_signer_pda(vault, NONCE),
+            executor,
+            transaction_id,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        )
+    }
+
+    fn expedite_ix(vault: &Pubkey, admin: &Pubkey, transaction_id: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::ExpediteTransaction { transaction_id }
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+
+    // 1-of-1 multisig owned by the payer that has put itself under DELAY (transaction 0), with
+    // transaction 1 setting the proposal limit approved and waiting
+    async fn delayed_vault(context: &mut ProgramTestContext) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                propose_ix(
+                    &vault,
+                    &payer,
+                    VaultInstruction::SetExecutionDelay {
+                        execution_delay_seconds: DELAY,
+                    },
+                ),
+                // No delay yet, so this one runs straight away
+                execute_ix(&vault, &payer, 0),
+                propose_ix(
+                    &vault,
+                    &payer,
+                    VaultInstruction::SetMultiSigProposalLimit {
+                        max_proposals_per_day: 4,
+                    },
+                ),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_execution_waits_out_delay() {
+        let mut context = program_test().start_with_context().await;
+        let vault = delayed_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.execution_delay_seconds, DELAY);
+        let reached_at = state.threshold_reached(1).unwrap().reached_at;
+
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ExecutionDelayActive));
+
+        advance_clock(&mut context, DELAY - 10).await;
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ExecutionDelayActive));
+
+        advance_clock(&mut context, 10).await;
+        send(&mut context, &[execute_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multisig_transaction(1).unwrap().did_execute);
+        assert_eq!(state.effective_max_proposals_per_day(), 4);
+        assert_eq!(state.executable_at(1, 1), Some(reached_at + DELAY));
+    }
+
+    #[tokio::test]
+    async fn test_unrecorded_threshold_starts_delay_at_execute() {
+        let mut context = program_test().start_with_context().await;
+        let vault = delayed_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        // As if it had gone through a threshold change that left no record
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.threshold_reached.retain(|reached| reached.transaction_id != 1);
+        store_vault(&mut context, &vault, &state).await;
+        advance_clock(&mut context, DELAY).await;
+
+        send(&mut context, &[execute_ix(&vault, &payer, 1)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.multisig_transaction(1).unwrap().did_execute);
+        let reached_at = state.threshold_reached(1).unwrap().reached_at;
+        assert_eq!(state.executable_at(1, 1), Some(reached_at + DELAY));
+
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ExecutionDelayActive));
+
+        // Lowering the threshold records what it lets through straight away
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.threshold_reached.clear();
+        state.multi_sig.as_mut().unwrap().threshold = 1;
+        state.record_thresholds_met(reached_at);
+        assert_eq!(state.threshold_reached(1).unwrap().reached_at, reached_at);
+        assert!(state.threshold_reached(0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_emergency_admin_expedites() {
+        let mut context = program_test().start_with_context().await;
+        let vault = delayed_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        let stranger = Keypair::new();
+        let err = send(&mut context, &[expedite_ix(&vault, &stranger.pubkey(), 1)], &[&stranger])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        // The payer is also the vault's emergency admin
+        send(
+            &mut context,
+            &[expedite_ix(&vault, &payer, 1), execute_ix(&vault, &payer, 1)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multisig_transaction(1).unwrap().did_execute);
+        assert!(state.threshold_reached(1).unwrap().expedited);
+        let log = state.emergency_logs.last().unwrap();
+        assert_eq!(log.action, EmergencyActionLog::TRANSACTION_EXPEDITED);
+        assert_eq!(log.details, 1u64.to_le_bytes().to_vec());
+
+        let err = send(&mut context, &[expedite_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionAlreadyExecuted));
+    }
+}
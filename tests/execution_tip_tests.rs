@@ -0,0 +1,206 @@
+mod common;
+
+#[cfg(test)]
+mod execution_tip_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction, state::TransactionAccount, VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 4;
+    // Above the rent-exempt minimum, so an empty keeper account can receive it
+    const TIP: u64 = 1_000_000;
+
+    fn fund_tip_ix(vault: &Pubkey, funder: &Pubkey, transaction_id: u64, lamports: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*funder, true),
+                AccountMeta::new_readonly(solana_sdk::system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::FundExecutionTip {
+                transaction_id,
+                lamports,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    fn cancel_ix(vault: &Pubkey, proposer: &Pubkey, transaction_id: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*proposer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CancelMultiSigTransaction { transaction_id }
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, transaction_id: u64) -> Instruction {
+        execute_multisig_tx_ix(
+            vault,
+            &multisig_signer_pda(vault, NONCE),
+            executor,
+            transaction_id,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        )
+    }
+
+    // 1-of-1 multisig owned by the payer with one pending transaction (id 0) that sets the
+    // proposal limit, tipped by the payer
+    async fn setup(context: &mut ProgramTestContext) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(&vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::SetMultiSigProposalLimit {
+            max_proposals_per_day: 3,
+        }
+        .try_to_vec()
+        .unwrap();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data),
+                fund_tip_ix(&vault, &payer, 0, TIP),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    async fn lamports(context: &mut ProgramTestContext, key: &Pubkey) -> u64 {
+        context.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tip_paid_to_executor_once() {
+        let mut context = program_test().start_with_context().await;
+        let vault = setup(&mut context).await;
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.reserved_tip_lamports(), TIP);
+        let vault_lamports = lamports(&mut context, &vault).await;
+
+        // Any signer can execute a ready transaction and collect the tip
+        let keeper = Keypair::new();
+        send(&mut context, &[execute_ix(&vault, &keeper.pubkey(), 0)], &[&keeper])
+            .await
+            .unwrap();
+        assert_eq!(lamports(&mut context, &keeper.pubkey()).await, TIP);
+        assert_eq!(lamports(&mut context, &vault).await, vault_lamports - TIP);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multi_sig_transactions[0].did_execute);
+        assert_eq!(state.effective_max_proposals_per_day(), 3);
+        assert!(state.execution_tips.is_empty());
+
+        let payer = context.payer.pubkey();
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionAlreadyExecuted));
+        let err = send(&mut context, &[cancel_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionAlreadyExecuted));
+        assert_eq!(lamports(&mut context, &keeper.pubkey()).await, TIP);
+        assert_eq!(lamports(&mut context, &vault).await, vault_lamports - TIP);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_refunds_tip_and_blocks_execution() {
+        let mut context = program_test().start_with_context().await;
+        let vault = setup(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        // Only the proposer may top the tip up or cancel
+        let stranger = Keypair::new();
+        let err = send(&mut context, &[cancel_ix(&vault, &stranger.pubkey(), 0)], &[&stranger])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        let vault_lamports = lamports(&mut context, &vault).await;
+        send(&mut context, &[cancel_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap();
+        assert_eq!(lamports(&mut context, &vault).await, vault_lamports - TIP);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.execution_tips.is_empty());
+        assert_eq!(state.cancelled_transactions, vec![0]);
+
+        let keeper = Keypair::new();
+        let err = send(&mut context, &[execute_ix(&vault, &keeper.pubkey(), 0)], &[&keeper])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionCancelled));
+        let err = send(&mut context, &[fund_tip_ix(&vault, &payer, 0, TIP)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionCancelled));
+        let err = send(&mut context, &[cancel_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionCancelled));
+        assert_eq!(lamports(&mut context, &keeper.pubkey()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_top_up_and_failed_execution_keeps_tip() {
+        let mut context = program_test().start_with_context().await;
+        let vault = setup(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[fund_tip_ix(&vault, &payer, 0, 500)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.execution_tips.len(), 1);
+        assert_eq!(state.execution_tips[0].lamports, TIP + 500);
+
+        // A read-only executor can't be paid, so execution is refused and the tip stays put
+        let keeper = Keypair::new();
+        let mut execute = execute_ix(&vault, &keeper.pubkey(), 0);
+        execute.accounts[2].is_writable = false;
+        let err = send(&mut context, &[execute], &[&keeper])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.multi_sig_transactions[0].did_execute);
+        assert_eq!(state.reserved_tip_lamports(), TIP + 500);
+    }
+}
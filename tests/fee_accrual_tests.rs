@@ -0,0 +1,195 @@
+mod common;
+
+#[cfg(test)]
+mod fee_accrual_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{ParamKey, TokenBalance},
+        VaultError, PROGRAM_ID,
+    };
+
+    const FUNDED: u64 = 100_000;
+
+    fn collect_fees_ix(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        recipient_token_account: &Pubkey,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new(get_associated_token_address(vault, mint), false),
+                AccountMeta::new(*recipient_token_account, false),
+            ],
+            data: VaultInstruction::CollectFees.try_to_vec().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_withdrawal_then_collect_accrued_fees() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, FUNDED).await;
+        let payer = context.payer.pubkey();
+        let fee_recipient = Pubkey::new_unique();
+        let fee_tokens = create_funded_ata(&mut context, &mint, &fee_recipient, 0).await;
+        let vault_tokens = get_associated_token_address(&vault, &mint);
+        let user_tokens = get_associated_token_address(&payer, &mint);
+
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::DepositFeeBps, 100);
+        state.set_param(ParamKey::WithdrawalFeeBps, 200);
+        state.fee_config.fee_recipient = fee_recipient;
+        store_vault(&mut context, &vault, &state).await;
+
+        // The whole deposit lands in the vault; the 1% fee is set aside, not credited
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, FUNDED)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 99_000);
+        assert_eq!(state.free_balance(&mint), 99_000);
+        assert_eq!(state.accrued_fee(&mint), 1_000);
+        assert_eq!(token_balance(&mut context, &vault_tokens).await, FUNDED);
+
+        // The depositor can take out everything they were credited
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 99_000)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 0);
+        assert_eq!(state.total_value_locked, 0);
+        assert_eq!(state.accrued_fee(&mint), 1_000 + 1_980);
+        assert_eq!(token_balance(&mut context, &user_tokens).await, 97_020);
+        assert_eq!(token_balance(&mut context, &vault_tokens).await, 2_980);
+
+        // ... and not a token of the fees
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+
+        let collect = collect_fees_ix(&vault, &payer, &mint, &fee_tokens);
+        send(&mut context, &[collect.clone()], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &fee_tokens).await, 2_980);
+        assert_eq!(token_balance(&mut context, &vault_tokens).await, 0);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.accrued_fee(&mint), 0);
+        assert!(state.accrued_fees.is_empty());
+
+        // Nothing left to collect
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[collect], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &fee_tokens).await, 2_980);
+    }
+
+    #[tokio::test]
+    async fn test_collect_fees_checks_authority_and_recipient() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, FUNDED).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::DepositFeeBps, 100);
+        store_vault(&mut context, &vault, &state).await;
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, FUNDED)], &[]).await.unwrap();
+
+        // Fees go to the fee recipient, the authority by default, and nowhere else
+        let stranger = Keypair::new();
+        let stranger_tokens = create_funded_ata(&mut context, &mint, &stranger.pubkey(), 0).await;
+        let misdirected = collect_fees_ix(&vault, &payer, &mint, &stranger_tokens);
+        let err = send(&mut context, &[misdirected], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        let payer_tokens = get_associated_token_address(&payer, &mint);
+        let unauthorized = collect_fees_ix(&vault, &stranger.pubkey(), &mint, &payer_tokens);
+        let err = send(&mut context, &[unauthorized], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        assert_eq!(fetch_vault(&mut context, &vault).await.accrued_fee(&mint), 1_000);
+        send(&mut context, &[collect_fees_ix(&vault, &payer, &mint, &payer_tokens)], &[])
+            .await
+            .unwrap();
+        assert_eq!(token_balance(&mut context, &payer_tokens).await, 1_000);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_event_reports_resulting_balances() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, FUNDED).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::DepositFeeBps, 100);
+        store_vault(&mut context, &vault, &state).await;
+
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[deposit_ix(&vault, &mint, &payer, 40_000)],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert!(processed.result.is_ok());
+        let event: serde_json::Value = processed
+            .metadata
+            .unwrap()
+            .log_messages
+            .iter()
+            .find_map(|line| line.strip_prefix("Program log: EVENT: "))
+            .map(|json| serde_json::from_str(json).unwrap())
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(event["base"]["event_type"], "token_deposited");
+        assert_eq!(event["resulting_balance"], state.tracked_balance(&mint));
+        assert_eq!(event["resulting_tvl"], state.total_value_locked.to_string());
+        assert_eq!(event["accrued_fees_after"], state.accrued_fee(&mint));
+        assert_eq!((state.tracked_balance(&mint), state.accrued_fee(&mint)), (39_600, 400));
+    }
+
+    #[tokio::test]
+    async fn test_payouts_refused_when_books_exceed_holdings() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, FUNDED).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, FUNDED)], &[]).await.unwrap();
+
+        // Fees booked on top of a fully credited deposit: the books claim 500 more than the
+        // token account holds
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.accrued_fees.push(TokenBalance { mint, balance: 500, last_updated: 0 });
+        store_vault(&mut context, &vault, &state).await;
+        advance_clock(&mut context, 1).await;
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::CustodyShortfall));
+
+        let payer_tokens = get_associated_token_address(&payer, &mint);
+        let collect = collect_fees_ix(&vault, &payer, &mint, &payer_tokens);
+        let err = send(&mut context, &[collect], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::CustodyShortfall));
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!((state.tracked_balance(&mint), state.accrued_fee(&mint)), (FUNDED, 500));
+    }
+}
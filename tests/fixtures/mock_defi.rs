@@ -0,0 +1,265 @@
+// Stand-in for the external programs the vault CPIs into: a yield strategy and a Jupiter-style
+// router in one native program. Behaviour comes from a MockDefiConfig account the test writes
+// directly, and every address it uses is derived from fixed seeds, so nothing here draws on
+// the system RNG. Install it with common::add_mock_defi; it can be added more than once (e.g.
+// at jupiter::PROGRAM_ID as well), each id getting its own config and pool.
+//
+// Instruction data starts with a tag byte, except swaps, which start with a Jupiter v6 route
+// discriminator:
+//   DEPOSIT, amount: config, vault (signer), vault token, pool token, pool authority,
+//     token program. Takes `amount`; the position grows by it less deposit_slippage_bps.
+//   WITHDRAW, amount: same accounts. Pays withdraw_return_bps of min(amount, position) back
+//     and reduces the position.
+//   HARVEST: config, reward mint, destination token, pool authority, token program. Mints
+//     harvest_reward of the reward mint into the destination.
+//   route discriminator, amount_in: config, vault (signer), vault input token, vault output
+//     token, pool input token, pool output token, pool authority, token program. Takes
+//     amount_in and pays swap_rate_bps of it in the output mint.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction},
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address;
+use vault_program::jupiter;
+
+pub const MOCK_DEFI_ID: Pubkey = Pubkey::new_from_array([0xde; 32]);
+
+pub const DEPOSIT: u8 = 0;
+pub const WITHDRAW: u8 = 1;
+pub const HARVEST: u8 = 2;
+
+const CONFIG_SEED: &[u8] = b"config";
+const POOL_SEED: &[u8] = b"pool";
+const REWARD_MINT_SEED: &[u8] = b"reward_mint";
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct MockDefiConfig {
+    pub deposit_slippage_bps: u16,
+    pub withdraw_return_bps: u16,
+    pub harvest_reward: u64,
+    pub swap_rate_bps: u16,
+    pub position: u64, // deposits less slippage, minus withdrawals
+}
+
+// Everything goes in and comes out at face value
+impl Default for MockDefiConfig {
+    fn default() -> Self {
+        Self {
+            deposit_slippage_bps: 0,
+            withdraw_return_bps: 10_000,
+            harvest_reward: 0,
+            swap_rate_bps: 10_000,
+            position: 0,
+        }
+    }
+}
+
+pub fn config_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id).0
+}
+
+// Owns the pool token accounts and is the reward mint's mint authority
+pub fn pool_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[POOL_SEED], program_id)
+}
+
+pub fn pool_token_account(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&pool_authority(program_id).0, mint)
+}
+
+pub fn reward_mint(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[REWARD_MINT_SEED], program_id).0
+}
+
+fn bps_of(amount: u64, bps: u16) -> u64 {
+    (amount as u128 * bps as u128 / 10_000) as u64
+}
+
+fn read_amount(data: &[u8]) -> Result<u64, ProgramError> {
+    data.get(..8)
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)
+}
+
+fn load_config(program_id: &Pubkey, config: &AccountInfo) -> Result<MockDefiConfig, ProgramError> {
+    if *config.key != config_address(program_id) || config.owner != program_id {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(MockDefiConfig::deserialize(&mut &config.data.borrow()[..])?)
+}
+
+fn save_config(config: &MockDefiConfig, account: &AccountInfo) -> ProgramResult {
+    config.serialize(&mut &mut account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn transfer<'a>(
+    from: &AccountInfo<'a>,
+    to: &AccountInfo<'a>,
+    owner: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    amount: u64,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let mut ix = spl_token::instruction::transfer(
+        &spl_token::id(),
+        from.key,
+        to.key,
+        owner.key,
+        &[],
+        amount,
+    )?;
+    // Token-2022 takes the same instruction
+    ix.program_id = *token_program.key;
+    let infos = [from.clone(), to.clone(), owner.clone(), token_program.clone()];
+    if seeds.is_empty() {
+        invoke(&ix, &infos)
+    } else {
+        invoke_signed(&ix, &infos, &[seeds])
+    }
+}
+
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let bump = [pool_authority(program_id).1];
+    let pool_seeds: &[&[u8]] = &[POOL_SEED, &bump];
+    let account_info_iter = &mut accounts.iter();
+    let config_account = next_account_info(account_info_iter)?;
+    let mut config = load_config(program_id, config_account)?;
+
+    if jupiter::route_instruction(data).is_some() {
+        let amount_in = read_amount(&data[8..])?;
+        let vault = next_account_info(account_info_iter)?;
+        let vault_in = next_account_info(account_info_iter)?;
+        let vault_out = next_account_info(account_info_iter)?;
+        let pool_in = next_account_info(account_info_iter)?;
+        let pool_out = next_account_info(account_info_iter)?;
+        let pool = next_account_info(account_info_iter)?;
+        let token_program = next_account_info(account_info_iter)?;
+        transfer(vault_in, pool_in, vault, token_program, amount_in, &[])?;
+        let amount_out = bps_of(amount_in, config.swap_rate_bps);
+        return transfer(pool_out, vault_out, pool, token_program, amount_out, pool_seeds);
+    }
+
+    let (tag, rest) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    match *tag {
+        DEPOSIT | WITHDRAW => {
+            let amount = read_amount(rest)?;
+            let vault = next_account_info(account_info_iter)?;
+            let vault_token = next_account_info(account_info_iter)?;
+            let pool_token = next_account_info(account_info_iter)?;
+            let pool = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+            if *tag == DEPOSIT {
+                transfer(vault_token, pool_token, vault, token_program, amount, &[])?;
+                let kept = amount - bps_of(amount, config.deposit_slippage_bps);
+                config.position += kept;
+            } else {
+                let principal = amount.min(config.position);
+                config.position -= principal;
+                let paid = bps_of(principal, config.withdraw_return_bps);
+                transfer(pool_token, vault_token, pool, token_program, paid, pool_seeds)?;
+            }
+            save_config(&config, config_account)
+        }
+        HARVEST => {
+            let mint = next_account_info(account_info_iter)?;
+            let destination = next_account_info(account_info_iter)?;
+            let pool = next_account_info(account_info_iter)?;
+            let token_program = next_account_info(account_info_iter)?;
+            let ix = spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint.key,
+                destination.key,
+                pool.key,
+                &[],
+                config.harvest_reward,
+            )?;
+            invoke_signed(
+                &ix,
+                &[mint.clone(), destination.clone(), pool.clone(), token_program.clone()],
+                &[pool_seeds],
+            )
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+pub fn deposit_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![DEPOSIT];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+pub fn withdraw_data(amount: u64) -> Vec<u8> {
+    let mut data = vec![WITHDRAW];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data
+}
+
+pub fn harvest_data() -> Vec<u8> {
+    vec![HARVEST]
+}
+
+// A `route` call for amount_in, which is all the mock reads from it
+pub fn swap_data(amount_in: u64) -> Vec<u8> {
+    let mut data = jupiter::ROUTE_DISCRIMINATORS[0].1.to_vec();
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data
+}
+
+// Accounts a deposit or withdrawal of `mint` on behalf of `vault` takes
+pub fn strategy_accounts(program_id: &Pubkey, vault: &Pubkey, mint: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(config_address(program_id), false),
+        AccountMeta::new_readonly(*vault, false),
+        AccountMeta::new(get_associated_token_address(vault, mint), false),
+        AccountMeta::new(pool_token_account(program_id, mint), false),
+        AccountMeta::new_readonly(pool_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+// Accounts a harvest paying the reward mint into `destination` takes
+pub fn harvest_accounts(program_id: &Pubkey, destination: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(config_address(program_id), false),
+        AccountMeta::new(reward_mint(program_id), false),
+        AccountMeta::new(*destination, false),
+        AccountMeta::new_readonly(pool_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+// Accounts a swap of `input` for `output` out of `vault` takes
+pub fn swap_accounts(
+    program_id: &Pubkey,
+    vault: &Pubkey,
+    input: &Pubkey,
+    output: &Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(config_address(program_id), false),
+        AccountMeta::new_readonly(*vault, false),
+        AccountMeta::new(get_associated_token_address(vault, input), false),
+        AccountMeta::new(get_associated_token_address(vault, output), false),
+        AccountMeta::new(pool_token_account(program_id, input), false),
+        AccountMeta::new(pool_token_account(program_id, output), false),
+        AccountMeta::new_readonly(pool_authority(program_id).0, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]
+}
+
+// The mock called directly, for exercising it outside a vault CPI
+pub fn instruction(program_id: &Pubkey, data: Vec<u8>, accounts: Vec<AccountMeta>) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    }
+}
@@ -0,0 +1,118 @@
+mod common;
+
+#[cfg(test)]
+mod free_balance_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{TimeLock, TokenBalance, Vault, VestingSchedule},
+        VaultError, PROGRAM_ID,
+    };
+
+    fn create_time_lock_ix(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateTimeLock {
+                beneficiary: Pubkey::new_unique(),
+                mint: *mint,
+                amount,
+                duration: 1_000,
+                cliff_duration: None,
+                schedule: VestingSchedule::CliffAll,
+                revocable: false,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    fn invalid_amount() -> TransactionError {
+        let code = VaultError::InvalidAmount as u32;
+        TransactionError::InstructionError(0, InstructionError::Custom(code))
+    }
+
+    #[test]
+    fn test_free_balance_subtracts_open_locks() {
+        let mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let lock = |mint, amount, released_amount| TimeLock {
+            mint,
+            amount,
+            released_amount,
+            ..Default::default()
+        };
+        let vault = Vault {
+            token_balances: vec![TokenBalance {
+                mint,
+                balance: 1_000,
+                last_updated: 0,
+            }],
+            time_locks: vec![
+                lock(mint, 500, 200),
+                lock(other_mint, 400, 0),
+                // Migrated without a mint, so it may be claimed in this one too
+                lock(Pubkey::default(), 100, 0),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(vault.locked_for_timelocks(&mint), 400);
+        assert_eq!(vault.free_balance(&mint), 600);
+        assert_eq!(vault.free_balance(&other_mint), 0);
+    }
+
+    #[tokio::test]
+    async fn test_withdrawal_cannot_touch_locked_tokens() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let authority = context.payer.pubkey();
+        send(
+            &mut context,
+            &[
+                deposit_ix(&vault, &mint, &authority, 1_000),
+                create_time_lock_ix(&vault, &authority, &mint, 900),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let stats = &fetch_vault(&mut context, &vault).await.token_stats()[0];
+        assert_eq!(stats.balance, 1_000);
+        assert_eq!(stats.withdrawable, 100);
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &authority, 101)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, invalid_amount());
+        send(&mut context, &[withdraw_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap();
+
+        // Nothing is left over to back another lock either
+        let lock = create_time_lock_ix(&vault, &authority, &mint, 1);
+        let err = send(&mut context, &[lock], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, invalid_amount());
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 900);
+        assert_eq!(state.free_balance(&mint), 0);
+    }
+}
@@ -0,0 +1,245 @@
+mod common;
+
+#[cfg(test)]
+mod global_config_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{GlobalConfig, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 6;
+
+    fn settings(admin: Pubkey, max_fee_bps: u64, allowed_programs: Vec<Pubkey>) -> GlobalConfig {
+        GlobalConfig {
+            admin,
+            max_fee_bps,
+            allowed_programs,
+            protocol_fee_recipient: Pubkey::new_unique(),
+            ..GlobalConfig::default()
+        }
+    }
+
+    fn update_data(config: GlobalConfig) -> Vec<u8> {
+        VaultInstruction::UpdateGlobalConfig {
+            admin: config.admin,
+            max_fee_bps: config.max_fee_bps,
+            allowed_programs: config.allowed_programs,
+            protocol_fee_recipient: config.protocol_fee_recipient,
+            protocol_fee_bps: config.protocol_fee_bps,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    fn set_strategy_ix(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        strategy_program: Pubkey,
+        with_config: bool,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ];
+        if with_config {
+            accounts.push(AccountMeta::new_readonly(global_config_pda(), false));
+        }
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: VaultInstruction::SetYieldStrategy {
+                token_mint: Pubkey::new_unique(),
+                strategy_program,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    async fn fetch_config(context: &mut ProgramTestContext) -> GlobalConfig {
+        let account = context
+            .banks_client
+            .get_account(global_config_pda())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.owner, PROGRAM_ID);
+        assert_eq!(account.data.len(), GlobalConfig::SPACE);
+        fetch_global_config(context).await
+    }
+
+    // Admin vault with a 1-of-1 multisig, set as the admin of a fresh global config
+    async fn admin_vault(context: &mut ProgramTestContext, config: &GlobalConfig) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        deploy_program_data(context, Some(payer));
+        let config = GlobalConfig {
+            admin: vault,
+            ..config.clone()
+        };
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                initialize_global_config_ix(&payer, config),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_initialize_only_once() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let config = settings(Pubkey::new_unique(), 500, vec![MOCK_DEFI_ID]);
+        deploy_program_data(&mut context, Some(payer));
+        send(&mut context, &[initialize_global_config_ix(&payer, config.clone())], &[])
+            .await
+            .unwrap();
+
+        let stored = fetch_config(&mut context).await;
+        assert_eq!(stored.admin, config.admin);
+        assert_eq!(stored.max_fee_bps, 500);
+        assert_eq!(stored.allowed_programs, vec![MOCK_DEFI_ID]);
+        assert_eq!(stored.protocol_fee_recipient, config.protocol_fee_recipient);
+        assert_eq!(stored.bump, GlobalConfig::find_address(&PROGRAM_ID).1);
+
+        let takeover = settings(payer, 10_000, vec![]);
+        let err = send(&mut context, &[initialize_global_config_ix(&payer, takeover)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        assert_eq!(fetch_config(&mut context).await.admin, config.admin);
+    }
+
+    #[tokio::test]
+    async fn test_only_upgrade_authority_initializes() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let config = settings(Pubkey::new_unique(), 500, vec![]);
+
+        // Someone else deployed it, or nobody can upgrade it any more
+        for authority in [Some(Pubkey::new_unique()), None] {
+            deploy_program_data(&mut context, authority);
+            let init = initialize_global_config_ix(&payer, config.clone());
+            let err = send(&mut context, &[init], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::UpgradeAuthorityMismatch));
+            advance_clock(&mut context, 1).await;
+        }
+
+        // Nor does a lookalike ProgramData at another address count
+        let mut spoofed = initialize_global_config_ix(&payer, config.clone());
+        spoofed.accounts[5].pubkey = Pubkey::new_unique();
+        let err = send(&mut context, &[spoofed], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        deploy_program_data(&mut context, Some(payer));
+        send(&mut context, &[initialize_global_config_ix(&payer, config)], &[]).await.unwrap();
+        assert_eq!(fetch_config(&mut context).await.max_fee_bps, 500);
+    }
+
+    #[tokio::test]
+    async fn test_update_through_admin_multisig() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let vault = admin_vault(&mut context, &settings(payer, 500, vec![])).await;
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let updated = settings(vault, 200, vec![MOCK_DEFI_ID]);
+
+        // Signed, but not by the admin vault's multisig
+        let direct = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(global_config_pda(), false),
+                AccountMeta::new_readonly(vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: update_data(updated.clone()),
+        };
+        let err = send(&mut context, &[direct], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        let inner_accounts = vec![
+            TransactionAccount {
+                pubkey: global_config_pda(),
+                is_signer: false,
+                is_writable: true,
+            },
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: false },
+            TransactionAccount { pubkey: signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let remaining = vec![
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(global_config_pda(), false),
+        ];
+        send(
+            &mut context,
+            &[
+                create_multisig_tx_ix(
+                    &vault,
+                    &payer,
+                    PROGRAM_ID,
+                    inner_accounts,
+                    update_data(updated.clone()),
+                ),
+                execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let stored = fetch_config(&mut context).await;
+        assert_eq!(stored.max_fee_bps, 200);
+        assert_eq!(stored.allowed_programs, vec![MOCK_DEFI_ID]);
+        assert_eq!(stored.protocol_fee_recipient, updated.protocol_fee_recipient);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_programs_checked_against_registry() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let vault = admin_vault(&mut context, &settings(payer, 500, vec![MOCK_DEFI_ID])).await;
+        let unlisted = Pubkey::new_unique();
+
+        let err = send(&mut context, &[set_strategy_ix(&vault, &payer, unlisted, true)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ProgramNotAllowed));
+
+        send(&mut context, &[set_strategy_ix(&vault, &payer, MOCK_DEFI_ID, true)], &[])
+            .await
+            .unwrap();
+        // Left out, the registry isn't consulted
+        send(&mut context, &[set_strategy_ix(&vault, &payer, unlisted, false)], &[])
+            .await
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let programs: Vec<Pubkey> =
+            state.yield_strategies.iter().map(|s| s.strategy_program).collect();
+        assert_eq!(programs, vec![MOCK_DEFI_ID, unlisted]);
+    }
+}
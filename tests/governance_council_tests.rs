@@ -0,0 +1,334 @@
+mod common;
+
+#[cfg(test)]
+mod governance_council_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        governance::{
+            requires_council, COUNCIL_ALL_PROPOSALS, COUNCIL_SYSTEM_PROGRAM, COUNCIL_TOKEN_PROGRAM,
+        },
+        instruction::VaultInstruction,
+        state::{
+            CouncilReview, GovernanceConfig, GovernanceInstruction, ParamKey, TransactionAccount,
+            VoteLock, VoteType,
+        },
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 7;
+    const VOTING_PERIOD: i64 = 3_600;
+    const HELD: u64 = 1_000;
+
+    fn vault_ix(
+        vault: &Pubkey,
+        signer: &Pubkey,
+        extra: Option<Pubkey>,
+        data: VaultInstruction,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*signer, true),
+        ];
+        accounts.extend(extra.map(|key| AccountMeta::new_readonly(key, false)));
+        accounts.push(AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: data.try_to_vec().unwrap(),
+        }
+    }
+
+    // Governed vault whose multisig (the payer alone) is the council for `categories`. The
+    // payer holds HELD voting tokens and has as many locked since before any proposal, enough
+    // to propose and pass anything on their own.
+    async fn bicameral_vault(
+        context: &mut ProgramTestContext,
+        categories: u64,
+    ) -> (Pubkey, Pubkey) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let voting_mint = create_mint(context, 6).await.pubkey();
+        let voting_tokens = create_funded_ata(context, &voting_mint, &payer, HELD).await;
+        send(context, &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)], &[])
+            .await
+            .unwrap();
+
+        let mut state = fetch_vault(context, &vault).await;
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: voting_mint,
+            proposal_threshold: HELD,
+            voting_period: VOTING_PERIOD,
+            quorum_threshold: 5_000,
+            execution_threshold: 5_000,
+            ..Default::default()
+        });
+        state.set_param(ParamKey::CouncilCategories, categories);
+        state.vote_locks.push(VoteLock {
+            voter: payer,
+            amount: HELD,
+            locked_at: 0,
+            unlock_at: 0,
+        });
+        store_vault(context, &vault, &state).await;
+        (vault, voting_tokens)
+    }
+
+    // Proposes `instructions`, votes them through and queues them; returns the proposal id
+    async fn pass_token_vote(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        voting_tokens: &Pubkey,
+        instructions: Vec<Vec<u8>>,
+    ) -> u64 {
+        let payer = context.payer.pubkey();
+        let proposal_id = fetch_vault(context, vault).await.next_governance_proposal_id;
+        let propose = VaultInstruction::CreateGovernanceProposal {
+            title: "Council test".to_string(),
+            description: String::new(),
+            instructions,
+        };
+        let vote = VaultInstruction::CastVote {
+            proposal_id,
+            vote_type: VoteType::For,
+        };
+        send(
+            context,
+            &[
+                vault_ix(vault, &payer, Some(*voting_tokens), propose),
+                vault_ix(vault, &payer, None, vote),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        advance_clock(context, VOTING_PERIOD).await;
+        let queue = VaultInstruction::QueueProposal { proposal_id };
+        send(context, &[vault_ix(vault, &payer, None, queue)], &[]).await.unwrap();
+        proposal_id
+    }
+
+    // The caller is writable and signs, so proposals may have it pay for what they do
+    fn execute_ix(
+        vault: &Pubkey,
+        caller: &Pubkey,
+        proposal_id: u64,
+        remaining: Vec<AccountMeta>,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*caller, true),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        accounts.extend(remaining);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: VaultInstruction::ExecuteGovernanceProposal { proposal_id }
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+
+    fn approve_accounts(vault: &Pubkey) -> Vec<TransactionAccount> {
+        vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ]
+    }
+
+    // The council's multisig transaction `transaction_id` approving `proposal_id`, proposed
+    // and executed in one go by the payer, the only owner
+    fn council_approve_ixs(
+        vault: &Pubkey,
+        payer: &Pubkey,
+        transaction_id: u64,
+        proposal_id: u64,
+    ) -> [Instruction; 2] {
+        let data = VaultInstruction::CouncilApproveProposal { proposal_id }.try_to_vec().unwrap();
+        [
+            create_multisig_tx_ix(vault, payer, PROGRAM_ID, approve_accounts(vault), data),
+            execute_multisig_tx_ix(
+                vault,
+                &multisig_signer_pda(vault, NONCE),
+                payer,
+                transaction_id,
+                vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+            ),
+        ]
+    }
+
+    fn transfer_instruction(from: &Pubkey, to: &Pubkey, lamports: u64) -> Vec<u8> {
+        let transfer = system_instruction::transfer(from, to, lamports);
+        GovernanceInstruction {
+            program_id: transfer.program_id,
+            accounts: transfer
+                .accounts
+                .iter()
+                .map(|meta| TransactionAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect(),
+            data: transfer.data,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_categories_match_target_programs() {
+        let token_call = GovernanceInstruction {
+            program_id: spl_token::id(),
+            ..Default::default()
+        };
+        let system_call = GovernanceInstruction {
+            program_id: system_program::id(),
+            ..Default::default()
+        };
+        assert!(requires_council(COUNCIL_TOKEN_PROGRAM, &[system_call.clone(), token_call]));
+        assert!(!requires_council(COUNCIL_TOKEN_PROGRAM, &[system_call.clone()]));
+        assert!(!requires_council(COUNCIL_TOKEN_PROGRAM, &[]));
+        assert!(requires_council(COUNCIL_ALL_PROPOSALS, &[]));
+        assert!(!requires_council(0, &[system_call]));
+    }
+
+    #[tokio::test]
+    async fn test_passed_proposal_waits_for_council() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = bicameral_vault(&mut context, COUNCIL_SYSTEM_PROGRAM).await;
+        let payer = context.payer.pubkey();
+        let recipient = Pubkey::new_unique();
+        let transfer = transfer_instruction(&payer, &recipient, 1_000_000);
+
+        let proposal_id =
+            pass_token_vote(&mut context, &vault, &voting_tokens, vec![transfer]).await;
+        assert!(fetch_vault(&mut context, &vault).await.requires_council(proposal_id));
+
+        // Token vote passed and queued, but the council hasn't ratified it
+        let remaining = || {
+            vec![
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new(recipient, false),
+            ]
+        };
+        let err = send(&mut context, &[execute_ix(&vault, &payer, proposal_id, remaining())], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::CouncilApprovalRequired));
+
+        send(&mut context, &council_approve_ixs(&vault, &payer, 0, proposal_id), &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.council_review(proposal_id).unwrap().approved_at.is_some());
+
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[execute_ix(&vault, &payer, proposal_id, remaining())], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.governance_proposals[0].executed);
+        let received = context.banks_client.get_balance(recipient).await.unwrap();
+        assert_eq!(received, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_other_categories_skip_council() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = bicameral_vault(&mut context, COUNCIL_TOKEN_PROGRAM).await;
+        let payer = context.payer.pubkey();
+
+        let proposal_id = pass_token_vote(&mut context, &vault, &voting_tokens, vec![]).await;
+        assert!(!fetch_vault(&mut context, &vault).await.requires_council(proposal_id));
+
+        // The council can't approve what isn't before it
+        let err = send(&mut context, &council_approve_ixs(&vault, &payer, 0, proposal_id), &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(1, VaultError::InvalidInstruction));
+
+        send(&mut context, &[execute_ix(&vault, &payer, proposal_id, vec![])], &[])
+            .await
+            .unwrap();
+        assert!(fetch_vault(&mut context, &vault).await.governance_proposals[0].executed);
+    }
+
+    #[tokio::test]
+    async fn test_execute_takes_authority_or_council() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = bicameral_vault(&mut context, COUNCIL_TOKEN_PROGRAM).await;
+        let proposal_id = pass_token_vote(&mut context, &vault, &voting_tokens, vec![]).await;
+
+        let stranger = Keypair::new();
+        let execute = execute_ix(&vault, &stranger.pubkey(), proposal_id, vec![]);
+        let err = send(&mut context, &[execute], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        // A proposal stored with a program off the list still can't call it
+        let mut state = fetch_vault(&mut context, &vault).await;
+        let foreign = Pubkey::new_unique();
+        state.governance_proposals[0].instructions = vec![GovernanceInstruction {
+            program_id: foreign,
+            ..Default::default()
+        }
+        .try_to_vec()
+        .unwrap()];
+        store_vault(&mut context, &vault, &state).await;
+        let payer = context.payer.pubkey();
+        let remaining = vec![AccountMeta::new_readonly(foreign, false)];
+        let execute = execute_ix(&vault, &payer, proposal_id, remaining);
+        let err = send(&mut context, &[execute], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::GovernanceProgramNotAllowed));
+    }
+
+    #[tokio::test]
+    async fn test_pending_council_reviews_capped() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = bicameral_vault(&mut context, COUNCIL_ALL_PROPOSALS).await;
+        let payer = context.payer.pubkey();
+        let propose = |n: usize| {
+            let propose = VaultInstruction::CreateGovernanceProposal {
+                title: format!("Council review {}", n),
+                description: String::new(),
+                instructions: vec![],
+            };
+            vault_ix(&vault, &payer, Some(voting_tokens), propose)
+        };
+        for n in 0..CouncilReview::MAX_PENDING {
+            send(&mut context, &[propose(n)], &[]).await.unwrap();
+        }
+        let over = propose(CouncilReview::MAX_PENDING);
+        let err = send(&mut context, &[over], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ProposalRateLimited));
+
+        // A cancelled proposal frees its place
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.governance_proposals[0].cancelled = true;
+        store_vault(&mut context, &vault, &state).await;
+        send(&mut context, &[propose(CouncilReview::MAX_PENDING + 1)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.council_reviews.len(), CouncilReview::MAX_PENDING);
+        assert!(!state.requires_council(0));
+    }
+}
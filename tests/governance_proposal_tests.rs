@@ -0,0 +1,304 @@
+mod common;
+
+#[cfg(test)]
+mod governance_proposal_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_program,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{GovernanceConfig, GovernanceInstruction, GovernanceProposal, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const THRESHOLD: u64 = 1_000;
+
+    // Vault whose governance votes with a fresh mint; the payer holds `held` of it
+    async fn governed_vault(context: &mut ProgramTestContext, held: u64) -> (Pubkey, Pubkey) {
+        let vault = create_vault(context).await;
+        let voting_mint = create_mint(context, 6).await.pubkey();
+        let payer = context.payer.pubkey();
+        let voting_tokens = create_funded_ata(context, &voting_mint, &payer, held).await;
+
+        let mut state = fetch_vault(context, &vault).await;
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: voting_mint,
+            proposal_threshold: THRESHOLD,
+            voting_period: 3_600,
+            ..Default::default()
+        });
+        store_vault(context, &vault, &state).await;
+        (vault, voting_tokens)
+    }
+
+    fn propose_ix(
+        vault: &Pubkey,
+        proposer: &Pubkey,
+        voting_tokens: &Pubkey,
+        instructions: Vec<Vec<u8>>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*proposer, true),
+                AccountMeta::new_readonly(*voting_tokens, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateGovernanceProposal {
+                title: "t".to_string(),
+                description: String::new(),
+                instructions,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // A proposal without instructions, for checking the title and description
+    fn propose_text_ix(
+        vault: &Pubkey,
+        proposer: &Pubkey,
+        voting_tokens: &Pubkey,
+        title: &str,
+        description: &str,
+    ) -> Instruction {
+        let mut propose = propose_ix(vault, proposer, voting_tokens, vec![]);
+        propose.data = VaultInstruction::CreateGovernanceProposal {
+            title: title.to_string(),
+            description: description.to_string(),
+            instructions: vec![],
+        }
+        .try_to_vec()
+        .unwrap();
+        propose
+    }
+
+    fn encoded(program_id: Pubkey, data_len: usize) -> Vec<u8> {
+        GovernanceInstruction {
+            program_id,
+            accounts: vec![],
+            data: vec![7; data_len],
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_oversized_instruction_rejected_by_index() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = governed_vault(&mut context, THRESHOLD).await;
+        let payer = context.payer.pubkey();
+
+        let instructions = vec![
+            encoded(PROGRAM_ID, 8),
+            encoded(PROGRAM_ID, GovernanceInstruction::MAX_ENCODED_LEN),
+        ];
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[propose_ix(&vault, &payer, &voting_tokens, instructions)],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert_eq!(
+            processed.result.unwrap_err(),
+            vault_error(0, VaultError::GovernanceInstructionTooLarge)
+        );
+        let logs = processed.metadata.unwrap().log_messages;
+        assert!(logs
+            .iter()
+            .any(|line| line.contains("Governance instruction 1 is")));
+        assert!(fetch_vault(&mut context, &vault).await.governance_proposals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_malformed_payloads_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = governed_vault(&mut context, THRESHOLD).await;
+        let payer = context.payer.pubkey();
+
+        let cases = [
+            (vec![vec![1, 2, 3]], VaultError::InvalidGovernanceInstruction),
+            (
+                vec![encoded(Pubkey::new_unique(), 0)],
+                VaultError::GovernanceProgramNotAllowed,
+            ),
+            (
+                vec![encoded(PROGRAM_ID, 0); GovernanceInstruction::MAX_INSTRUCTIONS + 1],
+                VaultError::GovernanceProposalTooLarge,
+            ),
+        ];
+        for (instructions, expected) in cases {
+            let propose = propose_ix(&vault, &payer, &voting_tokens, instructions);
+            let err = send(&mut context, &[propose], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, expected));
+        }
+
+        let many_accounts = GovernanceInstruction {
+            program_id: system_program::id(),
+            accounts: vec![
+                TransactionAccount {
+                    pubkey: payer,
+                    is_signer: false,
+                    is_writable: false,
+                };
+                GovernanceInstruction::MAX_ACCOUNTS + 1
+            ],
+            data: vec![],
+        };
+        let instructions = vec![many_accounts.try_to_vec().unwrap()];
+        let propose = propose_ix(&vault, &payer, &voting_tokens, instructions);
+        let err = send(&mut context, &[propose], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::GovernanceInstructionTooLarge));
+    }
+
+    #[tokio::test]
+    async fn test_valid_proposal_stored() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = governed_vault(&mut context, THRESHOLD).await;
+        let payer = context.payer.pubkey();
+
+        let instructions = vec![encoded(PROGRAM_ID, 8), encoded(spl_token::id(), 0)];
+        let propose = propose_ix(&vault, &payer, &voting_tokens, instructions.clone());
+        send(&mut context, &[propose], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let proposal = &state.governance_proposals[0];
+        assert_eq!((proposal.id, proposal.proposer), (0, payer));
+        assert_eq!(proposal.instructions, instructions);
+        assert_eq!(proposal.end_time, proposal.start_time + 3_600);
+        assert_eq!(state.next_governance_proposal_id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_proposal_threshold_enforced() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = governed_vault(&mut context, THRESHOLD - 1).await;
+        let payer = context.payer.pubkey();
+
+        let propose = propose_ix(&vault, &payer, &voting_tokens, vec![encoded(PROGRAM_ID, 0)]);
+        let err = send(&mut context, &[propose], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+    }
+
+    #[test]
+    fn test_text_limits_at_the_caps() {
+        let title = "t".repeat(GovernanceProposal::MAX_TITLE_LEN);
+        let description = "d".repeat(GovernanceProposal::MAX_DESCRIPTION_LEN);
+        assert_eq!(GovernanceProposal::validate_text(&title, &description), Ok(()));
+
+        // The caps are bytes: 41 two-byte characters are 82 of them
+        let too_long = [
+            (format!("{}t", title), description.clone()),
+            ("\u{e9}".repeat(41), String::new()),
+            (title.clone(), format!("{}d", description)),
+        ];
+        for (title, description) in too_long {
+            assert_eq!(
+                GovernanceProposal::validate_text(&title, &description),
+                Err(VaultError::GovernanceProposalTooLarge)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_control_characters_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, voting_tokens) = governed_vault(&mut context, THRESHOLD).await;
+        let payer = context.payer.pubkey();
+
+        let too_long = "t".repeat(GovernanceProposal::MAX_TITLE_LEN + 1);
+        let cases = [
+            ("\x1b[2J clear screen", "", VaultError::InvalidProposalText),
+            ("tabbed", "one\ttwo", VaultError::InvalidProposalText),
+            ("delete", "\x7f", VaultError::InvalidProposalText),
+            ("\u{9b}2J single-byte CSI", "", VaultError::InvalidProposalText),
+            (too_long.as_str(), "", VaultError::GovernanceProposalTooLarge),
+        ];
+        for (title, description, expected) in cases {
+            let propose = propose_text_ix(&vault, &payer, &voting_tokens, title, description);
+            let err = send(&mut context, &[propose], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, expected));
+        }
+        assert!(fetch_vault(&mut context, &vault).await.governance_proposals.is_empty());
+
+        // Newlines are fine in the description, and a title can use the whole cap
+        let title = "t".repeat(GovernanceProposal::MAX_TITLE_LEN);
+        let propose = propose_text_ix(&vault, &payer, &voting_tokens, &title, "first\nsecond");
+        send(&mut context, &[propose], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.governance_proposals[0].title, title);
+        assert_eq!(state.governance_proposals[0].description, "first\nsecond");
+    }
+
+    fn initialize_governance_ix(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        voting_token_mint: Pubkey,
+        voting_period: i64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::InitializeGovernance {
+                voting_token_mint,
+                quorum_threshold: 2_000,
+                proposal_threshold: THRESHOLD,
+                voting_period,
+                time_lock_delay: 60,
+                execution_threshold: 5_000,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_governance() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let voting_mint = Pubkey::new_unique();
+
+        let stranger = Keypair::new();
+        let initialize = initialize_governance_ix(&vault, &stranger.pubkey(), voting_mint, 3_600);
+        let err = send(&mut context, &[initialize], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        let initialize = initialize_governance_ix(&vault, &payer, voting_mint, 0);
+        let err = send(&mut context, &[initialize], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+
+        let initialize = initialize_governance_ix(&vault, &payer, voting_mint, 3_600);
+        send(&mut context, &[initialize], &[]).await.unwrap();
+        let config = fetch_vault(&mut context, &vault).await.governance_config.unwrap();
+        assert_eq!(config.voting_token_mint, voting_mint);
+        assert_eq!((config.quorum_threshold, config.execution_threshold), (2_000, 5_000));
+        assert_eq!((config.time_lock_delay, config.timelock_delay), (60, 60));
+
+        // A second initialization would wipe the live config; updates have their own instruction
+        advance_clock(&mut context, 1).await;
+        let initialize = initialize_governance_ix(&vault, &payer, Pubkey::new_unique(), 3_600);
+        let err = send(&mut context, &[initialize], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+    }
+}
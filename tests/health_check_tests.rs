@@ -0,0 +1,126 @@
+mod common;
+
+#[cfg(test)]
+mod health_check_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{instruction::VaultInstruction, state::Vault, VaultError, PROGRAM_ID};
+
+    fn health_check_ix(vault: &Pubkey, token_accounts: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ];
+        accounts.extend(token_accounts.iter().map(|key| AccountMeta::new_readonly(*key, false)));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: VaultInstruction::EmitHealthCheck.try_to_vec().unwrap(),
+        }
+    }
+
+    // The heartbeat event logged by `instruction`
+    async fn heartbeat(
+        context: &mut ProgramTestContext,
+        instruction: Instruction,
+    ) -> serde_json::Value {
+        let payer = context.payer.pubkey();
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert!(processed.result.is_ok(), "{:?}", processed.result);
+        processed
+            .metadata
+            .unwrap()
+            .log_messages
+            .iter()
+            .find_map(|line| line.strip_prefix("Program log: EVENT: "))
+            .map(|json| serde_json::from_str(json).unwrap())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_reports_drift_and_is_rate_limited() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 600)], &[]).await.unwrap();
+
+        // 50 minted straight into the vault's ATA, which the vault never recorded
+        let vault_tokens = get_associated_token_address(&vault, &mint);
+        let stray =
+            spl_token::instruction::mint_to(&spl_token::id(), &mint, &vault_tokens, &payer, &[], 50)
+                .unwrap();
+        send(&mut context, &[stray], &[]).await.unwrap();
+
+        let event = heartbeat(&mut context, health_check_ix(&vault, &[vault_tokens])).await;
+        assert_eq!(event["base"]["event_type"], "health_check");
+        assert_eq!(event["total_value_locked"], "600");
+        assert_eq!(event["paused"], false);
+        assert_eq!(event["pending_multisig_transactions"], 0);
+        assert_eq!(event["active_time_locks"], 0);
+        let balances = event["balances"].as_array().unwrap();
+        assert_eq!(balances.len(), 1);
+        assert_eq!(balances[0]["tracked"], 600);
+        assert_eq!(balances[0]["drift"], 50);
+
+        let slot = event["base"]["slot"].as_u64().unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.last_heartbeat_slot, slot);
+
+        // A different transaction, still within the interval
+        let err = send(&mut context, &[health_check_ix(&vault, &[])], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::HealthCheckTooSoon as u32)
+            )
+        );
+
+        context.warp_to_slot(slot + Vault::HEALTH_CHECK_INTERVAL_SLOTS).unwrap();
+        let event = heartbeat(&mut context, health_check_ix(&vault, &[])).await;
+        assert!(event["balances"][0]["drift"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_foreign_token_account_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer_tokens = get_associated_token_address(&context.payer.pubkey(), &mint);
+
+        let err = send(&mut context, &[health_check_ix(&vault, &[payer_tokens])], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::VaultTokenAccountOwnerMismatch as u32)
+            )
+        );
+    }
+}
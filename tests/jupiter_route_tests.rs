@@ -0,0 +1,218 @@
+mod common;
+
+#[cfg(test)]
+mod jupiter_route_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        jupiter::{self, MAX_ROUTE_LEN, ROUTE_DISCRIMINATORS},
+        VaultError, PROGRAM_ID,
+    };
+
+    const ROUTE: [u8; 8] = [229, 23, 203, 151, 122, 227, 173, 42];
+    // mock_defi stands in for Jupiter and pays 97% of what it takes
+    const SWAP_RATE_BPS: u16 = 9_700;
+
+    fn route_data(take: u64) -> Vec<u8> {
+        mock_defi::swap_data(take)
+    }
+
+    struct Swap {
+        vault: Pubkey,
+        input: Pubkey,
+        output: Pubkey,
+    }
+
+    fn route_ix(swap: &Swap, authority: &Pubkey, amount: u64, route: Vec<u8>) -> Instruction {
+        swap_ix(swap, authority, amount, false, route)
+    }
+
+    fn swap_ix(
+        swap: &Swap,
+        authority: &Pubkey,
+        amount: u64,
+        auto_add: bool,
+        route: Vec<u8>,
+    ) -> Instruction {
+        let vault_in = get_associated_token_address(&swap.vault, &swap.input);
+        let vault_out = get_associated_token_address(&swap.vault, &swap.output);
+        let mut accounts = vec![
+            AccountMeta::new(swap.vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(vault_in, false),
+            AccountMeta::new(vault_out, false),
+            AccountMeta::new_readonly(jupiter::PROGRAM_ID, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        if auto_add {
+            accounts.extend([
+                AccountMeta::new_readonly(swap.output, false),
+                AccountMeta::new(*authority, true),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            ]);
+        }
+        // Forwarded to the router
+        accounts.extend(mock_defi::swap_accounts(
+            &jupiter::PROGRAM_ID,
+            &swap.vault,
+            &swap.input,
+            &swap.output,
+        ));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: VaultInstruction::JupiterRoute {
+                input_mint: swap.input,
+                output_mint: swap.output,
+                amount,
+                auto_add,
+                route,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // Vault holding 1_000 of the input mint, both mints supported, and a pool with 5_000 of
+    // the output mint to pay from
+    async fn setup() -> (ProgramTestContext, Swap) {
+        setup_with(true).await
+    }
+
+    async fn setup_with(output_supported: bool) -> (ProgramTestContext, Swap) {
+        let mut test = program_test();
+        let config = MockDefiConfig {
+            swap_rate_bps: SWAP_RATE_BPS,
+            ..MockDefiConfig::default()
+        };
+        add_mock_defi(&mut test, jupiter::PROGRAM_ID, config);
+        let mut context = test.start_with_context().await;
+        let (vault, input) = vault_with_token(&mut context, 1_000).await;
+        let output = create_mint(&mut context, 6).await.pubkey();
+        let authority = context.payer.pubkey();
+        let mut ixs = vec![deposit_ix(&vault, &input, &authority, 1_000)];
+        if output_supported {
+            ixs.push(add_supported_token_ix(&vault, &output, &authority, &authority));
+        }
+        send(&mut context, &ixs, &[]).await.unwrap();
+        fund_mock_pool(&mut context, &jupiter::PROGRAM_ID, &input, 0).await;
+        fund_mock_pool(&mut context, &jupiter::PROGRAM_ID, &output, 5_000).await;
+        (context, Swap { vault, input, output })
+    }
+
+    #[test]
+    fn test_route_validation() {
+        for (name, discriminator) in ROUTE_DISCRIMINATORS {
+            let mut route = discriminator.to_vec();
+            route.extend_from_slice(&[0; 16]);
+            assert_eq!(jupiter::validate_route(&route), Ok(*name));
+        }
+        assert_eq!(jupiter::validate_route(&ROUTE[..7]), Err(VaultError::UnknownSwapInstruction));
+        assert_eq!(jupiter::validate_route(&[0; 24]), Err(VaultError::UnknownSwapInstruction));
+
+        let mut oversized = ROUTE.to_vec();
+        oversized.resize(MAX_ROUTE_LEN + 1, 0);
+        assert_eq!(jupiter::validate_route(&oversized), Err(VaultError::SwapRouteTooLarge));
+        oversized.truncate(MAX_ROUTE_LEN);
+        assert_eq!(jupiter::validate_route(&oversized), Ok("route"));
+    }
+
+    #[tokio::test]
+    async fn test_swap_recorded_with_measured_amounts() {
+        let (mut context, swap) = setup().await;
+        let authority = context.payer.pubkey();
+
+        // Allowed up to 400; the route takes 300 and pays out 291
+        let ix = route_ix(&swap, &authority, 400, route_data(300));
+        send(&mut context, &[ix], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &swap.vault).await;
+        assert_eq!(state.tracked_balance(&swap.input), 700);
+        assert_eq!(state.tracked_balance(&swap.output), 291);
+        assert_eq!(state.swap_history.len(), 1);
+        let record = &state.swap_history[0];
+        assert_eq!(record.input_mint, swap.input);
+        assert_eq!(record.output_mint, swap.output);
+        assert_eq!(record.amount_in_measured, 300);
+        assert_eq!(record.amount_out_measured, 291);
+        assert_eq!(record.executor, authority);
+        assert!(record.timestamp > 0);
+    }
+
+    #[tokio::test]
+    async fn test_route_spending_over_amount_rejected() {
+        let (mut context, swap) = setup().await;
+        let authority = context.payer.pubkey();
+        let ix = route_ix(&swap, &authority, 400, route_data(401));
+        let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+    }
+
+    #[tokio::test]
+    async fn test_bad_routes_rejected() {
+        let (mut context, swap) = setup().await;
+        let authority = context.payer.pubkey();
+
+        let mut oversized = route_data(1);
+        oversized.resize(MAX_ROUTE_LEN + 1, 0);
+        let mut ix = route_ix(&swap, &authority, 1, oversized);
+        ix.accounts.truncate(6);
+        let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::SwapRouteTooLarge));
+
+        let mut unknown = route_data(1);
+        unknown[0] ^= 0xff;
+        let ix = route_ix(&swap, &authority, 1, unknown);
+        let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnknownSwapInstruction));
+
+        assert!(fetch_vault(&mut context, &swap.vault).await.swap_history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_output_mint_rejected() {
+        let (mut context, swap) = setup_with(false).await;
+        let authority = context.payer.pubkey();
+        let ix = route_ix(&swap, &authority, 300, route_data(300));
+        let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        let state = fetch_vault(&mut context, &swap.vault).await;
+        assert_eq!(state.tracked_balance(&swap.input), 1_000);
+        assert!(state.swap_history.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_auto_add_registers_output_mint() {
+        let (mut context, swap) = setup_with(false).await;
+        let authority = context.payer.pubkey();
+        let ix = swap_ix(&swap, &authority, 300, true, route_data(300));
+        send(&mut context, &[ix], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &swap.vault).await;
+        let token = state.supported_tokens.iter().find(|t| t.mint == swap.output).unwrap();
+        assert!(token.is_active);
+        assert_eq!(token.decimals, 6);
+        assert_eq!(state.tracked_balance(&swap.input), 700);
+        assert_eq!(state.tracked_balance(&swap.output), 291);
+        let vault_out = get_associated_token_address(&swap.vault, &swap.output);
+        assert_eq!(token_balance(&mut context, &vault_out).await, 291);
+
+        // Already registered now, so auto_add just swaps
+        let ix = swap_ix(&swap, &authority, 100, true, route_data(100));
+        send(&mut context, &[ix], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &swap.vault).await;
+        assert_eq!(state.supported_tokens.iter().filter(|t| t.mint == swap.output).count(), 1);
+        assert_eq!(state.tracked_balance(&swap.output), 388);
+    }
+}
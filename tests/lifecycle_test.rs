@@ -0,0 +1,374 @@
+mod common;
+
+// One treasury taken through every feature in the order a deployment would use them. Each
+// stage checks token balances, the events it logged and that the vault's books still match
+// its token accounts, so account-order drift between features shows up here first.
+#[cfg(test)]
+mod lifecycle_test {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use serde_json::Value;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::{BanksClientError, ProgramTestContext};
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{GlobalConfig, ParamKey, TransactionAccount, VestingSchedule},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 8;
+
+    // Sends the instructions and returns the events they logged, inner instructions included
+    async fn run(
+        context: &mut ProgramTestContext,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Vec<Value>, BanksClientError> {
+        let mut all_signers = vec![&context.payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        if let Err(err) = processed.result {
+            return Err(BanksClientError::TransactionError(err));
+        }
+        let logs = processed.metadata.map(|m| m.log_messages).unwrap_or_default();
+        Ok(logs
+            .iter()
+            .filter_map(|line| line.strip_prefix("Program log: EVENT: "))
+            .map(|json| serde_json::from_str(json).unwrap())
+            .collect())
+    }
+
+    fn event_types(events: &[Value]) -> Vec<&str> {
+        events
+            .iter()
+            .map(|event| event["base"]["event_type"].as_str().unwrap())
+            .collect()
+    }
+
+    fn vault_ix(vault: &Pubkey, signer: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    fn claim_time_lock_ix(vault: &Pubkey, mint: &Pubkey, beneficiary: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(get_associated_token_address(vault, mint), false),
+                AccountMeta::new(get_associated_token_address(beneficiary, mint), false),
+                AccountMeta::new_readonly(*beneficiary, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::ClaimTimeLockV2 { time_lock_id: 0 }
+                .try_to_vec()
+                .unwrap(),
+        }
+    }
+
+    fn stored_accounts(instruction: &Instruction) -> Vec<TransactionAccount> {
+        instruction
+            .accounts
+            .iter()
+            .map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect()
+    }
+
+    // Tracked balances match the vault's token accounts and add up to the TVL
+    async fn assert_books(context: &mut ProgramTestContext, vault: &Pubkey, mints: &[Pubkey]) {
+        let state = fetch_vault(context, vault).await;
+        let mut tracked_total = 0;
+        for mint in mints {
+            let held = token_balance(context, &get_associated_token_address(vault, mint)).await;
+            let tracked = state.tracked_balance(mint);
+            assert_eq!(tracked + state.accrued_fee(mint), held, "books for {}", mint);
+            tracked_total += tracked;
+        }
+        assert_eq!(state.total_value_locked, u128::from(tracked_total));
+    }
+
+    #[tokio::test]
+    async fn test_treasury_lifecycle() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+
+        // Vault with two supported tokens
+        let vault = create_vault(&mut context).await;
+        let usdc = create_mint(&mut context, 6).await.pubkey();
+        let gov = create_mint(&mut context, 9).await.pubkey();
+        let events = run(
+            &mut context,
+            &[
+                add_supported_token_ix(&vault, &usdc, &payer, &payer),
+                add_supported_token_ix(&vault, &gov, &payer, &payer),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(event_types(&events), ["token_added", "token_added"]);
+        let mints = [usdc, gov];
+        let state = fetch_vault(&mut context, &vault).await;
+        let supported: Vec<Pubkey> = state.supported_tokens.iter().map(|t| t.mint).collect();
+        assert_eq!(supported, mints);
+
+        // Deposits from two users
+        let alice = context.payer.pubkey();
+        let bob = Keypair::new();
+        create_funded_ata(&mut context, &usdc, &alice, 10_000).await;
+        create_funded_ata(&mut context, &gov, &alice, 5_000).await;
+        create_funded_ata(&mut context, &usdc, &bob.pubkey(), 8_000).await;
+        let events = run(
+            &mut context,
+            &[
+                deposit_ix(&vault, &usdc, &alice, 4_000),
+                deposit_ix(&vault, &gov, &alice, 2_000),
+                deposit_ix(&vault, &usdc, &bob.pubkey(), 3_000),
+            ],
+            &[&bob],
+        )
+        .await
+        .unwrap();
+        assert_eq!(event_types(&events), ["token_deposited"; 3]);
+        let amounts: Vec<&Value> = events.iter().map(|event| &event["amount"]).collect();
+        assert_eq!(amounts, [4_000, 2_000, 3_000]);
+        let bob_usdc = get_associated_token_address(&bob.pubkey(), &usdc);
+        assert_eq!(token_balance(&mut context, &bob_usdc).await, 5_000);
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&usdc), 7_000);
+        assert_books(&mut context, &vault, &mints).await;
+
+        // 2-of-3 multisig
+        let (owner2, owner3) = (Keypair::new(), Keypair::new());
+        let owners = vec![payer, owner2.pubkey(), owner3.pubkey()];
+        let events = run(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, owners.clone(), 2, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(event_types(&events), ["multisig_initialized"]);
+        let multi_sig = fetch_vault(&mut context, &vault).await.multi_sig.unwrap();
+        assert_eq!((multi_sig.owners, multi_sig.threshold), (owners, 2));
+
+        // Token transfer out of the multisig signer's own account
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let signer_usdc = create_funded_ata(&mut context, &usdc, &signer, 1_500).await;
+        let payee = Pubkey::new_unique();
+        let payee_usdc = create_funded_ata(&mut context, &usdc, &payee, 0).await;
+        let transfer = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &signer_usdc,
+            &payee_usdc,
+            &signer,
+            &[],
+            1_000,
+        )
+        .unwrap();
+        let remaining = vec![
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(signer_usdc, false),
+            AccountMeta::new(payee_usdc, false),
+        ];
+        let propose = create_multisig_tx_ix(
+            &vault,
+            &payer,
+            spl_token::id(),
+            stored_accounts(&transfer),
+            transfer.data.clone(),
+        );
+        let events = run(&mut context, &[propose], &[]).await.unwrap();
+        assert_eq!(event_types(&events), ["multisig_transaction_created"]);
+        let early = execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining.clone());
+        let err = run(&mut context, &[early], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::NotEnoughSigners));
+        let events = run(
+            &mut context,
+            &[
+                approve_multisig_tx_ix(&vault, &owner2.pubkey(), 0),
+                execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining),
+            ],
+            &[&owner2],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            event_types(&events),
+            ["multisig_transaction_approved", "multisig_transaction_executed"]
+        );
+        assert_eq!(token_balance(&mut context, &payee_usdc).await, 1_000);
+        assert_eq!(token_balance(&mut context, &signer_usdc).await, 500);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multisig_transaction(0).unwrap().did_execute);
+        assert_books(&mut context, &vault, &mints).await;
+
+        // Time lock, claimed in full once it has vested
+        let beneficiary = Keypair::new();
+        let beneficiary_gov =
+            create_funded_ata(&mut context, &gov, &beneficiary.pubkey(), 0).await;
+        let create_lock = vault_ix(
+            &vault,
+            &payer,
+            VaultInstruction::CreateTimeLock {
+                beneficiary: beneficiary.pubkey(),
+                mint: gov,
+                amount: 1_200,
+                duration: 100,
+                cliff_duration: None,
+                schedule: VestingSchedule::Linear,
+                revocable: false,
+            },
+        );
+        let events = run(&mut context, &[create_lock], &[]).await.unwrap();
+        assert_eq!(event_types(&events), ["time_lock_created"]);
+        advance_clock(&mut context, 150).await;
+        let claim = claim_time_lock_ix(&vault, &gov, &beneficiary.pubkey());
+        let events = run(&mut context, &[claim], &[&beneficiary]).await.unwrap();
+        assert_eq!(event_types(&events), ["time_lock_claimed"]);
+        assert_eq!(token_balance(&mut context, &beneficiary_gov).await, 1_200);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.time_locks[0].released_amount, 1_200);
+        assert_eq!(state.tracked_balance(&gov), 800);
+        assert_books(&mut context, &vault, &mints).await;
+
+        // Pausing blocks deposits and withdrawals until the vault is unpaused
+        let pause = vault_ix(&vault, &payer, VaultInstruction::PauseVault);
+        let events = run(&mut context, &[pause], &[]).await.unwrap();
+        assert_eq!(event_types(&events), ["vault_paused"]);
+        for blocked in [
+            deposit_ix(&vault, &usdc, &bob.pubkey(), 500),
+            withdraw_ix(&vault, &usdc, &bob.pubkey(), 500),
+        ] {
+            let err = run(&mut context, &[blocked], &[&bob]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+        }
+        let unpause = vault_ix(&vault, &payer, VaultInstruction::UnpauseVault);
+        let events = run(&mut context, &[unpause], &[]).await.unwrap();
+        assert_eq!(event_types(&events), ["vault_unpaused"]);
+        assert!(!fetch_vault(&mut context, &vault).await.paused);
+
+        // A 1% deposit fee, set through a multisig proposal
+        let set_fee = VaultInstruction::SetParam {
+            key: ParamKey::DepositFeeBps,
+            value: 100,
+        };
+        let set_fee_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let events = run(
+            &mut context,
+            &[
+                create_multisig_tx_ix(
+                    &vault,
+                    &payer,
+                    PROGRAM_ID,
+                    set_fee_accounts,
+                    set_fee.try_to_vec().unwrap(),
+                ),
+                approve_multisig_tx_ix(&vault, &owner3.pubkey(), 1),
+                execute_multisig_tx_ix(
+                    &vault,
+                    &signer,
+                    &payer,
+                    1,
+                    vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+                ),
+            ],
+            &[&owner3],
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            event_types(&events),
+            [
+                "multisig_transaction_created",
+                "multisig_transaction_approved",
+                "param_updated",
+                "multisig_transaction_executed",
+            ]
+        );
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.param(ParamKey::DepositFeeBps), 100);
+
+        // Fees collected: routed to the vault's fee recipient (the authority) and, for a 10%
+        // share, to the protocol. Routed fees never accrue, so there is nothing for
+        // CollectFees to drain afterwards.
+        let protocol_fee_recipient = Pubkey::new_unique();
+        let config = GlobalConfig {
+            admin: vault,
+            max_fee_bps: 1_000,
+            protocol_fee_recipient,
+            protocol_fee_bps: 1_000,
+            ..GlobalConfig::default()
+        };
+        let protocol_usdc =
+            create_funded_ata(&mut context, &usdc, &protocol_fee_recipient, 0).await;
+        let fee_usdc = get_associated_token_address(&state.fee_config.fee_recipient, &usdc);
+        let mut deposit = deposit_ix(&vault, &usdc, &bob.pubkey(), 2_000);
+        deposit.accounts.extend([
+            AccountMeta::new(global_config_pda(), false),
+            AccountMeta::new(fee_usdc, false),
+            AccountMeta::new(protocol_usdc, false),
+        ]);
+        deploy_program_data(&mut context, Some(payer));
+        let events = run(
+            &mut context,
+            &[initialize_global_config_ix(&payer, config), deposit],
+            &[&bob],
+        )
+        .await
+        .unwrap();
+        assert_eq!(event_types(&events), ["global_config_initialized", "token_deposited"]);
+        assert_eq!(events[1]["fee_amount"], 20);
+        assert_eq!(events[1]["vault_fee_amount"], 18);
+        assert_eq!(events[1]["protocol_fee_amount"], 2);
+
+        let alice_usdc = get_associated_token_address(&alice, &usdc);
+        assert_eq!(token_balance(&mut context, &alice_usdc).await, 6_018);
+        assert_eq!(token_balance(&mut context, &bob_usdc).await, 3_000);
+        assert_eq!(token_balance(&mut context, &protocol_usdc).await, 2);
+        assert_eq!(fetch_global_config(&mut context).await.protocol_fees_collected(&usdc), 2);
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&usdc), 8_980);
+        assert_eq!(state.tracked_balance(&gov), 800);
+        assert_books(&mut context, &vault, &mints).await;
+    }
+}
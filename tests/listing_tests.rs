@@ -0,0 +1,217 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod listing_tests {
+    use super::common::*;
+    use solana_program::{pubkey::Pubkey, sysvar};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        client::{self, ProposalStatus, TransactionStatus},
+        state::{GovernanceProposal, ParamKey, TransactionAccount},
+    };
+
+    const NONCE: u8 = 10;
+    const TRANSACTIONS: u64 = 12;
+
+    // A dozen stored transactions don't fit the headroom a new vault is created with
+    async fn grow_vault(context: &mut ProgramTestContext, vault: &Pubkey) {
+        let mut account = context
+            .banks_client
+            .get_account(*vault)
+            .await
+            .unwrap()
+            .unwrap();
+        account.data.resize(account.data.len() + 4_096, 0);
+        account.lamports += 1_000_000_000;
+        context.set_account(vault, &account.into());
+    }
+
+    fn proposal(id: u64, end_time: i64, queued: bool, cancelled: bool) -> GovernanceProposal {
+        GovernanceProposal {
+            id,
+            proposer: Pubkey::new_unique(),
+            title: format!("proposal {}", id),
+            description: String::new(),
+            instructions: vec![],
+            for_votes: 0,
+            against_votes: 0,
+            abstain_votes: 0,
+            created_at: 100 + id as i64,
+            end_time,
+            executed: false,
+            queued,
+            eta: None,
+            start_time: 100,
+            cancelled,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multisig_transactions_paginate_by_id() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let approver = Keypair::new();
+        let owners = vec![payer, approver.pubkey(), Pubkey::new_unique()];
+        send(&mut context, &[initialize_multisig_ix(&vault, &payer, owners, 2, NONCE)], &[])
+            .await
+            .unwrap();
+        grow_vault(&mut context, &vault).await;
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.max_proposals_per_day = TRANSACTIONS as u32;
+        store_vault(&mut context, &vault, &state).await;
+
+        let accounts = vec![TransactionAccount {
+            pubkey: sysvar::clock::id(),
+            is_signer: false,
+            is_writable: false,
+        }];
+        let proposals: Vec<_> = (0..TRANSACTIONS)
+            .map(|i| {
+                let target = Pubkey::new_unique();
+                create_multisig_tx_ix(&vault, &payer, target, accounts.clone(), vec![i as u8])
+            })
+            .collect();
+        for batch in proposals.chunks(4) {
+            send(&mut context, batch, &[]).await.unwrap();
+        }
+        send(
+            &mut context,
+            &[
+                approve_multisig_tx_ix(&vault, &approver.pubkey(), 3),
+                client::cancel_multisig_transaction(&vault, &payer, 5),
+            ],
+            &[&approver],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let mut pages = vec![];
+        let mut offset = Some(0);
+        while let Some(start) = offset {
+            let page = client::list_multisig_transactions(&state, 0, start, 5);
+            assert_eq!(page.total, TRANSACTIONS as usize);
+            offset = page.next_offset;
+            pages.push(page.items);
+        }
+        let sizes: Vec<_> = pages.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![5, 5, 2]);
+
+        let summaries: Vec<_> = pages.into_iter().flatten().collect();
+        let ids: Vec<_> = summaries.iter().map(|s| s.id).collect();
+        assert_eq!(ids, (0..TRANSACTIONS).collect::<Vec<_>>());
+        for summary in &summaries {
+            let expected = match summary.id {
+                3 => TransactionStatus::Approved,
+                5 => TransactionStatus::Cancelled,
+                _ => TransactionStatus::Pending,
+            };
+            assert_eq!(summary.status, expected, "transaction {}", summary.id);
+            assert_eq!(summary.proposer, payer);
+            assert_eq!(summary.approvals, if summary.id == 3 { 2 } else { 1 });
+            assert_eq!(summary.expires_at, None);
+        }
+
+        let past_end = client::list_multisig_transactions(&state, 0, 20, 5);
+        assert!(past_end.items.is_empty());
+        assert_eq!(past_end.next_offset, None);
+
+        let full = client::fetch_multisig_transaction(&state, 7).unwrap();
+        assert_eq!(full.data, vec![7]);
+        assert!(client::fetch_multisig_transaction(&state, TRANSACTIONS).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_expiring_transactions_soonest_first() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let owners = vec![payer, Pubkey::new_unique()];
+        send(&mut context, &[initialize_multisig_ix(&vault, &payer, owners, 2, NONCE)], &[])
+            .await
+            .unwrap();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::MultisigTransactionTtl, 3_600);
+        store_vault(&mut context, &vault, &state).await;
+
+        let accounts = vec![TransactionAccount {
+            pubkey: sysvar::clock::id(),
+            is_signer: false,
+            is_writable: false,
+        }];
+        for i in 0..3u8 {
+            let target = Pubkey::new_unique();
+            let propose = create_multisig_tx_ix(&vault, &payer, target, accounts.clone(), vec![i]);
+            send(&mut context, &[propose], &[]).await.unwrap();
+            advance_clock(&mut context, 1_000).await;
+        }
+        send(&mut context, &[client::cancel_multisig_transaction(&vault, &payer, 1)], &[])
+            .await
+            .unwrap();
+
+        // Transaction 0 has 600s left, 1 was cancelled and 2 has 2_600s
+        let state = fetch_vault(&mut context, &vault).await;
+        let created_at = state.multisig_transaction(0).unwrap().created_at;
+        let now = created_at + 3_000;
+        let expiring = client::list_expiring_multisig_transactions(&state, now, 3_600);
+        let ids: Vec<_> = expiring.iter().map(|s| (s.id, s.expires_at.unwrap() - now)).collect();
+        assert_eq!(ids, vec![(0, 600), (2, 2_600)]);
+        let soon = client::list_expiring_multisig_transactions(&state, now, 1_000);
+        assert_eq!(soon.len(), 1);
+
+        let later = created_at + 4_000;
+        let page = client::list_multisig_transactions(&state, later, 0, 10);
+        assert_eq!(page.items[0].status, TransactionStatus::Expired);
+        assert_eq!(page.items[2].status, TransactionStatus::Pending);
+        let expiring = client::list_expiring_multisig_transactions(&state, later, 3_600);
+        assert_eq!(expiring.iter().map(|s| s.id).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[tokio::test]
+    async fn test_governance_proposals_paginate_with_status() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let mut state = fetch_vault(&mut context, &vault).await;
+
+        let now = 1_000;
+        let mut executed = proposal(1, 500, true, false);
+        executed.executed = true;
+        state.governance_proposals = vec![
+            proposal(3, 2_000, false, false),
+            executed,
+            proposal(0, 500, false, false),
+            proposal(2, 500, true, false),
+            proposal(4, 2_000, false, true),
+        ];
+
+        let first = client::list_governance_proposals(&state, now, 0, 3);
+        assert_eq!(first.total, 5);
+        assert_eq!(first.next_offset, Some(3));
+        let statuses: Vec<_> = first.items.iter().map(|p| (p.id, p.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (0, ProposalStatus::Ended),
+                (1, ProposalStatus::Executed),
+                (2, ProposalStatus::Queued),
+            ]
+        );
+
+        let rest = client::list_governance_proposals(&state, now, 3, 3);
+        assert_eq!(rest.next_offset, None);
+        let statuses: Vec<_> = rest.items.iter().map(|p| (p.id, p.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![(3, ProposalStatus::Active), (4, ProposalStatus::Cancelled)]
+        );
+        assert_eq!(rest.items[0].title, "proposal 3");
+
+        let full = client::fetch_governance_proposal(&state, 3).unwrap();
+        assert_eq!(full.end_time, 2_000);
+        assert!(client::fetch_governance_proposal(&state, 5).is_none());
+    }
+}
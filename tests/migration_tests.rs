@@ -0,0 +1,370 @@
+mod common;
+
+#[cfg(test)]
+mod migration_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        migration::{LegacyTimeLockV0, LegacyVaultHeadV2, LegacyVaultV0},
+        state::{ActionKind, SupportedToken, TimeLock, Vault, VaultConfig, VestingSchedule},
+        VaultError, PROGRAM_ID,
+    };
+
+    // `vault` as versions 1 and 2 laid it out, with u64 TVL and fee counters
+    fn narrow_counters(vault: &Vault) -> Vec<u8> {
+        let head = LegacyVaultHeadV2 {
+            authority: vault.authority,
+            bump: vault.bump,
+            emergency_admin: vault.emergency_admin,
+            paused: vault.paused,
+            supported_tokens: vault.supported_tokens.clone(),
+            token_balances: vault.token_balances.clone(),
+            time_locks: vault.time_locks.clone(),
+            proposals: vault.proposals.clone(),
+            next_proposal_id: vault.next_proposal_id,
+            fee_config: vault.fee_config.clone(),
+            total_value_locked: vault.total_value_locked as u64,
+            total_fees_collected: vault.total_fees_collected as u64,
+        };
+        let mut data = head.try_to_vec().unwrap();
+        let rest = vault.try_to_vec().unwrap().split_off(data.len() + 16);
+        data.extend(rest);
+        data
+    }
+
+    fn legacy_lock(
+        is_linear: bool,
+        claimed_amount: u64,
+        released_amount: u64,
+    ) -> LegacyTimeLockV0 {
+        LegacyTimeLockV0 {
+            beneficiary: Pubkey::new_unique(),
+            amount: 1_000,
+            start_time: 100,
+            duration: 1_000,
+            cliff_duration: Some(250),
+            is_linear,
+            claimed_amount,
+            end_time: 1_100,
+            cliff_time: 350,
+            released_amount,
+        }
+    }
+
+    fn legacy_vault(authority: &Pubkey) -> LegacyVaultV0 {
+        LegacyVaultV0 {
+            authority: *authority,
+            emergency_admin: Pubkey::new_unique(),
+            legacy_mint: Some(Pubkey::new_unique()),
+            total_value_locked: 2_000,
+            time_locks: vec![legacy_lock(true, 300, 300), legacy_lock(false, 0, 0)],
+            ..Default::default()
+        }
+    }
+
+    fn migrate_vault_ix(vault: &Pubkey, authority: &Pubkey, payer: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(solana_program::system_program::id(), false),
+            ],
+            data: VaultInstruction::MigrateVault.try_to_vec().unwrap(),
+        }
+    }
+
+    fn update_config_ix(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UpdateVaultConfig {
+                config: VaultConfig::default(),
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_legacy_conversion() {
+        let legacy = legacy_vault(&Pubkey::new_unique());
+        let vault: Vault = legacy.clone().into();
+
+        assert_eq!(vault.state_version, Vault::STATE_VERSION);
+        assert_eq!(vault.authority, legacy.authority);
+        assert_eq!(vault.total_value_locked, 2_000);
+        assert_eq!(vault.next_time_lock_id, 2);
+
+        for (i, (lock, old)) in vault.time_locks.iter().zip(&legacy.time_locks).enumerate() {
+            assert_eq!(lock.id, i as u64);
+            assert_eq!(lock.beneficiary, old.beneficiary);
+            assert_eq!(lock.mint, legacy.legacy_mint.unwrap());
+            assert_eq!(lock.amount, old.amount);
+            assert_eq!(lock.released_amount, old.released_amount);
+            assert_eq!(lock.start_time, old.start_time);
+            assert_eq!(lock.cliff_time, old.cliff_time);
+            assert_eq!(lock.end_time, old.end_time);
+            assert!(!lock.revocable);
+        }
+        assert_eq!(vault.time_locks[0].schedule, VestingSchedule::Linear);
+        assert_eq!(vault.time_locks[1].schedule, VestingSchedule::CliffAll);
+    }
+
+    #[test]
+    fn test_claimed_amount_carried_over() {
+        // Older claim code only bumped claimed_amount
+        let mut legacy = legacy_vault(&Pubkey::new_unique());
+        legacy.time_locks = vec![legacy_lock(true, 400, 0)];
+        let vault: Vault = legacy.into();
+        assert_eq!(vault.time_locks[0].released_amount, 400);
+    }
+
+    #[test]
+    fn test_lock_mint_from_only_supported_token() {
+        let mut legacy = legacy_vault(&Pubkey::new_unique());
+        legacy.legacy_mint = None;
+        let token = SupportedToken {
+            mint: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        legacy.supported_tokens = vec![token.clone()];
+        let vault: Vault = legacy.clone().into();
+        assert!(vault.time_locks.iter().all(|lock| lock.mint == token.mint));
+
+        // With several it can't tell, and the locks wait for AssignTimeLockMint
+        legacy.supported_tokens.push(SupportedToken::default());
+        let vault: Vault = legacy.into();
+        assert!(vault.time_locks.iter().all(|lock| lock.mint == Pubkey::default()));
+    }
+
+    #[test]
+    fn test_load_requires_migration() {
+        let mut data = legacy_vault(&Pubkey::new_unique()).try_to_vec().unwrap();
+        data.extend_from_slice(&[0u8; 256]);
+        assert_eq!(
+            Vault::load(&data).unwrap_err(),
+            VaultError::StateMigrationRequired.into()
+        );
+
+        // A current layout with an unknown version is refused too
+        let mut vault: Vault = legacy_vault(&Pubkey::new_unique()).into();
+        vault.state_version = Vault::STATE_VERSION + 1;
+        assert_eq!(
+            Vault::load(&vault.try_to_vec().unwrap()).unwrap_err(),
+            VaultError::StateMigrationRequired.into()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_account() {
+        let authority = Keypair::new();
+        let vault_key = Pubkey::new_unique();
+        let legacy = legacy_vault(&authority.pubkey());
+        let mut data = legacy.try_to_vec().unwrap();
+        // Headroom for the fields the new layout adds
+        data.extend_from_slice(&[0u8; 256]);
+
+        let mut test = program_test();
+        test.add_account(
+            vault_key,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let mut context = test.start_with_context().await;
+        let payer = context.payer.pubkey();
+
+        let update_config = update_config_ix(&vault_key, &authority.pubkey());
+        let err = send(&mut context, &[update_config.clone()], &[&authority])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StateMigrationRequired));
+
+        let intruder = Keypair::new();
+        let intruder_migrate = migrate_vault_ix(&vault_key, &intruder.pubkey(), &payer);
+        let err = send(&mut context, &[intruder_migrate], &[&intruder])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        let migrate = migrate_vault_ix(&vault_key, &authority.pubkey(), &payer);
+        send(&mut context, &[migrate.clone()], &[&authority]).await.unwrap();
+
+        // The account was grown to fit the recent_actions ring, and the SOL it held above rent
+        // is now tracked
+        let vault = fetch_vault(&mut context, &vault_key).await;
+        let account = context.banks_client.get_account(vault_key).await.unwrap().unwrap();
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let mut expected: Vault = legacy.into();
+        expected.tracked_lamports = account.lamports - rent.minimum_balance(account.data.len());
+        expected.log_action(
+            ActionKind::Migrate,
+            authority.pubkey(),
+            Pubkey::default(),
+            0,
+            vault.recent_actions()[0].timestamp,
+        );
+        assert_eq!(vault.try_to_vec().unwrap(), expected.try_to_vec().unwrap());
+
+        // Migrated vaults accept regular instructions, and migrating again is a no-op
+        send(&mut context, &[update_config, migrate], &[&authority])
+            .await
+            .unwrap();
+        let vault = fetch_vault(&mut context, &vault_key).await;
+        assert_eq!(vault.state_version, Vault::STATE_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_version_one_account() {
+        let authority = Keypair::new();
+        let vault_key = Pubkey::new_unique();
+        let v1 = Vault {
+            authority: authority.pubkey(),
+            state_version: 1,
+            total_value_locked: 500,
+            next_time_lock_id: 1,
+            time_locks: vec![TimeLock {
+                beneficiary: Pubkey::new_unique(),
+                amount: 500,
+                end_time: 1_000,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        // Version 1 bytes are the current layout without the recent_actions ring and the
+        // fields after it: the (here empty) tip and cancellation lists, the pause timing, the
+        // stream settings, the multisig transaction id counter and (empty) id list, and the
+        // execution delay with its (empty) threshold records, the (empty) param registry, the
+        // (empty) template list with its id counter, the voting model with the (empty)
+        // proposal snapshots and raw vote balances, the (empty) vote locks with the lock
+        // settings, the (empty) swap history, the (empty) strategy caps and statuses, the
+        // (empty) owner join times, the owner set seqno, the (empty) accrued fees, the
+        // (empty) transaction expiries, the tracked lamports, the (empty) outflow windows, the
+        // circuit breaker flag, the TVL accumulator, the (empty) withdrawal tickets with their
+        // id counter and the heartbeat slot
+        let v2_tail = Vault::RECENT_ACTIONS_SPACE
+            + 4 + 4 + 8 + 8 + 1 + 2 + 8 + 4 + 8 + 4 + 4 + 4 + 8 + 1 + 4 + 4 + 4 + 8 + 2 + 4 + 4
+            + 4 + 4 + 8 + 4 + 4 + 8 + 4 + 1 + 16 + 8 + 4 + 8 + 8 + 4 + 4
+            + 1 + 1 + 4 + 4 + 4 + 4;
+        let mut data = narrow_counters(&v1);
+        data.truncate(data.len() - v2_tail);
+        data.extend_from_slice(&[0u8; 64]);
+        let original_len = data.len();
+        assert_eq!(
+            Vault::load(&data).unwrap_err(),
+            VaultError::StateMigrationRequired.into()
+        );
+
+        let mut test = program_test();
+        test.add_account(
+            vault_key,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let mut context = test.start_with_context().await;
+        let payer = context.payer.pubkey();
+
+        let migrate = migrate_vault_ix(&vault_key, &authority.pubkey(), &payer);
+        send(&mut context, &[migrate], &[&authority]).await.unwrap();
+
+        let account = context
+            .banks_client
+            .get_account(vault_key)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(account.data.len() > original_len);
+        let rent = context.banks_client.get_rent().await.unwrap();
+        assert!(rent.is_exempt(account.lamports, account.data.len()));
+
+        let vault = fetch_vault(&mut context, &vault_key).await;
+        assert_eq!(vault.state_version, Vault::STATE_VERSION);
+        assert_eq!(vault.total_value_locked, 500);
+        // Topped up to the rent floor only, so there was nothing to track
+        assert_eq!(vault.tracked_lamports, 0);
+        assert_eq!(vault.time_locks[0].amount, 500);
+        let actions = vault.recent_actions();
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0].kind, ActionKind::Migrate);
+        assert_eq!(actions[0].actor, authority.pubkey());
+    }
+
+    #[tokio::test]
+    async fn test_migrate_version_two_account() {
+        let authority = Keypair::new();
+        let vault_key = Pubkey::new_unique();
+        let mut v2 = Vault {
+            authority: authority.pubkey(),
+            state_version: 2,
+            total_value_locked: 7_000,
+            total_fees_collected: 30,
+            tracked_lamports: 7_000,
+            tick_cursor: 3,
+            ..Default::default()
+        };
+        v2.log_action(ActionKind::Deposit, authority.pubkey(), Pubkey::default(), 7_000, 50);
+        let mut data = narrow_counters(&v2);
+        data.resize(data.len() + Vault::DYNAMIC_HEADROOM, 0);
+        assert_eq!(
+            Vault::load(&data).unwrap_err(),
+            VaultError::StateMigrationRequired.into()
+        );
+
+        let mut test = program_test();
+        test.add_account(
+            vault_key,
+            Account {
+                lamports: 1_000_000_000,
+                data,
+                owner: PROGRAM_ID,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let mut context = test.start_with_context().await;
+        let payer = context.payer.pubkey();
+        let migrate = migrate_vault_ix(&vault_key, &authority.pubkey(), &payer);
+        send(&mut context, &[migrate], &[&authority]).await.unwrap();
+
+        // Everything but the version and the Migrate record carries over
+        let vault = fetch_vault(&mut context, &vault_key).await;
+        let mut expected = v2;
+        expected.state_version = Vault::STATE_VERSION;
+        expected.log_action(
+            ActionKind::Migrate,
+            authority.pubkey(),
+            Pubkey::default(),
+            0,
+            vault.recent_actions()[1].timestamp,
+        );
+        assert_eq!(vault.try_to_vec().unwrap(), expected.try_to_vec().unwrap());
+        assert_eq!((vault.total_value_locked, vault.total_fees_collected), (7_000, 30));
+    }
+}
@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_authority_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use vault_program::{
+        instruction::VaultInstruction, state::TransactionAccount, VaultError, PROGRAM_ID,
+    };
+
+    const NONCE_A: u8 = 18;
+    const NONCE_B: u8 = 19;
+
+    // Proposes and executes `instruction` through vault A's 1-of-1 multisig in one go
+    async fn execute_as(
+        context: &mut ProgramTestContext,
+        vault_a: &Pubkey,
+        transaction_id: u64,
+        instruction: Instruction,
+    ) {
+        let payer = context.payer.pubkey();
+        let stored = instruction
+            .accounts
+            .iter()
+            .map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect();
+        let mut remaining = vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+        // Signers among them are A's signer PDA, which only the CPI can sign for
+        remaining.extend(instruction.accounts.iter().map(|meta| AccountMeta {
+            is_signer: false,
+            ..meta.clone()
+        }));
+        let signer_a = multisig_signer_pda(vault_a, NONCE_A);
+        send(
+            context,
+            &[
+                create_multisig_tx_ix(vault_a, &payer, PROGRAM_ID, stored, instruction.data),
+                execute_multisig_tx_ix(vault_a, &signer_a, &payer, transaction_id, remaining),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        advance_clock(context, 1).await;
+    }
+
+    fn change_threshold_ix(vault: &Pubkey, authority: &Pubkey, threshold: u64) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(multisig_signer_pda(vault, NONCE_B), false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::ChangeMultiSigThreshold { threshold }.try_to_vec().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_multisig_governs_second_vault() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let vault_a = create_vault(&mut context).await;
+        let multisig_a = initialize_multisig_ix(&vault_a, &payer, vec![payer], 1, NONCE_A);
+        send(&mut context, &[multisig_a], &[]).await.unwrap();
+
+        // A's signer PDA pays for and becomes the authority of vault B
+        let signer_a = multisig_signer_pda(&vault_a, NONCE_A);
+        let fund = system_instruction::transfer(&payer, &signer_a, 1_000_000_000);
+        send(&mut context, &[fund], &[]).await.unwrap();
+        let (vault_b, _) = vault_pda(&signer_a, 0);
+        let emergency_admin = Pubkey::new_unique();
+        let initialize_b = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault_b, false),
+                AccountMeta::new(signer_a, true),
+                AccountMeta::new_readonly(emergency_admin, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::rent::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::InitializeVault {
+                index: 0,
+                allow_same_as_authority: false,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        execute_as(&mut context, &vault_a, 0, initialize_b).await;
+        assert_eq!(fetch_vault(&mut context, &vault_b).await.authority, signer_a);
+
+        let owners = vec![payer, Pubkey::new_unique(), Pubkey::new_unique()];
+        let multisig_b = initialize_multisig_ix(&vault_b, &signer_a, owners, 1, NONCE_B);
+        execute_as(&mut context, &vault_a, 1, multisig_b).await;
+
+        // Owning A's multisig doesn't make the payer B's authority
+        let err = send(&mut context, &[change_threshold_ix(&vault_b, &payer, 2)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InsufficientAuthority as u32)
+            )
+        );
+
+        execute_as(&mut context, &vault_a, 2, change_threshold_ix(&vault_b, &signer_a, 2)).await;
+        let state_b = fetch_vault(&mut context, &vault_b).await;
+        assert_eq!(state_b.multi_sig.unwrap().threshold, 2);
+        let state_a = fetch_vault(&mut context, &vault_a).await;
+        assert!(state_a.multi_sig_transactions.iter().all(|t| t.did_execute));
+    }
+}
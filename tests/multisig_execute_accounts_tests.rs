@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_execute_accounts_tests {
+    use super::common::*;
+    use solana_program::{
+        instruction::AccountMeta, pubkey::Pubkey, system_instruction, system_program,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        account::Account,
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    };
+    use vault_program::{state::TransactionAccount, VaultError, PROGRAM_ID};
+
+    const NONCE: u8 = 3;
+
+    // 1-of-1 multisig with transaction 0 paying `recipient` from the funded signer PDA
+    async fn payout_vault(context: &mut ProgramTestContext, recipient: &Pubkey) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: signer, is_signer: true, is_writable: true },
+            TransactionAccount { pubkey: *recipient, is_signer: false, is_writable: true },
+        ];
+        let inner_data = system_instruction::transfer(&signer, recipient, 1_000_000).data;
+        send(
+            context,
+            &[
+                system_instruction::transfer(&payer, &signer, 10_000_000),
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                create_multisig_tx_ix(&vault, &payer, system_program::id(), inner_accounts, inner_data),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    async fn execute_err(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        remaining: Vec<AccountMeta>,
+    ) -> TransactionError {
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(vault, NONCE);
+        let execute = execute_multisig_tx_ix(vault, &signer, &payer, 0, remaining);
+        send(context, &[execute], &[]).await.unwrap_err().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_target_program_account_required() {
+        let mut context = program_test().start_with_context().await;
+        let recipient = Pubkey::new_unique();
+        let vault = payout_vault(&mut context, &recipient).await;
+        let signer = multisig_signer_pda(&vault, NONCE);
+
+        // Program account left out entirely
+        let err = execute_err(&mut context, &vault, vec![]).await;
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+
+        // Accounts are all there, but not with the program first
+        let shuffled = vec![
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ];
+        let err = execute_err(&mut context, &vault, shuffled).await;
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+
+        // Some other program in its place
+        let wrong_program = vec![
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let err = execute_err(&mut context, &vault, wrong_program).await;
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.multisig_transaction(0).unwrap().did_execute);
+    }
+
+    #[tokio::test]
+    async fn test_stored_accounts_must_be_passed() {
+        let mut context = program_test().start_with_context().await;
+        let recipient = Pubkey::new_unique();
+        let vault = payout_vault(&mut context, &recipient).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+
+        let without_recipient = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+        ];
+        let err = execute_err(&mut context, &vault, without_recipient).await;
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+
+        let complete = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let execute = execute_multisig_tx_ix(&vault, &signer, &payer, 0, complete);
+        send(&mut context, &[execute], &[]).await.unwrap();
+        assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_inner_failure_is_logged() {
+        let mut context = program_test().start_with_context().await;
+        let recipient = Pubkey::new_unique();
+        let vault = payout_vault(&mut context, &recipient).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+
+        // Leave the signer PDA short of the 1_000_000 lamports the payout sends
+        let drained = Account {
+            lamports: 500_000,
+            owner: system_program::id(),
+            ..Account::default()
+        };
+        context.set_account(&signer, &drained.into());
+
+        let accounts = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        let execute = execute_multisig_tx_ix(&vault, &signer, &payer, 0, accounts);
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[execute],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert!(processed.result.is_err());
+
+        let logs = processed.metadata.unwrap().log_messages;
+        let expected = format!(
+            "Program log: EXEC target={} accounts=2 data_len=12 tx_id=0",
+            system_program::id()
+        );
+        assert!(logs.contains(&expected), "{:#?}", logs);
+        assert!(logs.iter().any(|line| line.starts_with("Program log: EXEC_FAILED tx_id=0 error=")));
+        assert!(logs
+            .iter()
+            .any(|line| line.contains("\"multisig_transaction_execution_failed\"")));
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.multisig_transaction(0).unwrap().did_execute);
+        assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 0);
+    }
+}
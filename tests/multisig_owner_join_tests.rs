@@ -0,0 +1,107 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_owner_join_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        instruction::VaultInstruction, state::TransactionAccount, VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 4;
+
+    fn set_owners_ix(vault: &Pubkey, authority: &Pubkey, owners: Vec<Pubkey>) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(multisig_signer_pda(vault, NONCE), false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::SetMultiSigOwners { owners }.try_to_vec().unwrap(),
+        }
+    }
+
+    fn propose_ix(vault: &Pubkey, proposer: &Pubkey) -> Instruction {
+        let accounts = vec![TransactionAccount {
+            pubkey: *vault,
+            is_signer: false,
+            is_writable: true,
+        }];
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, accounts, vec![0; 8])
+    }
+
+    // 1-of-1 multisig whose transaction 0, proposed by the payer, predates `newcomer` joining
+    async fn grown_vault(context: &mut ProgramTestContext, newcomer: &Pubkey) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                propose_ix(&vault, &payer),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        advance_clock(context, 60).await;
+        // The newcomer goes first, so the payer's approval has to move with them
+        send(context, &[set_owners_ix(&vault, &payer, vec![*newcomer, payer])], &[])
+            .await
+            .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_new_owner_cannot_approve_earlier_transactions() {
+        let mut context = program_test().start_with_context().await;
+        let newcomer = Keypair::new();
+        let vault = grown_vault(&mut context, &newcomer.pubkey()).await;
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let proposed_at = state.multisig_transaction(0).unwrap().created_at;
+        assert_eq!(state.multisig_owner_since.len(), 2);
+        assert_eq!(state.owner_since(1), proposed_at);
+        assert!(state.owner_since(0) > proposed_at);
+        assert_eq!(state.multisig_transaction(0).unwrap().signers, vec![false, true]);
+
+        let approve = approve_multisig_tx_ix(&vault, &newcomer.pubkey(), 0);
+        let err = send(&mut context, &[approve], &[&newcomer])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::OwnerJoinedAfterProposal as u32)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_new_owner_approves_later_transactions() {
+        let mut context = program_test().start_with_context().await;
+        let newcomer = Keypair::new();
+        let vault = grown_vault(&mut context, &newcomer.pubkey()).await;
+        let payer = context.payer.pubkey();
+
+        send(&mut context, &[propose_ix(&vault, &payer)], &[]).await.unwrap();
+        let approve = approve_multisig_tx_ix(&vault, &newcomer.pubkey(), 1);
+        send(&mut context, &[approve], &[&newcomer]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multisig_transaction(1).unwrap().signers, vec![true, true]);
+    }
+}
@@ -0,0 +1,84 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_payload_limit_tests {
+    use super::common::*;
+    use solana_program::{instruction::InstructionError, pubkey::Pubkey};
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use vault_program::{
+        state::{MultiSigTransaction, TransactionAccount},
+        VaultError,
+    };
+
+    fn accounts(count: usize) -> Vec<TransactionAccount> {
+        (0..count)
+            .map(|_| TransactionAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_payload_bounds() {
+        let max_accounts = MultiSigTransaction::MAX_ACCOUNTS;
+        let max_data = MultiSigTransaction::MAX_DATA_LEN;
+        let cases: &[(&str, usize, usize, bool)] = &[
+            ("no accounts", 0, 0, false),
+            ("one account", 1, 0, true),
+            ("accounts at cap", max_accounts, 0, true),
+            ("accounts over cap", max_accounts + 1, 0, false),
+            ("data at cap", 1, max_data, true),
+            ("data over cap", 1, max_data + 1, false),
+            ("both at cap", max_accounts, max_data, true),
+        ];
+
+        for (name, account_count, data_len, ok) in cases {
+            let data = vec![0; *data_len];
+            let result = MultiSigTransaction::validate_payload(&accounts(*account_count), &data);
+            let expected = if *ok { Ok(()) } else { Err(VaultError::InvalidTransactionData) };
+            assert_eq!(result, expected, "case: {}", name);
+        }
+    }
+
+    // Account-count bounds can't be exercised end to end: 30 accounts already exceed the
+    // transaction size limit, so only the data bound goes through the program here
+    #[tokio::test]
+    async fn test_data_bound_enforced_on_chain() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, 2)], &[])
+            .await
+            .unwrap();
+
+        let max_data = MultiSigTransaction::MAX_DATA_LEN;
+        let over = create_multisig_tx_ix(
+            &vault,
+            &payer,
+            Pubkey::new_unique(),
+            accounts(1),
+            vec![1; max_data + 1],
+        );
+        let err = send(&mut context, &[over], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InvalidTransactionData as u32)
+            )
+        );
+
+        let at_cap = create_multisig_tx_ix(
+            &vault,
+            &payer,
+            Pubkey::new_unique(),
+            accounts(1),
+            vec![1; max_data],
+        );
+        send(&mut context, &[at_cap], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig_transactions[0].data.len(), max_data);
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_reentrancy_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::instruction::{AccountMeta, InstructionError};
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        instruction::VaultInstruction, state::TransactionAccount, VaultError, PROGRAM_ID,
+    };
+
+    #[tokio::test]
+    async fn test_self_cpi_threshold_change_persists() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let second_owner = Keypair::new();
+        let nonce = 7;
+        let multisig_signer = multisig_signer_pda(&vault, nonce);
+
+        send(
+            &mut context,
+            &[initialize_multisig_ix(
+                &vault,
+                &payer,
+                vec![payer, second_owner.pubkey()],
+                2,
+                nonce,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        // Multisig transaction that calls back into this program to lower the threshold
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: multisig_signer, is_signer: true, is_writable: false },
+            TransactionAccount { pubkey: payer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::ChangeMultiSigThreshold { threshold: 1 }
+            .try_to_vec()
+            .unwrap();
+        send(
+            &mut context,
+            &[
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data),
+                approve_multisig_tx_ix(&vault, &second_owner.pubkey(), 0),
+            ],
+            &[&second_owner],
+        )
+        .await
+        .unwrap();
+
+        let execute = execute_multisig_tx_ix(
+            &vault,
+            &multisig_signer,
+            &payer,
+            0,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        );
+        send(&mut context, &[execute.clone()], &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig.unwrap().threshold, 1);
+        assert!(state.multi_sig_transactions[0].did_execute);
+
+        // The executed flag is what stops the same transaction from running twice
+        let slot = context.banks_client.get_root_slot().await.unwrap();
+        context.warp_to_slot(slot + 2).unwrap();
+        let err = send(&mut context, &[execute], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::TransactionAlreadyExecuted as u32)
+            )
+        );
+    }
+}
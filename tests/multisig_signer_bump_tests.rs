@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_signer_bump_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{instruction::AccountMeta, pubkey::Pubkey, sysvar};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{MultiSig, ParamKey, TransactionAccount},
+        PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 17;
+
+    // 1-of-1 multisig of the payer
+    async fn multisig_vault(context: &mut ProgramTestContext) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        send(context, &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)], &[])
+            .await
+            .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_stored_bump_is_canonical() {
+        let mut context = program_test().start_with_context().await;
+        let vault = multisig_vault(&mut context).await;
+
+        let (signer, canonical) =
+            Pubkey::find_program_address(&[vault.as_ref(), &[NONCE]], &PROGRAM_ID);
+        let multi_sig = fetch_vault(&mut context, &vault).await.multi_sig.unwrap();
+        assert_eq!(multi_sig.bump, canonical);
+        assert_eq!(multi_sig.signer_address(&vault, &PROGRAM_ID), signer);
+        assert_eq!(MultiSig::find_signer_address(&vault, NONCE, &PROGRAM_ID), (signer, canonical));
+    }
+
+    #[tokio::test]
+    async fn test_missing_bump_filled_in_on_first_execute() {
+        let mut context = program_test().start_with_context().await;
+        let vault = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+
+        // As stored before the bump was recorded
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.multi_sig.as_mut().unwrap().bump = 0;
+        store_vault(&mut context, &vault, &state).await;
+
+        let accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let data = VaultInstruction::SetParam {
+            key: ParamKey::MinDepositAmount,
+            value: 5,
+        }
+        .try_to_vec()
+        .unwrap();
+        let remaining = vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+        send(
+            &mut context,
+            &[
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, accounts, data),
+                execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.param(ParamKey::MinDepositAmount), 5);
+        let (_, canonical) = MultiSig::find_signer_address(&vault, NONCE, &PROGRAM_ID);
+        assert_eq!(state.multi_sig.unwrap().bump, canonical);
+    }
+}
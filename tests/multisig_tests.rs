@@ -35,7 +35,7 @@ mod tests {
         let vault_pubkey = vault_keypair.pubkey();
 
         // Create vault account
-        let vault_size = std::mem::size_of::<Vault>() + 1024; // Extra space for multisig data
+        let vault_size = Vault::initial_space().unwrap();
         let rent = banks_client.get_rent().await.unwrap();
         let vault_rent = rent.minimum_balance(vault_size);
 
@@ -57,9 +57,13 @@ mod tests {
                 AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
                 AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
             ],
-            data: VaultInstruction::Initialize { bump: 0 }
-                .try_to_vec()
-                .unwrap(),
+            data: VaultInstruction::Initialize {
+                bump: 0,
+                index: 0,
+                allow_same_as_authority: true,
+            }
+            .try_to_vec()
+            .unwrap(),
         };
 
         let transaction = Transaction::new_signed_with_payer(
@@ -533,10 +537,10 @@ mod tests {
                 AccountMeta::new_readonly(multisig_signer, false),
                 AccountMeta::new_readonly(payer.pubkey(), true),
                 AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
-                // Include the accounts needed for the transfer
+                // The target program first, then the accounts needed for the transfer
+                AccountMeta::new_readonly(system_program::id(), false),
                 AccountMeta::new(vault_pubkey, false),
                 AccountMeta::new(recipient.pubkey(), false),
-                AccountMeta::new_readonly(system_program::id(), false),
             ],
             data: VaultInstruction::ExecuteMultiSigTransaction { transaction_id: 0 }
                 .try_to_vec()
@@ -0,0 +1,160 @@
+mod common;
+
+#[cfg(test)]
+mod multisig_transaction_id_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{MultiSigTransaction, TransactionAccount, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 2;
+
+    fn prune_ix(vault: &Pubkey, owner: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::PruneMultiSigTransactions.try_to_vec().unwrap(),
+        }
+    }
+
+    // Proposes setting the vault's proposal limit, which the multisig signer may do
+    fn propose_limit_ix(vault: &Pubkey, proposer: &Pubkey, max_proposals_per_day: u32) -> Instruction {
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::SetMultiSigProposalLimit { max_proposals_per_day }
+            .try_to_vec()
+            .unwrap();
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, inner_accounts, inner_data)
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, transaction_id: u64) -> Instruction {
+        execute_multisig_tx_ix(
+            vault,
+            &multisig_signer_pda(vault, NONCE),
+            executor,
+            transaction_id,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        )
+    }
+
+    #[test]
+    fn test_ids_backfilled_from_positions() {
+        // Written before ids were stored: the id list and counter decode as empty/zero
+        let vault = Vault {
+            state_version: Vault::STATE_VERSION,
+            multi_sig_transactions: vec![MultiSigTransaction::default(); 2],
+            ..Default::default()
+        };
+        let loaded = Vault::load(&vault.try_to_vec().unwrap()).unwrap();
+        assert_eq!(loaded.multi_sig_transaction_ids, vec![0, 1]);
+        assert_eq!(loaded.next_transaction_id, 2);
+        assert_eq!(loaded.multisig_transaction_index(1), Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_pending_transaction_keeps_id_after_pruning() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let cosigner = Keypair::new();
+        send(
+            &mut context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer, cosigner.pubkey()], 2, NONCE),
+                propose_limit_ix(&vault, &payer, 3),
+                propose_limit_ix(&vault, &payer, 5),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        send(
+            &mut context,
+            &[
+                approve_multisig_tx_ix(&vault, &cosigner.pubkey(), 0),
+                execute_ix(&vault, &payer, 0),
+            ],
+            &[&cosigner],
+        )
+        .await
+        .unwrap();
+
+        send(&mut context, &[prune_ix(&vault, &payer)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig_transactions.len(), 1);
+        assert_eq!(state.multi_sig_transaction_ids, vec![1]);
+        assert_eq!(state.effective_max_proposals_per_day(), 3);
+
+        // Transaction 1 now sits at index 0 but is still reached by its id
+        let approve_pruned = approve_multisig_tx_ix(&vault, &cosigner.pubkey(), 0);
+        let err = send(&mut context, &[approve_pruned], &[&cosigner])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionNotFound));
+        send(
+            &mut context,
+            &[
+                approve_multisig_tx_ix(&vault, &cosigner.pubkey(), 1),
+                execute_ix(&vault, &payer, 1),
+                propose_limit_ix(&vault, &payer, 7),
+            ],
+            &[&cosigner],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.effective_max_proposals_per_day(), 5);
+        assert!(state.multisig_transaction(1).unwrap().did_execute);
+        // Ids are never reused, even though only two transactions are stored
+        assert_eq!(state.multi_sig_transaction_ids, vec![1, 2]);
+        assert_eq!(state.next_transaction_id, 3);
+    }
+
+    #[tokio::test]
+    async fn test_only_owners_prune() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let stranger = Keypair::new();
+        let err = send(&mut context, &[prune_ix(&vault, &stranger.pubkey())], &[&stranger])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidOwner));
+    }
+}
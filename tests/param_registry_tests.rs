@@ -0,0 +1,199 @@
+mod common;
+
+#[cfg(test)]
+mod param_registry_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{FeeConfig, MultiSig, ParamKey, TransactionAccount, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 7;
+
+    fn set_param_data(key: ParamKey, value: u64) -> Vec<u8> {
+        VaultInstruction::SetParam { key, value }.try_to_vec().unwrap()
+    }
+
+    fn propose_param_ix(vault: &Pubkey, proposer: &Pubkey, key: ParamKey, value: u64) -> Instruction {
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let data = set_param_data(key, value);
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, inner_accounts, data)
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, transaction_id: u64) -> Instruction {
+        execute_multisig_tx_ix(
+            vault,
+            &multisig_signer_pda(vault, NONCE),
+            executor,
+            transaction_id,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        )
+    }
+
+    #[test]
+    fn test_bounds_per_key() {
+        for key in [ParamKey::DepositFeeBps, ParamKey::WithdrawalFeeBps] {
+            assert!(key.validate(0).is_ok());
+            assert!(key.validate(ParamKey::MAX_FEE_BPS).is_ok());
+            assert_eq!(
+                key.validate(ParamKey::MAX_FEE_BPS + 1),
+                Err(VaultError::InvalidAmount)
+            );
+        }
+        let limit = ParamKey::MaxProposalsPerDay;
+        assert_eq!(limit.validate(0), Err(VaultError::InvalidAmount));
+        assert!(limit.validate(1).is_ok());
+        assert!(limit.validate(1_000).is_ok());
+        assert_eq!(limit.validate(1_001), Err(VaultError::InvalidAmount));
+
+        let cosign = ParamKey::EmergencyRequiresCosign;
+        assert!(cosign.validate(1).is_ok());
+        assert_eq!(cosign.validate(2), Err(VaultError::InvalidAmount));
+
+        for key in [ParamKey::MinDepositAmount, ParamKey::MinWithdrawalAmount] {
+            assert!(key.validate(0).is_ok());
+            assert!(key.validate(u64::MAX).is_ok());
+        }
+        let ttl = ParamKey::MultisigTransactionTtl;
+        assert!(ttl.validate(0).is_ok());
+        assert!(ttl.validate(ParamKey::MAX_TRANSACTION_TTL).is_ok());
+        assert_eq!(
+            ttl.validate(ParamKey::MAX_TRANSACTION_TTL + 1),
+            Err(VaultError::InvalidAmount)
+        );
+
+        let challenge = ParamKey::RecoveryChallengeSeconds;
+        assert_eq!(challenge.validate(0), Err(VaultError::InvalidAmount));
+        assert!(challenge.validate(ParamKey::MIN_RECOVERY_CHALLENGE).is_ok());
+        assert_eq!(
+            challenge.validate(ParamKey::MAX_RECOVERY_CHALLENGE + 1),
+            Err(VaultError::InvalidAmount)
+        );
+
+        for (index, key) in ParamKey::ALL.iter().enumerate() {
+            assert_eq!(*key as usize, index);
+            assert_eq!(ParamKey::from_u8(index as u8), Some(*key));
+        }
+        assert_eq!(ParamKey::from_u8(ParamKey::ALL.len() as u8), None);
+    }
+
+    #[test]
+    fn test_defaults_fall_back_to_legacy_fields() {
+        let mut vault = Vault::default();
+        assert_eq!(vault.param(ParamKey::DepositFeeBps), 0);
+        assert_eq!(
+            vault.param(ParamKey::RecoveryChallengeSeconds),
+            MultiSig::RECOVERY_CHALLENGE_SECONDS as u64
+        );
+        assert_eq!(
+            vault.param(ParamKey::MaxProposalsPerDay),
+            MultiSig::DEFAULT_MAX_PROPOSALS_PER_DAY as u64
+        );
+
+        vault.fee_config = FeeConfig {
+            withdrawal_fee_bps: 50,
+            ..Default::default()
+        };
+        vault.max_proposals_per_day = 4;
+        assert_eq!(vault.param(ParamKey::WithdrawalFeeBps), 50);
+        assert_eq!(vault.effective_max_proposals_per_day(), 4);
+
+        // A stored entry wins over the field it replaces
+        assert_eq!(vault.set_param(ParamKey::WithdrawalFeeBps, 20), 50);
+        assert_eq!(vault.param(ParamKey::WithdrawalFeeBps), 20);
+        assert_eq!(vault.fee_for(ParamKey::WithdrawalFeeBps, 1_000), 2);
+        assert_eq!(vault.set_param(ParamKey::WithdrawalFeeBps, 30), 20);
+        assert_eq!(vault.params.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_withdrawal_fee_set_through_multisig() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        send(
+            &mut context,
+            &[
+                deposit_ix(&vault, &mint, &payer, 1_000),
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                propose_param_ix(&vault, &payer, ParamKey::WithdrawalFeeBps, 100),
+                execute_ix(&vault, &payer, 0),
+                withdraw_ix(&vault, &mint, &payer, 500),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.param(ParamKey::WithdrawalFeeBps), 100);
+        assert_eq!(state.total_fees_collected, 5);
+        assert_eq!(state.tracked_balance(&mint), 500);
+        assert_eq!(state.accrued_fee(&mint), 5);
+        let payer_token = get_associated_token_address(&payer, &mint);
+        assert_eq!(token_balance(&mut context, &payer_token).await, 495);
+
+        // Out-of-range values fail inside the executed transaction
+        send(
+            &mut context,
+            &[propose_param_ix(&vault, &payer, ParamKey::DepositFeeBps, 1_001)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+    }
+
+    #[tokio::test]
+    async fn test_authority_cannot_set_params_directly() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let direct = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: set_param_data(ParamKey::DepositFeeBps, 10),
+        };
+        let err = send(&mut context, &[direct], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+    }
+}
@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod pause_expiry_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{instruction::VaultInstruction, state::Vault, VaultError, PROGRAM_ID};
+
+    const MAX_PAUSE: i64 = 100;
+
+    // The payer is both authority and emergency admin of test vaults
+    fn admin_ix(vault: &Pubkey, signer: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    // Vault holding a 400 deposit of its mint, paused with the given max_pause_duration
+    async fn paused_vault(
+        context: &mut ProgramTestContext,
+        max_pause_duration: i64,
+    ) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let admin = context.payer.pubkey();
+        send(
+            context,
+            &[
+                deposit_ix(&vault, &mint, &admin, 400),
+                admin_ix(
+                    &vault,
+                    &admin,
+                    VaultInstruction::SetMaxPauseDuration { max_pause_duration },
+                ),
+                admin_ix(&vault, &admin, VaultInstruction::PauseVault),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, mint)
+    }
+
+    #[test]
+    fn test_is_paused_honours_duration() {
+        let mut vault = Vault {
+            paused: true,
+            paused_at: 1_000,
+            ..Default::default()
+        };
+        assert!(vault.is_paused(i64::MAX));
+
+        vault.max_pause_duration = MAX_PAUSE;
+        assert_eq!(vault.pause_expires_at(), Some(1_100));
+        assert!(vault.is_paused(1_099));
+        assert!(!vault.is_paused(1_100));
+    }
+
+    #[tokio::test]
+    async fn test_withdrawals_resume_after_pause_expires() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = paused_vault(&mut context, MAX_PAUSE).await;
+        let user = context.payer.pubkey();
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        // Renewing halfway through pushes expiry out to a full duration from now
+        advance_clock(&mut context, MAX_PAUSE / 2).await;
+        send(&mut context, &[admin_ix(&vault, &user, VaultInstruction::ExtendPause)], &[])
+            .await
+            .unwrap();
+        advance_clock(&mut context, MAX_PAUSE / 2 + 10).await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        // No UnpauseVault: the pause simply runs out
+        advance_clock(&mut context, MAX_PAUSE / 2).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.paused);
+        assert_eq!(state.paused_at, 0);
+        assert_eq!(state.token_balances[0].balance, 300);
+
+        // A lapsed pause can't be renewed, only started again
+        let err = send(&mut context, &[admin_ix(&vault, &user, VaultInstruction::ExtendPause)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+    }
+
+    #[tokio::test]
+    async fn test_zero_duration_pauses_until_unpaused() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = paused_vault(&mut context, 0).await;
+        let admin = context.payer.pubkey();
+
+        advance_clock(&mut context, 10 * 365 * 86_400).await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &admin, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+        let err = send(&mut context, &[admin_ix(&vault, &admin, VaultInstruction::PauseVault)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        send(
+            &mut context,
+            &[
+                admin_ix(&vault, &admin, VaultInstruction::UnpauseVault),
+                withdraw_ix(&vault, &mint, &admin, 100),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert!(!fetch_vault(&mut context, &vault).await.paused);
+    }
+
+    #[tokio::test]
+    async fn test_duration_is_fixed_during_a_pause() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, _) = paused_vault(&mut context, MAX_PAUSE).await;
+        let admin = context.payer.pubkey();
+        let shorten = VaultInstruction::SetMaxPauseDuration { max_pause_duration: 1 };
+
+        advance_clock(&mut context, 10).await;
+        let err = send(&mut context, &[admin_ix(&vault, &admin, shorten.clone())], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+        assert_eq!(fetch_vault(&mut context, &vault).await.max_pause_duration, MAX_PAUSE);
+
+        // Once the pause has run out it applies to the next one
+        advance_clock(&mut context, MAX_PAUSE).await;
+        send(&mut context, &[admin_ix(&vault, &admin, shorten)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.max_pause_duration, 1);
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod position_statement_tests {
+    use super::common::*;
+    use serde_json::Value;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use vault_program::client::{parse_events, position_statement, PositionEntryKind};
+
+    // The events `instructions` emitted, as an indexer would have collected them
+    async fn events_of(
+        context: &mut ProgramTestContext,
+        instructions: &[Instruction],
+    ) -> Vec<Value> {
+        let payer = context.payer.pubkey();
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        parse_events(&processed.metadata.unwrap().log_messages)
+    }
+
+    #[tokio::test]
+    async fn test_statement_totals_match_events() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+
+        let mut events = Vec::new();
+        for instructions in [
+            vec![deposit_ix(&vault, &mint, &user, 400)],
+            vec![withdraw_ix(&vault, &mint, &user, 150)],
+            // Fails, so it leaves no events behind
+            vec![withdraw_ix(&vault, &mint, &user, 10_000)],
+            vec![deposit_ix(&vault, &mint, &user, 250)],
+        ] {
+            events.extend(events_of(&mut context, &instructions).await);
+            advance_clock(&mut context, 1).await;
+        }
+
+        let statement = position_statement(&user, &mint, &events);
+        let kinds: Vec<_> = statement.entries.iter().map(|e| (e.kind, e.amount)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (PositionEntryKind::Deposit, 400),
+                (PositionEntryKind::Withdrawal, 150),
+                (PositionEntryKind::Deposit, 250),
+            ]
+        );
+        assert_eq!((statement.deposited, statement.withdrawn), (650, 150));
+
+        // Nothing of it belongs to another wallet, or to the same wallet in another mint
+        assert!(position_statement(&Pubkey::new_unique(), &mint, &events).entries.is_empty());
+        assert!(position_statement(&user, &Pubkey::new_unique(), &events).entries.is_empty());
+    }
+}
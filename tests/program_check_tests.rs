@@ -0,0 +1,41 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod program_check_tests {
+    use vault_program::{
+        client::{check_program_account, cluster_name, ProgramCheckError},
+        PROGRAM_ID,
+    };
+
+    #[test]
+    fn test_cluster_names() {
+        assert_eq!(cluster_name("https://api.devnet.solana.com"), "devnet");
+        assert_eq!(cluster_name("https://api.mainnet-beta.solana.com/"), "mainnet-beta");
+        assert_eq!(cluster_name("http://127.0.0.1:8899"), "localnet");
+        assert_eq!(cluster_name("https://rpc.example.com"), "https://rpc.example.com");
+    }
+
+    #[test]
+    fn test_program_account_check() {
+        // As get_account reported it: a deployed program, a plain account, nothing at all
+        assert_eq!(
+            check_program_account(&PROGRAM_ID, "https://api.devnet.solana.com", Some(true)),
+            Ok(())
+        );
+        let err = check_program_account(&PROGRAM_ID, "http://localhost:8899", Some(false))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            ProgramCheckError::NotExecutable {
+                program_id: PROGRAM_ID,
+                cluster: "localnet".to_string(),
+            }
+        );
+        let err = check_program_account(&PROGRAM_ID, "https://api.testnet.solana.com", None)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("vault program {} isn't deployed on testnet", PROGRAM_ID)
+        );
+    }
+}
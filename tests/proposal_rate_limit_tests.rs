@@ -0,0 +1,196 @@
+mod common;
+
+#[cfg(test)]
+mod proposal_rate_limit_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{MultiSig, TransactionAccount, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 3;
+
+    fn rate_limited() -> TransactionError {
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::ProposalRateLimited as u32),
+        )
+    }
+
+    // Distinct data per call so identical transactions aren't deduplicated
+    fn propose_ix(vault: &Pubkey, proposer: &Pubkey, seq: u8) -> Instruction {
+        let accounts = vec![TransactionAccount {
+            pubkey: *vault,
+            is_signer: false,
+            is_writable: false,
+        }];
+        create_multisig_tx_ix(vault, proposer, Pubkey::new_unique(), accounts, vec![seq])
+    }
+
+    fn set_limit_ix(vault: &Pubkey, signer: &Pubkey, max_proposals_per_day: u32) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::SetMultiSigProposalLimit {
+                max_proposals_per_day,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_window_resets_lazily() {
+        let mut vault = Vault::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let limit = MultiSig::DEFAULT_MAX_PROPOSALS_PER_DAY;
+
+        for expected in 1..=limit {
+            assert_eq!(vault.record_proposal(&alice, 100), Ok(expected));
+        }
+        assert_eq!(vault.record_proposal(&alice, 100), Err(VaultError::ProposalRateLimited));
+        // A failed attempt doesn't extend or consume the window
+        assert_eq!(vault.proposal_windows[0].proposals_in_window, limit);
+
+        assert_eq!(vault.record_proposal(&bob, 100), Ok(1));
+
+        let next_window = 100 + MultiSig::PROPOSAL_WINDOW_SECONDS;
+        assert_eq!(
+            vault.record_proposal(&alice, next_window - 1),
+            Err(VaultError::ProposalRateLimited)
+        );
+        assert_eq!(vault.record_proposal(&alice, next_window), Ok(1));
+        // Bob's lapsed window went when Alice's new one opened
+        assert_eq!(vault.proposal_windows.len(), 1);
+    }
+
+    #[test]
+    fn test_open_windows_capped() {
+        let mut vault = Vault::default();
+        for _ in 0..Vault::MAX_PROPOSAL_WINDOWS {
+            assert_eq!(vault.record_proposal(&Pubkey::new_unique(), 100), Ok(1));
+        }
+        let late = Pubkey::new_unique();
+        assert_eq!(vault.record_proposal(&late, 100), Err(VaultError::ProposalRateLimited));
+        assert_eq!(vault.proposal_windows.len(), Vault::MAX_PROPOSAL_WINDOWS);
+
+        // Once the others lapse there is room again
+        let next_window = 100 + MultiSig::PROPOSAL_WINDOW_SECONDS;
+        assert_eq!(vault.record_proposal(&late, next_window), Ok(1));
+        assert_eq!(vault.proposal_windows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_limit_enforced_and_raised_by_multisig() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let multisig_signer = multisig_signer_pda(&vault, NONCE);
+        send(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        // Owners lower the limit to 3 through an executed multisig transaction,
+        // which itself counts as the first proposal of the window
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: multisig_signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::SetMultiSigProposalLimit {
+            max_proposals_per_day: 3,
+        }
+        .try_to_vec()
+        .unwrap();
+        let execute = execute_multisig_tx_ix(
+            &vault,
+            &multisig_signer,
+            &payer,
+            0,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        );
+        send(
+            &mut context,
+            &[
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data),
+                execute,
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.effective_max_proposals_per_day(), 3);
+
+        for seq in 1..3 {
+            send(&mut context, &[propose_ix(&vault, &payer, seq)], &[])
+                .await
+                .unwrap();
+        }
+        let err = send(&mut context, &[propose_ix(&vault, &payer, 3)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, rate_limited());
+        assert_eq!(fetch_vault(&mut context, &vault).await.multi_sig_transactions.len(), 3);
+
+        advance_clock(&mut context, MultiSig::PROPOSAL_WINDOW_SECONDS).await;
+        send(&mut context, &[propose_ix(&vault, &payer, 4)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig_transactions.len(), 4);
+        assert_eq!(state.proposal_windows[0].proposals_in_window, 1);
+    }
+
+    #[tokio::test]
+    async fn test_limit_requires_multisig_signer() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(
+            &mut context,
+            &[initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let impostor = Keypair::new();
+        let ix = set_limit_ix(&vault, &impostor.pubkey(), 1_000);
+        let err = send(&mut context, &[ix], &[&impostor])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InsufficientAuthority as u32)
+            )
+        );
+    }
+}
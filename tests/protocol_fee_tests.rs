@@ -0,0 +1,110 @@
+mod common;
+
+#[cfg(test)]
+mod protocol_fee_tests {
+    use super::common::*;
+    use solana_program::{instruction::{AccountMeta, Instruction}, pubkey::Pubkey};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::state::{GlobalConfig, ParamKey};
+
+    const FUNDED: u64 = 1_000_000;
+
+    struct Setup {
+        vault: Pubkey,
+        mint: Pubkey,
+        fee_account: Pubkey,
+        protocol_fee_account: Pubkey,
+    }
+
+    // Vault charging the given fees to a fee recipient of its own, under a global config
+    // skimming 10% of every fee for the protocol
+    async fn setup(context: &mut ProgramTestContext, deposit_bps: u64, withdraw_bps: u64) -> Setup {
+        let (vault, mint) = vault_with_token(context, FUNDED).await;
+        let payer = context.payer.pubkey();
+        let fee_recipient = Pubkey::new_unique();
+        let protocol_fee_recipient = Pubkey::new_unique();
+
+        let mut state = fetch_vault(context, &vault).await;
+        state.set_param(ParamKey::DepositFeeBps, deposit_bps);
+        state.set_param(ParamKey::WithdrawalFeeBps, withdraw_bps);
+        state.fee_config.fee_recipient = fee_recipient;
+        store_vault(context, &vault, &state).await;
+
+        let config = GlobalConfig {
+            admin: vault,
+            max_fee_bps: 1_000,
+            protocol_fee_recipient,
+            protocol_fee_bps: 1_000,
+            ..GlobalConfig::default()
+        };
+        deploy_program_data(context, Some(payer));
+        send(context, &[initialize_global_config_ix(&payer, config)], &[]).await.unwrap();
+        create_funded_ata(context, &mint, &fee_recipient, 0).await;
+        create_funded_ata(context, &mint, &protocol_fee_recipient, 0).await;
+
+        Setup {
+            vault,
+            mint,
+            fee_account: get_associated_token_address(&fee_recipient, &mint),
+            protocol_fee_account: get_associated_token_address(&protocol_fee_recipient, &mint),
+        }
+    }
+
+    fn routed(mut instruction: Instruction, setup: &Setup) -> Instruction {
+        instruction.accounts.extend([
+            AccountMeta::new(global_config_pda(), false),
+            AccountMeta::new(setup.fee_account, false),
+            AccountMeta::new(setup.protocol_fee_account, false),
+        ]);
+        instruction
+    }
+
+    // (user, vault, fee recipient, protocol fee recipient) token balances
+    async fn balances(context: &mut ProgramTestContext, setup: &Setup) -> (u64, u64, u64, u64) {
+        let user = get_associated_token_address(&context.payer.pubkey(), &setup.mint);
+        let vault = get_associated_token_address(&setup.vault, &setup.mint);
+        (
+            token_balance(context, &user).await,
+            token_balance(context, &vault).await,
+            token_balance(context, &setup.fee_account).await,
+            token_balance(context, &setup.protocol_fee_account).await,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_fees_split_between_vault_and_protocol() {
+        let mut context = program_test().start_with_context().await;
+        let setup = setup(&mut context, 100, 200).await;
+        let payer = context.payer.pubkey();
+
+        // 1% of 100_000 is 1_000, of which the protocol takes 100
+        let deposit = routed(deposit_ix(&setup.vault, &setup.mint, &payer, 100_000), &setup);
+        send(&mut context, &[deposit], &[]).await.unwrap();
+        assert_eq!(balances(&mut context, &setup).await, (900_000, 99_000, 900, 100));
+
+        // 2% of 50_000 is 1_000 again, and it leaves the vault along with the 49_000 paid out
+        let withdraw = routed(withdraw_ix(&setup.vault, &setup.mint, &payer, 50_000), &setup);
+        send(&mut context, &[withdraw], &[]).await.unwrap();
+        assert_eq!(balances(&mut context, &setup).await, (949_000, 49_000, 1_800, 200));
+
+        let state = fetch_vault(&mut context, &setup.vault).await;
+        assert_eq!(state.tracked_balance(&setup.mint), 49_000);
+        let config = fetch_global_config(&mut context).await;
+        assert_eq!(config.protocol_fees_collected(&setup.mint), 200);
+    }
+
+    #[tokio::test]
+    async fn test_fee_free_vault_pays_no_protocol_fee() {
+        let mut context = program_test().start_with_context().await;
+        let setup = setup(&mut context, 0, 0).await;
+        let payer = context.payer.pubkey();
+
+        let deposit = routed(deposit_ix(&setup.vault, &setup.mint, &payer, 100_000), &setup);
+        send(&mut context, &[deposit], &[]).await.unwrap();
+        assert_eq!(balances(&mut context, &setup).await, (900_000, 100_000, 0, 0));
+        let config = fetch_global_config(&mut context).await;
+        assert_eq!(config.protocol_fees_collected(&setup.mint), 0);
+    }
+}
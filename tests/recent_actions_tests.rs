@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod recent_actions_tests {
+    use super::common::*;
+    use solana_program::pubkey::Pubkey;
+    use solana_sdk::signature::Signer;
+    use vault_program::state::{ActionKind, Vault, RECENT_ACTIONS_LEN};
+
+    #[test]
+    fn test_ring_keeps_latest_in_order() {
+        let mut vault = Vault::default();
+        assert!(vault.recent_actions().is_empty());
+
+        let actor = Pubkey::new_unique();
+        for i in 0..5u64 {
+            vault.log_action(ActionKind::Deposit, actor, Pubkey::default(), i, i as i64);
+        }
+        let amounts: Vec<u64> = vault.recent_actions().iter().map(|r| r.amount).collect();
+        assert_eq!(amounts, vec![0, 1, 2, 3, 4]);
+
+        for i in 5..40u64 {
+            vault.log_action(ActionKind::Deposit, actor, Pubkey::default(), i, i as i64);
+        }
+        let amounts: Vec<u64> = vault.recent_actions().iter().map(|r| r.amount).collect();
+        assert_eq!(amounts, (8..40).collect::<Vec<u64>>());
+        assert_eq!(vault.recent_actions_head as usize, 40 % RECENT_ACTIONS_LEN);
+    }
+
+    #[tokio::test]
+    async fn test_forty_deposits_keep_latest_thirty_two() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 10_000).await;
+        let user = context.payer.pubkey();
+
+        // Distinct amounts keep every transaction unique and identify each entry
+        for batch in 0..10u64 {
+            let deposits: Vec<_> = (1..=4u64)
+                .map(|i| deposit_ix(&vault, &mint, &user, batch * 4 + i))
+                .collect();
+            send(&mut context, &deposits, &[]).await.unwrap();
+        }
+
+        let actions = fetch_vault(&mut context, &vault).await.recent_actions();
+        assert_eq!(actions.len(), RECENT_ACTIONS_LEN);
+        assert!(actions
+            .iter()
+            .all(|r| r.kind == ActionKind::Deposit && r.actor == user && r.mint == mint));
+        // Initialize, AddSupportedToken and deposits 1..=8 have been overwritten
+        let amounts: Vec<u64> = actions.iter().map(|r| r.amount).collect();
+        assert_eq!(amounts, (9..=40).collect::<Vec<u64>>());
+    }
+}
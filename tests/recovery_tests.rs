@@ -0,0 +1,194 @@
+mod common;
+
+#[cfg(test)]
+mod recovery_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{EmergencyActionLog, MultiSig, ParamKey, RecoveryConfig, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 9;
+    const INACTIVITY: i64 = 1_000;
+
+    fn recovery_ix(vault: &Pubkey, signer: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    // 1-of-1 multisig owned by the payer, with recovery configured through a multisig transaction
+    async fn setup(context: &mut ProgramTestContext, recovery_key: &Pubkey) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let multisig_signer = multisig_signer_pda(&vault, NONCE);
+
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: vault, is_signer: false, is_writable: true },
+            TransactionAccount { pubkey: multisig_signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = VaultInstruction::SetMultiSigRecovery {
+            recovery: Some(RecoveryConfig {
+                recovery_key: *recovery_key,
+                inactivity_period: INACTIVITY,
+            }),
+        }
+        .try_to_vec()
+        .unwrap();
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, inner_data),
+                execute_multisig_tx_ix(
+                    &vault,
+                    &multisig_signer,
+                    &payer,
+                    0,
+                    vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+                ),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_recovery_after_inactivity() {
+        let mut context = program_test().start_with_context().await;
+        let recovery_key = Keypair::new();
+        let key = recovery_key.pubkey();
+        let vault = setup(&mut context, &key).await;
+        let initiate = recovery_ix(&vault, &key, VaultInstruction::InitiateRecovery);
+        let finalize = recovery_ix(&vault, &key, VaultInstruction::FinalizeRecovery);
+
+        let err = send(&mut context, &[initiate.clone()], &[&recovery_key])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::OwnersStillActive));
+
+        advance_clock(&mut context, INACTIVITY).await;
+        send(&mut context, &[initiate], &[&recovery_key]).await.unwrap();
+
+        let err = send(&mut context, &[finalize.clone()], &[&recovery_key])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::RecoveryChallengeActive));
+
+        advance_clock(&mut context, MultiSig::RECOVERY_CHALLENGE_SECONDS).await;
+        send(&mut context, &[finalize], &[&recovery_key]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let multi_sig = state.multi_sig.unwrap();
+        assert_eq!(multi_sig.owners, vec![recovery_key.pubkey()]);
+        assert_eq!(multi_sig.threshold, 1);
+        assert!(state.recovery_initiated_at.is_none());
+        let actions: Vec<u8> = state.emergency_logs.iter().map(|log| log.action).collect();
+        assert_eq!(
+            actions,
+            vec![EmergencyActionLog::RECOVERY_INITIATED, EmergencyActionLog::RECOVERY_FINALIZED]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_owner_veto_resets_clock() {
+        let mut context = program_test().start_with_context().await;
+        let recovery_key = Keypair::new();
+        let key = recovery_key.pubkey();
+        let vault = setup(&mut context, &key).await;
+        let payer = context.payer.pubkey();
+        let initiate = recovery_ix(&vault, &key, VaultInstruction::InitiateRecovery);
+
+        advance_clock(&mut context, INACTIVITY).await;
+        send(&mut context, &[initiate.clone()], &[&recovery_key]).await.unwrap();
+        send(&mut context, &[recovery_ix(&vault, &payer, VaultInstruction::VetoRecovery)], &[])
+            .await
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.recovery_initiated_at.is_none());
+        assert_eq!(state.emergency_logs[1].action, EmergencyActionLog::RECOVERY_VETOED);
+
+        // The veto counts as owner activity, so a full inactivity period starts over
+        advance_clock(&mut context, INACTIVITY - 1).await;
+        let err = send(&mut context, &[initiate], &[&recovery_key])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::OwnersStillActive));
+
+        advance_clock(&mut context, MultiSig::RECOVERY_CHALLENGE_SECONDS).await;
+        let finalize = recovery_ix(&vault, &key, VaultInstruction::FinalizeRecovery);
+        let err = send(&mut context, &[finalize], &[&recovery_key])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::RecoveryNotInitiated));
+        assert_eq!(fetch_vault(&mut context, &vault).await.multi_sig.unwrap().owners, vec![payer]);
+    }
+
+    #[tokio::test]
+    async fn test_challenge_window_follows_param() {
+        let mut context = program_test().start_with_context().await;
+        let recovery_key = Keypair::new();
+        let key = recovery_key.pubkey();
+        let vault = setup(&mut context, &key).await;
+        let challenge = ParamKey::MIN_RECOVERY_CHALLENGE;
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::RecoveryChallengeSeconds, challenge);
+        store_vault(&mut context, &vault, &state).await;
+
+        advance_clock(&mut context, INACTIVITY).await;
+        let initiate = recovery_ix(&vault, &key, VaultInstruction::InitiateRecovery);
+        send(&mut context, &[initiate], &[&recovery_key]).await.unwrap();
+        advance_clock(&mut context, challenge as i64 - 60).await;
+        let finalize = recovery_ix(&vault, &key, VaultInstruction::FinalizeRecovery);
+        let err = send(&mut context, &[finalize.clone()], &[&recovery_key])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::RecoveryChallengeActive));
+
+        // Well short of the default three days
+        advance_clock(&mut context, 60).await;
+        send(&mut context, &[finalize], &[&recovery_key]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig.unwrap().owners, vec![key]);
+    }
+
+    #[tokio::test]
+    async fn test_only_recovery_key_can_initiate() {
+        let mut context = program_test().start_with_context().await;
+        let recovery_key = Keypair::new();
+        let vault = setup(&mut context, &recovery_key.pubkey()).await;
+        advance_clock(&mut context, INACTIVITY).await;
+
+        let impostor = Keypair::new();
+        let ix = recovery_ix(&vault, &impostor.pubkey(), VaultInstruction::InitiateRecovery);
+        let err = send(&mut context, &[ix], &[&impostor]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+    }
+}
@@ -0,0 +1,304 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+// Every instruction here is laid out by client::resolve_accounts alone, so a processor
+// that starts reading its accounts differently breaks the matching test.
+#[cfg(test)]
+mod resolver_tests {
+    use super::common::*;
+    use solana_program::{instruction::AccountMeta, pubkey::Pubkey, system_instruction, sysvar};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        client::{self, ResolveContext, ResolveError},
+        instruction::VaultInstruction,
+        state::{GlobalConfig, ParamKey, TransactionAccount, VestingSchedule},
+        view::ViewQuery,
+        PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 11;
+    const FUNDED: u64 = 100_000;
+
+    struct Fixture {
+        context: ProgramTestContext,
+        vault: Pubkey,
+        mint: Pubkey,
+    }
+
+    impl Fixture {
+        fn ctx(&self) -> ResolveContext {
+            ResolveContext {
+                vault: self.vault,
+                authority: self.context.payer.pubkey(),
+                mint: Some(self.mint),
+                multisig_nonce: Some(NONCE),
+                ..ResolveContext::default()
+            }
+        }
+
+        async fn run(&mut self, instruction: VaultInstruction, ctx: &ResolveContext) {
+            let ix = client::resolve_instruction(&instruction, ctx).unwrap();
+            send(&mut self.context, &[ix], &[]).await.unwrap();
+        }
+
+        // Proposes `instruction`, as resolved for the multisig signer, and executes it
+        async fn run_as_multisig(&mut self, instruction: VaultInstruction, transaction_id: u64) {
+            let ctx = self.ctx();
+            let inner = client::resolve_instruction(&instruction, &ctx).unwrap();
+            let accounts: Vec<_> = inner
+                .accounts
+                .iter()
+                .map(|meta| TransactionAccount {
+                    pubkey: meta.pubkey,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+                .collect();
+            let create = VaultInstruction::CreateMultiSigTransaction {
+                program_id: PROGRAM_ID,
+                accounts,
+                data: inner.data.clone(),
+            };
+            self.run(create, &ctx).await;
+            // Execution already passes the vault, the signer and the clock
+            let signer = client::multisig_signer_address(&self.vault, NONCE);
+            let remaining = inner
+                .accounts
+                .iter()
+                .filter(|meta| ![self.vault, signer, sysvar::clock::id()].contains(&meta.pubkey))
+                .map(|meta| AccountMeta { is_signer: false, ..meta.clone() })
+                .collect();
+            let execute = ResolveContext {
+                program: Some(PROGRAM_ID),
+                remaining,
+                ..self.ctx()
+            };
+            self.run(VaultInstruction::ExecuteMultiSigTransaction { transaction_id }, &execute)
+                .await;
+        }
+    }
+
+    // Vault holding FUNDED of one mint, with a 1-of-1 multisig of the payer
+    async fn fixture() -> Fixture {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, FUNDED).await;
+        let mut fixture = Fixture {
+            context,
+            vault,
+            mint,
+        };
+        let ctx = fixture.ctx();
+        fixture.run(VaultInstruction::Deposite { amount: FUNDED }, &ctx).await;
+        let multisig = VaultInstruction::InitializeMultiSig {
+            owners: vec![ctx.authority],
+            threshold: 1,
+            nonce: NONCE,
+        };
+        fixture.run(multisig, &ctx).await;
+        fixture
+    }
+
+    #[test]
+    fn test_missing_context_is_reported() {
+        let ctx = ResolveContext::default();
+        let deposit = VaultInstruction::Deposite { amount: 1 };
+        assert_eq!(
+            client::resolve_accounts(&deposit, &ctx),
+            Err(ResolveError::Missing("mint"))
+        );
+        let set_param = VaultInstruction::SetParam {
+            key: ParamKey::DepositFeeBps,
+            value: 0,
+        };
+        assert_eq!(
+            client::resolve_accounts(&set_param, &ctx),
+            Err(ResolveError::Missing("multisig_nonce"))
+        );
+        // Stubs read nothing but the trailing accounts they are handed
+        let trailing = ResolveContext {
+            remaining: vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+            ..ResolveContext::default()
+        };
+        let stub = VaultInstruction::TransferAuthority {
+            new_authority: Pubkey::new_unique(),
+        };
+        let accounts = client::resolve_accounts(&stub, &trailing);
+        assert_eq!(accounts, Ok(trailing.remaining.clone()));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_initialize_vault() {
+        let mut fixture = fixture().await;
+        let instruction = VaultInstruction::InitializeVault {
+            index: 1,
+            allow_same_as_authority: true,
+        };
+        let ctx = fixture.ctx();
+        fixture.run(instruction, &ctx).await;
+        let (second, _) = vault_pda(&ctx.authority, 1);
+        fetch_vault(&mut fixture.context, &second).await;
+    }
+
+    #[tokio::test]
+    async fn test_resolve_deposit_and_withdraw() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        advance_clock(&mut fixture.context, 1).await;
+        fixture.run(VaultInstruction::Withdraw { amount: 400 }, &ctx).await;
+        fixture.run(VaultInstruction::Deposite { amount: 100 }, &ctx).await;
+        let state = fetch_vault(&mut fixture.context, &fixture.vault).await;
+        assert_eq!(state.tracked_balance(&fixture.mint), FUNDED - 300);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_transfer() {
+        let mut fixture = fixture().await;
+        let payer = fixture.context.payer.pubkey();
+        let fund = system_instruction::transfer(&payer, &fixture.vault, 1_000_000_000);
+        send(&mut fixture.context, &[fund], &[]).await.unwrap();
+        let ctx = fixture.ctx();
+        fixture.run(VaultInstruction::SweepUntrackedLamports, &ctx).await;
+        let recipient = Pubkey::new_unique();
+        let instruction = VaultInstruction::Transfer {
+            recipient,
+            amount: 100_000_000,
+        };
+        fixture.run(instruction, &ctx).await;
+        let account = fixture.context.banks_client.get_account(recipient).await.unwrap();
+        assert_eq!(account.unwrap().lamports, 100_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_add_supported_token() {
+        let mut fixture = fixture().await;
+        let mint = create_mint(&mut fixture.context, 9).await.pubkey();
+        let instruction = VaultInstruction::AddSupportedToken {
+            mint,
+            bump: 0,
+            idempotent: false,
+        };
+        let ctx = fixture.ctx();
+        fixture.run(instruction, &ctx).await;
+        let vault_tokens = get_associated_token_address(&fixture.vault, &mint);
+        assert_eq!(token_balance(&mut fixture.context, &vault_tokens).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_pause_and_unpause() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        fixture.run(VaultInstruction::PauseVault, &ctx).await;
+        assert!(fetch_vault(&mut fixture.context, &fixture.vault).await.paused);
+        fixture.run(VaultInstruction::UnpauseVault, &ctx).await;
+        assert!(!fetch_vault(&mut fixture.context, &fixture.vault).await.paused);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_emergency_withdraw() {
+        let mut fixture = fixture().await;
+        let recipient = Pubkey::new_unique();
+        let recipient_tokens =
+            create_funded_ata(&mut fixture.context, &fixture.mint, &recipient, 0).await;
+        let instruction = VaultInstruction::EmergencyWithdraw {
+            token_mint: fixture.mint,
+            amount: 250,
+        };
+        let ctx = ResolveContext {
+            recipient: Some(recipient),
+            ..fixture.ctx()
+        };
+        fixture.run(instruction, &ctx).await;
+        assert_eq!(token_balance(&mut fixture.context, &recipient_tokens).await, 250);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_time_lock_claim() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        let create = VaultInstruction::CreateTimeLock {
+            beneficiary: ctx.authority,
+            mint: fixture.mint,
+            amount: 1_000,
+            duration: 100,
+            cliff_duration: None,
+            schedule: VestingSchedule::Linear,
+            revocable: false,
+        };
+        fixture.run(create, &ctx).await;
+        advance_clock(&mut fixture.context, 200).await;
+        let claim = VaultInstruction::ClaimTimeLockPartial {
+            time_lock_id: 0,
+            amount: 600,
+        };
+        fixture.run(claim, &ctx).await;
+        let user = get_associated_token_address(&ctx.authority, &fixture.mint);
+        assert_eq!(token_balance(&mut fixture.context, &user).await, 600);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_multisig_signed_set_param() {
+        let mut fixture = fixture().await;
+        let set_param = VaultInstruction::SetParam {
+            key: ParamKey::MinDepositAmount,
+            value: 10,
+        };
+        fixture.run_as_multisig(set_param, 0).await;
+        let state = fetch_vault(&mut fixture.context, &fixture.vault).await;
+        assert_eq!(state.param(ParamKey::MinDepositAmount), 10);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_cancel_and_prune() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        let accounts = vec![TransactionAccount {
+            pubkey: fixture.vault,
+            is_signer: false,
+            is_writable: false,
+        }];
+        let create = VaultInstruction::CreateMultiSigTransaction {
+            program_id: Pubkey::new_unique(),
+            accounts,
+            data: vec![1],
+        };
+        fixture.run(create, &ctx).await;
+        let cancel = VaultInstruction::CancelMultiSigTransaction { transaction_id: 0 };
+        fixture.run(cancel, &ctx).await;
+        fixture.run(VaultInstruction::PruneMultiSigTransactions, &ctx).await;
+        let state = fetch_vault(&mut fixture.context, &fixture.vault).await;
+        assert!(state.multisig_transaction(0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_view() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        let query = ViewQuery::FreeBalance { mint: fixture.mint };
+        fixture.run(VaultInstruction::View { query }, &ctx).await;
+    }
+
+    #[tokio::test]
+    async fn test_resolve_global_config_admin_calls() {
+        let mut fixture = fixture().await;
+        let ctx = fixture.ctx();
+        let initialize = VaultInstruction::InitializeGlobalConfig {
+            admin: fixture.vault,
+            max_fee_bps: 1_000,
+            allowed_programs: vec![],
+            protocol_fee_recipient: Pubkey::new_unique(),
+            protocol_fee_bps: 0,
+        };
+        fixture.run(initialize, &ctx).await;
+
+        let acknowledge = VaultInstruction::AcknowledgeUpgradeAuthority {
+            expected_upgrade_authority: None,
+            upgrade_check_threshold: 5_000,
+        };
+        fixture.run_as_multisig(acknowledge, 0).await;
+        let config: GlobalConfig = fetch_global_config(&mut fixture.context).await;
+        assert_eq!(config.upgrade_check_threshold, 5_000);
+    }
+}
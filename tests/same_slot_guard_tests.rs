@@ -0,0 +1,86 @@
+mod common;
+
+#[cfg(test)]
+mod same_slot_guard_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+    };
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use vault_program::{
+        instruction::VaultInstruction, state::VaultConfig, VaultError, PROGRAM_ID,
+    };
+
+    fn update_config_ix(vault: &Pubkey, authority: &Pubkey, same_slot_guard: bool) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UpdateVaultConfig {
+                config: VaultConfig {
+                    same_slot_guard,
+                    ..Default::default()
+                },
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_same_slot_round_trip_allowed_by_default() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+
+        send(
+            &mut context,
+            &[deposit_ix(&vault, &mint, &user, 500), withdraw_ix(&vault, &mint, &user, 500)],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_same_slot_round_trip_rejected_with_guard() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+
+        send(&mut context, &[update_config_ix(&vault, &user, true)], &[])
+            .await
+            .unwrap();
+
+        let err = send(
+            &mut context,
+            &[deposit_ix(&vault, &mint, &user, 500), withdraw_ix(&vault, &mint, &user, 500)],
+            &[],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(VaultError::SameSlotWithdrawal as u32)
+            )
+        );
+
+        // A later slot is fine
+        send(&mut context, &[deposit_ix(&vault, &mint, &user, 500)], &[])
+            .await
+            .unwrap();
+        let slot = context.banks_client.get_root_slot().await.unwrap();
+        context.warp_to_slot(slot + 2).unwrap();
+        send(&mut context, &[withdraw_ix(&vault, &mint, &user, 500)], &[])
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+mod common;
+
+#[cfg(test)]
+mod setup_stage_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction, state::SetupStage, VaultError, PROGRAM_ID,
+    };
+
+    // Same accounts as `initialize_ix`, with the V2 data
+    async fn create_gated_vault(context: &mut ProgramTestContext, skip_setup_gate: bool) -> Pubkey {
+        let authority = context.payer.pubkey();
+        let (vault, _) = vault_pda(&authority, 0);
+        let mut ix = initialize_ix(&vault, &authority, &authority, 0, true);
+        ix.data = VaultInstruction::InitializeVaultV2 {
+            index: 0,
+            allow_same_as_authority: true,
+            skip_setup_gate,
+        }
+        .try_to_vec()
+        .unwrap();
+        send(context, &[ix], &[]).await.unwrap();
+        vault
+    }
+
+    fn finalize_ix(vault: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::FinalizeSetup.try_to_vec().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gated_vault_holds_funds_until_finalized() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_gated_vault(&mut context, false).await;
+        let authority = context.payer.pubkey();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.setup_gate);
+        assert_eq!(state.setup_stage, SetupStage::Initialized);
+
+        let mint = create_mint(&mut context, 6).await.pubkey();
+        send(&mut context, &[add_supported_token_ix(&vault, &mint, &authority, &authority)], &[])
+            .await
+            .unwrap();
+        create_funded_ata(&mut context, &mint, &authority, 1_000).await;
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.setup_stage, SetupStage::TokensConfigured);
+
+        let err = send(&mut context, &[deposit_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VaultNotReady));
+
+        // Nothing to finalize without a multisig
+        let err = send(&mut context, &[finalize_ix(&vault, &authority)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VaultNotReady));
+
+        let multisig = initialize_multisig_ix(&vault, &authority, vec![authority], 1, 3);
+        send(&mut context, &[multisig], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.setup_stage, SetupStage::MultisigConfigured);
+
+        send(&mut context, &[finalize_ix(&vault, &authority)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.setup_stage, SetupStage::Ready);
+        send(&mut context, &[deposit_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 100);
+
+        // The multisig can't be swapped out by the authority any more
+        let multisig = initialize_multisig_ix(&vault, &authority, vec![authority], 1, 4);
+        let err = send(&mut context, &[multisig], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::SetupFinalized));
+    }
+
+    #[tokio::test]
+    async fn test_skipping_gate_allows_deposits() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_gated_vault(&mut context, true).await;
+        let authority = context.payer.pubkey();
+        let mint = create_mint(&mut context, 6).await.pubkey();
+        send(&mut context, &[add_supported_token_ix(&vault, &mint, &authority, &authority)], &[])
+            .await
+            .unwrap();
+        create_funded_ata(&mut context, &mint, &authority, 1_000).await;
+
+        send(&mut context, &[deposit_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.setup_gate);
+        assert_eq!(state.setup_stage, SetupStage::TokensConfigured);
+    }
+}
@@ -0,0 +1,134 @@
+mod common;
+
+#[cfg(test)]
+mod signed_approval_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        ed25519_instruction::new_ed25519_instruction,
+        signature::{Keypair, Signer},
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{MultiSigTransaction, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 12;
+
+    // What a custody API hands back: a detached ed25519 signature, here wrapped straight
+    // into the ed25519 program instruction
+    fn ed25519_ix(signer: &Keypair, message: &[u8]) -> Instruction {
+        let keypair = ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).unwrap();
+        new_ed25519_instruction(&keypair, message)
+    }
+
+    fn approve_with_signature_ix(
+        vault: &Pubkey,
+        owner: &Pubkey,
+        transaction_id: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(sysvar::instructions::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::ApproveWithSignature {
+                transaction_id,
+                owner: *owner,
+                signature_index: 0,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // 2-of-2 multisig of the payer and `custody`, with transaction 0 proposed by the payer
+    async fn setup(context: &mut ProgramTestContext, custody: &Keypair) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let owners = vec![payer, custody.pubkey()];
+        let accounts = vec![TransactionAccount {
+            pubkey: vault,
+            is_signer: false,
+            is_writable: false,
+        }];
+        send(
+            context,
+            &[
+                initialize_multisig_ix(&vault, &payer, owners, 2, NONCE),
+                create_multisig_tx_ix(&vault, &payer, Pubkey::new_unique(), accounts, vec![1]),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_approval_carried_by_ed25519_signature() {
+        let mut context = program_test().start_with_context().await;
+        let custody = Keypair::new();
+        let vault = setup(&mut context, &custody).await;
+        let seqno = fetch_vault(&mut context, &vault).await.owner_set_seqno;
+        assert_eq!(seqno, 1);
+
+        // Only the fee payer signs the transaction; custody's key never does
+        let message = MultiSigTransaction::approval_message(&vault, 0, seqno);
+        let approval = [
+            ed25519_ix(&custody, &message),
+            approve_with_signature_ix(&vault, &custody.pubkey(), 0),
+        ];
+        send(&mut context, &approval, &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multisig_transaction(0).unwrap().signers, vec![true, true]);
+        assert!(state.threshold_reached(0).is_some());
+
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &approval, &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(1, VaultError::TransactionAlreadySigned));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_message_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let custody = Keypair::new();
+        let vault = setup(&mut context, &custody).await;
+        let approve = approve_with_signature_ix(&vault, &custody.pubkey(), 0);
+
+        let wrong_messages = [
+            MultiSigTransaction::approval_message(&vault, 1, 1),
+            MultiSigTransaction::approval_message(&Pubkey::new_unique(), 0, 1),
+            // Signed under the owner set before the current one
+            MultiSigTransaction::approval_message(&vault, 0, 0),
+        ];
+        for message in wrong_messages {
+            let ixs = [ed25519_ix(&custody, &message), approve.clone()];
+            let err = send(&mut context, &ixs, &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(1, VaultError::InvalidApprovalSignature));
+        }
+
+        // The right message signed by someone else
+        let message = MultiSigTransaction::approval_message(&vault, 0, 1);
+        let ixs = [ed25519_ix(&Keypair::new(), &message), approve.clone()];
+        let err = send(&mut context, &ixs, &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(1, VaultError::InvalidApprovalSignature));
+
+        // And no signature at all
+        let err = send(&mut context, &[approve], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidApprovalSignature));
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multisig_transaction(0).unwrap().signers, vec![true, false]);
+    }
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_sdk::{account::Account, signature::Signer};
+    use vault_program::client::VaultSnapshot;
+
+    #[tokio::test]
+    async fn test_snapshot_restores_identical_state() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &user, 400)], &[])
+            .await
+            .unwrap();
+
+        let account = context
+            .banks_client
+            .get_account(vault)
+            .await
+            .unwrap()
+            .unwrap();
+        let snapshot =
+            VaultSnapshot::capture(vault, account.owner, account.lamports, &account.data).unwrap();
+        let json = snapshot.to_json().unwrap();
+
+        let restored = VaultSnapshot::from_json(&json).unwrap();
+        let mut test = program_test();
+        test.add_account(
+            restored.address,
+            Account {
+                lamports: restored.lamports,
+                data: restored.account_data().unwrap(),
+                owner: restored.owner,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        let mut replay = test.start_with_context().await;
+
+        let replayed_account = replay
+            .banks_client
+            .get_account(vault)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replayed_account.data, account.data);
+
+        let original = fetch_vault(&mut context, &vault).await;
+        let replayed = fetch_vault(&mut replay, &vault).await;
+        assert_eq!(replayed.try_to_vec().unwrap(), original.try_to_vec().unwrap());
+        assert_eq!(replayed.token_stats()[0].total_deposited, 400);
+    }
+
+    #[tokio::test]
+    async fn test_edited_data_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let account = context
+            .banks_client
+            .get_account(vault)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let mut snapshot =
+            VaultSnapshot::capture(vault, account.owner, account.lamports, &account.data).unwrap();
+        snapshot.state.paused = !snapshot.state.paused;
+        assert!(snapshot.account_data().is_err());
+    }
+}
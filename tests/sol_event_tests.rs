@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod sol_event_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    };
+    use solana_sdk::{signature::Signer, transaction::Transaction};
+    use vault_program::{instruction::VaultInstruction, PROGRAM_ID};
+
+    const AMOUNT: u64 = 1_000_000_000;
+
+    #[tokio::test]
+    async fn test_sol_transfer_event_carries_slot() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 60).await;
+        // Lamports sent straight to the vault are only paid out once swept in
+        let sweep = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::SweepUntrackedLamports.try_to_vec().unwrap(),
+        };
+        send(
+            &mut context,
+            &[system_instruction::transfer(&payer, &vault, AMOUNT), sweep],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let recipient = Pubkey::new_unique();
+        let transfer = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::Transfer {
+                recipient,
+                amount: AMOUNT,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[transfer],
+            Some(&payer),
+            &[&context.payer],
+            blockhash,
+        );
+        let processed = context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        assert!(processed.result.is_ok());
+
+        let logs = processed.metadata.unwrap().log_messages;
+        let event: serde_json::Value = logs
+            .iter()
+            .find_map(|line| line.strip_prefix("Program log: EVENT: "))
+            .map(|json| serde_json::from_str(json).unwrap())
+            .unwrap();
+        assert_eq!(event["base"]["event_type"], "sol_transferred");
+        assert!(event["base"]["slot"].as_u64().unwrap() > 0);
+        assert!(event.get("token_mint").is_none());
+        assert_eq!(event["amount"], AMOUNT);
+
+        let vault_lamports = context
+            .banks_client
+            .get_balance(vault)
+            .await
+            .unwrap();
+        assert_eq!(event["vault_lamports"], vault_lamports);
+    }
+}
@@ -0,0 +1,434 @@
+#![cfg(feature = "state-serde")]
+
+#[cfg(test)]
+mod state_serde_tests {
+    use borsh::BorshSerialize;
+    use serde::{de::DeserializeOwned, Serialize};
+    use solana_program::pubkey::Pubkey;
+    use vault_program::state::*;
+
+    // Borsh bytes are compared because most state types don't implement PartialEq
+    fn round_trip<T: Serialize + DeserializeOwned + BorshSerialize>(name: &str, value: &T) {
+        let json = serde_json::to_string(value).unwrap();
+        let back: T = serde_json::from_str(&json)
+            .unwrap_or_else(|e| panic!("{} failed to deserialize: {}", name, e));
+        assert_eq!(
+            back.try_to_vec().unwrap(),
+            value.try_to_vec().unwrap(),
+            "{} changed across JSON round trip",
+            name
+        );
+    }
+
+    fn key() -> Pubkey {
+        Pubkey::new_unique()
+    }
+
+    fn populated_vault() -> Vault {
+        Vault {
+            authority: key(),
+            bump: 254,
+            emergency_admin: key(),
+            paused: true,
+            supported_tokens: vec![SupportedToken {
+                mint: key(),
+                bump: 1,
+                total_deposited: 10,
+                total_withdrawn: 4,
+                is_active: true,
+                deposit_count: 2,
+                withdrawal_count: 1,
+                decimals: 6,
+            }],
+            token_balances: vec![TokenBalance {
+                mint: key(),
+                balance: 6,
+                last_updated: 1_700_000_000,
+            }],
+            time_locks: vec![TimeLock {
+                id: 0,
+                beneficiary: key(),
+                mint: key(),
+                amount: 100,
+                released_amount: 5,
+                start_time: 1,
+                cliff_time: 3,
+                end_time: 11,
+                schedule: VestingSchedule::CliffAll,
+                revocable: true,
+            }],
+            proposals: vec![Proposal {
+                id: 1,
+                instruction_data: vec![1, 2, 3],
+                approvals: vec![key(), key()],
+                executed: false,
+                created_at: 9,
+                proposer: key(),
+            }],
+            next_proposal_id: 2,
+            fee_config: FeeConfig {
+                deposit_fee_bps: 10,
+                withdrawal_fee_bps: 20,
+                fee_recipient: key(),
+            },
+            total_value_locked: 6,
+            total_fees_collected: 1,
+            legacy_mint: Some(key()),
+            legacy_total_deposited: 3,
+            governance_config: Some(GovernanceConfig {
+                voting_token_mint: key(),
+                quorum_threshold: 5,
+                proposal_threshold: 7,
+                voting_period: 100,
+                time_lock_delay: 50,
+                execution_threshold: 6,
+                timelock_delay: 50,
+            }),
+            governance_proposals: vec![GovernanceProposal {
+                id: 4,
+                proposer: key(),
+                title: "title".to_string(),
+                description: "description".to_string(),
+                instructions: vec![vec![9, 9]],
+                for_votes: 1,
+                against_votes: 2,
+                abstain_votes: 3,
+                created_at: 4,
+                end_time: 5,
+                executed: false,
+                queued: true,
+                eta: Some(6),
+                start_time: 4,
+                cancelled: false,
+            }],
+            next_governance_proposal_id: 5,
+            vote_records: vec![VoteRecord {
+                proposal_id: 4,
+                voter: key(),
+                vote_type: VoteType::Against,
+                voting_power: 8,
+                voted_at: 4,
+            }],
+            voter_registry: vec![VoterRegistry {
+                voter: key(),
+                ..Default::default()
+            }],
+            multi_sig: Some(MultiSig {
+                owners: vec![key(), key(), key()],
+                threshold: 2,
+                nonce: 3,
+                bump: 255,
+            }),
+            multi_sig_transactions: vec![MultiSigTransaction {
+                multisig: key(),
+                program_id: key(),
+                accounts: vec![TransactionAccount {
+                    pubkey: key(),
+                    is_signer: true,
+                    is_writable: false,
+                }],
+                data: vec![7],
+                signers: vec![true, false, false],
+                did_execute: false,
+                proposer: key(),
+                created_at: 12,
+            }],
+            yield_strategies: vec![YieldStrategyConfig {
+                token_mint: key(),
+                strategy_program: key(),
+                ..Default::default()
+            }],
+            emergency_logs: vec![EmergencyActionLog {
+                timestamp: 13,
+                admin: key(),
+                action: 2,
+                details: vec![4, 5],
+            }],
+            config: VaultConfig {
+                same_slot_guard: true,
+            },
+            user_action_slots: vec![UserActionSlot {
+                user: key(),
+                last_action_slot: 99,
+            }],
+            index: 3,
+            next_time_lock_id: 1,
+            state_version: Vault::STATE_VERSION,
+            max_proposals_per_day: 5,
+            proposal_windows: vec![ProposalWindow {
+                proposer: key(),
+                window_start: 1_700_000_000,
+                proposals_in_window: 2,
+            }],
+            attestations: vec![Attestation {
+                message_hash: [9; 32],
+                transaction_id: 4,
+                approvers: vec![key()],
+                attested_at: 1_700_000_000,
+            }],
+            multisig_recovery: Some(RecoveryConfig {
+                recovery_key: key(),
+                inactivity_period: 86_400,
+            }),
+            last_owner_activity: 1_700_000_000,
+            recovery_initiated_at: Some(1_700_086_400),
+            recent_actions: [ActionRecord {
+                kind: ActionKind::Deposit,
+                actor: key(),
+                mint: key(),
+                amount: 5,
+                timestamp: 1_700_000_000,
+            }; RECENT_ACTIONS_LEN],
+            recent_actions_head: 3,
+            execution_tips: vec![ExecutionTip {
+                transaction_id: 0,
+                funder: key(),
+                lamports: 5_000,
+            }],
+            cancelled_transactions: vec![1],
+            paused_at: 1_700_000_000,
+            max_pause_duration: 86_400,
+            streams_exempt_from_pause: true,
+            stream_claim_fee_bps: 25,
+            next_transaction_id: 3,
+            multi_sig_transaction_ids: vec![2],
+            execution_delay_seconds: 3_600,
+            threshold_reached: vec![ThresholdReached {
+                transaction_id: 2,
+                reached_at: 1_700_000_100,
+                expedited: true,
+            }],
+            params: vec![Param {
+                key: ParamKey::WithdrawalFeeBps as u8,
+                value: 30,
+            }],
+            templates: vec![TransactionTemplate {
+                id: 2,
+                name: "payroll".to_string(),
+                program_id: key(),
+                accounts: vec![TransactionAccount {
+                    pubkey: key(),
+                    is_signer: false,
+                    is_writable: true,
+                }],
+                data_prefix: vec![2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                mutable_ranges: vec![ByteRange { offset: 4, len: 8 }],
+            }],
+            next_template_id: 3,
+            voting_model: VotingModel::Quadratic,
+            proposal_snapshots: vec![ProposalSnapshot {
+                proposal_id: 4,
+                effective_supply: 9_000,
+            }],
+            vote_raw_balances: vec![250_000],
+            vote_locks: vec![VoteLock {
+                voter: key(),
+                amount: 40_000,
+                locked_at: 1_700_000_000,
+                unlock_at: 1_731_536_000,
+            }],
+            max_lock_duration: 126_144_000,
+            max_boost_bps: 15_000,
+            swap_history: vec![SwapRecord {
+                input_mint: key(),
+                output_mint: key(),
+                amount_in_measured: 300,
+                amount_out_measured: 290,
+                timestamp: 1_700_000_500,
+                executor: key(),
+            }],
+            strategy_allocation_caps: vec![3_000],
+            strategy_statuses: vec![StrategyStatus::DepositsPaused],
+            multisig_owner_since: vec![1_700_000_000],
+            owner_set_seqno: 3,
+            accrued_fees: vec![TokenBalance {
+                mint: key(),
+                balance: 25,
+                last_updated: 1_700_000_400,
+            }],
+            transaction_expiries: vec![TransactionExpiry {
+                transaction_id: 0,
+                expires_at: 1_700_003_600,
+            }],
+            tracked_lamports: 2_000_000_000,
+            outflow_windows: vec![OutflowWindow {
+                mint: key(),
+                window_start: 1_700_000_000,
+                opening_balance: 1_000,
+                amount_out: 100,
+            }],
+            circuit_breaker_tripped: false,
+            tvl_cumulative: 3_600_000_000_000,
+            tvl_last_updated: 1_700_003_600,
+            withdrawal_tickets: vec![WithdrawalTicket {
+                id: 0,
+                owner: key(),
+                mint: key(),
+                amount: 250,
+                requested_at: 1_700_000_000,
+                available_at: 1_700_086_400,
+            }],
+            next_withdrawal_ticket_id: 1,
+            last_heartbeat_slot: 250_000_000,
+            supported_token_programs: vec![key()],
+            council_reviews: vec![CouncilReview {
+                proposal_id: 0,
+                approved_at: Some(1_700_003_600),
+            }],
+            setup_stage: SetupStage::Ready,
+            setup_gate: true,
+            settled_items: vec![SettledItem {
+                kind: SettledKind::TimeLockVested,
+                id: 0,
+                settled_at: 1_700_003_600,
+            }],
+            tick_cursor: 2,
+            transaction_roles: vec![TransactionRoles {
+                transaction_id: 0,
+                roles: vec![AccountRole::VaultPda, AccountRole::MultisigSigner],
+            }],
+            transaction_assertions: vec![TransactionAssertions {
+                transaction_id: 0,
+                assertions: vec![BalanceAssertion {
+                    account: key(),
+                    min_post_balance: 1_000,
+                    is_token: false,
+                }],
+            }],
+            strategy_accounts: vec![StrategyAccounts {
+                token_mint: key(),
+                accounts: vec![key(), key()],
+            }],
+            mint_minimums: vec![MintMinimums {
+                mint: key(),
+                min_deposit: 1_000,
+                min_withdrawal: 500,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_vault_round_trip() {
+        let vault = populated_vault();
+        round_trip("Vault", &vault);
+        round_trip("Vault::default", &Vault::default());
+        for token in &vault.supported_tokens {
+            round_trip("SupportedToken", token);
+        }
+        round_trip("TokenBalance", &vault.token_balances[0]);
+        round_trip("TimeLock", &vault.time_locks[0]);
+        round_trip("Proposal", &vault.proposals[0]);
+        round_trip("FeeConfig", &vault.fee_config);
+        round_trip("GovernanceConfig", vault.governance_config.as_ref().unwrap());
+        round_trip("GovernanceProposal", &vault.governance_proposals[0]);
+        round_trip("VoteRecord", &vault.vote_records[0]);
+        round_trip("VoterRegistry", &vault.voter_registry[0]);
+        round_trip("MultiSig", vault.multi_sig.as_ref().unwrap());
+        round_trip("MultiSigTransaction", &vault.multi_sig_transactions[0]);
+        round_trip("TransactionAccount", &vault.multi_sig_transactions[0].accounts[0]);
+        round_trip("YieldStrategyConfig", &vault.yield_strategies[0]);
+        round_trip("EmergencyActionLog", &vault.emergency_logs[0]);
+        round_trip("VaultConfig", &vault.config);
+        round_trip("UserActionSlot", &vault.user_action_slots[0]);
+        round_trip("ProposalWindow", &vault.proposal_windows[0]);
+        round_trip("Attestation", &vault.attestations[0]);
+        round_trip("RecoveryConfig", vault.multisig_recovery.as_ref().unwrap());
+        round_trip("ActionRecord", &vault.recent_actions[0]);
+        round_trip("ExecutionTip", &vault.execution_tips[0]);
+        round_trip("ThresholdReached", &vault.threshold_reached[0]);
+        round_trip("Param", &vault.params[0]);
+        round_trip("MintMinimums", &vault.mint_minimums[0]);
+        round_trip("TransactionTemplate", &vault.templates[0]);
+        round_trip("ByteRange", &vault.templates[0].mutable_ranges[0]);
+        round_trip("ProposalSnapshot", &vault.proposal_snapshots[0]);
+        round_trip("VotingModel", &vault.voting_model);
+        round_trip("VoteLock", &vault.vote_locks[0]);
+        round_trip("SwapRecord", &vault.swap_history[0]);
+        round_trip("StrategyStatus", &vault.strategy_statuses[0]);
+        round_trip("VoteType", &VoteType::Abstain);
+        round_trip("VestingSchedule", &VestingSchedule::Linear);
+        round_trip(
+            "GovernanceInstruction",
+            &GovernanceInstruction {
+                program_id: Pubkey::new_unique(),
+                accounts: vault.multi_sig_transactions[0].accounts.clone(),
+                data: vec![1, 2, 3],
+            },
+        );
+        round_trip(
+            "GlobalConfig",
+            &GlobalConfig {
+                admin: key(),
+                max_fee_bps: 500,
+                allowed_programs: vec![key(), key()],
+                protocol_fee_recipient: key(),
+                bump: 253,
+                protocol_fee_bps: 1_000,
+                protocol_fees: vec![TokenBalance {
+                    mint: key(),
+                    balance: 12,
+                    last_updated: 1_700_000_000,
+                }],
+                expected_upgrade_authority: Some(key()),
+                upgrade_check_threshold: 1_000_000,
+            },
+        );
+    }
+
+    #[test]
+    fn test_legacy_state_round_trip() {
+        round_trip(
+            "VaultState",
+            &VaultState {
+                authority: key(),
+                emergency_admin: key(),
+                is_paused: false,
+                total_deposits: 1,
+                total_withdrawals: 2,
+                fee_recipient: key(),
+                deposit_fee_bps: 3,
+                withdrawal_fee_bps: 4,
+            },
+        );
+        round_trip(
+            "MultiSigState",
+            &MultiSigState {
+                authorities: vec![key()],
+                threshold: 1,
+                bump: 2,
+            },
+        );
+        round_trip(
+            "GovernanceState",
+            &GovernanceState {
+                voting_token_mint: key(),
+                quorum_threshold: 1,
+                proposal_threshold: 2,
+                voting_period: 3,
+                time_lock_delay: 4,
+                execution_threshold: 5,
+            },
+        );
+    }
+
+    #[test]
+    fn test_token_stats_round_trip() {
+        let stats = populated_vault().token_stats();
+        let json = serde_json::to_string(&stats).unwrap();
+        let back: Vec<TokenStats> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, stats);
+    }
+
+    #[test]
+    fn test_pubkeys_render_as_base58() {
+        let vault = populated_vault();
+        let json = serde_json::to_value(&vault).unwrap();
+        assert_eq!(json["authority"], vault.authority.to_string());
+        assert_eq!(json["legacy_mint"], vault.legacy_mint.unwrap().to_string());
+        assert_eq!(
+            json["multi_sig"]["owners"][0],
+            vault.multi_sig.as_ref().unwrap().owners[0].to_string()
+        );
+        assert!(serde_json::to_value(Vault::default()).unwrap()["legacy_mint"].is_null());
+    }
+}
@@ -0,0 +1,585 @@
+mod common;
+
+#[cfg(test)]
+mod strategy_pnl_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{StrategyAccounts, StrategyStatus, YieldStrategyConfig},
+        VaultError, PROGRAM_ID,
+    };
+
+    fn strategy_ix(
+        vault: &Pubkey,
+        mint: &Pubkey,
+        authority: &Pubkey,
+        instruction: VaultInstruction,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new_readonly(MOCK_DEFI_ID, false),
+            AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+        ];
+        // Forwarded to the mock strategy
+        accounts.extend(mock_defi::strategy_accounts(&MOCK_DEFI_ID, vault, mint));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    fn pinned_accounts(vault: &Pubkey, mint: &Pubkey) -> Vec<Pubkey> {
+        let accounts = mock_defi::strategy_accounts(&MOCK_DEFI_ID, vault, mint);
+        accounts.iter().map(|meta| meta.pubkey).collect()
+    }
+
+    fn deploy_ix(vault: &Pubkey, mint: &Pubkey, authority: &Pubkey, amount: u64) -> Instruction {
+        let instruction = VaultInstruction::DeployToStrategy {
+            token_mint: *mint,
+            amount,
+            strategy_data: mock_defi::deposit_data(amount),
+        };
+        strategy_ix(vault, mint, authority, instruction)
+    }
+
+    // Recalls `principal`; what comes back depends on the mock's withdraw_return_bps
+    fn recall_ix(vault: &Pubkey, mint: &Pubkey, authority: &Pubkey, principal: u64) -> Instruction {
+        let instruction = VaultInstruction::WithdrawFromStrategy {
+            token_mint: *mint,
+            amount: principal,
+            strategy_data: mock_defi::withdraw_data(principal),
+        };
+        strategy_ix(vault, mint, authority, instruction)
+    }
+
+    // From here on the mock pays back `bps` of what it is asked to withdraw
+    async fn set_withdraw_return(context: &mut ProgramTestContext, bps: u16) {
+        let mut config = mock_defi_config(context, &MOCK_DEFI_ID).await;
+        config.withdraw_return_bps = bps;
+        configure_mock_defi(context, &MOCK_DEFI_ID, &config);
+    }
+
+    fn simple_ix(vault: &Pubkey, authority: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    // Vault holding `deposit` of a mint with the mock strategy set for it. The strategy's
+    // token account starts with `strategy_float`, which lets it pay back more than it took.
+    async fn vault_with_strategy(
+        context: &mut ProgramTestContext,
+        deposit: u64,
+        strategy_float: u64,
+    ) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, deposit).await;
+        let authority = context.payer.pubkey();
+        fund_mock_pool(context, &MOCK_DEFI_ID, &mint, strategy_float).await;
+        let set_strategy = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::SetYieldStrategyV2 {
+                token_mint: mint,
+                strategy_program: MOCK_DEFI_ID,
+                accounts: pinned_accounts(&vault, &mint),
+            },
+        );
+        send(
+            context,
+            &[deposit_ix(&vault, &mint, &authority, deposit), set_strategy],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, mint)
+    }
+
+    async fn start() -> ProgramTestContext {
+        let mut test = program_test();
+        add_mock_defi(&mut test, MOCK_DEFI_ID, MockDefiConfig::default());
+        test.start_with_context().await
+    }
+
+    #[test]
+    fn test_record_withdrawal_bookkeeping() {
+        let mut strategy = YieldStrategyConfig {
+            deployed_amount: 1_000,
+            ..Default::default()
+        };
+
+        assert_eq!(strategy.record_withdrawal(400, 460), Ok(60));
+        assert_eq!(strategy.high_water_mark, 60);
+        assert!(!strategy.loss_pending);
+
+        // A later loss lowers realized pnl but never the peak
+        assert_eq!(strategy.record_withdrawal(400, 380), Ok(-20));
+        assert_eq!(strategy.realized_pnl, 40);
+        assert_eq!(strategy.high_water_mark, 60);
+        assert!(strategy.loss_pending);
+        assert_eq!(strategy.deployed_amount, 200);
+
+        // Principal beyond what was deployed is refused without touching the books
+        assert_eq!(
+            strategy.record_withdrawal(201, 201),
+            Err(VaultError::InvalidAmount)
+        );
+        assert_eq!(strategy.deployed_amount, 200);
+    }
+
+    #[tokio::test]
+    async fn test_loss_is_booked_and_blocks_deploys_until_acknowledged() {
+        let mut context = start().await;
+        let (vault, mint) = vault_with_strategy(&mut context, 1_000, 0).await;
+        let authority = context.payer.pubkey();
+
+        send(&mut context, &[deploy_ix(&vault, &mint, &authority, 600)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.token_balances[0].balance, 400);
+        assert_eq!(state.yield_strategies[0].deployed_amount, 600);
+        assert_eq!(state.total_value_locked, 1_000);
+
+        // The strategy was drained: only 450 of the 600 comes back
+        set_withdraw_return(&mut context, 7_500).await;
+        send(&mut context, &[recall_ix(&vault, &mint, &authority, 600)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let strategy = &state.yield_strategies[0];
+        assert_eq!(strategy.deployed_amount, 0);
+        assert_eq!(strategy.realized_pnl, -150);
+        assert_eq!(strategy.high_water_mark, 0);
+        assert!(strategy.loss_pending);
+        assert_eq!(state.token_balances[0].balance, 850);
+        assert_eq!(state.total_value_locked, 850);
+        let vault_token = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &vault_token).await, 850);
+
+        let err = send(&mut context, &[deploy_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyLossUnacknowledged));
+
+        let acknowledge = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::AcknowledgeStrategyLoss { token_mint: mint },
+        );
+        send(
+            &mut context,
+            &[acknowledge, deploy_ix(&vault, &mint, &authority, 100)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(!state.yield_strategies[0].loss_pending);
+        assert_eq!(state.yield_strategies[0].deployed_amount, 100);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_calls_take_only_the_pinned_accounts() {
+        let mut context = start().await;
+        let (vault, mint, admin) = deployed_vault(&mut context).await;
+        let authority = context.payer.pubkey();
+
+        // Neither the authority nor the emergency admin can add an account or swap out the
+        // vault's token account
+        let mut extra = deploy_ix(&vault, &mint, &authority, 100);
+        extra.accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        let mut swapped = exit_ix(&vault, &mint, &admin.pubkey());
+        swapped.accounts[7].pubkey = Pubkey::new_unique();
+        for (instruction, signers) in [(extra, vec![]), (swapped, vec![&admin])] {
+            let err = send(&mut context, &[instruction], &signers).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        }
+
+        // Re-registering without accounts unpins them, and pinning is capped
+        let unpin = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::SetYieldStrategy {
+                token_mint: mint,
+                strategy_program: MOCK_DEFI_ID,
+            },
+        );
+        send(&mut context, &[unpin], &[]).await.unwrap();
+        assert!(fetch_vault(&mut context, &vault).await.strategy_accounts.is_empty());
+        let err = send(&mut context, &[recall_ix(&vault, &mint, &authority, 600)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        let too_many = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::SetYieldStrategyV2 {
+                token_mint: mint,
+                strategy_program: MOCK_DEFI_ID,
+                accounts: vec![Pubkey::new_unique(); StrategyAccounts::MAX_ACCOUNTS + 1],
+            },
+        );
+        let err = send(&mut context, &[too_many], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+    }
+
+    #[tokio::test]
+    async fn test_gain_raises_high_water_mark() {
+        let mut context = start().await;
+        let (vault, mint) = vault_with_strategy(&mut context, 1_000, 100).await;
+        let authority = context.payer.pubkey();
+
+        set_withdraw_return(&mut context, 11_000).await;
+        send(
+            &mut context,
+            &[
+                deploy_ix(&vault, &mint, &authority, 500),
+                recall_ix(&vault, &mint, &authority, 500),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let strategy = &state.yield_strategies[0];
+        assert_eq!(strategy.realized_pnl, 50);
+        assert_eq!(strategy.high_water_mark, 50);
+        assert!(!strategy.loss_pending);
+        assert_eq!(state.token_balances[0].balance, 1_050);
+        assert_eq!(state.total_value_locked, 1_050);
+
+        // Withdrawing more principal than is deployed is refused
+        let err = send(&mut context, &[recall_ix(&vault, &mint, &authority, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+    }
+
+    #[test]
+    fn test_allocation_cap_math() {
+        assert!(YieldStrategyConfig::within_allocation_cap(300, 700, 3_000));
+        assert!(!YieldStrategyConfig::within_allocation_cap(301, 699, 3_000));
+        assert!(YieldStrategyConfig::within_allocation_cap(0, 0, 0));
+        // Holdings beyond u64 don't overflow
+        assert!(YieldStrategyConfig::within_allocation_cap(u64::MAX / 2, u64::MAX, 5_000));
+        assert!(!YieldStrategyConfig::within_allocation_cap(u64::MAX, u64::MAX, 5_000 - 1));
+    }
+
+    #[tokio::test]
+    async fn test_allocation_cap_limits_deploys() {
+        let mut context = start().await;
+        let (vault, mint) = vault_with_strategy(&mut context, 1_000, 0).await;
+        let authority = context.payer.pubkey();
+        let cap_ix = |bps| {
+            simple_ix(
+                &vault,
+                &authority,
+                VaultInstruction::UpdateYieldStrategy {
+                    token_mint: mint,
+                    max_allocation_bps: bps,
+                },
+            )
+        };
+
+        let err = send(&mut context, &[cap_ix(10_001)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+
+        // Exactly at the cap: 300 of 1_000 at 30%
+        send(
+            &mut context,
+            &[cap_ix(3_000), deploy_ix(&vault, &mint, &authority, 300)],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.strategy_allocation_cap(0), 3_000);
+        assert_eq!(state.yield_strategies[0].deployed_amount, 300);
+
+        let err = send(&mut context, &[deploy_ix(&vault, &mint, &authority, 1)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyAllocationExceeded));
+
+        // A lower cap leaves the deployment where it is and only blocks new deploys
+        send(&mut context, &[cap_ix(2_000)], &[]).await.unwrap();
+        let err = send(&mut context, &[deploy_ix(&vault, &mint, &authority, 2)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyAllocationExceeded));
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].deployed_amount, 300);
+        assert_eq!(state.tracked_balance(&mint), 700);
+
+        // Recalling brings it back under the cap
+        send(
+            &mut context,
+            &[
+                recall_ix(&vault, &mint, &authority, 150),
+                deploy_ix(&vault, &mint, &authority, 50),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].deployed_amount, 200);
+    }
+
+    fn status_ix(
+        vault: &Pubkey,
+        mint: &Pubkey,
+        signer: &Pubkey,
+        status: StrategyStatus,
+    ) -> Instruction {
+        let instruction = VaultInstruction::SetStrategyStatus {
+            token_mint: *mint,
+            status,
+        };
+        simple_ix(vault, signer, instruction)
+    }
+
+    // Exit withdrawing the mock's whole position
+    fn exit_ix(vault: &Pubkey, mint: &Pubkey, signer: &Pubkey) -> Instruction {
+        let instruction = VaultInstruction::EmergencyExitStrategy {
+            token_mint: *mint,
+            strategy_data: mock_defi::withdraw_data(u64::MAX),
+        };
+        strategy_ix(vault, mint, signer, instruction)
+    }
+
+    // vault_with_strategy with 600 deployed and a separate emergency admin
+    async fn deployed_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey, Keypair) {
+        let (vault, mint) = vault_with_strategy(context, 1_000, 0).await;
+        let authority = context.payer.pubkey();
+        send(context, &[deploy_ix(&vault, &mint, &authority, 600)], &[])
+            .await
+            .unwrap();
+        let admin = test_keypair(1);
+        let mut state = fetch_vault(context, &vault).await;
+        state.emergency_admin = admin.pubkey();
+        store_vault(context, &vault, &state).await;
+        (vault, mint, admin)
+    }
+
+    #[tokio::test]
+    async fn test_paused_strategy_blocks_deploys_only() {
+        let mut context = start().await;
+        let (vault, mint, admin) = deployed_vault(&mut context).await;
+        let authority = context.payer.pubkey();
+
+        let pause = status_ix(&vault, &mint, &admin.pubkey(), StrategyStatus::DepositsPaused);
+        send(&mut context, &[pause], &[&admin]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.strategy_status(0), StrategyStatus::DepositsPaused);
+        assert!(!state.paused);
+
+        let err = send(&mut context, &[deploy_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyNotActive));
+
+        // Recalls keep working, and only the authority can reactivate
+        send(&mut context, &[recall_ix(&vault, &mint, &authority, 100)], &[])
+            .await
+            .unwrap();
+        let resume = status_ix(&vault, &mint, &admin.pubkey(), StrategyStatus::Active);
+        let err = send(&mut context, &[resume], &[&admin])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+        send(
+            &mut context,
+            &[
+                status_ix(&vault, &mint, &authority, StrategyStatus::Active),
+                deploy_ix(&vault, &mint, &authority, 100),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].deployed_amount, 600);
+    }
+
+    #[tokio::test]
+    async fn test_emergency_exit_leaves_shortfall_outstanding() {
+        let mut context = start().await;
+        let (vault, mint, admin) = deployed_vault(&mut context).await;
+        let authority = context.payer.pubkey();
+
+        // Makes no difference to the exit
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.paused = true;
+        store_vault(&mut context, &vault, &state).await;
+
+        // The exploited protocol only gives back 420 of the 600
+        set_withdraw_return(&mut context, 7_000).await;
+        let exit = exit_ix(&vault, &mint, &admin.pubkey());
+        send(&mut context, &[exit], &[&admin]).await.unwrap();
+
+        // The 180 short is still owed rather than written off
+        let state = fetch_vault(&mut context, &vault).await;
+        let strategy = &state.yield_strategies[0];
+        assert_eq!(strategy.deployed_amount, 180);
+        assert_eq!(strategy.realized_pnl, 0);
+        assert!(!strategy.loss_pending);
+        assert_eq!(state.strategy_status(0), StrategyStatus::Exiting);
+        assert_eq!(state.tracked_balance(&mint), 820);
+        assert_eq!(state.total_value_locked, 1_000);
+        let vault_token = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &vault_token).await, 820);
+
+        // The protocol recovers and a later recall books what it gets back: 100 of the 180
+        let mut config = mock_defi_config(&mut context, &MOCK_DEFI_ID).await;
+        config.position = 180;
+        config.withdraw_return_bps = 5_556;
+        configure_mock_defi(&mut context, &MOCK_DEFI_ID, &config);
+        send(&mut context, &[recall_ix(&vault, &mint, &authority, 180)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let strategy = &state.yield_strategies[0];
+        assert_eq!(strategy.deployed_amount, 0);
+        assert_eq!(strategy.realized_pnl, -80);
+        assert!(strategy.loss_pending);
+        assert_eq!(state.tracked_balance(&mint), 920);
+        assert_eq!(state.total_value_locked, 920);
+        assert_eq!(token_balance(&mut context, &vault_token).await, 920);
+
+        // Nothing goes back in, even once the loss is acknowledged and the vault unpaused
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.paused = false;
+        store_vault(&mut context, &vault, &state).await;
+        let acknowledge = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::AcknowledgeStrategyLoss { token_mint: mint },
+        );
+        let err = send(
+            &mut context,
+            &[acknowledge, deploy_ix(&vault, &mint, &authority, 100)],
+            &[],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(err, vault_error(1, VaultError::StrategyNotActive));
+
+        // Anyone else is turned away
+        let stranger = test_keypair(2);
+        let exit = exit_ix(&vault, &mint, &stranger.pubkey());
+        let err = send(&mut context, &[exit], &[&stranger])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+    }
+
+    #[tokio::test]
+    async fn test_deposit_slippage_shows_up_as_loss() {
+        let mut test = program_test();
+        let config = MockDefiConfig {
+            deposit_slippage_bps: 100,
+            ..MockDefiConfig::default()
+        };
+        add_mock_defi(&mut test, MOCK_DEFI_ID, config);
+        let mut context = test.start_with_context().await;
+        let (vault, mint) = vault_with_strategy(&mut context, 1_000, 0).await;
+        let authority = context.payer.pubkey();
+
+        // The full 1_000 leaves the vault but the position is only worth 990
+        send(
+            &mut context,
+            &[
+                deploy_ix(&vault, &mint, &authority, 1_000),
+                recall_ix(&vault, &mint, &authority, 1_000),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        assert_eq!(mock_defi_config(&mut context, &MOCK_DEFI_ID).await.position, 0);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].realized_pnl, -10);
+        assert_eq!(state.tracked_balance(&mint), 990);
+    }
+
+    #[tokio::test]
+    async fn test_harvest_follows_strategy_status() {
+        let mut test = program_test();
+        let config = MockDefiConfig {
+            harvest_reward: 25,
+            ..MockDefiConfig::default()
+        };
+        add_mock_defi(&mut test, MOCK_DEFI_ID, config);
+        let mut context = test.start_with_context().await;
+        let (vault, mint) = vault_with_strategy(&mut context, 1_000, 0).await;
+        let authority = context.payer.pubkey();
+
+        // The mock's harvest pays its reward token into the vault's ATA for it
+        let reward_mint = mock_defi::reward_mint(&MOCK_DEFI_ID);
+        let reward_account = create_funded_ata(&mut context, &reward_mint, &vault, 0).await;
+        let claim = mock_defi::instruction(
+            &MOCK_DEFI_ID,
+            mock_defi::harvest_data(),
+            mock_defi::harvest_accounts(&MOCK_DEFI_ID, &reward_account),
+        );
+        send(&mut context, &[claim], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &reward_account).await, 25);
+
+        let harvest = |token_mint| {
+            simple_ix(&vault, &authority, VaultInstruction::HarvestYield { token_mint })
+        };
+        let compound = |token_mint| {
+            simple_ix(&vault, &authority, VaultInstruction::CompoundYield { token_mint })
+        };
+        send(&mut context, &[harvest(mint), compound(mint)], &[])
+            .await
+            .unwrap();
+
+        // Paused deposits still harvest but no longer compound
+        let pause = status_ix(&vault, &mint, &authority, StrategyStatus::DepositsPaused);
+        send(&mut context, &[pause, harvest(mint)], &[]).await.unwrap();
+        let err = send(&mut context, &[compound(mint)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyNotActive));
+
+        let exit = status_ix(&vault, &mint, &authority, StrategyStatus::Exiting);
+        send(&mut context, &[exit], &[]).await.unwrap();
+        let err = send(&mut context, &[harvest(mint)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::StrategyNotActive));
+    }
+}
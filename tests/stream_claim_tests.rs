@@ -0,0 +1,400 @@
+mod common;
+
+#[cfg(test)]
+mod stream_claim_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{TimeLock, Vault, VestingSchedule},
+        VaultError, PROGRAM_ID,
+    };
+
+    const GRANT: u64 = 1_000;
+    const DURATION: i64 = 1_000;
+
+    fn simple_ix(vault: &Pubkey, signer: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    fn claim_call(
+        vault: &Pubkey,
+        mint: &Pubkey,
+        beneficiary: &Pubkey,
+        instruction: VaultInstruction,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(get_associated_token_address(vault, mint), false),
+                AccountMeta::new(get_associated_token_address(beneficiary, mint), false),
+                AccountMeta::new_readonly(*beneficiary, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    fn claim_ix(vault: &Pubkey, mint: &Pubkey, beneficiary: &Pubkey, amount: u64) -> Instruction {
+        let claim = VaultInstruction::ClaimTimeLockPartial {
+            time_lock_id: 0,
+            amount,
+        };
+        claim_call(vault, mint, beneficiary, claim)
+    }
+
+    // Vault holding GRANT, all of it streaming linearly to the returned beneficiary as lock 0
+    async fn streaming_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey, Keypair) {
+        let (vault, mint) = vault_with_token(context, GRANT).await;
+        let authority = context.payer.pubkey();
+        let beneficiary = Keypair::new();
+        create_funded_ata(context, &mint, &beneficiary.pubkey(), 0).await;
+        let create_lock = simple_ix(
+            &vault,
+            &authority,
+            VaultInstruction::CreateTimeLock {
+                beneficiary: beneficiary.pubkey(),
+                mint,
+                amount: GRANT,
+                duration: DURATION,
+                cliff_duration: None,
+                schedule: VestingSchedule::Linear,
+                revocable: false,
+            },
+        );
+        send(
+            context,
+            &[deposit_ix(&vault, &mint, &authority, GRANT), create_lock],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, mint, beneficiary)
+    }
+
+    #[tokio::test]
+    async fn test_partial_claims_reconcile() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let beneficiary_token = get_associated_token_address(&key, &mint);
+
+        // Each claim stays well inside what has vested by then
+        for amount in [100, 250, 300] {
+            advance_clock(&mut context, DURATION / 3).await;
+            send(&mut context, &[claim_ix(&vault, &mint, &key, amount)], &[&beneficiary])
+                .await
+                .unwrap();
+        }
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.time_locks[0].released_amount, 650);
+        assert_eq!(token_balance(&mut context, &beneficiary_token).await, 650);
+
+        // Past the end everything has vested, leaving exactly the unclaimed rest
+        advance_clock(&mut context, DURATION).await;
+        let err = send(&mut context, &[claim_ix(&vault, &mint, &key, 351)], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[claim_ix(&vault, &mint, &key, 350)], &[&beneficiary])
+            .await
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.time_locks[0].released_amount, GRANT);
+        assert_eq!(state.tracked_balance(&mint), 0);
+        assert_eq!(token_balance(&mut context, &beneficiary_token).await, GRANT);
+        let vault_token = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &vault_token).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_exempt_streams_pay_during_pause_with_own_fee() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let admin = context.payer.pubkey();
+        let key = beneficiary.pubkey();
+        send(&mut context, &[simple_ix(&vault, &admin, VaultInstruction::PauseVault)], &[])
+            .await
+            .unwrap();
+        advance_clock(&mut context, DURATION / 2).await;
+
+        let err = send(&mut context, &[claim_ix(&vault, &mint, &key, 200)], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        let stream_config = simple_ix(
+            &vault,
+            &admin,
+            VaultInstruction::SetStreamConfig {
+                exempt_from_pause: true,
+                claim_fee_bps: 100,
+            },
+        );
+        send(
+            &mut context,
+            &[stream_config, claim_ix(&vault, &mint, &key, 200)],
+            &[&beneficiary],
+        )
+        .await
+        .unwrap();
+
+        // The lock is charged the full 200; 1% of it stays in the vault as an accrued fee
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.paused);
+        assert_eq!(state.time_locks[0].released_amount, 200);
+        assert_eq!(state.tracked_balance(&mint), GRANT - 200);
+        assert_eq!(state.accrued_fee(&mint), 2);
+        assert_eq!(state.total_fees_collected, 2);
+        let beneficiary_token = get_associated_token_address(&key, &mint);
+        assert_eq!(token_balance(&mut context, &beneficiary_token).await, 198);
+    }
+
+    #[tokio::test]
+    async fn test_beneficiary_splits_remaining_grant() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let hot_wallet = Pubkey::new_unique();
+        let split = |amount_to_new| VaultInstruction::SplitTimeLock {
+            id: 0,
+            amount_to_new,
+            new_beneficiary: hot_wallet,
+        };
+
+        advance_clock(&mut context, DURATION / 2).await;
+        send(&mut context, &[claim_ix(&vault, &mint, &key, 300)], &[&beneficiary])
+            .await
+            .unwrap();
+
+        let not_beneficiary = simple_ix(&vault, &context.payer.pubkey(), split(400));
+        let err = send(&mut context, &[not_beneficiary], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        send(&mut context, &[simple_ix(&vault, &key, split(400))], &[&beneficiary])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let (original, new) = (&state.time_locks[0], &state.time_locks[1]);
+        assert_eq!((original.amount, original.released_amount), (600, 180));
+        assert_eq!((new.id, new.beneficiary), (1, hot_wallet));
+        assert_eq!((new.amount, new.released_amount), (400, 120));
+        assert_eq!((new.start_time, new.end_time), (original.start_time, original.end_time));
+        assert_eq!(state.next_time_lock_id, 2);
+
+        // Both halves have vested half; the original's 300 - 180 is still claimable
+        send(&mut context, &[claim_ix(&vault, &mint, &key, 120)], &[&beneficiary])
+            .await
+            .unwrap();
+
+        // Nothing left to split once the original is fully claimed
+        advance_clock(&mut context, DURATION).await;
+        send(&mut context, &[claim_ix(&vault, &mint, &key, 300)], &[&beneficiary])
+            .await
+            .unwrap();
+        let err = send(&mut context, &[simple_ix(&vault, &key, split(1))], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidTimeLock));
+    }
+
+    #[tokio::test]
+    async fn test_splits_stop_at_open_lock_limit() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, _, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let split = |amount_to_new| VaultInstruction::SplitTimeLock {
+            id: 0,
+            amount_to_new,
+            new_beneficiary: Pubkey::new_unique(),
+        };
+
+        // Room for the locks below, which a new vault's headroom can't hold
+        let mut account = context.banks_client.get_account(vault).await.unwrap().unwrap();
+        let room = (Vault::MAX_OPEN_TIME_LOCKS + 1) * TimeLock::LEN;
+        account.data.resize(account.data.len() + room, 0);
+        account.lamports += 1_000_000_000;
+        context.set_account(&vault, &account.into());
+
+        // Fill up with open locks, keeping one fully released one that doesn't count
+        let mut state = fetch_vault(&mut context, &vault).await;
+        let mut finished = state.time_locks[0].clone();
+        finished.id = 1;
+        finished.released_amount = finished.amount;
+        state.time_locks.push(finished);
+        for id in 2..Vault::MAX_OPEN_TIME_LOCKS as u64 {
+            let mut open = state.time_locks[0].clone();
+            open.id = id;
+            state.time_locks.push(open);
+        }
+        state.next_time_lock_id = Vault::MAX_OPEN_TIME_LOCKS as u64;
+        store_vault(&mut context, &vault, &state).await;
+
+        send(&mut context, &[simple_ix(&vault, &key, split(1))], &[&beneficiary])
+            .await
+            .unwrap();
+        let err = send(&mut context, &[simple_ix(&vault, &key, split(2))], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TimeLockLimitReached));
+    }
+
+    #[tokio::test]
+    async fn test_claims_address_locks_by_id() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let other = Keypair::new();
+        create_funded_ata(&mut context, &mint, &other.pubkey(), 0).await;
+
+        // Splitting appends lock 1; a claim by id finds it wherever it sits
+        let split = VaultInstruction::SplitTimeLock {
+            id: 0,
+            amount_to_new: 400,
+            new_beneficiary: other.pubkey(),
+        };
+        send(&mut context, &[simple_ix(&vault, &key, split)], &[&beneficiary])
+            .await
+            .unwrap();
+        advance_clock(&mut context, DURATION).await;
+        let claim = VaultInstruction::ClaimTimeLockV2 { time_lock_id: 1 };
+        send(&mut context, &[claim_call(&vault, &mint, &other.pubkey(), claim)], &[&other])
+            .await
+            .unwrap();
+        let other_token = get_associated_token_address(&other.pubkey(), &mint);
+        assert_eq!(token_balance(&mut context, &other_token).await, 400);
+
+        // The index-addressed variants only point clients at their replacements
+        for deprecated in [
+            VaultInstruction::ClaimTimeLock { time_lock_index: 0 },
+            VaultInstruction::CancelTimeLock { time_lock_index: 0 },
+        ] {
+            let instruction = claim_call(&vault, &mint, &key, deprecated);
+            let err = send(&mut context, &[instruction], &[&beneficiary])
+                .await
+                .unwrap_err()
+                .unwrap();
+            assert_eq!(err, vault_error(0, VaultError::DeprecatedInstruction));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_returns_unvested_part() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let authority = context.payer.pubkey();
+        let cancel = || VaultInstruction::CancelTimeLockV2 { time_lock_id: 0 };
+
+        let err = send(&mut context, &[simple_ix(&vault, &authority, cancel())], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TimeLockNotRevocable));
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.time_locks[0].revocable = true;
+        store_vault(&mut context, &vault, &state).await;
+        let err = send(&mut context, &[simple_ix(&vault, &key, cancel())], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        // A quarter in, the beneficiary keeps that quarter and the rest is free again
+        advance_clock(&mut context, DURATION / 4).await;
+        send(&mut context, &[simple_ix(&vault, &authority, cancel())], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let lock = &state.time_locks[0];
+        assert_eq!(lock.amount, GRANT / 4);
+        assert!(!lock.revocable);
+        assert_eq!(state.free_balance(&mint), GRANT - GRANT / 4);
+        let err = send(&mut context, &[simple_ix(&vault, &authority, cancel())], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TimeLockNotRevocable));
+
+        // Nothing more vests, and what did stays claimable
+        advance_clock(&mut context, DURATION).await;
+        let claim = VaultInstruction::ClaimTimeLockV2 { time_lock_id: 0 };
+        send(&mut context, &[claim_call(&vault, &mint, &key, claim)], &[&beneficiary])
+            .await
+            .unwrap();
+        let beneficiary_token = get_associated_token_address(&key, &mint);
+        assert_eq!(token_balance(&mut context, &beneficiary_token).await, GRANT / 4);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.time_locks[0].released_amount, GRANT / 4);
+        assert_eq!(state.free_balance(&mint), GRANT - GRANT / 4);
+    }
+
+    #[tokio::test]
+    async fn test_lock_without_mint_waits_for_assignment() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, beneficiary) = streaming_vault(&mut context).await;
+        let key = beneficiary.pubkey();
+        let authority = context.payer.pubkey();
+
+        // As a migration leaves a lock whose mint it couldn't tell
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.time_locks[0].mint = Pubkey::default();
+        store_vault(&mut context, &vault, &state).await;
+        advance_clock(&mut context, DURATION).await;
+        let err = send(&mut context, &[claim_ix(&vault, &mint, &key, 100)], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TimeLockMintUnassigned));
+
+        let assign = |mint| VaultInstruction::AssignTimeLockMint { time_lock_id: 0, mint };
+        let err = send(&mut context, &[simple_ix(&vault, &key, assign(mint))], &[&beneficiary])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+        let unsupported = simple_ix(&vault, &authority, assign(Pubkey::new_unique()));
+        let err = send(&mut context, &[unsupported], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        send(&mut context, &[simple_ix(&vault, &authority, assign(mint))], &[])
+            .await
+            .unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.time_locks[0].mint, mint);
+        send(&mut context, &[claim_ix(&vault, &mint, &key, 100)], &[&beneficiary])
+            .await
+            .unwrap();
+
+        // Once assigned, the mint is fixed
+        let other = Pubkey::new_unique();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.supported_tokens.push(state.supported_tokens[0].clone());
+        state.supported_tokens[1].mint = other;
+        store_vault(&mut context, &vault, &state).await;
+        let reassign = simple_ix(&vault, &authority, assign(other));
+        let err = send(&mut context, &[reassign], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidMint));
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+#[cfg(test)]
+mod sweep_lamports_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{instruction::VaultInstruction, VaultError, PROGRAM_ID};
+
+    const NONCE: u8 = 15;
+    const AIRDROP: u64 = 5_000_000_000;
+
+    fn sweep_ix(vault: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::SweepUntrackedLamports.try_to_vec().unwrap(),
+        }
+    }
+
+    fn withdraw_sol_ix(
+        vault: &Pubkey,
+        recipient: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::WithdrawSOL { amount }.try_to_vec().unwrap(),
+        }
+    }
+
+    async fn lamports(context: &mut ProgramTestContext, account: &Pubkey) -> u64 {
+        context.banks_client.get_balance(*account).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_swept_lamports_withdraw_through_normal_path() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let rent_floor = lamports(&mut context, &vault).await;
+
+        // Sent straight to the address: held, but nobody's until swept
+        send(&mut context, &[system_instruction::transfer(&payer, &vault, AIRDROP)], &[])
+            .await
+            .unwrap();
+        let recipient = Pubkey::new_unique();
+        let withdraw = |amount| withdraw_sol_ix(&vault, &recipient, &payer, amount);
+        let err = send(&mut context, &[withdraw(AIRDROP)], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_lamports, 0);
+        assert_eq!(state.total_value_locked, 0);
+
+        send(&mut context, &[sweep_ix(&vault)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_lamports, AIRDROP);
+        assert_eq!(state.total_value_locked, u128::from(AIRDROP));
+
+        // A second sweep finds nothing new
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[sweep_ix(&vault)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_lamports, AIRDROP);
+
+        send(&mut context, &[withdraw(2_000_000_000)], &[]).await.unwrap();
+        assert_eq!(lamports(&mut context, &recipient).await, 2_000_000_000);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_lamports, 3_000_000_000);
+        assert_eq!(state.total_value_locked, 3_000_000_000);
+
+        // Everything tracked can leave, and the rent floor stays behind
+        let err = send(&mut context, &[withdraw(3_000_000_001)], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[withdraw(3_000_000_000)], &[]).await.unwrap();
+        assert_eq!(lamports(&mut context, &vault).await, rent_floor);
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_lamports, 0);
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_sol_needs_the_authority() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let transfer = system_instruction::transfer(&payer, &vault, AIRDROP);
+        send(&mut context, &[transfer, sweep_ix(&vault)], &[]).await.unwrap();
+
+        let stranger = Keypair::new();
+        let stolen = withdraw_sol_ix(&vault, &stranger.pubkey(), &stranger.pubkey(), AIRDROP);
+        let err = send(&mut context, &[stolen], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+
+        let mut unsigned = withdraw_sol_ix(&vault, &stranger.pubkey(), &payer, AIRDROP);
+        unsigned.accounts[2].is_signer = false;
+        let err = send(&mut context, &[unsigned], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_lamports, AIRDROP);
+        assert_eq!(lamports(&mut context, &stranger.pubkey()).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_leaves_escrowed_tips_alone() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let accounts = vec![vault_program::state::TransactionAccount {
+            pubkey: vault,
+            is_signer: false,
+            is_writable: false,
+        }];
+        let fund_tip = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::FundExecutionTip {
+                transaction_id: 0,
+                lamports: 1_000_000,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        send(
+            &mut context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                create_multisig_tx_ix(&vault, &payer, Pubkey::new_unique(), accounts, vec![1]),
+                fund_tip,
+                system_instruction::transfer(&payer, &vault, AIRDROP),
+                sweep_ix(&vault),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.reserved_tip_lamports(), 1_000_000);
+        assert_eq!(state.tracked_lamports, AIRDROP);
+    }
+}
@@ -0,0 +1,102 @@
+mod common;
+
+#[cfg(test)]
+mod sysvar_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        rent::Rent,
+    };
+    use solana_sdk::{account::create_account_for_test, signature::Signer};
+    use vault_program::{instruction::VaultInstruction, VaultError, PROGRAM_ID};
+
+    fn admin_ix(vault: &Pubkey, signer: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    // A look-alike clock far in the future used to make a pause look expired
+    #[tokio::test]
+    async fn test_spoofed_clock_rejected() {
+        let fake_clock = Pubkey::new_unique();
+        let mut test = program_test();
+        test.add_account(
+            fake_clock,
+            create_account_for_test(&Clock {
+                unix_timestamp: i64::MAX / 2,
+                ..Clock::default()
+            }),
+        );
+        let mut context = test.start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let admin = context.payer.pubkey();
+        send(
+            &mut context,
+            &[
+                deposit_ix(&vault, &mint, &admin, 400),
+                admin_ix(
+                    &vault,
+                    &admin,
+                    VaultInstruction::SetMaxPauseDuration {
+                        max_pause_duration: 100,
+                    },
+                ),
+                admin_ix(&vault, &admin, VaultInstruction::PauseVault),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let mut spoofed = withdraw_ix(&vault, &mint, &admin, 100);
+        spoofed.accounts[5].pubkey = fake_clock;
+        let err = send(&mut context, &[spoofed], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidSysvar));
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &admin, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+        assert!(fetch_vault(&mut context, &vault).await.paused);
+    }
+
+    // Free rent would let a vault be created below the rent-exempt minimum
+    #[tokio::test]
+    async fn test_spoofed_rent_rejected() {
+        let fake_rent = Pubkey::new_unique();
+        let mut test = program_test();
+        test.add_account(
+            fake_rent,
+            create_account_for_test(&Rent {
+                lamports_per_byte_year: 0,
+                exemption_threshold: 0.0,
+                burn_percent: 0,
+            }),
+        );
+        let mut context = test.start_with_context().await;
+        let authority = context.payer.pubkey();
+        let (vault, _) = vault_pda(&authority, 0);
+
+        let mut spoofed = initialize_ix(&vault, &authority, &authority, 0, true);
+        spoofed.accounts[4].pubkey = fake_rent;
+        let err = send(&mut context, &[spoofed], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidSysvar));
+    }
+}
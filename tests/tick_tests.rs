@@ -0,0 +1,264 @@
+mod common;
+
+#[cfg(test)]
+mod tick_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::Signer;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{
+            GovernanceConfig, GovernanceProposal, MultiSigTransaction, ProposalSnapshot,
+            SettledItem, SettledKind, TimeLock, Vault, VoteType,
+        },
+        VaultError, PROGRAM_ID,
+    };
+
+    fn tick_ix(vault: &Pubkey, max_items: u16) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::Tick { max_items }.try_to_vec().unwrap(),
+        }
+    }
+
+    // Vote, queue and execute all take the vault, a signer and the clock
+    fn signed_ix(vault: &Pubkey, signer: &Pubkey, data: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: data.try_to_vec().unwrap(),
+        }
+    }
+
+    fn proposal(id: u64, end_time: i64, for_votes: u64, against_votes: u64) -> GovernanceProposal {
+        GovernanceProposal {
+            id,
+            title: "Tick test".to_string(),
+            for_votes,
+            against_votes,
+            end_time,
+            ..Default::default()
+        }
+    }
+
+    fn time_lock(id: u64, now: i64, end_time: i64) -> TimeLock {
+        TimeLock {
+            id,
+            beneficiary: Pubkey::new_unique(),
+            amount: 500,
+            start_time: now - 1_000,
+            cliff_time: now - 1_000,
+            end_time,
+            ..Default::default()
+        }
+    }
+
+    // Nine items, four of them due: transaction 0 expired (1 executed, 2 open-ended), proposal
+    // 0 passed, 1 defeated and 2 still voting, lock 0 fully vested and lock 1 halfway
+    async fn mixed_vault(context: &mut ProgramTestContext) -> Pubkey {
+        let vault = create_vault(context).await;
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        let now = clock.unix_timestamp;
+        let mut state = fetch_vault(context, &vault).await;
+        for executed in [false, true, false] {
+            state.push_multisig_transaction(MultiSigTransaction {
+                multisig: vault,
+                program_id: PROGRAM_ID,
+                did_execute: executed,
+                ..Default::default()
+            });
+        }
+        state.set_transaction_expiry(0, now - 1);
+        state.set_transaction_expiry(1, now - 1);
+
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: Pubkey::new_unique(),
+            quorum_threshold: 5_000,
+            execution_threshold: 5_000,
+            ..Default::default()
+        });
+        state.governance_proposals = vec![
+            proposal(0, now - 1, 80, 20),
+            proposal(1, now - 1, 10, 5),
+            proposal(2, now + 3_600, 90, 0),
+        ];
+        state.next_governance_proposal_id = 3;
+        state.proposal_snapshots = (0..3)
+            .map(|proposal_id| ProposalSnapshot { proposal_id, effective_supply: 100 })
+            .collect();
+
+        state.time_locks = vec![time_lock(0, now, now - 1), time_lock(1, now, now + 1_000)];
+        state.next_time_lock_id = 2;
+        store_vault(context, &vault, &state).await;
+        vault
+    }
+
+    #[tokio::test]
+    async fn test_tick_settles_mixed_items() {
+        let mut context = program_test().start_with_context().await;
+        let vault = mixed_vault(&mut context).await;
+
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let settled: Vec<_> = state.settled_items.iter().map(|item| (item.kind, item.id)).collect();
+        assert_eq!(
+            settled,
+            [
+                (SettledKind::TransactionExpired, 0),
+                (SettledKind::ProposalPassed, 0),
+                (SettledKind::ProposalDefeated, 1),
+                (SettledKind::TimeLockVested, 0),
+            ]
+        );
+        assert_eq!(state.proposal_outcome(0), Some(true));
+        assert_eq!(state.proposal_outcome(1), Some(false));
+        assert_eq!(state.proposal_outcome(2), None);
+        assert_eq!(state.tick_cursor, 0);
+
+        // Nothing new is due, so a repeat changes nothing
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.settled_items, state.settled_items);
+
+        // Once voting on proposal 2 ends and lock 1 vests, they settle too
+        advance_clock(&mut context, 3_600).await;
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.proposal_outcome(2), Some(true));
+        assert!(state.is_settled(SettledKind::TimeLockVested, 1));
+        assert_eq!(state.settled_items.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_tick_resumes_where_it_stopped() {
+        let mut context = program_test().start_with_context().await;
+        let vault = mixed_vault(&mut context).await;
+
+        // Transactions first: 0 expired, then 1 and 2 have nothing due
+        send(&mut context, &[tick_ix(&vault, 3)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.settled_items.len(), 1);
+        assert_eq!(state.tick_cursor, 3);
+
+        send(&mut context, &[tick_ix(&vault, 4)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.proposal_outcome(1), Some(false));
+        assert!(state.is_settled(SettledKind::TimeLockVested, 0));
+        assert_eq!(state.tick_cursor, 7);
+
+        // Wraps around to the start
+        send(&mut context, &[tick_ix(&vault, 5)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.settled_items.len(), 4);
+        assert_eq!(state.tick_cursor, 4);
+
+        assert!(send(&mut context, &[tick_ix(&vault, 0)], &[]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_proposal_without_snapshot_settles_undetermined() {
+        let mut context = program_test().start_with_context().await;
+        let vault = mixed_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.proposal_snapshots.retain(|s| s.proposal_id != 0);
+        store_vault(&mut context, &vault, &state).await;
+
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.proposal_settlement(0), Some(SettledKind::ProposalUndetermined));
+        assert_eq!(state.proposal_outcome(0), None);
+
+        // 80 of an unknown supply can't be queued, however the votes went
+        let queue = VaultInstruction::QueueProposal { proposal_id: 0 };
+        let err = send(&mut context, &[signed_ix(&vault, &payer, queue)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ProposalNotPassed));
+    }
+
+    #[tokio::test]
+    async fn test_vote_and_execute_honor_the_settled_outcome() {
+        let mut context = program_test().start_with_context().await;
+        let vault = mixed_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+
+        // Defeated by Tick's account, though queued, due and with nothing left to check
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.governance_proposals[1].queued = true;
+        state.governance_proposals[1].eta = Some(clock.unix_timestamp - 1);
+        // A record for proposal 2 closes its voting early
+        state.settled_items.push(SettledItem {
+            kind: SettledKind::ProposalDefeated,
+            id: 2,
+            settled_at: clock.unix_timestamp,
+        });
+        store_vault(&mut context, &vault, &state).await;
+
+        let execute = VaultInstruction::ExecuteGovernanceProposal { proposal_id: 1 };
+        let err = send(&mut context, &[signed_ix(&vault, &payer, execute)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::ProposalNotPassed));
+
+        let vote = VaultInstruction::CastVote { proposal_id: 2, vote_type: VoteType::For };
+        let err = send(&mut context, &[signed_ix(&vault, &payer, vote)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VotingClosed));
+    }
+
+    #[tokio::test]
+    async fn test_settled_items_are_capped() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        let now = clock.unix_timestamp;
+        let locks = Vault::MAX_SETTLED_ITEMS as u64 + 6;
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.time_locks = (0..locks).map(|id| time_lock(id, now, now - 1)).collect();
+        state.next_time_lock_id = locks;
+        store_vault(&mut context, &vault, &state).await;
+
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.settled_items.len(), Vault::MAX_SETTLED_ITEMS);
+        assert_eq!(state.tick_cursor, 64);
+
+        // Full, so the next Tick stops on the first lock that doesn't fit
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.settled_items.len(), Vault::MAX_SETTLED_ITEMS);
+        assert_eq!(state.tick_cursor, 64);
+
+        // Claimed locks drop out and make room for the rest
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.time_locks.drain(..10);
+        store_vault(&mut context, &vault, &state).await;
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[tick_ix(&vault, 64)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.settled_items.len(), Vault::MAX_SETTLED_ITEMS - 4);
+        assert!((10..locks).all(|id| state.is_settled(SettledKind::TimeLockVested, id)));
+    }
+}
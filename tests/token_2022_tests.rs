@@ -0,0 +1,240 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod token_2022_tests {
+    use super::common::*;
+    use solana_program::{
+        instruction::{Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use spl_associated_token_account::{
+        get_associated_token_address_with_program_id, instruction::create_associated_token_account,
+    };
+    use vault_program::{
+        client::{
+            add_supported_token, collect_fees, deploy_to_strategy, deposit, set_yield_strategy_v2,
+            transfer_v2, with_token_program, withdraw, withdraw_from_strategy,
+        },
+        state::{ParamKey, TOKEN_2022_PROGRAM_ID},
+        VaultError,
+    };
+
+    // spl_token's builders refuse other program ids; Token-2022 takes the same instructions
+    fn under_token_2022(mut instruction: Instruction) -> Instruction {
+        instruction.program_id = TOKEN_2022_PROGRAM_ID;
+        instruction
+    }
+
+    // A Token-2022 mint without extensions, and the payer's associated account holding
+    // `amount` of it
+    async fn token_2022_mint(context: &mut ProgramTestContext, amount: u64) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = context.payer.pubkey();
+        let rent = context.banks_client.get_rent().await.unwrap();
+        let len = spl_token::state::Mint::LEN;
+        let ata = get_associated_token_address_with_program_id(
+            &payer,
+            &mint.pubkey(),
+            &TOKEN_2022_PROGRAM_ID,
+        );
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(len),
+                len as u64,
+                &TOKEN_2022_PROGRAM_ID,
+            ),
+            under_token_2022(
+                spl_token::instruction::initialize_mint2(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &payer,
+                    None,
+                    6,
+                )
+                .unwrap(),
+            ),
+            create_associated_token_account(
+                &payer,
+                &payer,
+                &mint.pubkey(),
+                &TOKEN_2022_PROGRAM_ID,
+            ),
+            under_token_2022(
+                spl_token::instruction::mint_to(
+                    &spl_token::id(),
+                    &mint.pubkey(),
+                    &ata,
+                    &payer,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+            ),
+        ];
+        send(context, &instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    #[tokio::test]
+    async fn test_token_2022_mint_deposits_and_withdraws() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let mint = token_2022_mint(&mut context, 1_000).await;
+        let program = TOKEN_2022_PROGRAM_ID;
+
+        let add = add_supported_token(&vault, &mint, &payer, &payer);
+        send(&mut context, &[with_token_program(add, &mint, &program)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.token_program_for(&mint), program);
+
+        let deposit_ix = with_token_program(deposit(&vault, &mint, &payer, 400), &mint, &program);
+        let withdraw_ix = with_token_program(withdraw(&vault, &mint, &payer, 100), &mint, &program);
+        send(&mut context, &[deposit_ix.clone(), withdraw_ix], &[]).await.unwrap();
+        let vault_ata = get_associated_token_address_with_program_id(&vault, &mint, &program);
+        let payer_ata = get_associated_token_address_with_program_id(&payer, &mint, &program);
+        assert_eq!(token_balance(&mut context, &vault_ata).await, 300);
+        assert_eq!(token_balance(&mut context, &payer_ata).await, 700);
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 300);
+
+        // The token program is the one recorded for the mint, not the caller's pick
+        let mut classic = deposit_ix;
+        classic.accounts[4].pubkey = spl_token::id();
+        let err = send(&mut context, &[classic], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InvalidAccountData as u32)
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_token_2022_transfers_and_fee_collection() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let mint = token_2022_mint(&mut context, 1_000).await;
+        let program = TOKEN_2022_PROGRAM_ID;
+        let add = add_supported_token(&vault, &mint, &payer, &payer);
+        send(&mut context, &[with_token_program(add, &mint, &program)], &[])
+            .await
+            .unwrap();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::DepositFeeBps, 100);
+        state.fee_config.fee_recipient = payer;
+        store_vault(&mut context, &vault, &state).await;
+        let deposit_ix = with_token_program(deposit(&vault, &mint, &payer, 400), &mint, &program);
+        send(&mut context, &[deposit_ix], &[]).await.unwrap();
+
+        // TransferV2 creates the recipient's Token-2022 account and pays into it
+        let recipient = Pubkey::new_unique();
+        let transfer = transfer_v2(&vault, &recipient, &payer, Some(&mint), 96, Some(&payer));
+        send(&mut context, &[with_token_program(transfer, &mint, &program)], &[])
+            .await
+            .unwrap();
+        let recipient_ata =
+            get_associated_token_address_with_program_id(&recipient, &mint, &program);
+        assert_eq!(token_balance(&mut context, &recipient_ata).await, 96);
+
+        // Fees only go out under the mint's own token program
+        let collect = collect_fees(&vault, &payer, &payer, &program, &[mint]);
+        let mut classic = collect.clone();
+        classic.accounts[2].pubkey = spl_token::id();
+        let err = send(&mut context, &[classic], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InvalidAccountData as u32)
+            )
+        );
+        send(&mut context, &[collect], &[]).await.unwrap();
+        let payer_ata = get_associated_token_address_with_program_id(&payer, &mint, &program);
+        assert_eq!(token_balance(&mut context, &payer_ata).await, 604);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!((state.tracked_balance(&mint), state.accrued_fee(&mint)), (300, 0));
+    }
+
+    #[tokio::test]
+    async fn test_token_2022_strategy_round_trip() {
+        let mut test = program_test();
+        add_mock_defi(&mut test, MOCK_DEFI_ID, MockDefiConfig::default());
+        let mut context = test.start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let mint = token_2022_mint(&mut context, 1_000).await;
+        let program = TOKEN_2022_PROGRAM_ID;
+        let pool = mock_defi::pool_authority(&MOCK_DEFI_ID).0;
+        let add = add_supported_token(&vault, &mint, &payer, &payer);
+        let deposit_ix = with_token_program(deposit(&vault, &mint, &payer, 400), &mint, &program);
+        let instructions = [
+            with_token_program(add, &mint, &program),
+            create_associated_token_account(&payer, &pool, &mint, &program),
+            deposit_ix,
+        ];
+        send(&mut context, &instructions, &[]).await.unwrap();
+
+        // The mock's accounts, rewritten like the rest for Token-2022, are the pinned ones
+        let strategy_accounts = mock_defi::strategy_accounts(&MOCK_DEFI_ID, &vault, &mint);
+        let deploy = deploy_to_strategy(
+            &vault,
+            &payer,
+            &mint,
+            &MOCK_DEFI_ID,
+            250,
+            mock_defi::deposit_data(250),
+            strategy_accounts.clone(),
+        );
+        let deploy = with_token_program(deploy, &mint, &program);
+        let pinned = deploy.accounts[5..].iter().map(|meta| meta.pubkey).collect();
+        let set_strategy = set_yield_strategy_v2(&vault, &payer, &mint, &MOCK_DEFI_ID, pinned);
+        send(&mut context, &[set_strategy, deploy], &[]).await.unwrap();
+        let vault_ata = get_associated_token_address_with_program_id(&vault, &mint, &program);
+        let pool_ata = get_associated_token_address_with_program_id(&pool, &mint, &program);
+        assert_eq!(token_balance(&mut context, &vault_ata).await, 150);
+        assert_eq!(token_balance(&mut context, &pool_ata).await, 250);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].deployed_amount, 250);
+        assert_eq!(state.tracked_balance(&mint), 150);
+
+        let recall = withdraw_from_strategy(
+            &vault,
+            &payer,
+            &mint,
+            &MOCK_DEFI_ID,
+            250,
+            mock_defi::withdraw_data(250),
+            strategy_accounts,
+        );
+        send(&mut context, &[with_token_program(recall, &mint, &program)], &[])
+            .await
+            .unwrap();
+        assert_eq!(token_balance(&mut context, &vault_ata).await, 400);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.yield_strategies[0].deployed_amount, 0);
+        assert_eq!(state.tracked_balance(&mint), 400);
+    }
+
+    #[test]
+    fn test_classic_instructions_are_left_alone() {
+        let mint = Pubkey::new_unique();
+        let instruction = deposit(&Pubkey::new_unique(), &mint, &Pubkey::new_unique(), 1);
+        let rewritten = with_token_program(instruction.clone(), &mint, &spl_token::id());
+        assert_eq!(rewritten, instruction);
+    }
+}
@@ -0,0 +1,159 @@
+mod common;
+
+#[cfg(test)]
+mod token_account_pair_tests {
+    use super::common::*;
+    use solana_program::{program_pack::Pack, pubkey::Pubkey};
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        account::{Account, AccountSharedData},
+        signature::{Keypair, Signer},
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::{Account as TokenAccount, AccountState};
+    use vault_program::VaultError;
+
+    // Vault holding 500 of `mint`, with a second supported mint the payer also holds 500 of
+    async fn two_mint_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let other_mint = create_mint(context, 6).await.pubkey();
+        send(
+            context,
+            &[
+                add_supported_token_ix(&vault, &other_mint, &payer, &payer),
+                deposit_ix(&vault, &mint, &payer, 500),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        create_funded_ata(context, &other_mint, &payer, 500).await;
+        (vault, mint, other_mint)
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_mints_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, other_mint) = two_mint_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        // Depositing from the other mint's account into this mint's vault account
+        let mut deposit = deposit_ix(&vault, &mint, &payer, 100);
+        deposit.accounts[1].pubkey = get_associated_token_address(&payer, &other_mint);
+        let err = send(&mut context, &[deposit], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TokenMintMismatch));
+
+        // Withdrawing this mint into the payer's account for the other one
+        let mut withdraw = withdraw_ix(&vault, &mint, &payer, 100);
+        withdraw.accounts[2].pubkey = get_associated_token_address(&payer, &other_mint);
+        let err = send(&mut context, &[withdraw], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TokenMintMismatch));
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 500);
+        assert_eq!(state.tracked_balance(&other_mint), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_mint_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, _) = vault_with_token(&mut context, 0).await;
+        let payer = context.payer.pubkey();
+        let unsupported = create_mint(&mut context, 6).await.pubkey();
+        create_funded_ata(&mut context, &unsupported, &payer, 100).await;
+        create_funded_ata(&mut context, &unsupported, &vault, 0).await;
+
+        let err = send(&mut context, &[deposit_ix(&vault, &unsupported, &payer, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidMint));
+    }
+
+    #[tokio::test]
+    async fn test_token_account_owners_checked() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, _) = two_mint_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let attacker = Keypair::new();
+        let attacker_token = create_funded_ata(&mut context, &mint, &attacker.pubkey(), 100).await;
+
+        // The attacker's account posing as the vault's own
+        let mut withdraw = withdraw_ix(&vault, &mint, &payer, 100);
+        withdraw.accounts[1].pubkey = attacker_token;
+        let err = send(&mut context, &[withdraw], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VaultTokenAccountOwnerMismatch));
+
+        // Depositing out of an account the signer doesn't own
+        let mut deposit = deposit_ix(&vault, &mint, &payer, 100);
+        deposit.accounts[1].pubkey = attacker_token;
+        let err = send(&mut context, &[deposit], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UserTokenAccountOwnerMismatch));
+
+        assert_eq!(token_balance(&mut context, &attacker_token).await, 100);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 500);
+    }
+
+    #[tokio::test]
+    async fn test_vault_token_account_must_be_ata() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint, _) = two_mint_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+
+        // Right mint and owner, but not at the vault's associated address
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(
+            TokenAccount {
+                mint,
+                owner: vault,
+                amount: 100,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        let stray = Pubkey::new_unique();
+        let owned_by = |owner| Account {
+            lamports: 1_000_000_000,
+            data: data.clone(),
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        };
+        context.set_account(&stray, &AccountSharedData::from(owned_by(spl_token::id())));
+
+        let mut withdraw = withdraw_ix(&vault, &mint, &payer, 100);
+        withdraw.accounts[1].pubkey = stray;
+        let err = send(&mut context, &[withdraw.clone()], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        // Same bytes under a program other than the token program
+        context.set_account(&stray, &AccountSharedData::from(owned_by(Pubkey::new_unique())));
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &[withdraw], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountOwner));
+
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 500);
+    }
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "client")]
+
+#[cfg(test)]
+mod token_metadata_tests {
+    use serde_json::json;
+    use solana_program::{program_option::COption, program_pack::Pack, pubkey, pubkey::Pubkey};
+    use spl_token::state::Mint;
+    use std::collections::HashMap;
+    use vault_program::client::{
+        annotate_token_symbols, metadata_address, parse_metadata, resolve_token, TokenCache,
+    };
+
+    const USDC: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+
+    // Mainnet-shaped metadata account for USDC, padded strings and all
+    fn usdc_metadata() -> Vec<u8> {
+        base64::decode(include_str!("fixtures/usdc_metadata.b64").trim()).unwrap()
+    }
+
+    fn mint_data(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        let mint = Mint {
+            mint_authority: COption::None,
+            supply: 1_000_000,
+            decimals,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        };
+        Mint::pack(mint, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_decode_metadata_fixture() {
+        let metadata = parse_metadata(&usdc_metadata()).unwrap();
+        assert_eq!(metadata.mint, USDC);
+        assert_eq!(metadata.name, "USD Coin");
+        assert_eq!(metadata.symbol, "USDC");
+        assert_eq!(metadata.uri, "https://example.com/usdc.json");
+
+        // Not a metadata account, or cut off mid-string
+        assert!(parse_metadata(&mint_data(6)).is_none());
+        assert!(parse_metadata(&usdc_metadata()[..80]).is_none());
+    }
+
+    #[test]
+    fn test_resolve_token() {
+        let accounts: HashMap<Pubkey, Vec<u8>> = [
+            (USDC, mint_data(6)),
+            (metadata_address(&USDC), usdc_metadata()),
+        ]
+        .into_iter()
+        .collect();
+        let info = resolve_token(&USDC, |key| accounts.get(key).cloned()).unwrap();
+        assert_eq!(info.symbol.as_deref(), Some("USDC"));
+        assert_eq!(info.name.as_deref(), Some("USD Coin"));
+        assert_eq!(info.decimals, 6);
+        assert_eq!(info.logo_uri.as_deref(), Some("https://example.com/usdc.json"));
+
+        // Metadata naming another mint is ignored; a lookalike can't borrow USDC's symbol
+        let lookalike = Pubkey::new_unique();
+        let accounts: HashMap<Pubkey, Vec<u8>> = [
+            (lookalike, mint_data(9)),
+            (metadata_address(&lookalike), usdc_metadata()),
+        ]
+        .into_iter()
+        .collect();
+        let info = resolve_token(&lookalike, |key| accounts.get(key).cloned()).unwrap();
+        assert_eq!((info.symbol, info.name, info.decimals), (None, None, 9));
+        assert!(info.label().contains('…'));
+
+        assert!(resolve_token(&Pubkey::new_unique(), |_| None).is_none());
+    }
+
+    #[test]
+    fn test_cache_reads_each_mint_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(vault_program::client::TOKEN_CACHE_FILE);
+        let mut reads = 0;
+        let mut fetch = |key: &Pubkey| {
+            reads += 1;
+            if *key == USDC {
+                Some(mint_data(6))
+            } else if *key == metadata_address(&USDC) {
+                Some(usdc_metadata())
+            } else {
+                None
+            }
+        };
+
+        let mut cache = TokenCache::load(&path).unwrap();
+        assert_eq!(cache.resolve(&USDC, &mut fetch).unwrap().decimals, 6);
+        assert!(cache.resolve(&USDC, &mut fetch).is_some());
+        assert!(cache.resolve(&Pubkey::new_unique(), &mut fetch).is_none());
+        cache.save(&path).unwrap();
+        assert_eq!(reads, 3);
+
+        // A restart serves USDC from disk
+        let mut cache = TokenCache::load(&path).unwrap();
+        assert_eq!(cache.get(&USDC).and_then(|i| i.symbol.as_deref()), Some("USDC"));
+        assert!(cache.resolve(&USDC, |_| panic!("cached")).is_some());
+
+        assert!(cache.forget(&USDC).is_some());
+        assert!(cache.get(&USDC).is_none());
+    }
+
+    #[test]
+    fn test_annotate_event_mints() {
+        let accounts: HashMap<Pubkey, Vec<u8>> = [
+            (USDC, mint_data(6)),
+            (metadata_address(&USDC), usdc_metadata()),
+        ]
+        .into_iter()
+        .collect();
+        let mut cache = TokenCache::default();
+        let mut resolve = |mint: &Pubkey| {
+            cache
+                .resolve(mint, |key| accounts.get(key).cloned())
+                .cloned()
+        };
+
+        let unknown = Pubkey::new_unique().to_string();
+        let mut event = json!({
+            "event_type": "TokenDeposit",
+            "data": { "token_mint": USDC.to_string(), "amount": 5 },
+            "other": { "mint": unknown },
+        });
+        annotate_token_symbols(&mut event, &mut resolve);
+        assert_eq!(event["data"]["token_mint_symbol"], "USDC");
+        assert!(event["other"].get("mint_symbol").is_none());
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+#[cfg(test)]
+mod token_refresh_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{account::AccountSharedData, signature::Signer};
+    use spl_token::state::Mint;
+    use vault_program::{
+        instruction::VaultInstruction, state::TOKEN_2022_PROGRAM_ID, VaultError, PROGRAM_ID,
+    };
+
+    fn refresh_ix(
+        vault: &Pubkey,
+        authority: &Pubkey,
+        mint_account: &Pubkey,
+        mint: &Pubkey,
+        allow_with_balance: bool,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(*mint_account, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::RefreshSupportedTokenMetadata {
+                mint: *mint,
+                allow_with_balance,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // Rewrites the mint in place as the migration left it: new decimals, and optionally a
+    // new owning program with extension bytes after the classic layout
+    async fn migrate_mint(
+        context: &mut ProgramTestContext,
+        mint: &Pubkey,
+        decimals: u8,
+        owner: &Pubkey,
+        extension_bytes: usize,
+    ) {
+        let account = context.banks_client.get_account(*mint).await.unwrap().unwrap();
+        let mut state = Mint::unpack(&account.data).unwrap();
+        state.decimals = decimals;
+        let mut data = vec![0; Mint::LEN + extension_bytes];
+        Mint::pack(state, &mut data[..Mint::LEN]).unwrap();
+        let mut migrated = AccountSharedData::new(account.lamports, data.len(), owner);
+        migrated.set_data_from_slice(&data);
+        context.set_account(mint, &migrated);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_picks_up_new_decimals() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let authority = context.payer.pubkey();
+        assert_eq!(fetch_vault(&mut context, &vault).await.supported_tokens[0].decimals, 6);
+
+        // The account passed in has to be the mint being refreshed
+        let other = create_mint(&mut context, 9).await.pubkey();
+        let err = send(&mut context, &[refresh_ix(&vault, &authority, &other, &mint, false)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        migrate_mint(&mut context, &mint, 9, &spl_token::id(), 0).await;
+        send(&mut context, &[refresh_ix(&vault, &authority, &mint, &mint, false)], &[])
+            .await
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.supported_tokens[0].decimals, 9);
+        assert_eq!(state.supported_token_program(0), spl_token::id());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_with_balance_needs_flag() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let authority = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &authority, 400)], &[]).await.unwrap();
+
+        // Reissued under Token-2022 with a different precision
+        migrate_mint(&mut context, &mint, 8, &TOKEN_2022_PROGRAM_ID, 83).await;
+        let err = send(&mut context, &[refresh_ix(&vault, &authority, &mint, &mint, false)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TokenBalanceOutstanding));
+        assert_eq!(fetch_vault(&mut context, &vault).await.supported_tokens[0].decimals, 6);
+
+        send(&mut context, &[refresh_ix(&vault, &authority, &mint, &mint, true)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.supported_tokens[0].decimals, 8);
+        assert_eq!(state.supported_token_program(0), TOKEN_2022_PROGRAM_ID);
+        assert_eq!(state.tracked_balance(&mint), 400);
+    }
+}
@@ -0,0 +1,39 @@
+mod common;
+
+#[cfg(test)]
+mod token_stats_tests {
+    use super::common::*;
+    use solana_sdk::signature::Signer;
+
+    #[tokio::test]
+    async fn test_counters_after_scripted_sequence() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 10_000).await;
+        let user = context.payer.pubkey();
+
+        let stats = fetch_vault(&mut context, &vault).await.token_stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!((stats[0].deposit_count, stats[0].withdrawal_count), (0, 0));
+        assert_eq!(stats[0].last_updated, 0);
+
+        for amount in [1_000, 2_000, 3_000] {
+            send(&mut context, &[deposit_ix(&vault, &mint, &user, amount)], &[])
+                .await
+                .unwrap();
+        }
+        for amount in [500, 700] {
+            send(&mut context, &[withdraw_ix(&vault, &mint, &user, amount)], &[])
+                .await
+                .unwrap();
+        }
+
+        let stats = &fetch_vault(&mut context, &vault).await.token_stats()[0];
+        assert_eq!(stats.mint, mint);
+        assert_eq!(stats.deposit_count, 3);
+        assert_eq!(stats.withdrawal_count, 2);
+        assert_eq!(stats.total_deposited, 6_000);
+        assert_eq!(stats.total_withdrawn, 1_200);
+        assert_eq!(stats.balance, 4_800);
+        assert!(stats.last_updated > 0);
+    }
+}
@@ -0,0 +1,116 @@
+mod common;
+
+#[cfg(test)]
+mod token_transfer_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        system_program, sysvar,
+    };
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{instruction::VaultInstruction, state::ParamKey, VaultError, PROGRAM_ID};
+
+    const DEPOSIT: u64 = 1_000;
+
+    fn transfer_v2_ix(
+        vault: &Pubkey,
+        recipient: &Pubkey,
+        authority: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+        payer: Option<&Pubkey>,
+    ) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(get_associated_token_address(vault, mint), false),
+            AccountMeta::new(get_associated_token_address(recipient, mint), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ];
+        if let Some(payer) = payer {
+            accounts.push(AccountMeta::new(*payer, true));
+            accounts.push(AccountMeta::new_readonly(*mint, false));
+            accounts.push(AccountMeta::new_readonly(spl_associated_token_account::id(), false));
+        }
+        let data = VaultInstruction::TransferV2 {
+            recipient: *recipient,
+            mint: Some(*mint),
+            amount,
+            create_ata: payer.is_some(),
+        };
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: data.try_to_vec().unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_token_payout_creates_recipient_ata() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, DEPOSIT).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::WithdrawalFeeBps, 100);
+        store_vault(&mut context, &vault, &state).await;
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, DEPOSIT)], &[]).await.unwrap();
+
+        // The recipient has never held the mint
+        let recipient = Pubkey::new_unique();
+        let recipient_tokens = get_associated_token_address(&recipient, &mint);
+        assert!(context
+            .banks_client
+            .get_account(recipient_tokens)
+            .await
+            .unwrap()
+            .is_none());
+
+        // The withdrawal fee isn't charged on the authority's own payouts
+        let transfer = transfer_v2_ix(&vault, &recipient, &payer, &mint, 400, Some(&payer));
+        send(&mut context, &[transfer], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &recipient_tokens).await, 400);
+        let vault_tokens = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &vault_tokens).await, 600);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 600);
+        assert_eq!(state.accrued_fee(&mint), 0);
+        assert_eq!(state.total_value_locked, 600);
+
+        // The account exists now, so creating it again is a no-op
+        advance_clock(&mut context, 1).await;
+        let transfer = transfer_v2_ix(&vault, &recipient, &payer, &mint, 100, Some(&payer));
+        send(&mut context, &[transfer], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &recipient_tokens).await, 500);
+    }
+
+    #[tokio::test]
+    async fn test_token_payout_requires_authority() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, DEPOSIT).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, DEPOSIT)], &[]).await.unwrap();
+
+        let stranger = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let transfer =
+            transfer_v2_ix(&vault, &recipient, &stranger.pubkey(), &mint, 400, Some(&payer));
+        let err = send(&mut context, &[transfer], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InsufficientAuthority as u32)
+            )
+        );
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), DEPOSIT);
+    }
+}
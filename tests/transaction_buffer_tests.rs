@@ -0,0 +1,213 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+#[cfg(test)]
+mod transaction_buffer_tests {
+    use super::common::*;
+    use solana_program::{
+        instruction::AccountMeta, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey,
+        system_instruction,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        client::{
+            close_transaction_buffer, create_transaction_buffer, finalize_transaction_from_buffer,
+            transaction_buffer_address, write_transaction_buffer,
+        },
+        state::{TransactionAccount, TransactionBuffer},
+        VaultError,
+    };
+
+    const NONCE: u8 = 17;
+    const HARVEST_REWARD: u64 = 25;
+
+    async fn start() -> ProgramTestContext {
+        let mut test = program_test();
+        let config = MockDefiConfig {
+            harvest_reward: HARVEST_REWARD,
+            ..MockDefiConfig::default()
+        };
+        add_mock_defi(&mut test, MOCK_DEFI_ID, config);
+        test.start_with_context().await
+    }
+
+    // 1-of-1 multisig of the payer
+    async fn multisig_vault(context: &mut ProgramTestContext) -> Pubkey {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let initialize = initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE);
+        send(context, &[initialize], &[]).await.unwrap();
+        vault
+    }
+
+    fn transaction_accounts(metas: &[AccountMeta]) -> Vec<TransactionAccount> {
+        metas
+            .iter()
+            .map(|meta| TransactionAccount {
+                pubkey: meta.pubkey,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_three_chunk_payload_executes() {
+        let mut context = start().await;
+        let vault = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let reward_mint = mock_defi::reward_mint(&MOCK_DEFI_ID);
+        let destination = create_funded_ata(&mut context, &reward_mint, &signer, 0).await;
+        let harvest = mock_defi::harvest_accounts(&MOCK_DEFI_ID, &destination);
+
+        // A harvest, padded with bytes the mock ignores past what one transaction could carry
+        // alongside the proposal
+        let mut payload = mock_defi::harvest_data();
+        payload.extend((1..900u32).map(|i| i as u8));
+        let buffer = transaction_buffer_address(&vault, &payer);
+        send(
+            &mut context,
+            &[create_transaction_buffer(&vault, &payer, payload.len() as u32)],
+            &[],
+        )
+        .await
+        .unwrap();
+        for (index, chunk) in payload.chunks(300).enumerate() {
+            let offset = (index * 300) as u32;
+            let write = write_transaction_buffer(&vault, &payer, offset, chunk.to_vec());
+            send(&mut context, &[write], &[]).await.unwrap();
+        }
+        let account = context.banks_client.get_account(buffer).await.unwrap().unwrap();
+        let staged = TransactionBuffer::load(&account.data).unwrap();
+        assert!(staged.is_complete());
+        assert_eq!(staged.data, payload);
+
+        let finalize = finalize_transaction_from_buffer(
+            &vault,
+            &payer,
+            MOCK_DEFI_ID,
+            transaction_accounts(&harvest),
+        );
+        send(&mut context, &[finalize], &[]).await.unwrap();
+        assert!(context.banks_client.get_account(buffer).await.unwrap().is_none());
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multisig_transaction(0).unwrap().data, payload);
+
+        let mut remaining = vec![AccountMeta::new_readonly(MOCK_DEFI_ID, false)];
+        remaining.extend(harvest);
+        let execute = execute_multisig_tx_ix(&vault, &signer, &payer, 0, remaining);
+        send(&mut context, &[execute], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &destination).await, HARVEST_REWARD);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.multisig_transaction(0).unwrap().did_execute);
+    }
+
+    #[tokio::test]
+    async fn test_writes_are_bounds_checked() {
+        let mut context = start().await;
+        let vault = multisig_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let buffer = transaction_buffer_address(&vault, &payer);
+        let create = create_transaction_buffer(&vault, &payer, 10);
+        send(&mut context, &[create], &[]).await.unwrap();
+        let finalize = || finalize_transaction_from_buffer(&vault, &payer, MOCK_DEFI_ID, vec![]);
+
+        // A gap before the offset, past the end, or from someone other than the proposer
+        let stranger = Keypair::new();
+        let mut foreign = write_transaction_buffer(&vault, &stranger.pubkey(), 0, vec![1]);
+        foreign.accounts[1].pubkey = buffer;
+        let gap = write_transaction_buffer(&vault, &payer, 4, vec![1]);
+        let past_end = write_transaction_buffer(&vault, &payer, 0, vec![1; 11]);
+        for (write, signers, error) in [
+            (gap, vec![], VaultError::InvalidTransactionData),
+            (past_end, vec![], VaultError::InvalidTransactionData),
+            (foreign, vec![&stranger], VaultError::InvalidOwner),
+        ] {
+            let err = send(&mut context, &[write], &signers).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, error));
+        }
+
+        // Rewriting what is already there is fine; finalizing needs every byte
+        let chunks = [(0, vec![1; 6]), (4, vec![2; 4])];
+        for (offset, chunk) in chunks {
+            let write = write_transaction_buffer(&vault, &payer, offset, chunk);
+            send(&mut context, &[write], &[]).await.unwrap();
+        }
+        let err = send(&mut context, &[finalize()], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+
+        let write = write_transaction_buffer(&vault, &payer, 8, vec![3; 2]);
+        send(&mut context, &[write, finalize()], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(
+            state.multisig_transaction(0).unwrap().data,
+            [1, 1, 1, 1, 2, 2, 2, 2, 3, 3]
+        );
+    }
+
+    // 1-of-2 multisig of the payer and a funded second owner
+    async fn shared_multisig_vault(context: &mut ProgramTestContext) -> (Pubkey, Keypair) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let owner = Keypair::new();
+        let fund = system_instruction::transfer(&payer, &owner.pubkey(), LAMPORTS_PER_SOL);
+        let owners = vec![payer, owner.pubkey()];
+        let initialize = initialize_multisig_ix(&vault, &payer, owners, 1, NONCE);
+        send(context, &[fund, initialize], &[]).await.unwrap();
+        (vault, owner)
+    }
+
+    #[tokio::test]
+    async fn test_removed_owner_can_no_longer_write() {
+        let mut context = start().await;
+        let (vault, owner) = shared_multisig_vault(&mut context).await;
+        let create = create_transaction_buffer(&vault, &owner.pubkey(), 10);
+        send(&mut context, &[create], &[&owner]).await.unwrap();
+
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.multi_sig.as_mut().unwrap().owners.retain(|key| *key == payer);
+        store_vault(&mut context, &vault, &state).await;
+        let write = write_transaction_buffer(&vault, &owner.pubkey(), 0, vec![1; 10]);
+        let err = send(&mut context, &[write], &[&owner]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidOwner));
+    }
+
+    #[tokio::test]
+    async fn test_authority_closes_buffer_and_refunds_proposer() {
+        let mut context = start().await;
+        let (vault, owner) = shared_multisig_vault(&mut context).await;
+        let buffer = transaction_buffer_address(&vault, &owner.pubkey());
+        let create = create_transaction_buffer(&vault, &owner.pubkey(), 10);
+        send(&mut context, &[create], &[&owner]).await.unwrap();
+
+        // Neither a stranger nor a refund address other than the proposer
+        let stranger = Keypair::new();
+        let close = close_transaction_buffer(&vault, &owner.pubkey(), &stranger.pubkey());
+        let err = send(&mut context, &[close], &[&stranger]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+        let payer = context.payer.pubkey();
+        let mut misdirected = close_transaction_buffer(&vault, &owner.pubkey(), &payer);
+        misdirected.accounts[3].pubkey = stranger.pubkey();
+        let err = send(&mut context, &[misdirected], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+
+        let rent = context.banks_client.get_account(buffer).await.unwrap().unwrap().lamports;
+        let before = context.banks_client.get_balance(owner.pubkey()).await.unwrap();
+        let close = close_transaction_buffer(&vault, &owner.pubkey(), &payer);
+        send(&mut context, &[close], &[]).await.unwrap();
+        assert!(context.banks_client.get_account(buffer).await.unwrap().is_none());
+        let after = context.banks_client.get_balance(owner.pubkey()).await.unwrap();
+        assert_eq!(after, before + rent);
+
+        // The proposer can close their own, and the address is free again afterwards
+        let recreate = create_transaction_buffer(&vault, &owner.pubkey(), 12);
+        send(&mut context, &[recreate], &[&owner]).await.unwrap();
+        let close = close_transaction_buffer(&vault, &owner.pubkey(), &owner.pubkey());
+        send(&mut context, &[close], &[&owner]).await.unwrap();
+        assert!(context.banks_client.get_account(buffer).await.unwrap().is_none());
+    }
+}
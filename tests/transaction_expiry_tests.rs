@@ -0,0 +1,222 @@
+mod common;
+
+#[cfg(test)]
+mod transaction_expiry_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{ParamKey, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 13;
+    const TTL: i64 = 3_600;
+
+    fn extend_ix(
+        vault: &Pubkey,
+        signer: &Pubkey,
+        transaction_id: u64,
+        expires_at: i64,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*signer, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::ExtendTransactionExpiry {
+                transaction_id,
+                new_expires_at: expires_at,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // SetParam through the multisig, so executing it changes something observable
+    fn propose_min_deposit_ix(vault: &Pubkey, proposer: &Pubkey, value: u64) -> Instruction {
+        let accounts = vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let data = VaultInstruction::SetParam {
+            key: ParamKey::MinDepositAmount,
+            value,
+        }
+        .try_to_vec()
+        .unwrap();
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, accounts, data)
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, transaction_id: u64) -> Instruction {
+        execute_multisig_tx_ix(
+            vault,
+            &multisig_signer_pda(vault, NONCE),
+            executor,
+            transaction_id,
+            vec![AccountMeta::new_readonly(PROGRAM_ID, false)],
+        )
+    }
+
+    async fn now(context: &mut ProgramTestContext) -> i64 {
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp
+    }
+
+    // 2-of-2 multisig of the payer and `owner` under a one hour TTL, with transactions 0
+    // and 1 proposed by the payer; returns the time they were created
+    async fn setup(context: &mut ProgramTestContext, owner: &Keypair) -> (Pubkey, i64) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let owners = vec![payer, owner.pubkey()];
+        send(context, &[initialize_multisig_ix(&vault, &payer, owners, 2, NONCE)], &[])
+            .await
+            .unwrap();
+        let mut state = fetch_vault(context, &vault).await;
+        state.set_param(ParamKey::MultisigTransactionTtl, TTL as u64);
+        store_vault(context, &vault, &state).await;
+
+        let created_at = now(context).await;
+        send(
+            context,
+            &[
+                propose_min_deposit_ix(&vault, &payer, 10),
+                propose_min_deposit_ix(&vault, &payer, 20),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, created_at)
+    }
+
+    #[tokio::test]
+    async fn test_extended_transaction_executes_after_original_deadline() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, created_at) = setup(&mut context, &owner).await;
+        let payer = context.payer.pubkey();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.transaction_expires_at(0), Some(created_at + TTL));
+
+        // Nearly lapsed; the other owner pushes it back by up to a TTL from now
+        advance_clock(&mut context, TTL - 100).await;
+        let latest = created_at + 2 * TTL - 100;
+        let too_late = extend_ix(&vault, &owner.pubkey(), 0, latest + 1);
+        let err = send(&mut context, &[too_late], &[&owner]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[extend_ix(&vault, &owner.pubkey(), 0, latest)], &[&owner])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.transaction_expires_at(0), Some(latest));
+
+        // Past the original deadline: transaction 1 has lapsed, transaction 0 hasn't
+        advance_clock(&mut context, 200).await;
+        for expired in [
+            approve_multisig_tx_ix(&vault, &owner.pubkey(), 1),
+            extend_ix(&vault, &owner.pubkey(), 1, latest),
+        ] {
+            let err = send(&mut context, &[expired], &[&owner]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::TransactionExpired));
+        }
+
+        send(
+            &mut context,
+            &[approve_multisig_tx_ix(&vault, &owner.pubkey(), 0), execute_ix(&vault, &payer, 0)],
+            &[&owner],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.param(ParamKey::MinDepositAmount), 10);
+
+        // Executed transactions are past extending
+        let err = send(&mut context, &[extend_ix(&vault, &payer, 0, latest)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionAlreadyExecuted));
+    }
+
+    #[tokio::test]
+    async fn test_expired_transaction_cannot_execute() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, created_at) = setup(&mut context, &owner).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[approve_multisig_tx_ix(&vault, &owner.pubkey(), 0)], &[&owner])
+            .await
+            .unwrap();
+
+        // Only the proposer or an owner may extend, and never backwards
+        let stranger = Keypair::new();
+        let err = send(
+            &mut context,
+            &[extend_ix(&vault, &stranger.pubkey(), 0, created_at + TTL + 10)],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidOwner));
+        let err = send(&mut context, &[extend_ix(&vault, &payer, 0, created_at + TTL)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+
+        advance_clock(&mut context, TTL).await;
+        let err = send(&mut context, &[execute_ix(&vault, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TransactionExpired));
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.param(ParamKey::MinDepositAmount), 0);
+    }
+
+    #[tokio::test]
+    async fn test_new_proposal_frees_expired_slots() {
+        let mut context = program_test().start_with_context().await;
+        let owner = Keypair::new();
+        let (vault, created_at) = setup(&mut context, &owner).await;
+        let payer = context.payer.pubkey();
+
+        // Transaction 1 is kept alive, transaction 0 lapses
+        advance_clock(&mut context, TTL - 100).await;
+        let extended = created_at + 2 * TTL - 100;
+        send(&mut context, &[extend_ix(&vault, &payer, 1, extended)], &[])
+            .await
+            .unwrap();
+        advance_clock(&mut context, 100).await;
+        send(&mut context, &[propose_min_deposit_ix(&vault, &payer, 30)], &[])
+            .await
+            .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.multi_sig_transaction_ids, vec![1, 2]);
+        assert_eq!(state.transaction_expires_at(0), None);
+        assert_eq!(state.transaction_expires_at(1), Some(extended));
+        assert!(state.multisig_transaction(0).is_none());
+    }
+}
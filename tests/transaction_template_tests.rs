@@ -0,0 +1,237 @@
+mod common;
+
+#[cfg(test)]
+mod transaction_template_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    };
+    use solana_sdk::signature::{Keypair, Signer};
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{ByteRange, TransactionAccount, TransactionTemplate},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 8;
+
+    // Pays `recipient` from the multisig signer; the lamports (bytes 4..12) vary per use
+    fn payout_template(signer: &Pubkey, recipient: &Pubkey) -> TransactionTemplate {
+        TransactionTemplate {
+            id: 0,
+            name: "payout".to_string(),
+            program_id: system_program::id(),
+            accounts: vec![
+                TransactionAccount { pubkey: *signer, is_signer: true, is_writable: true },
+                TransactionAccount { pubkey: *recipient, is_signer: false, is_writable: true },
+            ],
+            data_prefix: system_instruction::transfer(signer, recipient, 0).data,
+            mutable_ranges: vec![ByteRange { offset: 4, len: 8 }],
+        }
+    }
+
+    // Proposes an instruction signed by the multisig signer alone
+    fn propose_ix(vault: &Pubkey, proposer: &Pubkey, inner: VaultInstruction) -> Instruction {
+        let inner_accounts = vec![
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: true },
+            TransactionAccount {
+                pubkey: multisig_signer_pda(vault, NONCE),
+                is_signer: true,
+                is_writable: false,
+            },
+            TransactionAccount {
+                pubkey: solana_sdk::sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let inner_data = inner.try_to_vec().unwrap();
+        create_multisig_tx_ix(vault, proposer, PROGRAM_ID, inner_accounts, inner_data)
+    }
+
+    fn execute_ix(vault: &Pubkey, executor: &Pubkey, id: u64, remaining: Vec<AccountMeta>) -> Instruction {
+        execute_multisig_tx_ix(vault, &multisig_signer_pda(vault, NONCE), executor, id, remaining)
+    }
+
+    fn from_template_ix(
+        vault: &Pubkey,
+        proposer: &Pubkey,
+        template_id: u64,
+        fill_bytes: Vec<u8>,
+    ) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*proposer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateTransactionFromTemplate {
+                template_id,
+                fill_bytes,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_instantiate_splices_ranges() {
+        let template = TransactionTemplate {
+            name: "two fields".to_string(),
+            accounts: vec![TransactionAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: false,
+            }],
+            data_prefix: vec![9, 0, 0, 7, 0, 0],
+            mutable_ranges: vec![ByteRange { offset: 1, len: 2 }, ByteRange { offset: 4, len: 2 }],
+            ..Default::default()
+        };
+        assert!(template.validate().is_ok());
+        assert_eq!(template.fill_len(), 4);
+        assert_eq!(template.instantiate(&[1, 2, 3, 4]).unwrap(), vec![9, 1, 2, 7, 3, 4]);
+        assert_eq!(template.instantiate(&[1, 2, 3]), Err(VaultError::InvalidTransactionData));
+
+        let overlapping = TransactionTemplate {
+            mutable_ranges: vec![ByteRange { offset: 1, len: 2 }, ByteRange { offset: 2, len: 2 }],
+            ..template.clone()
+        };
+        assert_eq!(overlapping.validate(), Err(VaultError::InvalidTransactionData));
+        let past_end = TransactionTemplate {
+            mutable_ranges: vec![ByteRange { offset: 5, len: 2 }],
+            ..template
+        };
+        assert_eq!(past_end.validate(), Err(VaultError::InvalidTransactionData));
+    }
+
+    #[tokio::test]
+    async fn test_payouts_from_template() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let recipient = Keypair::new().pubkey();
+        let template = payout_template(&signer, &recipient);
+        let create_template = VaultInstruction::CreateTemplate {
+            name: template.name.clone(),
+            program_id: template.program_id,
+            accounts: template.accounts.clone(),
+            data_prefix: template.data_prefix.clone(),
+            mutable_ranges: template.mutable_ranges.clone(),
+        };
+        send(
+            &mut context,
+            &[
+                system_instruction::transfer(&payer, &signer, 1_000_000_000),
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                propose_ix(&vault, &payer, create_template),
+                execute_ix(&vault, &payer, 0, vec![AccountMeta::new_readonly(PROGRAM_ID, false)]),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.templates.len(), 1);
+        assert_eq!(state.template(0).unwrap().name, "payout");
+
+        let payout_accounts = vec![
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(signer, false),
+            AccountMeta::new(recipient, false),
+        ];
+        send(
+            &mut context,
+            &[
+                from_template_ix(&vault, &payer, 0, 1_000_000u64.to_le_bytes().to_vec()),
+                from_template_ix(&vault, &payer, 0, 2_500_000u64.to_le_bytes().to_vec()),
+                execute_ix(&vault, &payer, 1, payout_accounts.clone()),
+                execute_ix(&vault, &payer, 2, payout_accounts),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        for (id, amount) in [(1, 1_000_000), (2, 2_500_000)] {
+            let transaction = state.multisig_transaction(id).unwrap();
+            assert!(transaction.did_execute);
+            assert_eq!(transaction.program_id, system_program::id());
+            assert_eq!(transaction.accounts.len(), 2);
+            assert_eq!(
+                transaction.data,
+                system_instruction::transfer(&signer, &recipient, amount).data
+            );
+        }
+        assert_eq!(context.banks_client.get_balance(recipient).await.unwrap(), 3_500_000);
+
+        let err = send(&mut context, &[from_template_ix(&vault, &payer, 0, vec![1; 4])], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidTransactionData));
+    }
+
+    #[tokio::test]
+    async fn test_deleted_template_unusable() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(&vault, NONCE);
+        let template = payout_template(&signer, &Pubkey::new_unique());
+        let create_template = VaultInstruction::CreateTemplate {
+            name: template.name,
+            program_id: template.program_id,
+            accounts: template.accounts,
+            data_prefix: template.data_prefix,
+            mutable_ranges: template.mutable_ranges,
+        };
+        let self_cpi = || vec![AccountMeta::new_readonly(PROGRAM_ID, false)];
+        send(
+            &mut context,
+            &[
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                propose_ix(&vault, &payer, create_template),
+                execute_ix(&vault, &payer, 0, self_cpi()),
+                propose_ix(&vault, &payer, VaultInstruction::DeleteTemplate { template_id: 0 }),
+                execute_ix(&vault, &payer, 1, self_cpi()),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.templates.is_empty());
+        assert_eq!(state.next_template_id, 1);
+
+        let fill = 5u64.to_le_bytes().to_vec();
+        let err = send(&mut context, &[from_template_ix(&vault, &payer, 0, fill)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::TemplateNotFound));
+
+        // Owners can't manage templates without going through the multisig
+        let direct = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::DeleteTemplate { template_id: 0 }
+                .try_to_vec()
+                .unwrap(),
+        };
+        let err = send(&mut context, &[direct], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InsufficientAuthority));
+    }
+}
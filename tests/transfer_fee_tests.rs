@@ -0,0 +1,84 @@
+mod common;
+
+#[cfg(test)]
+mod transfer_fee_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        system_instruction, system_program, sysvar,
+    };
+    use solana_sdk::signature::Signer;
+    use vault_program::{instruction::VaultInstruction, state::ParamKey, PROGRAM_ID};
+
+    const FUNDED: u64 = 2_000_000_000;
+    const AMOUNT: u64 = 1_000_000_000;
+
+    // The authority's SOL payout used to book a withdrawal fee that never left the vault
+    #[tokio::test]
+    async fn test_sol_transfer_reconciles_lamports() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let fee_recipient = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::WithdrawalFeeBps, 100);
+        state.fee_config.fee_recipient = fee_recipient;
+        store_vault(&mut context, &vault, &state).await;
+        let sweep = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::SweepUntrackedLamports.try_to_vec().unwrap(),
+        };
+        send(
+            &mut context,
+            &[
+                system_instruction::transfer(&payer, &vault, FUNDED),
+                system_instruction::transfer(&payer, &fee_recipient, 1_000_000),
+                sweep,
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let before = fetch_vault(&mut context, &vault).await;
+        let vault_before = context.banks_client.get_balance(vault).await.unwrap();
+        let transfer = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::Transfer {
+                recipient,
+                amount: AMOUNT,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        send(&mut context, &[transfer], &[]).await.unwrap();
+
+        let banks = &mut context.banks_client;
+        assert_eq!(banks.get_balance(recipient).await.unwrap(), AMOUNT);
+        assert_eq!(banks.get_balance(fee_recipient).await.unwrap(), 1_000_000);
+        let vault_after = banks.get_balance(vault).await.unwrap();
+        assert_eq!(vault_before - vault_after, AMOUNT);
+
+        // The books moved by exactly what the lamports did
+        let after = fetch_vault(&mut context, &vault).await;
+        assert_eq!(before.tracked_lamports - after.tracked_lamports, AMOUNT);
+        assert_eq!(before.total_value_locked - after.total_value_locked, u128::from(AMOUNT));
+        assert_eq!(after.total_fees_collected, before.total_fees_collected);
+        assert_eq!(after.accrued_fee(&Pubkey::default()), 0);
+    }
+}
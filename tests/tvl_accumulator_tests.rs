@@ -0,0 +1,128 @@
+mod common;
+
+#[cfg(test)]
+mod tvl_accumulator_tests {
+    use super::common::*;
+    use solana_program::instruction::InstructionError;
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use vault_program::VaultError;
+
+    #[tokio::test]
+    async fn test_accumulator_over_deposit_withdraw_timeline() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let created = fetch_vault(&mut context, &vault).await;
+        assert!(created.tvl_last_updated > 0);
+        assert_eq!(created.tvl_cumulative, 0);
+
+        // TVL 0 until the deposit, so nothing accrues before it
+        advance_clock(&mut context, 30).await;
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 1_000)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tvl_last_updated, created.tvl_last_updated + 30);
+        assert_eq!(state.tvl_cumulative, 0);
+
+        // 1_000 held for 100s, then 600 for 50s, then 1_000 again for 20s
+        advance_clock(&mut context, 100).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 400)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tvl_cumulative, 100_000);
+
+        advance_clock(&mut context, 50).await;
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 400)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tvl_cumulative, 130_000);
+
+        advance_clock(&mut context, 20).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 1_000)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tvl_cumulative, 150_000);
+        assert_eq!(state.total_value_locked, 0);
+        assert_eq!(state.tvl_last_updated, created.tvl_last_updated + 200);
+
+        // 150_000 over the 170s the vault held anything
+        assert_eq!(state.tvl_cumulative / 170, 882);
+    }
+
+    #[tokio::test]
+    async fn test_vault_without_accumulator_starts_at_next_change() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 500)], &[]).await.unwrap();
+
+        // As written before the accumulator existed
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.tvl_last_updated = 0;
+        store_vault(&mut context, &vault, &state).await;
+
+        advance_clock(&mut context, 1_000).await;
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 500)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tvl_cumulative, 0);
+        assert!(state.tvl_last_updated > 0);
+
+        advance_clock(&mut context, 10).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 1)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tvl_cumulative, 10_000);
+    }
+
+    // Two 9-decimal mints, each deposited near its u64::MAX supply, put the TVL past what a
+    // u64 holds
+    #[tokio::test]
+    async fn test_tvl_past_u64_max() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let payer = context.payer.pubkey();
+        let amount = u64::MAX - 1_000;
+        let mut mints = Vec::new();
+        for _ in 0..2 {
+            let mint = create_mint(&mut context, 9).await.pubkey();
+            let add = add_supported_token_ix(&vault, &mint, &payer, &payer);
+            send(&mut context, &[add], &[]).await.unwrap();
+            create_funded_ata(&mut context, &mint, &payer, amount).await;
+            mints.push(mint);
+        }
+        let deposits: Vec<_> = mints
+            .iter()
+            .map(|mint| deposit_ix(&vault, mint, &payer, amount))
+            .collect();
+        send(&mut context, &deposits, &[]).await.unwrap();
+
+        let state = fetch_vault(&mut context, &vault).await;
+        let tracked: u128 = mints.iter().map(|mint| u128::from(state.tracked_balance(mint))).sum();
+        assert_eq!(state.total_value_locked, tracked);
+        assert!(state.total_value_locked > u128::from(u64::MAX));
+
+        // The accumulator keeps crediting it at full width
+        advance_clock(&mut context, 10).await;
+        send(&mut context, &[withdraw_ix(&vault, &mints[0], &payer, 1_000)], &[]).await.unwrap();
+        let after = fetch_vault(&mut context, &vault).await;
+        assert_eq!(after.tvl_cumulative, state.total_value_locked * 10);
+        assert!(after.total_value_locked < state.total_value_locked);
+    }
+
+    // A TVL that has fallen behind the balances refuses the withdrawal instead of wrapping
+    #[tokio::test]
+    async fn test_withdrawal_past_tvl_is_refused() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 500).await;
+        let payer = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 500)], &[]).await.unwrap();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.total_value_locked = 100;
+        store_vault(&mut context, &vault, &state).await;
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 200)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::ArithmeticOverflow as u32)
+            )
+        );
+        assert_eq!(fetch_vault(&mut context, &vault).await.total_value_locked, 100);
+    }
+}
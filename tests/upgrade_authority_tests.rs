@@ -0,0 +1,263 @@
+mod common;
+
+#[cfg(test)]
+mod upgrade_authority_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{signature::Signer, transaction::TransactionError};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{program_data_upgrade_authority, GlobalConfig, TransactionAccount},
+        VaultError, PROGRAM_ID,
+    };
+
+    const NONCE: u8 = 7;
+    const THRESHOLD: u64 = 10_000;
+
+    struct Setup {
+        vault: Pubkey,
+        mint: Pubkey,
+        fee_recipient: Pubkey,
+        protocol_fee_recipient: Pubkey,
+        recipient: Pubkey,
+    }
+
+    // Fee-free vault holding 100_000 tokens whose 1-of-1 multisig (the payer) administers the
+    // global config, with the fee routing and emergency recipient ATAs in place
+    async fn setup(context: &mut ProgramTestContext) -> Setup {
+        let (vault, mint) = vault_with_token(context, 100_000).await;
+        let payer = context.payer.pubkey();
+        deploy_program_data(context, Some(payer));
+        let config = GlobalConfig {
+            admin: vault,
+            max_fee_bps: 1_000,
+            protocol_fee_recipient: Pubkey::new_unique(),
+            ..GlobalConfig::default()
+        };
+        let fee_recipient = Pubkey::new_unique();
+        let mut state = fetch_vault(context, &vault).await;
+        state.fee_config.fee_recipient = fee_recipient;
+        store_vault(context, &vault, &state).await;
+        let recipient = Pubkey::new_unique();
+        for owner in [fee_recipient, config.protocol_fee_recipient, recipient] {
+            create_funded_ata(context, &mint, &owner, 0).await;
+        }
+        let protocol_fee_recipient = config.protocol_fee_recipient;
+        send(
+            context,
+            &[
+                deposit_ix(&vault, &mint, &payer, 100_000),
+                initialize_multisig_ix(&vault, &payer, vec![payer], 1, NONCE),
+                initialize_global_config_ix(&payer, config),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        Setup {
+            vault,
+            mint,
+            fee_recipient,
+            protocol_fee_recipient,
+            recipient,
+        }
+    }
+
+    // Proposes and executes AcknowledgeUpgradeAuthority as multisig transaction `transaction_id`
+    async fn acknowledge(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        transaction_id: u64,
+        expected: Option<Pubkey>,
+    ) {
+        let payer = context.payer.pubkey();
+        let signer = multisig_signer_pda(vault, NONCE);
+        let data = VaultInstruction::AcknowledgeUpgradeAuthority {
+            expected_upgrade_authority: expected,
+            upgrade_check_threshold: THRESHOLD,
+        }
+        .try_to_vec()
+        .unwrap();
+        let accounts = vec![
+            TransactionAccount {
+                pubkey: global_config_pda(),
+                is_signer: false,
+                is_writable: true,
+            },
+            TransactionAccount { pubkey: *vault, is_signer: false, is_writable: false },
+            TransactionAccount { pubkey: signer, is_signer: true, is_writable: false },
+            TransactionAccount {
+                pubkey: sysvar::clock::id(),
+                is_signer: false,
+                is_writable: false,
+            },
+        ];
+        let remaining = vec![
+            AccountMeta::new_readonly(PROGRAM_ID, false),
+            AccountMeta::new(global_config_pda(), false),
+        ];
+        send(
+            context,
+            &[
+                create_multisig_tx_ix(vault, &payer, PROGRAM_ID, accounts, data),
+                execute_multisig_tx_ix(vault, &signer, &payer, transaction_id, remaining),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    fn routed_withdraw(setup: &Setup, payer: &Pubkey, amount: u64, proof: bool) -> Instruction {
+        let mut ix = withdraw_ix(&setup.vault, &setup.mint, payer, amount);
+        let fee_account = get_associated_token_address(&setup.fee_recipient, &setup.mint);
+        let protocol_fee_account =
+            get_associated_token_address(&setup.protocol_fee_recipient, &setup.mint);
+        ix.accounts.extend([
+            AccountMeta::new(fee_account, false),
+            AccountMeta::new(protocol_fee_account, false),
+        ]);
+        if proof {
+            ix.accounts.push(AccountMeta::new_readonly(program_data_address(), false));
+        }
+        ix
+    }
+
+    fn emergency_withdraw_ix(setup: &Setup, admin: &Pubkey, amount: u64) -> Instruction {
+        let recipient = get_associated_token_address(&setup.recipient, &setup.mint);
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(setup.vault, false),
+                AccountMeta::new(get_associated_token_address(&setup.vault, &setup.mint), false),
+                AccountMeta::new(recipient, false),
+                AccountMeta::new_readonly(*admin, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(global_config_pda(), false),
+                AccountMeta::new_readonly(program_data_address(), false),
+            ],
+            data: VaultInstruction::EmergencyWithdraw {
+                token_mint: setup.mint,
+                amount,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_program_data_layout() {
+        let authority = Pubkey::new_unique();
+        assert_eq!(
+            program_data_upgrade_authority(&program_data(Some(authority))),
+            Ok(Some(authority))
+        );
+        assert_eq!(program_data_upgrade_authority(&program_data(None)), Ok(None));
+
+        // A Program account (tag 2) is not ProgramData
+        let mut program = program_data(Some(authority));
+        program[0] = 2;
+        assert_eq!(
+            program_data_upgrade_authority(&program),
+            Err(VaultError::InvalidAccountData)
+        );
+        assert_eq!(
+            program_data_upgrade_authority(&program_data(Some(authority))[..20]),
+            Err(VaultError::InvalidAccountData)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_large_withdrawals_need_expected_authority() {
+        let mut context = program_test().start_with_context().await;
+        let setup = setup(&mut context).await;
+        let payer = context.payer.pubkey();
+        let deployer = Pubkey::new_unique();
+        acknowledge(&mut context, &setup.vault, 0, Some(deployer)).await;
+        let config = fetch_global_config(&mut context).await;
+        assert_eq!(config.expected_upgrade_authority, Some(deployer));
+        assert_eq!(config.upgrade_check_threshold, THRESHOLD);
+        deploy_program_data(&mut context, Some(Pubkey::new_unique()));
+
+        // Up to the threshold nothing has to be proven
+        send(&mut context, &[routed_withdraw(&setup, &payer, THRESHOLD, false)], &[])
+            .await
+            .unwrap();
+
+        let missing = routed_withdraw(&setup, &payer, 20_000, false);
+        let err = send(&mut context, &[missing], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UpgradeAuthorityMismatch));
+
+        // Leaving the config out doesn't skip the check either, nor does a stand-in for it
+        let mut unrouted = withdraw_ix(&setup.vault, &setup.mint, &payer, 20_000);
+        unrouted.accounts.pop();
+        let err = send(&mut context, &[unrouted.clone()], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+        unrouted.accounts.push(AccountMeta::new(Pubkey::new_unique(), false));
+        let err = send(&mut context, &[unrouted], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAccountData));
+        let unrouted = withdraw_ix(&setup.vault, &setup.mint, &payer, 20_000);
+        let err = send(&mut context, &[unrouted], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UpgradeAuthorityMismatch));
+
+        let swapped = routed_withdraw(&setup, &payer, 20_000, true);
+        let err = send(&mut context, &[swapped.clone()], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UpgradeAuthorityMismatch));
+
+        deploy_program_data(&mut context, Some(deployer));
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[swapped], &[]).await.unwrap();
+
+        let user = get_associated_token_address(&payer, &setup.mint);
+        assert_eq!(token_balance(&mut context, &user).await, 30_000);
+        let state = fetch_vault(&mut context, &setup.vault).await;
+        assert_eq!(state.tracked_balance(&setup.mint), 70_000);
+    }
+
+    #[tokio::test]
+    async fn test_emergency_withdraw_after_acknowledged_handover() {
+        let mut context = program_test().start_with_context().await;
+        let setup = setup(&mut context).await;
+        let admin = context.payer.pubkey();
+        let old_deployer = Pubkey::new_unique();
+        let new_deployer = Pubkey::new_unique();
+        acknowledge(&mut context, &setup.vault, 0, Some(old_deployer)).await;
+        deploy_program_data(&mut context, Some(new_deployer));
+
+        let ix = emergency_withdraw_ix(&setup, &admin, 400);
+        let err = send(&mut context, &[ix.clone()], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UpgradeAuthorityMismatch));
+        let mut unchecked = ix.clone();
+        unchecked.accounts.truncate(6);
+        let err = send(&mut context, &[unchecked], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+
+        acknowledge(&mut context, &setup.vault, 1, Some(new_deployer)).await;
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[ix], &[]).await.unwrap();
+        let recipient = get_associated_token_address(&setup.recipient, &setup.mint);
+        assert_eq!(token_balance(&mut context, &recipient).await, 400);
+
+        // Made immutable, the program can't be swapped any more
+        deploy_program_data(&mut context, None);
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[emergency_withdraw_ix(&setup, &admin, 100)], &[])
+            .await
+            .unwrap();
+        assert_eq!(token_balance(&mut context, &recipient).await, 500);
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+#[cfg(test)]
+mod validation_helper_tests {
+    use super::common::*;
+    use solana_program::{
+        account_info::AccountInfo, clock::Clock, program_error::ProgramError, pubkey::Pubkey,
+        system_program,
+    };
+    use solana_sdk::{
+        instruction::InstructionError, signature::Signer, transaction::TransactionError,
+    };
+    use vault_program::{
+        modules::utils::{
+            load_vault_checked, require_authority, require_multisig_owner, require_not_paused,
+        },
+        state::{MultiSig, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    fn account<'a>(
+        key: &'a Pubkey,
+        is_signer: bool,
+        owner: &'a Pubkey,
+        lamports: &'a mut u64,
+        data: &'a mut [u8],
+    ) -> AccountInfo<'a> {
+        AccountInfo::new(key, is_signer, false, lamports, data, owner, false, 0)
+    }
+
+    fn vault_bytes(vault: &Vault) -> Vec<u8> {
+        let mut data = vec![0; vault.required_space().unwrap()];
+        vault.save(&mut data).unwrap();
+        data
+    }
+
+    fn current_vault() -> Vault {
+        Vault {
+            state_version: Vault::STATE_VERSION,
+            authority: Pubkey::new_unique(),
+            ..Vault::default()
+        }
+    }
+
+    #[test]
+    fn test_load_vault_checked() {
+        let vault = current_vault();
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vault_bytes(&vault);
+
+        let owned = account(&key, false, &PROGRAM_ID, &mut lamports, &mut data);
+        let loaded = load_vault_checked(&owned, &PROGRAM_ID).unwrap();
+        assert_eq!(loaded.authority, vault.authority);
+        // Nothing is left borrowed for a CPI or the save that follows
+        assert!(owned.try_borrow_mut_data().is_ok());
+
+        let foreign = AccountInfo { owner: &system_program::ID, ..owned };
+        assert_eq!(
+            load_vault_checked(&foreign, &PROGRAM_ID).unwrap_err(),
+            ProgramError::from(VaultError::InvalidAccountOwner)
+        );
+
+        let (mut lamports, mut garbage) = (0, vec![0xff; 8]);
+        let garbled = account(&key, false, &PROGRAM_ID, &mut lamports, &mut garbage);
+        assert_eq!(
+            load_vault_checked(&garbled, &PROGRAM_ID).unwrap_err(),
+            ProgramError::from(VaultError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_require_authority() {
+        let vault = current_vault();
+        let stranger = Pubkey::new_unique();
+        let (mut lamports, mut data) = (0, vec![]);
+
+        let authority =
+            account(&vault.authority, true, &system_program::ID, &mut lamports, &mut data);
+        assert!(require_authority(&vault, &authority).is_ok());
+
+        let unsigned = AccountInfo { is_signer: false, ..authority.clone() };
+        assert_eq!(
+            require_authority(&vault, &unsigned).unwrap_err(),
+            ProgramError::MissingRequiredSignature
+        );
+
+        let other = AccountInfo { key: &stranger, ..authority };
+        assert_eq!(
+            require_authority(&vault, &other).unwrap_err(),
+            ProgramError::from(VaultError::InsufficientAuthority)
+        );
+    }
+
+    #[test]
+    fn test_require_not_paused() {
+        let (key, actor) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let clock = Clock { unix_timestamp: 1_000, ..Clock::default() };
+
+        let mut vault = current_vault();
+        assert!(require_not_paused(&mut vault, &key, &actor, &clock).is_ok());
+
+        vault.paused = true;
+        vault.paused_at = 900;
+        assert_eq!(
+            require_not_paused(&mut vault, &key, &actor, &clock).unwrap_err(),
+            ProgramError::from(VaultError::UnauthorizedAccess)
+        );
+        assert!(vault.paused);
+
+        // A pause that ran out at 950 is lifted, which the caller then saves
+        vault.max_pause_duration = 50;
+        assert!(require_not_paused(&mut vault, &key, &actor, &clock).is_ok());
+        assert!(!vault.paused);
+        assert_eq!(vault.paused_at, 0);
+    }
+
+    #[test]
+    fn test_require_multisig_owner() {
+        let owner = Pubkey::new_unique();
+        let stranger = Pubkey::new_unique();
+        let (mut lamports, mut data) = (0, vec![]);
+        let signer = account(&owner, true, &system_program::ID, &mut lamports, &mut data);
+
+        let mut vault = current_vault();
+        assert_eq!(
+            require_multisig_owner(&vault, &signer).unwrap_err(),
+            ProgramError::from(VaultError::MultisigNotInitialized)
+        );
+
+        vault.multi_sig = Some(MultiSig {
+            owners: vec![owner],
+            threshold: 1,
+            ..MultiSig::default()
+        });
+        assert_eq!(require_multisig_owner(&vault, &signer).unwrap().threshold, 1);
+
+        let outsider = AccountInfo { key: &stranger, ..signer.clone() };
+        assert_eq!(
+            require_multisig_owner(&vault, &outsider).unwrap_err(),
+            ProgramError::from(VaultError::InvalidOwner)
+        );
+
+        let unsigned = AccountInfo { is_signer: false, ..signer };
+        assert_eq!(
+            require_multisig_owner(&vault, &unsigned).unwrap_err(),
+            ProgramError::MissingRequiredSignature
+        );
+    }
+
+    // Approvals used to decode any account handed in as the vault
+    #[tokio::test]
+    async fn test_approval_rejects_foreign_vault_account() {
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.pubkey();
+        let ix = approve_multisig_tx_ix(&Pubkey::new_unique(), &payer, 0);
+        let error = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+        assert_eq!(
+            error,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::InvalidAccountOwner as u32)
+            )
+        );
+    }
+}
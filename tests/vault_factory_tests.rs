@@ -0,0 +1,56 @@
+mod common;
+
+#[cfg(test)]
+mod vault_factory_tests {
+    use super::common::*;
+    use solana_sdk::signature::Signer;
+    use vault_program::{state::Vault, PROGRAM_ID};
+
+    #[test]
+    fn test_index_zero_keeps_legacy_derivation() {
+        let authority = solana_program::pubkey::Pubkey::new_unique();
+        let legacy = solana_program::pubkey::Pubkey::find_program_address(
+            &[b"vault", authority.as_ref()],
+            &PROGRAM_ID,
+        );
+        assert_eq!(Vault::find_address(&PROGRAM_ID, &authority, 0), legacy);
+        assert_ne!(Vault::find_address(&PROGRAM_ID, &authority, 1).0, legacy.0);
+    }
+
+    #[tokio::test]
+    async fn test_two_vaults_per_authority_are_independent() {
+        let mut context = program_test().start_with_context().await;
+        let authority = context.payer.pubkey();
+
+        let operating = create_vault_with_index(&mut context, 0).await;
+        let grants = create_vault_with_index(&mut context, 1).await;
+        assert_ne!(operating, grants);
+
+        let mint = create_mint(&mut context, 6).await.pubkey();
+        for vault in [operating, grants] {
+            send(&mut context, &[add_supported_token_ix(&vault, &mint, &authority, &authority)], &[])
+                .await
+                .unwrap();
+        }
+        create_funded_ata(&mut context, &mint, &authority, 1_000).await;
+
+        send(&mut context, &[deposit_ix(&grants, &mint, &authority, 400)], &[])
+            .await
+            .unwrap();
+        send(&mut context, &[withdraw_ix(&grants, &mint, &authority, 100)], &[])
+            .await
+            .unwrap();
+
+        let grants_state = fetch_vault(&mut context, &grants).await;
+        let operating_state = fetch_vault(&mut context, &operating).await;
+        assert_eq!(grants_state.index, 1);
+        assert_eq!(grants_state.total_value_locked, 300);
+        assert_eq!(operating_state.index, 0);
+        assert_eq!(operating_state.total_value_locked, 0);
+
+        // The operating vault holds nothing, so it cannot pay out
+        assert!(send(&mut context, &[withdraw_ix(&operating, &mint, &authority, 1)], &[])
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,292 @@
+mod common;
+
+#[cfg(test)]
+mod vault_token_account_tests {
+    use super::common::*;
+    use solana_program_test::ProgramTestContext;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::InstructionError, program_option::COption, program_pack::Pack,
+        pubkey::Pubkey,
+    };
+    use solana_sdk::{
+        account::Account,
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::state::{Account as TokenAccount, AccountState, Mint};
+    use vault_program::{instruction::VaultInstruction, VaultError};
+
+    #[tokio::test]
+    async fn test_separate_payer_funds_ata_rent() {
+        let mut context = program_test().start_with_context().await;
+        let authority = Keypair::new();
+        let vault = create_vault_for(&mut context, &authority).await;
+        let mint = create_mint(&mut context, 6).await.pubkey();
+        let payer = context.payer.pubkey();
+
+        let authority_lamports = context
+            .banks_client
+            .get_balance(authority.pubkey())
+            .await
+            .unwrap();
+
+        send(
+            &mut context,
+            &[add_supported_token_ix(&vault, &mint, &authority.pubkey(), &payer)],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            context.banks_client.get_balance(authority.pubkey()).await.unwrap(),
+            authority_lamports
+        );
+        let ata = get_associated_token_address(&vault, &mint);
+        assert_eq!(token_balance(&mut context, &ata).await, 0);
+        assert_eq!(fetch_vault(&mut context, &vault).await.supported_tokens.len(), 1);
+    }
+
+    // Pre-creates the vault's ATA with the given tampering applied, then tries to register the mint
+    async fn add_token_with_existing_ata(tamper: fn(&mut TokenAccount)) -> TransactionError {
+        let authority = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let (vault, _) = vault_pda(&authority.pubkey(), 0);
+
+        let mut mint_data = vec![0u8; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(Pubkey::new_unique()),
+                supply: 0,
+                decimals: 6,
+                is_initialized: true,
+                freeze_authority: COption::Some(Pubkey::new_unique()),
+            },
+            &mut mint_data,
+        )
+        .unwrap();
+
+        let mut token_account = TokenAccount {
+            mint,
+            owner: vault,
+            state: AccountState::Initialized,
+            ..TokenAccount::default()
+        };
+        tamper(&mut token_account);
+        let mut token_data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(token_account, &mut token_data).unwrap();
+
+        let mut test = program_test();
+        let ata = get_associated_token_address(&vault, &mint);
+        for (key, data) in [(mint, mint_data), (ata, token_data)] {
+            test.add_account(
+                key,
+                Account {
+                    lamports: 1_000_000_000,
+                    data,
+                    owner: spl_token::id(),
+                    executable: false,
+                    rent_epoch: 0,
+                },
+            );
+        }
+        let mut context = test.start_with_context().await;
+        let vault = create_vault_for(&mut context, &authority).await;
+        let payer = context.payer.pubkey();
+
+        send(
+            &mut context,
+            &[add_supported_token_ix(&vault, &mint, &authority.pubkey(), &payer)],
+            &[&authority],
+        )
+        .await
+        .unwrap_err()
+        .unwrap()
+    }
+
+    fn compromised() -> TransactionError {
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::CompromisedTokenAccount as u32),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_existing_delegated_ata_rejected() {
+        let err = add_token_with_existing_ata(|account| {
+            account.delegate = COption::Some(Pubkey::new_unique());
+            account.delegated_amount = u64::MAX;
+        })
+        .await;
+        assert_eq!(err, compromised());
+    }
+
+    #[tokio::test]
+    async fn test_existing_frozen_ata_rejected() {
+        let err = add_token_with_existing_ata(|account| account.state = AccountState::Frozen).await;
+        assert_eq!(err, compromised());
+    }
+
+    async fn add_token_error(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        mint: &Pubkey,
+    ) -> TransactionError {
+        let authority = context.payer.pubkey();
+        send(
+            context,
+            &[add_supported_token_ix(vault, mint, &authority, &authority)],
+            &[],
+        )
+        .await
+        .unwrap_err()
+        .unwrap()
+    }
+
+    fn invalid_mint() -> TransactionError {
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(VaultError::InvalidMint as u32),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_mint_decimals_recorded() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 0).await;
+        let stats = &fetch_vault(&mut context, &vault).await.token_stats()[0];
+        assert_eq!((stats.mint, stats.decimals), (mint, 6));
+    }
+
+    #[tokio::test]
+    async fn test_system_account_rejected_as_mint() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let system_account = context.payer.pubkey();
+        assert_eq!(add_token_error(&mut context, &vault, &system_account).await, invalid_mint());
+    }
+
+    #[tokio::test]
+    async fn test_token_account_rejected_as_mint() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let mint = create_mint(&mut context, 6).await.pubkey();
+        let owner = context.payer.pubkey();
+        let token_account = create_funded_ata(&mut context, &mint, &owner, 0).await;
+        assert_eq!(add_token_error(&mut context, &vault, &token_account).await, invalid_mint());
+    }
+
+    // wSOL positions go through DepositWrappedSOL, so the native mint registers like any other
+    #[tokio::test]
+    async fn test_native_mint_registers() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let native_mint = spl_token::native_mint::id();
+        let authority = context.payer.pubkey();
+        send(
+            &mut context,
+            &[add_supported_token_ix(&vault, &native_mint, &authority, &authority)],
+            &[],
+        )
+        .await
+        .unwrap();
+
+        let ata = get_associated_token_address(&vault, &native_mint);
+        let data = context.banks_client.get_account(ata).await.unwrap().unwrap().data;
+        assert!(TokenAccount::unpack(&data).unwrap().is_native());
+        assert_eq!(fetch_vault(&mut context, &vault).await.token_stats()[0].decimals, 9);
+    }
+
+    #[tokio::test]
+    async fn test_re_adding_registered_mint() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 0).await;
+        let authority = context.payer.pubkey();
+
+        // Fresh blockhash, so the repeat isn't dropped as already processed
+        advance_clock(&mut context, 1).await;
+        let err = add_token_error(&mut context, &vault, &mint).await;
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::TokenAlreadySupported as u32),
+            )
+        );
+
+        let mut idempotent = add_supported_token_ix(&vault, &mint, &authority, &authority);
+        idempotent.data = VaultInstruction::AddSupportedToken {
+            mint,
+            bump: 0,
+            idempotent: true,
+        }
+        .try_to_vec()
+        .unwrap();
+        let before = fetch_vault(&mut context, &vault).await;
+        send(&mut context, &[idempotent], &[]).await.unwrap();
+        let after = fetch_vault(&mut context, &vault).await;
+        assert_eq!(after.supported_tokens.len(), 1);
+        assert_eq!(after.recent_actions_head, before.recent_actions_head);
+    }
+
+    // Rewrites the vault's ATA in place, as if it had been closed and recreated at the same
+    // address by someone else
+    async fn tamper_vault_ata(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        mint: &Pubkey,
+        tamper: impl FnOnce(&mut TokenAccount),
+    ) {
+        let ata = get_associated_token_address(vault, mint);
+        let mut account = context.banks_client.get_account(ata).await.unwrap().unwrap();
+        let mut token_account = TokenAccount::unpack(&account.data).unwrap();
+        tamper(&mut token_account);
+        TokenAccount::pack(token_account, &mut account.data).unwrap();
+        context.set_account(&ata, &account.into());
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_refuses_delegated_vault_ata() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 1_000).await;
+        let user = context.payer.pubkey();
+        send(&mut context, &[deposit_ix(&vault, &mint, &user, 400)], &[]).await.unwrap();
+
+        let attacker = Pubkey::new_unique();
+        tamper_vault_ata(&mut context, &vault, &mint, |account| {
+            account.delegate = COption::Some(attacker);
+            account.delegated_amount = u64::MAX;
+        })
+        .await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, compromised());
+
+        // A close authority other than the vault is refused too, the vault itself is fine.
+        // Fresh blockhashes, so the repeated withdrawals aren't dropped as already processed.
+        advance_clock(&mut context, 1).await;
+        tamper_vault_ata(&mut context, &vault, &mint, |account| {
+            account.delegate = COption::None;
+            account.delegated_amount = 0;
+            account.close_authority = COption::Some(attacker);
+        })
+        .await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, compromised());
+
+        tamper_vault_ata(&mut context, &vault, &mint, |account| {
+            account.close_authority = COption::Some(vault);
+        })
+        .await;
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[withdraw_ix(&vault, &mint, &user, 100)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.tracked_balance(&mint), 300);
+    }
+}
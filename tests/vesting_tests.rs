@@ -0,0 +1,195 @@
+#[cfg(test)]
+mod vesting_tests {
+    use solana_program::pubkey::Pubkey;
+    use vault_program::{
+        state::{TimeLock, VestingSchedule},
+        vesting, VaultError,
+    };
+
+    const START: i64 = 1_000;
+    const DURATION: i64 = 1_000;
+    const CLIFF: i64 = START + 250;
+
+    fn lock(
+        amount: u64,
+        cliff_time: i64,
+        schedule: VestingSchedule,
+        released_amount: u64,
+    ) -> TimeLock {
+        TimeLock {
+            id: 0,
+            beneficiary: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            amount,
+            released_amount,
+            start_time: START,
+            cliff_time,
+            end_time: START + DURATION,
+            schedule,
+            revocable: false,
+        }
+    }
+
+    #[test]
+    fn test_linear_schedule() {
+        let cases: &[(&str, i64, u64)] = &[
+            ("before start", START - 1, 0),
+            ("one second before cliff", CLIFF - 1, 0),
+            ("exactly at cliff", CLIFF, 250),
+            ("one second after cliff", CLIFF + 1, 251),
+            ("halfway", START + 500, 500),
+            ("one second before end", START + DURATION - 1, 999),
+            ("exactly at end", START + DURATION, 1_000),
+            ("long after end", START + 10 * DURATION, 1_000),
+        ];
+
+        let time_lock = lock(1_000, CLIFF, VestingSchedule::Linear, 0);
+        for (name, now, expected) in cases {
+            assert_eq!(
+                vesting::releasable(&time_lock, *now),
+                Ok(*expected),
+                "case: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_cliff_all_schedule() {
+        let cases: &[(&str, i64, u64)] = &[
+            ("one second before cliff", CLIFF - 1, 0),
+            ("exactly at cliff", CLIFF, 1_000),
+            ("one second after cliff", CLIFF + 1, 1_000),
+            ("exactly at end", START + DURATION, 1_000),
+        ];
+
+        let time_lock = lock(1_000, CLIFF, VestingSchedule::CliffAll, 0);
+        for (name, now, expected) in cases {
+            assert_eq!(
+                vesting::releasable(&time_lock, *now),
+                Ok(*expected),
+                "case: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_amounts_near_u64_max() {
+        let cases: &[(&str, i64, u64)] = &[
+            ("exactly at cliff", CLIFF, u64::MAX / 4),
+            ("halfway", START + 500, u64::MAX / 2),
+            ("one second before end", START + DURATION - 1, 18_428_297_329_635_842_063),
+            ("exactly at end", START + DURATION, u64::MAX),
+        ];
+
+        let time_lock = lock(u64::MAX, CLIFF, VestingSchedule::Linear, 0);
+        for (name, now, expected) in cases {
+            assert_eq!(
+                vesting::releasable(&time_lock, *now),
+                Ok(*expected),
+                "case: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_partially_and_fully_claimed() {
+        let cases: &[(&str, u64, i64, u64)] = &[
+            ("partial claim, more vested", 300, START + 500, 200),
+            ("claimed up to vested", 500, START + 500, 0),
+            ("fully claimed before end", 1_000, START + 500, 0),
+            ("fully claimed at end", 1_000, START + DURATION, 0),
+            ("remainder at end", 999, START + DURATION, 1),
+        ];
+
+        for (name, released, now, expected) in cases {
+            let time_lock = lock(1_000, CLIFF, VestingSchedule::Linear, *released);
+            assert_eq!(
+                vesting::releasable(&time_lock, *now),
+                Ok(*expected),
+                "case: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_schedules_rejected() {
+        let mut zero_duration = lock(1_000, START, VestingSchedule::Linear, 0);
+        zero_duration.end_time = START;
+
+        let mut negative_duration = lock(1_000, START, VestingSchedule::Linear, 0);
+        negative_duration.end_time = START - 1;
+
+        let cliff_before_start = lock(1_000, START - 1, VestingSchedule::Linear, 0);
+        let cliff_after_end = lock(1_000, START + DURATION + 1, VestingSchedule::Linear, 0);
+
+        let cases = [
+            ("zero duration", zero_duration),
+            ("negative duration", negative_duration),
+            ("cliff before start", cliff_before_start),
+            ("cliff after end", cliff_after_end),
+        ];
+
+        for (name, time_lock) in cases.iter() {
+            assert_eq!(
+                vesting::releasable(time_lock, START + DURATION),
+                Err(VaultError::InvalidTimeLock),
+                "case: {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_repeated_splits_conserve_amounts() {
+        let mut original = lock(1_000, CLIFF, VestingSchedule::Linear, 333);
+        let mut pieces = Vec::new();
+        for (id, amount_to_new) in [(1, 250), (2, 125), (3, 1), (4, 400)] {
+            let beneficiary = Pubkey::new_unique();
+            let piece = vesting::split(&mut original, amount_to_new, id, beneficiary).unwrap();
+            assert_eq!((piece.id, piece.beneficiary), (id, beneficiary));
+            assert_eq!(piece.cliff_time, original.cliff_time);
+            assert_eq!(piece.end_time, original.end_time);
+            assert!(original.released_amount <= original.amount);
+            pieces.push(piece);
+            let amounts: u64 = pieces.iter().map(|p| p.amount).sum();
+            let released: u64 = pieces.iter().map(|p| p.released_amount).sum();
+            assert_eq!(original.amount + amounts, 1_000);
+            assert_eq!(original.released_amount + released, 333);
+        }
+        assert_eq!(original.amount, 224);
+        // Shares round down, so the original keeps the remainders
+        assert_eq!(pieces[0].released_amount, 83);
+
+        // Nothing vested is lost across the pieces, up to one token of rounding each
+        let vested: u64 = pieces
+            .iter()
+            .chain([&original])
+            .map(|p| vesting::vested_amount(p, START + 500).unwrap())
+            .sum();
+        assert!((496..=500).contains(&vested));
+    }
+
+    #[test]
+    fn test_split_rejections() {
+        let mut claimed = lock(1_000, CLIFF, VestingSchedule::Linear, 1_000);
+        assert_eq!(
+            vesting::split(&mut claimed, 1, 1, Pubkey::new_unique()).unwrap_err(),
+            VaultError::InvalidTimeLock
+        );
+
+        let mut time_lock = lock(1_000, CLIFF, VestingSchedule::Linear, 0);
+        for amount_to_new in [0, 1_000, 1_001] {
+            assert_eq!(
+                vesting::split(&mut time_lock, amount_to_new, 1, Pubkey::new_unique()).unwrap_err(),
+                VaultError::InvalidAmount,
+                "amount_to_new: {}",
+                amount_to_new
+            );
+        }
+        assert_eq!(time_lock.amount, 1_000);
+    }
+}
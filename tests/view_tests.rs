@@ -0,0 +1,160 @@
+mod common;
+
+#[cfg(test)]
+mod view_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        clock::Clock,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::Transaction,
+    };
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{TransactionAccount, VestingSchedule},
+        vesting,
+        view::{QuorumProgress, TransactionStatus, ViewQuery, ViewResult},
+        PROGRAM_ID,
+    };
+
+    fn view_ix(vault: &Pubkey, query: ViewQuery) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new_readonly(*vault, false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::View { query }.try_to_vec().unwrap(),
+        }
+    }
+
+    async fn simulate_view(
+        context: &mut ProgramTestContext,
+        vault: &Pubkey,
+        query: ViewQuery,
+    ) -> ViewResult {
+        let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[view_ix(vault, query)],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            blockhash,
+        );
+        let simulation = context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        assert!(matches!(simulation.result, Some(Ok(()))));
+        let details = simulation.simulation_details.unwrap();
+        assert!(details.logs.iter().any(|line| line.contains("VIEW: ")));
+        let data = match details.return_data {
+            Some(return_data) => {
+                assert_eq!(return_data.program_id, PROGRAM_ID);
+                return_data.data
+            }
+            None => Vec::new(),
+        };
+        ViewResult::decode(&data).unwrap()
+    }
+
+    // Vault holding 1_000 of its mint, 600 of it vesting linearly over 1_000 seconds, and a
+    // 2-of-2 multisig with one transaction approved only by its proposer
+    async fn populated_vault(context: &mut ProgramTestContext) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, 1_000).await;
+        let payer = context.payer.pubkey();
+        let cosigner = Keypair::new();
+        let create_lock = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateTimeLock {
+                beneficiary: Pubkey::new_unique(),
+                mint,
+                amount: 600,
+                duration: 1_000,
+                cliff_duration: None,
+                schedule: VestingSchedule::Linear,
+                revocable: false,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        let inner_accounts = vec![TransactionAccount {
+            pubkey: vault,
+            is_signer: false,
+            is_writable: true,
+        }];
+        send(
+            context,
+            &[
+                deposit_ix(&vault, &mint, &payer, 1_000),
+                create_lock,
+                initialize_multisig_ix(&vault, &payer, vec![payer, cosigner.pubkey()], 2, 1),
+                create_multisig_tx_ix(&vault, &payer, PROGRAM_ID, inner_accounts, vec![0]),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, mint)
+    }
+
+    #[tokio::test]
+    async fn test_views_match_state() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = populated_vault(&mut context).await;
+        advance_clock(&mut context, 250).await;
+        let state = fetch_vault(&mut context, &vault).await;
+        let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+
+        let claimable = vesting::releasable(&state.time_locks[0], clock.unix_timestamp).unwrap();
+        assert!(claimable > 0);
+        assert_eq!(
+            simulate_view(&mut context, &vault, ViewQuery::Claimable { time_lock_id: 0 }).await,
+            ViewResult::Amount(claimable)
+        );
+        assert_eq!(
+            simulate_view(&mut context, &vault, ViewQuery::FreeBalance { mint }).await,
+            ViewResult::Amount(state.free_balance(&mint))
+        );
+        assert_eq!(state.free_balance(&mint), 400);
+
+        let status = ViewQuery::TransactionStatus { transaction_id: 0 };
+        assert_eq!(
+            simulate_view(&mut context, &vault, status).await,
+            ViewResult::TransactionStatus(TransactionStatus::NeedsApprovals)
+        );
+        let progress = ViewQuery::QuorumProgress { transaction_id: 0 };
+        assert_eq!(
+            simulate_view(&mut context, &vault, progress).await,
+            ViewResult::QuorumProgress(QuorumProgress {
+                approvals: 1,
+                threshold: 2,
+                owners: 2,
+            })
+        );
+    }
+
+    // An all-zero answer comes back as no return data at all
+    #[tokio::test]
+    async fn test_zero_result_decodes() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, _) = populated_vault(&mut context).await;
+        let query = ViewQuery::FreeBalance {
+            mint: Pubkey::new_unique(),
+        };
+        assert_eq!(
+            simulate_view(&mut context, &vault, query).await,
+            ViewResult::Amount(0)
+        );
+    }
+}
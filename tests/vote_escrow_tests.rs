@@ -0,0 +1,296 @@
+mod common;
+
+#[cfg(test)]
+mod vote_escrow_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        governance::MAX_BOOST_BPS,
+        instruction::VaultInstruction,
+        state::{GovernanceConfig, VoteType, VotingModel},
+        VaultError, PROGRAM_ID,
+    };
+
+    const MAX_LOCK: i64 = 365 * 86_400;
+    const LOCKED: u64 = 1_000;
+
+    struct Escrow {
+        vault: Pubkey,
+        mint: Pubkey,
+        voter: Keypair,
+        voter_tokens: Pubkey,
+        vault_tokens: Pubkey,
+    }
+
+    // Governance over a fresh mint with a one-year max lock worth a full extra amount, and a
+    // voter holding LOCKED voting tokens
+    async fn escrow(context: &mut ProgramTestContext) -> Escrow {
+        let vault = create_vault(context).await;
+        let mint = create_mint(context, 0).await.pubkey();
+        let payer = context.payer.pubkey();
+        create_funded_ata(context, &mint, &payer, 0).await;
+        let vault_tokens = create_funded_ata(context, &mint, &vault, 0).await;
+        let voter = Keypair::new();
+        let voter_tokens = create_funded_ata(context, &mint, &voter.pubkey(), LOCKED).await;
+
+        let mut state = fetch_vault(context, &vault).await;
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: mint,
+            voting_period: 1,
+            ..Default::default()
+        });
+        store_vault(context, &vault, &state).await;
+
+        let update = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UpdateGovernanceConfig {
+                quorum_threshold: 0,
+                proposal_threshold: 0,
+                voting_period: 1_000,
+                time_lock_delay: 0,
+                execution_threshold: 5_000,
+                voting_model: VotingModel::Linear,
+                max_lock_duration: MAX_LOCK,
+                max_boost_bps: 10_000,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        send(context, &[update], &[]).await.unwrap();
+        Escrow {
+            vault,
+            mint,
+            voter,
+            voter_tokens,
+            vault_tokens,
+        }
+    }
+
+    fn voter_ix(escrow: &Escrow, data: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(escrow.vault, false),
+                AccountMeta::new_readonly(escrow.voter.pubkey(), true),
+                AccountMeta::new(escrow.voter_tokens, false),
+                AccountMeta::new(escrow.vault_tokens, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: data.try_to_vec().unwrap(),
+        }
+    }
+
+    async fn register(context: &mut ProgramTestContext, escrow: &Escrow, lock_duration: i64) {
+        let ix = voter_ix(
+            escrow,
+            VaultInstruction::RegisterVoterWithLock {
+                amount: LOCKED,
+                lock_duration,
+            },
+        );
+        send(context, &[ix], &[&escrow.voter]).await.unwrap();
+    }
+
+    fn cast_ix(escrow: &Escrow) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(escrow.vault, false),
+                AccountMeta::new_readonly(escrow.voter.pubkey(), true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CastVote {
+                proposal_id: 0,
+                vote_type: VoteType::For,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // Opens proposal 0 now and has the voter vote for it, returning the power counted
+    async fn vote_now(context: &mut ProgramTestContext, escrow: &Escrow) -> u64 {
+        let payer = context.payer.pubkey();
+        let propose = Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(escrow.vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(get_associated_token_address(&payer, &escrow.mint), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::CreateGovernanceProposal {
+                title: "raise quorum".to_string(),
+                description: String::new(),
+                instructions: vec![],
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        send(context, &[propose, cast_ix(escrow)], &[&escrow.voter]).await.unwrap();
+        let state = fetch_vault(context, &escrow.vault).await;
+        assert_eq!(state.vote_raw_balances[0], LOCKED);
+        state.vote_records[0].voting_power
+    }
+
+    #[tokio::test]
+    async fn test_full_lock_doubles_power() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        register(&mut context, &escrow, 2 * MAX_LOCK).await;
+        advance_clock(&mut context, 1).await;
+
+        assert_eq!(token_balance(&mut context, &escrow.voter_tokens).await, 0);
+        assert_eq!(token_balance(&mut context, &escrow.vault_tokens).await, LOCKED);
+        let state = fetch_vault(&mut context, &escrow.vault).await;
+        assert_eq!(state.voter_registry[0].voting_power, 2 * LOCKED);
+        assert_eq!(state.vote_locks[0].amount, LOCKED);
+        // Escrow isn't part of the vault's treasury
+        assert_eq!(state.tracked_balance(&escrow.mint), 0);
+
+        assert_eq!(vote_now(&mut context, &escrow).await, 2 * LOCKED);
+        let state = fetch_vault(&mut context, &escrow.vault).await;
+        assert_eq!(state.proposal_effective_supply(0), 2 * LOCKED);
+    }
+
+    #[tokio::test]
+    async fn test_boost_decays_with_remaining_lock() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        register(&mut context, &escrow, MAX_LOCK).await;
+        advance_clock(&mut context, MAX_LOCK / 2).await;
+
+        assert_eq!(vote_now(&mut context, &escrow).await, LOCKED + LOCKED / 2);
+
+        let unregister = voter_ix(&escrow, VaultInstruction::UnregisterVoter);
+        let err = send(&mut context, &[unregister], &[&escrow.voter])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VoteLockActive));
+        assert_eq!(token_balance(&mut context, &escrow.vault_tokens).await, LOCKED);
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_votes_unboosted_and_unlocks() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        register(&mut context, &escrow, MAX_LOCK).await;
+        advance_clock(&mut context, MAX_LOCK).await;
+
+        assert_eq!(vote_now(&mut context, &escrow).await, LOCKED);
+
+        let unregister = voter_ix(&escrow, VaultInstruction::UnregisterVoter);
+        send(&mut context, &[unregister], &[&escrow.voter]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &escrow.voter_tokens).await, LOCKED);
+        assert_eq!(token_balance(&mut context, &escrow.vault_tokens).await, 0);
+        let state = fetch_vault(&mut context, &escrow.vault).await;
+        assert!(state.voter_registry.is_empty());
+        assert!(state.vote_locks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_register_twice_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        register(&mut context, &escrow, 0).await;
+
+        let again = voter_ix(
+            &escrow,
+            VaultInstruction::RegisterVoterWithLock {
+                amount: 1,
+                lock_duration: MAX_LOCK,
+            },
+        );
+        let err = send(&mut context, &[again], &[&escrow.voter])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VoterAlreadyRegistered));
+    }
+
+    #[tokio::test]
+    async fn test_moved_tokens_cannot_vote_twice() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        register(&mut context, &escrow, 0).await;
+        advance_clock(&mut context, 1).await;
+        assert_eq!(vote_now(&mut context, &escrow).await, LOCKED);
+
+        // The expired lock comes back out and moves to a second wallet, which locks it again
+        let unregister = voter_ix(&escrow, VaultInstruction::UnregisterVoter);
+        send(&mut context, &[unregister], &[&escrow.voter]).await.unwrap();
+        let second = Keypair::new();
+        let second_tokens =
+            create_funded_ata(&mut context, &escrow.mint, &second.pubkey(), 0).await;
+        let transfer = spl_token::instruction::transfer(
+            &spl_token::id(),
+            &escrow.voter_tokens,
+            &second_tokens,
+            &escrow.voter.pubkey(),
+            &[],
+            LOCKED,
+        )
+        .unwrap();
+        send(&mut context, &[transfer], &[&escrow.voter]).await.unwrap();
+        let relocked = Escrow {
+            voter: second,
+            voter_tokens: second_tokens,
+            ..escrow
+        };
+        register(&mut context, &relocked, 0).await;
+
+        let err = send(&mut context, &[cast_ix(&relocked)], &[&relocked.voter])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::VoteLockTooRecent));
+        let state = fetch_vault(&mut context, &relocked.vault).await;
+        assert_eq!(state.governance_proposals[0].for_votes, LOCKED);
+    }
+
+    #[tokio::test]
+    async fn test_boost_capped() {
+        let mut context = program_test().start_with_context().await;
+        let escrow = escrow(&mut context).await;
+        let payer = context.payer.pubkey();
+        let update = |max_boost_bps| Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(escrow.vault, false),
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UpdateGovernanceConfig {
+                quorum_threshold: 0,
+                proposal_threshold: 0,
+                voting_period: 1_000,
+                time_lock_delay: 0,
+                execution_threshold: 5_000,
+                voting_model: VotingModel::Linear,
+                max_lock_duration: MAX_LOCK,
+                max_boost_bps,
+            }
+            .try_to_vec()
+            .unwrap(),
+        };
+        let err = send(&mut context, &[update(MAX_BOOST_BPS + 1)], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+        send(&mut context, &[update(MAX_BOOST_BPS)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &escrow.vault).await;
+        assert_eq!(state.max_boost_bps, MAX_BOOST_BPS);
+    }
+}
@@ -0,0 +1,273 @@
+mod common;
+
+#[cfg(test)]
+mod voting_power_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use vault_program::{
+        governance::{approved, effective_power, integer_sqrt, lock_boost, locked_power, quorum_reached},
+        instruction::VaultInstruction,
+        state::{GovernanceConfig, VoteLock, VoteType, VoterRegistry, VotingModel},
+        VaultError, PROGRAM_ID,
+    };
+
+    const VOTING_PERIOD: i64 = 100;
+    const WHALE: u64 = 90_000;
+    const SMALL: u64 = 10_000;
+
+    #[test]
+    fn test_integer_sqrt() {
+        for (n, root) in [(0, 0), (1, 1), (3, 1), (4, 2), (15, 3), (16, 4), (99, 9), (100, 10)] {
+            assert_eq!(integer_sqrt(n), root, "sqrt({})", n);
+        }
+        assert_eq!(integer_sqrt(u64::MAX), u32::MAX as u64);
+        assert_eq!(integer_sqrt(1 << 62), 1 << 31);
+        // Floor at every perfect square boundary around large roots
+        for root in [65_535u64, 1_000_000, 3_037_000_499] {
+            assert_eq!(integer_sqrt(root * root), root);
+            assert_eq!(integer_sqrt(root * root - 1), root - 1);
+            assert_eq!(integer_sqrt(root * root + 2 * root), root);
+        }
+    }
+
+    #[test]
+    fn test_tally_thresholds() {
+        assert_eq!(effective_power(VotingModel::Linear, WHALE), WHALE);
+        assert_eq!(effective_power(VotingModel::Quadratic, WHALE), 300);
+
+        // 50% quorum of 1000: abstentions count, 499 cast doesn't
+        assert!(quorum_reached(200, 100, 200, 1_000, 5_000));
+        assert!(!quorum_reached(200, 100, 199, 1_000, 5_000));
+        assert!(quorum_reached(0, 0, 0, 0, 5_000));
+
+        // Simple majority of for + against
+        assert!(approved(51, 49, 5_000));
+        assert!(!approved(49, 51, 5_000));
+        assert!(!approved(0, 0, 0));
+    }
+
+    #[test]
+    fn test_lock_boost_decay() {
+        const YEAR: i64 = 31_536_000;
+        // Full boost at or beyond max_lock, linear below it, nothing once expired
+        assert_eq!(lock_boost(1_000, YEAR, YEAR, 10_000), 1_000);
+        assert_eq!(lock_boost(1_000, 4 * YEAR, YEAR, 10_000), 1_000);
+        assert_eq!(lock_boost(1_000, YEAR / 2, YEAR, 10_000), 500);
+        assert_eq!(lock_boost(1_000, YEAR / 4, YEAR, 20_000), 500);
+        assert_eq!(lock_boost(1_000, 0, YEAR, 10_000), 0);
+        assert_eq!(lock_boost(1_000, -5, YEAR, 10_000), 0);
+        // Boosting off
+        assert_eq!(lock_boost(1_000, YEAR, 0, 10_000), 0);
+        assert_eq!(lock_boost(1_000, YEAR, YEAR, 0), 0);
+
+        assert_eq!(locked_power(1_000, YEAR / 2, YEAR, 10_000), 1_500);
+        assert_eq!(locked_power(u64::MAX, YEAR, YEAR, u16::MAX), u64::MAX);
+    }
+
+    fn update_config_ix(vault: &Pubkey, authority: &Pubkey, voting_model: VotingModel) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::UpdateGovernanceConfig {
+                quorum_threshold: 5_000,
+                proposal_threshold: 0,
+                voting_period: VOTING_PERIOD,
+                time_lock_delay: 60,
+                execution_threshold: 5_000,
+                voting_model,
+                max_lock_duration: 0,
+                max_boost_bps: 0,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    fn vault_ix(vault: &Pubkey, signer: &Pubkey, extra: Option<Pubkey>, data: VaultInstruction) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*vault, false),
+            AccountMeta::new_readonly(*signer, true),
+        ];
+        accounts.extend(extra.map(|key| AccountMeta::new_readonly(key, false)));
+        accounts.push(AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false));
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts,
+            data: data.try_to_vec().unwrap(),
+        }
+    }
+
+    struct Governed {
+        vault: Pubkey,
+        voters: Vec<Keypair>, // whale first, then four small holders
+    }
+
+    const LOCKS: [u64; 5] = [WHALE, SMALL, SMALL, SMALL, SMALL];
+
+    // Governance voting with a fresh mint under `model`, every voter registered with an
+    // unboosted lock, and an open proposal 0 from the payer
+    async fn governed(context: &mut ProgramTestContext, model: VotingModel) -> Governed {
+        let vault = create_vault(context).await;
+        let mint = create_mint(context, 0).await.pubkey();
+        let payer = context.payer.pubkey();
+        let payer_tokens = create_funded_ata(context, &mint, &payer, 0).await;
+
+        let voters: Vec<_> = LOCKS.iter().map(|_| Keypair::new()).collect();
+
+        let mut state = fetch_vault(context, &vault).await;
+        state.governance_config = Some(GovernanceConfig {
+            voting_token_mint: mint,
+            voting_period: VOTING_PERIOD,
+            ..Default::default()
+        });
+        state.voter_registry = voters
+            .iter()
+            .zip(LOCKS)
+            .map(|(voter, voting_power)| VoterRegistry {
+                voter: voter.pubkey(),
+                voting_power,
+                registered_at: 0,
+            })
+            .collect();
+        state.vote_locks = voters
+            .iter()
+            .zip(LOCKS)
+            .map(|(voter, amount)| VoteLock {
+                voter: voter.pubkey(),
+                amount,
+                locked_at: 0,
+                unlock_at: 0,
+            })
+            .collect();
+        store_vault(context, &vault, &state).await;
+
+        let propose = VaultInstruction::CreateGovernanceProposal {
+            title: "fund grants".to_string(),
+            description: String::new(),
+            instructions: vec![],
+        };
+        send(
+            context,
+            &[
+                update_config_ix(&vault, &payer, model),
+                vault_ix(&vault, &payer, Some(payer_tokens), propose),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        Governed { vault, voters }
+    }
+
+    // Whale votes against, the small holders for; then voting closes and queueing is tried
+    async fn vote_and_queue(context: &mut ProgramTestContext, governed: &Governed) -> Result<(), TransactionError> {
+        for (i, voter) in governed.voters.iter().enumerate() {
+            let vote_type = if i == 0 { VoteType::Against } else { VoteType::For };
+            let cast = VaultInstruction::CastVote {
+                proposal_id: 0,
+                vote_type,
+            };
+            let ix = vault_ix(&governed.vault, &voter.pubkey(), None, cast);
+            send(context, &[ix], &[voter]).await.unwrap();
+        }
+        advance_clock(context, VOTING_PERIOD).await;
+        let payer = context.payer.pubkey();
+        let queue = vault_ix(
+            &governed.vault,
+            &payer,
+            None,
+            VaultInstruction::QueueProposal { proposal_id: 0 },
+        );
+        send(context, &[queue], &[]).await.map_err(|e| e.unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_linear_tally_follows_balances() {
+        let mut context = program_test().start_with_context().await;
+        let governed = governed(&mut context, VotingModel::Linear).await;
+        let result = vote_and_queue(&mut context, &governed).await;
+        assert_eq!(result.unwrap_err(), vault_error(0, VaultError::ProposalNotPassed));
+
+        let state = fetch_vault(&mut context, &governed.vault).await;
+        let proposal = &state.governance_proposals[0];
+        assert_eq!((proposal.for_votes, proposal.against_votes), (4 * SMALL, WHALE));
+        assert_eq!(state.proposal_effective_supply(0), WHALE + 4 * SMALL);
+        assert!(!proposal.queued);
+    }
+
+    #[tokio::test]
+    async fn test_quadratic_tally_blunts_the_whale() {
+        let mut context = program_test().start_with_context().await;
+        let governed = governed(&mut context, VotingModel::Quadratic).await;
+        vote_and_queue(&mut context, &governed).await.unwrap();
+
+        let state = fetch_vault(&mut context, &governed.vault).await;
+        let proposal = &state.governance_proposals[0];
+        assert_eq!((proposal.for_votes, proposal.against_votes), (400, 300));
+        assert_eq!(state.proposal_effective_supply(0), 700);
+        assert!(proposal.queued);
+
+        // Each record keeps the balance alongside the power it was worth
+        assert_eq!(state.vote_records[0].voting_power, 300);
+        assert_eq!(state.vote_raw_balances[0], WHALE);
+        assert_eq!(state.vote_records[1].voting_power, 100);
+        assert_eq!(state.vote_raw_balances[1], SMALL);
+    }
+
+    #[tokio::test]
+    async fn test_model_locked_while_voting() {
+        let mut context = program_test().start_with_context().await;
+        let governed = governed(&mut context, VotingModel::Linear).await;
+        let payer = context.payer.pubkey();
+
+        let switch = update_config_ix(&governed.vault, &payer, VotingModel::Quadratic);
+        let err = send(&mut context, &[switch.clone()], &[]).await.unwrap_err().unwrap();
+        assert_eq!(err, vault_error(0, VaultError::GovernanceProposalActive));
+
+        // Same model is fine mid-vote; a switch is fine once voting has closed
+        send(&mut context, &[update_config_ix(&governed.vault, &payer, VotingModel::Linear)], &[])
+            .await
+            .unwrap();
+        advance_clock(&mut context, VOTING_PERIOD).await;
+        send(&mut context, &[switch], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &governed.vault).await;
+        assert_eq!(state.voting_model, VotingModel::Quadratic);
+    }
+
+    #[tokio::test]
+    async fn test_double_vote_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let governed = governed(&mut context, VotingModel::Quadratic).await;
+        let voter = &governed.voters[1];
+        let cast = |vote_type| {
+            vault_ix(
+                &governed.vault,
+                &voter.pubkey(),
+                None,
+                VaultInstruction::CastVote {
+                    proposal_id: 0,
+                    vote_type,
+                },
+            )
+        };
+        send(&mut context, &[cast(VoteType::For)], &[voter]).await.unwrap();
+        let err = send(&mut context, &[cast(VoteType::Against)], &[voter])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::AlreadyVoted));
+    }
+}
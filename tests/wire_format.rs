@@ -0,0 +1,773 @@
+#[cfg(test)]
+mod wire_format {
+    // Freezes the Borsh layout clients and stored multisig payloads depend on. Each sample
+    // below must keep encoding to its line in tests/fixtures; a layout change shows up as a
+    // failing hex diff. New variants and fields get a new sample and a new golden line.
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+    use vault_program::{instruction::VaultInstruction, state::*, view::ViewQuery};
+
+    const GOLDEN_INSTRUCTIONS: &str = include_str!("fixtures/golden_instructions.txt");
+    const GOLDEN_STATE: &str = include_str!("fixtures/golden_state.txt");
+
+    fn pk(byte: u8) -> Pubkey {
+        Pubkey::new_from_array([byte; 32])
+    }
+
+    fn golden(fixture: &'static str) -> Vec<(&'static str, &'static str)> {
+        fixture
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| line.split_once(' ').unwrap())
+            .collect()
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // Serialized, after checking the bytes read back into an identical value
+    fn encoded<T: BorshSerialize + BorshDeserialize>(value: &T) -> Vec<u8> {
+        let bytes = value.try_to_vec().unwrap();
+        assert_eq!(T::try_from_slice(&bytes).unwrap().try_to_vec().unwrap(), bytes);
+        bytes
+    }
+
+    // One per variant, in declaration order
+    fn instructions() -> Vec<VaultInstruction> {
+        vec![
+            VaultInstruction::Initialize { bump: 254, index: 2, allow_same_as_authority: true },
+            VaultInstruction::Deposite { amount: 1000 },
+            VaultInstruction::Withdraw { amount: 500 },
+            VaultInstruction::WithdrawSOL { amount: 1_500_000_000 },
+            VaultInstruction::Transfer { recipient: pk(1), amount: 42 },
+            VaultInstruction::InitializeMultiSig {
+                owners: vec![pk(1), pk(2)],
+                threshold: 2,
+                nonce: 7,
+            },
+            VaultInstruction::CreateMultiSigTransaction {
+                program_id: pk(3),
+                accounts: vec![
+                    TransactionAccount { pubkey: pk(4), is_signer: true, is_writable: false },
+                ],
+                data: vec![1, 2, 3],
+            },
+            VaultInstruction::ApproveMultiSigTransaction { transaction_id: 5 },
+            VaultInstruction::ExecuteMultiSigTransaction { transaction_id: 6 },
+            VaultInstruction::SetMultiSigOwners { owners: vec![pk(5)] },
+            VaultInstruction::ChangeMultiSigThreshold { threshold: 3 },
+            VaultInstruction::CreateProposal { instruction_data: vec![9, 8] },
+            VaultInstruction::ApproveProposal { proposal_id: 1 },
+            VaultInstruction::ExecuteProposal { proposal_id: 2 },
+            VaultInstruction::RejectProposal { proposal_id: 3 },
+            VaultInstruction::PauseVault,
+            VaultInstruction::UnpauseVault,
+            VaultInstruction::EmergencyWithdraw { token_mint: pk(6), amount: 77 },
+            VaultInstruction::AddSupportedToken { mint: pk(7), bump: 253, idempotent: true },
+            VaultInstruction::DepositMultiToken { mint: pk(8), amount: 300 },
+            VaultInstruction::CreateTimeLock {
+                beneficiary: pk(9),
+                mint: pk(10),
+                amount: 1000,
+                duration: 86_400,
+                cliff_duration: Some(3600),
+                schedule: VestingSchedule::CliffAll,
+                revocable: true,
+            },
+            VaultInstruction::ClaimTimeLock { time_lock_index: 4 },
+            VaultInstruction::CancelTimeLock { time_lock_index: 5 },
+            VaultInstruction::SetYieldStrategy { token_mint: pk(11), strategy_program: pk(12) },
+            VaultInstruction::HarvestYield { token_mint: pk(13) },
+            VaultInstruction::CompoundYield { token_mint: pk(14) },
+            VaultInstruction::JupiterSwap { input_mint: pk(15), output_mint: pk(16), amount: 250 },
+            VaultInstruction::JupiterRoute {
+                input_mint: pk(17),
+                output_mint: pk(18),
+                amount: 260,
+                auto_add: true,
+                route: vec![229, 23],
+            },
+            VaultInstruction::CollectFees,
+            VaultInstruction::TransferAuthority { new_authority: pk(19) },
+            VaultInstruction::UpdateEmergencyAdmin { new_admin: pk(20) },
+            VaultInstruction::InitializeGovernance {
+                voting_token_mint: pk(21),
+                quorum_threshold: 2000,
+                proposal_threshold: 100,
+                voting_period: 259_200,
+                time_lock_delay: 86_400,
+                execution_threshold: 5000,
+            },
+            VaultInstruction::CreateGovernanceProposal {
+                title: "Raise fee".to_string(),
+                description: "to 1%".to_string(),
+                instructions: vec![vec![1, 2]],
+            },
+            VaultInstruction::CastVote { proposal_id: 4, vote_type: VoteType::Against },
+            VaultInstruction::QueueProposal { proposal_id: 5 },
+            VaultInstruction::ExecuteGovernanceProposal { proposal_id: 6 },
+            VaultInstruction::UpdateGovernanceConfig {
+                quorum_threshold: 2500,
+                proposal_threshold: 200,
+                voting_period: 172_800,
+                time_lock_delay: 3600,
+                execution_threshold: 6000,
+                voting_model: VotingModel::Quadratic,
+                max_lock_duration: 31_536_000,
+                max_boost_bps: 10_000,
+            },
+            VaultInstruction::UpdateVaultConfig { config: VaultConfig { same_slot_guard: true } },
+            VaultInstruction::MigrateVault,
+            VaultInstruction::SetMultiSigProposalLimit { max_proposals_per_day: 20 },
+            VaultInstruction::AttestMessage { message_hash: [171; 32] },
+            VaultInstruction::SetMultiSigRecovery { recovery: None },
+            VaultInstruction::InitiateRecovery,
+            VaultInstruction::VetoRecovery,
+            VaultInstruction::FinalizeRecovery,
+            VaultInstruction::DeployToStrategy {
+                token_mint: pk(22),
+                amount: 400,
+                strategy_data: vec![4],
+            },
+            VaultInstruction::WithdrawFromStrategy {
+                token_mint: pk(23),
+                amount: 410,
+                strategy_data: vec![],
+            },
+            VaultInstruction::AcknowledgeStrategyLoss { token_mint: pk(24) },
+            VaultInstruction::FundExecutionTip { transaction_id: 7, lamports: 1_000_000 },
+            VaultInstruction::CancelMultiSigTransaction { transaction_id: 8 },
+            VaultInstruction::ExtendPause,
+            VaultInstruction::SetMaxPauseDuration { max_pause_duration: 604_800 },
+            VaultInstruction::ClaimTimeLockPartial { time_lock_id: 9, amount: 120 },
+            VaultInstruction::SetStreamConfig { exempt_from_pause: true, claim_fee_bps: 100 },
+            VaultInstruction::PruneMultiSigTransactions,
+            VaultInstruction::SetExecutionDelay { execution_delay_seconds: 3600 },
+            VaultInstruction::ExpediteTransaction { transaction_id: 10 },
+            VaultInstruction::SetParam { key: ParamKey::MultisigTransactionTtl, value: 86_400 },
+            VaultInstruction::View { query: ViewQuery::FreeBalance { mint: pk(25) } },
+            VaultInstruction::CreateTemplate {
+                name: "payroll".to_string(),
+                program_id: pk(26),
+                accounts: vec![
+                    TransactionAccount { pubkey: pk(27), is_signer: false, is_writable: true },
+                ],
+                data_prefix: vec![3, 0, 0, 0],
+                mutable_ranges: vec![ByteRange { offset: 1, len: 8 }],
+            },
+            VaultInstruction::DeleteTemplate { template_id: 11 },
+            VaultInstruction::CreateTransactionFromTemplate {
+                template_id: 12,
+                fill_bytes: vec![255],
+            },
+            VaultInstruction::DepositWrappedSOL { amount: 2_000_000, close_wrapped_account: true },
+            VaultInstruction::RegisterVoterWithLock { amount: 5000, lock_duration: 2_592_000 },
+            VaultInstruction::UnregisterVoter,
+            VaultInstruction::InitializeVault { index: 3, allow_same_as_authority: false },
+            VaultInstruction::UpdateYieldStrategy { token_mint: pk(28), max_allocation_bps: 7500 },
+            VaultInstruction::SetStrategyStatus {
+                token_mint: pk(29),
+                status: StrategyStatus::Exiting,
+            },
+            VaultInstruction::EmergencyExitStrategy {
+                token_mint: pk(30),
+                strategy_data: vec![6, 6],
+            },
+            VaultInstruction::InitializeGlobalConfig {
+                admin: pk(31),
+                max_fee_bps: 500,
+                allowed_programs: vec![pk(32)],
+                protocol_fee_recipient: pk(33),
+                protocol_fee_bps: 1000,
+            },
+            VaultInstruction::UpdateGlobalConfig {
+                admin: pk(34),
+                max_fee_bps: 600,
+                allowed_programs: vec![],
+                protocol_fee_recipient: pk(35),
+                protocol_fee_bps: 0,
+            },
+            VaultInstruction::AcknowledgeUpgradeAuthority {
+                expected_upgrade_authority: Some(pk(36)),
+                upgrade_check_threshold: 1_000_000,
+            },
+            VaultInstruction::ApproveWithSignature {
+                transaction_id: 13,
+                owner: pk(37),
+                signature_index: 1,
+            },
+            VaultInstruction::ExtendTransactionExpiry {
+                transaction_id: 14,
+                new_expires_at: 1_700_086_400,
+            },
+            VaultInstruction::ProposeSolTransfer {
+                recipient: pk(38),
+                amount: 1_000_000,
+                memo: "grant".to_string(),
+            },
+            VaultInstruction::ProposeTokenTransfer {
+                mint: pk(39),
+                recipient: pk(40),
+                amount: 2000,
+                memo: "".to_string(),
+            },
+            VaultInstruction::SweepUntrackedLamports,
+            VaultInstruction::SplitTimeLock { id: 15, amount_to_new: 400, new_beneficiary: pk(41) },
+            VaultInstruction::RequestWithdrawal { mint: pk(42), amount: 2500 },
+            VaultInstruction::ClaimWithdrawal { ticket_id: 16 },
+            VaultInstruction::CancelWithdrawalRequest { ticket_id: 17 },
+            VaultInstruction::EmitHealthCheck,
+            VaultInstruction::TransferV2 {
+                recipient: pk(45),
+                mint: Some(pk(46)),
+                amount: 5000,
+                create_ata: true,
+            },
+            VaultInstruction::RefreshSupportedTokenMetadata {
+                mint: pk(47),
+                allow_with_balance: false,
+            },
+            VaultInstruction::ClaimTimeLockV2 { time_lock_id: 16 },
+            VaultInstruction::CancelTimeLockV2 { time_lock_id: 17 },
+            VaultInstruction::CouncilApproveProposal { proposal_id: 18 },
+            VaultInstruction::InitializeVaultV2 {
+                index: 4,
+                allow_same_as_authority: true,
+                skip_setup_gate: false,
+            },
+            VaultInstruction::FinalizeSetup,
+            VaultInstruction::DepositAsDelegate { amount: 6000, on_behalf_of: pk(48) },
+            VaultInstruction::Tick { max_items: 32 },
+            VaultInstruction::CreateMultiSigTransactionV2 {
+                program_id: pk(49),
+                accounts: vec![
+                    TransactionAccount { pubkey: pk(50), is_signer: true, is_writable: true },
+                ],
+                roles: vec![AccountRole::MultisigSigner],
+                data: vec![4, 5],
+            },
+            VaultInstruction::CreateMultiSigTransactionV3 {
+                program_id: pk(51),
+                accounts: vec![
+                    TransactionAccount { pubkey: pk(52), is_signer: false, is_writable: true },
+                ],
+                roles: vec![AccountRole::Writable],
+                assertions: vec![
+                    BalanceAssertion { account: pk(52), min_post_balance: 100_000, is_token: true },
+                ],
+                data: vec![6],
+            },
+            VaultInstruction::CreateTransactionBuffer { total_len: 600 },
+            VaultInstruction::WriteTransactionBuffer { offset: 200, chunk: vec![7, 8, 9] },
+            VaultInstruction::FinalizeTransactionFromBuffer {
+                program_id: pk(53),
+                accounts: vec![
+                    TransactionAccount { pubkey: pk(54), is_signer: false, is_writable: true },
+                ],
+            },
+            VaultInstruction::CloseTransactionBuffer,
+            VaultInstruction::AssignTimeLockMint { time_lock_id: 18, mint: pk(55) },
+            VaultInstruction::SetYieldStrategyV2 {
+                token_mint: pk(56),
+                strategy_program: pk(57),
+                accounts: vec![pk(58)],
+            },
+            VaultInstruction::SetMintMinimums {
+                mint: pk(59),
+                min_deposit: 1_000_000,
+                min_withdrawal: 500_000,
+            },
+        ]
+    }
+
+    // State samples fill fields with 1, 2, 3... in declaration order, one element per Vec,
+    // Some for every Option and the second variant of every enum
+    fn governance_instruction() -> GovernanceInstruction {
+        GovernanceInstruction {
+            program_id: pk(1),
+            accounts: vec![transaction_account()],
+            data: vec![2],
+        }
+    }
+
+    fn fee_config() -> FeeConfig {
+        FeeConfig { deposit_fee_bps: 1, withdrawal_fee_bps: 2, fee_recipient: pk(3) }
+    }
+
+    fn supported_token() -> SupportedToken {
+        SupportedToken {
+            mint: pk(1),
+            bump: 2,
+            total_deposited: 3,
+            total_withdrawn: 4,
+            is_active: true,
+            deposit_count: 5,
+            withdrawal_count: 6,
+            decimals: 7,
+        }
+    }
+
+    fn token_balance() -> TokenBalance {
+        TokenBalance { mint: pk(1), balance: 2, last_updated: 3 }
+    }
+
+    fn time_lock() -> TimeLock {
+        TimeLock {
+            id: 1,
+            beneficiary: pk(2),
+            mint: pk(3),
+            amount: 4,
+            released_amount: 5,
+            start_time: 6,
+            cliff_time: 7,
+            end_time: 8,
+            schedule: VestingSchedule::CliffAll,
+            revocable: true,
+        }
+    }
+
+    fn proposal() -> Proposal {
+        Proposal {
+            id: 1,
+            instruction_data: vec![2],
+            approvals: vec![pk(3)],
+            executed: true,
+            created_at: 4,
+            proposer: pk(5),
+        }
+    }
+
+    fn governance_proposal() -> GovernanceProposal {
+        GovernanceProposal {
+            id: 1,
+            proposer: pk(2),
+            title: "title".to_string(),
+            description: "description".to_string(),
+            instructions: vec![vec![3]],
+            for_votes: 4,
+            against_votes: 5,
+            abstain_votes: 6,
+            created_at: 7,
+            end_time: 8,
+            executed: true,
+            queued: true,
+            eta: Some(9),
+            start_time: 10,
+            cancelled: true,
+        }
+    }
+
+    fn vote_record() -> VoteRecord {
+        VoteRecord {
+            proposal_id: 1,
+            voter: pk(2),
+            vote_type: VoteType::Against,
+            voting_power: 3,
+            voted_at: 4,
+        }
+    }
+
+    fn voter_registry() -> VoterRegistry {
+        VoterRegistry { voter: pk(1), voting_power: 2, registered_at: 3 }
+    }
+
+    fn governance_config() -> GovernanceConfig {
+        GovernanceConfig {
+            voting_token_mint: pk(1),
+            quorum_threshold: 2,
+            proposal_threshold: 3,
+            voting_period: 4,
+            time_lock_delay: 5,
+            execution_threshold: 6,
+            timelock_delay: 7,
+        }
+    }
+
+    fn multi_sig() -> MultiSig {
+        MultiSig { owners: vec![pk(1)], threshold: 2, nonce: 3, bump: 4 }
+    }
+
+    fn recovery_config() -> RecoveryConfig {
+        RecoveryConfig { recovery_key: pk(1), inactivity_period: 2 }
+    }
+
+    fn multi_sig_transaction() -> MultiSigTransaction {
+        MultiSigTransaction {
+            multisig: pk(1),
+            program_id: pk(2),
+            accounts: vec![transaction_account()],
+            data: vec![3],
+            signers: vec![true],
+            did_execute: true,
+            proposer: pk(4),
+            created_at: 5,
+        }
+    }
+
+    fn transaction_account() -> TransactionAccount {
+        TransactionAccount { pubkey: pk(1), is_signer: true, is_writable: true }
+    }
+
+    fn transaction_roles() -> TransactionRoles {
+        TransactionRoles { transaction_id: 1, roles: vec![AccountRole::Writable] }
+    }
+
+    fn balance_assertion() -> BalanceAssertion {
+        BalanceAssertion { account: pk(1), min_post_balance: 2, is_token: true }
+    }
+
+    fn transaction_assertions() -> TransactionAssertions {
+        TransactionAssertions { transaction_id: 1, assertions: vec![balance_assertion()] }
+    }
+
+    fn transaction_buffer() -> TransactionBuffer {
+        TransactionBuffer {
+            vault: pk(1),
+            proposer: pk(2),
+            bump: 3,
+            total_len: 4,
+            written_len: 5,
+            data: vec![6],
+        }
+    }
+
+    fn byte_range() -> ByteRange {
+        ByteRange { offset: 1, len: 2 }
+    }
+
+    fn transaction_template() -> TransactionTemplate {
+        TransactionTemplate {
+            id: 1,
+            name: "name".to_string(),
+            program_id: pk(2),
+            accounts: vec![transaction_account()],
+            data_prefix: vec![3],
+            mutable_ranges: vec![byte_range()],
+        }
+    }
+
+    fn yield_strategy_config() -> YieldStrategyConfig {
+        YieldStrategyConfig {
+            token_mint: pk(1),
+            strategy_program: pk(2),
+            auto_compound: true,
+            last_harvested_slot: 3,
+            deployed_amount: 4,
+            realized_pnl: 5,
+            high_water_mark: 6,
+            loss_pending: true,
+        }
+    }
+
+    fn strategy_accounts() -> StrategyAccounts {
+        StrategyAccounts { token_mint: pk(1), accounts: vec![pk(2)] }
+    }
+
+    fn emergency_action_log() -> EmergencyActionLog {
+        EmergencyActionLog { timestamp: 1, admin: pk(2), action: 3, details: vec![4] }
+    }
+
+    fn vault_config() -> VaultConfig {
+        VaultConfig { same_slot_guard: true }
+    }
+
+    fn param() -> Param {
+        Param { key: 1, value: 2 }
+    }
+
+    fn mint_minimums() -> MintMinimums {
+        MintMinimums { mint: pk(1), min_deposit: 2, min_withdrawal: 3 }
+    }
+
+    fn user_action_slot() -> UserActionSlot {
+        UserActionSlot { user: pk(1), last_action_slot: 2 }
+    }
+
+    fn proposal_window() -> ProposalWindow {
+        ProposalWindow { proposer: pk(1), window_start: 2, proposals_in_window: 3 }
+    }
+
+    fn outflow_window() -> OutflowWindow {
+        OutflowWindow { mint: pk(1), window_start: 2, opening_balance: 3, amount_out: 4 }
+    }
+
+    fn attestation() -> Attestation {
+        Attestation {
+            message_hash: [1; 32],
+            transaction_id: 2,
+            approvers: vec![pk(3)],
+            attested_at: 4,
+        }
+    }
+
+    fn action_record() -> ActionRecord {
+        ActionRecord {
+            kind: ActionKind::Initialize,
+            actor: pk(1),
+            mint: pk(2),
+            amount: 3,
+            timestamp: 4,
+        }
+    }
+
+    fn execution_tip() -> ExecutionTip {
+        ExecutionTip { transaction_id: 1, funder: pk(2), lamports: 3 }
+    }
+
+    fn threshold_reached() -> ThresholdReached {
+        ThresholdReached { transaction_id: 1, reached_at: 2, expedited: true }
+    }
+
+    fn transaction_expiry() -> TransactionExpiry {
+        TransactionExpiry { transaction_id: 1, expires_at: 2 }
+    }
+
+    fn withdrawal_ticket() -> WithdrawalTicket {
+        WithdrawalTicket {
+            id: 1,
+            owner: pk(2),
+            mint: pk(3),
+            amount: 4,
+            requested_at: 5,
+            available_at: 6,
+        }
+    }
+
+    fn proposal_snapshot() -> ProposalSnapshot {
+        ProposalSnapshot { proposal_id: 1, effective_supply: 2 }
+    }
+
+    fn council_review() -> CouncilReview {
+        CouncilReview { proposal_id: 1, approved_at: Some(2) }
+    }
+
+    fn settled_item() -> SettledItem {
+        SettledItem { kind: SettledKind::ProposalPassed, id: 1, settled_at: 2 }
+    }
+
+    fn vote_lock() -> VoteLock {
+        VoteLock { voter: pk(1), amount: 2, locked_at: 3, unlock_at: 4 }
+    }
+
+    fn swap_record() -> SwapRecord {
+        SwapRecord {
+            input_mint: pk(1),
+            output_mint: pk(2),
+            amount_in_measured: 3,
+            amount_out_measured: 4,
+            timestamp: 5,
+            executor: pk(6),
+        }
+    }
+
+    fn vault() -> Vault {
+        Vault {
+            authority: pk(1),
+            bump: 2,
+            emergency_admin: pk(3),
+            paused: true,
+            supported_tokens: vec![supported_token()],
+            token_balances: vec![token_balance()],
+            time_locks: vec![time_lock()],
+            proposals: vec![proposal()],
+            next_proposal_id: 4,
+            fee_config: fee_config(),
+            total_value_locked: 5,
+            total_fees_collected: 6,
+            legacy_mint: Some(pk(7)),
+            legacy_total_deposited: 8,
+            governance_config: Some(governance_config()),
+            governance_proposals: vec![governance_proposal()],
+            next_governance_proposal_id: 9,
+            vote_records: vec![vote_record()],
+            voter_registry: vec![voter_registry()],
+            multi_sig: Some(multi_sig()),
+            multi_sig_transactions: vec![multi_sig_transaction()],
+            yield_strategies: vec![yield_strategy_config()],
+            emergency_logs: vec![emergency_action_log()],
+            config: vault_config(),
+            user_action_slots: vec![user_action_slot()],
+            index: 10,
+            next_time_lock_id: 11,
+            state_version: 12,
+            max_proposals_per_day: 13,
+            proposal_windows: vec![proposal_window()],
+            attestations: vec![attestation()],
+            multisig_recovery: Some(recovery_config()),
+            last_owner_activity: 14,
+            recovery_initiated_at: Some(15),
+            recent_actions: [action_record(); RECENT_ACTIONS_LEN],
+            recent_actions_head: 16,
+            execution_tips: vec![execution_tip()],
+            cancelled_transactions: vec![17],
+            paused_at: 18,
+            max_pause_duration: 19,
+            streams_exempt_from_pause: true,
+            stream_claim_fee_bps: 20,
+            next_transaction_id: 21,
+            multi_sig_transaction_ids: vec![22],
+            execution_delay_seconds: 23,
+            threshold_reached: vec![threshold_reached()],
+            params: vec![param()],
+            templates: vec![transaction_template()],
+            next_template_id: 24,
+            voting_model: VotingModel::Quadratic,
+            proposal_snapshots: vec![proposal_snapshot()],
+            vote_raw_balances: vec![25],
+            vote_locks: vec![vote_lock()],
+            max_lock_duration: 26,
+            max_boost_bps: 27,
+            swap_history: vec![swap_record()],
+            strategy_allocation_caps: vec![28],
+            strategy_statuses: vec![StrategyStatus::DepositsPaused],
+            multisig_owner_since: vec![29],
+            owner_set_seqno: 30,
+            accrued_fees: vec![token_balance()],
+            transaction_expiries: vec![transaction_expiry()],
+            tracked_lamports: 31,
+            outflow_windows: vec![outflow_window()],
+            circuit_breaker_tripped: true,
+            tvl_cumulative: 32,
+            tvl_last_updated: 33,
+            withdrawal_tickets: vec![withdrawal_ticket()],
+            next_withdrawal_ticket_id: 34,
+            last_heartbeat_slot: 35,
+            supported_token_programs: vec![pk(36)],
+            council_reviews: vec![council_review()],
+            setup_stage: SetupStage::TokensConfigured,
+            setup_gate: true,
+            settled_items: vec![settled_item()],
+            tick_cursor: 37,
+            transaction_roles: vec![transaction_roles()],
+            transaction_assertions: vec![transaction_assertions()],
+            strategy_accounts: vec![strategy_accounts()],
+            mint_minimums: vec![mint_minimums()],
+        }
+    }
+
+    fn global_config() -> GlobalConfig {
+        GlobalConfig {
+            admin: pk(1),
+            max_fee_bps: 2,
+            allowed_programs: vec![pk(3)],
+            protocol_fee_recipient: pk(4),
+            bump: 5,
+            protocol_fee_bps: 6,
+            protocol_fees: vec![token_balance()],
+            expected_upgrade_authority: Some(pk(7)),
+            upgrade_check_threshold: 8,
+        }
+    }
+
+    fn vault_state() -> VaultState {
+        VaultState {
+            authority: pk(1),
+            emergency_admin: pk(2),
+            is_paused: true,
+            total_deposits: 3,
+            total_withdrawals: 4,
+            fee_recipient: pk(5),
+            deposit_fee_bps: 6,
+            withdrawal_fee_bps: 7,
+        }
+    }
+
+    fn multi_sig_state() -> MultiSigState {
+        MultiSigState { authorities: vec![pk(1)], threshold: 2, bump: 3 }
+    }
+
+    fn governance_state() -> GovernanceState {
+        GovernanceState {
+            voting_token_mint: pk(1),
+            quorum_threshold: 2,
+            proposal_threshold: 3,
+            voting_period: 4,
+            time_lock_delay: 5,
+            execution_threshold: 6,
+        }
+    }
+
+    #[test]
+    fn test_instruction_bytes_match_golden() {
+        let golden = golden(GOLDEN_INSTRUCTIONS);
+        let samples = instructions();
+        assert_eq!(samples.len(), golden.len());
+
+        for (index, (instruction, (name, expected))) in samples.iter().zip(golden).enumerate() {
+            let debug = format!("{:?}", instruction);
+            assert_eq!(debug.split(|c: char| !c.is_ascii_alphanumeric()).next(), Some(name));
+            let bytes = encoded(instruction);
+            assert_eq!(hex(&bytes), expected, "{}", name);
+            assert_eq!(bytes[0] as usize, index, "{} is out of declaration order", name);
+        }
+    }
+
+    #[test]
+    fn test_state_bytes_match_golden() {
+        let samples = vec![
+            ("GovernanceInstruction", encoded(&governance_instruction())),
+            ("FeeConfig", encoded(&fee_config())),
+            ("SupportedToken", encoded(&supported_token())),
+            ("TokenBalance", encoded(&token_balance())),
+            ("TimeLock", encoded(&time_lock())),
+            ("Proposal", encoded(&proposal())),
+            ("GovernanceProposal", encoded(&governance_proposal())),
+            ("VoteRecord", encoded(&vote_record())),
+            ("VoterRegistry", encoded(&voter_registry())),
+            ("GovernanceConfig", encoded(&governance_config())),
+            ("MultiSig", encoded(&multi_sig())),
+            ("RecoveryConfig", encoded(&recovery_config())),
+            ("MultiSigTransaction", encoded(&multi_sig_transaction())),
+            ("TransactionAccount", encoded(&transaction_account())),
+            ("TransactionRoles", encoded(&transaction_roles())),
+            ("BalanceAssertion", encoded(&balance_assertion())),
+            ("TransactionAssertions", encoded(&transaction_assertions())),
+            ("TransactionBuffer", encoded(&transaction_buffer())),
+            ("ByteRange", encoded(&byte_range())),
+            ("TransactionTemplate", encoded(&transaction_template())),
+            ("YieldStrategyConfig", encoded(&yield_strategy_config())),
+            ("StrategyAccounts", encoded(&strategy_accounts())),
+            ("EmergencyActionLog", encoded(&emergency_action_log())),
+            ("VaultConfig", encoded(&vault_config())),
+            ("Param", encoded(&param())),
+            ("MintMinimums", encoded(&mint_minimums())),
+            ("UserActionSlot", encoded(&user_action_slot())),
+            ("ProposalWindow", encoded(&proposal_window())),
+            ("OutflowWindow", encoded(&outflow_window())),
+            ("Attestation", encoded(&attestation())),
+            ("ActionRecord", encoded(&action_record())),
+            ("ExecutionTip", encoded(&execution_tip())),
+            ("ThresholdReached", encoded(&threshold_reached())),
+            ("TransactionExpiry", encoded(&transaction_expiry())),
+            ("WithdrawalTicket", encoded(&withdrawal_ticket())),
+            ("ProposalSnapshot", encoded(&proposal_snapshot())),
+            ("CouncilReview", encoded(&council_review())),
+            ("SettledItem", encoded(&settled_item())),
+            ("VoteLock", encoded(&vote_lock())),
+            ("SwapRecord", encoded(&swap_record())),
+            ("Vault", encoded(&vault())),
+            ("GlobalConfig", encoded(&global_config())),
+            ("VaultState", encoded(&vault_state())),
+            ("MultiSigState", encoded(&multi_sig_state())),
+            ("GovernanceState", encoded(&governance_state())),
+        ];
+        let golden = golden(GOLDEN_STATE);
+        assert_eq!(samples.len(), golden.len());
+
+        for ((name, bytes), (expected_name, expected)) in samples.iter().zip(golden) {
+            assert_eq!(*name, expected_name);
+            assert_eq!(hex(bytes), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_explicit_enum_discriminants_match_tags() {
+        for vote_type in [VoteType::For, VoteType::Against, VoteType::Abstain] {
+            assert_eq!(vote_type.try_to_vec().unwrap(), vec![vote_type as u8]);
+        }
+        for key in ParamKey::ALL {
+            assert_eq!(key.try_to_vec().unwrap(), vec![*key as u8]);
+        }
+    }
+}
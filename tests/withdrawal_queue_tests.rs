@@ -0,0 +1,182 @@
+mod common;
+
+#[cfg(test)]
+mod withdrawal_queue_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        sysvar,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::signature::{Keypair, Signer};
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{ParamKey, Vault},
+        VaultError, PROGRAM_ID,
+    };
+
+    const DEPOSIT: u64 = 1_000;
+    const DELAY: u64 = 3_600;
+
+    fn owner_call(vault: &Pubkey, owner: &Pubkey, instruction: VaultInstruction) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new_readonly(*owner, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: instruction.try_to_vec().unwrap(),
+        }
+    }
+
+    fn request_ix(vault: &Pubkey, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Instruction {
+        owner_call(vault, owner, VaultInstruction::RequestWithdrawal { mint: *mint, amount })
+    }
+
+    fn claim_ix(vault: &Pubkey, mint: &Pubkey, owner: &Pubkey, ticket_id: u64) -> Instruction {
+        Instruction {
+            data: VaultInstruction::ClaimWithdrawal { ticket_id }.try_to_vec().unwrap(),
+            ..withdraw_ix(vault, mint, owner, 0)
+        }
+    }
+
+    // Vault holding a DEPOSIT of its mint from the payer, with `delay` set
+    async fn funded_vault(context: &mut ProgramTestContext, delay: u64) -> (Pubkey, Pubkey) {
+        let (vault, mint) = vault_with_token(context, DEPOSIT).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(context, &vault).await;
+        state.set_param(ParamKey::WithdrawalDelaySeconds, delay);
+        store_vault(context, &vault, &state).await;
+        send(context, &[deposit_ix(&vault, &mint, &payer, DEPOSIT)], &[]).await.unwrap();
+        (vault, mint)
+    }
+
+    #[tokio::test]
+    async fn test_request_wait_claim() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, DELAY).await;
+        let payer = context.payer.pubkey();
+        let user_tokens = get_associated_token_address(&payer, &mint);
+
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::WithdrawalRequestRequired));
+
+        send(&mut context, &[request_ix(&vault, &mint, &payer, 400)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        let ticket = state.withdrawal_tickets[0].clone();
+        assert_eq!((ticket.id, ticket.amount, ticket.owner), (0, 400, payer));
+        assert_eq!(ticket.available_at, ticket.requested_at + DELAY as i64);
+        assert_eq!(state.free_balance(&mint), 600);
+        assert_eq!(state.tracked_balance(&mint), 600);
+        assert_eq!(state.total_value_locked, 600);
+        assert_eq!(token_balance(&mut context, &user_tokens).await, 0);
+
+        // The escrowed 400 can't be requested a second time
+        let err = send(&mut context, &[request_ix(&vault, &mint, &payer, 601)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+
+        advance_clock(&mut context, DELAY as i64 - 10).await;
+        let err = send(&mut context, &[claim_ix(&vault, &mint, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::WithdrawalNotReady));
+
+        advance_clock(&mut context, 10).await;
+        send(&mut context, &[claim_ix(&vault, &mint, &payer, 0)], &[]).await.unwrap();
+        assert_eq!(token_balance(&mut context, &user_tokens).await, 400);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.withdrawal_tickets.is_empty());
+        assert_eq!(state.tracked_balance(&mint), 600);
+        assert_eq!(state.free_balance(&mint), 600);
+
+        advance_clock(&mut context, 1).await;
+        let err = send(&mut context, &[claim_ix(&vault, &mint, &payer, 0)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::WithdrawalTicketNotFound));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_releases_reservation() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, DELAY).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 1).await;
+        send(&mut context, &[request_ix(&vault, &mint, &payer, DEPOSIT)], &[]).await.unwrap();
+        assert_eq!(fetch_vault(&mut context, &vault).await.free_balance(&mint), 0);
+
+        let stranger = Keypair::new();
+        let cancel = VaultInstruction::CancelWithdrawalRequest { ticket_id: 0 };
+        let err = send(
+            &mut context,
+            &[owner_call(&vault, &stranger.pubkey(), cancel.clone())],
+            &[&stranger],
+        )
+        .await
+        .unwrap_err()
+        .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::UnauthorizedAccess));
+
+        send(&mut context, &[owner_call(&vault, &payer, cancel)], &[]).await.unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert!(state.withdrawal_tickets.is_empty());
+        assert_eq!(state.free_balance(&mint), DEPOSIT);
+        assert_eq!(state.total_value_locked, u128::from(DEPOSIT));
+        assert_eq!(state.next_withdrawal_ticket_id, 1);
+    }
+
+    #[tokio::test]
+    async fn test_one_owner_cannot_fill_the_queue() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, DELAY).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 1).await;
+        for amount in [100, 200] {
+            send(&mut context, &[request_ix(&vault, &mint, &payer, amount)], &[])
+                .await
+                .unwrap();
+        }
+        let err = send(&mut context, &[request_ix(&vault, &mint, &payer, 300)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::WithdrawalQueueFull));
+
+        // Someone else still gets a ticket
+        let other = Keypair::new();
+        send(&mut context, &[request_ix(&vault, &mint, &other.pubkey(), 300)], &[&other])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.withdrawal_tickets.len(), 3);
+        assert_eq!(state.withdrawal_tickets_of(&payer), Vault::MAX_WITHDRAWAL_TICKETS_PER_OWNER);
+    }
+
+    #[tokio::test]
+    async fn test_no_delay_keeps_instant_withdrawals() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = funded_vault(&mut context, 0).await;
+        let payer = context.payer.pubkey();
+        advance_clock(&mut context, 1).await;
+
+        let err = send(&mut context, &[request_ix(&vault, &mint, &payer, 100)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidInstruction));
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 100)], &[]).await.unwrap();
+    }
+}
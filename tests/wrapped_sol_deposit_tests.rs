@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod wrapped_sol_deposit_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction, InstructionError},
+        program_pack::Pack,
+        pubkey::Pubkey,
+        system_instruction, system_program,
+    };
+    use solana_program_test::ProgramTestContext;
+    use solana_sdk::{
+        signature::{Keypair, Signer},
+        transaction::TransactionError,
+    };
+    use spl_associated_token_account::get_associated_token_address;
+    use spl_token::{native_mint, state::Account as TokenAccount};
+    use vault_program::{
+        instruction::VaultInstruction, state::SetupStage, VaultError, PROGRAM_ID,
+    };
+
+    const AMOUNT: u64 = 2_000_000_000;
+
+    fn deposit_wrapped_ix(vault: &Pubkey, user: &Pubkey, amount: u64, close: bool) -> Instruction {
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(get_associated_token_address(user, &native_mint::id()), false),
+                AccountMeta::new(get_associated_token_address(vault, &native_mint::id()), false),
+                AccountMeta::new(*user, true),
+                AccountMeta::new_readonly(native_mint::id(), false),
+                AccountMeta::new_readonly(spl_associated_token_account::id(), false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new_readonly(system_program::id(), false),
+                AccountMeta::new_readonly(solana_sdk::sysvar::clock::id(), false),
+            ],
+            data: VaultInstruction::DepositWrappedSOL {
+                amount,
+                close_wrapped_account: close,
+            }
+            .try_to_vec()
+            .unwrap(),
+        }
+    }
+
+    // Vault supporting the native mint, and a depositor with 10 SOL who never pays tx fees
+    async fn wsol_vault(context: &mut ProgramTestContext) -> (Pubkey, Keypair) {
+        let vault = create_vault(context).await;
+        let payer = context.payer.pubkey();
+        let depositor = Keypair::new();
+        send(
+            context,
+            &[
+                add_supported_token_ix(&vault, &native_mint::id(), &payer, &payer),
+                system_instruction::transfer(&payer, &depositor.pubkey(), 10_000_000_000),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+        (vault, depositor)
+    }
+
+    async fn lamports(context: &mut ProgramTestContext, key: &Pubkey) -> u64 {
+        context.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_wrap_and_deposit_keeps_wsol_account() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, depositor) = wsol_vault(&mut context).await;
+        let user = depositor.pubkey();
+        let user_wsol = get_associated_token_address(&user, &native_mint::id());
+        let vault_wsol = get_associated_token_address(&vault, &native_mint::id());
+        let before = lamports(&mut context, &user).await;
+
+        send(&mut context, &[deposit_wrapped_ix(&vault, &user, AMOUNT, false)], &[&depositor])
+            .await
+            .unwrap();
+
+        // The depositor paid the amount plus the rent of the ATA they now own, empty
+        let rent = context
+            .banks_client
+            .get_rent()
+            .await
+            .unwrap()
+            .minimum_balance(TokenAccount::LEN);
+        assert_eq!(before - lamports(&mut context, &user).await, AMOUNT + rent);
+        assert_eq!(lamports(&mut context, &user_wsol).await, rent);
+        assert_eq!(token_balance(&mut context, &user_wsol).await, 0);
+
+        assert_eq!(token_balance(&mut context, &vault_wsol).await, AMOUNT);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&native_mint::id()), AMOUNT);
+        assert_eq!(state.total_value_locked, u128::from(AMOUNT));
+
+        // A second wrap reuses the existing ATA
+        send(&mut context, &[deposit_wrapped_ix(&vault, &user, AMOUNT, false)], &[&depositor])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&native_mint::id()), 2 * AMOUNT);
+    }
+
+    #[tokio::test]
+    async fn test_closing_returns_wsol_rent() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, depositor) = wsol_vault(&mut context).await;
+        let user = depositor.pubkey();
+        let before = lamports(&mut context, &user).await;
+
+        send(&mut context, &[deposit_wrapped_ix(&vault, &user, AMOUNT, true)], &[&depositor])
+            .await
+            .unwrap();
+
+        assert_eq!(before - lamports(&mut context, &user).await, AMOUNT);
+        let user_wsol = get_associated_token_address(&user, &native_mint::id());
+        assert!(context.banks_client.get_account(user_wsol).await.unwrap().is_none());
+        let vault_wsol = get_associated_token_address(&vault, &native_mint::id());
+        assert_eq!(token_balance(&mut context, &vault_wsol).await, AMOUNT);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_native_mint_rejected() {
+        let mut context = program_test().start_with_context().await;
+        let vault = create_vault(&mut context).await;
+        let user = context.payer.pubkey();
+        let before = lamports(&mut context, &user).await;
+
+        let deposit = deposit_wrapped_ix(&vault, &user, AMOUNT, true);
+        assert!(send(&mut context, &[deposit], &[]).await.is_err());
+
+        // The wrap is rolled back with the failed deposit; only the transaction fee is gone
+        let user_wsol = get_associated_token_address(&user, &native_mint::id());
+        assert!(context.banks_client.get_account(user_wsol).await.unwrap().is_none());
+        assert!(before - lamports(&mut context, &user).await < 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_vault_in_setup_refuses_wrapped_sol() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, depositor) = wsol_vault(&mut context).await;
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.setup_gate = true;
+        state.setup_stage = SetupStage::TokensConfigured;
+        store_vault(&mut context, &vault, &state).await;
+
+        let user = depositor.pubkey();
+        let deposit = deposit_wrapped_ix(&vault, &user, AMOUNT, false);
+        let err = send(&mut context, &[deposit], &[&depositor]).await.unwrap_err().unwrap();
+        assert_eq!(
+            err,
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(VaultError::VaultNotReady as u32)
+            )
+        );
+        let user_wsol = get_associated_token_address(&user, &native_mint::id());
+        assert!(context.banks_client.get_account(user_wsol).await.unwrap().is_none());
+    }
+}
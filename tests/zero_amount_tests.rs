@@ -0,0 +1,161 @@
+mod common;
+
+#[cfg(test)]
+mod zero_amount_tests {
+    use super::common::*;
+    use borsh::BorshSerialize;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use solana_sdk::signature::Signer;
+    use spl_associated_token_account::get_associated_token_address;
+    use vault_program::{
+        instruction::VaultInstruction,
+        state::{MintMinimums, ParamKey, VestingSchedule},
+        VaultError, PROGRAM_ID,
+    };
+
+    // Every instruction that carries an amount, with that amount set to 0
+    fn zero_amount_instructions() -> Vec<VaultInstruction> {
+        let mint = Pubkey::new_unique();
+        vec![
+            VaultInstruction::Deposite { amount: 0 },
+            VaultInstruction::DepositWrappedSOL {
+                amount: 0,
+                close_wrapped_account: false,
+            },
+            VaultInstruction::Withdraw { amount: 0 },
+            VaultInstruction::WithdrawSOL { amount: 0 },
+            VaultInstruction::Transfer {
+                recipient: Pubkey::new_unique(),
+                amount: 0,
+            },
+            VaultInstruction::EmergencyWithdraw {
+                token_mint: mint,
+                amount: 0,
+            },
+            VaultInstruction::DepositMultiToken { mint, amount: 0 },
+            VaultInstruction::CreateTimeLock {
+                beneficiary: Pubkey::new_unique(),
+                mint,
+                amount: 0,
+                duration: 3_600,
+                cliff_duration: None,
+                schedule: VestingSchedule::Linear,
+                revocable: false,
+            },
+            VaultInstruction::ClaimTimeLockPartial {
+                time_lock_id: 0,
+                amount: 0,
+            },
+            VaultInstruction::DeployToStrategy {
+                token_mint: mint,
+                amount: 0,
+                strategy_data: vec![],
+            },
+            VaultInstruction::WithdrawFromStrategy {
+                token_mint: mint,
+                amount: 0,
+                strategy_data: vec![],
+            },
+            VaultInstruction::JupiterSwap {
+                input_mint: mint,
+                output_mint: Pubkey::new_unique(),
+                amount: 0,
+            },
+            VaultInstruction::JupiterRoute {
+                input_mint: mint,
+                output_mint: Pubkey::new_unique(),
+                amount: 0,
+                auto_add: false,
+                route: vec![],
+            },
+            VaultInstruction::FundExecutionTip {
+                transaction_id: 0,
+                lamports: 0,
+            },
+            VaultInstruction::RegisterVoterWithLock {
+                amount: 0,
+                lock_duration: 0,
+            },
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_zero_amounts_rejected_before_accounts_are_read() {
+        let mut context = program_test().start_with_context().await;
+        for instruction in zero_amount_instructions() {
+            let name = format!("{:?}", instruction);
+            let ix = Instruction {
+                program_id: PROGRAM_ID,
+                accounts: vec![],
+                data: instruction.try_to_vec().unwrap(),
+            };
+            let err = send(&mut context, &[ix], &[]).await.unwrap_err().unwrap();
+            assert_eq!(err, vault_error(0, VaultError::InvalidAmount), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_minimum_deposit_and_withdrawal() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 10_000).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::MinDepositAmount, 1_000);
+        state.set_param(ParamKey::MinWithdrawalAmount, 500);
+        store_vault(&mut context, &vault, &state).await;
+
+        let err = send(&mut context, &[deposit_ix(&vault, &mint, &payer, 999)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 1_000)], &[])
+            .await
+            .unwrap();
+
+        let err = send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 499)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 500)], &[])
+            .await
+            .unwrap();
+
+        let user = get_associated_token_address(&payer, &mint);
+        assert_eq!(token_balance(&mut context, &user).await, 9_500);
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 500);
+    }
+
+    #[tokio::test]
+    async fn test_mint_minimums_replace_vault_wide_ones() {
+        let mut context = program_test().start_with_context().await;
+        let (vault, mint) = vault_with_token(&mut context, 10_000).await;
+        let payer = context.payer.pubkey();
+        let mut state = fetch_vault(&mut context, &vault).await;
+        state.set_param(ParamKey::MinDepositAmount, 1_000);
+        state.set_param(ParamKey::MinWithdrawalAmount, 500);
+        state.set_mint_minimums(MintMinimums {
+            mint,
+            min_deposit: 10,
+            min_withdrawal: 5,
+        });
+        store_vault(&mut context, &vault, &state).await;
+        assert_eq!(state.min_deposit(&Pubkey::new_unique()), 1_000);
+
+        let err = send(&mut context, &[deposit_ix(&vault, &mint, &payer, 9)], &[])
+            .await
+            .unwrap_err()
+            .unwrap();
+        assert_eq!(err, vault_error(0, VaultError::InvalidAmount));
+        send(&mut context, &[deposit_ix(&vault, &mint, &payer, 10)], &[])
+            .await
+            .unwrap();
+        send(&mut context, &[withdraw_ix(&vault, &mint, &payer, 5)], &[])
+            .await
+            .unwrap();
+        let state = fetch_vault(&mut context, &vault).await;
+        assert_eq!(state.tracked_balance(&mint), 5);
+    }
+}